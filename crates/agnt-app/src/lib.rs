@@ -1,11 +1,18 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
 
 const APP_QUALIFIER: &str = "dev";
 const APP_ORGANIZATION: &str = "agnt";
 const APP_NAME: &str = "agnt";
 const SESSION_DB_FILENAME: &str = "sessions.sqlite3";
+const CONFIG_FILENAME: &str = "config.json";
+const MODEL_SPEC_CACHE_FILENAME: &str = "models.dev.json";
+const WINDOW_STATE_FILENAME: &str = "window.json";
+const SESSIONS_DIR: &str = "sessions";
+const ARTIFACTS_DIR: &str = "artifacts";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -14,6 +21,9 @@ pub enum Error {
 
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("config error: {0}")]
+    Config(#[from] serde_json::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -34,3 +44,258 @@ pub fn ensure_user_data_dir() -> Result<PathBuf> {
 pub fn session_db_path() -> Result<PathBuf> {
     Ok(ensure_user_data_dir()?.join(SESSION_DB_FILENAME))
 }
+
+/// Last models.dev spec fetched, loaded instead of fetching in offline mode.
+pub fn model_spec_cache_path() -> Result<PathBuf> {
+    Ok(ensure_user_data_dir()?.join(MODEL_SPEC_CACHE_FILENAME))
+}
+
+/// Size and position of the GUI window, restored at the next launch.
+pub fn window_state_path() -> Result<PathBuf> {
+    Ok(ensure_user_data_dir()?.join(WINDOW_STATE_FILENAME))
+}
+
+/// Files the agent saved as artifacts in the session `session_id`. Not
+/// created here; the first artifact creates it.
+pub fn session_artifacts_dir(session_id: &str) -> Result<PathBuf> {
+    Ok(user_data_dir()?
+        .join(SESSIONS_DIR)
+        .join(session_id)
+        .join(ARTIFACTS_DIR))
+}
+
+/// User-level configuration file.
+pub fn config_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from(APP_QUALIFIER, APP_ORGANIZATION, APP_NAME)
+        .ok_or(Error::MissingUserDataDir)?;
+    Ok(dirs.config_dir().join(CONFIG_FILENAME))
+}
+
+/// Per-user settings, stored as JSON at [`config_path`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    /// Model to start sessions with, as `provider/model`, unless the project
+    /// sets its own. Chosen by `agnt init`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// Encrypt session content in the session DB with a key kept in the OS
+    /// keychain.
+    pub encrypt_sessions: bool,
+    /// HTTP endpoint that `agnt sync` pushes session ops to and pulls them
+    /// from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_url: Option<String>,
+    /// Spend limits keyed by provider id, checked against the estimated cost
+    /// of stored turns.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub budgets: BTreeMap<String, ProviderBudget>,
+    /// Ask before sending a request estimated at more input tokens than
+    /// this. Unset asks above 100,000; `0` never asks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_request_tokens: Option<u64>,
+    /// Ask before sending a request whose input is estimated to cost more
+    /// than this many USD, for models with a known price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_request_usd: Option<f64>,
+    /// Never touch the network except to talk to providers on local
+    /// endpoints. Also enabled per run with `--offline`.
+    pub offline: bool,
+    /// Hours between background refreshes of the models.dev catalog while
+    /// the TUI or GUI runs. Unset refreshes every 6 hours; `0` only on
+    /// `/models:refresh`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec_refresh_hours: Option<u64>,
+    /// API base URLs keyed by provider id, replacing the registered or
+    /// models.dev ones, e.g. to point `openai` at a local server.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub provider_endpoints: BTreeMap<String, String>,
+    /// Settings keyed by profile name (`fast`, `balanced` or `thorough`),
+    /// applied in projects that select that profile.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    /// Timeouts and TCP keep-alive for the HTTP clients talking to
+    /// providers.
+    pub provider_http: ProviderHttpConfig,
+    /// Run OpenAI responses in the background, so a dropped connection or a
+    /// killed process doesn't lose the turn. Requires responses to be stored
+    /// by the provider.
+    pub background_responses: bool,
+    /// Let OpenAI models search the web on OpenAI's side.
+    pub web_search: bool,
+    /// Let OpenAI models run Python in a sandbox container on OpenAI's
+    /// side.
+    pub code_interpreter: bool,
+    /// Let OpenAI models generate images on OpenAI's side.
+    pub image_generation: bool,
+    /// In a jj (Jujutsu) repository, describe the working-copy change with
+    /// the session title after every turn that edited files and start a new
+    /// one, giving each turn a change of its own.
+    pub jj_change_per_turn: bool,
+    /// Keep the text of the files each turn read or edited with the turn,
+    /// not only their hashes, so a resumed session can show the model what
+    /// changed in them since.
+    pub snapshot_file_contents: bool,
+    /// Language of the TUI and GUI, e.g. `de`. Unset follows `AGNT_LOCALE`
+    /// or the system locale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Collapse runs of blank lines in a prompt to a single one before it
+    /// is sent.
+    pub collapse_blank_lines: bool,
+    /// Let repeated read-only tool calls with the same arguments reuse the
+    /// first result within a turn.
+    pub cache_tool_results: bool,
+    /// Times per turn an answer cut off at the output token limit is
+    /// continued automatically; `0` turns it off. Unset continues twice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_continuations: Option<usize>,
+    /// Run `bash` commands as the model gives them, without first flagging
+    /// ones that can't work in the project, like `npm test` in a Cargo-only
+    /// repository.
+    pub skip_command_checks: bool,
+    /// Leave `@path` mentions in prompts as plain text instead of attaching
+    /// the contents of the mentioned files.
+    pub skip_mention_attachments: bool,
+    /// Global shortcut, e.g. `cmd+shift+space`, that opens a quick prompt
+    /// window while the GUI runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quick_prompt_hotkey: Option<String>,
+    /// Show the GUI's status in the tray / menu bar and keep it running
+    /// when its window is closed.
+    pub tray_icon: bool,
+    /// Text size in the GUI as a multiple of the default, changed with
+    /// Cmd+= and Cmd+- (Ctrl on other platforms).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gui_zoom: Option<f32>,
+    /// URLs notified when a turn completes or fails, or a provider budget
+    /// nears its limit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+    /// URL of the index `agnt skill install <name>` looks skills up in: a
+    /// JSON object mapping skill names to their git source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skill_registry: Option<String>,
+    /// Tool plugin executables to load besides the `agnt-tool-*` ones on
+    /// `PATH`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_plugins: Vec<PathBuf>,
+}
+
+/// Spend limits in USD for one provider. Either limit may be left unset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderBudget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_usd: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_usd: Option<f64>,
+}
+
+/// One status webhook.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Shape of the request body, matching what the receiving service
+    /// expects.
+    pub format: WebhookFormat,
+    /// Events to send; all of them when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// A JSON object with the event, a message and session metadata.
+    #[default]
+    Generic,
+    /// A Slack incoming webhook.
+    Slack,
+    /// A Discord webhook.
+    Discord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    TurnComplete,
+    Error,
+    /// A provider budget reached its warning threshold or was exceeded.
+    Budget,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TurnComplete => "turn_complete",
+            Self::Error => "error",
+            Self::Budget => "budget",
+        }
+    }
+}
+
+/// Settings for one profile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// Only give the agent tools that cannot modify the workspace, as with
+    /// `--read-only`.
+    pub read_only: bool,
+    /// Bash commands running at the same time, across every session in
+    /// the process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_commands: Option<usize>,
+    /// CPU seconds the commands of one turn may use in total.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_command_cpu_secs: Option<u64>,
+    /// Bytes the `edit` tool may write in one turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_write_bytes: Option<u64>,
+}
+
+/// Provider HTTP client settings in seconds. Unset fields keep agnt's
+/// defaults, `0` turns a timeout off.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderHttpConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// Limit on any single read, including between streamed events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout_secs: Option<u64>,
+    /// How long idle pooled connections are kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive_secs: Option<u64>,
+    /// A streaming response fails when no event arrives for this long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_idle_timeout_secs: Option<u64>,
+}
+
+impl UserConfig {
+    /// Whether a config file has been written, i.e. setup has run before.
+    pub fn exists() -> Result<bool> {
+        Ok(config_path()?.try_exists()?)
+    }
+
+    /// Load the config, falling back to defaults when no file exists yet.
+    pub fn load() -> Result<Self> {
+        match std::fs::read_to_string(config_path()?) {
+            Ok(raw) => Ok(serde_json::from_str(&raw)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -30,6 +30,9 @@ pub enum Error {
     #[error("oauth token response missing required fields")]
     InvalidOAuthTokenResponse,
 
+    #[error("invalid Google application default credentials: {0}")]
+    InvalidGoogleCredentials(String),
+
     #[error("failed to parse redirect url: {0}")]
     InvalidRedirectUrl(String),
 
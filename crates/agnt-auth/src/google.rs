@@ -0,0 +1,266 @@
+//! Google Application Default Credentials: finding them and exchanging them
+//! for short-lived access tokens.
+
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agnt_llm_registry::TokenSource;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use parking_lot::Mutex;
+use ring::signature::{RSA_PKCS1_SHA256, RsaKeyPair};
+use serde::Deserialize;
+
+use crate::error::Error;
+
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+/// Lifetime requested for service account assertions; Google's maximum.
+const ASSERTION_LIFETIME_SECS: u64 = 3600;
+/// Tokens this close to expiring are minted again before use.
+const REFRESH_MARGIN_MS: u64 = 60_000;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum AdcCredentials {
+    ServiceAccount {
+        client_email: String,
+        private_key: String,
+        #[serde(default)]
+        token_uri: Option<String>,
+        #[serde(default)]
+        project_id: Option<String>,
+    },
+    AuthorizedUser {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+        #[serde(default)]
+        quota_project_id: Option<String>,
+    },
+}
+
+impl AdcCredentials {
+    fn project_id(&self) -> Option<String> {
+        if let Ok(project) = std::env::var("GOOGLE_CLOUD_PROJECT")
+            && !project.trim().is_empty()
+        {
+            return Some(project);
+        }
+        match self {
+            Self::ServiceAccount { project_id, .. } => project_id.clone(),
+            Self::AuthorizedUser {
+                quota_project_id, ..
+            } => quota_project_id.clone(),
+        }
+    }
+}
+
+struct AccessToken {
+    token: String,
+    expires_at_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+/// Where Application Default Credentials are looked for:
+/// `GOOGLE_APPLICATION_CREDENTIALS`, else the file
+/// `gcloud auth application-default login` writes.
+pub(crate) fn adc_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("GOOGLE_APPLICATION_CREDENTIALS")
+        && !path.trim().is_empty()
+    {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?).join("gcloud")
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?)
+            .join(".config")
+            .join("gcloud")
+    };
+    let path = config_dir.join("application_default_credentials.json");
+    path.exists().then_some(path)
+}
+
+/// Mints and caches access tokens from a set of ADC credentials.
+pub struct GoogleTokenSource {
+    credentials: AdcCredentials,
+    scopes: Vec<String>,
+    cached: Mutex<Option<AccessToken>>,
+}
+
+impl GoogleTokenSource {
+    /// Load the credentials found by [`adc_path`]. `None` when there are
+    /// none.
+    pub(crate) fn load(scopes: &[String]) -> Result<Option<Self>, Error> {
+        let Some(path) = adc_path() else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(&path)?;
+        let credentials: AdcCredentials = serde_json::from_str(&contents)
+            .map_err(|err| Error::InvalidGoogleCredentials(format!("{}: {err}", path.display())))?;
+        let scopes = if scopes.is_empty() {
+            vec![CLOUD_PLATFORM_SCOPE.to_string()]
+        } else {
+            scopes.to_vec()
+        };
+        Ok(Some(Self {
+            credentials,
+            scopes,
+            cached: Mutex::new(None),
+        }))
+    }
+
+    pub(crate) fn project_id(&self) -> Option<String> {
+        self.credentials.project_id()
+    }
+
+    /// The last minted token, if it has not expired yet.
+    pub(crate) fn cached_token(&self) -> Option<String> {
+        self.cached
+            .lock()
+            .as_ref()
+            .filter(|token| token.expires_at_ms > now_ms())
+            .map(|token| token.token.clone())
+    }
+
+    /// A token valid for at least another minute, minting one if needed.
+    pub(crate) async fn access_token(&self) -> Result<String, Error> {
+        if let Some(token) = self.cached.lock().as_ref()
+            && token.expires_at_ms > now_ms().saturating_add(REFRESH_MARGIN_MS)
+        {
+            return Ok(token.token.clone());
+        }
+        let minted = mint_token(&self.credentials, &self.scopes).await?;
+        let token = minted.token.clone();
+        *self.cached.lock() = Some(minted);
+        Ok(token)
+    }
+}
+
+impl fmt::Debug for GoogleTokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let account = match &self.credentials {
+            AdcCredentials::ServiceAccount { client_email, .. } => client_email.as_str(),
+            AdcCredentials::AuthorizedUser { .. } => "authorized_user",
+        };
+        f.debug_struct("GoogleTokenSource")
+            .field("account", &account)
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenSource for GoogleTokenSource {
+    fn token(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<String, agnt_llm_registry::Error>> + Send + '_>> {
+        Box::pin(async move {
+            self.access_token()
+                .await
+                .map_err(|e| agnt_llm_registry::Error::Factory(Box::new(e)))
+        })
+    }
+}
+
+async fn mint_token(credentials: &AdcCredentials, scopes: &[String]) -> Result<AccessToken, Error> {
+    let client = reqwest::Client::new();
+    let request = match credentials {
+        AdcCredentials::ServiceAccount {
+            client_email,
+            private_key,
+            token_uri,
+            ..
+        } => {
+            let token_uri = token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+            let assertion = sign_assertion(client_email, private_key, token_uri, scopes)?;
+            client.post(token_uri).form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+        }
+        AdcCredentials::AuthorizedUser {
+            client_id,
+            client_secret,
+            refresh_token,
+            ..
+        } => client.post(DEFAULT_TOKEN_URI).form(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+            ("refresh_token", refresh_token.as_str()),
+        ]),
+    };
+
+    let body: TokenResponse = request.send().await?.error_for_status()?.json().await?;
+    let token = body.access_token.ok_or(Error::InvalidOAuthTokenResponse)?;
+    let expires_in = body.expires_in.ok_or(Error::InvalidOAuthTokenResponse)?;
+    Ok(AccessToken {
+        token,
+        expires_at_ms: now_ms().saturating_add(expires_in.saturating_mul(1000)),
+    })
+}
+
+/// A JWT, signed with the service account's key, asserting its identity to
+/// the token endpoint.
+fn sign_assertion(
+    client_email: &str,
+    private_key: &str,
+    token_uri: &str,
+    scopes: &[String],
+) -> Result<String, Error> {
+    let issued_at = now_ms() / 1000;
+    let header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+    let claims = serde_json::json!({
+        "iss": client_email,
+        "scope": scopes.join(" "),
+        "aud": token_uri,
+        "iat": issued_at,
+        "exp": issued_at + ASSERTION_LIFETIME_SECS,
+    });
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let der = STANDARD
+        .decode(
+            private_key
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .collect::<String>(),
+        )
+        .map_err(|err| Error::InvalidGoogleCredentials(format!("private key: {err}")))?;
+    let key_pair = RsaKeyPair::from_pkcs8(&der)
+        .map_err(|err| Error::InvalidGoogleCredentials(format!("private key: {err}")))?;
+    let mut signature = vec![0; key_pair.public().modulus_len()];
+    key_pair
+        .sign(
+            &RSA_PKCS1_SHA256,
+            &ring::rand::SystemRandom::new(),
+            signing_input.as_bytes(),
+            &mut signature,
+        )
+        .map_err(|_| Error::InvalidGoogleCredentials("signing failed".to_string()))?;
+
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -1,7 +1,11 @@
 pub mod error;
+mod google;
 mod manager;
 mod oauth;
+mod session_key;
 mod store;
 
-pub use manager::AuthManager;
+pub use google::GoogleTokenSource;
+pub use manager::{AuthManager, CredentialStatus};
 pub use oauth::OAuthStart;
+pub use session_key::{load_or_create_session_key, load_session_key};
@@ -1,24 +1,51 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use agnt_db::Store;
 use agnt_llm_registry::{
-    ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, OAuthPkceAuth, ResolvedAuth,
+    ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, GoogleAdcAuth, OAuthPkceAuth, ResolvedAuth,
 };
 use parking_lot::Mutex;
 
 use crate::error::Error;
+use crate::google::{GoogleTokenSource, adc_path};
 use crate::oauth::{
     OAuthCredential, OAuthStart, begin_pkce, exchange_authorization_code, extract_code_from_input,
     refresh_pkce_token,
 };
 use crate::store::{CredentialStore, StoredCredential};
 
+/// Where a provider's credentials come from, as found without refreshing or
+/// prompting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialStatus {
+    Missing,
+    /// An API key set in this environment variable.
+    Environment(String),
+    /// An API key stored in the database.
+    ApiKey,
+    /// An OAuth session; expired access tokens are refreshed on next use.
+    OAuth {
+        expires_at_ms: u64,
+        expired: bool,
+    },
+    /// Google application default credentials in this file.
+    GoogleAdc(PathBuf),
+}
+
 /// Headless auth manager (db-backed credential persistence + oauth/api-key resolution).
+///
+/// Credentials are kept per [`AuthRequest::credential_key`], so the
+/// `provider_id` taken by the methods below may also name an account, as in
+/// `openai@work`.
 pub struct AuthManager {
     store: CredentialStore,
     cache: Mutex<HashMap<String, StoredCredential>>,
+    /// Google ADC token sources by provider; their tokens are kept in memory
+    /// only, the credentials file stays the source of truth.
+    google: Mutex<HashMap<String, Arc<GoogleTokenSource>>>,
 }
 
 impl AuthManager {
@@ -26,6 +53,7 @@ impl AuthManager {
         Self {
             store: CredentialStore::new(service_name, store),
             cache: Mutex::new(HashMap::new()),
+            google: Mutex::new(HashMap::new()),
         }
     }
 
@@ -36,43 +64,87 @@ impl AuthManager {
     }
 
     pub fn resolve_cached(&self, request: &AuthRequest) -> Result<Option<ResolvedAuth>, Error> {
+        let key = request.credential_key();
         match &request.auth_method {
-            AuthMethod::ApiKey(ApiKeyAuth { env }) => {
-                for var in env {
+            AuthMethod::ApiKey(config) => {
+                for var in default_account_env(request, &config.env) {
                     if let Ok(value) = std::env::var(var)
                         && !value.trim().is_empty()
                     {
-                        return Ok(Some(ResolvedAuth::api_key(value)));
+                        return Ok(Some(config.with_env_fields(ResolvedAuth::api_key(value))));
                     }
                 }
-                match self.load_credential(&request.provider_id)? {
-                    Some(StoredCredential::ApiKey { api_key }) => {
-                        Ok(Some(ResolvedAuth::api_key(api_key)))
+                match self.load_credential(&key)? {
+                    Some(StoredCredential::ApiKey { api_key, metadata }) => {
+                        let mut auth = ResolvedAuth::api_key(api_key);
+                        auth.values.extend(metadata);
+                        Ok(Some(auth))
                     }
                     _ => Ok(None),
                 }
             }
-            AuthMethod::OAuthPkce(_) => match self.load_credential(&request.provider_id)? {
+            AuthMethod::OAuthPkce(_) => match self.load_credential(&key)? {
                 Some(StoredCredential::OAuthPkce { access_token, .. }) => {
                     Ok(Some(ResolvedAuth::bearer(access_token)))
                 }
                 _ => Ok(None),
             },
+            AuthMethod::GoogleAdc(config) => {
+                let Some(source) = self.google_token_source(&key, config)? else {
+                    return Ok(None);
+                };
+                Ok(Some(google_adc_auth(source)))
+            }
         }
     }
 
+    /// Inspect stored credentials for `request`. Fails when they exist but
+    /// cannot be read, e.g. because the keychain key changed.
+    pub fn credential_status(&self, request: &AuthRequest) -> Result<CredentialStatus, Error> {
+        if let AuthMethod::ApiKey(ApiKeyAuth { env, .. }) = &request.auth_method
+            && let Some(var) = default_account_env(request, env)
+                .find(|var| std::env::var(var).is_ok_and(|value| !value.trim().is_empty()))
+        {
+            return Ok(CredentialStatus::Environment(var.clone()));
+        }
+        if let AuthMethod::GoogleAdc(_) = &request.auth_method {
+            return Ok(adc_path().map_or(CredentialStatus::Missing, CredentialStatus::GoogleAdc));
+        }
+
+        Ok(match self.load_credential(&request.credential_key())? {
+            None => CredentialStatus::Missing,
+            Some(StoredCredential::ApiKey { .. }) => match request.auth_method {
+                AuthMethod::ApiKey(_) => CredentialStatus::ApiKey,
+                AuthMethod::OAuthPkce(_) | AuthMethod::GoogleAdc(_) => CredentialStatus::Missing,
+            },
+            Some(StoredCredential::OAuthPkce { expires_at_ms, .. }) => match request.auth_method {
+                AuthMethod::OAuthPkce(_) => CredentialStatus::OAuth {
+                    expires_at_ms,
+                    expired: expires_at_ms <= now_ms(),
+                },
+                AuthMethod::ApiKey(_) | AuthMethod::GoogleAdc(_) => CredentialStatus::Missing,
+            },
+        })
+    }
+
+    /// Store an API key with the values of the provider's credential
+    /// fields, by [`CredentialField::key`](agnt_llm_registry::CredentialField::key).
     pub fn store_api_key(
         &self,
         provider_id: &str,
         api_key: impl Into<String>,
+        metadata: HashMap<String, String>,
     ) -> Result<ResolvedAuth, Error> {
         let api_key = api_key.into();
         let credential = StoredCredential::ApiKey {
             api_key: api_key.clone(),
+            metadata: metadata.clone(),
         };
         self.store.save(provider_id, &credential)?;
         self.cache_set(provider_id, credential);
-        Ok(ResolvedAuth::api_key(api_key))
+        let mut auth = ResolvedAuth::api_key(api_key);
+        auth.values.extend(metadata);
+        Ok(auth)
     }
 
     pub fn begin_oauth(
@@ -131,6 +203,38 @@ impl AuthManager {
         Ok(Some(ResolvedAuth::bearer(refreshed.access_token)))
     }
 
+    /// Mint a Google ADC access token for `provider_id` unless the last one
+    /// is still good. `None` when no application default credentials exist.
+    pub async fn refresh_google_adc(
+        &self,
+        provider_id: &str,
+        config: &GoogleAdcAuth,
+    ) -> Result<Option<ResolvedAuth>, Error> {
+        let Some(source) = self.google_token_source(provider_id, config)? else {
+            return Ok(None);
+        };
+        source.access_token().await?;
+        Ok(Some(google_adc_auth(source)))
+    }
+
+    fn google_token_source(
+        &self,
+        provider_id: &str,
+        config: &GoogleAdcAuth,
+    ) -> Result<Option<Arc<GoogleTokenSource>>, Error> {
+        if let Some(source) = self.google.lock().get(provider_id) {
+            return Ok(Some(Arc::clone(source)));
+        }
+        let Some(source) = GoogleTokenSource::load(&config.scopes)? else {
+            return Ok(None);
+        };
+        let source = Arc::new(source);
+        self.google
+            .lock()
+            .insert(provider_id.to_string(), Arc::clone(&source));
+        Ok(Some(source))
+    }
+
     fn save_oauth_credential(
         &self,
         provider_id: &str,
@@ -185,6 +289,20 @@ impl AuthResolver for AuthManagerResolver {
     }
 }
 
+/// The environment variables that may hold `request`'s API key. They only
+/// stand for the provider's default account, never a named one.
+fn default_account_env<'a>(
+    request: &AuthRequest,
+    env: &'a [String],
+) -> impl Iterator<Item = &'a String> {
+    let env: &[String] = if request.account.is_some() { &[] } else { env };
+    env.iter()
+}
+
+fn google_adc_auth(source: Arc<GoogleTokenSource>) -> ResolvedAuth {
+    ResolvedAuth::google_adc(source.cached_token(), source.project_id(), source)
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
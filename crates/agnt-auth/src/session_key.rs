@@ -0,0 +1,30 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use rand::random;
+
+use crate::error::Error;
+use crate::store::decode_key;
+
+const SESSION_KEY_ACCOUNT: &str = "session_db_key_v1";
+
+/// Load the session DB encryption key from the OS keychain, creating one on
+/// first use.
+pub fn load_or_create_session_key(service: &str) -> Result<[u8; 32], Error> {
+    let entry = keyring::Entry::new(service, SESSION_KEY_ACCOUNT)?;
+
+    match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded),
+        Err(keyring::Error::NoEntry) => {
+            let key: [u8; 32] = random();
+            entry.set_password(&STANDARD_NO_PAD.encode(key))?;
+            Ok(key)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Load the session DB encryption key, failing if none was created.
+pub fn load_session_key(service: &str) -> Result<[u8; 32], Error> {
+    let entry = keyring::Entry::new(service, SESSION_KEY_ACCOUNT)?;
+    decode_key(&entry.get_password()?)
+}
@@ -18,6 +18,9 @@ const ENCRYPTION_KEY_ACCOUNT: &str = "provider_credentials_key_v1";
 pub enum StoredCredential {
     ApiKey {
         api_key: String,
+        /// Values of the provider's credential fields, e.g. `organization`.
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        metadata: HashMap<String, String>,
     },
     OAuthPkce {
         access_token: String,
@@ -169,7 +172,7 @@ fn default_write_method() -> CredentialEncryptionMethod {
     }
 }
 
-fn decode_key(encoded: &str) -> Result<[u8; 32], Error> {
+pub(crate) fn decode_key(encoded: &str) -> Result<[u8; 32], Error> {
     let bytes = STANDARD_NO_PAD
         .decode(encoded)
         .map_err(|err| Error::Other(format!("invalid encryption key encoding: {err}")))?;
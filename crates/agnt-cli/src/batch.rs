@@ -0,0 +1,183 @@
+//! `agnt batch`: the same prompt run in many checkouts at once, each as a
+//! session of its own, followed by a report of how every run went.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use agnt_auth::AuthManager;
+use agnt_db::Store;
+use agnt_llm_registry::Registry;
+use parking_lot::Mutex;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::headless::{self, RunOptions};
+use crate::patch;
+
+/// How one checkout's run went.
+struct RepoResult {
+    root: PathBuf,
+    session_id: Option<String>,
+    /// Patch series of the run's file changes, if it made any.
+    patch: Option<PathBuf>,
+    error: Option<String>,
+}
+
+impl RepoResult {
+    fn failed(root: PathBuf, session_id: Option<String>, error: String) -> Self {
+        Self {
+            root,
+            session_id,
+            patch: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// What `agnt batch` runs, and where.
+pub struct Batch<'a> {
+    /// File listing the checkouts.
+    pub repos_file: &'a Path,
+    pub prompt_file: &'a Path,
+    /// How many checkouts run at the same time.
+    pub jobs: usize,
+    /// Profile to run with instead of each project's own.
+    pub profile: Option<&'a str>,
+}
+
+/// Run the batch's prompt in every checkout it lists and print the report.
+pub async fn run(
+    store: &Arc<Mutex<Store>>,
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    options: &RunOptions<'_>,
+    batch: &Batch<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Batch {
+        repos_file,
+        prompt_file,
+        jobs,
+        profile,
+    } = *batch;
+    let prompt = std::fs::read_to_string(prompt_file)?;
+    if prompt.trim().is_empty() {
+        return Err(format!("{} is empty", prompt_file.display()).into());
+    }
+    let repos = read_repos(repos_file)?;
+    if repos.is_empty() {
+        return Err(format!("{} lists no checkouts", repos_file.display()).into());
+    }
+
+    let prompt: Arc<str> = prompt.into();
+    let attach_mentions = !options.config.skip_mention_attachments;
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut results: Vec<Option<RepoResult>> = repos.iter().map(|_| None).collect();
+    let mut runs = JoinSet::new();
+    // Set up one checkout at a time, as that may ask for credentials; only
+    // the turns run concurrently.
+    for (index, root) in repos.into_iter().enumerate() {
+        if !root.is_dir() {
+            let error = "not a directory".to_string();
+            results[index] = Some(RepoResult::failed(root, None, error));
+            continue;
+        }
+        let (mut agent, session_store) =
+            match headless::open_project(store, registry, auth, options, &root, profile).await {
+                Ok(project) => project,
+                Err(err) => {
+                    results[index] = Some(RepoResult::failed(root, None, err.to_string()));
+                    continue;
+                }
+            };
+        let prompt = Arc::clone(&prompt);
+        let permits = Arc::clone(&permits);
+        runs.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            eprintln!("Running in {}...", root.display());
+            let outcome =
+                headless::run_turn(&mut agent, &session_store, &root, &prompt, attach_mentions)
+                    .await
+                    .map_err(|err| err.to_string());
+            let result = match outcome {
+                Ok(outcome) if outcome.error.is_some() => RepoResult {
+                    root,
+                    session_id: Some(outcome.session_id),
+                    patch: None,
+                    error: outcome.error,
+                },
+                Ok(outcome) => {
+                    let export = patch::export_active_session(&session_store.lock())
+                        .map_err(|err| err.to_string());
+                    match export {
+                        Ok(series) => RepoResult {
+                            root,
+                            session_id: Some(outcome.session_id),
+                            patch: series.map(|series| series.path),
+                            error: None,
+                        },
+                        Err(err) => RepoResult::failed(
+                            root,
+                            Some(outcome.session_id),
+                            format!("patch export failed: {err}"),
+                        ),
+                    }
+                }
+                Err(err) => RepoResult::failed(root, None, err),
+            };
+            (index, result)
+        });
+    }
+    while let Some(joined) = runs.join_next().await {
+        let (index, result) = joined?;
+        results[index] = Some(result);
+    }
+
+    print_report(&results.into_iter().flatten().collect::<Vec<_>>());
+    Ok(())
+}
+
+/// The checkouts listed one per line, skipping blank lines and `#`
+/// comments. Relative paths are relative to the list's directory.
+fn read_repos(repos_file: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let base = repos_file.parent().unwrap_or(Path::new("."));
+    Ok(std::fs::read_to_string(repos_file)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .map(|path| std::fs::canonicalize(&path).unwrap_or(path))
+        .collect())
+}
+
+fn print_report(results: &[RepoResult]) {
+    let failed = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .count();
+    println!();
+    println!(
+        "{} of {} run(s) succeeded, {failed} failed.",
+        results.len() - failed,
+        results.len()
+    );
+    for result in results {
+        let state = if result.error.is_some() {
+            "failed"
+        } else {
+            "ok"
+        };
+        println!("{state:<6} {}", result.root.display());
+        if let Some(session_id) = &result.session_id {
+            println!(
+                "  session: {session_id} (agnt tui {} --resume={session_id})",
+                result.root.display()
+            );
+        }
+        if let Some(patch) = &result.patch {
+            println!("  patch:   {}", patch.display());
+        }
+        if let Some(error) = &result.error {
+            println!("  error:   {error}");
+        }
+    }
+}
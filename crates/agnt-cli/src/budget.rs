@@ -0,0 +1,158 @@
+//! Daily and monthly spend budgets per provider, checked against the
+//! estimated cost of stored turns.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use agnt_app::{ProviderBudget, UserConfig};
+use agnt_db::{Store, TurnUsage};
+use agnt_llm_registry::{ModelCost, Registry};
+
+use crate::stats;
+
+/// Share of a budget at which the UI starts warning.
+const WARN_RATIO: f64 = 0.8;
+/// Far enough back to cover the whole current calendar month.
+const MONTH_LOOKBACK: Duration = Duration::from_secs(32 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+/// Spend against the budget closest to (or furthest past) its limit.
+#[derive(Debug, Clone)]
+pub struct BudgetStatus {
+    pub provider_id: String,
+    pub period: BudgetPeriod,
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+}
+
+impl BudgetStatus {
+    pub fn is_exceeded(&self) -> bool {
+        self.spent_usd >= self.limit_usd
+    }
+
+    pub fn message(&self) -> String {
+        let period = match self.period {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        };
+        let state = if self.is_exceeded() {
+            "exceeded"
+        } else {
+            "nearly used"
+        };
+        format!(
+            "{} {period} budget {state}: ${:.2} of ${:.2}",
+            self.provider_id, self.spent_usd, self.limit_usd
+        )
+    }
+}
+
+/// Tracks the budget of the provider the agent generates with.
+///
+/// Spend is kept as a running total: the first check reads the current
+/// month's turns, later ones only the turns stored since. Turns synced in
+/// with older timestamps are picked up on the next start.
+pub struct BudgetTracker {
+    provider_id: String,
+    budget: ProviderBudget,
+    /// models.dev prices of the provider's models, by model id.
+    prices: HashMap<String, ModelCost>,
+    /// Estimated spend of the turns counted so far, by local day
+    /// (`YYYY-MM-DD`).
+    spent_by_day: HashMap<String, f64>,
+    /// `created_at_ms` of the newest turn counted and the ids of the turns
+    /// stored at that instant, so the next check skips them.
+    counted_until: Option<(i64, HashSet<String>)>,
+}
+
+impl BudgetTracker {
+    /// `None` when the config sets no budget for `provider_id`.
+    pub fn new(registry: &Registry, config: &UserConfig, provider_id: &str) -> Option<Self> {
+        let budget = config.budgets.get(provider_id)?.clone();
+        if budget.daily_usd.is_none() && budget.monthly_usd.is_none() {
+            return None;
+        }
+        let prices = registry
+            .list_models(provider_id)
+            .into_iter()
+            .filter_map(|model| Some((model.id, model.cost?)))
+            .collect();
+        Some(Self {
+            provider_id: provider_id.to_string(),
+            budget,
+            prices,
+            spent_by_day: HashMap::new(),
+            counted_until: None,
+        })
+    }
+
+    /// The budget that has reached the warning threshold, preferring the one
+    /// most used up. `None` while spend is below it.
+    pub fn status(&mut self, store: &mut Store) -> agnt_db::Result<Option<BudgetStatus>> {
+        let since_ms = match &self.counted_until {
+            Some((counted_ms, _)) => *counted_ms,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .saturating_sub(MONTH_LOOKBACK)
+                .as_millis() as i64,
+        };
+        let usage_log = store.usage_log();
+        let today = usage_log.today()?;
+        // `YYYY-MM-`
+        let month = &today[..today.len().min(8)];
+
+        for turn in usage_log.turns(Some(since_ms))? {
+            match &mut self.counted_until {
+                Some((counted_ms, ids)) if *counted_ms == turn.created_at_ms => {
+                    if !ids.insert(turn.turn_id.clone()) {
+                        continue;
+                    }
+                }
+                counted_until => {
+                    *counted_until =
+                        Some((turn.created_at_ms, HashSet::from([turn.turn_id.clone()])));
+                }
+            }
+            if let Some(cost) = self.turn_cost(&turn) {
+                *self.spent_by_day.entry(turn.day).or_default() += cost;
+            }
+        }
+
+        self.spent_by_day.retain(|day, _| day.starts_with(month));
+        let spent_today = self.spent_by_day.get(&today).copied().unwrap_or(0.0);
+        let spent_month: f64 = self.spent_by_day.values().sum();
+
+        let candidates = [
+            (BudgetPeriod::Daily, self.budget.daily_usd, spent_today),
+            (BudgetPeriod::Monthly, self.budget.monthly_usd, spent_month),
+        ];
+        Ok(candidates
+            .into_iter()
+            .filter_map(|(period, limit, spent)| Some((period, limit?, spent)))
+            .filter(|(_, limit, spent)| *spent >= limit * WARN_RATIO)
+            .max_by(|a, b| (a.2 / a.1).total_cmp(&(b.2 / b.1)))
+            .map(|(period, limit_usd, spent_usd)| BudgetStatus {
+                provider_id: self.provider_id.clone(),
+                period,
+                spent_usd,
+                limit_usd,
+            }))
+    }
+
+    /// Estimated cost of `turn` if it was generated with this provider at a
+    /// known price.
+    fn turn_cost(&self, turn: &TurnUsage) -> Option<f64> {
+        let (provider, model) = stats::turn_model(turn)?;
+        if provider != self.provider_id {
+            return None;
+        }
+        let price = self.prices.get(&model)?;
+        Some(stats::estimate_cost(price, &stats::token_usage(turn)?))
+    }
+}
@@ -0,0 +1,195 @@
+//! Net effect of a session on the workspace: one diff per file, from its
+//! content before the session's first edit to what is on disk now.
+//!
+//! The starting content is recovered by undoing the session's edit diffs
+//! from the current file, newest first.
+
+use similar::TextDiff;
+
+use crate::patch::{diff_paths, file_diffs, is_plain_move};
+use crate::session::SessionStore;
+
+/// Same context as the `edit` tool's diffs.
+const HUNK_CONTEXT_LINES: usize = 5;
+
+/// Cumulative diff of the active session's edits.
+pub struct SessionChanges {
+    /// Unified diff of every file that differs from the session start.
+    pub diff: String,
+    /// Files changed outside agnt since the session edited them, so their
+    /// starting content can't be recovered.
+    pub unknown: Vec<String>,
+}
+
+/// One file through the session's edits, across moves.
+struct FileHistory {
+    /// Path before the first edit; `None` if the session created the file.
+    original_path: Option<String>,
+    path: String,
+    exists: bool,
+    diffs: Vec<String>,
+}
+
+/// `None` when the session's turns didn't edit any file.
+pub fn session_changes(
+    store: &SessionStore,
+) -> Result<Option<SessionChanges>, Box<dyn std::error::Error>> {
+    let mut files: Vec<FileHistory> = Vec::new();
+    for turn in store.active_turns()? {
+        for diff in file_diffs(&turn) {
+            let Some((old_path, new_path)) = diff_paths(&diff) else {
+                continue;
+            };
+            let Some(path) = new_path.or(old_path) else {
+                continue;
+            };
+            let tracked = files.iter().position(|file| match old_path {
+                Some(old_path) => file.exists && file.path == old_path,
+                None => !file.exists && file.path == path,
+            });
+            let file = match tracked {
+                Some(index) => &mut files[index],
+                None => {
+                    files.push(FileHistory {
+                        original_path: old_path.map(str::to_string),
+                        path: path.to_string(),
+                        exists: old_path.is_some(),
+                        diffs: Vec::new(),
+                    });
+                    files.last_mut().expect("just pushed")
+                }
+            };
+            file.path = path.to_string();
+            file.exists = new_path.is_some();
+            file.diffs.push(diff);
+        }
+    }
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut changes = SessionChanges {
+        diff: String::new(),
+        unknown: Vec::new(),
+    };
+    for file in files {
+        let current = if file.exists {
+            std::fs::read_to_string(&file.path)?
+                .lines()
+                .map(str::to_string)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut original = current.clone();
+        if file
+            .diffs
+            .iter()
+            .rev()
+            .any(|diff| undo_diff(&mut original, diff).is_none())
+        {
+            changes.unknown.push(file.path);
+            continue;
+        }
+        changes.diff.push_str(&net_diff(&file, &original, &current));
+    }
+    Ok(Some(changes))
+}
+
+/// Diff from the file at the session start to now; empty if it's the same.
+fn net_diff(file: &FileHistory, original: &[String], current: &[String]) -> String {
+    let old_label = match &file.original_path {
+        Some(path) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let new_label = if file.exists {
+        format!("b/{}", file.path)
+    } else {
+        "/dev/null".to_string()
+    };
+    let moved = file.exists && file.original_path.as_deref() != Some(file.path.as_str());
+    if original == current {
+        // A move is worth showing even without content changes, like `edit`
+        // does.
+        return if moved && file.original_path.is_some() {
+            format!("--- {old_label}\n+++ {new_label}\n@@ -0,0 +0,0 @@\n (no content changes)\n")
+        } else {
+            String::new()
+        };
+    }
+
+    let old_text = original
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    let new_text = current
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    TextDiff::from_lines(&old_text, &new_text)
+        .unified_diff()
+        .context_radius(HUNK_CONTEXT_LINES)
+        .header(&old_label, &new_label)
+        .to_string()
+}
+
+/// Turn `lines` back into what they were before `diff`. `None` if they
+/// don't match what the diff produced.
+fn undo_diff(lines: &mut Vec<String>, diff: &str) -> Option<()> {
+    if is_plain_move(diff) {
+        return Some(());
+    }
+    let hunks = parse_hunks(diff)?;
+    for hunk in hunks.iter().rev() {
+        let start = hunk.new_start;
+        let end = start + hunk.new_lines.len();
+        if lines.get(start..end)? != hunk.new_lines.as_slice() {
+            return None;
+        }
+        lines.splice(start..end, hunk.old_lines.iter().cloned());
+    }
+    Some(())
+}
+
+struct Hunk {
+    /// Index of the hunk's first line in the new file.
+    new_start: usize,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+fn parse_hunks(diff: &str) -> Option<Vec<Hunk>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for line in diff.lines().skip(2) {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            // `@@ -a,b +c,d @@`, where an empty side counts from the line
+            // before.
+            let new_range = header.split(' ').nth(1)?.strip_prefix('+')?;
+            let (start, len) = new_range.split_once(',')?;
+            let start = start.parse::<usize>().ok()?;
+            let len = len.parse::<usize>().ok()?;
+            hunks.push(Hunk {
+                new_start: if len == 0 {
+                    start
+                } else {
+                    start.checked_sub(1)?
+                },
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+            });
+            continue;
+        }
+        let hunk = hunks.last_mut()?;
+        let (prefix, content) = line.split_at_checked(1)?;
+        match prefix {
+            " " => {
+                hunk.old_lines.push(content.to_string());
+                hunk.new_lines.push(content.to_string());
+            }
+            "-" => hunk.old_lines.push(content.to_string()),
+            "+" => hunk.new_lines.push(content.to_string()),
+            _ => return None,
+        }
+    }
+    Some(hunks)
+}
@@ -0,0 +1,271 @@
+//! `/compare`: one prompt answered by two models at once, shown side by
+//! side, to see which model and reasoning effort suit a task. The answers
+//! are generated without tools and are not added to the conversation.
+
+use agnt_core::{Agent, AgentEvent, AgentStream};
+use agnt_llm::LanguageModel;
+use agnt_llm_openai::{OpenAIRequestExt, ReasoningEffort, ReasoningSummary};
+use agnt_llm_registry::{ModelsDevSpec, Registry};
+
+use crate::session::{RetryTurn, SessionStore};
+use crate::tui::app::turn_footer;
+use crate::{offline, openai_reasoning_effort, project_settings};
+
+const EFFORTS: &[&str] = &["none", "minimal", "low", "medium", "high"];
+
+/// Builds the models `/compare` runs and `/retry:with` switches to, from
+/// the registry the agent's own model came from.
+pub struct CompareModels {
+    registry: Registry,
+    offline: bool,
+    /// Effort for models given without one.
+    default_effort: String,
+}
+
+/// A model and reasoning effort to continue the session with
+/// (`/retry:with`).
+pub struct ModelSwitch {
+    provider_id: String,
+    model_id: String,
+    effort: String,
+    model: LanguageModel,
+}
+
+impl ModelSwitch {
+    /// Make `agent` generate with this model and effort from the next turn
+    /// on, and `session_store` store turns as generated with them.
+    pub fn apply(self, agent: &mut Agent, session_store: &mut SessionStore) {
+        session_store.set_model(&self.provider_id, &self.model_id, &self.effort);
+        let effort = openai_reasoning_effort(&self.effort);
+        agent.set_model(self.model);
+        agent.reconfigure_request(move |req| {
+            req.reasoning_effort(effort);
+        });
+    }
+}
+
+/// One side of a comparison.
+pub struct CompareModel {
+    /// `provider/model@effort`, as shown above its answer.
+    label: String,
+    model: LanguageModel,
+    effort: ReasoningEffort,
+}
+
+impl CompareModels {
+    pub fn new(registry: Registry, offline: bool, default_effort: &str) -> Self {
+        Self {
+            registry,
+            offline,
+            default_effort: default_effort.to_string(),
+        }
+    }
+
+    /// Build models from a refreshed models.dev catalog from now on.
+    pub fn load_spec(&mut self, spec: ModelsDevSpec) {
+        self.registry.load_spec(spec);
+    }
+
+    /// Parse `/compare` input: two `provider/model[@effort]` specs, then the
+    /// prompt, if any.
+    pub fn parse(
+        &mut self,
+        input: &str,
+    ) -> Result<([CompareModel; 2], String), Box<dyn std::error::Error>> {
+        let mut rest = input.trim_start();
+        let mut specs = Vec::new();
+        for _ in 0..2 {
+            let (spec, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if spec.is_empty() {
+                return Err("name two models, e.g. openai/gpt-5@low openai/gpt-5@high".into());
+            }
+            specs.push(self.model(spec)?);
+            rest = after.trim_start();
+        }
+        let [first, second]: [CompareModel; 2] =
+            specs.try_into().map_err(|_| "name two models to compare")?;
+        Ok(([first, second], rest.trim().to_string()))
+    }
+
+    /// Parse `/retry:with` input: `provider/model[@effort]`, or `@effort`
+    /// alone to keep `current`, the `provider/model` generated with so far.
+    pub fn switch(
+        &mut self,
+        input: &str,
+        current: &str,
+    ) -> Result<ModelSwitch, Box<dyn std::error::Error>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("name a model, an effort or both, e.g. openai/gpt-5@high".into());
+        }
+        if input.starts_with('@') {
+            self.resolve(&format!("{current}{input}"))
+        } else {
+            self.resolve(input)
+        }
+    }
+
+    fn model(&mut self, spec: &str) -> Result<CompareModel, Box<dyn std::error::Error>> {
+        let side = self.resolve(spec)?;
+        Ok(CompareModel {
+            label: format!("{}/{}@{}", side.provider_id, side.model_id, side.effort),
+            effort: openai_reasoning_effort(&side.effort),
+            model: side.model,
+        })
+    }
+
+    fn resolve(&mut self, spec: &str) -> Result<ModelSwitch, Box<dyn std::error::Error>> {
+        // `@` also names an account, as in `openai@work/gpt-5.2`; only a
+        // suffix after the model is an effort.
+        let (model, effort) = match spec.rsplit_once('@') {
+            Some((model, effort)) if !effort.contains('/') => (model, effort),
+            _ => (spec, self.default_effort.as_str()),
+        };
+        if !EFFORTS.contains(&effort) {
+            return Err(format!(
+                "unknown reasoning effort '{effort}' (one of {})",
+                EFFORTS.join(", ")
+            )
+            .into());
+        }
+        let (provider_id, model_id) = project_settings::split_model(model)
+            .ok_or_else(|| format!("'{model}' is not of the form provider/model"))?;
+        if self.offline {
+            offline::ensure_local_provider(&self.registry, provider_id)?;
+        }
+        Ok(ModelSwitch {
+            model: self.registry.model(provider_id, model_id)?,
+            provider_id: provider_id.to_string(),
+            model_id: model_id.to_string(),
+            effort: effort.to_string(),
+        })
+    }
+}
+
+/// Two answers to one prompt, streaming side by side.
+pub struct Comparison {
+    pub prompt: String,
+    pub panes: [ComparePane; 2],
+    /// Lines both panes are scrolled down by.
+    pub scroll: u16,
+}
+
+pub struct ComparePane {
+    pub label: String,
+    pub text: String,
+    pub status: PaneStatus,
+    stream: Option<AgentStream>,
+}
+
+pub enum PaneStatus {
+    Generating,
+    /// Finished, with the turn footer.
+    Done(String),
+    Failed(String),
+}
+
+impl Comparison {
+    /// Ask both models `prompt` after the conversation so far. With an empty
+    /// prompt, the conversation's last prompt is asked again in its place.
+    pub fn start(agent: &Agent, models: [CompareModel; 2], prompt: String) -> Result<Self, String> {
+        let messages = agent.messages();
+        let (history, prompt) = if prompt.is_empty() {
+            let retry = RetryTurn::rewind(&messages)
+                .ok_or("no prompt to compare; add one after the two models")?;
+            (retry.state.messages, retry.user_text)
+        } else {
+            (messages, prompt)
+        };
+
+        let panes = models.map(|side| {
+            let effort = side.effort;
+            let stream = agent.submit_detached(side.model, history.clone(), &prompt, move |req| {
+                req.reasoning_effort(effort);
+                req.reasoning_summary(ReasoningSummary::Detailed);
+            });
+            ComparePane {
+                label: side.label,
+                text: String::new(),
+                status: PaneStatus::Generating,
+                stream: Some(stream),
+            }
+        });
+        Ok(Self {
+            prompt,
+            panes,
+            scroll: 0,
+        })
+    }
+
+    pub fn is_generating(&self) -> bool {
+        self.panes
+            .iter()
+            .any(|pane| matches!(pane.status, PaneStatus::Generating))
+    }
+
+    /// Both answers' streams with their pane index, for callers that poll
+    /// each stream on its own task; events go back through
+    /// [`Self::handle_event`] and [`Self::stream_ended`].
+    pub fn take_streams(&mut self) -> Vec<(usize, AgentStream)> {
+        self.panes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, pane)| Some((index, pane.stream.take()?)))
+            .collect()
+    }
+
+    /// A taken stream ended; the pane's answer was cut off if it hadn't
+    /// finished.
+    pub fn stream_ended(&mut self, pane: usize) {
+        if let Some(side) = self.panes.get(pane)
+            && matches!(side.status, PaneStatus::Generating)
+        {
+            self.handle_event(pane, ended_early());
+        }
+    }
+
+    /// The next event from either answer, with the index of its pane;
+    /// `None` once both are finished.
+    pub async fn next_event(&mut self) -> Option<(usize, AgentEvent)> {
+        let [first, second] = &mut self.panes;
+        let (index, event) = tokio::select! {
+            event = next_pane_event(&mut first.stream), if first.stream.is_some() => (0, event),
+            event = next_pane_event(&mut second.stream), if second.stream.is_some() => (1, event),
+            else => return None,
+        };
+        // A stream that ends without finishing was cut off.
+        Some((index, event.unwrap_or_else(ended_early)))
+    }
+
+    pub fn handle_event(&mut self, pane: usize, event: AgentEvent) {
+        let Some(pane) = self.panes.get_mut(pane) else {
+            return;
+        };
+        match event {
+            AgentEvent::TextDelta { delta } => pane.text.push_str(&delta),
+            AgentEvent::Refusal { message, guidance } => {
+                pane.text.push_str(&message.unwrap_or_default());
+                pane.text.push_str(&format!("\n\n[refused: {guidance}]"));
+            }
+            AgentEvent::TurnComplete { usage, stats } => {
+                pane.status = PaneStatus::Done(turn_footer(&usage, &stats));
+                pane.stream = None;
+            }
+            AgentEvent::Error { error } => {
+                pane.status = PaneStatus::Failed(error);
+                pane.stream = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn ended_early() -> AgentEvent {
+    AgentEvent::Error {
+        error: "the answer ended early".to_string(),
+    }
+}
+
+async fn next_pane_event(stream: &mut Option<AgentStream>) -> Option<AgentEvent> {
+    stream.as_mut()?.next().await
+}
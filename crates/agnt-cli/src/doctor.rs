@@ -0,0 +1,380 @@
+//! `agnt doctor`: check the environment agnt depends on and suggest fixes.
+
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use agnt_auth::{AuthManager, CredentialStatus};
+use agnt_db::Store;
+use agnt_llm_registry::Registry;
+use parking_lot::Mutex;
+
+use crate::offline;
+
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Age past which the cached models.dev catalog is reported as stale.
+const SPEC_CACHE_STALE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Report {
+    warnings: usize,
+    failures: usize,
+}
+
+impl Report {
+    fn section(&self, title: &str) {
+        println!();
+        println!("{title}");
+    }
+
+    fn check(&mut self, status: Status, label: &str, detail: &str, fix: Option<&str>) {
+        let marker = match status {
+            Status::Ok => "ok",
+            Status::Warn => {
+                self.warnings += 1;
+                "warn"
+            }
+            Status::Fail => {
+                self.failures += 1;
+                "FAIL"
+            }
+        };
+        println!("  [{marker:>4}] {label}: {detail}");
+        if let Some(fix) = fix {
+            println!("         fix: {fix}");
+        }
+    }
+}
+
+/// Run every check and print the results. Returns an error when a check
+/// failed, so the exit status reflects the outcome.
+pub async fn run(
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    store: &Arc<Mutex<Store>>,
+    db_path: &Path,
+    default_provider_id: &str,
+    offline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = Report {
+        warnings: 0,
+        failures: 0,
+    };
+
+    report.section("Model catalog (models.dev)");
+    // Checked before loading the catalog, which rewrites the cache.
+    check_spec_cache(&mut report);
+    let started = Instant::now();
+    match offline::load_spec(registry, offline).await {
+        Ok(()) if offline && registry.spec().is_none() => report.check(
+            Status::Warn,
+            "catalog",
+            "offline and no cached catalog",
+            Some("run agnt once without --offline to cache it"),
+        ),
+        Ok(()) if offline => report.check(
+            Status::Ok,
+            "catalog",
+            &format!(
+                "loaded {} providers from the offline cache",
+                registry.spec_providers().len()
+            ),
+            None,
+        ),
+        Ok(()) => report.check(
+            Status::Ok,
+            "catalog",
+            &format!(
+                "fetched {} providers in {} ms",
+                registry.spec_providers().len(),
+                started.elapsed().as_millis()
+            ),
+            None,
+        ),
+        Err(err) => report.check(
+            Status::Fail,
+            "catalog",
+            &err.to_string(),
+            Some("agnt needs https://models.dev at startup; check your connection or proxy, or use --offline"),
+        ),
+    }
+
+    let mut provider_ids = registry
+        .known_providers()
+        .into_iter()
+        .filter(|provider| provider.configured || provider.id == default_provider_id)
+        .map(|provider| provider.id)
+        .collect::<Vec<_>>();
+    if !provider_ids.iter().any(|id| id == default_provider_id) {
+        provider_ids.insert(0, default_provider_id.to_string());
+    }
+
+    report.section("Credentials");
+    for provider_id in &provider_ids {
+        check_credentials(&mut report, registry, auth, provider_id);
+    }
+
+    report.section("Provider reachability");
+    let client = reqwest::Client::builder()
+        .timeout(REACHABILITY_TIMEOUT)
+        .build()?;
+    for provider_id in &provider_ids {
+        let Some(endpoint) = registry.api_endpoint(provider_id) else {
+            report.check(
+                Status::Warn,
+                provider_id,
+                "no API endpoint known",
+                Some("the provider's SDK default is used; nothing to check"),
+            );
+            continue;
+        };
+        if offline && !offline::is_local_endpoint(&endpoint) {
+            report.check(
+                Status::Warn,
+                provider_id,
+                &format!("{endpoint} is not local; not used in offline mode"),
+                Some("point it at a local server with \"provider_endpoints\" in the config file"),
+            );
+            continue;
+        }
+        // Any HTTP response, even an auth error, means the host is reachable.
+        match client.get(&endpoint).send().await {
+            Ok(response) => report.check(
+                Status::Ok,
+                provider_id,
+                &format!("{endpoint} answered with {}", response.status()),
+                None,
+            ),
+            Err(err) => report.check(
+                Status::Fail,
+                provider_id,
+                &format!("{endpoint} is unreachable: {err}"),
+                Some("check your network, proxy (HTTPS_PROXY) or firewall settings"),
+            ),
+        }
+    }
+
+    report.section("Session database");
+    check_database(&mut report, store, db_path);
+
+    report.section("Terminal");
+    check_terminal(&mut report);
+
+    println!();
+    match (report.failures, report.warnings) {
+        (0, 0) => println!("Everything looks good."),
+        (0, warnings) => println!("{warnings} warning(s); agnt should still work."),
+        (failures, _) => return Err(format!("{failures} check(s) failed").into()),
+    }
+    Ok(())
+}
+
+fn check_credentials(
+    report: &mut Report,
+    registry: &Registry,
+    auth: &Arc<AuthManager>,
+    provider_id: &str,
+) {
+    let Some(request) = registry.auth_request(provider_id) else {
+        report.check(
+            Status::Warn,
+            provider_id,
+            "unknown provider",
+            Some("run `agnt init` to choose a provider"),
+        );
+        return;
+    };
+
+    match auth.credential_status(&request) {
+        Ok(CredentialStatus::Environment(var)) => report.check(
+            Status::Ok,
+            provider_id,
+            &format!("API key from ${var}"),
+            None,
+        ),
+        Ok(CredentialStatus::ApiKey) => {
+            report.check(Status::Ok, provider_id, "stored API key", None)
+        }
+        Ok(CredentialStatus::OAuth {
+            expired: false,
+            expires_at_ms,
+        }) => report.check(
+            Status::Ok,
+            provider_id,
+            &format!(
+                "signed in, token expires {}",
+                describe_expiry(expires_at_ms)
+            ),
+            None,
+        ),
+        Ok(CredentialStatus::OAuth { expired: true, .. }) => report.check(
+            Status::Warn,
+            provider_id,
+            "access token expired",
+            Some("it is refreshed on next start; if that fails, run `agnt init` to sign in again"),
+        ),
+        Ok(CredentialStatus::GoogleAdc(path)) => report.check(
+            Status::Ok,
+            provider_id,
+            &format!(
+                "Google application default credentials from {}",
+                path.display()
+            ),
+            None,
+        ),
+        Ok(CredentialStatus::Missing) => report.check(
+            Status::Fail,
+            provider_id,
+            "no credentials",
+            Some("run `agnt init` to sign in"),
+        ),
+        Err(err) => report.check(
+            Status::Fail,
+            provider_id,
+            &format!("stored credentials are unreadable: {err}"),
+            Some("run `agnt init` to sign in again and replace them"),
+        ),
+    }
+}
+
+fn check_database(report: &mut Report, store: &Arc<Mutex<Store>>, db_path: &Path) {
+    match store.lock().maintenance().quick_check() {
+        Ok(problems) if problems.is_empty() => report.check(
+            Status::Ok,
+            "integrity",
+            &format!("{} passed quick_check", db_path.display()),
+            None,
+        ),
+        Ok(problems) => report.check(
+            Status::Fail,
+            "integrity",
+            &problems.join("; "),
+            Some(&format!(
+                "back up {} and recover it with `sqlite3 <file> .recover`",
+                db_path.display()
+            )),
+        ),
+        Err(err) => report.check(
+            Status::Fail,
+            "integrity",
+            &err.to_string(),
+            Some("make sure no other tool holds the database open, then retry"),
+        ),
+    }
+}
+
+fn check_terminal(report: &mut Report) {
+    if !std::io::stdout().is_terminal() {
+        report.check(
+            Status::Warn,
+            "tty",
+            "stdout is not a terminal; skipped terminal checks",
+            None,
+        );
+        return;
+    }
+
+    match crossterm::terminal::supports_keyboard_enhancement() {
+        Ok(true) => report.check(
+            Status::Ok,
+            "keyboard",
+            "enhanced keyboard reporting supported",
+            None,
+        ),
+        Ok(false) => report.check(
+            Status::Warn,
+            "keyboard",
+            "enhanced keyboard reporting unsupported; Shift+Enter may submit instead of adding a line",
+            Some(
+                "use Alt+Enter for new lines, or a terminal with the kitty keyboard protocol (kitty, WezTerm, foot, Ghostty)",
+            ),
+        ),
+        Err(err) => report.check(
+            Status::Warn,
+            "keyboard",
+            &format!("could not query keyboard support: {err}"),
+            None,
+        ),
+    }
+
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    if matches!(colorterm.as_str(), "truecolor" | "24bit") {
+        report.check(Status::Ok, "color", "truecolor", None);
+    } else {
+        report.check(
+            Status::Warn,
+            "color",
+            "truecolor not advertised (COLORTERM is not `truecolor`)",
+            Some("enable 24-bit color in your terminal, or set COLORTERM=truecolor if it already supports it"),
+        );
+    }
+}
+
+fn check_spec_cache(report: &mut Report) {
+    let path = match agnt_app::model_spec_cache_path() {
+        Ok(path) => path,
+        Err(err) => {
+            report.check(Status::Warn, "cache", &err.to_string(), None);
+            return;
+        }
+    };
+    match std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => {
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            let detail = format!("{} written {}", path.display(), describe_age(age));
+            if age > SPEC_CACHE_STALE_AFTER {
+                report.check(
+                    Status::Warn,
+                    "cache",
+                    &format!("{detail}; offline runs see an outdated model list"),
+                    Some("refresh the model list with /models:refresh, or run agnt once without --offline"),
+                );
+            } else {
+                report.check(Status::Ok, "cache", &detail, None);
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => report.check(
+            Status::Warn,
+            "cache",
+            &format!("no cached catalog at {}", path.display()),
+            Some("run agnt once without --offline to cache it"),
+        ),
+        Err(err) => report.check(
+            Status::Warn,
+            "cache",
+            &format!("{}: {err}", path.display()),
+            None,
+        ),
+    }
+}
+
+fn describe_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    match minutes {
+        0 => "under a minute ago".to_string(),
+        1..=119 => format!("{minutes} min ago"),
+        120..=2879 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / (24 * 60)),
+    }
+}
+
+fn describe_expiry(expires_at_ms: u64) -> String {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let minutes = expires_at_ms.saturating_sub(now_ms) / 60_000;
+    match minutes {
+        0 => "in under a minute".to_string(),
+        1..=119 => format!("in {minutes} min"),
+        _ => format!("in {} h", minutes / 60),
+    }
+}
@@ -1,10 +1,23 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    time::Duration,
+};
 
-use agnt_core::{Agent, AgentEvent, ConversationState, DisplayBody};
+use agnt_app::WebhookEvent;
+use agnt_core::{
+    Agent, AgentEvent, AgentStream, Attachment, ConversationState, DisplayBody, PromptTemplate,
+    RequestInspection,
+};
+use gpui::prelude::FluentBuilder as _;
 use gpui::{
-    AnyElement, App as GpuiApp, AppContext, ClickEvent, Context, Entity, InteractiveElement as _,
-    IntoElement, KeyBinding, ListAlignment, ListState, ParentElement, Pixels, Render,
-    ScrollWheelEvent, Styled, Subscription, Task, Window, WindowOptions, div, list, point, px,
+    AnyElement, App as GpuiApp, AppContext, ClickEvent, Context, Entity, ExternalPaths,
+    ImageSource, InteractiveElement as _, IntoElement, KeyBinding, KeyDownEvent, ListAlignment,
+    ListState, ObjectFit, ParentElement, Pixels, Render, ScrollWheelEvent, SharedString,
+    StatefulInteractiveElement as _, Styled, StyledImage as _, Subscription, Task, Window,
+    WindowOptions, div, img, list, point, px, size,
 };
 use gpui_component::{
     ActiveTheme as _, Disableable as _, Root, Sizable as _, StyledExt as _,
@@ -19,15 +32,57 @@ use gpui_component::{
     v_flex,
 };
 
-use crate::session::SharedSessionStore;
-use crate::tui::app::{DisplayMessage, Role, StreamChunk, display_messages_from_history};
-use crate::typeahead::{Command, Mention, TypeaheadActivation};
+use crate::budget::BudgetStatus;
+use crate::compare::{CompareModels, Comparison, ModelSwitch, PaneStatus};
+use crate::help::{self, GUI_KEYBINDINGS, HelpSection};
+use crate::i18n::{Msg, t};
+use crate::images;
+use crate::palette::{self, PaletteAction, PaletteState};
+use crate::pinned;
+use crate::presubmit::{self, FilePrompt};
+use crate::project_settings;
+use crate::request_size::{HeldRequest, RequestGuard};
+use crate::session::{
+    INTERRUPTED_PROMPT, RAW_OUTPUT_SAVED, RESUMING_RESPONSE, SharedSessionStore, now_ms,
+    relative_time,
+};
+use crate::snapshots;
+use crate::spec_refresh::SpecRefresh;
+use crate::summaries;
+use crate::template_fill::TemplateFill;
+use crate::tui::app::{
+    ComposerMode, DisplayMessage, Role, StreamChunk, attachment_chunk, image_chunk,
+    restored_messages, turn_footer,
+};
+use crate::typeahead::{
+    Command, Mention, TypeaheadActivation, available_commands, mention_attachments, mention_token,
+};
+use crate::write_approval::{self, PendingWrite};
 
+mod quick_prompt;
+mod rich_text;
 mod session_dialog;
+mod tray;
 mod typeahead;
-use session_dialog::ResumeDialogState;
-use session_dialog::{build_dialog_entries, move_selection, selected_session_id};
+mod window_state;
+use session_dialog::{DialogMode, ResumeDialogState};
+use session_dialog::{
+    build_bookmark_entries, build_dialog_entries, build_search_entries, move_selection,
+    selected_entry,
+};
 use typeahead::GuiTypeahead;
+use window_state::WindowState;
+
+/// Rem size at 100% zoom; every text size scales with it.
+const BASE_REM_SIZE: f32 = 16.;
+const ZOOM_STEP: f32 = 0.1;
+const MIN_ZOOM: f32 = 0.6;
+const MAX_ZOOM: f32 = 2.;
+/// Window width, at 100% zoom, below which the layout tightens for a
+/// narrow side-docked window.
+const NARROW_WIDTH: f32 = 560.;
+/// Tallest an image in the thread is shown.
+const IMAGE_MAX_HEIGHT: f32 = 420.;
 
 #[derive(Clone, Copy)]
 enum ThreadBlockKind {
@@ -37,7 +92,13 @@ enum ThreadBlockKind {
     ReasoningMarkdown,
     StreamingMarkdown,
     StreamingReasoning,
+    /// Text with tables or mermaid diagrams, rendered by [`rich_text`].
+    RichMarkdown,
     Tool,
+    HostedTool,
+    Footer,
+    /// An image's file or URL; `markdown_id` keys its Open button.
+    Image,
     Cursor,
     Hint,
     Spacer,
@@ -59,6 +120,10 @@ struct AgntGui {
     typeahead: GuiTypeahead,
     thread_list: ListState,
     messages: Vec<DisplayMessage>,
+    /// Title of the active session, shown in the window title bar.
+    session_title: Option<String>,
+    /// Window title last applied, so it is only set when it changes.
+    window_title: String,
     message_markdown_states: Vec<Vec<Option<Entity<TextViewState>>>>,
     stream_chunks: Vec<StreamChunk>,
     stream_markdown_states: Vec<Option<Entity<TextViewState>>>,
@@ -67,10 +132,62 @@ struct AgntGui {
     cursor_blink_on: bool,
     stick_to_bottom: bool,
     resume_dialog: Option<ResumeDialogState>,
+    /// The command palette, while open; the composer holds its query.
+    palette: Option<PaletteState>,
+    /// Composer text set aside while the palette is open.
+    palette_draft: String,
+    /// Sections of the help panel, while it is shown (F1 or the Help
+    /// button).
+    help: Option<Vec<HelpSection>>,
+    /// The request shown by `/debug:request`, until Escape.
+    request_view: Option<RequestInspection>,
+    /// What the composer holds.
+    composer: ComposerMode,
+    /// Tool output attached with `/last-output`, sent with the next message.
+    attached: Vec<Attachment>,
+    /// Answers from `/compare`, shown until Escape.
+    comparison: Option<Comparison>,
+    /// `None` where models can't be built on demand.
+    compare_models: Option<CompareModels>,
+    /// Refuse to fetch URLs that aren't on this machine.
+    offline: bool,
+    /// Refreshes the models.dev catalog in the background; `None` offline.
+    spec_refresh: Option<SpecRefresh>,
+    /// New models announced by the last refresh, until the next message.
+    models_notice: Option<String>,
+    /// Text size as a multiple of the default, kept in the user config.
+    zoom: f32,
+    budget_status: Option<BudgetStatus>,
+    /// Message held back until sending past the exceeded budget is confirmed.
+    budget_pending: Option<String>,
+    budget_overrun_confirmed: bool,
+    /// Size and cost limits above which a request is held back.
+    request_guard: RequestGuard,
+    /// Request held back for being over those limits until confirmed.
+    large_request: Option<HeldRequest>,
+    /// Prompt that is only a file path, held back until the user picks
+    /// between attaching the file and sending the path as typed.
+    file_prompt: Option<FilePrompt>,
+    /// Cleanup run on every prompt before it is sent.
+    presubmit: Vec<presubmit::Step>,
+    /// Attach the contents of files mentioned in a prompt.
+    attach_mentions: bool,
+    /// The protected write the turn waits on, until answered.
+    pending_write: Option<PendingWrite>,
     stream_task: Task<()>,
+    /// Stream the `/compare` answers; dropped with the comparison.
+    compare_tasks: Vec<Task<()>>,
     _blink_task: Task<()>,
+    _spec_refresh_task: Task<()>,
     _typeahead_updates_task: Task<()>,
+    /// Picks up the protected writes the agent asks about.
+    _write_approval_task: Task<()>,
     _input_subscription: Subscription,
+    /// Saves the composer text as the session's draft once typing pauses.
+    _draft_save_task: Task<()>,
+    _window_bounds_subscription: Subscription,
+    /// Saves the window's size and position once it stops moving.
+    _window_state_save_task: Task<()>,
     markdown_remeasure_scheduled: bool,
     _markdown_remeasure_task: Task<()>,
     _markdown_state_subscriptions: Vec<Subscription>,
@@ -78,22 +195,27 @@ struct AgntGui {
 
 impl AgntGui {
     fn new(
-        agent: Agent,
+        mut agent: Agent,
         session_store: SharedSessionStore,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
-        let messages = display_messages_from_history(&agent.messages());
+        let (messages, session_title) = restored_messages(&agent, &session_store.lock());
+        let mut write_approvals = write_approval::ask_through_ui(&mut agent);
         let message_markdown_states = Self::build_markdown_states(&messages, cx);
+        let config = agnt_app::UserConfig::load().unwrap_or_default();
         let typeahead = GuiTypeahead::new_for_current_project();
         let [mut command_typeahead_updates, mut mention_typeahead_updates] = typeahead.updates();
 
         let input = cx.new(|cx| {
             InputState::new(window, cx)
                 .auto_grow(1, 8)
-                .placeholder("Type a message...")
+                .placeholder(t(Msg::ComposerPlaceholder))
         });
         let input_subscription = cx.subscribe_in(&input, window, Self::on_input_event);
+        let window_bounds_subscription = cx.observe_window_bounds(window, |this, window, cx| {
+            this.schedule_window_state_save(window, cx);
+        });
         let blink_task = cx.spawn_in(window, async move |this, window| {
             loop {
                 window
@@ -143,6 +265,19 @@ impl AgntGui {
             }
         });
 
+        let write_approval_task = cx.spawn_in(window, async move |this, window| {
+            while let Some(pending) = write_approvals.recv().await {
+                let updated = this.update_in(window, |this, _, cx| {
+                    this.pending_write = Some(pending);
+                    this.maybe_auto_scroll_to_bottom();
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        });
+
         input.update(cx, |input, cx| input.focus(window, cx));
 
         let mut this = Self {
@@ -152,6 +287,8 @@ impl AgntGui {
             typeahead,
             thread_list: ListState::new(0, ListAlignment::Top, px(512.)).measure_all(),
             messages,
+            session_title,
+            window_title: String::new(),
             message_markdown_states,
             stream_chunks: Vec::new(),
             stream_markdown_states: Vec::new(),
@@ -160,17 +297,47 @@ impl AgntGui {
             cursor_blink_on: true,
             stick_to_bottom: true,
             resume_dialog: None,
+            palette: None,
+            palette_draft: String::new(),
+            help: None,
+            request_view: None,
+            composer: ComposerMode::Prompt,
+            comparison: None,
+            compare_models: None,
+            offline: false,
+            spec_refresh: None,
+            models_notice: None,
+            zoom: config.gui_zoom.unwrap_or(1.).clamp(MIN_ZOOM, MAX_ZOOM),
+            budget_status: None,
+            budget_pending: None,
+            budget_overrun_confirmed: false,
+            request_guard: RequestGuard::default(),
+            large_request: None,
+            file_prompt: None,
+            presubmit: presubmit::steps(&config),
+            attach_mentions: !config.skip_mention_attachments,
+            pending_write: None,
             stream_task: Task::ready(()),
+            compare_tasks: Vec::new(),
+            attached: Vec::new(),
             _blink_task: blink_task,
+            _spec_refresh_task: Task::ready(()),
             _typeahead_updates_task: typeahead_updates_task,
+            _write_approval_task: write_approval_task,
             _input_subscription: input_subscription,
+            _draft_save_task: Task::ready(()),
+            _window_bounds_subscription: window_bounds_subscription,
+            _window_state_save_task: Task::ready(()),
             markdown_remeasure_scheduled: false,
             _markdown_remeasure_task: Task::ready(()),
             _markdown_state_subscriptions: Vec::new(),
         };
 
+        this.refresh_budget_status();
         this.thread_list.reset(this.build_thread_blocks().len());
         this.rebuild_markdown_state_subscriptions(cx);
+        this.resume_pending_response(window, cx);
+        this.restore_draft(window, cx);
         this
     }
 
@@ -196,7 +363,10 @@ impl AgntGui {
                     let text = text.clone();
                     Some(cx.new(move |cx| TextViewState::markdown(&text, cx)))
                 }
-                StreamChunk::Tool(_) => None,
+                StreamChunk::Tool(_)
+                | StreamChunk::HostedTool(_)
+                | StreamChunk::Footer(_)
+                | StreamChunk::Image(_) => None,
             };
             states.push(state);
         }
@@ -258,10 +428,78 @@ impl AgntGui {
             }
             // Secondary enter (Shift+Enter on supported platforms) keeps newline for multiline input.
             InputEvent::PressEnter { secondary: true } => {}
+            InputEvent::Change { .. } => self.schedule_draft_save(cx),
             _ => {}
         }
     }
 
+    fn schedule_draft_save(&mut self, cx: &mut Context<Self>) {
+        self._draft_save_task = cx.spawn(async move |this, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(500))
+                .await;
+            let _ = this.update(cx, |this, cx| this.save_draft(cx));
+        });
+    }
+
+    fn schedule_window_state_save(&mut self, window: &Window, cx: &mut Context<Self>) {
+        let state = WindowState::of(window);
+        self._window_state_save_task = cx.spawn(async move |_, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(500))
+                .await;
+            if let Err(err) = state.save() {
+                eprintln!("could not save the window state: {err}");
+            }
+        });
+    }
+
+    /// Keep the composer text of the active session so it is back the next
+    /// time the session is opened. Text the composer holds for a prompt or
+    /// the palette is not a draft.
+    fn save_draft(&mut self, cx: &mut Context<Self>) {
+        if !self.composer.is_prompt() || self.palette.is_some() || self.resume_dialog.is_some() {
+            return;
+        }
+        let (text, cursor) = self.input_snapshot(cx);
+        if let Err(err) = self.session_store.lock().save_draft(&text, cursor) {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            self.stream_markdown_states.push(None);
+            cx.notify();
+        }
+    }
+
+    fn restore_draft(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let interrupted_result = self.session_store.lock().take_interrupted_prompt();
+        match interrupted_result {
+            Ok(Some(prompt)) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(INTERRUPTED_PROMPT.to_string()));
+                self.stream_markdown_states.push(None);
+                let cursor = prompt.len();
+                self.set_input_text_and_cursor(prompt, cursor, window, cx);
+                return;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+        let draft_result = self.session_store.lock().draft();
+        match draft_result {
+            Ok(Some((text, cursor))) => self.set_input_text_and_cursor(text, cursor, window, cx),
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+    }
+
     fn on_send_click(&mut self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
         let state = self.input.clone();
         self.submit_from_input(&state, window, cx);
@@ -277,8 +515,23 @@ impl AgntGui {
             return;
         }
 
-        if self.resume_dialog.is_some() {
-            self.confirm_resume_selection(window, cx);
+        if let Some(palette) = self.palette.as_ref() {
+            let action = palette.selected().map(|entry| entry.action.clone());
+            self.close_palette(window, cx);
+            if let Some(action) = action {
+                self.run_palette_action(action, window, cx);
+            }
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
+        if let Some(dialog) = self.resume_dialog.as_ref() {
+            match dialog.mode {
+                DialogMode::Browse => self.confirm_resume_selection(false, window, cx),
+                DialogMode::ConfirmDelete => self.delete_selected_session(cx),
+                DialogMode::Rename => self.rename_selected_session(window, cx),
+            }
             cx.stop_propagation();
             return;
         }
@@ -294,11 +547,39 @@ impl AgntGui {
     fn on_typeahead_escape_capture(
         &mut self,
         _: &InputEscape,
-        _window: &mut Window,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if self.resume_dialog.is_some() {
-            self.resume_dialog = None;
+        if self.request_view.take().is_some() || self.help.take().is_some() {
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
+        // Dropping the tasks stops answers still generating.
+        if self.comparison.take().is_some() {
+            self.compare_tasks.clear();
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
+        if self.palette.is_some() {
+            self.close_palette(window, cx);
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
+        if let Some(dialog) = self.resume_dialog.as_mut() {
+            match dialog.mode {
+                DialogMode::Browse => self.resume_dialog = None,
+                DialogMode::ConfirmDelete => dialog.mode = DialogMode::Browse,
+                DialogMode::Rename => {
+                    dialog.mode = DialogMode::Browse;
+                    self.set_input_text_and_cursor(String::new(), 0, window, cx);
+                }
+            }
             cx.stop_propagation();
             cx.notify();
             return;
@@ -308,6 +589,30 @@ impl AgntGui {
         if self.typeahead.dismiss_if_visible(&input, cursor_pos) {
             cx.stop_propagation();
             cx.notify();
+            return;
+        }
+
+        if let Some(prompt) = self.file_prompt.take() {
+            self.submit_text(prompt.text, window, cx);
+            cx.stop_propagation();
+            return;
+        }
+
+        if let Some(held) = self.large_request.take() {
+            // Back in the composer to be cut down.
+            let cursor = held.text.len();
+            self.set_input_text_and_cursor(held.text, cursor, window, cx);
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
+        let composer = std::mem::take(&mut self.composer);
+        let budget_pending = self.budget_pending.take().is_some();
+        if !composer.is_prompt() || budget_pending {
+            self.set_input_text_and_cursor(String::new(), 0, window, cx);
+            cx.stop_propagation();
+            cx.notify();
         }
     }
 
@@ -317,8 +622,17 @@ impl AgntGui {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if let Some(palette) = self.palette.as_mut() {
+            palette.move_selection(-1);
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
         if let Some(dialog) = self.resume_dialog.as_mut() {
-            move_selection(dialog, -1);
+            if dialog.mode == DialogMode::Browse {
+                move_selection(dialog, -1);
+            }
             cx.stop_propagation();
             cx.notify();
             return;
@@ -337,8 +651,17 @@ impl AgntGui {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if let Some(palette) = self.palette.as_mut() {
+            palette.move_selection(1);
+            cx.stop_propagation();
+            cx.notify();
+            return;
+        }
+
         if let Some(dialog) = self.resume_dialog.as_mut() {
-            move_selection(dialog, 1);
+            if dialog.mode == DialogMode::Browse {
+                move_selection(dialog, 1);
+            }
             cx.stop_propagation();
             cx.notify();
             return;
@@ -351,6 +674,208 @@ impl AgntGui {
         }
     }
 
+    /// F1 shows and hides the help panel.
+    fn on_help_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if event.keystroke.key != "f1" || event.keystroke.modifiers.modified() {
+            return;
+        }
+        self.toggle_help();
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn on_help_click(&mut self, _: &ClickEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_help();
+        cx.notify();
+    }
+
+    fn toggle_help(&mut self) {
+        if self.help.take().is_none() {
+            let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            self.help = Some(help::sections(GUI_KEYBINDINGS, &project_root));
+        }
+    }
+
+    /// Ctrl+K (Cmd+K on macOS) opens and closes the command palette.
+    fn on_palette_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if event.keystroke.key != "k" || !event.keystroke.modifiers.secondary() {
+            return;
+        }
+        if self.palette.is_some() {
+            self.close_palette(window, cx);
+        } else {
+            self.open_palette(window, cx);
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    /// Ctrl+= / Ctrl+- (Cmd on macOS) zoom in and out; Ctrl+0 goes back to
+    /// the default size.
+    fn on_zoom_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !event.keystroke.modifiers.secondary() {
+            return;
+        }
+        let zoom = match event.keystroke.key.as_str() {
+            "=" | "+" => self.zoom + ZOOM_STEP,
+            "-" => self.zoom - ZOOM_STEP,
+            "0" => 1.,
+            _ => return,
+        };
+        // Round away the float drift of repeated steps.
+        self.zoom = ((zoom * 10.).round() / 10.).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.save_zoom();
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn save_zoom(&mut self) {
+        let saved = agnt_app::UserConfig::load().and_then(|mut config| {
+            config.gui_zoom = (self.zoom != 1.).then_some(self.zoom);
+            config.save()
+        });
+        if let Err(err) = saved {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[config error: {err}]")));
+            self.stream_markdown_states.push(None);
+            self.maybe_auto_scroll_to_bottom();
+        }
+    }
+
+    /// Ctrl+O (Cmd+O on macOS) attaches the last tool output.
+    fn on_last_output_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if event.keystroke.key != "o" || !event.keystroke.modifiers.secondary() {
+            return;
+        }
+        self.attach_last_output();
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    /// Ctrl+B (Cmd+B on macOS) bookmarks the last turn.
+    fn on_bookmark_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if event.keystroke.key != "b" || !event.keystroke.modifiers.secondary() {
+            return;
+        }
+        self.bookmark_current_turn(None, cx);
+        cx.stop_propagation();
+    }
+
+    fn attach_last_output(&mut self) {
+        match Attachment::last_tool_output(&self.agent.messages()) {
+            Some(output) => {
+                if !self.attached.iter().any(|held| held.label == output.label) {
+                    self.attached.push(output);
+                }
+            }
+            None => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool("[no tool output yet]".to_string()));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+    }
+
+    /// `d`, `r` and `p` in the session list, `d` in the bookmark list and `b`
+    /// in search results; the composer keeps every other key.
+    fn on_resume_dialog_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        if dialog.mode != DialogMode::Browse || event.keystroke.modifiers.modified() {
+            return;
+        }
+        if dialog.lists_bookmarks && event.keystroke.key == "d" {
+            self.remove_selected_bookmark(cx);
+            cx.stop_propagation();
+            return;
+        }
+        if dialog.lists_search_hits && event.keystroke.key == "b" {
+            self.confirm_resume_selection(true, window, cx);
+            cx.stop_propagation();
+            return;
+        }
+        if !dialog.manages_sessions {
+            return;
+        }
+
+        match event.keystroke.key.as_str() {
+            "d" => dialog.mode = DialogMode::ConfirmDelete,
+            "p" => self.toggle_selected_session_pinned(),
+            "r" => {
+                dialog.mode = DialogMode::Rename;
+                let title = selected_entry(dialog)
+                    .and_then(|entry| entry.session_title.clone())
+                    .unwrap_or_default();
+                let cursor = title.len();
+                self.set_input_text_and_cursor(title, cursor, window, cx);
+            }
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn on_external_paths_drop(
+        &mut self,
+        paths: &ExternalPaths,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.resume_dialog.is_some() || self.palette.is_some() || paths.paths().is_empty() {
+            return;
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let mut insertion = paths
+            .paths()
+            .iter()
+            .map(|path| mention_token(path, &cwd))
+            .collect::<Vec<_>>()
+            .join(" ");
+        insertion.push(' ');
+
+        let (mut input, cursor_pos) = self.input_snapshot(cx);
+        let cursor_pos = cursor_pos.min(input.len());
+        if !input[..cursor_pos].is_empty() && !input[..cursor_pos].ends_with(char::is_whitespace) {
+            insertion.insert(0, ' ');
+        }
+        input.insert_str(cursor_pos, &insertion);
+        self.set_input_text_and_cursor(input, cursor_pos + insertion.len(), window, cx);
+        cx.notify();
+    }
+
     fn on_thread_scroll(
         &mut self,
         event: &ScrollWheelEvent,
@@ -389,16 +914,59 @@ impl AgntGui {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if self.generating || self.resume_dialog.is_some() {
+        if self.generating || self.resume_dialog.is_some() || self.palette.is_some() {
+            return;
+        }
+
+        if let Some(text) = self.budget_pending.take() {
+            self.budget_overrun_confirmed = true;
+            self.submit_text(text, window, cx);
+            return;
+        }
+        if let Some(held) = self.large_request.take() {
+            self.send(held.text, held.attachments, window, cx);
             return;
         }
 
-        let text = state.read(cx).value();
+        let text = state.read(cx).value().to_string();
+        if !self.composer.is_prompt() {
+            self.submit_composer_input(text, window, cx);
+            return;
+        }
         let text = text.trim().to_string();
         if text.is_empty() {
             return;
         }
 
+        let text = presubmit::run(&self.presubmit, text);
+        // Enter again on the unchanged prompt attaches the file.
+        if let Some(prompt) = self.file_prompt.take()
+            && prompt.text == text
+        {
+            self.submit_text(prompt.mention, window, cx);
+            return;
+        }
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        if let Some(prompt) = FilePrompt::detect(&text, &cwd) {
+            self.file_prompt = Some(prompt);
+            cx.notify();
+            return;
+        }
+        self.submit_text(text, window, cx);
+    }
+
+    fn submit_text(&mut self, text: String, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.budget_overrun_confirmed
+            && self
+                .budget_status
+                .as_ref()
+                .is_some_and(BudgetStatus::is_exceeded)
+        {
+            self.budget_pending = Some(text);
+            cx.notify();
+            return;
+        }
+
         let ensure_session_result = self.session_store.lock().ensure_active_session();
         if let Err(err) = ensure_session_result {
             self.stream_chunks
@@ -408,41 +976,161 @@ impl AgntGui {
             cx.notify();
             return;
         }
+        let artifacts_dir = self.session_store.lock().artifacts_dir();
+        self.agent.artifacts_dir(artifacts_dir);
 
-        state.update(cx, |state, cx| {
-            state.set_value("", window, cx);
-            state.focus(window, cx);
-        });
-
+        self.set_input_text_and_cursor(String::new(), 0, window, cx);
         self.start_stream(text, window, cx);
     }
 
     fn start_stream(&mut self, text: String, window: &mut Window, cx: &mut Context<Self>) {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let mut attachments = if self.attach_mentions {
+            mention_attachments(&text, &cwd)
+        } else {
+            Vec::new()
+        };
+        // Attached outputs stay attached until the request is sent.
+        attachments.extend(self.attached.iter().cloned());
         self.stream_chunks.clear();
         self.stream_markdown_states.clear();
         self.stream_block_height_floors.clear();
-        self.generating = true;
-        self.cursor_blink_on = true;
-        cx.notify();
-
-        let mut stream = self.agent.submit(&text);
-        self.stream_task = cx.spawn_in(window, async move |this, window| {
-            while let Some(event) = stream.next().await {
-                let finished = this
-                    .update_in(window, |this, window, cx| {
-                        this.handle_agent_event(event, window, cx);
-                        !this.generating
-                    })
-                    .unwrap_or(true);
-
-                if finished {
-                    return;
-                }
+        let stale_context = snapshots::stale_context(&mut self.session_store.lock());
+        match stale_context {
+            Ok(note) => attachments.extend(note),
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
             }
+        }
+        attachments.extend(summaries::resume_note(&mut self.session_store.lock()));
+        if let Some(size) = self.request_guard.check(&self.agent, &text, &attachments) {
+            self.stream_chunks.push(StreamChunk::Tool(size.summary()));
+            self.stream_markdown_states.push(None);
+            self.large_request = Some(HeldRequest { text, attachments });
+            self.maybe_auto_scroll_to_bottom();
+            cx.notify();
+            return;
+        }
+        self.send(text, attachments, window, cx);
+    }
 
-            _ = this.update_in(window, |this, _, cx| {
-                if this.generating {
-                    this.finalize_response(cx);
+    fn send(
+        &mut self,
+        text: String,
+        attachments: Vec<Attachment>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.attached.clear();
+        self.models_notice = None;
+        if let Err(err) = self.session_store.lock().begin_turn(&text) {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+            self.stream_markdown_states.push(None);
+        }
+        let stream = self.agent.submit_with_attachments(&text, attachments);
+        self.consume_stream(stream, window, cx);
+    }
+
+    /// Drop the tool results in the history to make the held request fit.
+    fn compact_for_large_request(&mut self, cx: &mut Context<Self>) {
+        let removed = self.agent.remove_tool_results(|_, _| true);
+        self.stream_chunks.push(StreamChunk::Tool(format!(
+            "[dropped {removed} tool result(s) from the history]"
+        )));
+        self.stream_markdown_states.push(None);
+        self.recheck_large_request(cx);
+    }
+
+    fn drop_large_request_attachments(&mut self, cx: &mut Context<Self>) {
+        let Some(held) = self.large_request.as_mut() else {
+            return;
+        };
+        let dropped = held.drop_attachments();
+        self.attached.clear();
+        self.stream_chunks.push(StreamChunk::Tool(format!(
+            "[dropped {dropped} attachment(s)]"
+        )));
+        self.stream_markdown_states.push(None);
+        self.recheck_large_request(cx);
+    }
+
+    /// Show the held request's size again after cutting it down. It stays
+    /// held either way, for Enter to send.
+    fn recheck_large_request(&mut self, cx: &mut Context<Self>) {
+        if let Some(held) = self.large_request.as_ref() {
+            let message = match self
+                .request_guard
+                .check(&self.agent, &held.text, &held.attachments)
+            {
+                Some(size) => size.summary(),
+                None => "[the request is within the limits now]".to_string(),
+            };
+            self.stream_chunks.push(StreamChunk::Tool(message));
+            self.stream_markdown_states.push(None);
+        }
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    /// Continue a turn whose background response was still generating when
+    /// an earlier run exited.
+    fn resume_pending_response(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let pending_result = self.session_store.lock().pending_response();
+        match pending_result {
+            Ok(Some((token, state))) => {
+                self.restore_active_session_state(Some(state), window, cx);
+                self.stream_chunks
+                    .push(StreamChunk::Tool(RESUMING_RESPONSE.to_string()));
+                self.stream_markdown_states.push(None);
+                let stream = self.agent.resume(token);
+                self.consume_stream(stream, window, cx);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+    }
+
+    fn consume_stream(
+        &mut self,
+        mut stream: AgentStream,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.generating = true;
+        self.cursor_blink_on = true;
+        cx.notify();
+
+        self.stream_task = cx.spawn_in(window, async move |this, window| {
+            while let Some(event) = stream.next().await {
+                let finished = this
+                    .update_in(window, |this, window, cx| {
+                        // A bug in showing one event shouldn't take the
+                        // window down with it.
+                        let handled = panic::catch_unwind(AssertUnwindSafe(|| {
+                            this.handle_agent_event(event, window, cx)
+                        }));
+                        if let Err(payload) = handled {
+                            this.recover_from_stream_panic(payload, window, cx);
+                        }
+                        !this.generating
+                    })
+                    .unwrap_or(true);
+
+                if finished {
+                    return;
+                }
+            }
+
+            _ = this.update_in(window, |this, _, cx| {
+                if this.generating {
+                    this.finalize_response(cx);
                     this.generating = false;
                     cx.notify();
                 }
@@ -450,6 +1138,41 @@ impl AgntGui {
         });
     }
 
+    /// Drop the stream whose event handling panicked, keeping what was shown
+    /// so far, and put the prompt back so it can be sent again.
+    fn recover_from_stream_panic(
+        &mut self,
+        payload: Box<dyn Any + Send>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let message = panic_message(&*payload);
+        agnt_llm::trace::log(
+            "gui",
+            format_args!("handling an agent event panicked: {message}"),
+        );
+        let interrupted_result = self.session_store.lock().take_interrupted_prompt();
+        let composer_empty = self.input_snapshot(cx).0.is_empty();
+        let prompt = interrupted_result.ok().flatten().filter(|_| composer_empty);
+        let restored = if prompt.is_some() {
+            "; the prompt is back in the composer to send again"
+        } else {
+            ""
+        };
+        self.stream_chunks.push(StreamChunk::Tool(format!(
+            "[the response stopped after an internal error: {message}{restored}]"
+        )));
+        self.stream_markdown_states.push(None);
+        self.finalize_response(cx);
+        self.generating = false;
+        if let Some(prompt) = prompt {
+            let cursor = prompt.len();
+            self.set_input_text_and_cursor(prompt, cursor, window, cx);
+        }
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
     fn handle_agent_event(
         &mut self,
         event: AgentEvent,
@@ -458,10 +1181,16 @@ impl AgntGui {
     ) {
         let mut markdown_states_changed = false;
         match event {
-            AgentEvent::UserMessage { content } => {
+            AgentEvent::UserMessage {
+                content,
+                attachments,
+            } => {
+                let mut chunks = vec![StreamChunk::Text(content)];
+                chunks.extend(attachments.iter().map(|summary| attachment_chunk(summary)));
                 self.messages.push(DisplayMessage {
                     role: Role::User,
-                    chunks: vec![StreamChunk::Text(content)],
+                    chunks,
+                    created_at_ms: Some(now_ms()),
                 });
                 let state = cx.new(|cx| TextViewState::markdown("", cx));
                 state.update(cx, |state, cx| {
@@ -471,7 +1200,9 @@ impl AgntGui {
                         state.set_text(content, cx);
                     }
                 });
-                self.message_markdown_states.push(vec![Some(state)]);
+                let mut states = vec![Some(state)];
+                states.resize(1 + attachments.len(), None);
+                self.message_markdown_states.push(states);
                 markdown_states_changed = true;
             }
             AgentEvent::TextDelta { delta } => {
@@ -487,6 +1218,17 @@ impl AgntGui {
                     markdown_states_changed = true;
                 }
             }
+            AgentEvent::ReasoningSectionStart { .. } => {
+                // Deltas of the new section go to a block of their own.
+                if matches!(self.stream_chunks.last(), Some(StreamChunk::Reasoning(s)) if !s.is_empty())
+                {
+                    self.stream_chunks
+                        .push(StreamChunk::Reasoning(String::new()));
+                    let state = cx.new(|cx| TextViewState::markdown("", cx));
+                    self.stream_markdown_states.push(Some(state));
+                    markdown_states_changed = true;
+                }
+            }
             AgentEvent::ReasoningDelta { delta } => {
                 if let Some(StreamChunk::Reasoning(s)) = self.stream_chunks.last_mut() {
                     s.push_str(&delta);
@@ -506,7 +1248,17 @@ impl AgntGui {
                     .push(StreamChunk::Tool(format!("[{}...]", display.title)));
                 self.stream_markdown_states.push(None);
             }
-            AgentEvent::ToolCallDone { display, .. } => {
+            AgentEvent::ToolCallDone {
+                id,
+                display,
+                record,
+                raw_output,
+            } => {
+                if let Err(err) = self.session_store.lock().record_tool_call(&record) {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[audit error: {err}]")));
+                    self.stream_markdown_states.push(None);
+                }
                 let diff = diff_from_display_body(display.body.as_ref());
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[{}]", display.title)));
@@ -518,21 +1270,92 @@ impl AgntGui {
                         diff,
                     );
                 }
+                if let Some(raw_output) = raw_output {
+                    let message = match self.session_store.lock().save_tool_output(&id, &raw_output)
+                    {
+                        Ok(()) => RAW_OUTPUT_SAVED.to_string(),
+                        Err(err) => format!("[session save error: {err}]"),
+                    };
+                    self.stream_chunks.push(StreamChunk::Tool(message));
+                    self.stream_markdown_states.push(None);
+                }
+            }
+            AgentEvent::HostedToolStart { display, .. } => {
+                self.stream_chunks
+                    .push(StreamChunk::HostedTool(format!("[{}...]", display.title)));
+                self.stream_markdown_states.push(None);
+            }
+            AgentEvent::HostedToolDone {
+                display, result, ..
+            } => {
+                self.stream_chunks.push(StreamChunk::HostedTool(format!(
+                    "[{} · {}]",
+                    display.title, result.title
+                )));
+                self.stream_markdown_states.push(None);
+            }
+            AgentEvent::Image { url } => {
+                self.stream_chunks.push(image_chunk(&url));
+                self.stream_markdown_states.push(None);
+            }
+            AgentEvent::Refusal { message, guidance } => {
+                if let Some(message) = message {
+                    let state = cx.new(|cx| TextViewState::markdown(&message, cx));
+                    self.stream_chunks.push(StreamChunk::Text(message));
+                    self.stream_markdown_states.push(Some(state));
+                    markdown_states_changed = true;
+                }
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[refused: {guidance}]")));
+                self.stream_markdown_states.push(None);
+            }
+            AgentEvent::Truncated { guidance, .. } => {
+                self.stream_chunks.push(StreamChunk::Tool(format!(
+                    "[cut off at the output token limit: {guidance}]"
+                )));
+                self.stream_markdown_states.push(None);
             }
-            AgentEvent::TurnComplete { usage } => {
+            AgentEvent::ResponsePending { token } => {
                 if let Err(err) = self
                     .session_store
                     .lock()
-                    .persist_turn_from_agent(&self.agent, &usage)
+                    .save_pending_response(&self.agent, &token)
+                {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+                    self.stream_markdown_states.push(None);
+                }
+            }
+            AgentEvent::TurnComplete { usage, stats } => {
+                if let Err(err) =
+                    self.session_store
+                        .lock()
+                        .persist_turn_from_agent(&self.agent, &usage, &stats)
                 {
                     self.stream_chunks
                         .push(StreamChunk::Tool(format!("[session save error: {err}]")));
                     self.stream_markdown_states.push(None);
                 }
+                if let Err(err) = self.session_store.lock().close_jj_change() {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[jj error: {err}]")));
+                    self.stream_markdown_states.push(None);
+                }
+                let footer = turn_footer(&usage, &stats);
+                self.session_store
+                    .lock()
+                    .notify(WebhookEvent::TurnComplete, &footer);
+                self.stream_chunks.push(StreamChunk::Footer(footer));
+                self.stream_markdown_states.push(None);
+                self.refresh_session_title();
+                self.refresh_budget_status();
                 self.finalize_response(cx);
                 self.generating = false;
             }
             AgentEvent::Error { error } => {
+                self.session_store
+                    .lock()
+                    .notify(WebhookEvent::Error, &error);
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[error: {error}]")));
                 self.stream_markdown_states.push(None);
@@ -548,7 +1371,50 @@ impl AgntGui {
         cx.notify();
     }
 
+    /// The first stored turn names the session.
+    fn refresh_session_title(&mut self) {
+        let title_result = self.session_store.lock().active_session_title();
+        match title_result {
+            Ok(title) => self.session_title = title,
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+    }
+
+    fn window_title_text(&self) -> String {
+        let session = match &self.session_title {
+            Some(title) => title.as_str(),
+            None if self.session_store.lock().active_session_id().is_some() => {
+                t(Msg::UntitledSession)
+            }
+            None => return "agnt".to_string(),
+        };
+        format!("{session} — agnt")
+    }
+
+    fn refresh_budget_status(&mut self) {
+        let status_result = self.session_store.lock().budget_status();
+        match status_result {
+            Ok(status) => self.budget_status = status,
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[budget error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+    }
+
     fn finalize_response(&mut self, cx: &mut Context<Self>) {
+        // A write still waiting when the turn stops is refused.
+        self.pending_write = None;
+        if let Err(err) = self.session_store.lock().end_turn() {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+            self.stream_markdown_states.push(None);
+        }
         let chunks = std::mem::take(&mut self.stream_chunks);
         let states = std::mem::take(&mut self.stream_markdown_states);
         self.stream_block_height_floors.clear();
@@ -556,6 +1422,7 @@ impl AgntGui {
             self.messages.push(DisplayMessage {
                 role: Role::Assistant,
                 chunks,
+                created_at_ms: Some(now_ms()),
             });
             self.message_markdown_states.push(states);
             self.rebuild_markdown_state_subscriptions(cx);
@@ -586,10 +1453,7 @@ impl AgntGui {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let mention_text = match mention {
-            Mention::File(path) => path.to_string_lossy().replace('\\', "/"),
-        };
-        let replacement = format!("{mention_text} ");
+        let replacement = format!("{} ", mention.token());
         let (mut input, _) = self.input_snapshot(cx);
         if token_start > token_end || token_end > input.len() {
             return;
@@ -603,51 +1467,155 @@ impl AgntGui {
         match command {
             Command::NewSession => self.start_new_session(window, cx),
             Command::ResumeSession => self.open_resume_dialog(cx),
+            Command::PinSession => self.toggle_active_session_pinned(cx),
+            Command::Retry => self.retry_last_turn(None, window, cx),
+            Command::RetryWith => self.start_retry_with(window, cx),
+            Command::RawOutput => self.show_raw_output(cx),
+            Command::LastOutput => {
+                self.attach_last_output();
+                cx.notify();
+            }
+            Command::Changes => self.show_session_changes(cx),
+            Command::Patch => self.export_patch(cx),
+            Command::Share => self.share_session(cx),
+            Command::History => self.start_history_search(window, cx),
+            Command::ProjectSettings => self.start_settings_edit(window, cx),
+            Command::DebugRequest => self.show_request(cx),
+            Command::DebugContext => self.show_context_breakdown(cx),
+            Command::ModelsRefresh => self.refresh_models(cx),
+            Command::AddContext => self.start_context_add(window, cx),
+            Command::Context => self.show_context(cx),
+            Command::Compare => self.start_compare(window, cx),
+            Command::Bookmark => self.start_bookmark_input(window, cx),
+            Command::Bookmarks => self.open_bookmarks(cx),
+            Command::Artifacts => self.show_artifacts(cx),
+            Command::Template(template) => self.start_template(template, window, cx),
         }
     }
 
-    fn start_new_session(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.generating {
-            self.finalize_response(cx);
-            self.generating = false;
+    /// Switch the composer to `mode`, emptied.
+    fn set_composer(&mut self, mode: ComposerMode, window: &mut Window, cx: &mut Context<Self>) {
+        self.composer = mode;
+        self.set_input_text_and_cursor(String::new(), 0, window, cx);
+        cx.notify();
+    }
+
+    /// Enter in a composer that doesn't hold a prompt.
+    fn submit_composer_input(&mut self, text: String, window: &mut Window, cx: &mut Context<Self>) {
+        let text = match self.composer {
+            ComposerMode::Settings => text,
+            _ => text.trim().to_string(),
+        };
+        match self.composer {
+            ComposerMode::Prompt => {}
+            ComposerMode::Template(_) => self.provide_template_value(text, window, cx),
+            ComposerMode::Settings => self.save_project_settings(&text, window, cx),
+            ComposerMode::Bookmark => {
+                self.set_composer(ComposerMode::Prompt, window, cx);
+                self.bookmark_current_turn(Some(&text), cx);
+            }
+            ComposerMode::RetryWith => self.retry_with_input(&text, window, cx),
+            // The rest need something typed.
+            _ if text.is_empty() => {}
+            ComposerMode::HistorySearch => self.search_history(text, window, cx),
+            ComposerMode::ContextAdd => self.pin_context_input(&text, window, cx),
+            ComposerMode::Compare => self.run_comparison(&text, window, cx),
         }
+    }
 
-        self.session_store.lock().clear_active_session();
-        self.restore_active_session_state(None, window, cx);
+    fn start_history_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.set_composer(ComposerMode::HistorySearch, window, cx);
+    }
 
-        self.maybe_auto_scroll_to_bottom();
+    fn start_context_add(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.set_composer(ComposerMode::ContextAdd, window, cx);
+    }
+
+    fn pin_context_input(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        match pinned::resolve(text, &cwd, self.offline) {
+            Ok(item) => {
+                self.set_composer(ComposerMode::Prompt, window, cx);
+                self.agent.pin_context(item);
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[context error: {err}]")));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
         cx.notify();
     }
 
-    fn open_resume_dialog(&mut self, cx: &mut Context<Self>) {
-        if self.generating {
-            self.finalize_response(cx);
-            self.generating = false;
+    /// Pinned items are always listed above the composer, each with its
+    /// own unpin button; this only says so when there are none.
+    fn show_context(&mut self, cx: &mut Context<Self>) {
+        if self.agent.pinned_context().is_empty() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[nothing pinned; /add pins a file, URL or note]".to_string(),
+            ));
+            self.stream_markdown_states.push(None);
+            self.maybe_auto_scroll_to_bottom();
         }
+        cx.notify();
+    }
 
-        let (active_session_id, sessions_result) = {
-            let store = self.session_store.lock();
-            (
-                store.active_session_id().map(str::to_owned),
-                store.list_sessions(100),
-            )
+    fn start_compare(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.compare_models.is_none() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[comparing models isn't available here]".to_string(),
+            ));
+            self.stream_markdown_states.push(None);
+            self.maybe_auto_scroll_to_bottom();
+            cx.notify();
+            return;
+        }
+        self.set_composer(ComposerMode::Compare, window, cx);
+    }
+
+    fn start_bookmark_input(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.set_composer(ComposerMode::Bookmark, window, cx);
+    }
+
+    fn bookmark_current_turn(&mut self, note: Option<&str>, cx: &mut Context<Self>) {
+        let bookmark_result = self.session_store.lock().bookmark_turn(0, note);
+        let message = match bookmark_result {
+            Ok(true) => "[turn bookmarked; /bookmarks lists bookmarks]".to_string(),
+            Ok(false) => "[nothing to bookmark yet]".to_string(),
+            Err(err) => format!("[session error: {err}]"),
         };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+        self.stream_markdown_states.push(None);
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
 
-        match sessions_result {
-            Ok(mut sessions) => {
-                if let Some(active_session_id) = active_session_id {
-                    sessions.retain(|session| session.id != active_session_id);
-                }
+    fn show_artifacts(&mut self, cx: &mut Context<Self>) {
+        let lines_result = self.session_store.lock().artifact_lines();
+        let lines = lines_result.unwrap_or_else(|err| vec![format!("[artifacts error: {err}]")]);
+        for line in lines {
+            self.stream_chunks.push(StreamChunk::Tool(line));
+            self.stream_markdown_states.push(None);
+        }
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
 
-                if sessions.is_empty() {
-                    self.stream_chunks.push(StreamChunk::Tool(
-                        "[no previous sessions to resume]".to_string(),
-                    ));
-                    self.stream_markdown_states.push(None);
-                } else {
-                    self.resume_dialog =
-                        Some(ResumeDialogState::new(build_dialog_entries(sessions)));
-                }
+    fn open_bookmarks(&mut self, cx: &mut Context<Self>) {
+        let bookmarks_result = self.session_store.lock().bookmarks(50);
+        match bookmarks_result {
+            Ok(bookmarks) if bookmarks.is_empty() => {
+                self.stream_chunks.push(StreamChunk::Tool(
+                    "[no bookmarks yet; /bookmark or Ctrl+B bookmarks the last turn]".to_string(),
+                ));
+                self.stream_markdown_states.push(None);
+            }
+            Ok(bookmarks) => {
+                self.resume_dialog = Some(ResumeDialogState::bookmarks(build_bookmark_entries(
+                    bookmarks,
+                )));
             }
             Err(err) => {
                 self.stream_chunks
@@ -660,17 +1628,117 @@ impl AgntGui {
         cx.notify();
     }
 
-    fn confirm_resume_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(dialog) = self.resume_dialog.take() else {
+    fn run_comparison(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(models) = self.compare_models.as_mut() else {
             return;
         };
-        let Some(session_id) = selected_session_id(&dialog).map(str::to_owned) else {
-            return;
+        let started = models
+            .parse(text)
+            .map_err(|err| err.to_string())
+            .and_then(|(models, prompt)| Comparison::start(&self.agent, models, prompt));
+        match started {
+            Ok(mut comparison) => {
+                self.set_composer(ComposerMode::Prompt, window, cx);
+                self.help = None;
+                self.request_view = None;
+                self.compare_tasks = comparison
+                    .take_streams()
+                    .into_iter()
+                    .map(|(pane, mut stream)| {
+                        cx.spawn(async move |this, cx| {
+                            while let Some(event) = stream.next().await {
+                                let updated = this.update(cx, |this, cx| {
+                                    if let Some(comparison) = this.comparison.as_mut() {
+                                        comparison.handle_event(pane, event);
+                                    }
+                                    cx.notify();
+                                });
+                                if updated.is_err() {
+                                    return;
+                                }
+                            }
+                            let _ = this.update(cx, |this, cx| {
+                                if let Some(comparison) = this.comparison.as_mut() {
+                                    comparison.stream_ended(pane);
+                                }
+                                cx.notify();
+                            });
+                        })
+                    })
+                    .collect();
+                self.comparison = Some(comparison);
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[compare error: {err}]")));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+        cx.notify();
+    }
+
+    fn unpin_context(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.agent.unpin_context(index);
+        cx.notify();
+    }
+
+    fn start_settings_edit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let settings_result = self.session_store.lock().project_settings();
+        match settings_result {
+            Ok(settings) => {
+                let text = project_settings::format(&settings);
+                let cursor_pos = text.len();
+                self.composer = ComposerMode::Settings;
+                self.set_input_text_and_cursor(text, cursor_pos, window, cx);
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+        cx.notify();
+    }
+
+    fn save_project_settings(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let message = match project_settings::parse(text) {
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => format!("[settings error: {err}]"),
+            Ok(settings) => {
+                self.set_composer(ComposerMode::Prompt, window, cx);
+                let save_result = self.session_store.lock().update_project_settings(&settings);
+                match save_result {
+                    Ok(()) => {
+                        "[project settings saved; they apply the next time agnt starts]".to_string()
+                    }
+                    Err(err) => format!("[session error: {err}]"),
+                }
+            }
         };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+        self.stream_markdown_states.push(None);
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
 
-        let activate_result = self.session_store.lock().activate_session(&session_id);
-        match activate_result {
-            Ok(restored_state) => self.restore_active_session_state(restored_state, window, cx),
+    fn search_history(&mut self, query: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.set_composer(ComposerMode::Prompt, window, cx);
+
+        let search_result = self.session_store.lock().search_turns(&query, 50);
+        match search_result {
+            Ok(hits) if hits.is_empty() => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[no matches for {query:?}]")));
+                self.stream_markdown_states.push(None);
+            }
+            Ok(hits) => {
+                self.resume_dialog = Some(ResumeDialogState::search_results(build_search_entries(
+                    hits,
+                )));
+            }
             Err(err) => {
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[session error: {err}]")));
@@ -682,26 +1750,637 @@ impl AgntGui {
         cx.notify();
     }
 
-    fn restore_active_session_state(
+    fn start_template(
         &mut self,
-        restored_state: Option<ConversationState>,
+        template: PromptTemplate,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.agent
-            .restore_conversation_state(restored_state.unwrap_or_else(|| ConversationState {
-                messages: Vec::new(),
-            }));
-        self.messages = display_messages_from_history(&self.agent.messages());
-        self.message_markdown_states = Self::build_markdown_states(&self.messages, cx);
-        self.stream_chunks.clear();
-        self.stream_markdown_states.clear();
-        self.stream_block_height_floors.clear();
-        self.cursor_blink_on = true;
-        self.stick_to_bottom = true;
-        self.resume_dialog = None;
-        self.thread_list.reset(self.build_thread_blocks().len());
-        self.rebuild_markdown_state_subscriptions(cx);
+        self.set_composer(
+            ComposerMode::Template(TemplateFill::new(template)),
+            window,
+            cx,
+        );
+        self.submit_template_if_complete(window, cx);
+    }
+
+    fn provide_template_value(
+        &mut self,
+        value: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let ComposerMode::Template(fill) = &mut self.composer else {
+            return;
+        };
+        fill.provide(value);
+        self.set_input_text_and_cursor(String::new(), 0, window, cx);
+        self.submit_template_if_complete(window, cx);
+        cx.notify();
+    }
+
+    fn submit_template_if_complete(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let complete = match &self.composer {
+            ComposerMode::Template(fill) => fill.pending_variable().is_none(),
+            _ => false,
+        };
+        if !complete {
+            return;
+        }
+        let ComposerMode::Template(fill) = std::mem::take(&mut self.composer) else {
+            return;
+        };
+
+        match fill.render() {
+            Ok(text) if !text.trim().is_empty() => {
+                self.submit_text(text.trim().to_string(), window, cx)
+            }
+            Ok(_) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[template error: {err}]")));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+    }
+
+    fn start_new_session(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.generating {
+            self.finalize_response(cx);
+            self.generating = false;
+        }
+
+        self.save_draft(cx);
+        summaries::summarize_active(&self.agent, &self.session_store);
+        self.session_store.lock().clear_active_session();
+        self.restore_active_session_state(None, window, cx);
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn start_retry_with(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.compare_models.is_none() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[switching models isn't available here]".to_string(),
+            ));
+            self.stream_markdown_states.push(None);
+            self.maybe_auto_scroll_to_bottom();
+            cx.notify();
+            return;
+        }
+        self.set_composer(ComposerMode::RetryWith, window, cx);
+    }
+
+    fn retry_with_input(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(models) = self.compare_models.as_mut() else {
+            return;
+        };
+        let current = self.session_store.lock().model_label().unwrap_or_default();
+        match models.switch(text, &current) {
+            Ok(switch) => {
+                self.set_composer(ComposerMode::Prompt, window, cx);
+                self.retry_last_turn(Some(switch), window, cx);
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[retry error: {err}]")));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+                cx.notify();
+            }
+        }
+    }
+
+    /// Generate the last response again, with `switch`'s model and effort
+    /// from then on when given.
+    fn retry_last_turn(
+        &mut self,
+        switch: Option<ModelSwitch>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.generating {
+            self.finalize_response(cx);
+            self.generating = false;
+        }
+
+        let retry_result = self
+            .session_store
+            .lock()
+            .prepare_retry(&self.agent.messages());
+        match retry_result {
+            Ok(Some(retry)) => {
+                self.restore_active_session_state(Some(retry.state), window, cx);
+                if let Some(switch) = switch {
+                    switch.apply(&mut self.agent, &mut self.session_store.lock());
+                }
+                self.submit_text(retry.user_text, window, cx);
+            }
+            Ok(None) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool("[nothing to retry]".to_string()));
+                self.stream_markdown_states.push(None);
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn show_raw_output(&mut self, cx: &mut Context<Self>) {
+        let output_result = self.session_store.lock().latest_tool_output();
+        match output_result {
+            Ok(Some(output)) => push_tool_diff_chunks(
+                &mut self.stream_chunks,
+                &mut self.stream_markdown_states,
+                &output,
+            ),
+            Ok(None) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool("[no truncated tool output]".to_string()));
+                self.stream_markdown_states.push(None);
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn show_session_changes(&mut self, cx: &mut Context<Self>) {
+        let changes_result = crate::changes::session_changes(&self.session_store.lock());
+        let mut notes = Vec::new();
+        match changes_result {
+            Ok(Some(changes)) => {
+                if changes.diff.is_empty() && changes.unknown.is_empty() {
+                    notes.push("[no net file changes in this session]".to_string());
+                }
+                push_tool_diff_chunks(
+                    &mut self.stream_chunks,
+                    &mut self.stream_markdown_states,
+                    &changes.diff,
+                );
+                notes.extend(changes.unknown.into_iter().map(|path| {
+                    format!("[{path} was changed outside agnt; see its diffs in the conversation]")
+                }));
+            }
+            Ok(None) => notes.push("[no file changes in this session]".to_string()),
+            Err(err) => notes.push(format!("[changes error: {err}]")),
+        }
+        for note in notes {
+            self.stream_chunks.push(StreamChunk::Tool(note));
+            self.stream_markdown_states.push(None);
+        }
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn show_request(&mut self, cx: &mut Context<Self>) {
+        match self.agent.inspect_request() {
+            Some(inspection) => {
+                self.help = None;
+                self.request_view = Some(inspection);
+            }
+            None => {
+                self.stream_chunks.push(StreamChunk::Tool(
+                    "[this provider's requests can't be shown]".to_string(),
+                ));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+        cx.notify();
+    }
+
+    fn start_spec_refresh(
+        &mut self,
+        mut refresh: SpecRefresh,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(mut updates) = refresh.take_updates() else {
+            return;
+        };
+        self.spec_refresh = Some(refresh);
+        self._spec_refresh_task = cx.spawn_in(window, async move |this, window| {
+            while let Some(update) = updates.recv().await {
+                let updated = this.update_in(window, |this, _, cx| {
+                    if let Some(notice) = update.notice() {
+                        this.models_notice = Some(notice);
+                    }
+                    if let (Ok(spec), Some(models)) = (update.spec, this.compare_models.as_mut()) {
+                        models.load_spec(spec);
+                    }
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn refresh_models(&mut self, cx: &mut Context<Self>) {
+        match &self.spec_refresh {
+            Some(refresh) => refresh.request(),
+            None => {
+                self.stream_chunks.push(StreamChunk::Tool(
+                    "[offline mode: the models.dev catalog is not fetched]".to_string(),
+                ));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+        cx.notify();
+    }
+
+    fn show_context_breakdown(&mut self, cx: &mut Context<Self>) {
+        let breakdown = self.request_guard.breakdown(&self.agent);
+        for line in breakdown.lines() {
+            self.stream_chunks.push(StreamChunk::Tool(line));
+            self.stream_markdown_states.push(None);
+        }
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn share_session(&mut self, cx: &mut Context<Self>) {
+        let export_result = crate::share::export_active_session(&self.session_store.lock());
+        let message = match export_result {
+            Ok(Some(page)) => page.summary(),
+            Ok(None) => "[nothing to share yet]".to_string(),
+            Err(err) => format!("[share error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+        self.stream_markdown_states.push(None);
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn export_patch(&mut self, cx: &mut Context<Self>) {
+        let export_result = crate::patch::export_active_session(&self.session_store.lock());
+        let message = match export_result {
+            Ok(Some(series)) => series.summary(),
+            Ok(None) => "[no file changes in this session]".to_string(),
+            Err(err) => format!("[patch export error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+        self.stream_markdown_states.push(None);
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn open_resume_dialog(&mut self, cx: &mut Context<Self>) {
+        if self.generating {
+            self.finalize_response(cx);
+            self.generating = false;
+        }
+
+        let (active_session_id, sessions_result) = {
+            let store = self.session_store.lock();
+            (
+                store.active_session_id().map(str::to_owned),
+                store.list_sessions(100),
+            )
+        };
+
+        match sessions_result {
+            Ok(mut sessions) => {
+                if let Some(active_session_id) = active_session_id {
+                    sessions.retain(|session| session.id != active_session_id);
+                }
+
+                if sessions.is_empty() {
+                    self.stream_chunks.push(StreamChunk::Tool(
+                        "[no previous sessions to resume]".to_string(),
+                    ));
+                    self.stream_markdown_states.push(None);
+                } else {
+                    self.resume_dialog =
+                        Some(ResumeDialogState::new(build_dialog_entries(sessions)));
+                }
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn delete_selected_session(&mut self, cx: &mut Context<Self>) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        dialog.mode = DialogMode::Browse;
+        let Some(session_id) = selected_entry(dialog).map(|entry| entry.session_id.clone()) else {
+            return;
+        };
+
+        let delete_result = self.session_store.lock().delete_session(&session_id);
+        match delete_result {
+            Ok(()) => {
+                dialog.remove_session(&session_id);
+                if dialog.entries.is_empty() {
+                    self.resume_dialog = None;
+                }
+            }
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+        cx.notify();
+    }
+
+    fn remove_selected_bookmark(&mut self, cx: &mut Context<Self>) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        let Some(turn_id) = selected_entry(dialog).and_then(|entry| entry.turn_id.clone()) else {
+            return;
+        };
+
+        let remove_result = self.session_store.lock().remove_bookmark(&turn_id);
+        match remove_result {
+            Ok(()) => {
+                dialog.remove_selected();
+                if dialog.entries.is_empty() {
+                    self.resume_dialog = None;
+                }
+            }
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+        cx.notify();
+    }
+
+    fn toggle_selected_session_pinned(&mut self) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        let Some(session_id) = selected_entry(dialog).map(|entry| entry.session_id.clone()) else {
+            return;
+        };
+
+        let pin_result = self.session_store.lock().toggle_session_pinned(&session_id);
+        match pin_result {
+            Ok(session) => dialog.update_session(&session),
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+    }
+
+    /// `/pin`: pin or unpin the open session.
+    fn toggle_active_session_pinned(&mut self, cx: &mut Context<Self>) {
+        let message = {
+            let mut store = self.session_store.lock();
+            match store.active_session_id().map(str::to_owned) {
+                None => "[no session to pin yet]".to_string(),
+                Some(session_id) => match store.toggle_session_pinned(&session_id) {
+                    Ok(session) if session.pinned => "[session pinned]".to_string(),
+                    Ok(_) => "[session unpinned]".to_string(),
+                    Err(err) => format!("[session error: {err}]"),
+                },
+            }
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+        self.stream_markdown_states.push(None);
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    fn rename_selected_session(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (title, _) = self.input_snapshot(cx);
+        self.set_input_text_and_cursor(String::new(), 0, window, cx);
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        dialog.mode = DialogMode::Browse;
+        let Some(session_id) = selected_entry(dialog).map(|entry| entry.session_id.clone()) else {
+            return;
+        };
+
+        let rename_result = self
+            .session_store
+            .lock()
+            .rename_session(&session_id, &title);
+        match rename_result {
+            Ok(session) => dialog.update_session(&session),
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Open the selected entry. A search hit opens its session as it is
+    /// unless `branch` asks to continue from the matching turn.
+    fn confirm_resume_selection(
+        &mut self,
+        branch: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(dialog) = self.resume_dialog.take() else {
+            return;
+        };
+        let Some(entry) = selected_entry(&dialog) else {
+            return;
+        };
+        let turn_id = entry
+            .turn_id
+            .as_deref()
+            .filter(|_| branch || !dialog.lists_search_hits);
+        self.resume_session(&entry.session_id, turn_id, window, cx);
+    }
+
+    /// Switch to `session_id`, checked out at `turn_id` if given.
+    fn resume_session(
+        &mut self,
+        session_id: &str,
+        turn_id: Option<&str>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.generating {
+            self.finalize_response(cx);
+            self.generating = false;
+        }
+        self.save_draft(cx);
+        let leaving = self.session_store.lock().active_session_id() != Some(session_id);
+        if leaving {
+            summaries::summarize_active(&self.agent, &self.session_store);
+        }
+
+        let activate_result = {
+            let mut store = self.session_store.lock();
+            match turn_id {
+                Some(turn_id) => store.activate_turn(session_id, turn_id),
+                None => store.activate_session(session_id),
+            }
+        };
+        match activate_result {
+            Ok(restored_state) => {
+                self.restore_active_session_state(restored_state, window, cx);
+                self.resume_pending_response(window, cx);
+                self.restore_draft(window, cx);
+                self.note_session_model();
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+            }
+        }
+
+        self.maybe_auto_scroll_to_bottom();
+        cx.notify();
+    }
+
+    /// The agent's model can only be picked at startup; say so when the
+    /// resumed session was using another one.
+    fn note_session_model(&mut self) {
+        let mismatch = self.session_store.lock().active_session_model_mismatch();
+        if let Ok(Some(model)) = mismatch {
+            let session_id = self
+                .session_store
+                .lock()
+                .active_session_id()
+                .map(str::to_owned);
+            self.stream_chunks.push(StreamChunk::Tool(format!(
+                "[this session was using {model}; restart with `agnt gui --resume={}` to continue on it]",
+                session_id.unwrap_or_default()
+            )));
+            self.stream_markdown_states.push(None);
+        }
+    }
+
+    fn open_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (active_session_id, sessions_result) = {
+            let store = self.session_store.lock();
+            (
+                store.active_session_id().map(str::to_owned),
+                store.list_sessions(20),
+            )
+        };
+        let mut sessions = match sessions_result {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                self.stream_markdown_states.push(None);
+                Vec::new()
+            }
+        };
+        sessions.retain(|session| Some(&session.id) != active_session_id.as_ref());
+
+        let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let config = agnt_app::UserConfig::load().unwrap_or_default();
+        self.resume_dialog = None;
+        self.palette = Some(PaletteState::new(palette::build_entries(
+            available_commands(&project_root),
+            sessions,
+            &config,
+        )));
+        // The palette's commands act on the composer; one waiting for
+        // something other than a prompt is given up, and only a prompt is
+        // kept as the draft.
+        self.palette_draft = if std::mem::take(&mut self.composer).is_prompt() {
+            self.input_snapshot(cx).0
+        } else {
+            String::new()
+        };
+        self.set_input_text_and_cursor(String::new(), 0, window, cx);
+    }
+
+    fn close_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.palette.take().is_none() {
+            return;
+        }
+        let draft = std::mem::take(&mut self.palette_draft);
+        let cursor = draft.len();
+        self.set_input_text_and_cursor(draft, cursor, window, cx);
+    }
+
+    fn run_palette_action(
+        &mut self,
+        action: PaletteAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match action {
+            PaletteAction::Command(command) => self.run_command(command, window, cx),
+            PaletteAction::Resume(session_id) => self.resume_session(&session_id, None, window, cx),
+            PaletteAction::Toggle(toggle) => {
+                let message =
+                    palette::apply_toggle(toggle, &mut self.agent, &mut self.session_store.lock());
+                self.stream_chunks.push(StreamChunk::Tool(message));
+                self.stream_markdown_states.push(None);
+                self.maybe_auto_scroll_to_bottom();
+            }
+        }
+    }
+
+    fn restore_active_session_state(
+        &mut self,
+        restored_state: Option<ConversationState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.agent
+            .restore_conversation_state(restored_state.unwrap_or_else(|| ConversationState {
+                messages: Vec::new(),
+            }));
+        (self.messages, self.session_title) =
+            restored_messages(&self.agent, &self.session_store.lock());
+        self.message_markdown_states = Self::build_markdown_states(&self.messages, cx);
+        self.stream_chunks.clear();
+        self.stream_markdown_states.clear();
+        self.stream_block_height_floors.clear();
+        self.cursor_blink_on = true;
+        self.stick_to_bottom = true;
+        self.resume_dialog = None;
+        self.composer = ComposerMode::Prompt;
+        self.comparison = None;
+        self.compare_tasks.clear();
+        self.attached.clear();
+        self.budget_pending = None;
+        self.large_request = None;
+        self.file_prompt = None;
+        self.thread_list.reset(self.build_thread_blocks().len());
+        self.rebuild_markdown_state_subscriptions(cx);
         self.set_input_text_and_cursor(String::new(), 0, window, cx);
     }
 
@@ -726,13 +2405,120 @@ impl AgntGui {
         });
     }
 
-    fn render_typeahead_panel(&mut self, cx: &mut Context<Self>) -> Option<AnyElement> {
-        if self.resume_dialog.is_some() {
-            return None;
-        }
+    fn render_typeahead_panel(&mut self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        if self.resume_dialog.is_some() || self.palette.is_some() {
+            return None;
+        }
+
+        let (input, cursor_pos) = self.input_snapshot(cx);
+        self.typeahead.render_panel(&input, cursor_pos, cx)
+    }
+
+    fn render_composer_hint_panel(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        if let Some(pending) = self.pending_write.as_ref() {
+            return Some(self.render_write_approval(pending, cx));
+        }
+        if self.large_request.is_some() {
+            return Some(self.render_large_request_hint(cx));
+        }
+        let hint = if let Some(status) = self.budget_status.as_ref()
+            && self.budget_pending.is_some()
+        {
+            format!("{}; {}", status.message(), t(Msg::BudgetConfirmHint))
+        } else if let Some(prompt) = self.file_prompt.as_ref() {
+            prompt.hint()
+        } else if let Some(hint) = self.composer.hint() {
+            hint
+        } else if !self.attached.is_empty() {
+            let summaries: Vec<String> = self.attached.iter().map(Attachment::summary).collect();
+            format!("{} attached", summaries.join(", "))
+        } else if let Some(status) = self.budget_status.as_ref() {
+            status.message()
+        } else {
+            self.models_notice.clone()?
+        };
+        Some(
+            div()
+                .w_full()
+                .px_2()
+                .text_xs()
+                .font_semibold()
+                .text_color(cx.theme().muted_foreground)
+                .child(hint)
+                .into_any_element(),
+        )
+    }
+
+    /// What Enter and Esc do with the held request, and buttons to cut it
+    /// down.
+    fn render_large_request_hint(&self, cx: &Context<Self>) -> AnyElement {
+        h_flex()
+            .w_full()
+            .px_2()
+            .gap_2()
+            .items_center()
+            .text_xs()
+            .font_semibold()
+            .text_color(cx.theme().muted_foreground)
+            .child(t(Msg::BudgetConfirmHint))
+            .child(
+                Button::new("drop-tool-results")
+                    .ghost()
+                    .xsmall()
+                    .label(t(Msg::DropToolResults))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.compact_for_large_request(cx);
+                    })),
+            )
+            .child(
+                Button::new("drop-attachments")
+                    .ghost()
+                    .xsmall()
+                    .label(t(Msg::DropAttachments))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.drop_large_request_attachments(cx);
+                    })),
+            )
+            .into_any_element()
+    }
+
+    /// The protected write the turn waits on, with buttons to answer it.
+    fn render_write_approval(&self, pending: &PendingWrite, cx: &Context<Self>) -> AnyElement {
+        h_flex()
+            .w_full()
+            .px_2()
+            .gap_2()
+            .items_center()
+            .text_xs()
+            .font_semibold()
+            .text_color(cx.theme().muted_foreground)
+            .child(pending.question())
+            .child(
+                Button::new("allow-write")
+                    .ghost()
+                    .xsmall()
+                    .label(t(Msg::AllowWrite))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.answer_pending_write(true, cx);
+                    })),
+            )
+            .child(
+                Button::new("refuse-write")
+                    .ghost()
+                    .xsmall()
+                    .label(t(Msg::RefuseWrite))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.answer_pending_write(false, cx);
+                    })),
+            )
+            .into_any_element()
+    }
 
-        let (input, cursor_pos) = self.input_snapshot(cx);
-        self.typeahead.render_panel(&input, cursor_pos, cx)
+    fn answer_pending_write(&mut self, allowed: bool, cx: &mut Context<Self>) {
+        if let Some(pending) = self.pending_write.take() {
+            pending.answer(allowed);
+            cx.notify();
+        }
     }
 
     fn render_resume_dialog_panel(&self, cx: &Context<Self>) -> Option<AnyElement> {
@@ -758,13 +2544,13 @@ impl AgntGui {
                     .text_xs()
                     .font_semibold()
                     .text_color(cx.theme().muted_foreground)
-                    .child("Resume session"),
+                    .child(dialog.title),
             )
             .child(
                 div()
                     .text_xs()
                     .text_color(cx.theme().muted_foreground)
-                    .child("Enter to resume, Esc to cancel"),
+                    .child(dialog.hint()),
             );
 
         for idx in start..end {
@@ -789,6 +2575,282 @@ impl AgntGui {
             }
             panel = panel.child(row);
         }
+        if let Some(summary) = selected_entry(dialog).and_then(|entry| entry.summary.clone()) {
+            panel = panel.child(
+                div()
+                    .w_full()
+                    .pt_1()
+                    .px_1()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(summary),
+            );
+        }
+
+        Some(panel.into_any_element())
+    }
+
+    fn render_palette_panel(&mut self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let (query, _) = self.input_snapshot(cx);
+        let palette = self.palette.as_mut()?;
+        palette.set_query(&query);
+        let max_items = 10usize;
+        let start = if palette.selected_index >= max_items {
+            palette.selected_index + 1 - max_items
+        } else {
+            0
+        };
+
+        let mut panel = v_flex()
+            .w_full()
+            .gap_1()
+            .p_2()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .bg(cx.theme().muted)
+            .child(
+                div()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t(Msg::PaletteTitle)),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t(Msg::PaletteHint)),
+            );
+
+        if palette.match_count() == 0 {
+            panel = panel.child(
+                div()
+                    .px_1()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t(Msg::NoMatches)),
+            );
+        }
+        for (idx, entry) in palette.matches().enumerate().skip(start).take(max_items) {
+            let marker = if idx == palette.selected_index {
+                "› "
+            } else {
+                "  "
+            };
+            let mut row = div()
+                .w_full()
+                .h_5()
+                .px_1()
+                .flex()
+                .items_center()
+                .text_sm()
+                .child(format!("{marker}{}", entry.title));
+            if idx == palette.selected_index {
+                row = row.text_color(cx.theme().cyan);
+            } else {
+                row = row.text_color(cx.theme().foreground);
+            }
+            panel = panel.child(row);
+        }
+
+        Some(panel.into_any_element())
+    }
+
+    fn render_help_panel(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let sections = self.help.as_ref()?;
+
+        let mut panel = v_flex()
+            .id("help-panel")
+            .w_full()
+            .max_h(px(360.))
+            .overflow_y_scroll()
+            .gap_1()
+            .p_2()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .bg(cx.theme().muted)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t(Msg::HelpCloseHint)),
+            );
+
+        for section in sections {
+            panel = panel.child(
+                div()
+                    .pt_1()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(section.title),
+            );
+            for (keys, action) in &section.rows {
+                panel = panel.child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .px_1()
+                        .text_sm()
+                        .child(
+                            div()
+                                .w(px(180.))
+                                .flex_none()
+                                .text_color(cx.theme().cyan)
+                                .child(keys.clone()),
+                        )
+                        .child(
+                            div()
+                                .text_color(cx.theme().foreground)
+                                .child(action.clone()),
+                        ),
+                );
+            }
+        }
+
+        Some(panel.into_any_element())
+    }
+
+    fn render_context_panel(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let items = self.agent.pinned_context();
+        if items.is_empty() {
+            return None;
+        }
+
+        let mut panel = v_flex()
+            .w_full()
+            .p_2()
+            .gap_1()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .child(
+                div()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t(Msg::PinnedContext)),
+            );
+        for (index, item) in items.iter().enumerate() {
+            panel = panel.child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(cx.theme().foreground)
+                            .child(item.label()),
+                    )
+                    .child(
+                        Button::new(("unpin-context", index))
+                            .ghost()
+                            .small()
+                            .label("×")
+                            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                                this.unpin_context(index, cx);
+                            })),
+                    ),
+            );
+        }
+
+        Some(panel.into_any_element())
+    }
+
+    fn render_request_panel(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let inspection = self.request_view.as_ref()?;
+        let title = if inspection.sent {
+            t(Msg::LastRequest)
+        } else {
+            t(Msg::NextRequest)
+        };
+
+        let mut panel = v_flex()
+            .id("request-panel")
+            .w_full()
+            .max_h(px(480.))
+            .overflow_y_scroll()
+            .p_2()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .bg(cx.theme().muted)
+            .child(
+                div()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(title),
+            );
+        for line in inspection.body.lines() {
+            panel = panel.child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().foreground)
+                    .child(line.to_string()),
+            );
+        }
+
+        Some(panel.into_any_element())
+    }
+
+    /// Side by side, or one above the other when `narrow`.
+    fn render_compare_panel(&self, narrow: bool, cx: &Context<Self>) -> Option<AnyElement> {
+        let comparison = self.comparison.as_ref()?;
+
+        let mut columns = if narrow { v_flex() } else { h_flex() }
+            .w_full()
+            .items_start()
+            .gap_2();
+        for pane in &comparison.panes {
+            let (status, color) = match &pane.status {
+                PaneStatus::Generating => {
+                    (t(Msg::Generating).to_string(), cx.theme().muted_foreground)
+                }
+                PaneStatus::Done(footer) => (footer.clone(), cx.theme().muted_foreground),
+                PaneStatus::Failed(err) => (format!("[error: {err}]"), cx.theme().danger),
+            };
+            let mut column = v_flex().flex_1().min_w_0().gap_1().child(
+                div()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().foreground)
+                    .child(pane.label.clone()),
+            );
+            for line in pane.text.lines() {
+                column = column.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().foreground)
+                        .child(line.to_string()),
+                );
+            }
+            columns = columns.child(column.child(div().text_xs().text_color(color).child(status)));
+        }
+
+        let panel = v_flex()
+            .id("compare-panel")
+            .w_full()
+            .max_h(px(480.))
+            .overflow_y_scroll()
+            .p_2()
+            .gap_2()
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .bg(cx.theme().muted)
+            .child(
+                div()
+                    .text_xs()
+                    .font_semibold()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!("{} · {}", t(Msg::CompareTitle), comparison.prompt)),
+            )
+            .child(columns);
 
         Some(panel.into_any_element())
     }
@@ -813,9 +2875,17 @@ impl AgntGui {
             };
             blocks.push(ThreadBlock {
                 kind: label_kind,
-                text: match msg.role {
-                    Role::User => "You".to_string(),
-                    Role::Assistant => "Assistant".to_string(),
+                text: {
+                    let label = match msg.role {
+                        Role::User => t(Msg::You),
+                        Role::Assistant => t(Msg::Assistant),
+                    };
+                    match msg.created_at_ms {
+                        Some(created_at_ms) => {
+                            format!("{label} · {}", relative_time(created_at_ms))
+                        }
+                        None => label.to_string(),
+                    }
                 },
                 markdown_state: None,
                 markdown_id: None,
@@ -845,7 +2915,7 @@ impl AgntGui {
 
             blocks.push(ThreadBlock {
                 kind: ThreadBlockKind::AssistantLabel,
-                text: "Assistant".to_string(),
+                text: t(Msg::Assistant).to_string(),
                 markdown_state: None,
                 markdown_id: None,
                 min_height: None,
@@ -873,7 +2943,7 @@ impl AgntGui {
         if blocks.is_empty() {
             blocks.push(ThreadBlock {
                 kind: ThreadBlockKind::Hint,
-                text: "Type a message and press Enter. Use Shift+Enter for newline.".to_string(),
+                text: t(Msg::EmptyThreadGui).to_string(),
                 markdown_state: None,
                 markdown_id: None,
                 min_height: None,
@@ -919,6 +2989,15 @@ impl AgntGui {
                     markdown_id: Some(format!("{id_prefix}-{i}")),
                     min_height: None,
                 }),
+                StreamChunk::Text(s) if rich_text::has_rich_segments(s) => {
+                    blocks.push(ThreadBlock {
+                        kind: ThreadBlockKind::RichMarkdown,
+                        text: s.clone(),
+                        markdown_state: None,
+                        markdown_id: Some(format!("{id_prefix}-{i}")),
+                        min_height: None,
+                    })
+                }
                 StreamChunk::Text(s) => blocks.push(ThreadBlock {
                     kind: if streaming {
                         ThreadBlockKind::StreamingMarkdown
@@ -939,6 +3018,27 @@ impl AgntGui {
                     markdown_id: None,
                     min_height: None,
                 }),
+                StreamChunk::HostedTool(s) => blocks.push(ThreadBlock {
+                    kind: ThreadBlockKind::HostedTool,
+                    text: s.clone(),
+                    markdown_state: None,
+                    markdown_id: None,
+                    min_height: None,
+                }),
+                StreamChunk::Footer(s) => blocks.push(ThreadBlock {
+                    kind: ThreadBlockKind::Footer,
+                    text: s.clone(),
+                    markdown_state: None,
+                    markdown_id: None,
+                    min_height: None,
+                }),
+                StreamChunk::Image(s) => blocks.push(ThreadBlock {
+                    kind: ThreadBlockKind::Image,
+                    text: s.clone(),
+                    markdown_state: None,
+                    markdown_id: Some(format!("{id_prefix}-{i}")),
+                    min_height: None,
+                }),
             }
         }
     }
@@ -993,7 +3093,7 @@ impl AgntGui {
             .retain(|id, _| active_ids.iter().any(|active| active == id));
     }
 
-    fn render_block(block: ThreadBlock, cx: &mut gpui::App) -> AnyElement {
+    fn render_block(block: ThreadBlock, offline: bool, cx: &mut gpui::App) -> AnyElement {
         match block.kind {
             ThreadBlockKind::Spacer => div().h_2().into_any_element(),
             ThreadBlockKind::UserLabel => div()
@@ -1056,12 +3156,72 @@ impl AgntGui {
                     container.child(view).into_any_element()
                 }
             }
+            ThreadBlockKind::RichMarkdown => {
+                let id = block
+                    .markdown_id
+                    .unwrap_or_else(|| "thread-md-fallback".to_string());
+                rich_text::render(&id, &block.text, offline, cx)
+            }
             ThreadBlockKind::Tool => div()
                 .w_full()
                 .text_sm()
                 .text_color(cx.theme().muted_foreground)
                 .child(block.text)
                 .into_any_element(),
+            ThreadBlockKind::HostedTool => div()
+                .w_full()
+                .text_sm()
+                .text_color(cx.theme().cyan)
+                .child(block.text)
+                .into_any_element(),
+            ThreadBlockKind::Footer => div()
+                .child(
+                    div()
+                        .px_2()
+                        .border_1()
+                        .border_color(cx.theme().border)
+                        .rounded(cx.theme().radius)
+                        .bg(cx.theme().muted)
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(block.text),
+                )
+                .into_any_element(),
+            ThreadBlockKind::Image => {
+                let source: ImageSource = if images::is_file(&block.text) {
+                    PathBuf::from(&block.text).into()
+                } else {
+                    block.text.clone().into()
+                };
+                let url = images::open_url(&block.text);
+                let id = block
+                    .markdown_id
+                    .unwrap_or_else(|| "thread-image".to_string());
+                v_flex()
+                    .w_full()
+                    .gap_1()
+                    .child(
+                        img(source)
+                            .max_w_full()
+                            .max_h(px(IMAGE_MAX_HEIGHT))
+                            .object_fit(ObjectFit::Contain),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(block.text)
+                            .child(
+                                Button::new(SharedString::from(format!("{id}-open")))
+                                    .ghost()
+                                    .xsmall()
+                                    .label(t(Msg::OpenImage))
+                                    .on_click(move |_, _, cx| cx.open_url(&url)),
+                            ),
+                    )
+                    .into_any_element()
+            }
             ThreadBlockKind::Cursor => div()
                 .w_full()
                 .text_sm()
@@ -1080,60 +3240,120 @@ impl AgntGui {
 }
 
 impl Render for AgntGui {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         self.maybe_auto_scroll_to_bottom();
 
+        window.set_rem_size(px(BASE_REM_SIZE * self.zoom));
+        let narrow = window.viewport_size().width < px(NARROW_WIDTH * self.zoom);
+
+        let window_title = self.window_title_text();
+        if window_title != self.window_title {
+            window.set_window_title(&window_title);
+            self.window_title = window_title;
+        }
+
         let mut blocks = self.build_thread_blocks();
         self.sync_thread_list_window(blocks.len());
         self.apply_stream_height_floors(&mut blocks);
         let thread_list = list(self.thread_list.clone(), {
             let blocks = blocks;
-            move |ix, _window, cx| Self::render_block(blocks[ix].clone(), cx)
+            let offline = self.offline;
+            move |ix, _window, cx| Self::render_block(blocks[ix].clone(), offline, cx)
         })
         .size_full();
         let resume_dialog_panel = self.render_resume_dialog_panel(cx);
+        let palette_panel = self.render_palette_panel(cx);
+        let help_panel = self.render_help_panel(cx);
+        let request_panel = self.render_request_panel(cx);
+        let compare_panel = self.render_compare_panel(narrow, cx);
+        let context_panel = self.render_context_panel(cx);
         let typeahead_panel = self.render_typeahead_panel(cx);
+        let composer_hint_panel = self.render_composer_hint_panel(cx);
         let send_label = if self.generating {
-            "Generating..."
+            t(Msg::Generating)
         } else {
-            "Send"
+            t(Msg::Send)
         };
+        let send_button = Button::new("send")
+            .primary()
+            .label(send_label)
+            .disabled(self.generating)
+            .on_click(cx.listener(Self::on_send_click));
         let input_row = div()
             .w_full()
             .capture_action(cx.listener(Self::on_typeahead_enter_capture))
             .capture_action(cx.listener(Self::on_typeahead_escape_capture))
             .capture_action(cx.listener(Self::on_typeahead_up_capture))
             .capture_action(cx.listener(Self::on_typeahead_down_capture))
+            .capture_key_down(cx.listener(Self::on_help_key_down))
+            .capture_key_down(cx.listener(Self::on_palette_key_down))
+            .capture_key_down(cx.listener(Self::on_last_output_key_down))
+            .capture_key_down(cx.listener(Self::on_bookmark_key_down))
+            .capture_key_down(cx.listener(Self::on_resume_dialog_key_down))
             .child(
                 h_flex()
                     .w_full()
                     .items_end()
                     .gap_2()
-                    .child(div().flex_1().child(Input::new(&self.input)))
-                    .child(
-                        Button::new("send")
-                            .primary()
-                            .large()
-                            .label(send_label)
-                            .disabled(self.generating)
-                            .on_click(cx.listener(Self::on_send_click)),
-                    ),
+                    .child(div().flex_1().min_w_0().child(Input::new(&self.input)))
+                    // F1 still opens help where there is no room for the
+                    // button.
+                    .when(!narrow, |row| {
+                        row.child(
+                            Button::new("help")
+                                .ghost()
+                                .large()
+                                .label(t(Msg::Help))
+                                .on_click(cx.listener(Self::on_help_click)),
+                        )
+                    })
+                    .child(if narrow {
+                        send_button
+                    } else {
+                        send_button.large()
+                    }),
             )
             .into_any_element();
         let mut input_section = v_flex().w_full().gap_2();
         if let Some(panel) = resume_dialog_panel {
             input_section = input_section.child(panel);
         }
+        if let Some(panel) = palette_panel {
+            input_section = input_section.child(panel);
+        }
+        if let Some(panel) = help_panel {
+            input_section = input_section.child(panel);
+        }
+        if let Some(panel) = request_panel {
+            input_section = input_section.child(panel);
+        }
+        if let Some(panel) = compare_panel {
+            input_section = input_section.child(panel);
+        }
+        if let Some(panel) = context_panel {
+            input_section = input_section.child(panel);
+        }
         if let Some(panel) = typeahead_panel {
             input_section = input_section.child(panel);
         }
+        if let Some(panel) = composer_hint_panel {
+            input_section = input_section.child(panel);
+        }
         let input_section = input_section.child(input_row).into_any_element();
 
         v_flex()
             .size_full()
-            .p_4()
-            .gap_3()
+            .map(|root| {
+                if narrow {
+                    root.p_2().gap_2()
+                } else {
+                    root.p_4().gap_3()
+                }
+            })
             .bg(cx.theme().background)
+            .capture_key_down(cx.listener(Self::on_zoom_key_down))
+            .drag_over::<ExternalPaths>(|style, _, _, cx| style.bg(cx.theme().muted))
+            .on_drop(cx.listener(Self::on_external_paths_drop))
             .text_color(cx.theme().foreground)
             .child(
                 div()
@@ -1149,7 +3369,7 @@ impl Render for AgntGui {
                             .id("thread-scroll-area")
                             .size_full()
                             .on_scroll_wheel(cx.listener(Self::on_thread_scroll))
-                            .p_3()
+                            .map(|area| if narrow { area.p_1() } else { area.p_3() })
                             .child(thread_list),
                     )
                     .vertical_scrollbar(&self.thread_list),
@@ -1158,10 +3378,32 @@ impl Render for AgntGui {
     }
 }
 
-pub fn run(agent: Agent, session_store: SharedSessionStore) {
+/// How the GUI starts, beyond the agent and its session.
+pub struct GuiOptions {
+    /// Open the session picker once the window is up.
+    pub pick_session: bool,
+    /// Global shortcut that opens the quick prompt window.
+    pub quick_prompt_hotkey: Option<String>,
+    /// Show a tray icon with the agent's status. Closing the window then
+    /// minimizes it instead of quitting.
+    pub tray_icon: bool,
+    /// Refuse to fetch URLs that aren't on this machine.
+    pub offline: bool,
+    /// Limits above which a request is held back until confirmed.
+    pub request_guard: RequestGuard,
+    /// Builds the models `/compare` runs.
+    pub compare_models: CompareModels,
+    /// Refreshes the models.dev catalog while the window is open.
+    pub spec_refresh: Option<SpecRefresh>,
+}
+
+pub fn run(agent: Agent, session_store: SharedSessionStore, options: GuiOptions) {
     let app = gpui::Application::new();
     let mut agent = Some(agent);
     let mut session_store = Some(session_store);
+    let mut compare_models = Some(options.compare_models);
+    let mut request_guard = Some(options.request_guard);
+    let mut spec_refresh = options.spec_refresh;
 
     app.run(move |cx: &mut GpuiApp| {
         gpui_component::init(cx);
@@ -1181,15 +3423,56 @@ pub fn run(agent: Agent, session_store: SharedSessionStore) {
         };
 
         if cx
-            .open_window(WindowOptions::default(), move |window, cx| {
-                window.on_window_should_close(cx, |_, cx| {
-                    cx.quit();
-                    true
-                });
+            .open_window(
+                WindowOptions {
+                    window_bounds: WindowState::load().map(WindowState::bounds),
+                    // Room for the composer and a few lines of the thread.
+                    window_min_size: Some(size(px(280.), px(240.))),
+                    ..WindowOptions::default()
+                },
+                move |window, cx| {
+                    let tray_icon = options.tray_icon;
+                    window.on_window_should_close(cx, move |window, cx| {
+                        if tray_icon {
+                            window.minimize_window();
+                            return false;
+                        }
+                        cx.quit();
+                        true
+                    });
 
-                let view = cx.new(|cx| AgntGui::new(agent, session_store, window, cx));
-                cx.new(|cx| Root::new(view, window, cx))
-            })
+                    let view = cx.new(|cx| {
+                        let mut gui = AgntGui::new(agent, session_store, window, cx);
+                        gui.offline = options.offline;
+                        gui.compare_models = compare_models.take();
+                        gui.request_guard = request_guard.take().unwrap_or_default();
+                        if let Some(refresh) = spec_refresh.take() {
+                            gui.start_spec_refresh(refresh, window, cx);
+                        }
+                        if options.pick_session {
+                            gui.open_resume_dialog(cx);
+                        }
+                        gui
+                    });
+                    if let Some(shortcut) = options.quick_prompt_hotkey.as_deref()
+                        && let Err(err) = quick_prompt::register(
+                            shortcut,
+                            view.downgrade(),
+                            window.window_handle(),
+                            cx,
+                        )
+                    {
+                        eprintln!("quick prompt unavailable: {err}");
+                    }
+                    if options.tray_icon
+                        && let Err(err) =
+                            tray::register(view.downgrade(), window.window_handle(), cx)
+                    {
+                        eprintln!("tray icon unavailable: {err}");
+                    }
+                    cx.new(|cx| Root::new(view, window, cx))
+                },
+            )
             .is_err()
         {
             cx.quit();
@@ -1197,12 +3480,21 @@ pub fn run(agent: Agent, session_store: SharedSessionStore) {
     });
 }
 
-pub fn launch(agent: Agent, session_store: SharedSessionStore) {
+pub fn launch(agent: Agent, session_store: SharedSessionStore, options: GuiOptions) {
     tokio::task::block_in_place(|| {
-        run(agent, session_store);
+        run(agent, session_store, options);
     });
 }
 
+/// The message a panic was raised with, when it has one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
 fn byte_offset_to_position(text: &str, byte_offset: usize) -> Position {
     let mut line = 0u32;
     let mut character = 0u32;
@@ -0,0 +1,252 @@
+//! Spotlight-style prompt window, summoned with a global shortcut while the
+//! GUI runs.
+
+use std::time::Duration;
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use gpui::{
+    AnyWindowHandle, App as GpuiApp, AppContext as _, Bounds, ClickEvent, Context, Entity,
+    InteractiveElement as _, IntoElement, ParentElement as _, Render, Styled as _, Subscription,
+    WeakEntity, Window, WindowBounds, WindowKind, WindowOptions, div, px, size,
+};
+use gpui_component::{
+    ActiveTheme as _, Root, Sizable as _,
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Escape as InputEscape, Input, InputEvent, InputState},
+    v_flex,
+};
+
+use super::AgntGui;
+use crate::tui::app::StreamChunk;
+
+/// How often shortcut presses are picked up.
+const HOTKEY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Open a quick prompt window bound to `main` whenever `shortcut` (e.g.
+/// `cmd+shift+space`) is pressed anywhere on the desktop.
+pub(super) fn register(
+    shortcut: &str,
+    main: WeakEntity<AgntGui>,
+    main_window: AnyWindowHandle,
+    cx: &mut GpuiApp,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hotkey: HotKey = shortcut
+        .parse()
+        .map_err(|err| format!("invalid quick prompt shortcut {shortcut:?}: {err}"))?;
+    let manager = GlobalHotKeyManager::new()?;
+    manager.register(hotkey)?;
+
+    cx.spawn(async move |cx| {
+        // Dropping the manager unregisters the shortcut.
+        let _manager = manager;
+        let events = GlobalHotKeyEvent::receiver();
+        loop {
+            cx.background_executor().timer(HOTKEY_POLL_INTERVAL).await;
+            let mut pressed = false;
+            while let Ok(event) = events.try_recv() {
+                pressed |= event.id == hotkey.id() && event.state == HotKeyState::Pressed;
+            }
+            if pressed && cx.update(|cx| open(main.clone(), main_window, cx)).is_err() {
+                break;
+            }
+        }
+    })
+    .detach();
+    Ok(())
+}
+
+fn open(main: WeakEntity<AgntGui>, main_window: AnyWindowHandle, cx: &mut GpuiApp) {
+    let options = WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(Bounds::centered(
+            None,
+            size(px(560.), px(320.)),
+            cx,
+        ))),
+        titlebar: None,
+        kind: WindowKind::PopUp,
+        ..WindowOptions::default()
+    };
+    let _ = cx.open_window(options, move |window, cx| {
+        let view = cx.new(|cx| QuickPrompt::new(main, main_window, window, cx));
+        cx.new(|cx| Root::new(view, window, cx))
+    });
+}
+
+struct QuickPrompt {
+    main: WeakEntity<AgntGui>,
+    main_window: AnyWindowHandle,
+    input: Entity<InputState>,
+    /// Whether a prompt was sent, so the reply is shown.
+    sent: bool,
+    _input_subscription: Subscription,
+    _main_subscription: Option<Subscription>,
+}
+
+impl QuickPrompt {
+    fn new(
+        main: WeakEntity<AgntGui>,
+        main_window: AnyWindowHandle,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .auto_grow(1, 4)
+                .placeholder("Ask agnt...")
+        });
+        let input_subscription = cx.subscribe_in(&input, window, Self::on_input_event);
+        // Re-render as the reply streams in.
+        let main_subscription = main
+            .upgrade()
+            .map(|main| cx.observe(&main, |_, _, cx| cx.notify()));
+        input.update(cx, |input, cx| input.focus(window, cx));
+
+        Self {
+            main,
+            main_window,
+            input,
+            sent: false,
+            _input_subscription: input_subscription,
+            _main_subscription: main_subscription,
+        }
+    }
+
+    fn on_input_event(
+        &mut self,
+        state: &Entity<InputState>,
+        event: &InputEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !matches!(event, InputEvent::PressEnter { secondary: false }) {
+            return;
+        }
+        let text = state.read(cx).value().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+
+        let main = self.main.clone();
+        let submitted = self.main_window.update(cx, |_, main_window, cx| {
+            main.update(cx, |gui, cx| gui.submit_quick_prompt(text, main_window, cx))
+        });
+        if matches!(submitted, Ok(Ok(true))) {
+            self.sent = true;
+            state.update(cx, |input, cx| input.set_value("", window, cx));
+        }
+        cx.notify();
+    }
+
+    fn on_escape(&mut self, _: &InputEscape, window: &mut Window, _: &mut Context<Self>) {
+        window.remove_window();
+    }
+
+    /// Bring up the full window with the conversation and close this one.
+    fn on_expand_click(&mut self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let _ = self
+            .main_window
+            .update(cx, |_, main_window, _| main_window.activate_window());
+        window.remove_window();
+    }
+}
+
+impl Render for QuickPrompt {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let (reply, generating) = self
+            .main
+            .upgrade()
+            .map(|main| {
+                let gui = main.read(cx);
+                (gui.quick_reply(), gui.generating)
+            })
+            .unwrap_or_default();
+
+        let mut content = v_flex()
+            .size_full()
+            .p_3()
+            .gap_2()
+            .bg(cx.theme().background)
+            .text_color(cx.theme().foreground)
+            .capture_action(cx.listener(Self::on_escape))
+            .child(Input::new(&self.input));
+        if self.sent {
+            let reply = if reply.is_empty() && generating {
+                "Thinking...".to_string()
+            } else {
+                reply
+            };
+            content = content.child(
+                div()
+                    .h_0()
+                    .flex_1()
+                    .w_full()
+                    .overflow_hidden()
+                    .text_sm()
+                    .child(reply),
+            );
+        }
+        content.child(
+            h_flex().w_full().justify_end().child(
+                Button::new("expand")
+                    .ghost()
+                    .small()
+                    .label("Open in agnt")
+                    .on_click(cx.listener(Self::on_expand_click)),
+            ),
+        )
+    }
+}
+
+impl AgntGui {
+    /// Send a prompt from the quick prompt window. Without an open session
+    /// it goes to the project's most recent one. Returns whether it was
+    /// sent; the agent may still be busy with an earlier one.
+    fn submit_quick_prompt(
+        &mut self,
+        text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.generating || self.resume_dialog.is_some() {
+            return false;
+        }
+
+        let has_session = self.session_store.lock().active_session_id().is_some();
+        if !has_session && self.messages.is_empty() {
+            let resume_result = self.session_store.lock().resume_most_recent_session();
+            match resume_result {
+                Ok(restored_state) => self.restore_active_session_state(restored_state, window, cx),
+                Err(err) => {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                    self.stream_markdown_states.push(None);
+                }
+            }
+        }
+
+        self.submit_text(text, window, cx);
+        true
+    }
+
+    /// Text of the reply being generated, or of the last one.
+    fn quick_reply(&self) -> String {
+        let chunks = if self.generating || !self.stream_chunks.is_empty() {
+            &self.stream_chunks
+        } else {
+            match self.messages.last() {
+                Some(message) => &message.chunks,
+                None => return String::new(),
+            }
+        };
+        chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
@@ -0,0 +1,358 @@
+//! Assistant text with tables and mermaid diagrams in it. The markdown view
+//! shows both as raw text, so such text is split into segments and those
+//! two kinds are rendered here; the rest still goes through the markdown
+//! view. Segments are recomputed as streamed text grows, so a table fills
+//! in row by row.
+
+use base64::Engine as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{AnyElement, App, ClipboardItem, IntoElement, ParentElement, SharedString, Styled, div};
+use gpui_component::{
+    ActiveTheme as _, Sizable as _, StyledExt as _,
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    text::TextView,
+    v_flex,
+};
+
+use crate::i18n::{Msg, t};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Table {
+    pub header: Vec<String>,
+    pub alignments: Vec<Alignment>,
+    /// Body rows, padded or cut to the header's width.
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Segment<'a> {
+    Markdown(&'a str),
+    Table(Table),
+    /// The source inside a ```` ```mermaid ```` fence, which may still be
+    /// streaming in.
+    Mermaid(&'a str),
+}
+
+/// Whether `text` has anything for [`render`] beyond plain markdown.
+pub(super) fn has_rich_segments(text: &str) -> bool {
+    (text.contains('|') || text.contains("mermaid"))
+        && segments(text)
+            .iter()
+            .any(|segment| !matches!(segment, Segment::Markdown(_)))
+}
+
+/// `text` split into markdown, tables (GitHub style, with a delimiter row
+/// under the header) and mermaid fences. Other fenced code is left in the
+/// markdown, tables inside it included.
+pub(super) fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((
+            offset,
+            offset + line.len(),
+            line.trim_end_matches(['\n', '\r']),
+        ));
+        offset += line.len();
+    }
+
+    let mut segments = Vec::new();
+    let mut markdown_start = 0;
+    let mut i = 0;
+    while i < lines.len() {
+        let (start, end, line) = lines[i];
+        if let Some((fence, info)) = fence_open(line) {
+            let close = (i + 1..lines.len()).find(|&j| fence_closes(lines[j].2, fence));
+            let body_end = close.map_or(text.len(), |j| lines[j].0);
+            let next = close.map_or(text.len(), |j| lines[j].1);
+            if info == "mermaid" {
+                flush(&mut segments, &text[markdown_start..start]);
+                segments.push(Segment::Mermaid(text[end..body_end].trim_end()));
+                markdown_start = next;
+            }
+            i = close.map_or(lines.len(), |j| j + 1);
+            continue;
+        }
+
+        let table = lines
+            .get(i + 1)
+            .filter(|_| is_row(line))
+            .and_then(|(_, _, delimiter)| alignments(delimiter))
+            .filter(|alignments| alignments.len() == cells(line).len());
+        let Some(alignments) = table else {
+            i += 1;
+            continue;
+        };
+        flush(&mut segments, &text[markdown_start..start]);
+        let width = alignments.len();
+        let mut rows = Vec::new();
+        let mut j = i + 2;
+        while let Some(&(_, _, row)) = lines.get(j).filter(|(_, _, row)| is_row(row)) {
+            let mut row = cells(row);
+            row.resize(width, String::new());
+            rows.push(row);
+            j += 1;
+        }
+        segments.push(Segment::Table(Table {
+            header: cells(line),
+            alignments,
+            rows,
+        }));
+        markdown_start = lines[j - 1].1;
+        i = j;
+    }
+    flush(&mut segments, &text[markdown_start..]);
+    segments
+}
+
+fn flush<'a>(segments: &mut Vec<Segment<'a>>, markdown: &'a str) {
+    if !markdown.trim().is_empty() {
+        segments.push(Segment::Markdown(markdown));
+    }
+}
+
+/// The fence characters and info string of a line opening a code fence.
+fn fence_open(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let fence_len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    (fence_len >= 3).then(|| (&trimmed[..fence_len], trimmed[fence_len..].trim()))
+}
+
+fn fence_closes(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let marker = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker)
+}
+
+fn is_row(line: &str) -> bool {
+    !line.starts_with("    ") && !line.trim().is_empty() && line.contains('|')
+}
+
+/// The alignments of a delimiter row such as `| :-- | :-: | --: |`.
+fn alignments(line: &str) -> Option<Vec<Alignment>> {
+    if !is_row(line) && !line.trim().starts_with('-') {
+        return None;
+    }
+    cells(line)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Alignment::Center,
+                (false, true) => Alignment::Right,
+                _ => Alignment::Left,
+            })
+        })
+        .collect()
+}
+
+/// The cells of a table row; a `|` that is escaped or inside inline code
+/// does not split them.
+fn cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// A cell's text without the inline markers a plain label would show.
+fn plain(cell: &str) -> String {
+    cell.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// `text` as a column of segments, with ids under `id`.
+pub(super) fn render(id: &str, text: &str, offline: bool, cx: &App) -> AnyElement {
+    v_flex()
+        .w_full()
+        .gap_2()
+        .children(segments(text).into_iter().enumerate().map(|(ix, segment)| {
+            match segment {
+                Segment::Markdown(markdown) => div()
+                    .w_full()
+                    .child(
+                        TextView::markdown(format!("{id}-{ix}"), markdown.to_string())
+                            .selectable(true),
+                    )
+                    .into_any_element(),
+                Segment::Table(table) => render_table(&table, cx),
+                Segment::Mermaid(source) => {
+                    render_mermaid(&format!("{id}-{ix}"), source, offline, cx)
+                }
+            }
+        }))
+        .into_any_element()
+}
+
+fn render_table(table: &Table, cx: &App) -> AnyElement {
+    let row = |cells: &[String], header: bool, striped: bool| {
+        h_flex()
+            .w_full()
+            .when(header, |this| this.font_semibold().bg(cx.theme().muted))
+            .when(striped, |this| this.bg(cx.theme().muted.opacity(0.4)))
+            .when(!header, |this| {
+                this.border_t_1().border_color(cx.theme().border)
+            })
+            .children(
+                cells
+                    .iter()
+                    .zip(&table.alignments)
+                    .map(|(cell, alignment)| {
+                        let cell = h_flex().flex_1().min_w_0().px_2().py_1().child(plain(cell));
+                        match alignment {
+                            Alignment::Left => cell,
+                            Alignment::Center => cell.justify_center(),
+                            Alignment::Right => cell.justify_end(),
+                        }
+                    }),
+            )
+    };
+
+    v_flex()
+        .w_full()
+        .text_sm()
+        .border_1()
+        .border_color(cx.theme().border)
+        .rounded(cx.theme().radius)
+        .overflow_hidden()
+        .child(row(&table.header, true, false))
+        .children(
+            table
+                .rows
+                .iter()
+                .enumerate()
+                .map(|(ix, cells)| row(cells, false, ix % 2 == 1)),
+        )
+        .into_any_element()
+}
+
+/// A mermaid diagram's source, with buttons to copy it and, unless
+/// offline, to open it rendered in the Mermaid Live Editor.
+fn render_mermaid(id: &str, source: &str, offline: bool, cx: &App) -> AnyElement {
+    let copy = {
+        let source = source.to_string();
+        Button::new(SharedString::from(format!("{id}-copy")))
+            .ghost()
+            .xsmall()
+            .label(t(Msg::Copy))
+            .on_click(move |_, _, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(source.clone()))
+            })
+    };
+    let open = (!offline).then(|| {
+        let url = mermaid_live_url(source);
+        Button::new(SharedString::from(format!("{id}-open")))
+            .ghost()
+            .xsmall()
+            .label(t(Msg::OpenDiagram))
+            .on_click(move |_, _, cx| cx.open_url(&url))
+    });
+
+    v_flex()
+        .w_full()
+        .border_1()
+        .border_color(cx.theme().border)
+        .rounded(cx.theme().radius)
+        .overflow_hidden()
+        .child(
+            h_flex()
+                .w_full()
+                .justify_between()
+                .px_2()
+                .py_1()
+                .bg(cx.theme().muted)
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(t(Msg::MermaidDiagram))
+                .child(h_flex().gap_1().child(copy).children(open)),
+        )
+        .child(
+            div()
+                .w_full()
+                .p_2()
+                .text_sm()
+                .font_family(cx.theme().mono_font_family.clone())
+                .child(source.to_string()),
+        )
+        .into_any_element()
+}
+
+/// The Mermaid Live Editor's view of `source`, which it reads from the
+/// URL fragment without sending it anywhere.
+fn mermaid_live_url(source: &str) -> String {
+    let state = serde_json::json!({
+        "code": source,
+        "mermaid": "{\"theme\": \"default\"}",
+    });
+    let encoded = base64::engine::general_purpose::STANDARD.encode(state.to_string());
+    format!("https://mermaid.live/view#base64:{encoded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_tables_and_mermaid_from_markdown() {
+        let text = "Two options:\n\n| Option | Cost |\n| :--- | ---: |\n| `a|b` | **1** |\n| c |\n\nAnd the flow:\n```mermaid\ngraph TD\n  A --> B\n```\nDone.";
+        let segments = segments(text);
+        assert_eq!(segments.len(), 5);
+        assert_eq!(segments[0], Segment::Markdown("Two options:\n\n"));
+        let Segment::Table(table) = &segments[1] else {
+            panic!("expected a table, got {:?}", segments[1]);
+        };
+        assert_eq!(table.header, ["Option", "Cost"]);
+        assert_eq!(table.alignments, [Alignment::Left, Alignment::Right]);
+        assert_eq!(
+            table.rows,
+            [vec!["`a|b`", "**1**"], vec!["c", ""]]
+                .map(|row| row.into_iter().map(String::from).collect::<Vec<_>>())
+        );
+        assert_eq!(segments[3], Segment::Mermaid("graph TD\n  A --> B"));
+        assert_eq!(segments[4], Segment::Markdown("Done."));
+    }
+
+    #[test]
+    fn leaves_tables_in_code_and_unfinished_ones_alone() {
+        assert!(!has_rich_segments("```\n| a | b |\n| - | - |\n```\n"));
+        assert!(!has_rich_segments("| a | b |\n| -"));
+        assert!(has_rich_segments("```mermaid\ngraph TD\n"));
+    }
+}
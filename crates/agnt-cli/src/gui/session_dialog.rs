@@ -1,23 +1,131 @@
-use agnt_db::Session;
+use agnt_db::{Session, TurnBookmark, TurnSearchHit};
 
-use crate::session::session_label;
+use crate::i18n::{Msg, t};
+use crate::session::{bookmark_label, search_hit_label, session_label, summary_preview};
 
 #[derive(Clone)]
 pub struct ResumeDialogEntry {
     pub session_id: String,
+    /// Turn to check out when resuming; `None` keeps the session's current turn.
+    pub turn_id: Option<String>,
     pub label: String,
+    pub session_title: Option<String>,
+    /// Stored summary of the session, shown while the entry is selected.
+    pub summary: Option<String>,
+    pub pinned: bool,
+}
+
+/// What keys in the dialog do.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DialogMode {
+    Browse,
+    /// Waiting for Enter to delete the selected session.
+    ConfirmDelete,
+    /// The composer holds a new title for the selected session.
+    Rename,
 }
 
 pub struct ResumeDialogState {
+    pub title: &'static str,
     pub entries: Vec<ResumeDialogEntry>,
     pub selected_index: usize,
+    pub mode: DialogMode,
+    /// Whether entries can be deleted, renamed and pinned. Only the session list
+    /// allows it; search results point at turns.
+    pub manages_sessions: bool,
+    /// Whether the entries are bookmarks, which `d` removes.
+    pub lists_bookmarks: bool,
+    /// Whether the entries are search hits. Enter opens the session as it is;
+    /// `b` branches from the matching turn.
+    pub lists_search_hits: bool,
 }
 
 impl ResumeDialogState {
     pub fn new(entries: Vec<ResumeDialogEntry>) -> Self {
         Self {
+            title: t(Msg::ResumeSessionTitle),
+            entries,
+            selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: true,
+            lists_bookmarks: false,
+            lists_search_hits: false,
+        }
+    }
+
+    pub fn search_results(entries: Vec<ResumeDialogEntry>) -> Self {
+        Self {
+            title: t(Msg::HistoryTitle),
+            entries,
+            selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: false,
+            lists_bookmarks: false,
+            lists_search_hits: true,
+        }
+    }
+
+    pub fn bookmarks(entries: Vec<ResumeDialogEntry>) -> Self {
+        Self {
+            title: t(Msg::BookmarksTitle),
             entries,
             selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: false,
+            lists_bookmarks: true,
+            lists_search_hits: false,
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self.mode {
+            DialogMode::Browse if self.manages_sessions => t(Msg::ResumeManageHint),
+            DialogMode::Browse if self.lists_bookmarks => t(Msg::BookmarksHint),
+            DialogMode::Browse if self.lists_search_hits => t(Msg::SearchHitsHint),
+            DialogMode::Browse => t(Msg::ResumeHint),
+            DialogMode::ConfirmDelete => t(Msg::ConfirmDeleteHint),
+            DialogMode::Rename => t(Msg::RenameHintGui),
+        }
+    }
+
+    /// Drop the selected entry, e.g. a removed bookmark.
+    pub fn remove_selected(&mut self) {
+        if self.selected_index < self.entries.len() {
+            self.entries.remove(self.selected_index);
+        }
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Drop a deleted session's entries.
+    pub fn remove_session(&mut self, session_id: &str) {
+        self.entries.retain(|entry| entry.session_id != session_id);
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Show a renamed or (un)pinned session as it now is, keeping pinned
+    /// sessions first.
+    pub fn update_session(&mut self, session: &Session) {
+        for entry in &mut self.entries {
+            if entry.session_id == session.id {
+                entry.label = session_label(session);
+                entry.session_title = session.title.clone();
+                entry.pinned = session.pinned;
+            }
+        }
+        if self.manages_sessions {
+            let selected = self.entries.get(self.selected_index).cloned();
+            self.entries.sort_by_key(|entry| !entry.pinned);
+            if let Some(selected) = selected {
+                self.selected_index = self
+                    .entries
+                    .iter()
+                    .position(|entry| entry.session_id == selected.session_id)
+                    .unwrap_or(0);
+            }
         }
     }
 }
@@ -27,7 +135,38 @@ pub fn build_dialog_entries(sessions: Vec<Session>) -> Vec<ResumeDialogEntry> {
         .into_iter()
         .map(|session| ResumeDialogEntry {
             session_id: session.id.clone(),
+            turn_id: None,
             label: session_label(&session),
+            summary: summary_preview(&session),
+            pinned: session.pinned,
+            session_title: session.title,
+        })
+        .collect()
+}
+
+pub fn build_search_entries(hits: Vec<TurnSearchHit>) -> Vec<ResumeDialogEntry> {
+    hits.into_iter()
+        .map(|hit| ResumeDialogEntry {
+            label: search_hit_label(&hit),
+            session_id: hit.session_id,
+            turn_id: Some(hit.turn_id),
+            session_title: hit.session_title,
+            summary: None,
+            pinned: false,
+        })
+        .collect()
+}
+
+pub fn build_bookmark_entries(bookmarks: Vec<(TurnBookmark, String)>) -> Vec<ResumeDialogEntry> {
+    bookmarks
+        .into_iter()
+        .map(|(bookmark, prompt)| ResumeDialogEntry {
+            label: bookmark_label(&bookmark, &prompt),
+            session_id: bookmark.session_id,
+            turn_id: Some(bookmark.turn_id),
+            session_title: bookmark.session_title,
+            summary: bookmark.note,
+            pinned: false,
         })
         .collect()
 }
@@ -48,9 +187,6 @@ pub fn move_selection(dialog: &mut ResumeDialogState, direction: i32) {
     }
 }
 
-pub fn selected_session_id(dialog: &ResumeDialogState) -> Option<&str> {
-    dialog
-        .entries
-        .get(dialog.selected_index)
-        .map(|entry| entry.session_id.as_str())
+pub fn selected_entry(dialog: &ResumeDialogState) -> Option<&ResumeDialogEntry> {
+    dialog.entries.get(dialog.selected_index)
 }
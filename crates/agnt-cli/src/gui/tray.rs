@@ -0,0 +1,142 @@
+//! Tray / menu bar icon showing what the agent is doing, so a long turn can
+//! be followed with the window closed.
+
+use std::time::Duration;
+
+use gpui::{AnyWindowHandle, App as GpuiApp, WeakEntity};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIconBuilder};
+
+use super::AgntGui;
+
+/// How often the icon picks up status changes and menu clicks.
+const TRAY_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const ICON_SIZE: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayStatus {
+    Idle,
+    Generating,
+    /// Held back until the user confirms, e.g. sending past the budget or
+    /// writing a protected file.
+    AwaitingConfirmation,
+}
+
+impl TrayStatus {
+    fn of(gui: &AgntGui) -> Self {
+        if gui.pending_write.is_some() {
+            Self::AwaitingConfirmation
+        } else if gui.generating {
+            Self::Generating
+        } else if gui.budget_pending.is_some() || gui.large_request.is_some() {
+            Self::AwaitingConfirmation
+        } else {
+            Self::Idle
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Idle => "agnt: idle",
+            Self::Generating => "agnt: generating",
+            Self::AwaitingConfirmation => "agnt: waiting for you",
+        }
+    }
+
+    /// Icon fill colour.
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Self::Idle => [128, 128, 128],
+            Self::Generating => [52, 168, 83],
+            Self::AwaitingConfirmation => [230, 160, 20],
+        }
+    }
+}
+
+/// Show the tray icon until the app quits. Closing `main_window` only
+/// minimizes it while the icon is up.
+pub(super) fn register(
+    main: WeakEntity<AgntGui>,
+    main_window: AnyWindowHandle,
+    cx: &mut GpuiApp,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let status_item = MenuItem::new(TrayStatus::Idle.label(), false, None);
+    let show_item = MenuItem::new("Show agnt", true, None);
+    let new_session_item = MenuItem::new("New session", true, None);
+    let resume_item = MenuItem::new("Resume session...", true, None);
+    let quit_item = MenuItem::new("Quit", true, None);
+    let menu = Menu::new();
+    menu.append_items(&[
+        &status_item,
+        &PredefinedMenuItem::separator(),
+        &show_item,
+        &new_session_item,
+        &resume_item,
+        &PredefinedMenuItem::separator(),
+        &quit_item,
+    ])?;
+    let tray = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip(TrayStatus::Idle.label())
+        .with_icon(status_icon(TrayStatus::Idle)?)
+        .build()?;
+
+    cx.spawn(async move |cx| {
+        let menu_events = MenuEvent::receiver();
+        let mut shown_status = TrayStatus::Idle;
+        loop {
+            cx.background_executor().timer(TRAY_POLL_INTERVAL).await;
+
+            while let Ok(event) = menu_events.try_recv() {
+                let id = event.id();
+                let updated = if id == quit_item.id() {
+                    cx.update(|cx| cx.quit())
+                } else {
+                    main_window.update(cx, |_, window, cx| {
+                        window.activate_window();
+                        let _ = main.update(cx, |gui, cx| {
+                            if id == new_session_item.id() {
+                                gui.start_new_session(window, cx);
+                            } else if id == resume_item.id() {
+                                gui.open_resume_dialog(cx);
+                            }
+                        });
+                    })
+                };
+                if updated.is_err() {
+                    return;
+                }
+            }
+
+            let Ok(status) = main.read_with(cx, |gui, _| TrayStatus::of(gui)) else {
+                return;
+            };
+            if status != shown_status {
+                shown_status = status;
+                status_item.set_text(status.label());
+                let _ = tray.set_tooltip(Some(status.label()));
+                if let Ok(icon) = status_icon(status) {
+                    let _ = tray.set_icon(Some(icon));
+                }
+            }
+        }
+    })
+    .detach();
+    Ok(())
+}
+
+/// A filled circle in the status colour.
+fn status_icon(status: TrayStatus) -> Result<Icon, tray_icon::BadIcon> {
+    let [r, g, b] = status.rgb();
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 / 2.0 - 1.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            let alpha = if distance <= radius { 255 } else { 0 };
+            rgba.extend_from_slice(&[r, g, b, alpha]);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+}
@@ -0,0 +1,59 @@
+//! The main window's size and position, kept in the user data dir so the
+//! GUI opens where it was left.
+
+use gpui::{Bounds, Window, WindowBounds, point, px, size};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(super) struct WindowState {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// The bounds are then the ones the window restores to.
+    maximized: bool,
+}
+
+impl WindowState {
+    pub(super) fn of(window: &Window) -> Self {
+        // A full-screen window opens at its windowed size next time.
+        let (bounds, maximized) = match window.window_bounds() {
+            WindowBounds::Windowed(bounds) | WindowBounds::Fullscreen(bounds) => (bounds, false),
+            WindowBounds::Maximized(bounds) => (bounds, true),
+        };
+        Self {
+            x: bounds.origin.x.into(),
+            y: bounds.origin.y.into(),
+            width: bounds.size.width.into(),
+            height: bounds.size.height.into(),
+            maximized,
+        }
+    }
+
+    pub(super) fn bounds(self) -> WindowBounds {
+        let bounds = Bounds::new(
+            point(px(self.x), px(self.y)),
+            size(px(self.width), px(self.height)),
+        );
+        if self.maximized {
+            WindowBounds::Maximized(bounds)
+        } else {
+            WindowBounds::Windowed(bounds)
+        }
+    }
+
+    /// The state saved by the last launch; `None` on the first one or when
+    /// the file can't be read.
+    pub(super) fn load() -> Option<Self> {
+        let raw = std::fs::read_to_string(agnt_app::window_state_path().ok()?).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    pub(super) fn save(self) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(
+            agnt_app::window_state_path()?,
+            serde_json::to_string_pretty(&self)?,
+        )?;
+        Ok(())
+    }
+}
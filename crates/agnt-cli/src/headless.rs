@@ -0,0 +1,186 @@
+//! Running a prompt to completion without a UI, storing the turn the way
+//! the TUI and GUI do.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use agnt_app::{UserConfig, WebhookEvent};
+use agnt_auth::AuthManager;
+use agnt_core::{Agent, AgentEvent};
+use agnt_db::Store;
+use agnt_llm_registry::Registry;
+use parking_lot::Mutex;
+
+use crate::budget::BudgetTracker;
+use crate::session::{SessionStore, SharedSessionStore};
+use crate::tui::app::turn_footer;
+use crate::typeahead::mention_attachments;
+use crate::webhooks::Webhooks;
+use crate::{
+    build_default_agent, default_model, ensure_provider_credentials, offline, project_settings,
+};
+
+/// What a headless turn produced.
+pub struct TurnOutcome {
+    /// The session the turn was stored in.
+    pub session_id: String,
+    /// Why the turn failed, if it did.
+    pub error: Option<String>,
+}
+
+/// What a headless run, or building an agent, needs to know besides the
+/// project.
+pub struct RunOptions<'a> {
+    pub config: &'a UserConfig,
+    pub offline: bool,
+    /// `--read-only` was given (or, when building an agent, the profile is
+    /// read-only).
+    pub read_only: bool,
+}
+
+/// An agent and a session store for `root`, set up as `agnt tui` would be
+/// there, with `profile` in place of the project's own. Fails where the TUI
+/// would ask for confirmation, as with an exceeded budget.
+pub async fn open_project(
+    store: &Arc<Mutex<Store>>,
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    options: &RunOptions<'_>,
+    root: &Path,
+    profile: Option<&str>,
+) -> Result<(Agent, SharedSessionStore), Box<dyn std::error::Error>> {
+    let config = options.config;
+    let session_store = SessionStore::open_for_project_root(Arc::clone(store), root)?;
+    let session_store: SharedSessionStore = Arc::new(Mutex::new(session_store));
+    SessionStore::spawn_lock_heartbeat(&session_store);
+
+    let mut settings = session_store.lock().project_settings()?;
+    if let Some(profile) = profile {
+        settings.profile = Some(profile.to_string());
+    }
+    let (provider_id, model_id) = default_model(&settings, config);
+    let reasoning_effort = project_settings::reasoning_effort(&settings);
+    {
+        let mut session_store = session_store.lock();
+        session_store.set_model(&provider_id, model_id, reasoning_effort);
+        session_store.set_budget(BudgetTracker::new(registry, config, &provider_id));
+        session_store.set_jj_change_per_turn(config.jj_change_per_turn);
+        session_store.set_snapshot_file_contents(config.snapshot_file_contents);
+        session_store.set_webhooks(Webhooks::new(config, options.offline));
+    }
+    if let Some(status) = session_store.lock().budget_status()?
+        && status.is_exceeded()
+    {
+        return Err(format!("{}; refusing to run unattended", status.message()).into());
+    }
+    if options.offline {
+        offline::ensure_local_provider(registry, &provider_id)?;
+    }
+
+    let read_only = options.read_only
+        || settings
+            .profile
+            .as_deref()
+            .and_then(|profile| config.profiles.get(profile))
+            .is_some_and(|profile| profile.read_only);
+    ensure_provider_credentials(registry, auth, &provider_id).await?;
+    let agent = build_default_agent(
+        registry,
+        &settings,
+        &RunOptions {
+            config,
+            offline: options.offline,
+            read_only,
+        },
+        root,
+        reasoning_effort,
+        None,
+    )?;
+    Ok((agent, session_store))
+}
+
+/// Send `prompt`, with the files it mentions under `root` attached unless
+/// `attach_mentions` is off, in the
+/// store's active session (creating one when there is none) and wait for
+/// the turn to finish. Errors from the turn end up in the outcome; the
+/// `Err` case is for failing to store it.
+pub async fn run_turn(
+    agent: &mut Agent,
+    session_store: &SharedSessionStore,
+    root: &Path,
+    prompt: &str,
+    attach_mentions: bool,
+) -> Result<TurnOutcome, Box<dyn std::error::Error>> {
+    session_store.lock().ensure_active_session()?;
+    agent.artifacts_dir(session_store.lock().artifacts_dir());
+    let session_id = session_store
+        .lock()
+        .active_session_id()
+        .map(str::to_string)
+        .ok_or("no active session")?;
+
+    session_store.lock().begin_turn(prompt)?;
+    let attachments = if attach_mentions {
+        mention_attachments(prompt, root)
+    } else {
+        Vec::new()
+    };
+    let mut stream = agent.submit_with_attachments(prompt, attachments);
+    let streamed = async {
+        let mut error = None;
+        let mut completed = false;
+        while let Some(event) = stream.next().await {
+            match event {
+                AgentEvent::ToolCallDone {
+                    id,
+                    record,
+                    raw_output,
+                    ..
+                } => {
+                    let mut session_store = session_store.lock();
+                    session_store.record_tool_call(&record)?;
+                    if let Some(raw_output) = raw_output {
+                        session_store.save_tool_output(&id, &raw_output)?;
+                    }
+                }
+                AgentEvent::ResponsePending { token } => {
+                    session_store.lock().save_pending_response(agent, &token)?;
+                }
+                // The turn still completes and is stored, but didn't do what
+                // was asked.
+                AgentEvent::Refusal { message, guidance } => {
+                    error = Some(match message {
+                        Some(message) => format!("the model refused: {message}"),
+                        None => format!("the model refused. {guidance}"),
+                    });
+                }
+                AgentEvent::TurnComplete { usage, stats } => {
+                    let mut session_store = session_store.lock();
+                    session_store.persist_turn_from_agent(agent, &usage, &stats)?;
+                    session_store.close_jj_change()?;
+                    session_store.notify(WebhookEvent::TurnComplete, &turn_footer(&usage, &stats));
+                    completed = true;
+                    break;
+                }
+                AgentEvent::Error { error: message } => {
+                    session_store.lock().notify(WebhookEvent::Error, &message);
+                    error = Some(message);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok::<_, Box<dyn std::error::Error>>((error, completed))
+    }
+    .await;
+    // The pending turn is cleared even when storing what the turn did
+    // failed, so it isn't offered again as interrupted.
+    let ended = session_store.lock().end_turn();
+    let (mut error, completed) = streamed?;
+    ended?;
+    if !completed && error.is_none() {
+        error = Some("the turn ended before it completed".to_string());
+    }
+
+    Ok(TurnOutcome { session_id, error })
+}
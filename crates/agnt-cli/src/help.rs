@@ -0,0 +1,98 @@
+//! Contents of the help overlay: the keybindings of each UI, the slash
+//! commands available in the project and the mention syntax.
+
+use std::path::Path;
+
+use crate::i18n::{Msg, t};
+use crate::typeahead::{Command, TypeaheadItem, available_commands};
+
+/// A key combination and what it does.
+pub type Keybinding = (&'static str, &'static str);
+
+pub const TUI_KEYBINDINGS: &[Keybinding] = &[
+    ("Enter", "Send the message, or run the selected suggestion"),
+    ("Shift+Enter / Alt+Enter", "Insert a newline"),
+    (
+        "Esc",
+        "Stop generating, hide suggestions or cancel the prompt",
+    ),
+    ("Ctrl+C", "Stop generating, or quit when idle"),
+    ("Ctrl+K", "Open the command palette"),
+    ("Ctrl+O", "Attach the last tool output to the next message"),
+    ("Ctrl+B", "Bookmark the last turn"),
+    (
+        "Ctrl+T / Ctrl+D",
+        "Drop old tool results / attachments from a held-back large request",
+    ),
+    (
+        "Esc (idle)",
+        "Select messages and tool blocks: Enter collapses, c copies, b bookmarks, o opens the diff or image",
+    ),
+    ("? / F1", "Show this help (? with an empty composer)"),
+    ("Up / Down", "Move through suggestions or composer lines"),
+    ("Ctrl+A / Ctrl+E", "Move to the start / end of the line"),
+    ("Home / End", "Move to the start / end of the composer"),
+    ("PageUp / PageDown", "Scroll the conversation"),
+];
+
+pub const GUI_KEYBINDINGS: &[Keybinding] = &[
+    ("Enter", "Send the message, or run the selected suggestion"),
+    ("Shift+Enter", "Insert a newline"),
+    ("Esc", "Hide suggestions or cancel the prompt"),
+    ("Ctrl+K / Cmd+K", "Open the command palette"),
+    (
+        "Ctrl+O / Cmd+O",
+        "Attach the last tool output to the next message",
+    ),
+    ("Ctrl+B / Cmd+B", "Bookmark the last turn"),
+    (
+        "Ctrl+= / Ctrl+- / Ctrl+0 (Cmd on macOS)",
+        "Make text larger, smaller, or the default size",
+    ),
+    ("F1", "Show this help"),
+    ("Up / Down", "Move through suggestions"),
+];
+
+const MENTIONS: &[Keybinding] = &[(
+    "@path",
+    "Attach a file's contents; type part of a path to search the project",
+)];
+
+pub struct HelpSection {
+    pub title: &'static str,
+    pub rows: Vec<(String, String)>,
+}
+
+/// The help overlay's sections for a UI with `keybindings`, listing the
+/// commands available in `project_root`.
+pub fn sections(keybindings: &[Keybinding], project_root: &Path) -> Vec<HelpSection> {
+    let owned = |rows: &[Keybinding]| {
+        rows.iter()
+            .map(|(keys, action)| (keys.to_string(), action.to_string()))
+            .collect()
+    };
+    vec![
+        HelpSection {
+            title: t(Msg::HelpKeys),
+            rows: owned(keybindings),
+        },
+        HelpSection {
+            title: t(Msg::HelpCommands),
+            rows: available_commands(project_root)
+                .iter()
+                .map(command_row)
+                .collect(),
+        },
+        HelpSection {
+            title: t(Msg::HelpMentions),
+            rows: owned(MENTIONS),
+        },
+    ]
+}
+
+fn command_row(command: &Command) -> (String, String) {
+    (
+        format!("/{}", command.token_text()),
+        command.description().unwrap_or_default(),
+    )
+}
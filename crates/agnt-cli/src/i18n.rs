@@ -0,0 +1,272 @@
+//! The message catalog: UI strings of the TUI and GUI per locale.
+//!
+//! The locale is picked once at startup from the `locale` config setting,
+//! then `AGNT_LOCALE`, then the usual `LC_ALL` / `LC_MESSAGES` / `LANG`
+//! variables, falling back to English. A translation is a `match` over
+//! [`Msg`] below; one missing from it can't compile.
+
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+}
+
+impl Locale {
+    /// The locale for a tag such as `de`, `de_DE.UTF-8` or `en-US`; `None`
+    /// when there is no catalog for its language.
+    fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Pick the locale, preferring `configured` (the `locale` config setting)
+/// over the environment. Only the first call has an effect.
+pub fn init(configured: Option<&str>) {
+    let from_env = || {
+        ["AGNT_LOCALE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+    };
+    let locale = configured
+        .map(str::to_string)
+        .or_else(from_env)
+        .and_then(|tag| Locale::from_tag(&tag))
+        .unwrap_or(Locale::En);
+    let _ = LOCALE.set(locale);
+}
+
+fn locale() -> Locale {
+    *LOCALE.get_or_init(|| Locale::En)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    You,
+    Assistant,
+    ComposerPlaceholder,
+    EmptyThreadTui,
+    EmptyThreadGui,
+    Send,
+    Generating,
+    Help,
+    UntitledSession,
+    ResumeSessionTitle,
+    HistoryTitle,
+    ResumeManageHint,
+    ResumeHint,
+    ConfirmDeleteHint,
+    RenameHintTui,
+    RenameHintGui,
+    HistorySearchHint,
+    SettingsEditHint,
+    BudgetConfirmHint,
+    WriteApprovalHint,
+    AllowWrite,
+    RefuseWrite,
+    LargeRequestHint,
+    DropToolResults,
+    DropAttachments,
+    PaletteTitle,
+    PaletteHint,
+    NoMatches,
+    HelpScrollHint,
+    HelpCloseHint,
+    HelpKeys,
+    HelpCommands,
+    HelpMentions,
+    LastRequest,
+    NextRequest,
+    ContextAddHint,
+    PinnedContext,
+    ContextPanelHint,
+    CompareHint,
+    CompareTitle,
+    RetryWithHint,
+    CompareViewHint,
+    BookmarkHint,
+    BookmarksTitle,
+    BookmarksHint,
+    SearchHitsHint,
+    NavigationHint,
+    DiffTitle,
+    Copy,
+    MermaidDiagram,
+    OpenDiagram,
+    OpenImage,
+}
+
+/// `msg` in the current locale.
+pub fn t(msg: Msg) -> &'static str {
+    match locale() {
+        Locale::En => en(msg),
+        Locale::De => de(msg),
+    }
+}
+
+fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::You => "You",
+        Msg::Assistant => "Assistant",
+        Msg::ComposerPlaceholder => "Type a message...",
+        Msg::EmptyThreadTui => "Type a message and press Enter to start.",
+        Msg::EmptyThreadGui => "Type a message and press Enter. Use Shift+Enter for newline.",
+        Msg::Send => "Send",
+        Msg::Generating => "Generating...",
+        Msg::Help => "Help",
+        Msg::UntitledSession => "Untitled session",
+        Msg::ResumeSessionTitle => "Resume session",
+        Msg::HistoryTitle => "History",
+        Msg::ResumeManageHint => {
+            "Enter to resume, d to delete, r to rename, p to pin or unpin, Esc to cancel"
+        }
+        Msg::ResumeHint => "Enter to resume, Esc to cancel",
+        Msg::ConfirmDeleteHint => "Delete this session? Enter to delete, Esc to keep it",
+        Msg::RenameHintTui => "New title (Enter to save, Esc to cancel):",
+        Msg::RenameHintGui => "Type the new title below, Enter to save, Esc to cancel",
+        Msg::HistorySearchHint => "search history (Enter to search, Esc to cancel)",
+        Msg::SettingsEditHint => {
+            "project settings (Enter to save, Shift+Enter for a new line, Esc to cancel)"
+        }
+        Msg::BudgetConfirmHint => "Enter to send anyway, Esc to cancel",
+        Msg::WriteApprovalHint => "y to allow, n to refuse",
+        Msg::AllowWrite => "Allow",
+        Msg::RefuseWrite => "Refuse",
+        Msg::DropToolResults => "Drop old tool results",
+        Msg::DropAttachments => "Drop attachments",
+        Msg::LargeRequestHint => {
+            "Enter to send anyway, Ctrl+T to drop old tool results, Ctrl+D to drop attachments, Esc to cancel"
+        }
+        Msg::PaletteTitle => "Commands",
+        Msg::PaletteHint => "Type to filter, Enter to run, Esc to close",
+        Msg::NoMatches => "No matches",
+        Msg::HelpScrollHint => "Up/Down to scroll, Esc to close",
+        Msg::HelpCloseHint => "Esc or F1 to close",
+        Msg::HelpKeys => "Keys",
+        Msg::HelpCommands => "Commands",
+        Msg::HelpMentions => "Mentions",
+        Msg::LastRequest => "Last request",
+        Msg::NextRequest => "Next request (none sent yet)",
+        Msg::ContextAddHint => "pin a file, URL or note into context (Enter to pin, Esc to cancel)",
+        Msg::PinnedContext => "Pinned context",
+        Msg::ContextPanelHint => "Up/Down select, Delete unpins, Esc closes",
+        Msg::CompareHint => {
+            "two models as provider/model@effort, then a prompt or nothing to ask the last one again (Enter to run, Esc to cancel)"
+        }
+        Msg::CompareTitle => "Compare",
+        Msg::RetryWithHint => {
+            "model as provider/model@effort, or @effort to keep the model (Enter to regenerate the last response, Esc to cancel)"
+        }
+        Msg::CompareViewHint => "Up/Down scroll, Esc closes (stopping answers still generating)",
+        Msg::BookmarkHint => {
+            "note for the bookmark, if any (Enter to bookmark the last turn, Esc to cancel)"
+        }
+        Msg::BookmarksTitle => "Bookmarks",
+        Msg::BookmarksHint => "Enter to jump to the turn, d to remove the bookmark, Esc to cancel",
+        Msg::SearchHitsHint => {
+            "Enter to open the session, b to continue from the matching turn, Esc to cancel"
+        }
+        Msg::NavigationHint => {
+            "Up/Down select, Enter collapse, c copy, b bookmark, o open diff or image, Esc back to the composer"
+        }
+        Msg::DiffTitle => "Diff",
+        Msg::Copy => "Copy",
+        Msg::MermaidDiagram => "mermaid diagram",
+        Msg::OpenDiagram => "Open rendered",
+        Msg::OpenImage => "Open",
+    }
+}
+
+fn de(msg: Msg) -> &'static str {
+    match msg {
+        Msg::You => "Du",
+        Msg::Assistant => "Assistent",
+        Msg::ComposerPlaceholder => "Nachricht eingeben...",
+        Msg::EmptyThreadTui => "Nachricht eingeben und mit Enter starten.",
+        Msg::EmptyThreadGui => {
+            "Nachricht eingeben und Enter drücken. Shift+Enter für eine neue Zeile."
+        }
+        Msg::Send => "Senden",
+        Msg::Generating => "Generiere...",
+        Msg::Help => "Hilfe",
+        Msg::UntitledSession => "Unbenannte Sitzung",
+        Msg::ResumeSessionTitle => "Sitzung fortsetzen",
+        Msg::HistoryTitle => "Verlauf",
+        Msg::ResumeManageHint => {
+            "Enter zum Fortsetzen, d zum Löschen, r zum Umbenennen, p zum (Los-)Anheften, Esc zum Abbrechen"
+        }
+        Msg::ResumeHint => "Enter zum Fortsetzen, Esc zum Abbrechen",
+        Msg::ConfirmDeleteHint => "Diese Sitzung löschen? Enter zum Löschen, Esc zum Behalten",
+        Msg::RenameHintTui => "Neuer Titel (Enter zum Speichern, Esc zum Abbrechen):",
+        Msg::RenameHintGui => "Neuen Titel unten eingeben, Enter zum Speichern, Esc zum Abbrechen",
+        Msg::HistorySearchHint => "Verlauf durchsuchen (Enter zum Suchen, Esc zum Abbrechen)",
+        Msg::SettingsEditHint => {
+            "Projekteinstellungen (Enter zum Speichern, Shift+Enter für eine neue Zeile, Esc zum Abbrechen)"
+        }
+        Msg::BudgetConfirmHint => "Enter zum trotzdem Senden, Esc zum Abbrechen",
+        Msg::WriteApprovalHint => "y zum Erlauben, n zum Ablehnen",
+        Msg::AllowWrite => "Erlauben",
+        Msg::RefuseWrite => "Ablehnen",
+        Msg::DropToolResults => "Alte Tool-Ergebnisse entfernen",
+        Msg::DropAttachments => "Anhänge entfernen",
+        Msg::LargeRequestHint => {
+            "Enter zum trotzdem Senden, Strg+T entfernt alte Tool-Ergebnisse, Strg+D entfernt Anhänge, Esc zum Abbrechen"
+        }
+        Msg::PaletteTitle => "Befehle",
+        Msg::PaletteHint => "Tippen zum Filtern, Enter zum Ausführen, Esc zum Schließen",
+        Msg::NoMatches => "Keine Treffer",
+        Msg::HelpScrollHint => "Hoch/Runter zum Blättern, Esc zum Schließen",
+        Msg::HelpCloseHint => "Esc oder F1 zum Schließen",
+        Msg::HelpKeys => "Tasten",
+        Msg::HelpCommands => "Befehle",
+        Msg::HelpMentions => "Erwähnungen",
+        Msg::LastRequest => "Letzte Anfrage",
+        Msg::NextRequest => "Nächste Anfrage (noch keine gesendet)",
+        Msg::ContextAddHint => {
+            "Datei, URL oder Notiz an den Kontext heften (Enter zum Anheften, Esc zum Abbrechen)"
+        }
+        Msg::PinnedContext => "Angehefteter Kontext",
+        Msg::ContextPanelHint => "Auf/Ab wählen, Entf löst, Esc schließt",
+        Msg::CompareHint => {
+            "zwei Modelle als Anbieter/Modell@Aufwand, dann ein Prompt oder nichts, um den letzten erneut zu stellen (Enter zum Starten, Esc zum Abbrechen)"
+        }
+        Msg::CompareTitle => "Vergleich",
+        Msg::RetryWithHint => {
+            "Modell als Anbieter/Modell@Aufwand, oder @Aufwand für dasselbe Modell (Enter erzeugt die letzte Antwort neu, Esc zum Abbrechen)"
+        }
+        Msg::CompareViewHint => {
+            "Auf/Ab scrollen, Esc schließt (laufende Antworten werden abgebrochen)"
+        }
+        Msg::BookmarkHint => {
+            "Notiz zum Lesezeichen, falls gewünscht (Enter setzt es auf den letzten Turn, Esc zum Abbrechen)"
+        }
+        Msg::BookmarksTitle => "Lesezeichen",
+        Msg::BookmarksHint => {
+            "Enter springt zum Turn, d entfernt das Lesezeichen, Esc zum Abbrechen"
+        }
+        Msg::SearchHitsHint => {
+            "Enter öffnet die Sitzung, b setzt ab dem gefundenen Turn fort, Esc zum Abbrechen"
+        }
+        Msg::NavigationHint => {
+            "Hoch/Runter auswählen, Enter einklappen, c kopieren, b Lesezeichen, o Diff oder Bild öffnen, Esc zurück zur Eingabe"
+        }
+        Msg::DiffTitle => "Diff",
+        Msg::Copy => "Kopieren",
+        Msg::MermaidDiagram => "Mermaid-Diagramm",
+        Msg::OpenDiagram => "Gerendert öffnen",
+        Msg::OpenImage => "Öffnen",
+    }
+}
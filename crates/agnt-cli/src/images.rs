@@ -0,0 +1,61 @@
+//! Images in assistant answers. Inline ones arrive as `data:` URLs and are
+//! saved to the user data dir, so the TUI can link to a file and the GUI
+//! can load one.
+
+use std::path::PathBuf;
+
+use base64::Engine as _;
+use sha2::{Digest, Sha256};
+
+const IMAGES_DIR: &str = "images";
+
+/// Where the image at `url` can be opened: the file a `data:` URL was saved
+/// to, or a remote URL as given.
+pub fn location(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(data) = url.strip_prefix("data:") else {
+        return Ok(url.to_string());
+    };
+    let (media_type, payload) = data.split_once(',').ok_or("malformed data URL")?;
+    let mime = media_type
+        .strip_suffix(";base64")
+        .ok_or("image data URL is not base64")?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(payload.trim())?;
+
+    let hash = Sha256::digest(&bytes);
+    let name: String = hash[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+    let dir = agnt_app::ensure_user_data_dir()?.join(IMAGES_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{name}.{}", extension(mime)));
+    // Named by content, so an image seen before is already there.
+    if !path.exists() {
+        std::fs::write(&path, bytes)?;
+    }
+    Ok(path.display().to_string())
+}
+
+/// Whether `location` is a saved file rather than a remote URL.
+pub fn is_file(location: &str) -> bool {
+    !location.starts_with("http://") && !location.starts_with("https://")
+}
+
+/// A URL that opens `location` in the system viewer or browser.
+pub fn open_url(location: &str) -> String {
+    if is_file(location) {
+        url::Url::from_file_path(PathBuf::from(location))
+            .map(String::from)
+            .unwrap_or_else(|()| location.to_string())
+    } else {
+        location.to_string()
+    }
+}
+
+fn extension(mime: &str) -> &str {
+    match mime {
+        "image/jpeg" => "jpg",
+        "image/svg+xml" => "svg",
+        mime => mime
+            .strip_prefix("image/")
+            .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or("bin"),
+    }
+}
@@ -0,0 +1,128 @@
+//! `agnt init`: interactive setup that picks a provider, signs in and
+//! chooses the default model. Also runs on first launch, before any config
+//! file exists.
+
+use std::sync::Arc;
+
+use agnt_app::UserConfig;
+use agnt_auth::AuthManager;
+use agnt_llm_registry::Registry;
+
+use crate::providers::model_details;
+use crate::{DEFAULT_MODEL_ID, DEFAULT_PROVIDER_ID, ensure_provider_credentials, prompt_line};
+
+pub async fn run(
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    config: &mut UserConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let current = config
+        .default_model
+        .as_deref()
+        .and_then(|model| model.split_once('/'));
+    let current_provider = current.map_or(DEFAULT_PROVIDER_ID, |(provider, _)| provider);
+
+    let mut providers = registry
+        .known_providers()
+        .into_iter()
+        .filter(|provider| provider.compatible)
+        .collect::<Vec<_>>();
+    // Providers already signed in to first.
+    providers.sort_by_key(|provider| !provider.configured);
+    if providers.is_empty() {
+        return Err("no usable providers are registered".into());
+    }
+
+    println!("Choose a provider:");
+    for (index, provider) in providers.iter().enumerate() {
+        let configured = if provider.configured {
+            ", signed in"
+        } else {
+            ""
+        };
+        println!(
+            "  {:>3}) {} ({}) [{}{configured}]",
+            index + 1,
+            provider.id,
+            provider.name,
+            provider.auth_method
+        );
+    }
+    let provider = choose(
+        &providers,
+        |provider| provider.id.as_str(),
+        current_provider,
+        "Provider",
+    )?
+    .id
+    .clone();
+
+    ensure_provider_credentials(registry, auth, &provider).await?;
+
+    let mut models = registry.list_models(&provider);
+    if models.is_empty() {
+        return Err(format!("{provider} does not list any models").into());
+    }
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let default_model = match current {
+        Some((current_provider, model)) if current_provider == provider => model,
+        _ if provider == DEFAULT_PROVIDER_ID => DEFAULT_MODEL_ID,
+        _ => models[0].id.as_str(),
+    };
+
+    println!();
+    println!("Choose a default model:");
+    for (index, model) in models.iter().enumerate() {
+        let name = model.name.as_deref().unwrap_or("");
+        println!(
+            "  {:>3}) {:<30} {name:<30} {}",
+            index + 1,
+            model.id,
+            model_details(model)
+        );
+    }
+    let model = choose(&models, |model| model.id.as_str(), default_model, "Model")?
+        .id
+        .clone();
+
+    config.default_model = Some(format!("{provider}/{model}"));
+    config.save()?;
+    println!();
+    println!(
+        "Saved {provider}/{model} as the default model in {}.",
+        agnt_app::config_path()?.display()
+    );
+    println!("Run `agnt init` again at any time to change it.");
+    Ok(())
+}
+
+/// Ask for one of `items` by number or id, re-prompting on invalid input.
+/// An empty answer picks `default` when it is one of the items.
+fn choose<'a, T>(
+    items: &'a [T],
+    id: impl Fn(&T) -> &str,
+    default: &str,
+    label: &str,
+) -> Result<&'a T, Box<dyn std::error::Error>> {
+    let default = items.iter().find(|item| id(item) == default);
+    let prompt = match default {
+        Some(item) => format!("{label} [{}]: ", id(item)),
+        None => format!("{label}: "),
+    };
+
+    loop {
+        let answer = prompt_line(&prompt)?;
+        let choice = if answer.is_empty() {
+            Some(default.ok_or_else(|| format!("no {} chosen", label.to_lowercase()))?)
+        } else if let Ok(number) = answer.parse::<usize>() {
+            number.checked_sub(1).and_then(|index| items.get(index))
+        } else {
+            items.iter().find(|item| id(item) == answer)
+        };
+        match choice {
+            Some(item) => return Ok(item),
+            None => println!("Enter a number from 1 to {} or an id.", items.len()),
+        }
+    }
+}
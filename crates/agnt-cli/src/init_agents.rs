@@ -0,0 +1,163 @@
+//! `agnt init-agents`: a read-only agent pass over the repository that
+//! drafts an AGENTS.md, written only once the user has reviewed it.
+
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Arc;
+
+use agnt_auth::AuthManager;
+use agnt_core::attachment::ATTACHMENT_METADATA_KEY;
+use agnt_db::Store;
+use agnt_llm::{AssistantPart, Message, TextPart, UserPart};
+use agnt_llm_registry::Registry;
+use parking_lot::Mutex;
+
+use crate::headless::{self, RunOptions};
+use crate::prompt_line;
+use crate::typeahead::project_files;
+
+/// Files listed for the model; it can read any of them, but not list
+/// directories.
+const MAX_LISTED_FILES: usize = 2000;
+
+const PROMPT: &str = "Draft an AGENTS.md for this repository: the instructions a coding agent \
+reads before working in it. Read the files that tell you how the project is built and laid out \
+(manifests, READMEs, CI configuration, a few representative sources) before writing anything. \
+Cover how to build, test, lint and format it with the exact commands, the layout of the code \
+and what lives where, and the conventions a change must follow (naming, error handling, tests, \
+commit style). Only state what you have seen in the files; leave out anything generic. If an \
+AGENTS.md already exists, keep what is still accurate. Answer with the file's Markdown content \
+only, without a preamble or a code fence around it.";
+
+/// Draft AGENTS.md for `root`, show it and write it once confirmed (or
+/// straight away with `yes`).
+pub async fn run(
+    store: &Arc<Mutex<Store>>,
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    options: &RunOptions<'_>,
+    root: &Path,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = RunOptions {
+        config: options.config,
+        offline: options.offline,
+        read_only: true,
+    };
+    let (mut agent, session_store) =
+        headless::open_project(store, registry, auth, &options, root, None).await?;
+
+    let files = project_files(root);
+    let mut listing = format!("<files count=\"{}\">\n", files.len());
+    for file in files.iter().take(MAX_LISTED_FILES) {
+        listing.push_str(file);
+        listing.push('\n');
+    }
+    listing.push_str("</files>");
+    agent.inject_context([UserPart::Text(TextPart {
+        text: listing,
+        metadata: HashMap::from([(
+            ATTACHMENT_METADATA_KEY.to_string(),
+            format!("file list ({} files)", files.len()),
+        )]),
+    })]);
+
+    eprintln!("Reading {}...", root.display());
+    let outcome = headless::run_turn(&mut agent, &session_store, root, PROMPT, false).await?;
+    if let Some(error) = outcome.error {
+        return Err(error.into());
+    }
+    let draft = last_answer(&agent.messages());
+    if draft.trim().is_empty() {
+        return Err("the model returned an empty draft".into());
+    }
+
+    let path = root.join("AGENTS.md");
+    println!("{draft}");
+    println!();
+    if path.exists() {
+        println!("This replaces the existing {}.", path.display());
+    }
+
+    let draft = if yes {
+        draft
+    } else if io::stdin().is_terminal() {
+        let answer = prompt_line(&format!(
+            "Write it to {}? [y]es, [e]dit first, [N]o: ",
+            path.display()
+        ))?;
+        match answer.to_ascii_lowercase().as_str() {
+            "y" | "yes" => draft,
+            "e" | "edit" => edit(&draft)?,
+            _ => {
+                println!("Not written (session {}).", outcome.session_id);
+                return Ok(());
+            }
+        }
+    } else {
+        println!("Not written; pass --yes to write it without a terminal.");
+        return Ok(());
+    };
+
+    std::fs::write(&path, ensure_trailing_newline(draft))?;
+    println!("Wrote {}.", path.display());
+    Ok(())
+}
+
+/// Text of the final assistant message, without a code fence the model put
+/// around it despite being asked not to.
+fn last_answer(messages: &[Message]) -> String {
+    let text = messages
+        .iter()
+        .rev()
+        .find_map(|message| match message {
+            Message::Assistant { parts } => Some(
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        AssistantPart::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<String>(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let trimmed = text.trim();
+    let unfenced = trimmed
+        .strip_prefix("```markdown")
+        .or_else(|| trimmed.strip_prefix("```md"))
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"));
+    unfenced.unwrap_or(trimmed).trim().to_string()
+}
+
+/// Let the user change `draft` in `$VISUAL` or `$EDITOR`.
+fn edit(draft: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .map_err(|_| "set $VISUAL or $EDITOR to edit the draft")?;
+    let path = std::env::temp_dir().join(format!("agnt-AGENTS-{}.md", std::process::id()));
+    std::fs::write(&path, draft)?;
+    // The variable may carry arguments, e.g. `code --wait`.
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        return Err(format!("{editor} exited with an error; nothing written").into());
+    }
+    Ok(edited?)
+}
+
+fn ensure_trailing_newline(mut text: String) -> String {
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
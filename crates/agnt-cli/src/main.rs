@@ -1,16 +1,49 @@
+mod batch;
+mod budget;
+mod changes;
+mod compare;
+mod doctor;
 mod gui;
+mod headless;
+mod help;
+mod i18n;
+mod images;
+mod init;
+mod init_agents;
+mod offline;
+mod palette;
+mod patch;
+mod pinned;
+mod presubmit;
+mod project_settings;
+mod providers;
+mod queue;
+mod request_size;
 mod session;
+mod share;
+mod skills;
+mod snapshots;
+mod spec_refresh;
+mod stats;
+mod summaries;
+mod sync;
+mod template_fill;
 mod tui;
 mod typeahead;
+mod vcs;
+mod webhooks;
+mod write_approval;
 
 use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
+use agnt_app::{ProviderHttpConfig, UserConfig};
 use agnt_auth::AuthManager;
-use agnt_db::Store;
+use agnt_db::{ProjectSettings, RetentionPolicy, SessionCipher, Store};
+use agnt_llm::HttpSettings;
 use agnt_llm_registry::{AuthMethod, OAuthPkceAuth, Registry};
 use axum::extract::{Query, State};
 use axum::http::{StatusCode, Uri};
@@ -21,11 +54,17 @@ use parking_lot::Mutex;
 use tokio::sync::{mpsc, oneshot};
 use url::Url;
 
-use crate::session::{SessionStore, SharedSessionStore};
+use crate::compare::CompareModels;
+use crate::i18n::{Msg, t};
+use crate::request_size::RequestGuard;
+use crate::session::{SessionStore, SharedSessionStore, StartupSession};
+use crate::spec_refresh::SpecRefresh;
 use crate::tui::app::App;
+use crate::typeahead::TypeaheadState;
 
 const DEFAULT_PROVIDER_ID: &str = agnt_llm_codex::PROVIDER_ID;
 const DEFAULT_MODEL_ID: &str = agnt_llm_codex::DEFAULT_MODEL_ID;
+const KEYRING_SERVICE: &str = "agnt";
 const OAUTH_CALLBACK_TIMEOUT: Duration = Duration::from_secs(180);
 const OAUTH_SUCCESS_HTML: &str = "<!doctype html><html><head><meta charset=\"utf-8\" /><title>Authentication successful</title></head><body><p>Authentication successful. Return to your terminal.</p></body></html>";
 
@@ -39,9 +78,34 @@ struct Cli {
     #[arg(long, hide = true)]
     providers: bool,
 
-    /// Resume the most recently active session for the current project.
+    /// Pick a session of the current project to resume when the UI opens,
+    /// or resume the one given with `--resume=<SESSION_ID>`.
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        value_name = "SESSION_ID",
+        conflicts_with_all = ["continue_session", "new"]
+    )]
+    resume: Option<Option<String>>,
+
+    /// Continue the most recently active session for the current project.
+    #[arg(long = "continue", global = true, conflicts_with = "new")]
+    continue_session: bool,
+
+    /// Start a new session (the default).
+    #[arg(long, global = true)]
+    new: bool,
+
+    /// Don't use the network except for providers on local endpoints.
     #[arg(long, global = true)]
-    resume: bool,
+    offline: bool,
+
+    /// Only give the agent tools that cannot modify the workspace, whatever
+    /// the project's approval policy.
+    #[arg(long, global = true)]
+    read_only: bool,
 }
 
 #[derive(Clone, Subcommand)]
@@ -56,15 +120,227 @@ enum Command {
         /// Run the GUI from this working directory.
         cwd: Option<PathBuf>,
     },
+    /// Choose a provider, sign in and pick the default model.
+    Init,
+    /// Draft an AGENTS.md for the current project with a read-only agent
+    /// pass, and write it after review.
+    InitAgents {
+        /// Write the draft without asking.
+        #[arg(long)]
+        yes: bool,
+    },
     /// List known providers and their models.
-    Providers,
+    Providers {
+        /// Only list providers with credentials.
+        #[arg(long)]
+        configured: bool,
+        /// Only list this provider.
+        #[arg(long, value_name = "PROVIDER_ID")]
+        provider: Option<String>,
+        /// Only list models with this capability.
+        #[arg(long, value_enum)]
+        capability: Option<providers::Capability>,
+        /// Send a request to each provider to check its credentials,
+        /// latency and remaining quota.
+        #[arg(long)]
+        check: bool,
+        /// Print JSON instead of text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check connectivity, credentials, the session database and the
+    /// terminal, and suggest fixes.
+    Doctor,
+    /// Inspect stored sessions for the current project.
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommand,
+    },
+    /// Inspect and maintain the session database.
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Review the audit log of executed tool calls.
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+    /// Summarize token usage, estimated cost and tool calls from stored
+    /// sessions.
+    Stats {
+        /// Only include turns from the last this many days.
+        #[arg(long)]
+        days: Option<u64>,
+        /// How to group the rows.
+        #[arg(long, value_enum, default_value = "model")]
+        by: stats::GroupBy,
+        /// Output format.
+        #[arg(long, value_enum, default_value = "table")]
+        format: stats::OutputFormat,
+    },
+    /// Push local session history to the configured sync remote and pull
+    /// sessions recorded on other machines.
+    Sync,
+    /// Run one prompt in many checkouts concurrently, without a UI, and
+    /// report how each run went.
+    Batch {
+        /// File listing the checkouts, one path per line. Blank lines and
+        /// lines starting with `#` are skipped.
+        #[arg(long)]
+        repos: PathBuf,
+        /// File holding the prompt.
+        #[arg(long)]
+        prompt: PathBuf,
+        /// How many checkouts run at the same time.
+        #[arg(long, default_value_t = 4)]
+        jobs: usize,
+        /// Run with this profile instead of each project's own.
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Queue prompts and run them one after another without a UI.
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+    /// Print the JSON Schema of agent events in their versioned JSON form.
+    EventSchema,
+    /// Install, update and remove the workspace's skills in
+    /// `.agents/skills`.
+    Skill {
+        #[command(subcommand)]
+        command: SkillCommand,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum SessionsCommand {
+    /// Full-text search over the user and assistant messages of past sessions.
+    Search {
+        query: String,
+        /// Maximum number of hits to show.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum DbCommand {
+    /// Show the database location, journal mode and size per table.
+    Stats,
+    /// Rebuild the database file to reclaim space from deleted data.
+    Vacuum,
+    /// Delete old sessions, across all projects, together with their turns.
+    Prune {
+        /// Delete sessions not updated in this many days.
+        #[arg(long)]
+        older_than_days: Option<u64>,
+        /// Keep only this many of the most recently updated sessions per project.
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    /// Encrypt stored sessions with a key kept in the OS keychain, and keep
+    /// encrypting new ones.
+    Encrypt,
+    /// Decrypt stored sessions and stop encrypting new ones.
+    Decrypt,
+}
+
+#[derive(Clone, Subcommand)]
+enum AuditCommand {
+    /// List recorded tool calls, oldest first, and check the hash chain.
+    Show {
+        /// Only show calls made in this session.
+        #[arg(long)]
+        session: Option<String>,
+        /// Maximum number of calls to show.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum QueueCommand {
+    /// Queue a prompt to run in the current directory, or in `--repo`.
+    Add {
+        /// The prompt; read from stdin when neither it nor `--file` is given.
+        prompt: Option<String>,
+        /// Read the prompt from this file.
+        #[arg(long, conflicts_with = "prompt")]
+        file: Option<PathBuf>,
+        /// Run with this profile instead of the project's own.
+        #[arg(long)]
+        profile: Option<String>,
+        /// Run in this checkout.
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+    /// Show queued, running and finished tasks.
+    Status {
+        /// Maximum number of tasks to show, most recently queued last.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Run queued tasks one after another until the queue is empty.
+    Run {
+        /// Keep waiting for new tasks once the queue is empty.
+        #[arg(long)]
+        watch: bool,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum SkillCommand {
+    /// Install a skill from a git repository or the skill registry, or
+    /// without a source, every skill pinned in `.agents/skills.lock`.
+    Install {
+        /// Git URL or path of the repository, or a name in the registry.
+        source: Option<String>,
+        /// Branch, tag or commit to install instead of the default branch.
+        #[arg(long, requires = "source")]
+        rev: Option<String>,
+        /// Directory of the skill in the repository.
+        #[arg(long, requires = "source")]
+        path: Option<String>,
+        /// Install under this name instead of the directory's.
+        #[arg(long, requires = "source")]
+        name: Option<String>,
+        /// Refuse to install unless the skill's files have this SHA-256
+        /// checksum, as shown in the lock file.
+        #[arg(long, requires = "source")]
+        sha256: Option<String>,
+    },
+    /// Move installed skills to the latest commit of the revision they
+    /// were installed from.
+    Update {
+        /// Skills to update; all of them when none are given.
+        names: Vec<String>,
+    },
+    /// List the workspace's skills and whether they still match their
+    /// checksums.
+    List,
+    /// Remove an installed skill.
+    Remove { name: String },
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Mode {
     Tui,
     Gui,
+    Init,
+    InitAgents,
     Providers,
+    Doctor,
+    Sessions,
+    Db,
+    Audit,
+    Stats,
+    Sync,
+    Queue,
+    Batch,
+    Skill,
+    EventSchema,
 }
 
 impl Cli {
@@ -76,7 +352,19 @@ impl Cli {
         match &self.command {
             Some(Command::Tui { .. }) | None => Mode::Tui,
             Some(Command::Gui { .. }) => Mode::Gui,
-            Some(Command::Providers) => Mode::Providers,
+            Some(Command::Init) => Mode::Init,
+            Some(Command::InitAgents { .. }) => Mode::InitAgents,
+            Some(Command::Providers { .. }) => Mode::Providers,
+            Some(Command::Doctor) => Mode::Doctor,
+            Some(Command::Sessions { .. }) => Mode::Sessions,
+            Some(Command::Db { .. }) => Mode::Db,
+            Some(Command::Audit { .. }) => Mode::Audit,
+            Some(Command::Stats { .. }) => Mode::Stats,
+            Some(Command::Sync) => Mode::Sync,
+            Some(Command::Queue { .. }) => Mode::Queue,
+            Some(Command::Batch { .. }) => Mode::Batch,
+            Some(Command::Skill { .. }) => Mode::Skill,
+            Some(Command::EventSchema) => Mode::EventSchema,
         }
     }
 
@@ -88,8 +376,13 @@ impl Cli {
         }
     }
 
-    fn should_resume_session(&self) -> bool {
-        self.resume && matches!(self.mode(), Mode::Tui | Mode::Gui)
+    fn startup_session(&self) -> StartupSession {
+        match &self.resume {
+            Some(Some(session_id)) => StartupSession::Resume(session_id.clone()),
+            Some(None) => StartupSession::Pick,
+            None if self.continue_session => StartupSession::Continue,
+            None => StartupSession::New,
+        }
     }
 }
 
@@ -98,30 +391,243 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     let mode = cli.mode();
 
+    if mode == Mode::EventSchema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&agnt_core::event::json_schema())?
+        );
+        return Ok(());
+    }
+
     let _ = dotenvy::dotenv();
 
     // Install a panic hook that restores the terminal before printing the
-    // panic message, so the user isn't left with a broken terminal.
+    // panic message, so the user isn't left with a broken terminal. Panics
+    // also go to the trace log, with where they were raised, since the GUI
+    // recovers from some of them.
     let default_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
+        agnt_llm::trace::log("panic", info);
         tui::restore_terminal();
         default_hook(info);
     }));
 
     let db_path = agnt_app::session_db_path()?;
-    let store = Arc::new(Mutex::new(Store::open(db_path)?));
+    let store = Arc::new(Mutex::new(Store::open(&db_path)?));
+    let mut config = UserConfig::load()?;
+    i18n::init(config.locale.as_deref());
+    if config.encrypt_sessions {
+        let key = agnt_auth::load_session_key(KEYRING_SERVICE)?;
+        store
+            .lock()
+            .set_session_cipher(Some(SessionCipher::new(key)?));
+    }
+
+    if let Some(Command::Db { command }) = &cli.command {
+        return run_db_command(&mut store.lock(), &mut config, &db_path, command);
+    }
+
+    if let Some(Command::Audit { command }) = &cli.command {
+        return run_audit_command(&mut store.lock(), command);
+    }
+
+    let offline = cli.offline || config.offline;
+
+    if mode == Mode::Sync {
+        if offline {
+            return Err("agnt sync needs the network and is unavailable in offline mode".into());
+        }
+        let sync_url = config.sync_url.as_deref().ok_or_else(|| {
+            format!(
+                "no sync remote configured; set \"sync_url\" in {}",
+                agnt_app::config_path()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|_| "the agnt config file".to_string())
+            )
+        })?;
+        let summary = sync::run(&store, sync_url).await?;
+        println!(
+            "Pushed {} op(s), pulled {} op(s), applied {}.",
+            summary.pushed, summary.pulled, summary.applied
+        );
+        return Ok(());
+    }
+
+    if let Some(Command::Sessions { command }) = &cli.command {
+        let cwd = std::env::current_dir()?;
+        let session_store = SessionStore::open_for_project_root(Arc::clone(&store), &cwd)?;
+        return run_sessions_command(&session_store, command);
+    }
+
+    if let Some(Command::Skill { command }) = &cli.command {
+        return run_skill_command(&config, offline, command).await;
+    }
+
+    if let Some(Command::Queue { command }) = &cli.command {
+        match command {
+            QueueCommand::Add {
+                prompt,
+                file,
+                profile,
+                repo,
+            } => {
+                let source = match (prompt, file) {
+                    (Some(prompt), _) => queue::PromptSource::Text(prompt),
+                    (None, Some(file)) => queue::PromptSource::File(file),
+                    (None, None) => queue::PromptSource::Stdin,
+                };
+                return queue::add(
+                    &mut store.lock(),
+                    source,
+                    repo.as_deref(),
+                    profile.as_deref(),
+                );
+            }
+            QueueCommand::Status { limit } => return queue::status(&mut store.lock(), *limit),
+            // Needs the registry, set up below.
+            QueueCommand::Run { .. } => {}
+        }
+    }
 
     // Set up auth + registry.
-    let auth_manager = Arc::new(AuthManager::new("agnt", Arc::clone(&store)));
+    let auth_manager = Arc::new(AuthManager::new(KEYRING_SERVICE, Arc::clone(&store)));
     let mut registry = Registry::new();
     registry.set_auth_resolver(auth_manager.resolver());
+    registry.set_http_settings(provider_http_settings(&config.provider_http));
     agnt_llm_openai::register(&mut registry);
     agnt_llm_codex::register(&mut registry);
-    registry.fetch_spec().await?;
+    agnt_llm_xai::register(&mut registry);
+    agnt_llm_mistral::register(&mut registry);
+    for (provider_id, endpoint) in &config.provider_endpoints {
+        registry.set_api_endpoint(provider_id, endpoint);
+    }
+
+    if mode == Mode::Doctor {
+        let cwd = std::env::current_dir()?;
+        let settings =
+            SessionStore::open_for_project_root(Arc::clone(&store), &cwd)?.project_settings()?;
+        let (provider_id, _) = default_model(&settings, &config);
+        return doctor::run(
+            &mut registry,
+            &auth_manager,
+            &store,
+            &db_path,
+            &provider_id,
+            offline,
+        )
+        .await;
+    }
+
+    if let Some(Command::Stats { days, by, format }) = &cli.command {
+        // Prices come from models.dev; without them only costs are missing.
+        let registry = match offline::load_spec(&mut registry, offline).await {
+            Ok(()) => Some(&registry),
+            Err(err) => {
+                eprintln!("could not load model prices, costs are omitted: {err}");
+                None
+            }
+        };
+        return stats::run(&mut store.lock(), registry, *days, *by, *format);
+    }
+
+    // The TUI and GUI start without waiting for models.dev where they can;
+    // everything else needs the fresh catalog up front.
+    let mut spec_load = if matches!(mode, Mode::Tui | Mode::Gui) {
+        offline::SpecLoad::start(&mut registry, offline)?
+    } else {
+        offline::load_spec(&mut registry, offline).await?;
+        offline::SpecLoad::Ready
+    };
 
     if mode == Mode::Providers {
-        print_providers(&registry);
-        return Ok(());
+        let Some(Command::Providers {
+            configured,
+            provider,
+            capability,
+            check,
+            json,
+        }) = cli.command.clone()
+        else {
+            // The hidden `--providers` alias.
+            let filter = providers::ProviderFilter {
+                configured: true,
+                provider_id: None,
+                capability: None,
+            };
+            return providers::run(&registry, &auth_manager, &filter, false, false, offline).await;
+        };
+        let filter = providers::ProviderFilter {
+            configured,
+            provider_id: provider,
+            capability,
+        };
+        return providers::run(&registry, &auth_manager, &filter, check, json, offline).await;
+    }
+
+    if let Some(Command::Queue {
+        command: QueueCommand::Run { watch },
+    }) = &cli.command
+    {
+        let options = headless::RunOptions {
+            config: &config,
+            offline,
+            read_only: cli.read_only,
+        };
+        return queue::run(&store, &mut registry, &auth_manager, &options, *watch).await;
+    }
+
+    if let Some(Command::Batch {
+        repos,
+        prompt,
+        jobs,
+        profile,
+    }) = &cli.command
+    {
+        if let Some(profile) = profile
+            && !project_settings::PROFILES.contains(&profile.as_str())
+        {
+            return Err(format!(
+                "unknown profile `{profile}` (expected one of: {})",
+                project_settings::PROFILES.join(", ")
+            )
+            .into());
+        }
+        let options = headless::RunOptions {
+            config: &config,
+            offline,
+            read_only: cli.read_only,
+        };
+        let batch = batch::Batch {
+            repos_file: repos,
+            prompt_file: prompt,
+            jobs: *jobs,
+            profile: profile.as_deref(),
+        };
+        return batch::run(&store, &mut registry, &auth_manager, &options, &batch).await;
+    }
+
+    if let Some(Command::InitAgents { yes }) = &cli.command {
+        let options = headless::RunOptions {
+            config: &config,
+            offline,
+            read_only: true,
+        };
+        let cwd = std::env::current_dir()?;
+        return init_agents::run(&store, &mut registry, &auth_manager, &options, &cwd, *yes).await;
+    }
+
+    if mode == Mode::Init {
+        return init::run(&mut registry, &auth_manager, &mut config).await;
+    }
+
+    // First launch of the TUI or GUI: walk through setup instead of prompting
+    // for credentials of a provider the user never picked. Subcommands like
+    // `sessions` or `stats` run without it.
+    if matches!(mode, Mode::Tui | Mode::Gui) && !UserConfig::exists()? && io::stdin().is_terminal()
+    {
+        spec_load.finish(&mut registry).await?;
+        init::run(&mut registry, &auth_manager, &mut config).await?;
+        println!();
     }
 
     if mode != Mode::Providers
@@ -131,65 +637,479 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let cwd = std::env::current_dir()?;
+    // Index the project's files for `@` mentions while the rest starts up.
+    let typeahead = (mode == Mode::Tui).then(TypeaheadState::new_for_current_project);
     let session_store = SessionStore::open_for_project_root(Arc::clone(&store), &cwd)?;
     let session_store: SharedSessionStore = Arc::new(Mutex::new(session_store));
+    SessionStore::spawn_lock_heartbeat(&session_store);
 
-    let mut restored_state = if cli.should_resume_session() {
-        session_store.lock().resume_most_recent_session()?
-    } else {
-        None
-    };
+    let startup_session = cli.startup_session();
+    let mut restored_state = session_store
+        .lock()
+        .open_startup_session(&startup_session)?;
+
+    let mut settings = session_store.lock().project_settings()?;
+    let mut reasoning_effort = project_settings::reasoning_effort(&settings).to_string();
+    // A resumed session continues on the model it was using, not whatever
+    // the default has become since.
+    if let Some(model) = session_store.lock().active_session_model()? {
+        settings.default_model = Some(format!("{}/{}", model.provider_id, model.model_id));
+        if let Some(effort) = model.reasoning_effort {
+            reasoning_effort = effort;
+        }
+    }
+    let (provider_id, model_id) = default_model(&settings, &config);
+    {
+        let mut session_store = session_store.lock();
+        session_store.set_model(&provider_id, model_id, &reasoning_effort);
+        session_store.set_jj_change_per_turn(config.jj_change_per_turn);
+        session_store.set_snapshot_file_contents(config.snapshot_file_contents);
+        session_store.set_webhooks(webhooks::Webhooks::new(&config, offline));
+    }
+
+    if offline {
+        offline::ensure_local_provider(&registry, &provider_id)?;
+    }
+
+    // A model newer than the cached catalog needs the fresh one.
+    if spec_load.is_cached() && registry.model_spec(&provider_id, model_id).is_none() {
+        spec_load.refetch();
+    }
+    // Credentials are resolved, or asked for, while the catalog is fetched,
+    // unless the provider is only known from the catalog.
+    if registry.auth_request(&provider_id).is_none() {
+        spec_load.finish(&mut registry).await?;
+    }
+    let (fetched, credentials) = tokio::join!(
+        spec_load.fetched(),
+        ensure_provider_credentials(&registry, &auth_manager, &provider_id)
+    );
+    credentials?;
+    if let Some(spec) = fetched? {
+        offline::load_fetched_spec(&mut registry, spec)?;
+    }
+
+    // The budget's prices come from the catalog.
+    session_store
+        .lock()
+        .set_budget(budget::BudgetTracker::new(&registry, &config, &provider_id));
+    // Going past the budget needs a person to confirm it.
+    if let Some(status) = session_store.lock().budget_status()?
+        && status.is_exceeded()
+        && !io::stdin().is_terminal()
+    {
+        return Err(format!("{}; refusing to run without a terminal", status.message()).into());
+    }
+
+    let read_only = cli.read_only
+        || settings
+            .profile
+            .as_deref()
+            .and_then(|profile| config.profiles.get(profile))
+            .is_some_and(|profile| profile.read_only);
 
     if mode == Mode::Gui {
-        ensure_provider_credentials(&registry, &auth_manager, DEFAULT_PROVIDER_ID).await?;
-        let agent = build_default_agent(&mut registry, restored_state.take())?;
-        gui::launch(agent, session_store);
+        let agent = build_default_agent(
+            &mut registry,
+            &settings,
+            &headless::RunOptions {
+                config: &config,
+                offline,
+                read_only,
+            },
+            &cwd,
+            &reasoning_effort,
+            restored_state.take(),
+        )?;
+        summaries::summarize_left_sessions(&agent, &session_store);
+        gui::launch(
+            agent,
+            session_store,
+            gui::GuiOptions {
+                pick_session: startup_session == StartupSession::Pick,
+                quick_prompt_hotkey: config.quick_prompt_hotkey.clone(),
+                tray_icon: config.tray_icon,
+                offline,
+                request_guard: RequestGuard::new(&registry, &config, &provider_id, model_id),
+                spec_refresh: SpecRefresh::start(
+                    &registry,
+                    &config,
+                    offline,
+                    spec_load.is_cached(),
+                ),
+                compare_models: CompareModels::new(registry, offline, &reasoning_effort),
+            },
+        );
         return Ok(());
     }
 
-    ensure_provider_credentials(&registry, &auth_manager, DEFAULT_PROVIDER_ID).await?;
-    let agent = build_default_agent(&mut registry, restored_state.take())?;
-    let mut app = App::new(agent, session_store);
+    let agent = build_default_agent(
+        &mut registry,
+        &settings,
+        &headless::RunOptions {
+            config: &config,
+            offline,
+            read_only,
+        },
+        &cwd,
+        &reasoning_effort,
+        restored_state.take(),
+    )?;
+    summaries::summarize_left_sessions(&agent, &session_store);
+    let mut app = App::new(
+        agent,
+        session_store,
+        typeahead.unwrap_or_else(TypeaheadState::new_for_current_project),
+    );
+    app.offline = offline;
+    app.request_guard = RequestGuard::new(&registry, &config, &provider_id, model_id);
+    // A catalog read from the cache is refreshed in the background while
+    // the UI comes up.
+    app.spec_refresh = SpecRefresh::start(&registry, &config, offline, spec_load.is_cached());
+    app.compare_models = Some(CompareModels::new(registry, offline, &reasoning_effort));
+    if startup_session == StartupSession::Pick {
+        app.open_resume_dialog();
+    }
     tui::launch(&mut app).await
 }
 
-fn print_providers(registry: &Registry) {
-    for provider in registry
-        .known_providers()
-        .into_iter()
-        .filter(|provider| provider.configured)
-    {
-        let compat = if provider.compatible {
-            "compatible"
-        } else {
-            "no-factory"
-        };
-        println!(
-            "{} ({}) [{} | configured | {}]",
-            provider.id, provider.name, provider.auth_method, compat
-        );
+fn run_sessions_command(
+    session_store: &SessionStore,
+    command: &SessionsCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        SessionsCommand::Search { query, limit } => {
+            let hits = session_store.search_turns(query, *limit)?;
+            if hits.is_empty() {
+                println!("No matches for \"{query}\".");
+                return Ok(());
+            }
 
-        let mut models = registry.list_models(&provider.id);
-        models.sort_by(|a, b| a.id.cmp(&b.id));
-        for model in &models {
-            let name = model.name.as_deref().unwrap_or("");
-            println!("  {:<30} {}", model.id, name);
+            for hit in &hits {
+                let title = hit
+                    .session_title
+                    .as_deref()
+                    .unwrap_or(t(Msg::UntitledSession));
+                println!("{title} [{} / {}]", hit.session_id, hit.turn_id);
+                let snippet = hit.snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+                println!("  {snippet}");
+            }
+            println!();
+            println!("Open a hit with /history in the TUI or GUI.");
+        }
+    }
+    Ok(())
+}
+
+async fn run_skill_command(
+    config: &UserConfig,
+    offline: bool,
+    command: &SkillCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root = agnt_core::agent::find_workspace_root(&std::env::current_dir()?);
+    let sources = skills::Sources {
+        registry: config.skill_registry.as_deref(),
+        offline,
+    };
+    match command {
+        SkillCommand::Install {
+            source,
+            rev,
+            path,
+            name,
+            sha256,
+        } => {
+            let request = source.as_deref().map(|source| skills::InstallRequest {
+                source,
+                rev: rev.as_deref(),
+                path: path.as_deref(),
+                name: name.as_deref(),
+                sha256: sha256.as_deref(),
+            });
+            skills::install(&root, &sources, request.as_ref()).await
+        }
+        SkillCommand::Update { names } => skills::update(&root, &sources, names).await,
+        SkillCommand::List => skills::list(&root),
+        SkillCommand::Remove { name } => skills::remove(&root, name),
+    }
+}
+
+fn run_audit_command(
+    store: &mut Store,
+    command: &AuditCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        AuditCommand::Show { session, limit } => {
+            let audit_log = store.audit_log();
+            let entries = audit_log.entries(session.as_deref(), *limit)?;
+            if entries.is_empty() {
+                println!("No tool calls recorded.");
+            }
+            for entry in &entries {
+                let outcome = match (entry.succeeded, entry.exit_code) {
+                    (_, Some(code)) => format!("exit {code}"),
+                    (true, None) => "ok".to_string(),
+                    (false, None) => "failed".to_string(),
+                };
+                println!(
+                    "#{} {} {} ({outcome}, {}) args {} entry {}",
+                    entry.seq,
+                    entry.time,
+                    entry.tool_name,
+                    entry.approval,
+                    &entry.arguments_hash[..12],
+                    &entry.hash[..12]
+                );
+                println!(
+                    "  {} [{}]",
+                    entry.project_root.display(),
+                    entry.session_id.as_deref().unwrap_or("no session")
+                );
+                for file in &entry.files {
+                    println!("  {file}");
+                }
+            }
+            println!();
+            match audit_log.verify()? {
+                None => println!("Hash chain intact."),
+                Some(seq) => {
+                    return Err(format!(
+                        "audit log was tampered with: hash chain broken at entry #{seq}"
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_db_command(
+    store: &mut Store,
+    config: &mut UserConfig,
+    db_path: &std::path::Path,
+    command: &DbCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        DbCommand::Stats => {
+            let stats = store.maintenance().stats()?;
+            println!("{}", db_path.display());
+            println!(
+                "  {} total, {} free, journal mode {}, encryption {}",
+                format_bytes(stats.total_bytes),
+                format_bytes(stats.free_bytes),
+                stats.journal_mode,
+                if config.encrypt_sessions { "on" } else { "off" }
+            );
+            println!();
+            for table in &stats.tables {
+                println!("  {:<30} {:>10}", table.name, format_bytes(table.bytes));
+            }
+        }
+        DbCommand::Vacuum => {
+            let before = store.maintenance().stats()?.total_bytes;
+            store.maintenance().vacuum()?;
+            let after = store.maintenance().stats()?.total_bytes;
+            println!(
+                "Vacuumed {}: {} -> {}",
+                db_path.display(),
+                format_bytes(before),
+                format_bytes(after)
+            );
+        }
+        DbCommand::Prune {
+            older_than_days,
+            keep,
+        } => {
+            if older_than_days.is_none() && keep.is_none() {
+                return Err("pass --older-than-days and/or --keep to choose what to prune".into());
+            }
+            let policy = RetentionPolicy {
+                max_age: older_than_days.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+                max_sessions_per_project: *keep,
+            };
+            let deleted = store.maintenance().apply_retention(&policy)?;
+            println!("Deleted {deleted} session(s). Run `agnt db vacuum` to reclaim the space.");
+        }
+        DbCommand::Encrypt => {
+            if !store.has_session_cipher() {
+                let key = agnt_auth::load_or_create_session_key(KEYRING_SERVICE)?;
+                store.set_session_cipher(Some(SessionCipher::new(key)?));
+            }
+            let rewritten = store.maintenance().encrypt_existing()?;
+            // Drop the plaintext left behind in free pages.
+            store.maintenance().vacuum()?;
+            config.encrypt_sessions = true;
+            config.save()?;
+            println!(
+                "Encrypted {rewritten} row(s). New sessions will be encrypted; they are no longer searchable."
+            );
+        }
+        DbCommand::Decrypt => {
+            if !store.has_session_cipher() {
+                let key = agnt_auth::load_session_key(KEYRING_SERVICE)?;
+                store.set_session_cipher(Some(SessionCipher::new(key)?));
+            }
+            let rewritten = store.maintenance().decrypt_existing()?;
+            store.set_session_cipher(None);
+            config.encrypt_sessions = false;
+            config.save()?;
+            println!("Decrypted {rewritten} row(s). New sessions will be stored in plaintext.");
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// HTTP client settings for providers: the configured ones over the
+/// defaults. `0` turns a timeout off; the client's own defaults stay for the
+/// rest.
+fn provider_http_settings(config: &ProviderHttpConfig) -> HttpSettings {
+    let defaults = HttpSettings::default();
+    let pick =
+        |secs: Option<u64>, default: Option<Duration>| secs.map(Duration::from_secs).or(default);
+    HttpSettings {
+        connect_timeout: pick(config.connect_timeout_secs, defaults.connect_timeout),
+        read_timeout: pick(config.read_timeout_secs, defaults.read_timeout),
+        pool_idle_timeout: pick(config.idle_timeout_secs, defaults.pool_idle_timeout),
+        tcp_keepalive: pick(config.tcp_keepalive_secs, defaults.tcp_keepalive),
+        stream_idle_timeout: pick(
+            config.stream_idle_timeout_secs,
+            defaults.stream_idle_timeout,
+        ),
+    }
+}
+
+/// The provider, with the project's account for it, and the model to start
+/// with: the project's default model when set, then the one chosen in
+/// `agnt init`, then the built-in default.
+fn default_model<'a>(settings: &'a ProjectSettings, config: &'a UserConfig) -> (String, &'a str) {
+    let (provider_id, model_id) = settings
+        .default_model
+        .as_deref()
+        .or(config.default_model.as_deref())
+        .and_then(project_settings::split_model)
+        .unwrap_or((DEFAULT_PROVIDER_ID, DEFAULT_MODEL_ID));
+    (
+        project_settings::with_account(settings, provider_id),
+        model_id,
+    )
+}
+
+/// Register the `agnt-tool-*` executables on `PATH` and those in the
+/// config, then the WASM components in the workspace's `.agnt/tools` that
+/// the project's `wasm_tools` setting approves, as tools. In read-only mode
+/// only those that can't change the workspace are kept.
+fn load_tool_plugins(
+    agent: &mut agnt_core::Agent,
+    config: &UserConfig,
+    settings: &ProjectSettings,
+    cwd: &Path,
+    read_only: bool,
+) {
+    for path in agnt_core::tools::plugin_paths(&config.tool_plugins) {
+        let loaded = agnt_core::PluginTool::load(&path, cwd.to_path_buf()).and_then(|tool| {
+            if read_only && !tool.read_only() {
+                return Ok(());
+            }
+            agent.plugin_tool(tool).map(|_| ())
+        });
+        if let Err(err) = loaded {
+            eprintln!("skipping tool plugin {}: {err}", path.display());
+        }
+    }
+
+    let root = agnt_core::agent::find_workspace_root(cwd);
+    for path in agnt_core::tools::wasm_tool_paths(&root) {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let Some(grant) = project_settings::wasm_tool_grant(settings, &name) else {
+            eprintln!(
+                "skipping WASM tool {}: not approved; add `{name}: none` to wasm_tools in the project settings to load it",
+                path.display()
+            );
+            continue;
+        };
+        let loaded = agnt_core::WasmTool::load(&path, root.clone(), &grant).and_then(|tool| {
+            if read_only && !tool.read_only() {
+                return Ok(());
+            }
+            agent.wasm_tool(tool).map(|_| ())
+        });
+        if let Err(err) = loaded {
+            eprintln!("skipping WASM tool {}: {err}", path.display());
         }
     }
 }
 
 fn build_default_agent(
     registry: &mut Registry,
+    settings: &ProjectSettings,
+    options: &headless::RunOptions<'_>,
+    cwd: &Path,
+    reasoning_effort: &str,
     restored_state: Option<agnt_core::ConversationState>,
 ) -> Result<agnt_core::Agent, Box<dyn std::error::Error>> {
-    let model = registry.model(DEFAULT_PROVIDER_ID, DEFAULT_MODEL_ID)?;
-    let cwd = std::env::current_dir()?;
-    let mut agent = agnt_core::Agent::with_defaults(model, cwd);
+    let config = options.config;
+    let (provider_id, model_id) = default_model(settings, config);
+    let model = registry.model(&provider_id, model_id)?;
+    for notice in registry.take_deprecation_notices() {
+        eprintln!("{notice}");
+    }
+    let mut agent_settings = project_settings::agent_settings(settings);
+    if let Some(profile) = settings
+        .profile
+        .as_deref()
+        .and_then(|profile| config.profiles.get(profile))
+    {
+        agent_settings.resource_limits = agnt_core::ResourceLimits {
+            max_concurrent_commands: profile.max_concurrent_commands,
+            max_command_cpu: profile.max_command_cpu_secs.map(Duration::from_secs),
+            max_write_bytes: profile.max_write_bytes,
+        };
+    }
+    if options.read_only {
+        agent_settings.approval_policy = agnt_core::ApprovalPolicy::ReadOnly;
+    }
+    agent_settings.skip_command_checks = config.skip_command_checks;
+    let mut agent = agnt_core::Agent::with_settings(model, cwd.to_path_buf(), &agent_settings);
+    let read_only = agent_settings.approval_policy == agnt_core::ApprovalPolicy::ReadOnly;
+    load_tool_plugins(&mut agent, config, settings, cwd, read_only);
+    agent.cache_tool_results(config.cache_tool_results);
+    if let Some(max) = config.max_continuations {
+        agent.max_continuations(max);
+    }
 
-    use agnt_llm_openai::{OpenAIRequestExt, ReasoningEffort, ReasoningSummary};
-    agent.configure_request(|req| {
-        req.reasoning_effort(ReasoningEffort::High);
+    use agnt_llm_openai::{OpenAIRequestExt, ReasoningSummary};
+    let reasoning_effort = openai_reasoning_effort(reasoning_effort);
+    let background = config.background_responses;
+    // OpenAI's hosted tools reach the network from OpenAI's side.
+    let web_search = config.web_search && !options.offline;
+    let code_interpreter = config.code_interpreter && !options.offline;
+    let image_generation = config.image_generation && !options.offline;
+    agent.configure_request(move |req| {
+        req.reasoning_effort(reasoning_effort);
         req.reasoning_summary(ReasoningSummary::Detailed);
+        req.background(background);
+        req.web_search(web_search);
+        req.code_interpreter(code_interpreter);
+        req.image_generation(image_generation);
     });
 
     if let Some(state) = restored_state {
@@ -199,6 +1119,18 @@ fn build_default_agent(
     Ok(agent)
 }
 
+/// The reasoning effort named `effort`; `high` for names it doesn't know.
+fn openai_reasoning_effort(effort: &str) -> agnt_llm_openai::ReasoningEffort {
+    use agnt_llm_openai::ReasoningEffort;
+    match effort {
+        "none" => ReasoningEffort::None,
+        "minimal" => ReasoningEffort::Minimal,
+        "low" => ReasoningEffort::Low,
+        "medium" => ReasoningEffort::Medium,
+        _ => ReasoningEffort::High,
+    }
+}
+
 async fn ensure_provider_credentials(
     registry: &Registry,
     auth: &Arc<AuthManager>,
@@ -207,19 +1139,34 @@ async fn ensure_provider_credentials(
     let Some(request) = registry.auth_request(provider_id) else {
         return Ok(());
     };
+    // Credentials are stored per account, e.g. under `openai@work`.
+    let key = request.credential_key();
+    let provider_id = key.as_str();
 
     match request.auth_method {
-        AuthMethod::ApiKey(_) => {
+        AuthMethod::ApiKey(ref config) => {
             if auth.resolve_cached(&request)?.is_some() {
                 return Ok(());
             }
 
-            let prompt = format!("Enter API key for {}: ", request.provider_name);
+            let prompt = format!(
+                "Enter API key for {} (or run `agnt init` to choose another provider): ",
+                request.provider_name
+            );
             let value = rpassword::prompt_password(prompt)?;
             if value.trim().is_empty() {
                 return Err(format!("no API key provided for {}", request.provider_name).into());
             }
-            auth.store_api_key(provider_id, value)?;
+            // Values such as the OpenAI organization to bill belong to the
+            // key, so they are asked for along with it.
+            let mut metadata = HashMap::new();
+            for field in &config.fields {
+                let field_value = prompt_line(&format!("{} (optional): ", field.label))?;
+                if !field_value.is_empty() {
+                    metadata.insert(field.key.clone(), field_value);
+                }
+            }
+            auth.store_api_key(provider_id, value, metadata)?;
         }
         AuthMethod::OAuthPkce(ref config) => {
             match auth.refresh_oauth_if_needed(provider_id, config).await {
@@ -249,6 +1196,19 @@ async fn ensure_provider_credentials(
             auth.complete_oauth(provider_id, config, &pending, &authorization_input)
                 .await?;
         }
+        AuthMethod::GoogleAdc(ref config) => {
+            if auth
+                .refresh_google_adc(provider_id, config)
+                .await?
+                .is_none()
+            {
+                return Err(format!(
+                    "no Google application default credentials found for {}; set GOOGLE_APPLICATION_CREDENTIALS or run `gcloud auth application-default login`",
+                    request.provider_name
+                )
+                .into());
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,153 @@
+//! Offline mode: no network access except to providers on local endpoints.
+
+use std::net::IpAddr;
+
+use agnt_llm_registry::{ModelsDevSpec, Registry};
+use tokio::task::JoinHandle;
+use url::{Host, Url};
+
+/// Load the models.dev spec: fetched and cached when online, read from the
+/// cache when offline. Offline without a cache, only providers with their
+/// own model list are usable.
+pub async fn load_spec(
+    registry: &mut Registry,
+    offline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if offline {
+        return load_cached_spec_offline(registry);
+    }
+    let spec = Registry::fetch_remote_spec().await?;
+    load_fetched_spec(registry, spec)
+}
+
+/// The models.dev catalog of a TUI or GUI session, loaded without waiting
+/// for the network where it can be: from the cache, with the fresh copy
+/// fetched once the UI is up, or fetched in the background while the rest
+/// of startup runs when there is no cache yet.
+pub enum SpecLoad {
+    /// Loaded: fetched, or read from the cache in offline mode.
+    Ready,
+    /// Loaded from the cache, so possibly out of date.
+    Cached,
+    /// Being fetched, with nothing loaded yet.
+    Fetching(JoinHandle<Result<ModelsDevSpec, agnt_llm_registry::Error>>),
+}
+
+impl SpecLoad {
+    pub fn start(
+        registry: &mut Registry,
+        offline: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if offline {
+            load_cached_spec_offline(registry)?;
+            return Ok(Self::Ready);
+        }
+        // An unreadable cache is only a reason to wait for the network.
+        let cached = std::fs::read_to_string(agnt_app::model_spec_cache_path()?)
+            .ok()
+            .is_some_and(|raw| registry.load_spec_from_str(&raw).is_ok());
+        Ok(if cached { Self::Cached } else { Self::fetch() })
+    }
+
+    fn fetch() -> Self {
+        Self::Fetching(tokio::spawn(Registry::fetch_remote_spec()))
+    }
+
+    /// Whether the loaded catalog came from the cache and should be
+    /// refreshed.
+    pub fn is_cached(&self) -> bool {
+        matches!(self, Self::Cached)
+    }
+
+    /// Fetch the fresh catalog now after all, e.g. because the cached one
+    /// lacks the model to start with.
+    pub fn refetch(&mut self) {
+        if self.is_cached() {
+            *self = Self::fetch();
+        }
+    }
+
+    /// The catalog being fetched, once it arrives; `None` when no fetch
+    /// runs. Unlike [`finish`](Self::finish) this leaves the registry
+    /// free to use in the meantime.
+    pub async fn fetched(&mut self) -> Result<Option<ModelsDevSpec>, Box<dyn std::error::Error>> {
+        let Self::Fetching(task) = self else {
+            return Ok(None);
+        };
+        let spec = task.await??;
+        *self = Self::Ready;
+        Ok(Some(spec))
+    }
+
+    /// Wait for the fetch, if one runs, and load what it got.
+    pub async fn finish(
+        &mut self,
+        registry: &mut Registry,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(spec) = self.fetched().await? {
+            load_fetched_spec(registry, spec)?;
+        }
+        Ok(())
+    }
+}
+
+/// Load a freshly fetched catalog and cache it for offline runs.
+pub fn load_fetched_spec(
+    registry: &mut Registry,
+    spec: ModelsDevSpec,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cache_path = agnt_app::model_spec_cache_path()?;
+    // The cache only matters for later offline runs; failing to write it
+    // shouldn't stop this one.
+    if let Ok(raw) = serde_json::to_string(&spec) {
+        let _ = std::fs::write(&cache_path, raw);
+    }
+    registry.load_spec(spec);
+    Ok(())
+}
+
+fn load_cached_spec_offline(registry: &mut Registry) -> Result<(), Box<dyn std::error::Error>> {
+    match std::fs::read_to_string(agnt_app::model_spec_cache_path()?) {
+        Ok(raw) => registry.load_spec_from_str(&raw)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => eprintln!(
+            "offline mode: no cached models.dev catalog, only providers with built-in model lists are available"
+        ),
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+/// Refuse to use a provider whose API isn't on this machine.
+pub fn ensure_local_provider(
+    registry: &Registry,
+    provider_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match registry.api_endpoint(provider_id) {
+        Some(endpoint) if is_local_endpoint(&endpoint) => Ok(()),
+        Some(endpoint) => Err(format!(
+            "offline mode: {provider_id} sends requests to {endpoint}, which is not a local endpoint; \
+             point it at a local server with \"provider_endpoints\" in the config file or pick a local model"
+        )
+        .into()),
+        None => Err(format!(
+            "offline mode: no API endpoint known for {provider_id}; \
+             set one with \"provider_endpoints\" in the config file"
+        )
+        .into()),
+    }
+}
+
+/// Whether `endpoint` points at the loopback interface.
+pub fn is_local_endpoint(endpoint: &str) -> bool {
+    let Ok(url) = Url::parse(endpoint) else {
+        return false;
+    };
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            domain.eq_ignore_ascii_case("localhost") || domain.ends_with(".localhost")
+        }
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip).is_loopback(),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip).is_loopback(),
+        None => false,
+    }
+}
@@ -0,0 +1,227 @@
+//! The command palette (Ctrl+K): slash commands, recent sessions and
+//! settings toggles behind one fuzzy-filtered list, shared by both UIs.
+
+use agnt_app::UserConfig;
+use agnt_core::Agent;
+use agnt_db::Session;
+
+use crate::session::{SessionStore, session_label};
+use crate::typeahead::{Command, TypeaheadItem};
+
+/// Settings that can be flipped from the palette; each takes effect right
+/// away and is saved to the user config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggle {
+    CacheToolResults,
+    JjChangePerTurn,
+    SnapshotFileContents,
+}
+
+impl Toggle {
+    pub const ALL: [Toggle; 3] = [
+        Toggle::CacheToolResults,
+        Toggle::JjChangePerTurn,
+        Toggle::SnapshotFileContents,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Toggle::CacheToolResults => "Cache tool results",
+            Toggle::JjChangePerTurn => "jj change per turn",
+            Toggle::SnapshotFileContents => "Snapshot file contents",
+        }
+    }
+
+    pub fn value(self, config: &UserConfig) -> bool {
+        match self {
+            Toggle::CacheToolResults => config.cache_tool_results,
+            Toggle::JjChangePerTurn => config.jj_change_per_turn,
+            Toggle::SnapshotFileContents => config.snapshot_file_contents,
+        }
+    }
+
+    fn set(self, config: &mut UserConfig, enabled: bool) {
+        match self {
+            Toggle::CacheToolResults => config.cache_tool_results = enabled,
+            Toggle::JjChangePerTurn => config.jj_change_per_turn = enabled,
+            Toggle::SnapshotFileContents => config.snapshot_file_contents = enabled,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PaletteAction {
+    Command(Command),
+    Resume(String),
+    Toggle(Toggle),
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub title: String,
+    pub action: PaletteAction,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteState {
+    entries: Vec<PaletteEntry>,
+    pub query: String,
+    /// Indices into `entries` matching `query`, best match first.
+    matches: Vec<usize>,
+    pub selected_index: usize,
+}
+
+impl PaletteState {
+    pub fn new(entries: Vec<PaletteEntry>) -> Self {
+        let mut state = Self {
+            entries,
+            query: String::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        state.refilter();
+        state
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        if self.query != query {
+            self.query = query.to_string();
+            self.refilter();
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn move_selection(&mut self, direction: i32) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.selected_index = if direction < 0 {
+            self.selected_index
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1)
+        } else {
+            (self.selected_index + 1) % self.matches.len()
+        };
+    }
+
+    pub fn matches(&self) -> impl Iterator<Item = &PaletteEntry> {
+        self.matches.iter().map(|&index| &self.entries[index])
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn selected(&self) -> Option<&PaletteEntry> {
+        self.matches
+            .get(self.selected_index)
+            .map(|&index| &self.entries[index])
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                fuzzy_score(&self.query, &entry.title).map(|score| (score, index))
+            })
+            .collect();
+        // Stable, so equally good matches keep their listed order.
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected_index = 0;
+    }
+}
+
+/// The palette's entries: every command, then the most recent sessions
+/// other than the active one, then the settings toggles with their current
+/// values.
+pub fn build_entries(
+    commands: Vec<Command>,
+    sessions: Vec<Session>,
+    config: &UserConfig,
+) -> Vec<PaletteEntry> {
+    let commands = commands.into_iter().map(|command| PaletteEntry {
+        title: match command.description() {
+            Some(description) => format!("/{} — {description}", command.token_text()),
+            None => format!("/{}", command.token_text()),
+        },
+        action: PaletteAction::Command(command),
+    });
+    let sessions = sessions.into_iter().map(|session| PaletteEntry {
+        title: format!("Resume: {}", session_label(&session)),
+        action: PaletteAction::Resume(session.id),
+    });
+    let toggles = Toggle::ALL.into_iter().map(|toggle| PaletteEntry {
+        title: format!(
+            "Setting: {}: {}",
+            toggle.label(),
+            if toggle.value(config) { "on" } else { "off" }
+        ),
+        action: PaletteAction::Toggle(toggle),
+    });
+    commands.chain(sessions).chain(toggles).collect()
+}
+
+/// Flip `toggle` in the user config and in the running agent and session
+/// store. Returns the note to show in the conversation.
+pub fn apply_toggle(toggle: Toggle, agent: &mut Agent, store: &mut SessionStore) -> String {
+    let mut config = match UserConfig::load() {
+        Ok(config) => config,
+        Err(err) => return format!("[config error: {err}]"),
+    };
+    let enabled = !toggle.value(&config);
+    toggle.set(&mut config, enabled);
+    if let Err(err) = config.save() {
+        return format!("[config error: {err}]");
+    }
+
+    match toggle {
+        Toggle::CacheToolResults => {
+            agent.cache_tool_results(enabled);
+        }
+        Toggle::JjChangePerTurn => store.set_jj_change_per_turn(enabled),
+        Toggle::SnapshotFileContents => store.set_snapshot_file_contents(enabled),
+    }
+    format!(
+        "[{}: {}]",
+        toggle.label(),
+        if enabled { "on" } else { "off" }
+    )
+}
+
+/// Score `text` against `query` as a case-insensitive subsequence; `None`
+/// if some query character is missing. Consecutive characters and matches
+/// at the start of a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last_match: Option<usize> = None;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[position..]
+            .iter()
+            .position(|c| c.to_lowercase().eq(wanted.to_lowercase()))?;
+        let found = position + offset;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if last_match.is_some_and(|last| last + 1 == found) {
+            score += 4;
+        }
+        last_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
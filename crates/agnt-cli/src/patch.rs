@@ -0,0 +1,185 @@
+//! Export the file changes made in a session as a patch series: one mail
+//! per turn for `git am`, which `git apply` also takes as a whole.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use agnt_core::attachment_summary;
+use agnt_db::Turn;
+use agnt_llm::{AssistantPart, ToolDisplayBodyPart, UserPart};
+
+use crate::session::SessionStore;
+
+const SUBJECT_MAX_CHARS: usize = 72;
+/// Marker `edit` puts in the diff of a move without content changes.
+const NO_CONTENT_CHANGES: &str = " (no content changes)";
+
+/// A patch series written by [`export_active_session`].
+pub struct ExportedSeries {
+    pub path: PathBuf,
+    /// One per turn that changed files.
+    pub patch_count: usize,
+}
+
+impl ExportedSeries {
+    /// What to tell the user after exporting.
+    pub fn summary(&self) -> String {
+        let path = self.path.display();
+        format!(
+            "[{} patch(es) written to {path}; `git apply {path}` applies them all, `git am {path}` commits each turn on the current branch]",
+            self.patch_count
+        )
+    }
+}
+
+/// Write the file changes of the active session's turns to the user data
+/// dir. `None` when no turn changed a file.
+pub fn export_active_session(
+    store: &SessionStore,
+) -> Result<Option<ExportedSeries>, Box<dyn std::error::Error>> {
+    let Some(session_id) = store.active_session_id() else {
+        return Ok(None);
+    };
+    let turns = store.active_turns()?;
+    let Some((series, patch_count)) = format_series(session_id, &turns) else {
+        return Ok(None);
+    };
+
+    let dir = agnt_app::ensure_user_data_dir()?.join("patches");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{session_id}.patch"));
+    std::fs::write(&path, series)?;
+    Ok(Some(ExportedSeries { path, patch_count }))
+}
+
+/// One mail per turn that changed files, in order, and how many there are.
+fn format_series(session_id: &str, turns: &[Turn]) -> Option<(String, usize)> {
+    let patches = turns
+        .iter()
+        .filter_map(|turn| {
+            let diffs = turn_diffs(turn);
+            (!diffs.is_empty()).then_some((turn, diffs))
+        })
+        .collect::<Vec<_>>();
+    if patches.is_empty() {
+        return None;
+    }
+
+    let total = patches.len();
+    let mut series = String::new();
+    for (index, (turn, diffs)) in patches.into_iter().enumerate() {
+        let _ = writeln!(series, "From {} Mon Sep 17 00:00:00 2001", "0".repeat(40));
+        let _ = writeln!(series, "From: agnt <agnt@localhost>");
+        let _ = writeln!(
+            series,
+            "Subject: [PATCH {}/{total}] {}",
+            index + 1,
+            turn_subject(turn)
+        );
+        let _ = writeln!(series);
+        let _ = writeln!(series, "Turn {} of agnt session {session_id}.", turn.id);
+        let _ = writeln!(series, "---");
+        for diff in diffs {
+            series.push_str(&diff);
+        }
+        let _ = writeln!(series, "-- ");
+        let _ = writeln!(series, "agnt");
+        let _ = writeln!(series);
+    }
+    Some((series, total))
+}
+
+/// File diffs from the turn's tool results, with git headers.
+fn turn_diffs(turn: &Turn) -> Vec<String> {
+    file_diffs(turn)
+        .iter()
+        .filter_map(|diff| git_diff(diff))
+        .collect()
+}
+
+/// The unified diffs `edit` showed for the turn's changes, in order.
+pub fn file_diffs(turn: &Turn) -> Vec<String> {
+    let Ok(parts) = serde_json::from_value::<Vec<AssistantPart>>(turn.assistant_parts.clone())
+    else {
+        return Vec::new();
+    };
+    parts
+        .into_iter()
+        .filter_map(|part| match part {
+            AssistantPart::ToolCall(call) => call.display?.result?.body,
+            _ => None,
+        })
+        .filter_map(|body| match body {
+            ToolDisplayBodyPart::Diff(diff) => Some(diff),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Add the `diff --git` header (and file mode or rename lines) `git am`
+/// expects to a `--- a/x` / `+++ b/x` diff.
+fn git_diff(diff: &str) -> Option<String> {
+    let (old_path, new_path) = diff_paths(diff)?;
+
+    let mut out = String::new();
+    let (a, b) = (old_path.or(new_path)?, new_path.or(old_path)?);
+    let _ = writeln!(out, "diff --git a/{a} b/{b}");
+    match (old_path, new_path) {
+        (None, Some(_)) => out.push_str("new file mode 100644\n"),
+        (Some(_), None) => out.push_str("deleted file mode 100644\n"),
+        (Some(old_path), Some(new_path)) if old_path != new_path => {
+            let _ = writeln!(out, "rename from {old_path}");
+            let _ = writeln!(out, "rename to {new_path}");
+        }
+        _ => {}
+    }
+
+    if is_plain_move(diff) {
+        // A plain move: the rename lines say it all.
+        return (old_path != new_path).then_some(out);
+    }
+    out.push_str(diff);
+    if !diff.ends_with('\n') {
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// Paths a diff goes from and to; `None` for `/dev/null`.
+pub fn diff_paths(diff: &str) -> Option<(Option<&str>, Option<&str>)> {
+    let mut lines = diff.lines();
+    let old = lines.next()?.strip_prefix("--- ")?;
+    let new = lines.next()?.strip_prefix("+++ ")?;
+    Some((old.strip_prefix("a/"), new.strip_prefix("b/")))
+}
+
+/// Whether the diff is of a move that left the content alone.
+pub fn is_plain_move(diff: &str) -> bool {
+    diff.lines().any(|line| line == NO_CONTENT_CHANGES)
+}
+
+/// First line of the turn's prompt.
+fn turn_subject(turn: &Turn) -> String {
+    let parts =
+        serde_json::from_value::<Vec<UserPart>>(turn.user_parts.clone()).unwrap_or_default();
+    let prompt = parts
+        .iter()
+        .find_map(|part| match part {
+            UserPart::Text(text) if attachment_summary(part).is_none() => text
+                .text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty()),
+            _ => None,
+        })
+        .unwrap_or("agnt changes");
+    if prompt.chars().count() <= SUBJECT_MAX_CHARS {
+        return prompt.to_string();
+    }
+    let mut subject = prompt
+        .chars()
+        .take(SUBJECT_MAX_CHARS - 3)
+        .collect::<String>();
+    subject.push_str("...");
+    subject
+}
@@ -0,0 +1,76 @@
+//! `/add`: what the composer text pins into the agent's context.
+
+use std::path::Path;
+use std::time::Duration;
+
+use agnt_core::{ContextItem, is_agntignored};
+
+use crate::offline;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The item `/add <input>` pins. An `http(s)` URL is fetched, a file under
+/// `root` (written as is or as an `@` mention) is pinned by its path, and
+/// anything else is kept as a note. Fetching blocks until the page is in.
+pub fn resolve(
+    input: &str,
+    root: &Path,
+    offline: bool,
+) -> Result<ContextItem, Box<dyn std::error::Error>> {
+    let input = input.trim();
+    if input.starts_with("http://") || input.starts_with("https://") {
+        if offline && !offline::is_local_endpoint(input) {
+            return Err("offline mode: only URLs on this machine can be pinned".into());
+        }
+        let text = fetch(input)?;
+        return Ok(ContextItem::url(input, &text));
+    }
+
+    let mention = input.strip_prefix('@').map(|path| {
+        path.strip_prefix('"')
+            .and_then(|path| path.strip_suffix('"'))
+            .unwrap_or(path)
+    });
+    let path = mention.unwrap_or(input);
+    let resolved = root.join(path);
+    if !path.is_empty() && resolved.is_file() {
+        if is_agntignored(root, &resolved) {
+            return Err(format!("{path} is excluded by .agntignore").into());
+        }
+        return Ok(ContextItem::File(path.replace('\\', "/")));
+    }
+    if mention.is_some() {
+        return Err(format!("no such file: {path}").into());
+    }
+    Ok(ContextItem::Memory(input.to_string()))
+}
+
+/// The body of `url`. Runs on a thread of its own, as the TUI and GUI call
+/// in from a runtime worker and from outside the runtime respectively.
+fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| -> Result<String, String> {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(|err| err.to_string())?;
+                runtime.block_on(async {
+                    let client = reqwest::Client::builder()
+                        .timeout(FETCH_TIMEOUT)
+                        .build()
+                        .map_err(|err| err.to_string())?;
+                    let response = client
+                        .get(url)
+                        .send()
+                        .await
+                        .and_then(reqwest::Response::error_for_status)
+                        .map_err(|err| err.to_string())?;
+                    response.text().await.map_err(|err| err.to_string())
+                })
+            })
+            .join()
+            .map_err(|_| "fetching the page panicked".to_string())?
+    })
+    .map_err(Into::into)
+}
@@ -0,0 +1,95 @@
+//! Cleanup applied to a prompt between the composer and the agent, as a
+//! list of steps each UI runs in order.
+
+use std::path::{Path, PathBuf};
+
+use agnt_app::UserConfig;
+
+use crate::typeahead::mention_token;
+
+/// One pass over the prompt text.
+pub type Step = fn(String) -> String;
+
+/// The steps enabled by `config`, in the order they run.
+pub fn steps(config: &UserConfig) -> Vec<Step> {
+    let mut steps: Vec<Step> = vec![normalize_newlines, trim_whitespace];
+    if config.collapse_blank_lines {
+        steps.push(collapse_blank_lines);
+    }
+    steps
+}
+
+pub fn run(steps: &[Step], text: String) -> String {
+    steps.iter().fold(text, |text, step| step(text))
+}
+
+/// `\r\n` and lone `\r` become `\n`.
+fn normalize_newlines(text: String) -> String {
+    if !text.contains('\r') {
+        return text;
+    }
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Drop trailing whitespace from every line, and whitespace around the
+/// whole prompt.
+fn trim_whitespace(text: String) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Runs of more than one blank line become a single one.
+fn collapse_blank_lines(text: String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut newlines = 0;
+    for c in text.chars() {
+        if c == '\n' {
+            newlines += 1;
+            if newlines > 2 {
+                continue;
+            }
+        } else {
+            newlines = 0;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A prompt that is nothing but the path of a file, held back to ask
+/// whether the file should be attached instead.
+#[derive(Debug, Clone)]
+pub struct FilePrompt {
+    /// The prompt as typed.
+    pub text: String,
+    /// A mention of the file, sent when the user accepts.
+    pub mention: String,
+}
+
+impl FilePrompt {
+    /// `Some` when `text` names an existing file relative to `root` (or
+    /// absolute), optionally in quotes.
+    pub fn detect(text: &str, root: &Path) -> Option<Self> {
+        let path = text
+            .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+            .trim();
+        if path.is_empty() || path.starts_with('@') {
+            return None;
+        }
+        let path = PathBuf::from(path);
+        if !root.join(&path).is_file() {
+            return None;
+        }
+        Some(Self {
+            text: text.to_string(),
+            mention: mention_token(&path, root),
+        })
+    }
+
+    pub fn hint(&self) -> String {
+        format!(
+            "This is a file path. Enter to attach {} instead, Esc to send it as typed",
+            self.mention
+        )
+    }
+}
@@ -0,0 +1,225 @@
+use agnt_core::{AgentSettings, ApprovalPolicy, Formatter, PatternFilter, WasmToolGrant};
+use agnt_db::ProjectSettings;
+
+/// Profiles selectable in project settings, from cheapest to most thorough.
+pub const PROFILES: [&str; 3] = ["fast", "balanced", "thorough"];
+
+/// Render settings as the `key = value` lines edited in the composer. Unset
+/// keys are listed with an empty value so they are easy to fill in.
+pub fn format(settings: &ProjectSettings) -> String {
+    [
+        ("default_model", settings.default_model.clone()),
+        ("profile", settings.profile.clone()),
+        ("approval_policy", settings.approval_policy.clone()),
+        (
+            "ignored_paths",
+            Some(settings.ignored_paths.join(", ")).filter(|paths| !paths.is_empty()),
+        ),
+        (
+            "protected_paths",
+            Some(settings.protected_paths.join(", ")).filter(|paths| !paths.is_empty()),
+        ),
+        (
+            "redaction_allowlist",
+            Some(settings.redaction_allowlist.join(", ")).filter(|values| !values.is_empty()),
+        ),
+        (
+            "blocked_patterns",
+            Some(settings.blocked_patterns.join(", ")).filter(|patterns| !patterns.is_empty()),
+        ),
+        (
+            "scrubbed_patterns",
+            Some(settings.scrubbed_patterns.join(", ")).filter(|patterns| !patterns.is_empty()),
+        ),
+        (
+            "formatters",
+            Some(settings.formatters.join(", ")).filter(|formatters| !formatters.is_empty()),
+        ),
+        (
+            "accounts",
+            Some(settings.accounts.join(", ")).filter(|accounts| !accounts.is_empty()),
+        ),
+        (
+            "wasm_tools",
+            Some(settings.wasm_tools.join(", ")).filter(|tools| !tools.is_empty()),
+        ),
+    ]
+    .into_iter()
+    .map(|(key, value)| format!("{key} = {}", value.unwrap_or_default()))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Parse the composer text produced by [`format`]. Keys that are missing or
+/// left empty are unset.
+pub fn parse(text: &str) -> Result<ProjectSettings, String> {
+    let mut settings = ProjectSettings::default();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key = value`, got `{line}`"))?;
+        let value = value.trim();
+        let optional = || Some(value.to_string()).filter(|value| !value.is_empty());
+
+        match key.trim() {
+            "default_model" => {
+                if let Some(model) = optional()
+                    && split_model(&model).is_none()
+                {
+                    return Err(format!(
+                        "default_model must be `provider/model`, got `{model}`"
+                    ));
+                }
+                settings.default_model = optional();
+            }
+            "profile" => {
+                if let Some(profile) = optional()
+                    && !PROFILES.contains(&profile.as_str())
+                {
+                    return Err(format!(
+                        "unknown profile `{profile}` (expected one of: {})",
+                        PROFILES.join(", ")
+                    ));
+                }
+                settings.profile = optional();
+            }
+            "approval_policy" => {
+                if let Some(policy) = optional() {
+                    policy.parse::<ApprovalPolicy>()?;
+                }
+                settings.approval_policy = optional();
+            }
+            "ignored_paths" => settings.ignored_paths = split_list(value),
+            "protected_paths" => settings.protected_paths = split_list(value),
+            "redaction_allowlist" => settings.redaction_allowlist = split_list(value),
+            "blocked_patterns" => settings.blocked_patterns = split_patterns(key, value)?,
+            "scrubbed_patterns" => settings.scrubbed_patterns = split_patterns(key, value)?,
+            "formatters" => {
+                settings.formatters = split_list(value);
+                for formatter in &settings.formatters {
+                    formatter
+                        .parse::<Formatter>()
+                        .map_err(|err| format!("invalid formatter: {err}"))?;
+                }
+            }
+            "accounts" => {
+                settings.accounts = split_list(value);
+                for entry in &settings.accounts {
+                    if split_account_entry(entry).is_none() {
+                        return Err(format!(
+                            "accounts must be `provider: account`, got `{entry}`"
+                        ));
+                    }
+                }
+            }
+            "wasm_tools" => {
+                settings.wasm_tools = split_list(value);
+                for tool in &settings.wasm_tools {
+                    tool.parse::<WasmToolGrant>()
+                        .map_err(|err| format!("invalid WASM tool grant: {err}"))?;
+                }
+            }
+            key => return Err(format!("unknown project setting `{key}`")),
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Split a comma-separated setting, dropping empty entries.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split a comma-separated list of regexes, rejecting invalid ones.
+fn split_patterns(key: &str, value: &str) -> Result<Vec<String>, String> {
+    let patterns = split_list(value);
+    PatternFilter::new(&patterns, &[])
+        .map_err(|err| format!("invalid {} pattern: {err}", key.trim()))?;
+    Ok(patterns)
+}
+
+/// Split a `provider/model` setting into its parts.
+pub fn split_model(model: &str) -> Option<(&str, &str)> {
+    let (provider, model) = model.split_once('/')?;
+    (!provider.trim().is_empty() && !model.trim().is_empty())
+        .then(|| (provider.trim(), model.trim()))
+}
+
+/// `provider` with the account the project's `accounts` setting picks for
+/// it, as `provider@account`. Providers that already name an account, or
+/// that the setting doesn't mention, are returned as is.
+pub fn with_account(settings: &ProjectSettings, provider: &str) -> String {
+    if provider.contains('@') {
+        return provider.to_string();
+    }
+    settings
+        .accounts
+        .iter()
+        .filter_map(|entry| split_account_entry(entry))
+        .find(|(entry_provider, _)| *entry_provider == provider)
+        .map_or_else(
+            || provider.to_string(),
+            |(_, account)| format!("{provider}@{account}"),
+        )
+}
+
+/// Split an `accounts` entry, `provider: account`, into its parts.
+fn split_account_entry(entry: &str) -> Option<(&str, &str)> {
+    let (provider, account) = entry.split_once(':')?;
+    let (provider, account) = (provider.trim(), account.trim());
+    (!provider.is_empty() && !account.is_empty() && !account.contains('@'))
+        .then_some((provider, account))
+}
+
+/// The grant the project's `wasm_tools` setting approves the WASM tool
+/// `name` with, if any.
+pub fn wasm_tool_grant(settings: &ProjectSettings, name: &str) -> Option<WasmToolGrant> {
+    settings
+        .wasm_tools
+        .iter()
+        .filter_map(|entry| entry.parse::<WasmToolGrant>().ok())
+        .find(|grant| grant.name == name)
+}
+
+/// Reasoning effort requested by the project's profile: `low` for `fast`,
+/// `medium` for `balanced`, `high` otherwise.
+pub fn reasoning_effort(settings: &ProjectSettings) -> &'static str {
+    match settings.profile.as_deref() {
+        Some("fast") => "low",
+        Some("balanced") => "medium",
+        _ => "high",
+    }
+}
+
+pub fn agent_settings(settings: &ProjectSettings) -> AgentSettings {
+    AgentSettings {
+        // Stored values are validated on save; anything unreadable falls back
+        // to the default.
+        approval_policy: settings
+            .approval_policy
+            .as_deref()
+            .and_then(|policy| policy.parse().ok())
+            .unwrap_or_default(),
+        ignored_paths: settings.ignored_paths.clone(),
+        protected_paths: settings.protected_paths.clone(),
+        redaction_allowlist: settings.redaction_allowlist.clone(),
+        blocked_patterns: settings.blocked_patterns.clone(),
+        scrubbed_patterns: settings.scrubbed_patterns.clone(),
+        formatters: settings
+            .formatters
+            .iter()
+            .filter_map(|formatter| formatter.parse().ok())
+            .collect(),
+        ..AgentSettings::default()
+    }
+}
@@ -0,0 +1,247 @@
+//! `agnt providers`: list known providers and their models, as text or
+//! JSON, and with `--check` make a request to each to see that it answers.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use agnt_auth::AuthManager;
+use agnt_llm_registry::{ModelSpec, Registry};
+use clap::ValueEnum;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+use crate::offline;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Rate limit headers OpenAI-compatible APIs report the remaining quota in.
+const QUOTA_HEADERS: &[(&str, &str)] = &[
+    ("x-ratelimit-remaining-requests", "requests"),
+    ("x-ratelimit-remaining-tokens", "tokens"),
+];
+
+/// Model feature `--capability` filters on.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Capability {
+    ToolCall,
+    Reasoning,
+    Attachment,
+    StructuredOutput,
+    Temperature,
+}
+
+impl Capability {
+    fn supported_by(self, model: &ModelSpec) -> bool {
+        match self {
+            Self::ToolCall => model.tool_call,
+            Self::Reasoning => model.reasoning,
+            Self::Attachment => model.attachment,
+            Self::StructuredOutput => model.structured_output,
+            Self::Temperature => model.temperature,
+        }
+    }
+}
+
+/// Which providers and models `agnt providers` lists.
+pub struct ProviderFilter {
+    /// Only providers with credentials.
+    pub configured: bool,
+    pub provider_id: Option<String>,
+    /// Only models with this capability, and providers that have one.
+    pub capability: Option<Capability>,
+}
+
+pub async fn run(
+    registry: &Registry,
+    auth: &Arc<AuthManager>,
+    filter: &ProviderFilter,
+    check: bool,
+    json: bool,
+    offline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut providers = registry.known_providers();
+    // Providers that can be used right away first.
+    providers.sort_by_key(|provider| !provider.configured);
+    if let Some(provider_id) = &filter.provider_id
+        && !providers.iter().any(|provider| provider.id == *provider_id)
+    {
+        return Err(format!("unknown provider {provider_id}").into());
+    }
+
+    let client = reqwest::Client::builder().timeout(CHECK_TIMEOUT).build()?;
+    let mut entries = Vec::new();
+    for provider in providers.into_iter().filter(|provider| {
+        (!filter.configured || provider.configured)
+            && filter
+                .provider_id
+                .as_ref()
+                .is_none_or(|provider_id| provider.id == *provider_id)
+    }) {
+        let mut models = registry
+            .list_models(&provider.id)
+            .into_iter()
+            .filter(|model| {
+                filter
+                    .capability
+                    .is_none_or(|capability| capability.supported_by(model))
+            })
+            .collect::<Vec<_>>();
+        if filter.capability.is_some() && models.is_empty() {
+            continue;
+        }
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        let check = if check {
+            Some(check_provider(&client, registry, auth, &provider.id, offline).await)
+        } else {
+            None
+        };
+
+        if json {
+            let mut entry = serde_json::to_value(&provider)?;
+            entry["models"] = serde_json::to_value(&models)?;
+            if let Some(check) = check {
+                entry["check"] = Value::String(check);
+            }
+            entries.push(entry);
+            continue;
+        }
+
+        let compat = if provider.compatible {
+            "compatible"
+        } else {
+            "no-factory"
+        };
+        let configured = if provider.configured {
+            "configured"
+        } else {
+            "not configured"
+        };
+        println!(
+            "{} ({}) [{} | {configured} | {compat}]",
+            provider.id, provider.name, provider.auth_method
+        );
+        if let Some(check) = check {
+            println!("  check: {check}");
+        }
+        for model in &models {
+            let name = model.name.as_deref().unwrap_or("");
+            println!("  {:<30} {:<30} {}", model.id, name, model_details(model));
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    }
+    Ok(())
+}
+
+/// Limits, price, capabilities and knowledge cutoff of a model, for
+/// pickers and listings, e.g. `400k ctx · 128k out · $1.25/$10.00 per 1M ·
+/// reasoning, tools · knowledge 2024-09`.
+pub fn model_details(model: &ModelSpec) -> String {
+    let mut details = Vec::new();
+    if let Some(limit) = &model.limit {
+        if limit.context > 0 {
+            details.push(format!("{} ctx", format_tokens(limit.context)));
+        }
+        if limit.output > 0 {
+            details.push(format!("{} out", format_tokens(limit.output)));
+        }
+    }
+    if let Some(cost) = &model.cost {
+        details.push(format!("${:.2}/${:.2} per 1M", cost.input, cost.output));
+    }
+    let capabilities = [
+        (model.reasoning, "reasoning"),
+        (model.tool_call, "tools"),
+        (model.attachment, "attachments"),
+    ]
+    .into_iter()
+    .filter_map(|(supported, label)| supported.then_some(label))
+    .collect::<Vec<_>>();
+    if !capabilities.is_empty() {
+        details.push(capabilities.join(", "));
+    }
+    if let Some(knowledge) = &model.knowledge {
+        details.push(format!("knowledge {knowledge}"));
+    }
+    details.join(" · ")
+}
+
+fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 && tokens.is_multiple_of(100_000) {
+        format!("{}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{}k", tokens / 1_000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// List the provider's models with its credentials and describe how that
+/// went: whether they were accepted, how long it took and the quota left.
+async fn check_provider(
+    client: &reqwest::Client,
+    registry: &Registry,
+    auth: &Arc<AuthManager>,
+    provider_id: &str,
+    offline: bool,
+) -> String {
+    let Some(endpoint) = registry.api_endpoint(provider_id) else {
+        return "skipped, no API endpoint known".to_string();
+    };
+    if offline && !offline::is_local_endpoint(&endpoint) {
+        return format!("skipped, {endpoint} is not local");
+    }
+    let credentials = match registry.auth_request(provider_id) {
+        Some(request) => match auth.resolve_cached(&request) {
+            Ok(credentials) => credentials,
+            Err(err) => return format!("stored credentials are unreadable: {err}"),
+        },
+        None => None,
+    };
+    let token = credentials.as_ref().and_then(|credentials| {
+        credentials
+            .get("api_key")
+            .or_else(|| credentials.get("access_token"))
+    });
+
+    let url = format!("{}/models", endpoint.trim_end_matches('/'));
+    let mut request = client.get(&url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let started = Instant::now();
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) => return format!("{endpoint} is unreachable: {err}"),
+    };
+    let latency = started.elapsed().as_millis();
+    let status = response.status();
+
+    let verdict = if status.is_success() {
+        "ok".to_string()
+    } else if status.as_u16() == 401 || status.as_u16() == 403 {
+        format!("credentials rejected ({status})")
+    } else {
+        // The host answered, but not every API lists models; this says
+        // nothing about the credentials.
+        format!("answered with {status}")
+    };
+    match quota(response.headers()) {
+        Some(quota) => format!("{verdict}, {latency} ms, {quota} left"),
+        None => format!("{verdict}, {latency} ms"),
+    }
+}
+
+fn quota(headers: &HeaderMap) -> Option<String> {
+    let remaining = QUOTA_HEADERS
+        .iter()
+        .filter_map(|(header, unit)| {
+            let value = headers.get(*header)?.to_str().ok()?;
+            Some(format!("{value} {unit}"))
+        })
+        .collect::<Vec<_>>();
+    (!remaining.is_empty()).then(|| remaining.join(", "))
+}
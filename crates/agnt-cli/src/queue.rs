@@ -0,0 +1,186 @@
+//! `agnt queue`: prompts queued to run later, one after another and
+//! without a UI, e.g. for an overnight batch of refactors. Each run is
+//! stored as a session of its own, which can be resumed like any other.
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agnt_auth::AuthManager;
+use agnt_db::{QueuedTask, Store, TaskStatus};
+use agnt_llm_registry::Registry;
+use parking_lot::Mutex;
+
+use crate::headless::{self, RunOptions};
+use crate::project_settings::PROFILES;
+use crate::session::{now_ms, relative_time, truncate_with_ellipsis};
+
+/// How often `agnt queue run --watch` looks for new tasks.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where `agnt queue add` reads the prompt from.
+pub enum PromptSource<'a> {
+    Text(&'a str),
+    File(&'a Path),
+    Stdin,
+}
+
+/// Queue a prompt to run in `repo`, or the current directory.
+pub fn add(
+    store: &mut Store,
+    source: PromptSource<'_>,
+    repo: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prompt = match source {
+        PromptSource::Text(prompt) => prompt.to_string(),
+        PromptSource::File(path) => std::fs::read_to_string(path)?,
+        PromptSource::Stdin => io::read_to_string(io::stdin())?,
+    };
+    if prompt.trim().is_empty() {
+        return Err("the prompt is empty".into());
+    }
+    let root = match repo {
+        Some(repo) => std::fs::canonicalize(repo)?,
+        None => std::env::current_dir()?,
+    };
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()).into());
+    }
+    if let Some(profile) = profile
+        && !PROFILES.contains(&profile)
+    {
+        return Err(format!(
+            "unknown profile `{profile}` (expected one of: {})",
+            PROFILES.join(", ")
+        )
+        .into());
+    }
+    let task = store.task_queue().enqueue(&root, &prompt, profile)?;
+    println!("Queued {} in {}.", task.id, root.display());
+    Ok(())
+}
+
+pub fn status(store: &mut Store, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let tasks = store.task_queue().tasks(limit)?;
+    if tasks.is_empty() {
+        println!("The queue is empty.");
+        return Ok(());
+    }
+
+    for task in &tasks {
+        println!("{} {:<7} {}", task.id, task.status.as_str(), progress(task));
+        println!("  {}", task.project_root.display());
+        if let Some(profile) = &task.profile {
+            println!("  profile: {profile}");
+        }
+        let prompt = task.prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        println!("  {}", truncate_with_ellipsis(&prompt, 100));
+        if let Some(session_id) = &task.session_id {
+            println!("  session: {session_id}");
+        }
+        if let Some(error) = &task.error {
+            println!("  error: {error}");
+        }
+    }
+    let waiting = tasks
+        .iter()
+        .filter(|task| task.status == TaskStatus::Queued)
+        .count();
+    println!();
+    println!("{waiting} task(s) waiting. Run them with `agnt queue run`.");
+    Ok(())
+}
+
+/// Run queued tasks until none are left, or with `watch`, keep waiting for
+/// more.
+pub async fn run(
+    store: &Arc<Mutex<Store>>,
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    options: &RunOptions<'_>,
+    watch: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ran = 0;
+    loop {
+        let Some(task) = store.lock().task_queue().claim_next()? else {
+            if !watch {
+                break;
+            }
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            continue;
+        };
+
+        println!("Running {} in {}...", task.id, task.project_root.display());
+        let (session_id, error) = match run_task(store, registry, auth, options, &task).await {
+            Ok(outcome) => (Some(outcome.session_id), outcome.error),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        store
+            .lock()
+            .task_queue()
+            .finish(&task.id, error.as_deref())?;
+        match (&error, &session_id) {
+            (Some(error), _) => println!("{} failed: {error}", task.id),
+            (None, Some(session_id)) => {
+                println!("{} done, stored as session {session_id}.", task.id)
+            }
+            (None, None) => println!("{} done.", task.id),
+        }
+        ran += 1;
+    }
+    println!("Ran {ran} task(s); the queue is empty.");
+    Ok(())
+}
+
+async fn run_task(
+    store: &Arc<Mutex<Store>>,
+    registry: &mut Registry,
+    auth: &Arc<AuthManager>,
+    options: &RunOptions<'_>,
+    task: &QueuedTask,
+) -> Result<headless::TurnOutcome, Box<dyn std::error::Error>> {
+    let (mut agent, session_store) = headless::open_project(
+        store,
+        registry,
+        auth,
+        options,
+        &task.project_root,
+        task.profile.as_deref(),
+    )
+    .await?;
+    session_store.lock().ensure_active_session()?;
+    let session_id = session_store.lock().active_session_id().map(str::to_string);
+    if let Some(session_id) = session_id {
+        store
+            .lock()
+            .task_queue()
+            .set_session(&task.id, &session_id)?;
+    }
+    headless::run_turn(
+        &mut agent,
+        &session_store,
+        &task.project_root,
+        &task.prompt,
+        !options.config.skip_mention_attachments,
+    )
+    .await
+}
+
+/// When a task started and how long it ran, or when it was queued.
+fn progress(task: &QueuedTask) -> String {
+    match (task.started_at_ms, task.finished_at_ms) {
+        (Some(started), Some(finished)) => format!(
+            "finished {}, took {}s",
+            relative_time(finished),
+            (finished - started).max(0) / 1000
+        ),
+        (Some(started), None) => format!(
+            "started {}, running for {}s",
+            relative_time(started),
+            (now_ms() - started).max(0) / 1000
+        ),
+        _ => format!("queued {}", relative_time(task.created_at_ms)),
+    }
+}
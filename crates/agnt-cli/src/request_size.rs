@@ -0,0 +1,382 @@
+//! A check before sending that a request isn't far larger than meant, e.g.
+//! from mentioning a huge file or a long history of tool output. Requests
+//! over the configured size or cost are held back until confirmed, with a
+//! breakdown of what takes the space. `/debug:context` shows the same kind
+//! of breakdown on demand, by section of the request.
+
+use std::path::Path;
+
+use agnt_app::UserConfig;
+use agnt_core::{Agent, Attachment, AttachmentContent, ContextItem, RequestSections};
+use agnt_llm::{AssistantPart, Message, SystemPart, ToolDefinition, UserPart};
+use agnt_llm_registry::Registry;
+
+/// Input tokens above which a request is held back, unless the config says
+/// otherwise.
+const DEFAULT_MAX_TOKENS: u64 = 100_000;
+/// Rough average for English text and code.
+const BYTES_PER_TOKEN: u64 = 4;
+/// What providers bill a typical image at.
+const IMAGE_TOKENS: u64 = 1_000;
+/// Parts of the breakdown listed by name; the rest are summed up.
+const BREAKDOWN_ITEMS: usize = 5;
+
+/// The limits a request is checked against, for the model in use.
+#[derive(Debug, Clone, Default)]
+pub struct RequestGuard {
+    /// `None` doesn't check the size.
+    max_tokens: Option<u64>,
+    /// `None` doesn't check the cost.
+    max_usd: Option<f64>,
+    /// Input price of the model in USD per million tokens, when known.
+    input_price: Option<f64>,
+    /// The model's context window in tokens, when known.
+    context_window: Option<u64>,
+}
+
+impl RequestGuard {
+    pub fn new(
+        registry: &Registry,
+        config: &UserConfig,
+        provider_id: &str,
+        model_id: &str,
+    ) -> Self {
+        Self {
+            max_tokens: match config.confirm_request_tokens {
+                Some(0) => None,
+                Some(tokens) => Some(tokens),
+                None => Some(DEFAULT_MAX_TOKENS),
+            },
+            max_usd: config.confirm_request_usd,
+            input_price: registry
+                .model_spec(provider_id, model_id)
+                .and_then(|spec| spec.cost)
+                .map(|cost| cost.input),
+            context_window: registry
+                .model_spec(provider_id, model_id)
+                .and_then(|spec| spec.limit)
+                .map(|limit| limit.context)
+                .filter(|tokens| *tokens > 0),
+        }
+    }
+
+    /// What the next request is made of, by section.
+    pub fn breakdown(&self, agent: &Agent) -> ContextBreakdown {
+        ContextBreakdown::estimate(&agent.request_sections(), self.context_window)
+    }
+
+    /// The estimated size of sending `prompt` with `attachments` next, when
+    /// it is over a limit.
+    pub fn check(
+        &self,
+        agent: &Agent,
+        prompt: &str,
+        attachments: &[Attachment],
+    ) -> Option<RequestSize> {
+        if self.max_tokens.is_none() && self.max_usd.is_none() {
+            return None;
+        }
+        let size = RequestSize::estimate(agent, prompt, attachments, self.input_price);
+        let too_big = self.max_tokens.is_some_and(|max| size.tokens > max);
+        let too_costly = self
+            .max_usd
+            .zip(size.cost_usd)
+            .is_some_and(|(max, cost)| cost > max);
+        (too_big || too_costly).then_some(size)
+    }
+}
+
+/// A request's estimated input size and what makes it up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestSize {
+    pub tokens: u64,
+    /// Input cost in USD, when the model's price is known.
+    pub cost_usd: Option<f64>,
+    /// Estimated tokens by where they come from, largest first.
+    pub parts: Vec<(String, u64)>,
+}
+
+impl RequestSize {
+    fn estimate(
+        agent: &Agent,
+        prompt: &str,
+        attachments: &[Attachment],
+        input_price: Option<f64>,
+    ) -> Self {
+        let mut parts = Vec::new();
+        let (history, tool_results) = history_tokens(&agent.messages());
+        parts.push(("conversation".to_string(), history));
+        parts.push(("tool results in history".to_string(), tool_results));
+        let cwd = std::env::current_dir().unwrap_or_default();
+        for item in agent.pinned_context() {
+            parts.push((
+                format!("pinned {}", item.label()),
+                pinned_tokens(&item, &cwd),
+            ));
+        }
+        for attachment in attachments {
+            let tokens = match &attachment.content {
+                AttachmentContent::Text(text) | AttachmentContent::Note(text) => text_tokens(text),
+                AttachmentContent::Image(_) => IMAGE_TOKENS,
+            };
+            parts.push((attachment.label.clone(), tokens));
+        }
+        parts.push(("prompt".to_string(), text_tokens(prompt)));
+        parts.retain(|(_, tokens)| *tokens > 0);
+        parts.sort_by_key(|(_, tokens)| std::cmp::Reverse(*tokens));
+
+        let tokens = parts.iter().map(|(_, tokens)| tokens).sum();
+        Self {
+            tokens,
+            cost_usd: input_price.map(|price| tokens as f64 * price / 1_000_000.0),
+            parts,
+        }
+    }
+
+    /// A status line with the total and the largest parts.
+    pub fn summary(&self) -> String {
+        let mut breakdown: Vec<String> = self
+            .parts
+            .iter()
+            .take(BREAKDOWN_ITEMS)
+            .map(|(label, tokens)| format!("{label} {}", format_tokens(*tokens)))
+            .collect();
+        let rest: u64 = self
+            .parts
+            .iter()
+            .skip(BREAKDOWN_ITEMS)
+            .map(|(_, tokens)| tokens)
+            .sum();
+        if rest > 0 {
+            breakdown.push(format!("other {}", format_tokens(rest)));
+        }
+        let cost = self
+            .cost_usd
+            .map(|cost| format!(", ~${cost:.2} input"))
+            .unwrap_or_default();
+        format!(
+            "[large request, about {} tokens{cost}: {}]",
+            format_tokens(self.tokens),
+            breakdown.join(", ")
+        )
+    }
+}
+
+/// The estimated tokens of each section of a request, for `/debug:context`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextBreakdown {
+    /// Estimated tokens by section, in the order sent.
+    pub sections: Vec<(&'static str, u64)>,
+    /// The model's context window in tokens, when known.
+    pub context_window: Option<u64>,
+}
+
+impl ContextBreakdown {
+    fn estimate(sections: &RequestSections, context_window: Option<u64>) -> Self {
+        let (history, tool_results) = history_tokens(&sections.history);
+        Self {
+            sections: vec![
+                ("system prompt", text_tokens(&sections.system_prompt)),
+                (
+                    "AGENTS.md",
+                    sections.agents_md.as_deref().map_or(0, text_tokens),
+                ),
+                ("pinned context", user_parts_tokens(&sections.context)),
+                ("conversation", history),
+                ("tool results", tool_results),
+                ("tool definitions", tool_definition_tokens(&sections.tools)),
+            ],
+            context_window,
+        }
+    }
+
+    /// A line with the total, then one per section with its share.
+    pub fn lines(&self) -> Vec<String> {
+        let total: u64 = self.sections.iter().map(|(_, tokens)| tokens).sum();
+        let window = self
+            .context_window
+            .map(|window| {
+                format!(
+                    ", {} of the {} context window",
+                    percent(total, window),
+                    format_tokens(window)
+                )
+            })
+            .unwrap_or_default();
+        let mut lines = vec![format!(
+            "[request context: about {} tokens{window}]",
+            format_tokens(total)
+        )];
+        let width = self
+            .sections
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or_default();
+        for (label, tokens) in &self.sections {
+            lines.push(format!(
+                "  {label:<width$}  {:>6}  {:>4}",
+                format_tokens(*tokens),
+                percent(*tokens, total)
+            ));
+        }
+        lines
+    }
+}
+
+/// A prompt held back until sending it is confirmed.
+pub struct HeldRequest {
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+}
+
+impl HeldRequest {
+    /// Drop the files and pastes sent along with the prompt. Notes from agnt
+    /// itself, such as files changed since a resumed session, are kept.
+    /// Returns how many were dropped.
+    pub fn drop_attachments(&mut self) -> usize {
+        let before = self.attachments.len();
+        self.attachments
+            .retain(|attachment| matches!(attachment.content, AttachmentContent::Note(_)));
+        before - self.attachments.len()
+    }
+}
+
+/// Tokens of the conversation so far, and of the tool results within it.
+fn history_tokens(messages: &[Message]) -> (u64, u64) {
+    let mut history = 0;
+    let mut tool_results = 0;
+    for message in messages {
+        match message {
+            Message::System { parts } => {
+                for SystemPart::Text(text) in parts {
+                    history += text_tokens(&text.text);
+                }
+            }
+            Message::User { parts } => history += user_parts_tokens(parts),
+            Message::Assistant { parts } => {
+                for part in parts {
+                    history += match part {
+                        AssistantPart::Text(text) => text_tokens(&text.text),
+                        AssistantPart::ToolCall(call) => text_tokens(&call.arguments),
+                        AssistantPart::Reasoning(reasoning) => {
+                            reasoning.text.as_deref().map_or(0, text_tokens)
+                        }
+                        // Not sent back to the model.
+                        AssistantPart::Image(_) => 0,
+                    };
+                }
+            }
+            Message::Tool { parts } => {
+                for part in parts {
+                    tool_results += text_tokens(&part.content);
+                }
+            }
+        }
+    }
+    (history, tool_results)
+}
+
+fn user_parts_tokens(parts: &[UserPart]) -> u64 {
+    parts
+        .iter()
+        .map(|part| match part {
+            UserPart::Text(text) => text_tokens(&text.text),
+            UserPart::Image(_) => IMAGE_TOKENS,
+        })
+        .sum()
+}
+
+fn tool_definition_tokens(tools: &[ToolDefinition]) -> u64 {
+    tools
+        .iter()
+        .map(|tool| {
+            text_tokens(&tool.name)
+                + text_tokens(&tool.description)
+                + text_tokens(&tool.parameters.to_json_schema().to_string())
+        })
+        .sum()
+}
+
+fn pinned_tokens(item: &ContextItem, root: &Path) -> u64 {
+    match item {
+        ContextItem::File(path) => std::fs::metadata(root.join(path))
+            .map(|metadata| {
+                metadata
+                    .len()
+                    .min(agnt_core::attachment::MAX_ATTACHMENT_BYTES as u64)
+                    .div_ceil(BYTES_PER_TOKEN)
+            })
+            .unwrap_or(0),
+        ContextItem::Url { text, .. } | ContextItem::Memory(text) => text_tokens(text),
+    }
+}
+
+fn text_tokens(text: &str) -> u64 {
+    (text.len() as u64).div_ceil(BYTES_PER_TOKEN)
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    if whole == 0 {
+        return "0%".to_string();
+    }
+    format!("{:.0}%", part as f64 * 100.0 / whole as f64)
+}
+
+fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_the_largest_parts_and_sums_the_rest() {
+        let size = RequestSize {
+            tokens: 126_400,
+            cost_usd: Some(0.316),
+            parts: vec![
+                ("tool results in history".to_string(), 80_000),
+                ("src/data.json".to_string(), 40_000),
+                ("conversation".to_string(), 5_000),
+                ("a.rs".to_string(), 600),
+                ("b.rs".to_string(), 400),
+                ("c.rs".to_string(), 300),
+                ("prompt".to_string(), 100),
+            ],
+        };
+        assert_eq!(
+            size.summary(),
+            "[large request, about 126.4k tokens, ~$0.32 input: tool results in history 80.0k, \
+             src/data.json 40.0k, conversation 5.0k, a.rs 600, b.rs 400, other 400]"
+        );
+    }
+
+    #[test]
+    fn shows_each_section_with_its_share() {
+        let breakdown = ContextBreakdown {
+            sections: vec![
+                ("system prompt", 3_000),
+                ("AGENTS.md", 1_000),
+                ("conversation", 12_000),
+                ("tool definitions", 4_000),
+            ],
+            context_window: Some(200_000),
+        };
+        assert_eq!(
+            breakdown.lines(),
+            [
+                "[request context: about 20.0k tokens, 10% of the 200.0k context window]",
+                "  system prompt       3.0k   15%",
+                "  AGENTS.md           1.0k    5%",
+                "  conversation       12.0k   60%",
+                "  tool definitions    4.0k   20%",
+            ]
+        );
+    }
+}
@@ -1,21 +1,101 @@
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use agnt_core::{Agent, ConversationState};
-use agnt_db::{AppendTurnInput, CreateSessionInput, Session, Store};
+use agnt_app::WebhookEvent;
+use agnt_core::{
+    Agent, ConversationState, ConversationSummary, ToolCallRecord, TurnStats, attachment_summary,
+};
+use agnt_db::{
+    AppendAuditInput, AppendTurnInput, CreateSessionInput, ProjectSettings, Session, Store, Turn,
+    TurnBookmark, TurnSearchHit,
+};
 use agnt_llm::stream::Usage;
 use agnt_llm::{AssistantPart, Message, UserPart};
 use parking_lot::Mutex;
-use serde_json::Value;
+use serde_json::{Value, json};
+
+use crate::budget::{BudgetPeriod, BudgetStatus, BudgetTracker};
+use crate::i18n::{Msg, t};
+use crate::snapshots;
+use crate::vcs;
+use crate::webhooks::Webhooks;
 
 pub type SharedSessionStore = Arc<Mutex<SessionStore>>;
 
 const SESSION_TITLE_MAX_CHARS: usize = 80;
+/// A session lock without a heartbeat for this long belongs to a process
+/// that has exited or hung, and may be taken over.
+const SESSION_LOCK_STALE_AFTER: Duration = Duration::from_secs(120);
+const SESSION_LOCK_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// A session resumed after sitting idle this long has its summary put in
+/// front of the model with the next prompt.
+const RESUME_SUMMARY_AFTER: Duration = Duration::from_secs(4 * 60 * 60);
+/// Recent sessions looked at for ones left without a current summary.
+const UNSUMMARIZED_SCAN_LIMIT: usize = 20;
+/// Characters of a bookmarked turn's prompt shown when it has no note.
+const BOOKMARK_PROMPT_CHARS: usize = 60;
+
+/// Which session the UI opens with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupSession {
+    /// A new session, stored once the first message is sent.
+    New,
+    /// The project's most recently active session.
+    Continue,
+    /// The session with this id.
+    Resume(String),
+    /// A new session, with the session picker open.
+    Pick,
+}
 
 pub struct SessionStore {
     store: Arc<Mutex<Store>>,
     project_id: String,
+    project_root: PathBuf,
+    /// Linked worktree the store was opened in, recorded on new sessions.
+    worktree_dir: Option<PathBuf>,
     active_session_id: Option<String>,
+    /// Turn this process has the active session checked out at. Appends
+    /// branch from here even if another process moved the session.
+    current_turn_id: Option<String>,
+    /// Identifies this process in session locks.
+    lock_owner: String,
+    /// `(provider, model)` the agent generates with, recorded with each turn.
+    model: Option<(String, String)>,
+    /// Reasoning effort requested from the model, stored with the session.
+    reasoning_effort: Option<String>,
+    budget: Option<BudgetTracker>,
+    /// Budget state last sent to the webhooks, so each is sent once.
+    budget_notified: Option<(BudgetPeriod, bool)>,
+    webhooks: Option<Webhooks>,
+    /// Put each turn's edits into a jj change of their own.
+    jj_change_per_turn: bool,
+    /// Files read or edited by the turn in progress.
+    turn_files: BTreeSet<String>,
+    /// Store the text of those files with the turn, not only their hashes.
+    snapshot_file_contents: bool,
+    /// Whether the restored conversation's files still need to be checked
+    /// for changes made since.
+    context_check_pending: bool,
+    /// Summary of a session resumed after a long gap, still to be sent with
+    /// the next prompt.
+    resume_summary: Option<ConversationSummary>,
+}
+
+/// A stored conversation whose summary doesn't cover its current turn.
+pub struct SummaryTarget {
+    pub session_id: String,
+    pub turn_id: String,
+    pub state: ConversationState,
+}
+
+/// Model a stored session generates with.
+pub struct SessionModel {
+    pub provider_id: String,
+    pub model_id: String,
+    pub reasoning_effort: Option<String>,
 }
 
 impl SessionStore {
@@ -23,31 +103,646 @@ impl SessionStore {
         store: Arc<Mutex<Store>>,
         project_root: &Path,
     ) -> Result<Self, Box<dyn std::error::Error>> {
+        // Worktrees of one repository share the main checkout's project.
+        let identity = vcs::project_identity(project_root);
         let project = {
             let mut db = store.lock();
-            db.sessions().upsert_project(project_root, None)?
+            db.sessions().upsert_project(&identity.root, None)?
         };
 
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
         Ok(Self {
             store,
             project_id: project.id,
+            project_root: project_root.to_path_buf(),
+            worktree_dir: identity.worktree,
             active_session_id: None,
+            current_turn_id: None,
+            lock_owner: format!("{}-{started_at:x}", std::process::id()),
+            model: None,
+            reasoning_effort: None,
+            budget: None,
+            budget_notified: None,
+            webhooks: None,
+            jj_change_per_turn: false,
+            turn_files: BTreeSet::new(),
+            snapshot_file_contents: false,
+            context_check_pending: false,
+            resume_summary: None,
         })
     }
 
+    pub fn set_model(&mut self, provider_id: &str, model_id: &str, reasoning_effort: &str) {
+        self.model = Some((provider_id.to_string(), model_id.to_string()));
+        self.reasoning_effort = Some(reasoning_effort.to_string());
+    }
+
+    /// The model turns are stored as generated with, as `provider/model`.
+    pub fn model_label(&self) -> Option<String> {
+        let (provider_id, model_id) = self.model.as_ref()?;
+        Some(format!("{provider_id}/{model_id}"))
+    }
+
+    /// The model the active session last generated with, if it has been
+    /// stored.
+    pub fn active_session_model(&self) -> Result<Option<SessionModel>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let mut db = self.store.lock();
+        Ok(db.sessions().get_session(session_id)?.and_then(|session| {
+            Some(SessionModel {
+                provider_id: session.provider_id?,
+                model_id: session.model_id?,
+                reasoning_effort: session.reasoning_effort,
+            })
+        }))
+    }
+
+    /// The active session's stored model as `provider/model`, when it isn't
+    /// the one the agent generates with.
+    pub fn active_session_model_mismatch(
+        &self,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let Some(stored) = self.active_session_model()? else {
+            return Ok(None);
+        };
+        let matches = self.model.as_ref().is_some_and(|(provider_id, model_id)| {
+            *provider_id == stored.provider_id && *model_id == stored.model_id
+        });
+        Ok((!matches).then(|| format!("{}/{}", stored.provider_id, stored.model_id)))
+    }
+
+    pub fn set_budget(&mut self, budget: Option<BudgetTracker>) {
+        self.budget = budget;
+    }
+
+    pub fn set_webhooks(&mut self, webhooks: Option<Webhooks>) {
+        self.webhooks = webhooks;
+    }
+
+    pub fn set_jj_change_per_turn(&mut self, enabled: bool) {
+        self.jj_change_per_turn = enabled;
+    }
+
+    pub fn set_snapshot_file_contents(&mut self, enabled: bool) {
+        self.snapshot_file_contents = enabled;
+    }
+
+    /// Whether the conversation was just restored and its files haven't been
+    /// checked for changes yet. Only answers `true` once per restore.
+    pub fn take_context_check(&mut self) -> bool {
+        std::mem::take(&mut self.context_check_pending)
+    }
+
+    /// The stored summary of a session just resumed after a long gap. Only
+    /// answers once per resume.
+    pub fn take_resume_summary(&mut self) -> Option<ConversationSummary> {
+        self.resume_summary.take()
+    }
+
+    /// The active session at its current turn, unless its summary already
+    /// covers that turn.
+    pub fn active_summary_target(
+        &self,
+    ) -> Result<Option<SummaryTarget>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let mut db = self.store.lock();
+        match db.sessions().get_session(session_id)? {
+            Some(session) => summary_target(&mut db, session),
+            None => Ok(None),
+        }
+    }
+
+    /// Up to `limit` of the project's sessions, most recent first, that were
+    /// left without a summary of their current turn. Skips the active
+    /// session and ones open in another process.
+    pub fn unsummarized_sessions(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<SummaryTarget>, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        let sessions = db
+            .sessions()
+            .list_sessions_for_project(&self.project_id, UNSUMMARIZED_SCAN_LIMIT)?;
+        let mut targets = Vec::new();
+        for session in sessions {
+            if targets.len() == limit {
+                break;
+            }
+            if self.active_session_id.as_deref() == Some(session.id.as_str()) {
+                continue;
+            }
+            let open_elsewhere = db
+                .sessions()
+                .session_lock(&session.id)?
+                .is_some_and(|lock| {
+                    lock.owner != self.lock_owner
+                        && now_ms() - lock.heartbeat_at_ms
+                            < SESSION_LOCK_STALE_AFTER.as_millis() as i64
+                });
+            if open_elsewhere {
+                continue;
+            }
+            if let Some(target) = summary_target(&mut db, session)? {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    pub fn save_summary(
+        &self,
+        session_id: &str,
+        turn_id: &str,
+        summary: &ConversationSummary,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        db.sessions()
+            .set_session_summary(session_id, &serde_json::to_value(summary)?, turn_id)?;
+        Ok(())
+    }
+
+    /// With a change per turn enabled in a jj repository, describe the
+    /// working-copy change with the session title and start a new one.
+    /// Returns whether a change was closed.
+    pub fn close_jj_change(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.jj_change_per_turn {
+            return Ok(false);
+        }
+        let Some(root) = vcs::jj_root(&self.project_root) else {
+            return Ok(false);
+        };
+        let title = self
+            .active_session_title()?
+            .unwrap_or_else(|| t(Msg::UntitledSession).to_string());
+        Ok(vcs::jj_close_change(&root, &title)?)
+    }
+
+    /// Spend against the configured budget once it nears its limit. The
+    /// webhooks hear about each new warning or overrun.
+    pub fn budget_status(&mut self) -> Result<Option<BudgetStatus>, Box<dyn std::error::Error>> {
+        let Some(budget) = &mut self.budget else {
+            return Ok(None);
+        };
+        let status = budget.status(&mut self.store.lock())?;
+        let state = status
+            .as_ref()
+            .map(|status| (status.period, status.is_exceeded()));
+        if state != self.budget_notified {
+            self.budget_notified = state;
+            if let Some(status) = &status {
+                self.notify(WebhookEvent::Budget, &status.message());
+            }
+        }
+        Ok(status)
+    }
+
+    /// Send `event` to the configured webhooks along with the active
+    /// session's metadata.
+    pub fn notify(&self, event: WebhookEvent, message: &str) {
+        let Some(webhooks) = &self.webhooks else {
+            return;
+        };
+        let title = self.active_session_title().ok().flatten();
+        let session = json!({
+            "id": self.active_session_id,
+            "title": title,
+            "project_root": self.project_root,
+            "model": self
+                .model
+                .as_ref()
+                .map(|(provider, model)| format!("{provider}/{model}")),
+        });
+        webhooks.send(event, message, session);
+    }
+
+    /// Append an executed tool call to the audit log, and note the files it
+    /// touched for the turn's snapshot.
+    pub fn record_tool_call(
+        &mut self,
+        record: &ToolCallRecord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if record.succeeded {
+            self.turn_files.extend(record.files.iter().cloned());
+        }
+        self.store.lock().audit_log().append(AppendAuditInput {
+            session_id: self.active_session_id.clone(),
+            project_root: self.project_root.clone(),
+            tool_name: record.name.clone(),
+            arguments: record.arguments.clone(),
+            files: record.files.clone(),
+            exit_code: record.exit_code,
+            succeeded: record.succeeded,
+            approval: record.decision.as_str().to_string(),
+        })?;
+        Ok(())
+    }
+
+    /// Keep the full output of a tool call that was truncated for the model.
+    pub fn save_tool_output(
+        &self,
+        tool_call_id: &str,
+        content: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Err("no active session selected".into());
+        };
+        self.store
+            .lock()
+            .sessions()
+            .save_tool_output(session_id, tool_call_id, content)?;
+        Ok(())
+    }
+
+    /// Full output of the active session's most recently truncated tool call.
+    pub fn latest_tool_output(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let output = self
+            .store
+            .lock()
+            .sessions()
+            .latest_tool_output(session_id)?;
+        Ok(output.map(|(_, content)| content))
+    }
+
+    /// Remember the agent's background response until the turn finishes, so
+    /// it can be picked up again if this process exits first.
+    pub fn save_pending_response(
+        &self,
+        agent: &Agent,
+        token: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Err("no active session selected".into());
+        };
+        let state = serde_json::to_value(agent.conversation_state())?;
+        self.store
+            .lock()
+            .sessions()
+            .set_pending_response(session_id, token, &state)?;
+        Ok(())
+    }
+
+    /// Background response an earlier process left unfinished in the active
+    /// session, with the conversation state it continues.
+    pub fn pending_response(
+        &self,
+    ) -> Result<Option<(String, ConversationState)>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let pending = self.store.lock().sessions().pending_response(session_id)?;
+        let Some(pending) = pending else {
+            return Ok(None);
+        };
+        Ok(Some((
+            pending.token,
+            serde_json::from_value(pending.conversation_state)?,
+        )))
+    }
+
+    /// Record `prompt` as submitted in the active session, so it survives
+    /// the process being killed before the turn is stored.
+    pub fn begin_turn(&self, prompt: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Err("no active session selected".into());
+        };
+        self.store.lock().sessions().begin_turn(
+            session_id,
+            self.current_turn_id.as_deref(),
+            prompt,
+        )?;
+        Ok(())
+    }
+
+    /// The turn finished, failed or was cancelled: nothing is left to
+    /// resume or recover.
+    pub fn end_turn(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(());
+        };
+        let mut db = self.store.lock();
+        db.sessions().clear_pending_response(session_id)?;
+        db.sessions().clear_pending_turn(session_id)?;
+        Ok(())
+    }
+
+    /// The prompt of a turn an earlier process was killed in before storing
+    /// it, to offer again. A turn still generating in the background is
+    /// left to finish instead.
+    pub fn take_interrupted_prompt(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let mut db = self.store.lock();
+        let Some(pending) = db.sessions().pending_turn(session_id)? else {
+            return Ok(None);
+        };
+        if db.sessions().pending_response(session_id)?.is_some() {
+            return Ok(None);
+        }
+        db.sessions().clear_pending_turn(session_id)?;
+        // Once the session has moved on, the prompt is moot.
+        Ok((pending.parent_turn_id == self.current_turn_id).then_some(pending.prompt))
+    }
+
+    /// Keep the unsent composer text of the active session, with the
+    /// cursor's byte offset in it, for the next time it is opened. Nothing
+    /// is kept before the session exists.
+    pub fn save_draft(&self, text: &str, cursor: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(());
+        };
+        self.store
+            .lock()
+            .sessions()
+            .set_draft(session_id, text, cursor)?;
+        Ok(())
+    }
+
+    /// The composer text left unsent in the active session, and where the
+    /// cursor was in it.
+    pub fn draft(&self) -> Result<Option<(String, usize)>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        Ok(self.store.lock().sessions().draft(session_id)?)
+    }
+
+    /// Keep this process's session lock fresh from a background thread for
+    /// as long as the store is alive.
+    pub fn spawn_lock_heartbeat(store: &SharedSessionStore) {
+        let store = Arc::downgrade(store);
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(SESSION_LOCK_HEARTBEAT_INTERVAL);
+                let Some(store) = store.upgrade() else {
+                    return;
+                };
+                let _ = store.lock().refresh_lock();
+            }
+        });
+    }
+
+    fn refresh_lock(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(());
+        };
+        let mut db = self.store.lock();
+        db.sessions()
+            .refresh_session_lock(session_id, &self.lock_owner)?;
+        Ok(())
+    }
+
+    /// Lock `session_id` for this process and make it the active session,
+    /// releasing the lock on the previously active one.
+    fn set_active_session(
+        &mut self,
+        session_id: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        if let Some(session_id) = session_id.as_deref() {
+            db.sessions().lock_session(
+                session_id,
+                &self.lock_owner,
+                std::process::id(),
+                SESSION_LOCK_STALE_AFTER,
+            )?;
+        }
+        if let Some(previous) = self.active_session_id.as_deref()
+            && session_id.as_deref() != Some(previous)
+        {
+            // A lock that fails to release goes stale on its own.
+            let _ = db.sessions().unlock_session(previous, &self.lock_owner);
+        }
+
+        self.active_session_id = session_id;
+        self.current_turn_id = None;
+        self.turn_files.clear();
+        self.context_check_pending = false;
+        self.resume_summary = None;
+        Ok(())
+    }
+
+    pub fn project_settings(&self) -> Result<ProjectSettings, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        Ok(db
+            .sessions()
+            .get_project(&self.project_id)?
+            .map(|project| project.settings)
+            .unwrap_or_default())
+    }
+
+    pub fn update_project_settings(
+        &mut self,
+        settings: &ProjectSettings,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        db.sessions()
+            .update_project_settings(&self.project_id, settings)?;
+        Ok(())
+    }
+
+    /// This project's sessions as pickers list them: pinned ones first,
+    /// then the most recently updated.
     pub fn list_sessions(&self, limit: usize) -> Result<Vec<Session>, Box<dyn std::error::Error>> {
         let mut db = self.store.lock();
         Ok(db
             .sessions()
-            .list_sessions_for_project(&self.project_id, limit)?)
+            .list_sessions_pinned_first(&self.project_id, limit)?)
+    }
+
+    /// Bookmark the turn `turns_back` turns before the active session's
+    /// current one with an optional note. Returns `false` when there is no
+    /// such turn.
+    pub fn bookmark_turn(
+        &self,
+        turns_back: usize,
+        note: Option<&str>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let turns = self.active_turns()?;
+        let Some(turn) = turns
+            .len()
+            .checked_sub(turns_back + 1)
+            .and_then(|index| turns.get(index))
+        else {
+            return Ok(false);
+        };
+        self.store.lock().bookmarks().set(&turn.id, note)?;
+        Ok(true)
+    }
+
+    pub fn remove_bookmark(&self, turn_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.store.lock().bookmarks().remove(turn_id)?;
+        Ok(())
+    }
+
+    /// This project's bookmarks, newest first, each with the start of the
+    /// bookmarked turn's prompt.
+    pub fn bookmarks(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<(TurnBookmark, String)>, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        let bookmarks = db.bookmarks().list(Some(&self.project_id), limit)?;
+        bookmarks
+            .into_iter()
+            .map(|bookmark| {
+                let prompt = db
+                    .sessions()
+                    .get_turn(&bookmark.turn_id)?
+                    .and_then(|turn| serde_json::from_value::<Vec<UserPart>>(turn.user_parts).ok())
+                    .map(|parts| prompt_excerpt(&parts))
+                    .unwrap_or_default();
+                Ok((bookmark, prompt))
+            })
+            .collect()
+    }
+
+    /// Full-text search over this project's stored turns.
+    pub fn search_turns(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<TurnSearchHit>, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        Ok(db
+            .sessions()
+            .search_turns(Some(&self.project_id), query, limit)?)
     }
 
     pub fn active_session_id(&self) -> Option<&str> {
         self.active_session_id.as_deref()
     }
 
+    /// Where the active session's artifacts are saved, once there is one.
+    pub fn artifacts_dir(&self) -> Option<PathBuf> {
+        let session_id = self.active_session_id.as_deref()?;
+        agnt_app::session_artifacts_dir(session_id).ok()
+    }
+
+    /// The active session's artifacts as status lines, for `/artifacts`.
+    pub fn artifact_lines(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let artifacts = match self.artifacts_dir() {
+            Some(dir) => agnt_core::artifacts::list(&dir)?,
+            None => Vec::new(),
+        };
+        if artifacts.is_empty() {
+            return Ok(vec!["[no artifacts in this session yet]".to_string()]);
+        }
+        Ok(artifacts
+            .iter()
+            .map(|artifact| {
+                format!(
+                    "[{} {} · {} · {}]",
+                    artifact.id,
+                    artifact.name,
+                    crate::format_bytes(artifact.size),
+                    artifact.path.display()
+                )
+            })
+            .collect())
+    }
+
+    /// Title of the active session, once its first turn has given it one.
+    pub fn active_session_title(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(None);
+        };
+        let mut db = self.store.lock();
+        Ok(db
+            .sessions()
+            .get_session(session_id)?
+            .and_then(|session| session.title))
+    }
+
+    /// When each turn leading up to the active session's current turn was
+    /// stored, oldest first.
+    pub fn active_turn_times(&self) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        Ok(self
+            .active_turns()?
+            .into_iter()
+            .map(|turn| turn.created_at_ms)
+            .collect())
+    }
+
+    /// Turns of the active session from the root to the current one.
+    pub fn active_turns(&self) -> Result<Vec<Turn>, Box<dyn std::error::Error>> {
+        let Some(session_id) = self.active_session_id.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let mut db = self.store.lock();
+        Ok(db
+            .sessions()
+            .turn_path_to_current(session_id)?
+            .into_iter()
+            .map(|item| item.turn)
+            .collect())
+    }
+
+    pub fn rename_session(
+        &mut self,
+        session_id: &str,
+        title: &str,
+    ) -> Result<Session, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        Ok(db.sessions().rename_session(session_id, title)?)
+    }
+
+    /// Pin the session to the top of session lists, or unpin it if it is
+    /// pinned. Returns the session as it now is.
+    pub fn toggle_session_pinned(
+        &mut self,
+        session_id: &str,
+    ) -> Result<Session, Box<dyn std::error::Error>> {
+        let mut db = self.store.lock();
+        let mut sessions = db.sessions();
+        let session = sessions
+            .get_session(session_id)?
+            .ok_or_else(|| format!("session {session_id} not found"))?;
+        sessions.set_session_pinned(session_id, !session.pinned)?;
+        Ok(Session {
+            pinned: !session.pinned,
+            ..session
+        })
+    }
+
+    /// Delete a session that is not open here or in another agnt process.
+    pub fn delete_session(&mut self, session_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.active_session_id.as_deref() == Some(session_id) {
+            return Err("cannot delete the open session; start a new one first".into());
+        }
+        let mut db = self.store.lock();
+        // Taking the lock fails while another process has the session open;
+        // the lock row goes away with the session.
+        db.sessions().lock_session(
+            session_id,
+            &self.lock_owner,
+            std::process::id(),
+            SESSION_LOCK_STALE_AFTER,
+        )?;
+        db.sessions().delete_session(session_id)?;
+        drop(db);
+        if let Ok(dir) = agnt_app::session_artifacts_dir(session_id)
+            && let Some(session_dir) = dir.parent()
+        {
+            // Best effort: the session itself is gone either way.
+            let _ = std::fs::remove_dir_all(session_dir);
+        }
+        Ok(())
+    }
+
     pub fn clear_active_session(&mut self) {
-        self.active_session_id = None;
+        // Clearing takes no lock, so this cannot fail.
+        let _ = self.set_active_session(None);
     }
 
     pub fn create_session(
@@ -59,10 +754,11 @@ impl SessionStore {
             db.sessions().create_session(CreateSessionInput {
                 project_id: self.project_id.clone(),
                 title,
+                worktree_dir: self.worktree_dir.clone(),
             })?
         };
 
-        self.active_session_id = Some(session.id.clone());
+        self.set_active_session(Some(session.id.clone()))?;
         Ok(session)
     }
 
@@ -92,10 +788,38 @@ impl SessionStore {
             .into());
         }
 
-        self.active_session_id = Some(session.id.clone());
+        self.set_active_session(Some(session.id.clone()))?;
+        self.load_active_conversation_state()
+    }
+
+    /// Activate `session_id` checked out at `turn_id`, so the conversation
+    /// continues from that turn.
+    pub fn activate_turn(
+        &mut self,
+        session_id: &str,
+        turn_id: &str,
+    ) -> Result<Option<ConversationState>, Box<dyn std::error::Error>> {
+        self.activate_session(session_id)?;
+        {
+            let mut db = self.store.lock();
+            db.sessions().checkout_turn(session_id, turn_id)?;
+        }
         self.load_active_conversation_state()
     }
 
+    /// Activate the session to start with. Returns the conversation to
+    /// restore, if any.
+    pub fn open_startup_session(
+        &mut self,
+        startup: &StartupSession,
+    ) -> Result<Option<ConversationState>, Box<dyn std::error::Error>> {
+        match startup {
+            StartupSession::New | StartupSession::Pick => Ok(None),
+            StartupSession::Continue => self.resume_most_recent_session(),
+            StartupSession::Resume(session_id) => self.activate_session(session_id),
+        }
+    }
+
     pub fn resume_most_recent_session(
         &mut self,
     ) -> Result<Option<ConversationState>, Box<dyn std::error::Error>> {
@@ -112,7 +836,7 @@ impl SessionStore {
             return Ok(None);
         };
 
-        self.active_session_id = Some(session_id);
+        self.set_active_session(Some(session_id))?;
         self.load_active_conversation_state()
     }
 
@@ -123,41 +847,159 @@ impl SessionStore {
             return Ok(None);
         };
 
-        let turn = {
+        let (session, turn) = {
             let mut db = self.store.lock();
-            db.sessions().current_turn(session_id)?
+            (
+                db.sessions().get_session(session_id)?,
+                db.sessions().current_turn(session_id)?,
+            )
         };
+        self.current_turn_id = turn.as_ref().map(|turn| turn.id.clone());
 
         let Some(turn) = turn else {
             return Ok(None);
         };
 
+        self.context_check_pending = true;
+        let idle = Duration::from_millis((now_ms() - turn.created_at_ms).max(0) as u64);
+        self.resume_summary = session
+            .filter(|session| {
+                idle >= RESUME_SUMMARY_AFTER
+                    && session.summary_turn_id.as_deref() == Some(turn.id.as_str())
+            })
+            .as_ref()
+            .and_then(session_summary);
         Ok(Some(serde_json::from_value(turn.conversation_state)?))
     }
 
+    /// Rewind the latest turn so it can be generated again.
+    ///
+    /// A turn that never made it into the database (e.g. it failed midway)
+    /// is dropped from `messages`; otherwise the session is checked out at
+    /// the parent turn. Returns the state to restore and the user text to
+    /// re-submit, or `None` if there is nothing to retry.
+    pub fn prepare_retry(
+        &mut self,
+        messages: &[Message],
+    ) -> Result<Option<RetryTurn>, Box<dyn std::error::Error>> {
+        let current_turn = match self.active_session_id.as_deref() {
+            Some(session_id) => self.store.lock().sessions().current_turn(session_id)?,
+            None => None,
+        };
+        let stored_state: Option<ConversationState> = current_turn
+            .as_ref()
+            .map(|turn| serde_json::from_value(turn.conversation_state.clone()))
+            .transpose()?;
+        let stored_len = stored_state
+            .as_ref()
+            .map_or(0, |state| state.messages.len());
+
+        if messages.len() > stored_len {
+            return Ok(RetryTurn::rewind(messages));
+        }
+
+        let (Some(session_id), Some(turn), Some(stored_state)) =
+            (self.active_session_id.clone(), current_turn, stored_state)
+        else {
+            return Ok(None);
+        };
+        let Some(mut retry) = RetryTurn::rewind(&stored_state.messages) else {
+            return Ok(None);
+        };
+
+        let mut db = self.store.lock();
+        match turn.parent_turn_id.as_deref() {
+            Some(parent_turn_id) => {
+                let parent = db
+                    .sessions()
+                    .get_turn(parent_turn_id)?
+                    .ok_or_else(|| format!("turn not found: {parent_turn_id}"))?;
+                db.sessions().checkout_turn(&session_id, parent_turn_id)?;
+                retry.state = serde_json::from_value(parent.conversation_state)?;
+                self.current_turn_id = Some(parent_turn_id.to_string());
+            }
+            None => {
+                db.sessions().clear_current_turn(&session_id)?;
+                self.current_turn_id = None;
+            }
+        }
+
+        Ok(Some(retry))
+    }
+
     pub fn persist_turn_from_agent(
         &mut self,
         agent: &Agent,
         usage: &Usage,
+        stats: &TurnStats,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let Some(session_id) = self.active_session_id.clone() else {
             return Err("no active session selected".into());
         };
 
         let snapshot = agent.conversation_state();
+        // Record which model produced the turn so `agnt stats` can attribute
+        // tokens and cost.
+        let mut usage = serde_json::to_value(usage)?;
+        if let Value::Object(fields) = &mut usage
+            && let Some((provider_id, model_id)) = &self.model
+        {
+            fields.insert("provider".into(), provider_id.as_str().into());
+            fields.insert("model".into(), model_id.as_str().into());
+        }
+        // And how long it took, so `agnt stats` can point out slow models
+        // and tools.
+        if let Value::Object(fields) = &mut usage {
+            fields.insert(
+                "duration_ms".into(),
+                (stats.duration.as_millis() as u64).into(),
+            );
+            fields.insert("timings".into(), serde_json::to_value(&stats.timings)?);
+        }
         let (user_parts, assistant_parts) = extract_latest_turn_parts(&snapshot.messages)?;
         let session_title = derive_session_title(&snapshot.messages);
 
         let mut db = self.store.lock();
-        db.sessions().append_turn(AppendTurnInput {
+        // Re-taking the lock refreshes it, or fails if another process took
+        // it over while this one was unresponsive.
+        db.sessions().lock_session(
+            &session_id,
+            &self.lock_owner,
+            std::process::id(),
+            SESSION_LOCK_STALE_AFTER,
+        )?;
+
+        let mut input = AppendTurnInput {
             session_id: session_id.clone(),
-            parent_turn_id: None,
+            parent_turn_id: self.current_turn_id.clone(),
+            expected_current_turn_id: self.current_turn_id.clone(),
             user_parts,
             assistant_parts,
             conversation_state: serde_json::to_value(&snapshot)?,
-            usage: Some(serde_json::to_value(usage)?),
-        })?;
+            usage: Some(usage),
+            file_snapshots: (!self.turn_files.is_empty())
+                .then(|| snapshots::capture(&self.turn_files, self.snapshot_file_contents)),
+        };
+        let turn = match db.sessions().append_turn(input.clone()) {
+            // Someone else moved the session: keep both lines of work by
+            // saving this turn as a branch off the turn it continues.
+            Err(agnt_db::Error::CurrentTurnConflict { .. }) => {
+                input.expected_current_turn_id = None;
+                db.sessions().append_turn(input)?
+            }
+            result => result?,
+        };
+        self.current_turn_id = Some(turn.id);
+        self.turn_files.clear();
 
+        if let Some((provider_id, model_id)) = &self.model {
+            db.sessions().set_session_model(
+                &session_id,
+                provider_id,
+                model_id,
+                self.reasoning_effort.as_deref(),
+            )?;
+        }
         if let Some(title) = session_title.as_deref() {
             db.sessions()
                 .set_session_title_if_missing(&session_id, title)?;
@@ -167,11 +1009,177 @@ impl SessionStore {
     }
 }
 
+impl Drop for SessionStore {
+    fn drop(&mut self) {
+        if let Some(session_id) = self.active_session_id.as_deref() {
+            let _ = self
+                .store
+                .lock()
+                .sessions()
+                .unlock_session(session_id, &self.lock_owner);
+        }
+    }
+}
+
+/// A turn rewound by [`SessionStore::prepare_retry`].
+pub struct RetryTurn {
+    /// Conversation state from before the turn.
+    pub state: ConversationState,
+    /// The user's original prompt, without attachments.
+    pub user_text: String,
+}
+
+impl RetryTurn {
+    /// Split `messages` right before the last user message.
+    pub fn rewind(messages: &[Message]) -> Option<Self> {
+        let user_idx = messages
+            .iter()
+            .rposition(|m| matches!(m, Message::User { .. }))?;
+        let Message::User { parts } = &messages[user_idx] else {
+            return None;
+        };
+
+        let user_text = parts
+            .iter()
+            .filter_map(|part| match part {
+                UserPart::Text(text) if attachment_summary(part).is_none() => {
+                    Some(text.text.as_str())
+                }
+                UserPart::Text(_) | UserPart::Image(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if user_text.trim().is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            state: ConversationState {
+                messages: messages[..user_idx].to_vec(),
+            },
+            user_text,
+        })
+    }
+}
+
 pub fn session_label(session: &Session) -> String {
+    let mut updated = relative_time(session.updated_at_ms);
+    if let Some(worktree) = &session.worktree_dir {
+        updated.push_str(&format!(" · in {}", worktree.display()));
+    }
+    let pin = if session.pinned { "★ " } else { "" };
     if let Some(title) = &session.title {
-        return format!("{title} ({})", session.id);
+        return format!("{pin}{title} ({}) · {updated}", session.id);
     }
-    format!("Session {}", session.id)
+    format!("{pin}Session {} · {updated}", session.id)
+}
+
+/// The summary stored on `session`, if any.
+pub fn session_summary(session: &Session) -> Option<ConversationSummary> {
+    serde_json::from_value(session.summary.clone()?).ok()
+}
+
+/// The summary stored on `session` as the session picker shows it: the
+/// summary, then a line per decision.
+pub fn summary_preview(session: &Session) -> Option<String> {
+    let summary = session_summary(session)?;
+    let mut preview = summary.summary.trim().to_string();
+    for decision in &summary.decisions {
+        preview.push_str(&format!("\n- {}", decision.trim()));
+    }
+    Some(preview)
+}
+
+/// `session` at its current turn, unless its summary already covers it.
+fn summary_target(
+    db: &mut Store,
+    session: Session,
+) -> Result<Option<SummaryTarget>, Box<dyn std::error::Error>> {
+    let Some(turn_id) = session.current_turn_id else {
+        return Ok(None);
+    };
+    if session.summary_turn_id.as_deref() == Some(turn_id.as_str()) {
+        return Ok(None);
+    }
+    let Some(turn) = db.sessions().current_turn(&session.id)? else {
+        return Ok(None);
+    };
+    Ok(Some(SummaryTarget {
+        session_id: session.id,
+        turn_id: turn.id,
+        state: serde_json::from_value(turn.conversation_state)?,
+    }))
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// How long ago `ms` was, e.g. "2 hours ago".
+pub fn relative_time(ms: i64) -> String {
+    let seconds = (now_ms() - ms).max(0) / 1000;
+    let (count, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (seconds / 60, "minute"),
+        3_600..86_400 => (seconds / 3_600, "hour"),
+        86_400..2_592_000 => (seconds / 86_400, "day"),
+        2_592_000..31_536_000 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+/// Shown while picking up a background response an earlier run left behind.
+pub const RESUMING_RESPONSE: &str = "[resuming the response interrupted in the last run]";
+
+/// Shown when the last run was killed before a submitted prompt's turn was
+/// stored.
+pub const INTERRUPTED_PROMPT: &str =
+    "[the last prompt was cut off before its turn was stored; it is back in the composer]";
+
+/// Shown after a tool result that was truncated before the model saw it.
+pub const RAW_OUTPUT_SAVED: &str = "[output truncated for the model; /raw shows all of it]";
+
+pub fn search_hit_label(hit: &TurnSearchHit) -> String {
+    let title = hit
+        .session_title
+        .as_deref()
+        .unwrap_or(t(Msg::UntitledSession));
+    let snippet = hit.snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{title} · {}: {snippet}", relative_time(hit.created_at_ms))
+}
+
+/// A bookmark as `/bookmarks` lists it: its note, or else the start of the
+/// turn's `prompt`, then the session and when it was bookmarked.
+pub fn bookmark_label(bookmark: &TurnBookmark, prompt: &str) -> String {
+    let title = bookmark
+        .session_title
+        .as_deref()
+        .unwrap_or(t(Msg::UntitledSession));
+    let what = bookmark.note.as_deref().unwrap_or(prompt);
+    format!(
+        "{what} · {title} · {}",
+        relative_time(bookmark.created_at_ms)
+    )
+}
+
+/// The prompt text of a user message, without its attachments, on one line
+/// and cut to [`BOOKMARK_PROMPT_CHARS`].
+fn prompt_excerpt(parts: &[UserPart]) -> String {
+    let text = parts
+        .iter()
+        .filter_map(|part| match part {
+            UserPart::Text(text) if attachment_summary(part).is_none() => Some(text.text.as_str()),
+            UserPart::Text(_) | UserPart::Image(_) => None,
+        })
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+    truncate_with_ellipsis(&text, BOOKMARK_PROMPT_CHARS)
 }
 
 fn extract_latest_turn_parts(
@@ -209,8 +1217,8 @@ fn derive_session_title(messages: &[Message]) -> Option<String> {
     let title_text = first_user_parts
         .iter()
         .filter_map(|part| match part {
-            UserPart::Text(text) => Some(text.text.trim()),
-            UserPart::Image(_) => None,
+            UserPart::Text(text) if attachment_summary(part).is_none() => Some(text.text.trim()),
+            UserPart::Text(_) | UserPart::Image(_) => None,
         })
         .filter(|text| !text.is_empty())
         .collect::<Vec<_>>()
@@ -229,7 +1237,7 @@ fn derive_session_title(messages: &[Message]) -> Option<String> {
     Some(truncate_with_ellipsis(&normalized, SESSION_TITLE_MAX_CHARS))
 }
 
-fn truncate_with_ellipsis(input: &str, max_chars: usize) -> String {
+pub fn truncate_with_ellipsis(input: &str, max_chars: usize) -> String {
     if input.chars().count() <= max_chars {
         return input.to_string();
     }
@@ -0,0 +1,324 @@
+//! Share a session as one self-contained HTML file, for posting in a PR or
+//! docs: the prompts and answers, with reasoning and tool calls collapsed
+//! and diffs colored. Styles are inline and nothing is loaded from
+//! elsewhere, so the file can be attached or hosted as is.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use agnt_core::{EventTiming, attachment_summary};
+use agnt_db::Turn;
+use agnt_llm::{AssistantPart, ToolDisplayBodyPart, UserPart};
+
+use crate::session::SessionStore;
+use crate::stats;
+
+const STYLE: &str = "
+body { margin: 0 auto; max-width: 860px; padding: 24px; font: 15px/1.5 system-ui, sans-serif; color: #1f2328; background: #fff; }
+h1 { font-size: 22px; margin: 0 0 4px; }
+.meta { color: #656d76; font-size: 13px; margin-bottom: 24px; }
+.turn { border-top: 1px solid #d0d7de; padding: 16px 0; }
+.role { font-size: 12px; font-weight: 600; text-transform: uppercase; color: #656d76; margin: 8px 0 4px; }
+.text { white-space: pre-wrap; overflow-wrap: anywhere; }
+.user .text { background: #f6f8fa; border-radius: 6px; padding: 8px 12px; }
+.attachment { color: #656d76; font-size: 13px; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: 8px 0; }
+summary { cursor: pointer; padding: 4px 10px; font-size: 13px; color: #656d76; }
+details > .text, details > pre { margin: 0; padding: 8px 10px; border-top: 1px solid #d0d7de; }
+pre { font: 12px/1.45 ui-monospace, SFMono-Regular, Menlo, monospace; overflow-x: auto; background: #f6f8fa; border-radius: 6px; padding: 8px 10px; }
+.diff span { display: block; }
+.diff .add { background: #dafbe1; color: #116329; }
+.diff .del { background: #ffebe9; color: #82071e; }
+.diff .hunk { color: #0550ae; }
+.diff .file { font-weight: 600; }
+img { max-width: 100%; border-radius: 6px; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  .meta, .role, .attachment, summary { color: #8d96a0; }
+  .turn, details, details > .text, details > pre { border-color: #30363d; }
+  pre, .user .text { background: #161b22; }
+  .diff .add { background: #12261e; color: #3fb950; }
+  .diff .del { background: #25171c; color: #f85149; }
+  .diff .hunk { color: #79c0ff; }
+}
+";
+
+/// A page written by [`export_active_session`].
+pub struct SharedPage {
+    pub path: PathBuf,
+}
+
+impl SharedPage {
+    /// What to tell the user after exporting.
+    pub fn summary(&self) -> String {
+        format!(
+            "[session shared as {}; a single HTML file with everything inline]",
+            self.path.display()
+        )
+    }
+}
+
+/// Write the active session's turns as an HTML page to the user data dir.
+/// `None` when the session has no turns yet.
+pub fn export_active_session(
+    store: &SessionStore,
+) -> Result<Option<SharedPage>, Box<dyn std::error::Error>> {
+    let Some(session_id) = store.active_session_id() else {
+        return Ok(None);
+    };
+    let turns = store.active_turns()?;
+    if turns.is_empty() {
+        return Ok(None);
+    }
+    let title = store
+        .active_session_title()?
+        .unwrap_or_else(|| "agnt session".to_string());
+
+    let dir = agnt_app::ensure_user_data_dir()?.join("shared");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{session_id}.html"));
+    std::fs::write(&path, render(&title, session_id, &turns))?;
+    Ok(Some(SharedPage { path }))
+}
+
+fn render(title: &str, session_id: &str, turns: &[Turn]) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<div class=\"meta\">agnt session {session_id} · {} turn(s)</div>\n",
+        turns.len(),
+        title = escape(title),
+        session_id = escape(session_id),
+    );
+    for turn in turns {
+        html.push_str("<section class=\"turn\">\n");
+        render_user(&mut html, turn);
+        render_assistant(&mut html, turn);
+        render_timing(&mut html, turn);
+        html.push_str("</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_user(html: &mut String, turn: &Turn) {
+    let parts =
+        serde_json::from_value::<Vec<UserPart>>(turn.user_parts.clone()).unwrap_or_default();
+    html.push_str("<div class=\"user\">\n<div class=\"role\">You</div>\n");
+    for part in &parts {
+        if let Some(summary) = attachment_summary(part) {
+            let _ = writeln!(html, "<div class=\"attachment\">{}</div>", escape(summary));
+            continue;
+        }
+        match part {
+            UserPart::Text(text) => {
+                let _ = writeln!(html, "<div class=\"text\">{}</div>", escape(&text.text));
+            }
+            UserPart::Image(image) => render_image(html, &image.url),
+        }
+    }
+    html.push_str("</div>\n");
+}
+
+fn render_assistant(html: &mut String, turn: &Turn) {
+    let parts = serde_json::from_value::<Vec<AssistantPart>>(turn.assistant_parts.clone())
+        .unwrap_or_default();
+    html.push_str("<div class=\"assistant\">\n<div class=\"role\">agnt</div>\n");
+    for part in &parts {
+        match part {
+            AssistantPart::Text(text) => {
+                let _ = writeln!(html, "<div class=\"text\">{}</div>", escape(&text.text));
+            }
+            AssistantPart::Reasoning(reasoning) => {
+                let Some(text) = reasoning.text.as_deref().filter(|text| !text.is_empty()) else {
+                    continue;
+                };
+                let _ = writeln!(
+                    html,
+                    "<details><summary>Reasoning</summary><div class=\"text\">{}</div></details>",
+                    escape(text)
+                );
+            }
+            AssistantPart::ToolCall(call) => {
+                let display = call.display.as_ref();
+                let title = display
+                    .and_then(|display| display.result.as_ref())
+                    .map(|result| result.title.as_str())
+                    .or(display.map(|display| display.title.as_str()))
+                    .unwrap_or(&call.name);
+                let body = display
+                    .and_then(|display| display.result.as_ref())
+                    .and_then(|result| result.body.as_ref());
+                let _ = write!(html, "<details><summary>{}</summary>", escape(title));
+                match body {
+                    Some(ToolDisplayBodyPart::Diff(diff)) => render_diff(html, diff),
+                    Some(ToolDisplayBodyPart::Code { content, .. }) => {
+                        let _ = write!(html, "<pre>{}</pre>", escape(content));
+                    }
+                    Some(ToolDisplayBodyPart::Text(text)) => {
+                        let _ = write!(html, "<div class=\"text\">{}</div>", escape(text));
+                    }
+                    None => {
+                        let _ = write!(html, "<pre>{}</pre>", escape(&call.arguments));
+                    }
+                }
+                html.push_str("</details>\n");
+            }
+            AssistantPart::Image(image) => render_image(html, &image.url),
+        }
+    }
+    html.push_str("</div>\n");
+}
+
+/// How long the turn took, for turns stored with their timings: the
+/// latency to the first token and the slowest tool call.
+fn render_timing(html: &mut String, turn: &Turn) {
+    let usage = turn.usage.as_ref();
+    let Some(duration_ms) = usage
+        .and_then(|usage| usage.get("duration_ms"))
+        .and_then(serde_json::Value::as_u64)
+    else {
+        return;
+    };
+    let timings = stats::turn_timings(usage);
+    let mut line = format!("{:.1}s", duration_ms as f64 / 1000.0);
+    if let Some(first_token) = timings.iter().find_map(|timing| match timing {
+        EventTiming::Response { first_token, .. } => Some(first_token),
+        EventTiming::Tool { .. } => None,
+    }) {
+        let _ = write!(line, " · first token {:.1}s", first_token.as_secs_f64());
+    }
+    if let Some((name, duration)) = timings
+        .iter()
+        .filter_map(|timing| match timing {
+            EventTiming::Tool { name, duration, .. } => Some((name, duration)),
+            EventTiming::Response { .. } => None,
+        })
+        .max_by_key(|(_, duration)| **duration)
+    {
+        let _ = write!(
+            line,
+            " · slowest tool {name} {:.1}s",
+            duration.as_secs_f64()
+        );
+    }
+    let _ = writeln!(html, "<div class=\"meta\">{}</div>", escape(&line));
+}
+
+/// A diff with each line classed by what it is, for the stylesheet to color.
+fn render_diff(html: &mut String, diff: &str) {
+    html.push_str("<pre class=\"diff\">");
+    for line in diff.lines() {
+        let class = if line.starts_with("+++") || line.starts_with("---") {
+            "file"
+        } else if line.starts_with('+') {
+            "add"
+        } else if line.starts_with('-') {
+            "del"
+        } else if line.starts_with("@@") {
+            "hunk"
+        } else {
+            "context"
+        };
+        let _ = write!(html, "<span class=\"{class}\">{}</span>", escape(line));
+    }
+    html.push_str("</pre>");
+}
+
+/// Images stay inline when they are `data:` URLs; remote ones are linked
+/// rather than loaded.
+fn render_image(html: &mut String, url: &str) {
+    if url.starts_with("data:image/") {
+        let _ = writeln!(html, "<img src=\"{}\" alt=\"image\">", escape(url));
+    } else if url.starts_with("https://") || url.starts_with("http://") {
+        let url = escape(url);
+        let _ = writeln!(html, "<div><a href=\"{url}\">{url}</a></div>");
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_text_and_classes_diff_lines() {
+        let turn = Turn {
+            id: "t1".to_string(),
+            session_id: "s1".to_string(),
+            parent_turn_id: None,
+            user_parts: serde_json::json!([{"Text": {"text": "fix <main>"}}]),
+            assistant_parts: serde_json::json!([
+                {"Reasoning": {"text": "thinking"}},
+                {"ToolCall": {
+                    "id": "c1",
+                    "name": "edit",
+                    "arguments": "{}",
+                    "display": {
+                        "title": "Edit src/main.rs",
+                        "result": {
+                            "title": "Edited src/main.rs",
+                            "body": {"Diff": "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-old\n+new"}
+                        }
+                    }
+                }},
+                {"Text": {"text": "Done & dusted."}}
+            ]),
+            conversation_state: serde_json::Value::Null,
+            usage: None,
+            created_at_ms: 0,
+            file_snapshots: None,
+        };
+        let html = render("Fix \"main\"", "s1", &[turn]);
+        assert!(html.contains("<title>Fix &quot;main&quot;</title>"));
+        assert!(html.contains("fix &lt;main&gt;"));
+        assert!(html.contains("<details><summary>Reasoning</summary>"));
+        assert!(html.contains("<summary>Edited src/main.rs</summary>"));
+        assert!(html.contains("<span class=\"del\">-old</span><span class=\"add\">+new</span>"));
+        assert!(html.contains("Done &amp; dusted."));
+    }
+
+    #[test]
+    fn annotates_turns_with_their_timings() {
+        let turn = Turn {
+            id: "t1".to_string(),
+            session_id: "s1".to_string(),
+            parent_turn_id: None,
+            user_parts: serde_json::json!([{"Text": {"text": "build it"}}]),
+            assistant_parts: serde_json::json!([{"Text": {"text": "Built."}}]),
+            conversation_state: serde_json::Value::Null,
+            usage: Some(serde_json::json!({
+                "input_tokens": 10,
+                "output_tokens": 2,
+                "duration_ms": 4200,
+                "timings": [
+                    {"kind": "response", "start_ms": 0, "first_token_ms": 800, "duration_ms": 1000},
+                    {"kind": "tool", "name": "bash", "start_ms": 1000, "duration_ms": 2100},
+                    {"kind": "tool", "name": "read", "start_ms": 3100, "duration_ms": 100},
+                ],
+            })),
+            created_at_ms: 0,
+            file_snapshots: None,
+        };
+        let html = render("Build", "s1", &[turn]);
+        assert!(html.contains(
+            "<div class=\"meta\">4.2s · first token 0.8s · slowest tool bash 2.1s</div>"
+        ));
+    }
+}
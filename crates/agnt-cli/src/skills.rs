@@ -0,0 +1,649 @@
+//! `agnt skill`: skills installed into `.agents/skills` from git
+//! repositories or the configured registry. Each install is pinned to a
+//! commit and a checksum of its files in `.agents/skills.lock`, meant to be
+//! committed, so `agnt skill install` without a source gives everyone on a
+//! team the same skills.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::session::now_ms;
+
+const LOCK_FILE: &str = "skills.lock";
+
+/// Where an installed skill came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LockedSkill {
+    /// Git URL or path of the repository.
+    source: String,
+    /// Name of the skill in the registry, if it was installed from there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    registry_name: Option<String>,
+    /// Branch, tag or commit asked for, which `update` follows. Unset
+    /// follows the repository's default branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rev: Option<String>,
+    /// Directory of the skill in the repository, unset for its root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Commit installed.
+    commit: String,
+    /// Checksum of the installed files, see [`checksum`].
+    sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SkillLock {
+    #[serde(default)]
+    skills: BTreeMap<String, LockedSkill>,
+}
+
+/// A skill in the registry's index.
+#[derive(Debug, Deserialize)]
+struct RegistryEntry {
+    source: String,
+    #[serde(default)]
+    rev: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    /// Checksum the installed files must have.
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// Where skills are fetched from.
+pub struct Sources<'a> {
+    /// URL of the registry's index.
+    pub registry: Option<&'a str>,
+    pub offline: bool,
+}
+
+/// What `agnt skill install <source>` installs.
+pub struct InstallRequest<'a> {
+    /// Git URL or path of a repository, or a name in the registry.
+    pub source: &'a str,
+    pub rev: Option<&'a str>,
+    /// Directory of the skill in the repository.
+    pub path: Option<&'a str>,
+    /// Name to install the skill under.
+    pub name: Option<&'a str>,
+    /// Checksum the installed files must have.
+    pub sha256: Option<&'a str>,
+}
+
+/// The skills of the workspace at `root`, with their lock file.
+struct Workspace {
+    skills_dir: PathBuf,
+    lock_path: PathBuf,
+    lock: SkillLock,
+}
+
+impl Workspace {
+    fn open(root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let agents_dir = root.join(".agents");
+        let lock_path = agents_dir.join(LOCK_FILE);
+        let lock = match fs::read_to_string(&lock_path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| format!("{} is invalid: {err}", lock_path.display()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => SkillLock::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            skills_dir: agents_dir.join("skills"),
+            lock_path,
+            lock,
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.lock.skills.is_empty() {
+            match fs::remove_file(&self.lock_path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                _ => return Ok(()),
+            }
+        }
+        let mut contents = serde_json::to_string_pretty(&self.lock)?;
+        contents.push('\n');
+        fs::write(&self.lock_path, contents)?;
+        Ok(())
+    }
+
+    /// Whether the installed files of `name` differ from what was
+    /// installed; `None` when it isn't installed.
+    fn modified(
+        &self,
+        name: &str,
+        locked: &LockedSkill,
+    ) -> Result<Option<bool>, Box<dyn std::error::Error>> {
+        let dir = self.skills_dir.join(name);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        Ok(Some(checksum(&dir)? != locked.sha256))
+    }
+
+    /// Replace the skill `name` with the files in `from`.
+    fn place(&self, name: &str, from: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.skills_dir)?;
+        let staging = self.skills_dir.join(format!(".{name}.installing"));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        copy_dir(from, &staging)?;
+        let target = self.skills_dir.join(name);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::rename(&staging, &target)?;
+        Ok(())
+    }
+}
+
+/// Install the skill `request` names, or with `None`, every skill in the
+/// lock file at its pinned commit.
+pub async fn install(
+    root: &Path,
+    sources: &Sources<'_>,
+    request: Option<&InstallRequest<'_>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut workspace = Workspace::open(root)?;
+    let Some(request) = request else {
+        return restore(&workspace, sources);
+    };
+
+    let (locked, expected) = if is_registry_name(request.source) {
+        let entry = registry_entry(sources, request.source).await?;
+        let locked = LockedSkill {
+            source: entry.source,
+            registry_name: Some(request.source.to_string()),
+            rev: request.rev.map(str::to_string).or(entry.rev),
+            path: request.path.map(str::to_string).or(entry.path),
+            commit: String::new(),
+            sha256: String::new(),
+        };
+        (locked, request.sha256.map(str::to_string).or(entry.sha256))
+    } else {
+        let locked = LockedSkill {
+            source: request.source.to_string(),
+            registry_name: None,
+            rev: request.rev.map(str::to_string),
+            path: request.path.map(str::to_string),
+            commit: String::new(),
+            sha256: String::new(),
+        };
+        (locked, request.sha256.map(str::to_string))
+    };
+    let name = match request.name {
+        Some(name) => name.to_string(),
+        None => default_name(&locked),
+    };
+    check_name(&name)?;
+    if workspace.skills_dir.join(&name).exists() {
+        return Err(match workspace.lock.skills.get(&name) {
+            Some(_) => format!(
+                "{name} is already installed; use `agnt skill update {name}`, or remove it first"
+            ),
+            None => format!(
+                "{} already exists and wasn't installed by `agnt skill`; remove it first",
+                workspace.skills_dir.join(&name).display()
+            ),
+        }
+        .into());
+    }
+
+    let locked = fetch_and_place(&workspace, sources, &name, locked, expected.as_deref())?;
+    println!(
+        "Installed {name} from {} at {}.",
+        locked.source,
+        short(&locked.commit)
+    );
+    workspace.lock.skills.insert(name, locked);
+    workspace.save()
+}
+
+/// Install the locked skills that are missing, at their pinned commits.
+fn restore(workspace: &Workspace, sources: &Sources<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    if workspace.lock.skills.is_empty() {
+        return Err(format!(
+            "{} lists no skills; install one with `agnt skill install <source>`",
+            workspace.lock_path.display()
+        )
+        .into());
+    }
+    for (name, locked) in &workspace.lock.skills {
+        match workspace.modified(name, locked)? {
+            Some(false) => println!("{name} is up to date."),
+            Some(true) => {
+                return Err(format!(
+                    "{name} was changed since it was installed; remove and reinstall it to discard the changes"
+                )
+                .into());
+            }
+            None => {
+                let pinned = LockedSkill {
+                    rev: Some(locked.commit.clone()),
+                    ..locked.clone()
+                };
+                fetch_and_place(workspace, sources, name, pinned, Some(&locked.sha256))?;
+                println!("Installed {name} at {}.", short(&locked.commit));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Move the skills `names`, or all installed ones, to the latest commit of
+/// the revision they follow.
+pub async fn update(
+    root: &Path,
+    sources: &Sources<'_>,
+    names: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut workspace = Workspace::open(root)?;
+    for name in names {
+        if !workspace.lock.skills.contains_key(name) {
+            return Err(format!("{name} wasn't installed by `agnt skill`").into());
+        }
+    }
+    let names: Vec<String> = if names.is_empty() {
+        workspace.lock.skills.keys().cloned().collect()
+    } else {
+        names.to_vec()
+    };
+    if names.is_empty() {
+        println!("No skills installed.");
+        return Ok(());
+    }
+
+    for name in names {
+        let locked = workspace.lock.skills[&name].clone();
+        if workspace.modified(&name, &locked)? == Some(true) {
+            return Err(format!(
+                "{name} was changed since it was installed; remove and reinstall it to discard the changes"
+            )
+            .into());
+        }
+        let (wanted, expected) = match &locked.registry_name {
+            Some(registry_name) => {
+                let entry = registry_entry(sources, registry_name).await?;
+                let wanted = LockedSkill {
+                    source: entry.source,
+                    rev: entry.rev,
+                    path: entry.path,
+                    ..locked.clone()
+                };
+                (wanted, entry.sha256)
+            }
+            None => (locked.clone(), None),
+        };
+        let previous = locked.commit;
+        let updated = fetch_and_place(&workspace, sources, &name, wanted, expected.as_deref())?;
+        if updated.commit == previous {
+            println!("{name} is up to date.");
+        } else {
+            println!(
+                "Updated {name} from {} to {}.",
+                short(&previous),
+                short(&updated.commit)
+            );
+        }
+        workspace.lock.skills.insert(name, updated);
+    }
+    workspace.save()
+}
+
+pub fn list(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = Workspace::open(root)?;
+    let mut names: Vec<String> = workspace.lock.skills.keys().cloned().collect();
+    if let Ok(entries) = fs::read_dir(&workspace.skills_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with('.')
+                && entry.path().join("SKILL.md").is_file()
+                && !names.contains(&name)
+            {
+                names.push(name);
+            }
+        }
+    }
+    if names.is_empty() {
+        println!("No skills in {}.", workspace.skills_dir.display());
+        return Ok(());
+    }
+    names.sort();
+
+    for name in names {
+        let Some(locked) = workspace.lock.skills.get(&name) else {
+            println!("{name:<24} local");
+            continue;
+        };
+        let state = match workspace.modified(&name, locked)? {
+            Some(false) => "ok",
+            Some(true) => "modified",
+            None => "missing",
+        };
+        let source = match &locked.registry_name {
+            Some(registry_name) => format!("{registry_name} (registry)"),
+            None => locked.source.clone(),
+        };
+        println!("{name:<24} {state:<8} {source} @ {}", short(&locked.commit));
+    }
+    Ok(())
+}
+
+pub fn remove(root: &Path, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    check_name(name)?;
+    let mut workspace = Workspace::open(root)?;
+    let dir = workspace.skills_dir.join(name);
+    let locked = workspace.lock.skills.remove(name).is_some();
+    if !locked && !dir.exists() {
+        return Err(format!("no skill named {name}").into());
+    }
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    workspace.save()?;
+    println!("Removed {name}.");
+    Ok(())
+}
+
+/// Check out `wanted.rev` of `wanted.source`, check the skill's checksum
+/// against `expected` and install it as `name`. Returns the lock entry
+/// with the commit and checksum filled in.
+fn fetch_and_place(
+    workspace: &Workspace,
+    sources: &Sources<'_>,
+    name: &str,
+    mut wanted: LockedSkill,
+    expected: Option<&str>,
+) -> Result<LockedSkill, Box<dyn std::error::Error>> {
+    check_source(&wanted.source)?;
+    if let Some(rev) = &wanted.rev
+        && rev.starts_with('-')
+    {
+        return Err(format!("`{rev}` is not a valid revision").into());
+    }
+    if sources.offline && !Path::new(&wanted.source).exists() {
+        return Err("fetching skills needs the network and is unavailable in offline mode".into());
+    }
+    let checkout = TempDir::new()?;
+    git(
+        None,
+        &[
+            "clone",
+            "--quiet",
+            "--no-checkout",
+            "--",
+            &wanted.source,
+            &checkout.0.to_string_lossy(),
+        ],
+    )?;
+    let commit = resolve_rev(&checkout.0, wanted.rev.as_deref())?;
+    git(
+        Some(&checkout.0),
+        &["checkout", "--quiet", "--detach", &commit],
+    )?;
+
+    let skill_dir = match &wanted.path {
+        Some(path) => checkout.0.join(relative_path(path)?),
+        None => checkout.0.clone(),
+    };
+    if !skill_dir.join("SKILL.md").is_file() {
+        return Err(format!(
+            "{} has no SKILL.md{}",
+            wanted.source,
+            wanted
+                .path
+                .as_deref()
+                .map(|path| format!(" in {path}"))
+                .unwrap_or_default()
+        )
+        .into());
+    }
+    let sha256 = checksum(&skill_dir)?;
+    if let Some(expected) = expected
+        && !expected.eq_ignore_ascii_case(&sha256)
+    {
+        return Err(format!(
+            "checksum mismatch for {name} at {}: expected {expected}, got {sha256}; not installed",
+            short(&commit)
+        )
+        .into());
+    }
+
+    workspace.place(name, &skill_dir)?;
+    wanted.commit = commit;
+    wanted.sha256 = sha256;
+    Ok(wanted)
+}
+
+/// The commit `rev` names in a fresh clone, trying it as a remote branch
+/// too; `None` is the default branch.
+fn resolve_rev(checkout: &Path, rev: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let candidates = match rev {
+        Some(rev) => vec![rev.to_string(), format!("origin/{rev}")],
+        None => vec!["HEAD".to_string()],
+    };
+    for candidate in &candidates {
+        if let Ok(commit) = git(
+            Some(checkout),
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                "--end-of-options",
+                &format!("{candidate}^{{commit}}"),
+            ],
+        ) {
+            return Ok(commit.trim().to_string());
+        }
+    }
+    Err(format!("no revision {}", rev.unwrap_or("HEAD")).into())
+}
+
+/// Refuse sources git would read as an option or reach through anything
+/// but https, ssh or the file system (e.g. `ext::` remote helpers).
+fn check_source(source: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let allowed = if source.starts_with('-') || source.contains("::") {
+        false
+    } else if let Some((scheme, _)) = source.split_once("://") {
+        matches!(
+            scheme.to_ascii_lowercase().as_str(),
+            "https" | "ssh" | "file"
+        )
+    } else {
+        // `user@host:path` over ssh, or a local path.
+        true
+    };
+    if !allowed {
+        return Err(format!(
+            "`{source}` is not a supported skill source; use an https, ssh or file URL, or a path"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut command = Command::new("git");
+    // Also keeps git from following other transports, e.g. in redirects.
+    command.env("GIT_ALLOW_PROTOCOL", "https:ssh:file");
+    command.args(args);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command
+        .output()
+        .map_err(|err| format!("could not run git: {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn registry_entry(
+    sources: &Sources<'_>,
+    name: &str,
+) -> Result<RegistryEntry, Box<dyn std::error::Error>> {
+    let Some(url) = sources.registry else {
+        return Err(format!(
+            "`{name}` isn't a git URL or path and no skill registry is configured; set \"skill_registry\" in {}",
+            agnt_app::config_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|_| "the agnt config file".to_string())
+        )
+        .into());
+    };
+    if sources.offline {
+        return Err("the skill registry is unavailable in offline mode".into());
+    }
+    let mut index: BTreeMap<String, RegistryEntry> =
+        reqwest::get(url).await?.error_for_status()?.json().await?;
+    index
+        .remove(name)
+        .ok_or_else(|| format!("the skill registry has no skill named {name}").into())
+}
+
+/// Whether `source` names a skill in the registry rather than a
+/// repository.
+fn is_registry_name(source: &str) -> bool {
+    !source.is_empty()
+        && source
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && !Path::new(source).exists()
+}
+
+/// The last directory of the skill's path, or the repository's name.
+fn default_name(locked: &LockedSkill) -> String {
+    if let Some(registry_name) = &locked.registry_name {
+        return registry_name.clone();
+    }
+    let last = locked
+        .path
+        .as_deref()
+        .unwrap_or(&locked.source)
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\', ':'])
+        .next()
+        .unwrap_or_default();
+    last.strip_suffix(".git").unwrap_or(last).to_string()
+}
+
+fn check_name(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("`{name}` is not a valid skill name; pass one with --name").into());
+    }
+    Ok(())
+}
+
+/// `path` as a path within the repository, refusing ones that leave it.
+fn relative_path(path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = Path::new(path);
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("{} is not a path within the repository", path.display()).into());
+    }
+    Ok(path.to_path_buf())
+}
+
+/// SHA-256 over the skill's files in path order, each as its path, its
+/// length and its contents, leaving out `.git`.
+fn checksum(dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    collect_files(dir, Path::new(""), &mut files)?;
+    files.sort();
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let contents = fs::read(dir.join(&relative))?;
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn collect_files(
+    root: &Path,
+    relative: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let path = relative.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        } else {
+            return Err(format!("{} is not a regular file", path.display()).into());
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+/// A scratch directory for a clone, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> io::Result<Self> {
+        let path =
+            std::env::temp_dir().join(format!("agnt-skill-{}-{}", std::process::id(), now_ms()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
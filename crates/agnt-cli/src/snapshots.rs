@@ -0,0 +1,129 @@
+//! Per-turn snapshots of the files a turn read or edited, so a resumed
+//! session can tell the model which of them changed since.
+//!
+//! Each stored turn records a content hash per file and, when
+//! `snapshot_file_contents` is on, the text itself, which lets the notice
+//! show what changed rather than only that something did.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use agnt_core::Attachment;
+use agnt_core::attachment::MAX_ATTACHMENT_BYTES;
+use serde_json::{Value, json};
+use similar::TextDiff;
+
+use crate::session::SessionStore;
+
+/// Context around changes in the diffs of a stale-context notice.
+const HUNK_CONTEXT_LINES: usize = 2;
+
+/// The snapshot stored with a turn: a hash of each file in `paths` as it is
+/// on disk now, and its text if `with_contents` is set and it fits in an
+/// attachment. Files that no longer exist are recorded without a hash.
+pub fn capture(paths: &BTreeSet<String>, with_contents: bool) -> Value {
+    let files: Vec<Value> = paths
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path).ok();
+            let mut file = json!({
+                "path": path,
+                "hash": bytes.as_deref().map(content_hash),
+            });
+            if with_contents
+                && let Some(bytes) = bytes.filter(|bytes| bytes.len() <= MAX_ATTACHMENT_BYTES)
+                && let Ok(text) = String::from_utf8(bytes)
+            {
+                file["content"] = text.into();
+            }
+            file
+        })
+        .collect();
+    Value::Array(files)
+}
+
+/// A notice for the model listing the files that the active session's turns
+/// last saw with different contents than they have now. Checked once after
+/// a session is resumed or checked out at another turn; `None` otherwise or
+/// when nothing changed.
+pub fn stale_context(
+    store: &mut SessionStore,
+) -> Result<Option<Attachment>, Box<dyn std::error::Error>> {
+    if !store.take_context_check() {
+        return Ok(None);
+    }
+
+    // The most recent snapshot of each file wins.
+    let mut seen: BTreeMap<String, Value> = BTreeMap::new();
+    for turn in store.active_turns()? {
+        let Some(Value::Array(files)) = turn.file_snapshots else {
+            continue;
+        };
+        for file in files {
+            if let Some(path) = file.get("path").and_then(Value::as_str) {
+                seen.insert(path.to_string(), file);
+            }
+        }
+    }
+
+    let mut listing = String::new();
+    let mut diffs = String::new();
+    let mut changed = 0;
+    for (path, file) in &seen {
+        let then = file.get("hash").and_then(Value::as_str);
+        let now = std::fs::read(path).ok();
+        if then == now.as_deref().map(content_hash).as_deref() {
+            continue;
+        }
+        changed += 1;
+        let state = match (then, &now) {
+            (_, None) => "deleted",
+            (None, Some(_)) => "created",
+            (Some(_), Some(_)) => "modified",
+        };
+        let _ = writeln!(listing, "- {path} ({state})");
+
+        if let (Some(old), Some(new)) = (
+            file.get("content").and_then(Value::as_str),
+            now.and_then(|bytes| String::from_utf8(bytes).ok()),
+        ) {
+            let diff = TextDiff::from_lines(old, new.as_str())
+                .unified_diff()
+                .context_radius(HUNK_CONTEXT_LINES)
+                .header(path, path)
+                .to_string();
+            if diffs.len() + diff.len() <= MAX_ATTACHMENT_BYTES {
+                diffs.push_str(&diff);
+            }
+        }
+    }
+    if changed == 0 {
+        return Ok(None);
+    }
+
+    let mut text = format!(
+        "<stale-context>\nThese files changed since this conversation last read or edited them. \
+         Line hashes from earlier reads no longer apply; read a file again before editing it.\n{listing}"
+    );
+    if !diffs.is_empty() {
+        let _ = write!(text, "\nChanges:\n{diffs}");
+    }
+    text.push_str("</stale-context>");
+
+    let label = if changed == 1 {
+        "1 file changed since last read".to_string()
+    } else {
+        format!("{changed} files changed since last read")
+    };
+    Ok(Some(Attachment::note(label, text)))
+}
+
+/// FNV-1a 64-bit, as hex; the same hash the `read` tool's line anchors use.
+fn content_hash(bytes: &[u8]) -> String {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
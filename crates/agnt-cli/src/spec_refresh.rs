@@ -0,0 +1,254 @@
+//! Keeps the models.dev catalog current while the TUI or GUI runs instead
+//! of only fetching it at startup: it is fetched again in the background
+//! every few hours and on `/models:refresh`, cached for offline runs, and
+//! models new to the configured providers are announced in the status line.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use agnt_app::UserConfig;
+use agnt_llm_registry::{ModelsDevSpec, Registry};
+use tokio::sync::{Notify, mpsc};
+
+/// Hours between refreshes, unless the config says otherwise.
+const DEFAULT_REFRESH_HOURS: u64 = 6;
+/// New models listed by name in the notice; the rest are counted.
+const LISTED_MODELS: usize = 3;
+
+/// The outcome of one refresh.
+pub struct SpecUpdate {
+    /// The fetched catalog, or why fetching it failed.
+    pub spec: Result<ModelsDevSpec, String>,
+    /// Models of configured providers the previous catalog lacked, as
+    /// `provider/model`.
+    pub new_models: Vec<String>,
+    /// Whether `/models:refresh` asked for it, rather than the timer.
+    pub requested: bool,
+}
+
+impl SpecUpdate {
+    /// The status line for this update, if it has anything to say: new
+    /// models, or for a requested refresh, how it went.
+    pub fn notice(&self) -> Option<String> {
+        if let Err(err) = &self.spec {
+            return self
+                .requested
+                .then(|| format!("[could not refresh the models.dev catalog: {err}]"));
+        }
+        if self.new_models.is_empty() {
+            return self
+                .requested
+                .then(|| "[models.dev catalog refreshed, no new models]".to_string());
+        }
+        let mut listed = self
+            .new_models
+            .iter()
+            .take(LISTED_MODELS)
+            .cloned()
+            .collect::<Vec<_>>();
+        if self.new_models.len() > LISTED_MODELS {
+            listed.push(format!("{} more", self.new_models.len() - LISTED_MODELS));
+        }
+        let noun = if self.new_models.len() == 1 {
+            "model"
+        } else {
+            "models"
+        };
+        Some(format!("[new {noun} available: {}]", listed.join(", ")))
+    }
+}
+
+/// Handle on the background refresh task; dropping it stops the task.
+pub struct SpecRefresh {
+    requests: Arc<Notify>,
+    /// `None` once taken by [`take_updates`](Self::take_updates).
+    updates: Option<mpsc::Receiver<SpecUpdate>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl SpecRefresh {
+    /// Start refreshing the catalog `registry` was loaded with, right away
+    /// when it is `stale`, e.g. read from the cache at startup. `None` in
+    /// offline mode, which never fetches it.
+    pub fn start(
+        registry: &Registry,
+        config: &UserConfig,
+        offline: bool,
+        stale: bool,
+    ) -> Option<Self> {
+        if offline {
+            return None;
+        }
+        let interval = match config.spec_refresh_hours {
+            Some(0) => None,
+            Some(hours) => Some(Duration::from_secs(hours * 3600)),
+            None => Some(Duration::from_secs(DEFAULT_REFRESH_HOURS * 3600)),
+        };
+        let providers = registry
+            .available_providers()
+            .into_iter()
+            .map(|provider| provider.id)
+            .collect::<Vec<_>>();
+        let previous = registry.spec().cloned().unwrap_or_default();
+        let requests = Arc::new(Notify::new());
+        let (tx, updates) = mpsc::channel(4);
+        let task = tokio::spawn(refresh_loop(
+            previous,
+            providers,
+            interval,
+            stale,
+            Arc::clone(&requests),
+            tx,
+        ));
+        Some(Self {
+            requests,
+            updates: Some(updates),
+            task,
+        })
+    }
+
+    /// Refresh now; the result arrives through [`next`](Self::next).
+    pub fn request(&self) {
+        self.requests.notify_one();
+    }
+
+    /// The next refresh's outcome.
+    pub async fn next(&mut self) -> Option<SpecUpdate> {
+        match self.updates.as_mut() {
+            Some(updates) => updates.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// The refreshes' outcomes, for a UI that waits for them in a task of
+    /// its own while keeping this handle to request refreshes.
+    pub fn take_updates(&mut self) -> Option<mpsc::Receiver<SpecUpdate>> {
+        self.updates.take()
+    }
+}
+
+impl Drop for SpecRefresh {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn refresh_loop(
+    mut previous: ModelsDevSpec,
+    providers: Vec<String>,
+    interval: Option<Duration>,
+    mut stale: bool,
+    requests: Arc<Notify>,
+    tx: mpsc::Sender<SpecUpdate>,
+) {
+    loop {
+        // A stale catalog is refreshed first thing.
+        let requested = if std::mem::take(&mut stale) {
+            false
+        } else {
+            match interval {
+                Some(interval) => tokio::select! {
+                    _ = tokio::time::sleep(interval) => false,
+                    _ = requests.notified() => true,
+                },
+                None => {
+                    requests.notified().await;
+                    true
+                }
+            }
+        };
+        let update = match Registry::fetch_remote_spec().await {
+            Ok(spec) => {
+                let new_models = new_models(&previous, &spec, &providers);
+                // As at startup, a cache that can't be written only matters
+                // to later offline runs.
+                if let Ok(path) = agnt_app::model_spec_cache_path()
+                    && let Ok(raw) = serde_json::to_string(&spec)
+                {
+                    let _ = std::fs::write(path, raw);
+                }
+                previous = spec.clone();
+                SpecUpdate {
+                    spec: Ok(spec),
+                    new_models,
+                    requested,
+                }
+            }
+            Err(err) => SpecUpdate {
+                spec: Err(err.to_string()),
+                new_models: Vec::new(),
+                requested,
+            },
+        };
+        if tx.send(update).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Models of `providers` in `current` but not in `previous`, as
+/// `provider/model`, sorted. A provider `previous` lacks altogether is
+/// skipped rather than announcing its whole catalog.
+fn new_models(
+    previous: &ModelsDevSpec,
+    current: &ModelsDevSpec,
+    providers: &[String],
+) -> Vec<String> {
+    let mut models = Vec::new();
+    for provider_id in providers {
+        let (Some(before), Some(after)) = (previous.get(provider_id), current.get(provider_id))
+        else {
+            continue;
+        };
+        models.extend(
+            after
+                .models
+                .keys()
+                .filter(|model_id| !before.models.contains_key(*model_id))
+                .map(|model_id| format!("{provider_id}/{model_id}")),
+        );
+    }
+    models.sort();
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(json: &str) -> ModelsDevSpec {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn announces_models_new_to_configured_providers() {
+        let previous = spec(
+            r#"{
+                "openai": {"id": "openai", "name": "OpenAI", "models": {"gpt-5": {"id": "gpt-5"}}},
+                "xai": {"id": "xai", "name": "xAI", "models": {}}
+            }"#,
+        );
+        let current = spec(
+            r#"{
+                "openai": {"id": "openai", "name": "OpenAI", "models": {
+                    "gpt-5": {"id": "gpt-5"},
+                    "gpt-6": {"id": "gpt-6"},
+                    "gpt-6-mini": {"id": "gpt-6-mini"}
+                }},
+                "xai": {"id": "xai", "name": "xAI", "models": {"grok-5": {"id": "grok-5"}}},
+                "mistral": {"id": "mistral", "name": "Mistral", "models": {"large": {"id": "large"}}}
+            }"#,
+        );
+        let providers = ["openai".to_string(), "mistral".to_string()];
+        let update = SpecUpdate {
+            new_models: new_models(&previous, &current, &providers),
+            spec: Ok(current),
+            requested: false,
+        };
+        assert_eq!(update.new_models, ["openai/gpt-6", "openai/gpt-6-mini"]);
+        assert_eq!(
+            update.notice().as_deref(),
+            Some("[new models available: openai/gpt-6, openai/gpt-6-mini]")
+        );
+    }
+}
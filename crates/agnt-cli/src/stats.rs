@@ -0,0 +1,363 @@
+//! `agnt stats`: token usage, estimated cost, latency and tool activity
+//! summarized from the usage stored with each turn.
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use agnt_core::EventTiming;
+use agnt_db::{Store, TurnUsage};
+use agnt_llm::stream::Usage;
+use agnt_llm_registry::{ModelCost, Registry};
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Day,
+    Model,
+    Project,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Default)]
+struct UsageRow {
+    sessions: HashSet<String>,
+    turns: usize,
+    tool_calls: usize,
+    input_tokens: u64,
+    cached_tokens: u64,
+    output_tokens: u64,
+    reasoning_tokens: u64,
+    /// Estimated cost in USD of the turns whose model has a known price.
+    cost: Option<f64>,
+    /// Model responses with a recorded latency, and their summed latency to
+    /// the first token.
+    responses: usize,
+    first_token_ms: u64,
+    /// Time spent executing tools.
+    tool_ms: u64,
+}
+
+impl UsageRow {
+    fn add(
+        &mut self,
+        turn: &TurnUsage,
+        usage: Option<&Usage>,
+        cost: Option<f64>,
+        timings: &[EventTiming],
+    ) {
+        self.sessions.insert(turn.session_id.clone());
+        self.turns += 1;
+        self.tool_calls += turn.tool_calls;
+        if let Some(usage) = usage {
+            self.input_tokens += u64::from(usage.input_tokens);
+            self.cached_tokens += u64::from(usage.cached_tokens.unwrap_or(0));
+            self.output_tokens += u64::from(usage.output_tokens);
+            self.reasoning_tokens += u64::from(usage.reasoning_tokens.unwrap_or(0));
+        }
+        if let Some(cost) = cost {
+            *self.cost.get_or_insert(0.0) += cost;
+        }
+        for timing in timings {
+            match timing {
+                EventTiming::Response { first_token, .. } => {
+                    self.responses += 1;
+                    self.first_token_ms += first_token.as_millis() as u64;
+                }
+                EventTiming::Tool { duration, .. } => {
+                    self.tool_ms += duration.as_millis() as u64;
+                }
+            }
+        }
+    }
+
+    /// Average latency to the first token, if any response recorded one.
+    fn avg_first_token_ms(&self) -> Option<u64> {
+        (self.responses > 0).then(|| self.first_token_ms / self.responses as u64)
+    }
+
+    fn to_json(&self, key: &str) -> Value {
+        json!({
+            "key": key,
+            "sessions": self.sessions.len(),
+            "turns": self.turns,
+            "tool_calls": self.tool_calls,
+            "input_tokens": self.input_tokens,
+            "cached_tokens": self.cached_tokens,
+            "output_tokens": self.output_tokens,
+            "reasoning_tokens": self.reasoning_tokens,
+            "cost_usd": self.cost,
+            "avg_first_token_ms": self.avg_first_token_ms(),
+            "tool_ms": self.tool_ms,
+        })
+    }
+}
+
+/// How long the calls of one tool took.
+#[derive(Default)]
+struct ToolTimes {
+    calls: usize,
+    total_ms: u64,
+    max_ms: u64,
+}
+
+impl ToolTimes {
+    fn avg_ms(&self) -> u64 {
+        self.total_ms / self.calls.max(1) as u64
+    }
+
+    fn to_json(&self, name: &str) -> Value {
+        json!({
+            "name": name,
+            "calls": self.calls,
+            "avg_ms": self.avg_ms(),
+            "max_ms": self.max_ms,
+        })
+    }
+}
+
+pub fn run(
+    store: &mut Store,
+    registry: Option<&Registry>,
+    days: Option<u64>,
+    group_by: GroupBy,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let since_ms = days.map(|days| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.saturating_sub(Duration::from_secs(days * 24 * 60 * 60))
+            .as_millis() as i64
+    });
+    let turns = store.usage_log().turns(since_ms)?;
+
+    let mut rows: BTreeMap<String, UsageRow> = BTreeMap::new();
+    let mut total = UsageRow::default();
+    let mut tools: BTreeMap<String, ToolTimes> = BTreeMap::new();
+    for turn in &turns {
+        let usage = token_usage(turn);
+        let timings = turn_timings(turn.usage.as_ref());
+        let model = turn_model(turn);
+        let cost = match (registry, &model, &usage) {
+            (Some(registry), Some((provider, model)), Some(usage)) => registry
+                .model_spec(provider, model)
+                .and_then(|spec| spec.cost)
+                .map(|cost| estimate_cost(&cost, usage)),
+            _ => None,
+        };
+
+        let key = match group_by {
+            GroupBy::Day => turn.day.clone(),
+            GroupBy::Model => model
+                .map(|(provider, model)| format!("{provider}/{model}"))
+                .unwrap_or_else(|| "unknown".to_string()),
+            GroupBy::Project => turn.project_root.display().to_string(),
+        };
+        rows.entry(key)
+            .or_default()
+            .add(turn, usage.as_ref(), cost, &timings);
+        total.add(turn, usage.as_ref(), cost, &timings);
+        for timing in timings {
+            if let EventTiming::Tool { name, duration, .. } = timing {
+                let ms = duration.as_millis() as u64;
+                let times = tools.entry(name).or_default();
+                times.calls += 1;
+                times.total_ms += ms;
+                times.max_ms = times.max_ms.max(ms);
+            }
+        }
+    }
+    // Slowest first, by average time per call.
+    let mut tools = tools.into_iter().collect::<Vec<_>>();
+    tools.sort_by_key(|(_, times)| std::cmp::Reverse(times.avg_ms()));
+
+    match format {
+        OutputFormat::Table => {
+            print_table(group_by, &rows, &total);
+            print_tool_times(&tools);
+        }
+        OutputFormat::Csv => print_csv(&rows),
+        OutputFormat::Json => {
+            let report = json!({
+                "rows": rows
+                    .iter()
+                    .map(|(key, row)| row.to_json(key))
+                    .collect::<Vec<_>>(),
+                "total": total.to_json("total"),
+                "tools": tools
+                    .iter()
+                    .map(|(name, times)| times.to_json(name))
+                    .collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
+
+/// Token counts recorded with a turn, if any.
+pub(crate) fn token_usage(turn: &TurnUsage) -> Option<Usage> {
+    serde_json::from_value(turn.usage.clone()?).ok()
+}
+
+/// The `(provider, model)` recorded with a turn. Turns stored before models
+/// were recorded have none.
+pub(crate) fn turn_model(turn: &TurnUsage) -> Option<(String, String)> {
+    let usage = turn.usage.as_ref()?;
+    Some((
+        usage.get("provider")?.as_str()?.to_string(),
+        usage.get("model")?.as_str()?.to_string(),
+    ))
+}
+
+/// The model responses and tool calls of a turn with how long they took,
+/// from its usage JSON. Turns stored before timings were recorded have none.
+pub(crate) fn turn_timings(usage: Option<&Value>) -> Vec<EventTiming> {
+    usage
+        .and_then(|usage| usage.get("timings"))
+        .and_then(|timings| serde_json::from_value(timings.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Cost in USD from the model's models.dev prices. Cached input tokens are
+/// billed at the cache read price when the model has one.
+pub(crate) fn estimate_cost(cost: &ModelCost, usage: &Usage) -> f64 {
+    let cached = f64::from(usage.cached_tokens.unwrap_or(0));
+    let uncached = f64::from(usage.input_tokens) - cached;
+    let cached_price = cost.cache_read.unwrap_or(cost.input);
+    (uncached.max(0.0) * cost.input
+        + cached * cached_price
+        + f64::from(usage.output_tokens) * cost.output)
+        / 1_000_000.0
+}
+
+fn print_table(group_by: GroupBy, rows: &BTreeMap<String, UsageRow>, total: &UsageRow) {
+    let key_header = match group_by {
+        GroupBy::Day => "day",
+        GroupBy::Model => "model",
+        GroupBy::Project => "project",
+    };
+    let key_width = rows
+        .keys()
+        .map(|key| key.chars().count())
+        .chain([key_header.len(), "total".len()])
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{key_header:<key_width$}  {:>8}  {:>6}  {:>6}  {:>12}  {:>12}  {:>12}  {:>10}  {:>11}  {:>10}",
+        "sessions",
+        "turns",
+        "tools",
+        "input",
+        "cached",
+        "output",
+        "cost",
+        "first token",
+        "tool time"
+    );
+    let print_row = |key: &str, row: &UsageRow| {
+        println!(
+            "{key:<key_width$}  {:>8}  {:>6}  {:>6}  {:>12}  {:>12}  {:>12}  {:>10}  {:>11}  {:>10}",
+            row.sessions.len(),
+            row.turns,
+            row.tool_calls,
+            row.input_tokens,
+            row.cached_tokens,
+            row.output_tokens,
+            format_cost(row.cost),
+            format_ms(row.avg_first_token_ms()),
+            format_ms((row.tool_ms > 0).then_some(row.tool_ms)),
+        );
+    };
+    for (key, row) in rows {
+        print_row(key, row);
+    }
+    println!("{}", "-".repeat(key_width + 105));
+    print_row("total", total);
+    println!();
+    println!(
+        "Costs are estimates from models.dev prices; turns without a known price are not included."
+    );
+}
+
+/// The tools by average time per call, slowest first.
+fn print_tool_times(tools: &[(String, ToolTimes)]) {
+    if tools.is_empty() {
+        return;
+    }
+    let name_width = tools
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(["tool".len()])
+        .max()
+        .unwrap_or(0);
+    println!();
+    println!(
+        "{:<name_width$}  {:>6}  {:>10}  {:>10}",
+        "tool", "calls", "average", "slowest"
+    );
+    for (name, times) in tools {
+        println!(
+            "{name:<name_width$}  {:>6}  {:>10}  {:>10}",
+            times.calls,
+            format_ms(Some(times.avg_ms())),
+            format_ms(Some(times.max_ms)),
+        );
+    }
+}
+
+fn print_csv(rows: &BTreeMap<String, UsageRow>) {
+    println!(
+        "key,sessions,turns,tool_calls,input_tokens,cached_tokens,output_tokens,reasoning_tokens,cost_usd,avg_first_token_ms,tool_ms"
+    );
+    for (key, row) in rows {
+        println!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(key),
+            row.sessions.len(),
+            row.turns,
+            row.tool_calls,
+            row.input_tokens,
+            row.cached_tokens,
+            row.output_tokens,
+            row.reasoning_tokens,
+            row.cost
+                .map(|cost| format!("{cost:.4}"))
+                .unwrap_or_default(),
+            row.avg_first_token_ms()
+                .map(|ms| ms.to_string())
+                .unwrap_or_default(),
+            row.tool_ms
+        );
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_ms(ms: Option<u64>) -> String {
+    match ms {
+        Some(ms) => format!("{:.1}s", ms as f64 / 1000.0),
+        None => "-".to_string(),
+    }
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    match cost {
+        Some(cost) => format!("${cost:.2}"),
+        None => "-".to_string(),
+    }
+}
@@ -0,0 +1,60 @@
+//! Session summaries: written in the background once a session is left,
+//! shown in the session picker and sent to the model when a session is
+//! resumed after a long gap.
+
+use std::sync::Arc;
+
+use agnt_core::{Agent, Attachment};
+
+use crate::session::{SessionStore, SharedSessionStore, SummaryTarget};
+
+/// Sessions left by earlier runs that are summarized at startup at most.
+const STARTUP_SUMMARIES: usize = 3;
+
+/// Summarize the active session before it is left, unless its summary is
+/// already current.
+pub fn summarize_active(agent: &Agent, store: &SharedSessionStore) {
+    let target = store.lock().active_summary_target();
+    if let Ok(Some(target)) = target {
+        spawn(agent, store, vec![target]);
+    }
+}
+
+/// Summarize the most recent sessions that earlier runs exited or crashed
+/// in before summarizing them.
+pub fn summarize_left_sessions(agent: &Agent, store: &SharedSessionStore) {
+    let targets = store.lock().unsummarized_sessions(STARTUP_SUMMARIES);
+    if let Ok(targets) = targets
+        && !targets.is_empty()
+    {
+        spawn(agent, store, targets);
+    }
+}
+
+/// The stored summary of a session resumed after a long gap, to send with
+/// the next prompt; `None` otherwise.
+pub fn resume_note(store: &mut SessionStore) -> Option<Attachment> {
+    let summary = store.take_resume_summary()?;
+    Some(Attachment::note("session summary", summary.to_note()))
+}
+
+/// Summarize `targets` one after another with `agent`'s model and store the
+/// results. A failed summary is dropped; the session is tried again the
+/// next time it is left or agnt starts.
+fn spawn(agent: &Agent, store: &SharedSessionStore, targets: Vec<SummaryTarget>) {
+    let jobs: Vec<_> = targets
+        .into_iter()
+        .map(|target| {
+            let summarize = agent.summarize(target.state);
+            (target.session_id, target.turn_id, summarize)
+        })
+        .collect();
+    let store = Arc::clone(store);
+    tokio::spawn(async move {
+        for (session_id, turn_id, summarize) in jobs {
+            if let Ok(Some(summary)) = summarize.await {
+                let _ = store.lock().save_summary(&session_id, &turn_id, &summary);
+            }
+        }
+    });
+}
@@ -0,0 +1,100 @@
+//! `agnt sync`: exchange session ops with a remote over HTTP.
+//!
+//! The remote keeps one append-only stream of ops from every device:
+//!
+//! - `POST <sync_url>` with `{"device_id": ..., "ops": [RemoteOp, ...]}`
+//!   appends ops. Re-sent ops (same `origin` and `origin_seq`) must be
+//!   ignored.
+//! - `GET <sync_url>?after=<cursor>` returns `{"ops": [...], "cursor": ...}`
+//!   with the ops following `cursor` (all ops when omitted) and the cursor
+//!   to continue from. An empty `ops` list means the stream is exhausted.
+//!
+//! Requests carry `Authorization: Bearer $AGNT_SYNC_TOKEN` when set. With
+//! `encrypt_sessions` on, op payloads are pushed sealed and only session
+//! ids, op types and project roots travel in the clear; devices pulling them
+//! need the same session key. Without it payloads are sent in plaintext, so
+//! only point this at a remote you trust.
+
+use std::sync::Arc;
+
+use agnt_db::{RemoteOp, Store};
+use parking_lot::Mutex;
+use serde_json::{Value, json};
+use url::Url;
+
+const SYNC_TOKEN_ENV: &str = "AGNT_SYNC_TOKEN";
+const PUSH_BATCH_SIZE: usize = 200;
+
+pub struct SyncSummary {
+    pub pushed: usize,
+    pub pulled: usize,
+    pub applied: usize,
+}
+
+pub async fn run(
+    store: &Arc<Mutex<Store>>,
+    sync_url: &str,
+) -> Result<SyncSummary, Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+    let token = std::env::var(SYNC_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.trim().is_empty());
+    let device_id = store.lock().sync_log().device_id()?;
+
+    let mut summary = SyncSummary {
+        pushed: 0,
+        pulled: 0,
+        applied: 0,
+    };
+
+    loop {
+        let ops = store.lock().sync_log().pending_ops(PUSH_BATCH_SIZE)?;
+        let Some(last_seq) = ops.last().map(|op| op.origin_seq) else {
+            break;
+        };
+
+        let mut request = client
+            .post(sync_url)
+            .json(&json!({ "device_id": device_id, "ops": ops }));
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await?.error_for_status()?;
+
+        store.lock().sync_log().mark_pushed(last_seq)?;
+        summary.pushed += ops.len();
+    }
+
+    loop {
+        let cursor = store.lock().sync_log().pull_cursor()?;
+        let mut url = Url::parse(sync_url)?;
+        if let Some(cursor) = &cursor {
+            url.query_pairs_mut().append_pair("after", cursor);
+        }
+        let mut request = client.get(url);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        let mut body: Value = request.send().await?.error_for_status()?.json().await?;
+
+        let ops: Vec<RemoteOp> = serde_json::from_value(body["ops"].take())?;
+        if ops.is_empty() {
+            break;
+        }
+        let next_cursor = match &body["cursor"] {
+            Value::String(cursor) => cursor.clone(),
+            Value::Number(cursor) => cursor.to_string(),
+            _ => return Err("sync remote returned ops without a cursor".into()),
+        };
+        if cursor.as_deref() == Some(next_cursor.as_str()) {
+            return Err("sync remote did not advance its cursor".into());
+        }
+
+        let mut db = store.lock();
+        summary.applied += db.sync_log().apply_remote_ops(&ops)?;
+        db.sync_log().set_pull_cursor(&next_cursor)?;
+        summary.pulled += ops.len();
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use agnt_core::PromptTemplate;
+
+/// Collects values for a prompt template's variables one at a time, using
+/// the composer as the input field.
+#[derive(Debug, Clone)]
+pub struct TemplateFill {
+    template: PromptTemplate,
+    values: HashMap<String, String>,
+}
+
+impl TemplateFill {
+    pub fn new(template: PromptTemplate) -> Self {
+        Self {
+            template,
+            values: HashMap::new(),
+        }
+    }
+
+    /// The next variable that still needs a value.
+    pub fn pending_variable(&self) -> Option<&str> {
+        self.template
+            .variables
+            .iter()
+            .find(|name| !self.values.contains_key(*name))
+            .map(String::as_str)
+    }
+
+    /// Record the value for the pending variable.
+    pub fn provide(&mut self, value: String) {
+        if let Some(name) = self.pending_variable().map(str::to_owned) {
+            self.values.insert(name, value);
+        }
+    }
+
+    /// Render the template once every variable has a value.
+    pub fn render(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self.template.render(&self.values)?)
+    }
+
+    /// Hint shown next to the composer while values are collected.
+    pub fn prompt_text(&self) -> String {
+        match self.pending_variable() {
+            Some(name) => format!(
+                "template {}: value for `{name}` (Enter to confirm, Esc to cancel)",
+                self.template.name
+            ),
+            None => format!("template {}", self.template.name),
+        }
+    }
+}
@@ -1,11 +1,44 @@
-use agnt_core::{Agent, AgentEvent, AgentStream, ConversationState, DisplayBody};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use agnt_app::WebhookEvent;
+use agnt_core::{
+    Agent, AgentEvent, AgentStream, Attachment, ConversationState, DisplayBody, EventTiming,
+    PromptTemplate, TurnStats, attachment_summary,
+};
+use agnt_llm::stream::Usage;
 use agnt_llm::{AssistantPart, Message, ToolDisplayBodyPart, UserPart};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
-use tokio::sync::watch;
-
-use crate::session::SharedSessionStore;
-use crate::tui::session_dialog::{self, ResumeSessionDialogState};
-use crate::typeahead::{ActiveTypeahead, Command, Mention, TypeaheadActivation, TypeaheadState};
+use tokio::sync::{mpsc, watch};
+
+use crate::budget::BudgetStatus;
+use crate::compare::{CompareModels, Comparison, ModelSwitch};
+use crate::help::{self, TUI_KEYBINDINGS};
+use crate::i18n::{Msg, t};
+use crate::images;
+use crate::palette::{self, PaletteAction, PaletteState};
+use crate::pinned;
+use crate::presubmit::{self, FilePrompt};
+use crate::project_settings;
+use crate::request_size::{HeldRequest, RequestGuard};
+use crate::session::{
+    INTERRUPTED_PROMPT, RAW_OUTPUT_SAVED, RESUMING_RESPONSE, SessionStore, SharedSessionStore,
+    now_ms,
+};
+use crate::snapshots;
+use crate::spec_refresh::{SpecRefresh, SpecUpdate};
+use crate::summaries;
+use crate::template_fill::TemplateFill;
+use crate::tui::diff_view::DiffView;
+use crate::tui::help::HelpOverlay;
+use crate::tui::navigation::{self, Block, Navigation};
+use crate::tui::request_view::RequestView;
+use crate::tui::session_dialog::{self, DialogMode, ResumeSessionDialogState};
+use crate::typeahead::{
+    ActiveTypeahead, Command, Mention, TypeaheadActivation, TypeaheadState, available_commands,
+    mention_attachments,
+};
+use crate::write_approval::{self, PendingWrite};
 
 // ---------------------------------------------------------------------------
 // Display messages (what the UI renders)
@@ -21,6 +54,8 @@ pub enum Role {
 pub struct DisplayMessage {
     pub role: Role,
     pub chunks: Vec<StreamChunk>,
+    /// When the message was sent or received; `None` when unknown.
+    pub created_at_ms: Option<i64>,
 }
 
 /// A typed chunk in the streaming assistant response, preserving
@@ -33,21 +68,82 @@ pub enum StreamChunk {
     Text(String),
     /// Tool call status line (e.g. "[Read src/main.rs...]" or "[Read src/main.rs]").
     Tool(String),
+    /// Status line of a tool the provider ran itself (e.g. "[Search the
+    /// web: ...]"), kept apart from local tool lines.
+    HostedTool(String),
+    /// Summary closing a completed turn: duration, model, tokens and tool
+    /// calls (see [`turn_footer`]).
+    Footer(String),
+    /// An image in the answer: the file it was saved to, or a remote URL
+    /// (see [`image_chunk`]).
+    Image(String),
 }
 
 // ---------------------------------------------------------------------------
 // App state
 // ---------------------------------------------------------------------------
 
+/// Pastes longer than either limit are held out of the composer and sent
+/// as an attachment instead.
+const PASTE_ATTACH_BYTES: usize = 4 * 1024;
+const PASTE_ATTACH_LINES: usize = 50;
+
 pub enum AppState {
     Idle,
     Generating { stream: AgentStream },
 }
 
+/// What the composer holds. Only one at a time: starting one ends the one
+/// before.
+#[derive(Debug, Clone, Default)]
+pub enum ComposerMode {
+    /// A prompt to send.
+    #[default]
+    Prompt,
+    /// The values of a prompt template's variables, one at a time.
+    Template(TemplateFill),
+    /// A session history search query (`/history`).
+    HistorySearch,
+    /// The project settings being edited (`/project:settings`).
+    Settings,
+    /// A file, URL or note to pin (`/add`).
+    ContextAdd,
+    /// The models and prompt to compare (`/compare`).
+    Compare,
+    /// The note for a new bookmark (`/bookmark`).
+    Bookmark,
+    /// The model and effort to regenerate the last response with
+    /// (`/retry:with`).
+    RetryWith,
+}
+
+impl ComposerMode {
+    pub fn is_prompt(&self) -> bool {
+        matches!(self, ComposerMode::Prompt)
+    }
+
+    /// What the composer asks for, shown next to it; `None` for a prompt.
+    pub fn hint(&self) -> Option<String> {
+        let msg = match self {
+            ComposerMode::Prompt => return None,
+            ComposerMode::Template(fill) => return Some(fill.prompt_text()),
+            ComposerMode::HistorySearch => Msg::HistorySearchHint,
+            ComposerMode::Settings => Msg::SettingsEditHint,
+            ComposerMode::ContextAdd => Msg::ContextAddHint,
+            ComposerMode::Compare => Msg::CompareHint,
+            ComposerMode::Bookmark => Msg::BookmarkHint,
+            ComposerMode::RetryWith => Msg::RetryWithHint,
+        };
+        Some(t(msg).to_string())
+    }
+}
+
 pub struct App {
     pub agent: Agent,
     pub session_store: SharedSessionStore,
     pub messages: Vec<DisplayMessage>,
+    /// Title of the active session, shown in the header bar.
+    pub session_title: Option<String>,
     pub input: String,
     pub cursor_pos: usize,
     pub scroll_offset: u16,
@@ -60,13 +156,75 @@ pub struct App {
     /// Maximum scroll offset (set by the renderer each frame).
     pub max_scroll: u16,
     pub resume_dialog: Option<ResumeSessionDialogState>,
+    /// The command palette, while open (Ctrl+K).
+    pub palette: Option<PaletteState>,
+    /// The help overlay, while open (`?` or F1).
+    pub help: Option<HelpOverlay>,
+    /// The request inspection pane, while open (`/debug:request`).
+    pub request_view: Option<RequestView>,
+    /// Long pastes and `/last-output` held out of the composer, sent as
+    /// attachments with the next message.
+    pub pasted: Vec<Attachment>,
+    /// What the composer holds.
+    pub composer: ComposerMode,
+    /// Pinned context item selected for unpinning, while the context panel
+    /// has the keyboard (`/context`).
+    pub context_focus: Option<usize>,
+    /// Selected message or tool block, while navigation mode is on.
+    pub navigation: Option<Navigation>,
+    /// Messages and tool blocks shown collapsed to one line.
+    pub collapsed: HashSet<Block>,
+    /// Diff opened from navigation mode, while shown.
+    pub diff_view: Option<DiffView>,
+    /// Two models' answers side by side, while shown (`/compare`).
+    pub comparison: Option<Comparison>,
+    /// Builds the models `/compare` runs; without it, comparing is
+    /// unavailable.
+    pub compare_models: Option<CompareModels>,
+    /// Refuse to fetch URLs that aren't on this machine.
+    pub offline: bool,
+    /// Refreshes the models.dev catalog in the background; `None` offline.
+    pub spec_refresh: Option<SpecRefresh>,
+    /// New models announced by the last refresh, until the next message.
+    pub models_notice: Option<String>,
+    /// Spend against the provider budget once it nears its limit.
+    pub budget_status: Option<BudgetStatus>,
+    /// Message held back until sending past the exceeded budget is confirmed.
+    pub budget_pending: Option<String>,
+    /// Prompt that is only a file path, held back until the user picks
+    /// between attaching the file and sending the path as typed.
+    pub file_prompt: Option<FilePrompt>,
+    /// Cleanup run on every prompt before it is sent.
+    presubmit: Vec<presubmit::Step>,
+    /// Attach the contents of files mentioned in a prompt.
+    attach_mentions: bool,
+    /// Set once sending past the exceeded budget was confirmed this run.
+    budget_overrun_confirmed: bool,
+    /// Size and cost limits above which a request is held back.
+    pub request_guard: RequestGuard,
+    /// Request held back for being over those limits until confirmed.
+    pub large_request: Option<HeldRequest>,
+    /// Protected writes the agent asks about while a turn runs.
+    pub write_approvals: mpsc::UnboundedReceiver<PendingWrite>,
+    /// The protected write the turn waits on, until answered.
+    pub pending_write: Option<PendingWrite>,
     typeahead: TypeaheadState,
 }
 
 impl App {
-    pub fn new(agent: Agent, session_store: SharedSessionStore) -> Self {
-        Self {
-            messages: display_messages_from_history(&agent.messages()),
+    /// `typeahead` may have been created early, to index the project's
+    /// files while startup goes on.
+    pub fn new(
+        mut agent: Agent,
+        session_store: SharedSessionStore,
+        typeahead: TypeaheadState,
+    ) -> Self {
+        let (messages, session_title) = restored_messages(&agent, &session_store.lock());
+        let write_approvals = write_approval::ask_through_ui(&mut agent);
+        let config = agnt_app::UserConfig::load().unwrap_or_default();
+        let mut app = Self {
+            messages,
+            session_title,
             agent,
             session_store,
             input: String::new(),
@@ -78,13 +236,109 @@ impl App {
             cursor_blink_on: true,
             max_scroll: 0,
             resume_dialog: None,
-            typeahead: TypeaheadState::new_for_current_project(),
+            palette: None,
+            help: None,
+            request_view: None,
+            pasted: Vec::new(),
+            composer: ComposerMode::Prompt,
+            context_focus: None,
+            navigation: None,
+            collapsed: HashSet::new(),
+            diff_view: None,
+            comparison: None,
+            compare_models: None,
+            offline: false,
+            spec_refresh: None,
+            models_notice: None,
+            budget_status: None,
+            budget_pending: None,
+            file_prompt: None,
+            presubmit: presubmit::steps(&config),
+            attach_mentions: !config.skip_mention_attachments,
+            budget_overrun_confirmed: false,
+            request_guard: RequestGuard::default(),
+            large_request: None,
+            write_approvals,
+            pending_write: None,
+            typeahead,
+        };
+        app.refresh_budget_status();
+        app.resume_pending_response();
+        app.restore_draft();
+        app
+    }
+
+    /// Keep the composer text of the active session so it is back the next
+    /// time the session is opened. Text the composer holds for a prompt
+    /// (a template variable, a search, the settings) is not a draft.
+    pub fn save_draft(&mut self) {
+        let (text, cursor) = if !self.composer.is_prompt() {
+            ("", 0)
+        } else {
+            (self.input.as_str(), self.cursor_pos)
+        };
+        let save_result = self.session_store.lock().save_draft(text, cursor);
+        if let Err(err) = save_result {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session error: {err}]")));
+        }
+    }
+
+    fn restore_draft(&mut self) {
+        let interrupted_result = self.session_store.lock().take_interrupted_prompt();
+        match interrupted_result {
+            Ok(Some(prompt)) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(INTERRUPTED_PROMPT.to_string()));
+                self.cursor_pos = prompt.len();
+                self.input = prompt;
+                self.typeahead.sync(&self.input, self.cursor_pos);
+                return;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+        let draft_result = self.session_store.lock().draft();
+        match draft_result {
+            Ok(Some((text, cursor))) => {
+                self.cursor_pos = cursor.min(text.len());
+                self.input = text;
+                self.normalize_cursor_pos();
+                self.typeahead.sync(&self.input, self.cursor_pos);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
         }
     }
 
     /// Handle a keyboard event. Returns true if the event was consumed.
     pub fn handle_key(&mut self, key: KeyEvent) -> bool {
         self.normalize_cursor_pos();
+        if let Some(pending) = self.pending_write.take() {
+            let cancel =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Char('y') => pending.answer(true),
+                KeyCode::Char('n') | KeyCode::Esc => pending.answer(false),
+                // Dropping the question refuses the write; the turn is
+                // cancelled below.
+                _ if cancel => {}
+                _ => self.pending_write = Some(pending),
+            }
+            if !cancel {
+                return true;
+            }
+        }
+        // Editing the prompt answers the file path question too.
+        if self.file_prompt.is_some() && !matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+            self.file_prompt = None;
+        }
         match key.code {
             // Quit
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -98,7 +352,41 @@ impl App {
                 true
             }
 
+            KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.open_palette();
+                true
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.attach_last_output();
+                true
+            }
+            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.bookmark_turn(0, None);
+                true
+            }
+
+            _ if self.request_view.is_some() => self.handle_request_view_key(key),
+            _ if self.diff_view.is_some() => self.handle_diff_view_key(key),
+            _ if self.comparison.is_some() => self.handle_comparison_key(key),
+            _ if self.context_focus.is_some() => self.handle_context_key(key),
+            _ if self.help.is_some() => self.handle_help_key(key),
+            KeyCode::F(1) => {
+                self.open_help();
+                true
+            }
+            KeyCode::Char('?')
+                if self.input.is_empty()
+                    && self.composer.is_prompt()
+                    && self.palette.is_none()
+                    && self.resume_dialog.is_none() =>
+            {
+                self.open_help();
+                true
+            }
+
+            _ if self.palette.is_some() => self.handle_palette_key(key),
             _ if self.resume_dialog.is_some() => self.handle_resume_dialog_key(key),
+            _ if self.navigation.is_some() => self.handle_navigation_key(key),
 
             // Submit
             KeyCode::Enter
@@ -113,7 +401,19 @@ impl App {
                     self.apply_typeahead_activation(activation);
                     return true;
                 }
-                if matches!(self.state, AppState::Idle) && !self.input.trim().is_empty() {
+                if matches!(self.state, AppState::Idle)
+                    && let Some(prompt) = self.file_prompt.take()
+                {
+                    self.submit_text(prompt.mention);
+                } else if matches!(self.state, AppState::Idle) && self.budget_pending.is_some() {
+                    self.confirm_budget_overrun();
+                } else if matches!(self.state, AppState::Idle) && self.large_request.is_some() {
+                    self.confirm_large_request();
+                } else if !self.composer.is_prompt() {
+                    self.submit_composer_input();
+                } else if matches!(self.state, AppState::Idle)
+                    && (!self.input.trim().is_empty() || !self.pasted.is_empty())
+                {
                     self.submit();
                 }
                 true
@@ -127,11 +427,24 @@ impl App {
 
             // Escape → cancel if generating
             KeyCode::Esc => {
-                if matches!(self.state, AppState::Generating { .. }) {
+                if let Some(prompt) = self.file_prompt.take() {
+                    self.submit_text(prompt.text);
+                } else if self.large_request.take().is_some() {
+                    // The prompt stays in the composer to be cut down.
+                    self.stream_chunks
+                        .push(StreamChunk::Tool("[not sent]".to_string()));
+                } else if matches!(self.state, AppState::Generating { .. }) {
                     self.finalize_response();
                     self.state = AppState::Idle;
-                } else {
+                } else if self.typeahead_is_visible() {
                     self.typeahead.dismiss(&self.input, self.cursor_pos);
+                } else if self.composer.is_prompt() && self.budget_pending.is_none() {
+                    // With nothing to cancel, Esc moves the keyboard to the
+                    // transcript.
+                    self.start_navigation();
+                } else {
+                    self.budget_pending = None;
+                    self.set_composer(ComposerMode::Prompt);
                 }
                 true
             }
@@ -141,6 +454,20 @@ impl App {
                 self.move_cursor_to_line_start();
                 true
             }
+            KeyCode::Char('t')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.large_request.is_some() =>
+            {
+                self.compact_for_large_request();
+                true
+            }
+            KeyCode::Char('d')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.large_request.is_some() =>
+            {
+                self.drop_large_request_attachments();
+                true
+            }
             KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.move_cursor_to_line_end();
                 true
@@ -151,6 +478,11 @@ impl App {
                 self.typeahead.sync(&self.input, self.cursor_pos);
                 true
             }
+            // Backspace in an empty composer drops the last held-out paste
+            KeyCode::Backspace if self.input.is_empty() && !self.pasted.is_empty() => {
+                self.pasted.pop();
+                true
+            }
             KeyCode::Backspace => {
                 if self.cursor_pos > 0 {
                     let start = prev_char_boundary(&self.input, self.cursor_pos);
@@ -227,7 +559,43 @@ impl App {
 
     /// Handle a mouse event.
     pub fn handle_mouse(&mut self, mouse: MouseEvent) {
-        if self.resume_dialog.is_some() {
+        if self.resume_dialog.is_some() || self.palette.is_some() {
+            return;
+        }
+        if let Some(view) = self.diff_view.as_mut() {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => view.scroll = view.scroll.saturating_sub(3),
+                MouseEventKind::ScrollDown => view.scroll = view.scroll.saturating_add(3),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(view) = self.request_view.as_mut() {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => view.scroll = view.scroll.saturating_sub(3),
+                MouseEventKind::ScrollDown => view.scroll = view.scroll.saturating_add(3),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(comparison) = self.comparison.as_mut() {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => {
+                    comparison.scroll = comparison.scroll.saturating_sub(3);
+                }
+                MouseEventKind::ScrollDown => {
+                    comparison.scroll = comparison.scroll.saturating_add(3);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let Some(help) = self.help.as_mut() {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => help.scroll = help.scroll.saturating_sub(3),
+                MouseEventKind::ScrollDown => help.scroll = help.scroll.saturating_add(3),
+                _ => {}
+            }
             return;
         }
 
@@ -243,7 +611,15 @@ impl App {
     }
 
     pub fn handle_paste(&mut self, text: &str) {
-        if self.resume_dialog.is_some() || text.is_empty() {
+        if self.resume_dialog.is_some()
+            || self.palette.is_some()
+            || self.help.is_some()
+            || self.request_view.is_some()
+            || self.diff_view.is_some()
+            || self.navigation.is_some()
+            || self.comparison.is_some()
+            || text.is_empty()
+        {
             return;
         }
 
@@ -251,6 +627,11 @@ impl App {
         if normalized.is_empty() {
             return;
         }
+        if normalized.len() > PASTE_ATTACH_BYTES || normalized.lines().count() > PASTE_ATTACH_LINES
+        {
+            self.pasted.push(Attachment::pasted(&normalized));
+            return;
+        }
 
         self.normalize_cursor_pos();
         self.input.insert_str(self.cursor_pos, &normalized);
@@ -261,14 +642,20 @@ impl App {
     /// Handle an agent event.
     pub fn handle_agent_event(&mut self, event: AgentEvent) {
         match event {
-            AgentEvent::UserMessage { content } => {
+            AgentEvent::UserMessage {
+                content,
+                attachments,
+            } => {
                 // Clear input now that the message is recorded in history
                 self.input.clear();
                 self.cursor_pos = 0;
                 self.typeahead.sync(&self.input, self.cursor_pos);
+                let mut chunks = vec![StreamChunk::Text(content)];
+                chunks.extend(attachments.iter().map(|summary| attachment_chunk(summary)));
                 self.messages.push(DisplayMessage {
                     role: Role::User,
-                    chunks: vec![StreamChunk::Text(content)],
+                    chunks,
+                    created_at_ms: Some(now_ms()),
                 });
             }
             AgentEvent::TextDelta { delta } => {
@@ -280,6 +667,14 @@ impl App {
                 }
                 self.cursor_blink_on = true;
             }
+            AgentEvent::ReasoningSectionStart { .. } => {
+                // Deltas of the new section go to a block of their own.
+                if matches!(self.stream_chunks.last(), Some(StreamChunk::Reasoning(s)) if !s.is_empty())
+                {
+                    self.stream_chunks
+                        .push(StreamChunk::Reasoning(String::new()));
+                }
+            }
             AgentEvent::ReasoningDelta { delta } => {
                 // Append to the last Reasoning chunk, or start a new one.
                 if let Some(StreamChunk::Reasoning(s)) = self.stream_chunks.last_mut() {
@@ -293,27 +688,93 @@ impl App {
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[{}...]", display.title)));
             }
-            AgentEvent::ToolCallDone { display, .. } => {
+            AgentEvent::ToolCallDone {
+                id,
+                display,
+                record,
+                raw_output,
+            } => {
+                if let Err(err) = self.session_store.lock().record_tool_call(&record) {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[audit error: {err}]")));
+                }
                 let diff = diff_from_display_body(display.body.as_ref());
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[{}]", display.title)));
                 if let Some(diff) = diff {
                     push_tool_diff_chunks(&mut self.stream_chunks, diff);
                 }
+                if let Some(raw_output) = raw_output {
+                    let message = match self.session_store.lock().save_tool_output(&id, &raw_output)
+                    {
+                        Ok(()) => RAW_OUTPUT_SAVED.to_string(),
+                        Err(err) => format!("[session save error: {err}]"),
+                    };
+                    self.stream_chunks.push(StreamChunk::Tool(message));
+                }
+            }
+            AgentEvent::HostedToolStart { display, .. } => {
+                self.stream_chunks
+                    .push(StreamChunk::HostedTool(format!("[{}...]", display.title)));
+            }
+            AgentEvent::HostedToolDone {
+                display, result, ..
+            } => {
+                self.stream_chunks.push(StreamChunk::HostedTool(format!(
+                    "[{} · {}]",
+                    display.title, result.title
+                )));
+            }
+            AgentEvent::Image { url } => self.stream_chunks.push(image_chunk(&url)),
+            AgentEvent::Refusal { message, guidance } => {
+                if let Some(message) = message {
+                    self.stream_chunks.push(StreamChunk::Text(message));
+                }
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[refused: {guidance}]")));
+            }
+            AgentEvent::Truncated { guidance, .. } => {
+                self.stream_chunks.push(StreamChunk::Tool(format!(
+                    "[cut off at the output token limit: {guidance}]"
+                )));
             }
-            AgentEvent::TurnComplete { usage } => {
+            AgentEvent::ResponsePending { token } => {
                 if let Err(err) = self
                     .session_store
                     .lock()
-                    .persist_turn_from_agent(&self.agent, &usage)
+                    .save_pending_response(&self.agent, &token)
+                {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+                }
+            }
+            AgentEvent::TurnComplete { usage, stats } => {
+                if let Err(err) =
+                    self.session_store
+                        .lock()
+                        .persist_turn_from_agent(&self.agent, &usage, &stats)
                 {
                     self.stream_chunks
                         .push(StreamChunk::Tool(format!("[session save error: {err}]")));
                 }
+                if let Err(err) = self.session_store.lock().close_jj_change() {
+                    self.stream_chunks
+                        .push(StreamChunk::Tool(format!("[jj error: {err}]")));
+                }
+                let footer = turn_footer(&usage, &stats);
+                self.session_store
+                    .lock()
+                    .notify(WebhookEvent::TurnComplete, &footer);
+                self.stream_chunks.push(StreamChunk::Footer(footer));
+                self.refresh_session_title();
+                self.refresh_budget_status();
                 self.finalize_response();
                 self.state = AppState::Idle;
             }
             AgentEvent::Error { error } => {
+                self.session_store
+                    .lock()
+                    .notify(WebhookEvent::Error, &error);
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[error: {error}]")));
                 self.finalize_response();
@@ -323,26 +784,180 @@ impl App {
     }
 
     fn submit(&mut self) {
+        let text = presubmit::run(&self.presubmit, self.input.clone());
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        if self.pasted.is_empty()
+            && let Some(prompt) = FilePrompt::detect(&text, &cwd)
+        {
+            self.file_prompt = Some(prompt);
+            return;
+        }
+        self.submit_text(text);
+    }
+
+    fn submit_text(&mut self, text: String) {
+        if !self.budget_overrun_confirmed
+            && self
+                .budget_status
+                .as_ref()
+                .is_some_and(BudgetStatus::is_exceeded)
+        {
+            self.budget_pending = Some(text);
+            return;
+        }
+
         let ensure_session_result = self.session_store.lock().ensure_active_session();
         if let Err(err) = ensure_session_result {
             self.stream_chunks
                 .push(StreamChunk::Tool(format!("[session error: {err}]")));
             return;
         }
-
-        let text = self.input.trim().to_string();
+        let artifacts_dir = self.session_store.lock().artifacts_dir();
+        self.agent.artifacts_dir(artifacts_dir);
+
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let mut attachments = if self.attach_mentions {
+            mention_attachments(&text, &cwd)
+        } else {
+            Vec::new()
+        };
+        // Pastes stay in the composer until the request is sent.
+        attachments.extend(self.pasted.iter().cloned());
         self.stream_chunks.clear();
+        let stale_context = snapshots::stale_context(&mut self.session_store.lock());
+        match stale_context {
+            Ok(note) => attachments.extend(note),
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+        attachments.extend(summaries::resume_note(&mut self.session_store.lock()));
+        if let Some(size) = self.request_guard.check(&self.agent, &text, &attachments) {
+            self.stream_chunks.push(StreamChunk::Tool(size.summary()));
+            self.large_request = Some(HeldRequest { text, attachments });
+            return;
+        }
+        self.send(text, attachments);
+    }
+
+    fn send(&mut self, text: String, attachments: Vec<Attachment>) {
+        self.pasted.clear();
+        self.models_notice = None;
+        if let Err(err) = self.session_store.lock().begin_turn(&text) {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+        }
         // Input stays visible until UserMessage event confirms it's in history
-        let stream = self.agent.submit(&text);
+        let stream = self.agent.submit_with_attachments(&text, attachments);
         self.state = AppState::Generating { stream };
     }
 
+    fn confirm_large_request(&mut self) {
+        if let Some(held) = self.large_request.take() {
+            self.send(held.text, held.attachments);
+        }
+    }
+
+    /// Drop the tool results in the history to make the held request fit.
+    fn compact_for_large_request(&mut self) {
+        let removed = self.agent.remove_tool_results(|_, _| true);
+        self.stream_chunks.push(StreamChunk::Tool(format!(
+            "[dropped {removed} tool result(s) from the history]"
+        )));
+        self.recheck_large_request();
+    }
+
+    fn drop_large_request_attachments(&mut self) {
+        let Some(held) = self.large_request.as_mut() else {
+            return;
+        };
+        let dropped = held.drop_attachments();
+        self.stream_chunks.push(StreamChunk::Tool(format!(
+            "[dropped {dropped} attachment(s)]"
+        )));
+        self.pasted.clear();
+        self.recheck_large_request();
+    }
+
+    /// Show the held request's size again after cutting it down. It stays
+    /// held either way, for Enter to send.
+    fn recheck_large_request(&mut self) {
+        let Some(held) = self.large_request.as_ref() else {
+            return;
+        };
+        let message = match self
+            .request_guard
+            .check(&self.agent, &held.text, &held.attachments)
+        {
+            Some(size) => size.summary(),
+            None => "[the request is within the limits now]".to_string(),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    /// Continue a turn whose background response was still generating when
+    /// an earlier run exited.
+    fn resume_pending_response(&mut self) {
+        let pending_result = self.session_store.lock().pending_response();
+        match pending_result {
+            Ok(Some((token, state))) => {
+                self.restore_active_session_state(Some(state));
+                self.stream_chunks
+                    .push(StreamChunk::Tool(RESUMING_RESPONSE.to_string()));
+                let stream = self.agent.resume(token);
+                self.state = AppState::Generating { stream };
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn confirm_budget_overrun(&mut self) {
+        let Some(text) = self.budget_pending.take() else {
+            return;
+        };
+        self.budget_overrun_confirmed = true;
+        self.submit_text(text);
+    }
+
+    /// The first stored turn names the session.
+    fn refresh_session_title(&mut self) {
+        let title_result = self.session_store.lock().active_session_title();
+        match title_result {
+            Ok(title) => self.session_title = title,
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn refresh_budget_status(&mut self) {
+        let status_result = self.session_store.lock().budget_status();
+        match status_result {
+            Ok(status) => self.budget_status = status,
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[budget error: {err}]")));
+            }
+        }
+    }
+
     fn finalize_response(&mut self) {
+        if let Err(err) = self.session_store.lock().end_turn() {
+            self.stream_chunks
+                .push(StreamChunk::Tool(format!("[session save error: {err}]")));
+        }
         let chunks = std::mem::take(&mut self.stream_chunks);
         if !chunks.is_empty() {
             self.messages.push(DisplayMessage {
                 role: Role::Assistant,
                 chunks,
+                created_at_ms: Some(now_ms()),
             });
         }
     }
@@ -431,7 +1046,7 @@ impl App {
     }
 
     pub fn typeahead_matches(&mut self) -> Option<ActiveTypeahead> {
-        if self.resume_dialog.is_some() {
+        if self.resume_dialog.is_some() || self.palette.is_some() {
             return None;
         }
         self.typeahead.visible_matches(&self.input, self.cursor_pos)
@@ -465,10 +1080,7 @@ impl App {
     }
 
     fn apply_mention(&mut self, mention: Mention, token_start: usize, token_end: usize) {
-        let mention_text = match mention {
-            Mention::File(path) => path.to_string_lossy().replace('\\', "/"),
-        };
-        let replacement = format!("{mention_text} ");
+        let replacement = format!("{} ", mention.token());
         self.input
             .replace_range(token_start..token_end, &replacement);
         self.cursor_pos = token_start + replacement.len();
@@ -479,48 +1091,115 @@ impl App {
         match command {
             Command::NewSession => self.start_new_session(),
             Command::ResumeSession => self.open_resume_dialog(),
+            Command::PinSession => self.toggle_active_session_pinned(),
+            Command::Retry => self.retry_last_turn(None),
+            Command::RetryWith => self.start_retry_with(),
+            Command::RawOutput => self.show_raw_output(),
+            Command::LastOutput => self.attach_last_output(),
+            Command::Changes => self.show_session_changes(),
+            Command::Patch => self.export_patch(),
+            Command::Share => self.share_session(),
+            Command::History => self.start_history_search(),
+            Command::ProjectSettings => self.start_settings_edit(),
+            Command::DebugRequest => self.show_request(),
+            Command::DebugContext => self.show_context_breakdown(),
+            Command::ModelsRefresh => self.refresh_models(),
+            Command::AddContext => self.start_context_add(),
+            Command::Context => self.focus_context(),
+            Command::Compare => self.start_compare(),
+            Command::Bookmark => self.start_bookmark_input(),
+            Command::Bookmarks => self.open_bookmarks(),
+            Command::Artifacts => self.show_artifacts(),
+            Command::Template(template) => self.start_template(template),
         }
     }
 
-    fn start_new_session(&mut self) {
-        if matches!(self.state, AppState::Generating { .. }) {
-            self.finalize_response();
-            self.state = AppState::Idle;
+    /// Switch the composer to `mode`, emptied.
+    fn set_composer(&mut self, mode: ComposerMode) {
+        self.composer = mode;
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.typeahead.sync(&self.input, self.cursor_pos);
+    }
+
+    /// Enter in a composer that doesn't hold a prompt. Apart from comparing
+    /// and bookmarking, this waits for the response being generated.
+    fn submit_composer_input(&mut self) {
+        let idle = matches!(self.state, AppState::Idle);
+        match self.composer {
+            ComposerMode::Prompt => {}
+            ComposerMode::Template(_) if idle => self.provide_template_value(),
+            ComposerMode::HistorySearch if idle => self.search_history(),
+            ComposerMode::Settings if idle => self.save_project_settings(),
+            ComposerMode::ContextAdd if idle => self.pin_context_input(),
+            ComposerMode::Compare => self.run_comparison(),
+            ComposerMode::Bookmark => self.save_bookmark_input(),
+            ComposerMode::RetryWith => self.retry_with_input(),
+            ComposerMode::Template(_)
+            | ComposerMode::HistorySearch
+            | ComposerMode::Settings
+            | ComposerMode::ContextAdd => {}
         }
+    }
 
-        self.session_store.lock().clear_active_session();
-        self.restore_active_session_state(None);
+    fn start_template(&mut self, template: PromptTemplate) {
+        self.set_composer(ComposerMode::Template(TemplateFill::new(template)));
+        self.submit_template_if_complete();
     }
 
-    fn open_resume_dialog(&mut self) {
-        if matches!(self.state, AppState::Generating { .. }) {
-            self.finalize_response();
-            self.state = AppState::Idle;
-        }
+    fn provide_template_value(&mut self) {
+        let ComposerMode::Template(fill) = &mut self.composer else {
+            return;
+        };
+        fill.provide(self.input.trim().to_string());
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.typeahead.sync(&self.input, self.cursor_pos);
+        self.submit_template_if_complete();
+    }
 
-        let (active_session_id, sessions_result) = {
-            let store = self.session_store.lock();
-            (
-                store.active_session_id().map(str::to_owned),
-                store.list_sessions(100),
-            )
+    fn submit_template_if_complete(&mut self) {
+        let complete = match &self.composer {
+            ComposerMode::Template(fill) => fill.pending_variable().is_none(),
+            _ => false,
+        };
+        if !complete {
+            return;
+        }
+        let ComposerMode::Template(fill) = std::mem::take(&mut self.composer) else {
+            return;
         };
 
-        match sessions_result {
-            Ok(mut sessions) => {
-                if let Some(active_session_id) = active_session_id {
-                    sessions.retain(|session| session.id != active_session_id);
-                }
+        match fill.render() {
+            Ok(text) if !text.trim().is_empty() => self.submit_text(text.trim().to_string()),
+            Ok(_) => {}
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[template error: {err}]")));
+            }
+        }
+    }
 
-                if sessions.is_empty() {
-                    self.stream_chunks.push(StreamChunk::Tool(
-                        "[no previous sessions to resume]".to_string(),
-                    ));
-                    return;
-                }
+    fn start_history_search(&mut self) {
+        self.set_composer(ComposerMode::HistorySearch);
+    }
 
-                self.resume_dialog = Some(ResumeSessionDialogState::new(
-                    session_dialog::build_dialog_entries(sessions),
+    fn search_history(&mut self) {
+        let query = self.input.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        self.set_composer(ComposerMode::Prompt);
+
+        let search_result = self.session_store.lock().search_turns(&query, 50);
+        match search_result {
+            Ok(hits) if hits.is_empty() => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[no matches for {query:?}]")));
+            }
+            Ok(hits) => {
+                self.resume_dialog = Some(ResumeSessionDialogState::search_results(
+                    session_dialog::build_search_entries(hits),
                 ));
             }
             Err(err) => {
@@ -530,55 +1209,915 @@ impl App {
         }
     }
 
-    fn handle_resume_dialog_key(&mut self, key: KeyEvent) -> bool {
-        match key.code {
-            KeyCode::Esc => {
-                self.resume_dialog = None;
-                true
-            }
-            KeyCode::Up => {
-                self.move_resume_dialog_selection(-1);
-                true
-            }
-            KeyCode::Down => {
-                self.move_resume_dialog_selection(1);
-                true
+    fn start_settings_edit(&mut self) {
+        let settings_result = self.session_store.lock().project_settings();
+        match settings_result {
+            Ok(settings) => {
+                self.set_composer(ComposerMode::Settings);
+                self.input = project_settings::format(&settings);
+                self.cursor_pos = self.input.len();
+                self.typeahead.sync(&self.input, self.cursor_pos);
             }
-            KeyCode::Enter
-                if !key
-                    .modifiers
-                    .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
-            {
-                self.confirm_resume_dialog_selection();
-                true
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
             }
-            _ => true,
         }
     }
 
-    fn move_resume_dialog_selection(&mut self, direction: i32) {
-        let Some(dialog) = self.resume_dialog.as_mut() else {
-            return;
+    fn save_project_settings(&mut self) {
+        let settings = match project_settings::parse(&self.input) {
+            Ok(settings) => settings,
+            Err(err) => {
+                // Keep the composer contents so the mistake can be fixed.
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[settings error: {err}]")));
+                return;
+            }
         };
-        session_dialog::move_selection(dialog, direction);
-    }
+        self.set_composer(ComposerMode::Prompt);
 
-    fn confirm_resume_dialog_selection(&mut self) {
-        let Some(dialog) = self.resume_dialog.take() else {
+        let save_result = self.session_store.lock().update_project_settings(&settings);
+        let message = match save_result {
+            Ok(()) => "[project settings saved; they apply the next time agnt starts]".to_string(),
+            Err(err) => format!("[session error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    fn start_context_add(&mut self) {
+        self.set_composer(ComposerMode::ContextAdd);
+    }
+
+    fn pin_context_input(&mut self) {
+        let input = self.input.trim().to_string();
+        if input.is_empty() {
+            return;
+        }
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        match pinned::resolve(&input, &cwd, self.offline) {
+            Ok(item) => {
+                self.set_composer(ComposerMode::Prompt);
+                self.agent.pin_context(item);
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => self
+                .stream_chunks
+                .push(StreamChunk::Tool(format!("[context error: {err}]"))),
+        }
+    }
+
+    fn focus_context(&mut self) {
+        if self.agent.pinned_context().is_empty() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[nothing pinned; /add pins a file, URL or note]".to_string(),
+            ));
+            return;
+        }
+        self.context_focus = Some(0);
+    }
+
+    fn handle_context_key(&mut self, key: KeyEvent) -> bool {
+        let Some(selected) = self.context_focus else {
+            return false;
+        };
+        let count = self.agent.pinned_context().len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.context_focus = None,
+            KeyCode::Up => self.context_focus = Some(selected.saturating_sub(1)),
+            KeyCode::Down => self.context_focus = Some((selected + 1).min(count.saturating_sub(1))),
+            KeyCode::Delete | KeyCode::Backspace | KeyCode::Char('x') => {
+                self.agent.unpin_context(selected);
+                self.context_focus = (count > 1).then(|| selected.min(count - 2));
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn start_compare(&mut self) {
+        if self.compare_models.is_none() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[comparing models isn't available here]".to_string(),
+            ));
+            return;
+        }
+        self.set_composer(ComposerMode::Compare);
+    }
+
+    fn run_comparison(&mut self) {
+        let Some(models) = self.compare_models.as_mut() else {
+            return;
+        };
+        let started = models
+            .parse(&self.input)
+            .map_err(|err| err.to_string())
+            .and_then(|(models, prompt)| Comparison::start(&self.agent, models, prompt));
+        match started {
+            Ok(comparison) => {
+                self.set_composer(ComposerMode::Prompt);
+                self.palette = None;
+                self.help = None;
+                self.comparison = Some(comparison);
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => self
+                .stream_chunks
+                .push(StreamChunk::Tool(format!("[compare error: {err}]"))),
+        }
+    }
+
+    fn start_bookmark_input(&mut self) {
+        self.set_composer(ComposerMode::Bookmark);
+    }
+
+    fn save_bookmark_input(&mut self) {
+        let note = self.input.trim().to_string();
+        self.set_composer(ComposerMode::Prompt);
+        self.bookmark_turn(0, Some(&note));
+    }
+
+    /// Bookmark the turn `turns_back` turns before the current one.
+    fn bookmark_turn(&mut self, turns_back: usize, note: Option<&str>) {
+        let bookmark_result = self.session_store.lock().bookmark_turn(turns_back, note);
+        let message = match bookmark_result {
+            Ok(true) => "[turn bookmarked; /bookmarks lists bookmarks]".to_string(),
+            Ok(false) => "[nothing to bookmark yet]".to_string(),
+            Err(err) => format!("[session error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    fn show_artifacts(&mut self) {
+        let lines_result = self.session_store.lock().artifact_lines();
+        match lines_result {
+            Ok(lines) => self
+                .stream_chunks
+                .extend(lines.into_iter().map(StreamChunk::Tool)),
+            Err(err) => self
+                .stream_chunks
+                .push(StreamChunk::Tool(format!("[artifacts error: {err}]"))),
+        }
+    }
+
+    fn open_bookmarks(&mut self) {
+        let bookmarks_result = self.session_store.lock().bookmarks(50);
+        match bookmarks_result {
+            Ok(bookmarks) if bookmarks.is_empty() => {
+                self.stream_chunks.push(StreamChunk::Tool(
+                    "[no bookmarks yet; /bookmark or Ctrl+B bookmarks the last turn]".to_string(),
+                ));
+            }
+            Ok(bookmarks) => {
+                self.resume_dialog = Some(ResumeSessionDialogState::bookmarks(
+                    session_dialog::build_bookmark_entries(bookmarks),
+                ));
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    pub fn handle_comparison_event(&mut self, pane: usize, event: AgentEvent) {
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.handle_event(pane, event);
+        }
+    }
+
+    fn handle_comparison_key(&mut self, key: KeyEvent) -> bool {
+        let Some(comparison) = self.comparison.as_mut() else {
+            return false;
+        };
+        match key.code {
+            // Dropping the streams stops answers still generating.
+            KeyCode::Esc | KeyCode::Char('q') => self.comparison = None,
+            KeyCode::Up => comparison.scroll = comparison.scroll.saturating_sub(1),
+            KeyCode::Down => comparison.scroll = comparison.scroll.saturating_add(1),
+            KeyCode::PageUp => comparison.scroll = comparison.scroll.saturating_sub(10),
+            KeyCode::PageDown => comparison.scroll = comparison.scroll.saturating_add(10),
+            KeyCode::Home => comparison.scroll = 0,
+            _ => {}
+        }
+        true
+    }
+
+    fn start_new_session(&mut self) {
+        if matches!(self.state, AppState::Generating { .. }) {
+            self.finalize_response();
+            self.state = AppState::Idle;
+        }
+
+        self.save_draft();
+        summaries::summarize_active(&self.agent, &self.session_store);
+        self.session_store.lock().clear_active_session();
+        self.restore_active_session_state(None);
+    }
+
+    fn start_retry_with(&mut self) {
+        if self.compare_models.is_none() {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[switching models isn't available here]".to_string(),
+            ));
+            return;
+        }
+        self.set_composer(ComposerMode::RetryWith);
+    }
+
+    fn retry_with_input(&mut self) {
+        let Some(models) = self.compare_models.as_mut() else {
+            return;
+        };
+        let current = self.session_store.lock().model_label().unwrap_or_default();
+        match models.switch(&self.input, &current) {
+            Ok(switch) => {
+                self.set_composer(ComposerMode::Prompt);
+                self.retry_last_turn(Some(switch));
+            }
+            // Keep the composer contents so the mistake can be fixed.
+            Err(err) => self
+                .stream_chunks
+                .push(StreamChunk::Tool(format!("[retry error: {err}]"))),
+        }
+    }
+
+    /// Generate the last response again, with `switch`'s model and effort
+    /// from then on when given.
+    fn retry_last_turn(&mut self, switch: Option<ModelSwitch>) {
+        if matches!(self.state, AppState::Generating { .. }) {
+            self.finalize_response();
+            self.state = AppState::Idle;
+        }
+
+        let retry_result = self
+            .session_store
+            .lock()
+            .prepare_retry(&self.agent.messages());
+        match retry_result {
+            Ok(Some(retry)) => {
+                self.restore_active_session_state(Some(retry.state));
+                if let Some(switch) = switch {
+                    switch.apply(&mut self.agent, &mut self.session_store.lock());
+                }
+                self.submit_text(retry.user_text);
+            }
+            Ok(None) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool("[nothing to retry]".to_string()));
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn show_raw_output(&mut self) {
+        let output_result = self.session_store.lock().latest_tool_output();
+        match output_result {
+            Ok(Some(output)) => push_tool_diff_chunks(&mut self.stream_chunks, &output),
+            Ok(None) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool("[no truncated tool output]".to_string()));
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn attach_last_output(&mut self) {
+        match Attachment::last_tool_output(&self.agent.messages()) {
+            Some(output) => {
+                if !self.pasted.iter().any(|held| held.label == output.label) {
+                    self.pasted.push(output);
+                }
+            }
+            None => self
+                .stream_chunks
+                .push(StreamChunk::Tool("[no tool output yet]".to_string())),
+        }
+    }
+
+    fn show_session_changes(&mut self) {
+        let changes_result = crate::changes::session_changes(&self.session_store.lock());
+        match changes_result {
+            Ok(Some(changes)) => {
+                if changes.diff.is_empty() && changes.unknown.is_empty() {
+                    self.stream_chunks.push(StreamChunk::Tool(
+                        "[no net file changes in this session]".to_string(),
+                    ));
+                }
+                push_tool_diff_chunks(&mut self.stream_chunks, &changes.diff);
+                for path in changes.unknown {
+                    self.stream_chunks.push(StreamChunk::Tool(format!(
+                        "[{path} was changed outside agnt; see its diffs in the conversation]"
+                    )));
+                }
+            }
+            Ok(None) => {
+                self.stream_chunks.push(StreamChunk::Tool(
+                    "[no file changes in this session]".to_string(),
+                ));
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[changes error: {err}]")));
+            }
+        }
+    }
+
+    fn show_request(&mut self) {
+        match self.agent.inspect_request() {
+            Some(inspection) => {
+                self.palette = None;
+                self.help = None;
+                self.request_view = Some(RequestView {
+                    inspection,
+                    scroll: 0,
+                });
+            }
+            None => self.stream_chunks.push(StreamChunk::Tool(
+                "[this provider's requests can't be shown]".to_string(),
+            )),
+        }
+    }
+
+    fn refresh_models(&mut self) {
+        match &self.spec_refresh {
+            Some(refresh) => refresh.request(),
+            None => self.stream_chunks.push(StreamChunk::Tool(
+                "[offline mode: the models.dev catalog is not fetched]".to_string(),
+            )),
+        }
+    }
+
+    /// Take in a refreshed models.dev catalog and announce what it added.
+    pub fn handle_spec_update(&mut self, update: SpecUpdate) {
+        if let Some(notice) = update.notice() {
+            self.models_notice = Some(notice);
+        }
+        if let (Ok(spec), Some(models)) = (update.spec, self.compare_models.as_mut()) {
+            models.load_spec(spec);
+        }
+    }
+
+    fn show_context_breakdown(&mut self) {
+        let breakdown = self.request_guard.breakdown(&self.agent);
+        self.stream_chunks
+            .extend(breakdown.lines().into_iter().map(StreamChunk::Tool));
+    }
+
+    fn handle_request_view_key(&mut self, key: KeyEvent) -> bool {
+        let Some(view) = self.request_view.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.request_view = None,
+            KeyCode::Up => view.scroll = view.scroll.saturating_sub(1),
+            KeyCode::Down => view.scroll = view.scroll.saturating_add(1),
+            KeyCode::PageUp => view.scroll = view.scroll.saturating_sub(10),
+            KeyCode::PageDown => view.scroll = view.scroll.saturating_add(10),
+            KeyCode::Home => view.scroll = 0,
+            _ => {}
+        }
+        true
+    }
+
+    fn start_navigation(&mut self) {
+        let Some(last) = self.messages.len().checked_sub(1) else {
+            return;
+        };
+        let selected = navigation::blocks(&self.messages)
+            .iter()
+            .position(|block| block.message == last && block.tool.is_none())
+            .unwrap_or_default();
+        self.navigation = Some(Navigation {
+            selected,
+            reveal: true,
+        });
+    }
+
+    fn handle_navigation_key(&mut self, key: KeyEvent) -> bool {
+        let Some(nav) = self.navigation.as_mut() else {
+            return false;
+        };
+        let blocks = navigation::blocks(&self.messages);
+        let Some(&block) = blocks.get(nav.selected) else {
+            self.navigation = None;
+            return true;
+        };
+        let last = blocks.len() - 1;
+        let previous = nav.selected;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q' | 'i') => self.navigation = None,
+            KeyCode::Up | KeyCode::Char('k') => nav.selected = nav.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => nav.selected = (nav.selected + 1).min(last),
+            KeyCode::Home | KeyCode::Char('g') => nav.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => nav.selected = last,
+            KeyCode::PageUp => self.scroll_by(10),
+            KeyCode::PageDown => self.scroll_by(-10),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if self.collapsed.contains(&block) {
+                    self.collapsed.remove(&block);
+                } else {
+                    self.collapsed.insert(block);
+                }
+            }
+            KeyCode::Char('c' | 'y') => self.copy_block(block),
+            KeyCode::Char('b') => {
+                self.bookmark_turn(navigation::turns_back(&self.messages, block), None);
+            }
+            KeyCode::Char('o') => self.open_block(block),
+            _ => {}
+        }
+        if let Some(nav) = self.navigation.as_mut()
+            && nav.selected != previous
+        {
+            nav.reveal = true;
+        }
+        true
+    }
+
+    /// Copy `block` to the system clipboard through the terminal (OSC 52).
+    fn copy_block(&mut self, block: Block) {
+        let text = navigation::text(&self.messages, block);
+        let copied = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::clipboard::CopyToClipboard::to_clipboard_from(&text)
+        );
+        let message = match copied {
+            Ok(()) => match text.lines().count() {
+                1 => "[copied 1 line]".to_string(),
+                count => format!("[copied {count} lines]"),
+            },
+            Err(err) => format!("[copy error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    /// Show `block`'s diff, or open its image in the system viewer.
+    fn open_block(&mut self, block: Block) {
+        if let Some(diff) = navigation::diff(&self.messages, block) {
+            self.diff_view = Some(DiffView {
+                title: navigation::title(&self.messages, block)
+                    .unwrap_or_else(|| t(Msg::DiffTitle).to_string()),
+                diff,
+                scroll: 0,
+            });
+        } else if let Some(image) = navigation::image(&self.messages, block) {
+            if let Err(err) = webbrowser::open(&images::open_url(image)) {
+                self.stream_chunks.push(StreamChunk::Tool(format!(
+                    "[could not open the image: {err}]"
+                )));
+            }
+        } else {
+            self.stream_chunks.push(StreamChunk::Tool(
+                "[no diff or image in the selection]".to_string(),
+            ));
+        }
+    }
+
+    fn handle_diff_view_key(&mut self, key: KeyEvent) -> bool {
+        let Some(view) = self.diff_view.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.diff_view = None,
+            KeyCode::Up => view.scroll = view.scroll.saturating_sub(1),
+            KeyCode::Down => view.scroll = view.scroll.saturating_add(1),
+            KeyCode::PageUp => view.scroll = view.scroll.saturating_sub(10),
+            KeyCode::PageDown => view.scroll = view.scroll.saturating_add(10),
+            KeyCode::Home => view.scroll = 0,
+            _ => {}
+        }
+        true
+    }
+
+    fn share_session(&mut self) {
+        let export_result = crate::share::export_active_session(&self.session_store.lock());
+        let message = match export_result {
+            Ok(Some(page)) => page.summary(),
+            Ok(None) => "[nothing to share yet]".to_string(),
+            Err(err) => format!("[share error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    fn export_patch(&mut self) {
+        let export_result = crate::patch::export_active_session(&self.session_store.lock());
+        let message = match export_result {
+            Ok(Some(series)) => series.summary(),
+            Ok(None) => "[no file changes in this session]".to_string(),
+            Err(err) => format!("[patch export error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    pub fn open_resume_dialog(&mut self) {
+        if matches!(self.state, AppState::Generating { .. }) {
+            self.finalize_response();
+            self.state = AppState::Idle;
+        }
+
+        let (active_session_id, sessions_result) = {
+            let store = self.session_store.lock();
+            (
+                store.active_session_id().map(str::to_owned),
+                store.list_sessions(100),
+            )
+        };
+
+        match sessions_result {
+            Ok(mut sessions) => {
+                if let Some(active_session_id) = active_session_id {
+                    sessions.retain(|session| session.id != active_session_id);
+                }
+
+                if sessions.is_empty() {
+                    self.stream_chunks.push(StreamChunk::Tool(
+                        "[no previous sessions to resume]".to_string(),
+                    ));
+                    return;
+                }
+
+                self.resume_dialog = Some(ResumeSessionDialogState::new(
+                    session_dialog::build_dialog_entries(sessions),
+                ));
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn handle_resume_dialog_key(&mut self, key: KeyEvent) -> bool {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return false;
+        };
+        match (&mut dialog.mode, key.code) {
+            (DialogMode::Browse, _) => {}
+            (DialogMode::ConfirmDelete, KeyCode::Enter) => {
+                self.delete_selected_session();
+                return true;
+            }
+            (DialogMode::Rename(_), KeyCode::Enter) => {
+                self.rename_selected_session();
+                return true;
+            }
+            (DialogMode::Rename(title), KeyCode::Char(c)) => {
+                title.push(c);
+                return true;
+            }
+            (DialogMode::Rename(title), KeyCode::Backspace) => {
+                title.pop();
+                return true;
+            }
+            (_, KeyCode::Esc) => {
+                dialog.mode = DialogMode::Browse;
+                return true;
+            }
+            _ => return true,
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                self.resume_dialog = None;
+                true
+            }
+            KeyCode::Char('d') if dialog.manages_sessions => {
+                dialog.mode = DialogMode::ConfirmDelete;
+                true
+            }
+            KeyCode::Char('d') if dialog.lists_bookmarks => {
+                self.remove_selected_bookmark();
+                true
+            }
+            KeyCode::Char('p') if dialog.manages_sessions => {
+                self.toggle_selected_session_pinned();
+                true
+            }
+            KeyCode::Char('b') if dialog.lists_search_hits => {
+                self.confirm_resume_dialog_selection(true);
+                true
+            }
+            KeyCode::Char('r') if dialog.manages_sessions => {
+                let title = session_dialog::selected_entry(dialog)
+                    .and_then(|entry| entry.session_title.clone())
+                    .unwrap_or_default();
+                dialog.mode = DialogMode::Rename(title);
+                true
+            }
+            KeyCode::Up => {
+                self.move_resume_dialog_selection(-1);
+                true
+            }
+            KeyCode::Down => {
+                self.move_resume_dialog_selection(1);
+                true
+            }
+            KeyCode::Enter
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+            {
+                self.confirm_resume_dialog_selection(false);
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn remove_selected_bookmark(&mut self) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        let Some(turn_id) =
+            session_dialog::selected_entry(dialog).and_then(|entry| entry.turn_id.clone())
+        else {
+            return;
+        };
+
+        let remove_result = self.session_store.lock().remove_bookmark(&turn_id);
+        match remove_result {
+            Ok(()) => {
+                dialog.remove_selected();
+                if dialog.entries.is_empty() {
+                    self.resume_dialog = None;
+                }
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn delete_selected_session(&mut self) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        dialog.mode = DialogMode::Browse;
+        let Some(session_id) =
+            session_dialog::selected_entry(dialog).map(|entry| entry.session_id.clone())
+        else {
+            return;
+        };
+
+        let delete_result = self.session_store.lock().delete_session(&session_id);
+        match delete_result {
+            Ok(()) => {
+                dialog.remove_session(&session_id);
+                if dialog.entries.is_empty() {
+                    self.resume_dialog = None;
+                }
+            }
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn toggle_selected_session_pinned(&mut self) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
             return;
         };
-        let Some(session_id) = session_dialog::selected_session_id(&dialog).map(str::to_owned)
+        let Some(session_id) =
+            session_dialog::selected_entry(dialog).map(|entry| entry.session_id.clone())
         else {
             return;
         };
 
-        let activate_result = self.session_store.lock().activate_session(&session_id);
+        let pin_result = self.session_store.lock().toggle_session_pinned(&session_id);
+        match pin_result {
+            Ok(session) => dialog.update_session(&session),
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    /// `/pin`: pin or unpin the open session.
+    fn toggle_active_session_pinned(&mut self) {
+        let mut store = self.session_store.lock();
+        let Some(session_id) = store.active_session_id().map(str::to_owned) else {
+            self.stream_chunks
+                .push(StreamChunk::Tool("[no session to pin yet]".to_string()));
+            return;
+        };
+        let message = match store.toggle_session_pinned(&session_id) {
+            Ok(session) if session.pinned => "[session pinned]".to_string(),
+            Ok(_) => "[session unpinned]".to_string(),
+            Err(err) => format!("[session error: {err}]"),
+        };
+        self.stream_chunks.push(StreamChunk::Tool(message));
+    }
+
+    fn rename_selected_session(&mut self) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        let DialogMode::Rename(title) = std::mem::replace(&mut dialog.mode, DialogMode::Browse)
+        else {
+            return;
+        };
+        let Some(session_id) =
+            session_dialog::selected_entry(dialog).map(|entry| entry.session_id.clone())
+        else {
+            return;
+        };
+
+        let rename_result = self
+            .session_store
+            .lock()
+            .rename_session(&session_id, &title);
+        match rename_result {
+            Ok(session) => dialog.update_session(&session),
+            Err(err) => {
+                self.resume_dialog = None;
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    fn move_resume_dialog_selection(&mut self, direction: i32) {
+        let Some(dialog) = self.resume_dialog.as_mut() else {
+            return;
+        };
+        session_dialog::move_selection(dialog, direction);
+    }
+
+    /// Open the selected entry. A search hit opens its session as it is
+    /// unless `branch` asks to continue from the matching turn.
+    fn confirm_resume_dialog_selection(&mut self, branch: bool) {
+        let Some(dialog) = self.resume_dialog.take() else {
+            return;
+        };
+        let Some(entry) = session_dialog::selected_entry(&dialog) else {
+            return;
+        };
+        let turn_id = entry
+            .turn_id
+            .as_deref()
+            .filter(|_| branch || !dialog.lists_search_hits);
+        self.resume_session(&entry.session_id, turn_id);
+    }
+
+    /// Switch to `session_id`, checked out at `turn_id` if given.
+    fn resume_session(&mut self, session_id: &str, turn_id: Option<&str>) {
+        if matches!(self.state, AppState::Generating { .. }) {
+            self.finalize_response();
+            self.state = AppState::Idle;
+        }
+        self.save_draft();
+        let leaving = self.session_store.lock().active_session_id() != Some(session_id);
+        if leaving {
+            summaries::summarize_active(&self.agent, &self.session_store);
+        }
+
+        let activate_result = {
+            let mut store = self.session_store.lock();
+            match turn_id {
+                Some(turn_id) => store.activate_turn(session_id, turn_id),
+                None => store.activate_session(session_id),
+            }
+        };
 
         match activate_result {
-            Ok(restored_state) => self.restore_active_session_state(restored_state),
+            Ok(restored_state) => {
+                self.restore_active_session_state(restored_state);
+                self.resume_pending_response();
+                self.restore_draft();
+                self.note_session_model();
+            }
+            Err(err) => {
+                self.stream_chunks
+                    .push(StreamChunk::Tool(format!("[session error: {err}]")));
+            }
+        }
+    }
+
+    /// The agent's model can only be picked at startup; say so when the
+    /// resumed session was using another one.
+    fn note_session_model(&mut self) {
+        let mismatch = self.session_store.lock().active_session_model_mismatch();
+        if let Ok(Some(model)) = mismatch {
+            let session_id = self
+                .session_store
+                .lock()
+                .active_session_id()
+                .map(str::to_owned);
+            self.stream_chunks.push(StreamChunk::Tool(format!(
+                "[this session was using {model}; restart with `agnt --resume={}` to continue on it]",
+                session_id.unwrap_or_default()
+            )));
+        }
+    }
+
+    fn open_help(&mut self) {
+        let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        self.palette = None;
+        self.help = Some(HelpOverlay {
+            sections: help::sections(TUI_KEYBINDINGS, &project_root),
+            scroll: 0,
+        });
+    }
+
+    fn handle_help_key(&mut self, key: KeyEvent) -> bool {
+        let Some(help) = self.help.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::F(1) | KeyCode::Char('?') | KeyCode::Char('q') => {
+                self.help = None;
+            }
+            KeyCode::Up => help.scroll = help.scroll.saturating_sub(1),
+            KeyCode::Down => help.scroll = help.scroll.saturating_add(1),
+            KeyCode::PageUp => help.scroll = help.scroll.saturating_sub(10),
+            KeyCode::PageDown => help.scroll = help.scroll.saturating_add(10),
+            _ => {}
+        }
+        true
+    }
+
+    fn open_palette(&mut self) {
+        if self.palette.take().is_some() {
+            return;
+        }
+        self.context_focus = None;
+        // The palette's commands act on the composer; one waiting for
+        // something other than a prompt is given up.
+        if !self.composer.is_prompt() {
+            self.set_composer(ComposerMode::Prompt);
+        }
+
+        let (active_session_id, sessions_result) = {
+            let store = self.session_store.lock();
+            (
+                store.active_session_id().map(str::to_owned),
+                store.list_sessions(20),
+            )
+        };
+        let mut sessions = match sessions_result {
+            Ok(sessions) => sessions,
             Err(err) => {
                 self.stream_chunks
                     .push(StreamChunk::Tool(format!("[session error: {err}]")));
+                Vec::new()
+            }
+        };
+        sessions.retain(|session| Some(&session.id) != active_session_id.as_ref());
+
+        let project_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let config = agnt_app::UserConfig::load().unwrap_or_default();
+        self.resume_dialog = None;
+        self.help = None;
+        self.request_view = None;
+        self.palette = Some(PaletteState::new(palette::build_entries(
+            available_commands(&project_root),
+            sessions,
+            &config,
+        )));
+    }
+
+    fn handle_palette_key(&mut self, key: KeyEvent) -> bool {
+        let Some(palette) = self.palette.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Up => palette.move_selection(-1),
+            KeyCode::Down => palette.move_selection(1),
+            KeyCode::Backspace => palette.pop(),
+            KeyCode::Char(c) => palette.push(c),
+            KeyCode::Enter => {
+                let action = palette.selected().map(|entry| entry.action.clone());
+                self.palette = None;
+                if let Some(action) = action {
+                    self.run_palette_action(action);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn run_palette_action(&mut self, action: PaletteAction) {
+        match action {
+            PaletteAction::Command(command) => self.run_command(command),
+            PaletteAction::Resume(session_id) => self.resume_session(&session_id, None),
+            PaletteAction::Toggle(toggle) => {
+                let message =
+                    palette::apply_toggle(toggle, &mut self.agent, &mut self.session_store.lock());
+                self.stream_chunks.push(StreamChunk::Tool(message));
             }
         }
     }
@@ -588,13 +2127,23 @@ impl App {
             .restore_conversation_state(restored_state.unwrap_or_else(|| ConversationState {
                 messages: Vec::new(),
             }));
-        self.messages = display_messages_from_history(&self.agent.messages());
+        (self.messages, self.session_title) =
+            restored_messages(&self.agent, &self.session_store.lock());
         self.stream_chunks.clear();
         self.input.clear();
         self.cursor_pos = 0;
         self.scroll_offset = 0;
         self.max_scroll = 0;
         self.resume_dialog = None;
+        self.pasted.clear();
+        self.composer = ComposerMode::Prompt;
+        self.navigation = None;
+        self.collapsed.clear();
+        self.diff_view = None;
+        self.comparison = None;
+        self.budget_pending = None;
+        self.large_request = None;
+        self.file_prompt = None;
         self.typeahead.sync(&self.input, self.cursor_pos);
     }
 }
@@ -633,20 +2182,54 @@ fn byte_index_for_column(line: &str, column: usize) -> usize {
         .map_or(line.len(), |(byte_idx, _)| byte_idx)
 }
 
-pub fn display_messages_from_history(messages: &[Message]) -> Vec<DisplayMessage> {
+/// Messages of the agent's restored conversation, stamped with the times
+/// their turns were stored, and the active session's title.
+///
+/// Both are only shown, so a failed lookup leaves them out rather than
+/// failing the restore.
+pub fn restored_messages(
+    agent: &Agent,
+    session_store: &SessionStore,
+) -> (Vec<DisplayMessage>, Option<String>) {
+    let turn_times = session_store.active_turn_times().unwrap_or_default();
+    let title = session_store.active_session_title().unwrap_or_default();
+    (
+        display_messages_from_history(&agent.messages(), &turn_times),
+        title,
+    )
+}
+
+/// `turn_times` holds when each stored turn was created, oldest first. The
+/// k-th user message from the end, and the replies that follow it, belong to
+/// the k-th turn from the end; compaction may have dropped earlier ones.
+pub fn display_messages_from_history(
+    messages: &[Message],
+    turn_times: &[i64],
+) -> Vec<DisplayMessage> {
     let mut out = Vec::new();
+    let user_messages = messages
+        .iter()
+        .filter(|message| matches!(message, Message::User { .. }))
+        .count();
+    let mut turn_index = turn_times.len() as isize - user_messages as isize - 1;
 
     for message in messages {
         match message {
             Message::User { parts } => {
+                turn_index += 1;
                 let mut chunks = Vec::new();
                 for part in parts {
                     match part {
                         UserPart::Text(text) => {
-                            if !text.text.is_empty() {
+                            if let Some(summary) = attachment_summary(part) {
+                                chunks.push(attachment_chunk(summary));
+                            } else if !text.text.is_empty() {
                                 chunks.push(StreamChunk::Text(text.text.clone()));
                             }
                         }
+                        // Inline images come from attachments, which are
+                        // already shown through their summary part.
+                        UserPart::Image(image) if image.url.starts_with("data:") => {}
                         UserPart::Image(image) => {
                             chunks.push(StreamChunk::Text(format!("[image: {}]", image.url)));
                         }
@@ -656,6 +2239,7 @@ pub fn display_messages_from_history(messages: &[Message]) -> Vec<DisplayMessage
                     out.push(DisplayMessage {
                         role: Role::User,
                         chunks,
+                        created_at_ms: turn_time(turn_times, turn_index),
                     });
                 }
             }
@@ -675,6 +2259,7 @@ pub fn display_messages_from_history(messages: &[Message]) -> Vec<DisplayMessage
                                 chunks.push(StreamChunk::Reasoning(text.clone()));
                             }
                         }
+                        AssistantPart::Image(image) => chunks.push(image_chunk(&image.url)),
                         AssistantPart::ToolCall(call) => {
                             if let Some(display) = &call.display {
                                 chunks.push(StreamChunk::Tool(format!("[{}...]", display.title)));
@@ -696,6 +2281,7 @@ pub fn display_messages_from_history(messages: &[Message]) -> Vec<DisplayMessage
                     out.push(DisplayMessage {
                         role: Role::Assistant,
                         chunks,
+                        created_at_ms: turn_time(turn_times, turn_index),
                     });
                 }
             }
@@ -706,6 +2292,77 @@ pub fn display_messages_from_history(messages: &[Message]) -> Vec<DisplayMessage
     out
 }
 
+fn turn_time(turn_times: &[i64], index: isize) -> Option<i64> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| turn_times.get(index).copied())
+}
+
+/// Visible indicator for context attached to a user message.
+pub fn attachment_chunk(summary: &str) -> StreamChunk {
+    StreamChunk::Tool(format!("[attached {summary}]"))
+}
+
+/// One line describing a completed turn, e.g.
+/// `4.2s (first token 0.8s) · gpt-5 · 12,345 in / 678 out · 3 tool calls
+/// (slowest: bash 2.1s)`.
+pub fn turn_footer(usage: &Usage, stats: &TurnStats) -> String {
+    let mut footer = format!("{:.1}s", stats.duration.as_secs_f64());
+    if let Some(first_token) = stats.timings.iter().find_map(|timing| match timing {
+        EventTiming::Response { first_token, .. } => Some(first_token),
+        EventTiming::Tool { .. } => None,
+    }) {
+        footer.push_str(&format!(" (first token {:.1}s)", first_token.as_secs_f64()));
+    }
+    footer.push_str(&format!(
+        " · {} · {} in",
+        stats.model,
+        group_digits(usage.input_tokens)
+    ));
+    if let Some(cached) = usage.cached_tokens.filter(|cached| *cached > 0) {
+        footer.push_str(&format!(" ({} cached)", group_digits(cached)));
+    }
+    footer.push_str(&format!(" / {} out", group_digits(usage.output_tokens)));
+    if let Some(reasoning) = usage.reasoning_tokens.filter(|reasoning| *reasoning > 0) {
+        footer.push_str(&format!(" ({} reasoning)", group_digits(reasoning)));
+    }
+    match stats.tool_calls {
+        0 => {}
+        1 => footer.push_str(" · 1 tool call"),
+        count => footer.push_str(&format!(" · {count} tool calls")),
+    }
+    let slowest_tool = stats
+        .timings
+        .iter()
+        .filter_map(|timing| match timing {
+            EventTiming::Tool { name, duration, .. } => Some((name, duration)),
+            EventTiming::Response { .. } => None,
+        })
+        .max_by_key(|(_, duration)| **duration);
+    if let Some((name, duration)) = slowest_tool {
+        let label = if stats.tool_calls > 1 {
+            "slowest: "
+        } else {
+            ""
+        };
+        footer.push_str(&format!(" ({label}{name} {:.1}s)", duration.as_secs_f64()));
+    }
+    footer
+}
+
+/// `12345` as `12,345`.
+fn group_digits(value: u32) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
 fn diff_from_display_body(body: Option<&DisplayBody>) -> Option<&str> {
     match body {
         Some(DisplayBody::Diff(diff)) if !diff.is_empty() => Some(diff.as_str()),
@@ -720,6 +2377,15 @@ fn diff_from_tool_display_body(body: Option<&ToolDisplayBodyPart>) -> Option<&st
     }
 }
 
+/// The chunk showing the image at `url`, saved to disk first if it is
+/// inline.
+pub fn image_chunk(url: &str) -> StreamChunk {
+    match images::location(url) {
+        Ok(location) => StreamChunk::Image(location),
+        Err(err) => StreamChunk::Tool(format!("[image error: {err}]")),
+    }
+}
+
 fn push_tool_diff_chunks(chunks: &mut Vec<StreamChunk>, diff: &str) {
     for line in diff.lines() {
         chunks.push(StreamChunk::Tool(line.to_string()));
@@ -0,0 +1,80 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::compare::{ComparePane, Comparison, PaneStatus};
+use crate::i18n::{Msg, t};
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+const FAILED: Style = Style::new().fg(Color::Red);
+
+/// The `/compare` answers, side by side, while shown.
+pub fn render(frame: &mut Frame, comparison: Option<&Comparison>, area: Rect) {
+    let Some(comparison) = comparison else {
+        return;
+    };
+
+    let width = area.width.saturating_sub(4).max(20).min(area.width);
+    let height = area.height.saturating_sub(2).max(3).min(area.height);
+    let popup_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default()
+        .title(format!(" {} ", t(Msg::CompareTitle)))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let [header, panes] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(1)]).areas(inner);
+    let prompt = comparison.prompt.lines().next().unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(Text::from(vec![
+            Line::from(Span::styled(t(Msg::CompareViewHint), DIM)),
+            Line::raw(format!("› {prompt}")),
+        ])),
+        header,
+    );
+
+    let [left, right] =
+        Layout::horizontal([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]).areas(panes);
+    render_pane(frame, &comparison.panes[0], comparison.scroll, left);
+    render_pane(frame, &comparison.panes[1], comparison.scroll, right);
+}
+
+fn render_pane(frame: &mut Frame, pane: &ComparePane, scroll: u16, area: Rect) {
+    let mut lines: Vec<Line> = pane.text.lines().map(Line::raw).collect();
+    match &pane.status {
+        PaneStatus::Generating => lines.push(Line::from(Span::styled("…", DIM))),
+        PaneStatus::Done(footer) => {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(footer.clone(), DIM)));
+        }
+        PaneStatus::Failed(error) => {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(
+                format!("[error: {error}]"),
+                FAILED,
+            )));
+        }
+    }
+
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0))
+            .block(
+                Block::default()
+                    .title(format!(" {} ", pane.label))
+                    .borders(Borders::TOP),
+            ),
+        area,
+    );
+}
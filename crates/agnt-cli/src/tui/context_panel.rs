@@ -0,0 +1,54 @@
+use agnt_core::ContextItem;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::Paragraph;
+
+use crate::i18n::{Msg, t};
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+const SELECTED: Style = Style::new().fg(Color::Yellow);
+/// Pinned items shown at once; the list scrolls with the selection.
+const MAX_VISIBLE_ITEMS: usize = 5;
+
+/// Rows the panel takes above the composer: none with nothing pinned.
+pub fn height(items: &[ContextItem], focused: bool) -> u16 {
+    if items.is_empty() {
+        return 0;
+    }
+    (1 + items.len().min(MAX_VISIBLE_ITEMS) + usize::from(focused)) as u16
+}
+
+/// The items pinned into context, with `selected` highlighted while the
+/// panel has the keyboard.
+pub fn render(frame: &mut Frame, items: &[ContextItem], selected: Option<usize>, area: Rect) {
+    if items.is_empty() || area.height == 0 {
+        return;
+    }
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!("── {} ({}) ", t(Msg::PinnedContext), items.len()),
+        DIM,
+    ))];
+    let start = selected
+        .unwrap_or(0)
+        .saturating_sub(MAX_VISIBLE_ITEMS - 1)
+        .min(items.len().saturating_sub(MAX_VISIBLE_ITEMS));
+    for (index, item) in items.iter().enumerate().skip(start).take(MAX_VISIBLE_ITEMS) {
+        let (marker, style) = if selected == Some(index) {
+            ("> ", SELECTED)
+        } else {
+            ("  ", DIM)
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{marker}{}", item.label()),
+            style,
+        )));
+    }
+    if selected.is_some() {
+        lines.push(Line::from(Span::styled(t(Msg::ContextPanelHint), DIM)));
+    }
+
+    frame.render_widget(Paragraph::new(Text::from(lines)), area);
+}
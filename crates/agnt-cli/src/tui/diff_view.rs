@@ -0,0 +1,53 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::i18n::{Msg, t};
+use crate::tui::ui::diff_lines;
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+
+/// A diff opened from navigation mode, while shown.
+pub struct DiffView {
+    /// The tool call the diff came from, e.g. `Edited src/main.rs`.
+    pub title: String,
+    pub diff: String,
+    pub scroll: u16,
+}
+
+pub fn render(frame: &mut Frame, view: Option<&DiffView>, area: Rect) {
+    let Some(view) = view else {
+        return;
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(t(Msg::HelpScrollHint), DIM)),
+        Line::default(),
+    ];
+    lines.extend(diff_lines(&view.diff));
+
+    let width = area.width.saturating_sub(4).max(20).min(area.width);
+    let height = area.height.saturating_sub(2).max(3).min(area.height);
+    let popup_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .scroll((view.scroll, 0))
+            .block(
+                Block::default()
+                    .title(format!(" {} ", view.title))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            ),
+        popup_area,
+    );
+}
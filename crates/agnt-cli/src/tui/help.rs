@@ -0,0 +1,65 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::help::HelpSection;
+use crate::i18n::{Msg, t};
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+const HEADER: Style = Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+const KEYS: Style = Style::new().fg(Color::Cyan);
+
+pub struct HelpOverlay {
+    pub sections: Vec<HelpSection>,
+    pub scroll: u16,
+}
+
+pub fn render(frame: &mut Frame, help: Option<&HelpOverlay>, area: Rect) {
+    let Some(help) = help else {
+        return;
+    };
+
+    let key_width = help
+        .sections
+        .iter()
+        .flat_map(|section| &section.rows)
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![Line::from(Span::styled(t(Msg::HelpScrollHint), DIM))];
+    for section in &help.sections {
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(section.title, HEADER)));
+        for (keys, action) in &section.rows {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {keys:<key_width$}  "), KEYS),
+                Span::raw(action.clone()),
+            ]));
+        }
+    }
+
+    let width = area.width.saturating_sub(8).clamp(20, 100).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2).max(3));
+    let popup_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height.min(area.height),
+    );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .scroll((help.scroll, 0))
+            .block(
+                Block::default()
+                    .title(format!(" {} ", t(Msg::Help)))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            ),
+        popup_area,
+    );
+}
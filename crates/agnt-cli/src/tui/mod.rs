@@ -1,4 +1,11 @@
 pub mod app;
+pub mod compare_view;
+pub mod context_panel;
+pub mod diff_view;
+pub mod help;
+pub mod navigation;
+pub mod palette;
+pub mod request_view;
 pub mod session_dialog;
 pub mod ui;
 
@@ -7,9 +14,8 @@ use std::time::Duration;
 
 use crossterm::event::{
     DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event,
-    EventStream, KeyEventKind,
-    KeyboardEnhancementFlags, MouseEventKind, PopKeyboardEnhancementFlags,
-    PushKeyboardEnhancementFlags,
+    EventStream, KeyEventKind, KeyboardEnhancementFlags, MouseEventKind,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
 };
 use crossterm::execute;
 use crossterm::terminal::{
@@ -145,6 +151,7 @@ async fn run_loop(
         terminal.draw(|frame| ui::render(frame, app))?;
 
         if app.should_quit {
+            app.save_draft();
             break;
         }
 
@@ -162,6 +169,28 @@ async fn run_loop(
                 app.handle_agent_event(agent_event);
             }
 
+            Some(pending) = app.write_approvals.recv(), if app.pending_write.is_none() => {
+                app.pending_write = Some(pending);
+            }
+
+            Some((pane, event)) = async {
+                match &mut app.comparison {
+                    Some(comparison) if comparison.is_generating() => comparison.next_event().await,
+                    _ => std::future::pending().await,
+                }
+            } => {
+                app.handle_comparison_event(pane, event);
+            }
+
+            Some(update) = async {
+                match &mut app.spec_refresh {
+                    Some(refresh) => refresh.next().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                app.handle_spec_update(update);
+            }
+
             _ = blink_interval.tick() => {
                 if matches!(app.state, AppState::Generating { .. }) {
                     app.toggle_cursor_blink();
@@ -0,0 +1,202 @@
+//! Navigation mode: a selection that moves over the transcript's messages
+//! and tool blocks, so one of them can be copied, bookmarked, collapsed or
+//! have its diff opened. Esc with nothing else to cancel enters it.
+
+use crate::tui::app::{DisplayMessage, Role, StreamChunk};
+
+/// A selectable part of the transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Block {
+    pub message: usize,
+    /// Range of one tool call's chunks within the message; `None` for the
+    /// whole message.
+    pub tool: Option<(usize, usize)>,
+}
+
+/// The selection while navigation mode is on.
+pub struct Navigation {
+    /// Index into [`blocks`].
+    pub selected: usize,
+    /// Set when the selection moves, so the next frame scrolls it into
+    /// view.
+    pub reveal: bool,
+}
+
+/// Every block in transcript order: each message, followed by its tool
+/// calls.
+pub fn blocks(messages: &[DisplayMessage]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        blocks.push(Block {
+            message: index,
+            tool: None,
+        });
+        if matches!(message.role, Role::Assistant) {
+            blocks.extend(tool_ranges(&message.chunks).map(|range| Block {
+                message: index,
+                tool: Some(range),
+            }));
+        }
+    }
+    blocks
+}
+
+/// Chunk ranges of the tool calls in `chunks`: a call's status line and
+/// everything up to the next call or non-tool chunk.
+fn tool_ranges(chunks: &[StreamChunk]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let starts: Vec<usize> = chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, chunk)| {
+            matches!(chunk, StreamChunk::Tool(line) if is_call_line(line)).then_some(index)
+        })
+        .collect();
+    (0..starts.len()).map(move |i| {
+        let start = starts[i];
+        let limit = starts.get(i + 1).copied().unwrap_or(chunks.len());
+        let end = (start + 1..limit)
+            .find(|&index| !matches!(chunks[index], StreamChunk::Tool(_)))
+            .unwrap_or(limit);
+        (start, end)
+    })
+}
+
+/// `[Read src/main.rs...]`, the line a tool call starts with.
+fn is_call_line(line: &str) -> bool {
+    line.starts_with('[') && line.ends_with("...]")
+}
+
+/// `[Read src/main.rs]` and other bracketed status lines, as opposed to
+/// the diff lines a tool call may show.
+fn is_status_line(line: &str) -> bool {
+    line.starts_with('[') && line.ends_with(']')
+}
+
+/// The chunks `block` covers.
+pub fn chunks(messages: &[DisplayMessage], block: Block) -> &[StreamChunk] {
+    let Some(message) = messages.get(block.message) else {
+        return &[];
+    };
+    match block.tool {
+        Some((start, end)) => message.chunks.get(start..end).unwrap_or(&[]),
+        None => &message.chunks,
+    }
+}
+
+/// What a tool block's diff came from, e.g. `Edited src/main.rs`.
+pub fn title(messages: &[DisplayMessage], block: Block) -> Option<String> {
+    block.tool?;
+    chunks(messages, block)
+        .iter()
+        .filter_map(|chunk| match chunk {
+            StreamChunk::Tool(line) if is_status_line(line) && !is_call_line(line) => Some(
+                line.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+            ),
+            _ => None,
+        })
+        .next()
+}
+
+/// The text of `block`, as copied to the clipboard.
+pub fn text(messages: &[DisplayMessage], block: Block) -> String {
+    chunks(messages, block)
+        .iter()
+        .map(|chunk| match chunk {
+            StreamChunk::Reasoning(text)
+            | StreamChunk::Text(text)
+            | StreamChunk::Tool(text)
+            | StreamChunk::HostedTool(text)
+            | StreamChunk::Footer(text)
+            | StreamChunk::Image(text) => text.trim_end_matches('\n'),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The diffs shown in `block`, if any.
+pub fn diff(messages: &[DisplayMessage], block: Block) -> Option<String> {
+    let lines: Vec<&str> = chunks(messages, block)
+        .iter()
+        .filter_map(|chunk| match chunk {
+            StreamChunk::Tool(line) if !is_status_line(line) => Some(line.as_str()),
+            _ => None,
+        })
+        .collect();
+    lines
+        .iter()
+        .any(|line| line.starts_with("@@"))
+        .then(|| lines.join("\n"))
+}
+
+/// The last image shown in `block`, if any.
+pub fn image(messages: &[DisplayMessage], block: Block) -> Option<&str> {
+    chunks(messages, block)
+        .iter()
+        .rev()
+        .find_map(|chunk| match chunk {
+            StreamChunk::Image(location) => Some(location.as_str()),
+            _ => None,
+        })
+}
+
+/// How many turns before the latest one `block` belongs to: one for each
+/// prompt sent after it.
+pub fn turns_back(messages: &[DisplayMessage], block: Block) -> usize {
+    messages
+        .iter()
+        .skip(block.message + 1)
+        .filter(|message| matches!(message.role, Role::User))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, chunks: &[StreamChunk]) -> DisplayMessage {
+        DisplayMessage {
+            role,
+            chunks: chunks.to_vec(),
+            created_at_ms: None,
+        }
+    }
+
+    fn tool(line: &str) -> StreamChunk {
+        StreamChunk::Tool(line.to_string())
+    }
+
+    #[test]
+    fn splits_tool_calls_into_blocks() {
+        let messages = vec![
+            message(Role::User, &[StreamChunk::Text("fix it".to_string())]),
+            message(
+                Role::Assistant,
+                &[
+                    StreamChunk::Text("Looking.".to_string()),
+                    tool("[Read src/lib.rs...]"),
+                    tool("[Read src/lib.rs]"),
+                    tool("[Edit src/lib.rs...]"),
+                    tool("[Edited src/lib.rs]"),
+                    tool("@@ -1 +1 @@"),
+                    tool("-old"),
+                    tool("+new"),
+                    StreamChunk::Text("Done.".to_string()),
+                ],
+            ),
+        ];
+        let blocks = blocks(&messages);
+        let tools: Vec<_> = blocks.iter().filter_map(|block| block.tool).collect();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(tools, vec![(1, 3), (3, 8)]);
+
+        let edit = blocks[3];
+        assert_eq!(
+            diff(&messages, edit).as_deref(),
+            Some("@@ -1 +1 @@\n-old\n+new")
+        );
+        assert_eq!(diff(&messages, blocks[2]), None);
+        assert_eq!(turns_back(&messages, blocks[0]), 0);
+    }
+}
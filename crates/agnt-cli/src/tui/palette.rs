@@ -0,0 +1,72 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::i18n::{Msg, t};
+use crate::palette::PaletteState;
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+const ACTIVE: Style = Style::new().fg(Color::Yellow);
+
+pub fn render(frame: &mut Frame, palette: Option<&PaletteState>, area: Rect) {
+    let Some(palette) = palette else {
+        return;
+    };
+
+    let max_visible_rows = 10usize;
+    let header_rows = 2;
+    let dialog_width = area.width.saturating_sub(8).clamp(20, 90);
+    let dialog_height =
+        (palette.match_count().clamp(1, max_visible_rows) as u16 + 2 + header_rows).clamp(5, 14);
+    let popup_area = Rect::new(
+        area.x + (area.width.saturating_sub(dialog_width.min(area.width))) / 2,
+        area.y + area.height.saturating_sub(dialog_height.min(area.height)) / 4,
+        dialog_width.min(area.width),
+        dialog_height.min(area.height),
+    );
+
+    let visible_rows = popup_area.height.saturating_sub(2 + header_rows) as usize;
+    let start = if palette.selected_index >= visible_rows && visible_rows > 0 {
+        palette.selected_index + 1 - visible_rows
+    } else {
+        0
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("› ", DIM),
+            Span::styled(format!("{}█", palette.query), ACTIVE),
+        ]),
+        Line::from(Span::styled(t(Msg::PaletteHint), DIM)),
+    ];
+    if palette.match_count() == 0 {
+        lines.push(Line::from(Span::styled(
+            format!("  {}", t(Msg::NoMatches)),
+            DIM,
+        )));
+    }
+    for (idx, entry) in palette.matches().enumerate().skip(start).take(visible_rows) {
+        let (marker, style) = if idx == palette.selected_index {
+            ("› ", ACTIVE)
+        } else {
+            ("  ", Style::default())
+        };
+        lines.push(Line::from(vec![
+            Span::styled(marker, DIM),
+            Span::styled(entry.title.clone(), style),
+        ]));
+    }
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(format!(" {} ", t(Msg::PaletteTitle)))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        ),
+        popup_area,
+    );
+}
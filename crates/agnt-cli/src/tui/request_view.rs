@@ -0,0 +1,56 @@
+use agnt_core::RequestInspection;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use crate::i18n::{Msg, t};
+
+const DIM: Style = Style::new().fg(Color::DarkGray);
+
+/// The request inspection pane, while open (`/debug:request`).
+pub struct RequestView {
+    pub inspection: RequestInspection,
+    pub scroll: u16,
+}
+
+pub fn render(frame: &mut Frame, view: Option<&RequestView>, area: Rect) {
+    let Some(view) = view else {
+        return;
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(t(Msg::HelpScrollHint), DIM)),
+        Line::default(),
+    ];
+    lines.extend(view.inspection.body.lines().map(Line::raw));
+
+    let width = area.width.saturating_sub(4).max(20).min(area.width);
+    let height = area.height.saturating_sub(2).max(3).min(area.height);
+    let popup_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+    let title = if view.inspection.sent {
+        t(Msg::LastRequest)
+    } else {
+        t(Msg::NextRequest)
+    };
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .scroll((view.scroll, 0))
+            .block(
+                Block::default()
+                    .title(format!(" {title} "))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            ),
+        popup_area,
+    );
+}
@@ -1,32 +1,142 @@
-use agnt_db::Session;
+use agnt_db::{Session, TurnBookmark, TurnSearchHit};
 use ratatui::Frame;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
-use crate::session::session_label;
+use crate::i18n::{Msg, t};
+use crate::session::{bookmark_label, search_hit_label, session_label, summary_preview};
 
 const DIM: Style = Style::new().fg(Color::DarkGray);
 const ACTIVE: Style = Style::new().fg(Color::Yellow);
+/// Rows below the list for the selected session's summary.
+const SUMMARY_ROWS: u16 = 4;
 
 #[derive(Debug, Clone)]
 pub struct ResumeSessionDialogEntry {
     pub session_id: String,
+    /// Turn to check out when resuming; `None` keeps the session's current turn.
+    pub turn_id: Option<String>,
     pub label: String,
+    pub session_title: Option<String>,
+    /// Stored summary of the session, shown while the entry is selected.
+    pub summary: Option<String>,
+    pub pinned: bool,
+}
+
+/// What keys in the dialog do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogMode {
+    Browse,
+    /// Waiting for Enter to delete the selected session.
+    ConfirmDelete,
+    /// Collecting a new title for the selected session.
+    Rename(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct ResumeSessionDialogState {
+    pub title: &'static str,
     pub entries: Vec<ResumeSessionDialogEntry>,
     pub selected_index: usize,
+    pub mode: DialogMode,
+    /// Whether entries can be deleted, renamed and pinned. Only the session list
+    /// allows it; search results point at turns.
+    pub manages_sessions: bool,
+    /// Whether the entries are bookmarks, which `d` removes.
+    pub lists_bookmarks: bool,
+    /// Whether the entries are search hits. Enter opens the session as it is;
+    /// `b` branches from the matching turn.
+    pub lists_search_hits: bool,
 }
 
 impl ResumeSessionDialogState {
     pub fn new(entries: Vec<ResumeSessionDialogEntry>) -> Self {
         Self {
+            title: t(Msg::ResumeSessionTitle),
+            entries,
+            selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: true,
+            lists_bookmarks: false,
+            lists_search_hits: false,
+        }
+    }
+
+    pub fn search_results(entries: Vec<ResumeSessionDialogEntry>) -> Self {
+        Self {
+            title: t(Msg::HistoryTitle),
+            entries,
+            selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: false,
+            lists_bookmarks: false,
+            lists_search_hits: true,
+        }
+    }
+
+    pub fn bookmarks(entries: Vec<ResumeSessionDialogEntry>) -> Self {
+        Self {
+            title: t(Msg::BookmarksTitle),
             entries,
             selected_index: 0,
+            mode: DialogMode::Browse,
+            manages_sessions: false,
+            lists_bookmarks: true,
+            lists_search_hits: false,
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self.mode {
+            DialogMode::Browse if self.manages_sessions => t(Msg::ResumeManageHint),
+            DialogMode::Browse if self.lists_bookmarks => t(Msg::BookmarksHint),
+            DialogMode::Browse if self.lists_search_hits => t(Msg::SearchHitsHint),
+            DialogMode::Browse => t(Msg::ResumeHint),
+            DialogMode::ConfirmDelete => t(Msg::ConfirmDeleteHint),
+            DialogMode::Rename(_) => t(Msg::RenameHintTui),
+        }
+    }
+
+    /// Drop the selected entry, e.g. a removed bookmark.
+    pub fn remove_selected(&mut self) {
+        if self.selected_index < self.entries.len() {
+            self.entries.remove(self.selected_index);
+        }
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Drop a deleted session's entries.
+    pub fn remove_session(&mut self, session_id: &str) {
+        self.entries.retain(|entry| entry.session_id != session_id);
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Show a renamed or (un)pinned session as it now is, keeping pinned
+    /// sessions first.
+    pub fn update_session(&mut self, session: &Session) {
+        for entry in &mut self.entries {
+            if entry.session_id == session.id {
+                entry.label = session_label(session);
+                entry.session_title = session.title.clone();
+                entry.pinned = session.pinned;
+            }
+        }
+        if self.manages_sessions {
+            let selected = self.entries.get(self.selected_index).cloned();
+            self.entries.sort_by_key(|entry| !entry.pinned);
+            if let Some(selected) = selected {
+                self.selected_index = self
+                    .entries
+                    .iter()
+                    .position(|entry| entry.session_id == selected.session_id)
+                    .unwrap_or(0);
+            }
         }
     }
 }
@@ -36,7 +146,40 @@ pub fn build_dialog_entries(sessions: Vec<Session>) -> Vec<ResumeSessionDialogEn
         .into_iter()
         .map(|session| ResumeSessionDialogEntry {
             session_id: session.id.clone(),
+            turn_id: None,
             label: session_label(&session),
+            summary: summary_preview(&session),
+            pinned: session.pinned,
+            session_title: session.title,
+        })
+        .collect()
+}
+
+pub fn build_search_entries(hits: Vec<TurnSearchHit>) -> Vec<ResumeSessionDialogEntry> {
+    hits.into_iter()
+        .map(|hit| ResumeSessionDialogEntry {
+            label: search_hit_label(&hit),
+            session_id: hit.session_id,
+            turn_id: Some(hit.turn_id),
+            session_title: hit.session_title,
+            summary: None,
+            pinned: false,
+        })
+        .collect()
+}
+
+pub fn build_bookmark_entries(
+    bookmarks: Vec<(TurnBookmark, String)>,
+) -> Vec<ResumeSessionDialogEntry> {
+    bookmarks
+        .into_iter()
+        .map(|(bookmark, prompt)| ResumeSessionDialogEntry {
+            label: bookmark_label(&bookmark, &prompt),
+            session_id: bookmark.session_id,
+            turn_id: Some(bookmark.turn_id),
+            session_title: bookmark.session_title,
+            summary: bookmark.note,
+            pinned: false,
         })
         .collect()
 }
@@ -57,11 +200,8 @@ pub fn move_selection(dialog: &mut ResumeSessionDialogState, direction: i32) {
     }
 }
 
-pub fn selected_session_id(dialog: &ResumeSessionDialogState) -> Option<&str> {
-    dialog
-        .entries
-        .get(dialog.selected_index)
-        .map(|entry| entry.session_id.as_str())
+pub fn selected_entry(dialog: &ResumeSessionDialogState) -> Option<&ResumeSessionDialogEntry> {
+    dialog.entries.get(dialog.selected_index)
 }
 
 pub fn render(frame: &mut Frame, dialog: Option<&ResumeSessionDialogState>, area: Rect) {
@@ -73,11 +213,27 @@ pub fn render(frame: &mut Frame, dialog: Option<&ResumeSessionDialogState>, area
     }
 
     let max_visible_rows = 8usize;
+    let header_rows = if matches!(dialog.mode, DialogMode::Rename(_)) {
+        2
+    } else {
+        1
+    };
+    let summary = selected_entry(dialog).and_then(|entry| entry.summary.as_deref());
+    // The summary sits under a blank row.
+    let summary_rows = if summary.is_some() {
+        SUMMARY_ROWS + 1
+    } else {
+        0
+    };
     let dialog_width = area.width.saturating_sub(8).clamp(20, 90);
-    let dialog_height = (dialog.entries.len().min(max_visible_rows) as u16 + 4).clamp(6, 16);
+    let dialog_height = (dialog.entries.len().min(max_visible_rows) as u16 + 3 + header_rows)
+        .clamp(6, 17)
+        + summary_rows;
     let popup_area = centered_rect(dialog_width, dialog_height, area);
 
-    let visible_rows = popup_area.height.saturating_sub(4) as usize;
+    let visible_rows = popup_area
+        .height
+        .saturating_sub(3 + header_rows + summary_rows) as usize;
     let start = if dialog.selected_index >= visible_rows && visible_rows > 0 {
         dialog.selected_index + 1 - visible_rows
     } else {
@@ -85,10 +241,13 @@ pub fn render(frame: &mut Frame, dialog: Option<&ResumeSessionDialogState>, area
     };
     let end = (start + visible_rows).min(dialog.entries.len());
 
-    let mut lines = vec![Line::from(Span::styled(
-        "Enter to resume, Esc to cancel",
-        DIM,
-    ))];
+    let mut lines = vec![Line::from(Span::styled(dialog.hint(), DIM))];
+    if let DialogMode::Rename(title) = &dialog.mode {
+        lines.push(Line::from(vec![
+            Span::styled("› ", DIM),
+            Span::styled(format!("{title}█"), ACTIVE),
+        ]));
+    }
     for (idx, entry) in dialog.entries[start..end].iter().enumerate() {
         let absolute_index = start + idx;
         let marker = if absolute_index == dialog.selected_index {
@@ -107,16 +266,32 @@ pub fn render(frame: &mut Frame, dialog: Option<&ResumeSessionDialogState>, area
         ]));
     }
 
+    let block = Block::default()
+        .title(format!(" {} ", dialog.title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup_area);
+    let list_area = Rect {
+        height: inner.height.saturating_sub(summary_rows),
+        ..inner
+    };
+
     frame.render_widget(Clear, popup_area);
-    frame.render_widget(
-        Paragraph::new(Text::from(lines)).block(
-            Block::default()
-                .title(" Resume Session ")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Yellow)),
-        ),
-        popup_area,
-    );
+    frame.render_widget(block, popup_area);
+    frame.render_widget(Paragraph::new(Text::from(lines)), list_area);
+    if let Some(summary) = summary {
+        let summary_area = Rect {
+            y: list_area.bottom() + 1,
+            height: inner.height.saturating_sub(list_area.height + 1),
+            ..inner
+        };
+        frame.render_widget(
+            Paragraph::new(summary)
+                .style(DIM)
+                .wrap(Wrap { trim: false }),
+            summary_area,
+        );
+    }
 }
 
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
@@ -1,13 +1,22 @@
+use agnt_core::Attachment;
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Layout};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Paragraph, Wrap};
 
+use std::ops::Range;
 use std::sync::OnceLock;
 
+use crate::budget::BudgetStatus;
+use crate::i18n::{Msg, t};
+use crate::images;
+use crate::session::{relative_time, truncate_with_ellipsis};
 use crate::tui::app::{App, AppState, Role, StreamChunk};
-use crate::tui::session_dialog;
+use crate::tui::navigation::{self, Block};
+use crate::tui::{
+    compare_view, context_panel, diff_view, help, palette, request_view, session_dialog,
+};
 use crate::typeahead::{
     ActiveTypeahead, TypeaheadItem, TypeaheadMatchSet, TypeaheadWindowItem,
     build_typeahead_window_items,
@@ -19,11 +28,17 @@ const REASONING_STYLE: Style = Style::new()
     .fg(Color::DarkGray)
     .add_modifier(Modifier::ITALIC);
 const DIM: Style = Style::new().fg(Color::DarkGray);
+const HOSTED_TOOL_STYLE: Style = Style::new().fg(Color::Blue);
+const LINK_STYLE: Style = Style::new()
+    .fg(Color::Cyan)
+    .add_modifier(Modifier::UNDERLINED);
 const TYPEAHEAD_HEADER: Style = Style::new().fg(Color::Yellow);
 const TYPEAHEAD_ACTIVE: Style = Style::new().fg(Color::Yellow);
 const DIFF_META_STYLE: Style = Style::new().fg(Color::DarkGray);
 const DIFF_HEADER_STYLE: Style = Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD);
 const DIFF_HUNK_STYLE: Style = Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+/// Characters of a collapsed message shown in its place.
+const COLLAPSED_PREVIEW_CHARS: usize = 80;
 
 #[derive(Clone, Copy, Debug)]
 enum TerminalTheme {
@@ -65,6 +80,14 @@ fn diff_removed_style() -> Style {
             .bg(Color::Rgb(246, 224, 224)),
     }
 }
+/// Background of the block selected in navigation mode.
+fn selection_style() -> Style {
+    match terminal_theme() {
+        TerminalTheme::Dark => Style::new().bg(Color::Rgb(40, 44, 56)),
+        TerminalTheme::Light => Style::new().bg(Color::Rgb(228, 232, 242)),
+    }
+}
+
 const DIFF_FILE_ADDED_STYLE: Style = Style::new().fg(Color::Green).add_modifier(Modifier::BOLD);
 const DIFF_FILE_REMOVED_STYLE: Style = Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
 const DIFF_CONTEXT_STYLE: Style = Style::new().fg(Color::Gray);
@@ -77,25 +100,36 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     let window_start = app.typeahead_window_start();
     let typeahead_height = calculate_typeahead_height(typeahead.as_ref());
     let input_height = calculate_input_height(app, area.width);
+    let pinned = app.agent.pinned_context();
+    let context_height = context_panel::height(&pinned, app.context_focus.is_some());
     let chunks = Layout::vertical([
+        Constraint::Length(1), // header
         Constraint::Min(1),
+        Constraint::Length(context_height),
         Constraint::Length(1), // separator
         Constraint::Length(typeahead_height),
         Constraint::Length(input_height),
     ])
     .split(area);
 
-    render_messages(frame, app, chunks[0]);
-    render_separator(frame, chunks[1]);
+    render_header(frame, app, chunks[0]);
+    render_messages(frame, app, chunks[1]);
+    context_panel::render(frame, &pinned, app.context_focus, chunks[2]);
+    render_separator(frame, separator_label(app), chunks[3]);
     render_typeahead(
         frame,
         typeahead.as_ref(),
         selected_index,
         window_start,
-        chunks[2],
+        chunks[4],
     );
-    render_input(frame, app, chunks[3]);
+    render_input(frame, app, chunks[5]);
     session_dialog::render(frame, app.resume_dialog.as_ref(), area);
+    palette::render(frame, app.palette.as_ref(), area);
+    help::render(frame, app.help.as_ref(), area);
+    request_view::render(frame, app.request_view.as_ref(), area);
+    compare_view::render(frame, app.comparison.as_ref(), area);
+    diff_view::render(frame, app.diff_view.as_ref(), area);
 }
 
 /// Manually wrap a styled line to fit within `width` columns.
@@ -171,9 +205,11 @@ fn is_empty_line(line: &Line) -> bool {
     line.spans.iter().all(|span| span.content.is_empty())
 }
 
-/// Append styled lines for a slice of [`StreamChunk`]s.
-fn render_chunks(chunks: &[StreamChunk], lines: &mut Vec<Line<'static>>) {
+/// Append styled lines for a slice of [`StreamChunk`]s. Returns the range
+/// of lines each chunk was rendered to.
+fn render_chunks(chunks: &[StreamChunk], lines: &mut Vec<Line<'static>>) -> Vec<Range<usize>> {
     let mut diff_state = DiffRenderState::default();
+    let mut ranges = Vec::with_capacity(chunks.len());
 
     for (i, chunk) in chunks.iter().enumerate() {
         // Blank line between chunks, except consecutive Tool chunks
@@ -186,6 +222,7 @@ fn render_chunks(chunks: &[StreamChunk], lines: &mut Vec<Line<'static>>) {
             }
         }
 
+        let start = lines.len();
         match chunk {
             StreamChunk::Reasoning(s) => {
                 diff_state.reset();
@@ -220,8 +257,33 @@ fn render_chunks(chunks: &[StreamChunk], lines: &mut Vec<Line<'static>>) {
                     }
                 }
             }
+            StreamChunk::HostedTool(s) => {
+                diff_state.reset();
+                lines.push(Line::from(Span::styled(s.clone(), HOSTED_TOOL_STYLE)));
+            }
+            StreamChunk::Footer(s) => {
+                diff_state.reset();
+                lines.push(Line::from(Span::styled(s.clone(), DIM)));
+            }
+            StreamChunk::Image(location) => {
+                diff_state.reset();
+                lines.push(Line::from(vec![
+                    Span::styled("[image] ", HOSTED_TOOL_STYLE),
+                    Span::styled(images::open_url(location), LINK_STYLE),
+                ]));
+            }
         }
+        ranges.push(start..lines.len());
     }
+    ranges
+}
+
+/// Styled lines for a unified diff, as tool output shows them.
+pub(super) fn diff_lines(diff: &str) -> Vec<Line<'static>> {
+    let mut state = DiffRenderState::default();
+    diff.lines()
+        .flat_map(|line| render_tool_lines(line, &mut state))
+        .collect()
 }
 
 #[derive(Default)]
@@ -459,25 +521,96 @@ fn render_tool_lines(line: &str, state: &mut DiffRenderState) -> Vec<Line<'stati
 }
 
 /// Build the logical lines for the messages area, then wrap them.
-fn build_message_lines(app: &App, width: usize) -> Vec<Line<'static>> {
+/// Also returns the lines of the block selected in navigation mode.
+fn build_message_lines(app: &App, width: usize) -> (Vec<Line<'static>>, Option<Range<usize>>) {
     let mut logical_lines: Vec<Line> = Vec::new();
+    let selected = app
+        .navigation
+        .as_ref()
+        .and_then(|nav| navigation::blocks(&app.messages).get(nav.selected).copied());
+    let mut selected_lines = None;
 
-    for msg in &app.messages {
+    for (index, msg) in app.messages.iter().enumerate() {
         if !logical_lines.is_empty() {
             logical_lines.push(Line::raw(""));
         }
+        let message_start = logical_lines.len();
 
         let (label, color) = match msg.role {
-            Role::User => ("You", USER_COLOR),
-            Role::Assistant => ("Assistant", ASSISTANT_COLOR),
+            Role::User => (t(Msg::You), USER_COLOR),
+            Role::Assistant => (t(Msg::Assistant), ASSISTANT_COLOR),
         };
 
-        logical_lines.push(Line::from(Span::styled(
+        let mut header = vec![Span::styled(
             label.to_string(),
             Style::default().fg(color).add_modifier(Modifier::BOLD),
-        )));
+        )];
+        if let Some(created_at_ms) = msg.created_at_ms {
+            header.push(Span::styled(
+                format!(" · {}", relative_time(created_at_ms)),
+                DIM,
+            ));
+        }
+        logical_lines.push(Line::from(header));
 
-        render_chunks(&msg.chunks, &mut logical_lines);
+        let whole = Block {
+            message: index,
+            tool: None,
+        };
+        if app.collapsed.contains(&whole) {
+            let text = navigation::text(&app.messages, whole);
+            let first = text.lines().find(|line| !line.trim().is_empty());
+            logical_lines.push(Line::from(Span::styled(
+                format!(
+                    "{} (+{} lines)",
+                    truncate_with_ellipsis(first.unwrap_or_default(), COLLAPSED_PREVIEW_CHARS),
+                    text.lines().count().saturating_sub(1)
+                ),
+                DIM,
+            )));
+        } else {
+            // Collapsed tool calls are shown as their last status line.
+            let mut shown = Vec::new();
+            let mut origins = Vec::new();
+            let mut folded_until = 0;
+            for (chunk_index, chunk) in msg.chunks.iter().enumerate() {
+                if chunk_index < folded_until {
+                    continue;
+                }
+                let tool = app.collapsed.iter().find_map(|block| {
+                    block
+                        .tool
+                        .filter(|(start, _)| block.message == index && *start == chunk_index)
+                });
+                match tool {
+                    Some((start, end)) => {
+                        folded_until = end;
+                        shown.push(StreamChunk::Tool(collapsed_tool_line(
+                            &msg.chunks[start..end],
+                        )));
+                    }
+                    None => shown.push(chunk.clone()),
+                }
+                origins.push(chunk_index);
+            }
+            let ranges = render_chunks(&shown, &mut logical_lines);
+            if let Some(Block {
+                message,
+                tool: Some((start, end)),
+            }) = selected
+                && message == index
+            {
+                let first = origins.iter().position(|origin| *origin == start);
+                let last = origins.iter().rposition(|origin| *origin < end);
+                if let (Some(first), Some(last)) = (first, last) {
+                    selected_lines = Some(ranges[first].start..ranges[last].end);
+                }
+            }
+        }
+
+        if selected == Some(whole) {
+            selected_lines = Some(message_start..logical_lines.len());
+        }
     }
 
     // Streaming / typing indicator
@@ -487,7 +620,7 @@ fn build_message_lines(app: &App, width: usize) -> Vec<Line<'static>> {
             logical_lines.push(Line::raw(""));
         }
         logical_lines.push(Line::from(Span::styled(
-            "Assistant".to_string(),
+            t(Msg::Assistant).to_string(),
             Style::default()
                 .fg(ASSISTANT_COLOR)
                 .add_modifier(Modifier::BOLD),
@@ -510,7 +643,12 @@ fn build_message_lines(app: &App, width: usize) -> Vec<Line<'static>> {
                 // Check if the last chunk ended with a newline or is a Tool
                 // line — if so the cursor belongs on a fresh line.
                 let needs_new_line = match app.stream_chunks.last() {
-                    Some(StreamChunk::Tool(_)) => true,
+                    Some(
+                        StreamChunk::Tool(_)
+                        | StreamChunk::HostedTool(_)
+                        | StreamChunk::Footer(_)
+                        | StreamChunk::Image(_),
+                    ) => true,
                     Some(StreamChunk::Text(s) | StreamChunk::Reasoning(s)) => s.ends_with('\n'),
                     None => false,
                 };
@@ -530,22 +668,67 @@ fn build_message_lines(app: &App, width: usize) -> Vec<Line<'static>> {
 
     if logical_lines.is_empty() {
         logical_lines.push(Line::from(Span::styled(
-            "Type a message and press Enter to start.".to_string(),
+            t(Msg::EmptyThreadTui).to_string(),
             DIM,
         )));
     }
 
     // Pre-wrap all lines so rendered height == lines.len()
-    logical_lines
+    let mut lines = Vec::with_capacity(logical_lines.len());
+    let mut wrapped_selection = None;
+    for (index, line) in logical_lines.iter().enumerate() {
+        let start = lines.len();
+        lines.extend(wrap_line(line, width));
+        if let Some(selected) = &selected_lines
+            && selected.contains(&index)
+        {
+            for line in &mut lines[start..] {
+                line.style = line.style.patch(selection_style());
+            }
+            let range: &mut Range<usize> = wrapped_selection.get_or_insert(start..start);
+            range.end = lines.len();
+        }
+    }
+    (lines, wrapped_selection)
+}
+
+/// A collapsed tool call: its result, or its call if it has none, and how
+/// many lines are hidden.
+fn collapsed_tool_line(chunks: &[StreamChunk]) -> String {
+    let status = chunks
         .iter()
-        .flat_map(|line| wrap_line(line, width))
-        .collect()
+        .take(2)
+        .filter_map(|chunk| match chunk {
+            StreamChunk::Tool(line) if line.starts_with('[') => Some(line.as_str()),
+            _ => None,
+        })
+        .next_back()
+        .unwrap_or_default();
+    let hidden = chunks.len().saturating_sub(1);
+    if hidden == 0 {
+        status.to_string()
+    } else {
+        format!("{status} (+{hidden} lines)")
+    }
+}
+
+fn render_header(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let title = match &app.session_title {
+        Some(title) => title.as_str(),
+        None if app.session_store.lock().active_session_id().is_some() => t(Msg::UntitledSession),
+        None => "New session",
+    };
+    let line = Line::from(Span::styled(
+        title.to_string(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    frame.render_widget(Paragraph::new(line), area);
 }
 
 fn render_messages(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
     let width = area.width as usize;
     let visible = area.height as usize;
-    let mut lines = build_message_lines(app, width);
+    let (mut lines, mut selected) = build_message_lines(app, width);
     let content_height = lines.len();
 
     // Anchor to bottom: pad top if content is shorter than viewport.
@@ -554,6 +737,7 @@ fn render_messages(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         let mut padded = vec![Line::raw(""); padding];
         padded.append(&mut lines);
         lines = padded;
+        selected = selected.map(|range| range.start + padding..range.end + padding);
     }
 
     // Now lines.len() >= visible. Scroll math is exact since we pre-wrapped.
@@ -568,6 +752,22 @@ fn render_messages(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
     }
     app.scroll_offset = app.scroll_offset.min(max_scroll as u16);
 
+    // Scroll a newly selected block into view, its top first.
+    if let Some(nav) = app.navigation.as_mut()
+        && std::mem::take(&mut nav.reveal)
+        && let Some(selected) = selected
+    {
+        let top = max_scroll - app.scroll_offset as usize;
+        let top = if selected.start < top {
+            selected.start
+        } else if selected.end > top + visible {
+            (selected.end - visible).min(selected.start)
+        } else {
+            top
+        };
+        app.scroll_offset = (max_scroll - top.min(max_scroll)) as u16;
+    }
+
     // Slice the visible window directly — no Paragraph::scroll needed.
     let scroll = max_scroll - app.scroll_offset as usize;
     let visible_lines = &lines[scroll..scroll + visible.min(total)];
@@ -577,8 +777,59 @@ fn render_messages(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
     frame.render_widget(messages_widget, area);
 }
 
-fn render_separator(frame: &mut Frame, area: ratatui::layout::Rect) {
-    let line = Line::from(Span::styled("─".repeat(area.width as usize), DIM));
+fn separator_label(app: &App) -> Option<String> {
+    if let Some(pending) = &app.pending_write {
+        return Some(pending.hint());
+    }
+    if let Some(prompt) = &app.file_prompt {
+        return Some(prompt.hint());
+    }
+    if app.budget_pending.is_some()
+        && let Some(status) = &app.budget_status
+    {
+        return Some(format!(
+            "{}; {}",
+            status.message(),
+            t(Msg::BudgetConfirmHint)
+        ));
+    }
+    if app.large_request.is_some() {
+        return Some(t(Msg::LargeRequestHint).to_string());
+    }
+    if app.navigation.is_some() {
+        return Some(t(Msg::NavigationHint).to_string());
+    }
+    app.composer
+        .hint()
+        .or_else(|| pasted_label(app))
+        .or_else(|| app.budget_status.as_ref().map(BudgetStatus::message))
+        .or_else(|| app.models_notice.clone())
+}
+
+fn pasted_label(app: &App) -> Option<String> {
+    if app.pasted.is_empty() {
+        return None;
+    }
+    let summaries: Vec<String> = app.pasted.iter().map(Attachment::summary).collect();
+    Some(format!(
+        "{} attached (Backspace on an empty composer removes the last)",
+        summaries.join(", ")
+    ))
+}
+
+fn render_separator(frame: &mut Frame, label: Option<String>, area: ratatui::layout::Rect) {
+    let width = area.width as usize;
+    let line = match label {
+        Some(label) => {
+            let label = format!("── {label} ");
+            let fill = width.saturating_sub(label.chars().count());
+            Line::from(vec![
+                Span::styled(label, TYPEAHEAD_HEADER),
+                Span::styled("─".repeat(fill), DIM),
+            ])
+        }
+        None => Line::from(Span::styled("─".repeat(width), DIM)),
+    };
     frame.render_widget(Paragraph::new(line), area);
 }
 
@@ -638,7 +889,7 @@ fn render_input(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
 
     if text_area.width > 0 {
         let input_text = if app.input.is_empty() && matches!(app.state, AppState::Idle) {
-            Text::from(Span::styled("Type a message...", DIM))
+            Text::from(Span::styled(t(Msg::ComposerPlaceholder), DIM))
         } else {
             Text::raw(app.input.as_str())
         };
@@ -1,9 +1,89 @@
+use std::path::Path;
+
+use agnt_core::{PromptTemplate, discover_templates};
+
 use super::provider::TypeaheadItem;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     NewSession,
     ResumeSession,
+    /// Pin the open session to the top of session lists, or unpin it.
+    PinSession,
+    /// Discard the last response and generate it again.
+    Retry,
+    /// Like `Retry`, with another model or reasoning effort.
+    RetryWith,
+    /// Show the full output of the last tool result that was truncated for
+    /// the model.
+    RawOutput,
+    /// Attach the most recent tool result to the next message.
+    LastOutput,
+    /// Show the net diff of everything the session changed.
+    Changes,
+    /// Export the session's file changes as a patch series.
+    Patch,
+    /// Export the session as a self-contained HTML page.
+    Share,
+    /// Full-text search over past sessions.
+    History,
+    /// Edit the current project's settings.
+    ProjectSettings,
+    /// Show the JSON of the last request sent to the provider.
+    DebugRequest,
+    /// Break the next request down by section, with token estimates.
+    DebugContext,
+    /// Fetch the models.dev catalog again now.
+    ModelsRefresh,
+    /// Pin a file, URL or note into the agent's context.
+    AddContext,
+    /// Select pinned context items to unpin.
+    Context,
+    /// Answer a prompt with two models side by side.
+    Compare,
+    /// Bookmark the last turn, with an optional note.
+    Bookmark,
+    /// List bookmarked turns to jump back to one.
+    Bookmarks,
+    /// List the files the agent saved as artifacts this session.
+    Artifacts,
+    /// Fill in and submit a prompt template from `.agnt/templates`.
+    Template(PromptTemplate),
+}
+
+/// Every command available in `project_root`, including its prompt
+/// templates.
+pub fn available_commands(project_root: &Path) -> Vec<Command> {
+    let mut commands = vec![
+        Command::NewSession,
+        Command::ResumeSession,
+        Command::PinSession,
+        Command::Retry,
+        Command::RetryWith,
+        Command::RawOutput,
+        Command::LastOutput,
+        Command::Changes,
+        Command::Patch,
+        Command::Share,
+        Command::History,
+        Command::ProjectSettings,
+        Command::DebugRequest,
+        Command::DebugContext,
+        Command::ModelsRefresh,
+        Command::AddContext,
+        Command::Context,
+        Command::Compare,
+        Command::Bookmark,
+        Command::Bookmarks,
+        Command::Artifacts,
+    ];
+    let templates_dir = project_root.join(".agnt").join("templates");
+    commands.extend(
+        discover_templates(&templates_dir)
+            .into_iter()
+            .map(Command::Template),
+    );
+    commands
 }
 
 impl TypeaheadItem for Command {
@@ -11,6 +91,26 @@ impl TypeaheadItem for Command {
         match self {
             Command::NewSession => "new".to_string(),
             Command::ResumeSession => "resume".to_string(),
+            Command::PinSession => "pin".to_string(),
+            Command::Retry => "retry".to_string(),
+            Command::RetryWith => "retry:with".to_string(),
+            Command::RawOutput => "raw".to_string(),
+            Command::LastOutput => "last-output".to_string(),
+            Command::Changes => "changes".to_string(),
+            Command::Patch => "patch".to_string(),
+            Command::Share => "share".to_string(),
+            Command::History => "history".to_string(),
+            Command::ProjectSettings => "project:settings".to_string(),
+            Command::DebugRequest => "debug:request".to_string(),
+            Command::DebugContext => "debug:context".to_string(),
+            Command::ModelsRefresh => "models:refresh".to_string(),
+            Command::AddContext => "add".to_string(),
+            Command::Context => "context".to_string(),
+            Command::Compare => "compare".to_string(),
+            Command::Bookmark => "bookmark".to_string(),
+            Command::Bookmarks => "bookmarks".to_string(),
+            Command::Artifacts => "artifacts".to_string(),
+            Command::Template(template) => format!("template:{}", template.name),
         }
     }
 
@@ -18,6 +118,39 @@ impl TypeaheadItem for Command {
         match self {
             Command::NewSession => Some("Create a new session".to_string()),
             Command::ResumeSession => Some("Resume an existing session".to_string()),
+            Command::PinSession => {
+                Some("Pin or unpin this session at the top of session lists".to_string())
+            }
+            Command::Retry => Some("Regenerate the last response".to_string()),
+            Command::RetryWith => {
+                Some("Regenerate the last response with another model or effort".to_string())
+            }
+            Command::RawOutput => {
+                Some("Show the full output of the last truncated tool result".to_string())
+            }
+            Command::LastOutput => {
+                Some("Attach the last tool output to the next message".to_string())
+            }
+            Command::Changes => Some("Show everything changed this session".to_string()),
+            Command::Patch => Some("Export file changes as a patch series".to_string()),
+            Command::Share => Some("Export the session as an HTML page to share".to_string()),
+            Command::History => Some("Search session history".to_string()),
+            Command::ProjectSettings => Some("Edit project settings".to_string()),
+            Command::DebugRequest => Some("Show the last request sent to the provider".to_string()),
+            Command::DebugContext => Some("Show what takes up the context, by section".to_string()),
+            Command::ModelsRefresh => Some("Check models.dev for new models".to_string()),
+            Command::AddContext => Some("Pin a file, URL or note into context".to_string()),
+            Command::Context => Some("Unpin context items".to_string()),
+            Command::Compare => Some("Compare two models' answers side by side".to_string()),
+            Command::Bookmark => Some("Bookmark the last turn, with a note".to_string()),
+            Command::Bookmarks => Some("Jump to a bookmarked turn".to_string()),
+            Command::Artifacts => Some("List files the agent saved this session".to_string()),
+            Command::Template(template) => Some(
+                template
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| "Prompt template".to_string()),
+            ),
         }
     }
 
@@ -25,6 +158,81 @@ impl TypeaheadItem for Command {
         match self {
             Command::NewSession => vec!["new".to_string(), "session".to_string()],
             Command::ResumeSession => vec!["resume".to_string(), "session".to_string()],
+            Command::PinSession => vec![
+                "pin".to_string(),
+                "favorite".to_string(),
+                "session".to_string(),
+            ],
+            Command::Retry => vec!["retry".to_string(), "regenerate".to_string()],
+            Command::RetryWith => vec![
+                "retry".to_string(),
+                "regenerate".to_string(),
+                "model".to_string(),
+                "effort".to_string(),
+            ],
+            Command::RawOutput => vec!["raw".to_string(), "output".to_string()],
+            Command::LastOutput => vec![
+                "last".to_string(),
+                "output".to_string(),
+                "attach".to_string(),
+            ],
+            Command::Changes => vec!["changes".to_string(), "diff".to_string()],
+            Command::Patch => vec![
+                "patch".to_string(),
+                "export".to_string(),
+                "diff".to_string(),
+            ],
+            Command::Share => vec![
+                "share".to_string(),
+                "export".to_string(),
+                "html".to_string(),
+            ],
+            Command::History => vec!["history".to_string(), "search".to_string()],
+            Command::ProjectSettings => vec!["project".to_string(), "settings".to_string()],
+            Command::DebugRequest => vec![
+                "debug".to_string(),
+                "request".to_string(),
+                "json".to_string(),
+            ],
+            Command::DebugContext => vec![
+                "debug".to_string(),
+                "context".to_string(),
+                "tokens".to_string(),
+            ],
+            Command::ModelsRefresh => vec![
+                "models".to_string(),
+                "refresh".to_string(),
+                "catalog".to_string(),
+            ],
+            Command::AddContext => {
+                vec!["add".to_string(), "pin".to_string(), "context".to_string()]
+            }
+            Command::Context => vec![
+                "context".to_string(),
+                "pinned".to_string(),
+                "unpin".to_string(),
+            ],
+            Command::Compare => vec![
+                "compare".to_string(),
+                "model".to_string(),
+                "regenerate".to_string(),
+            ],
+            Command::Bookmark => vec![
+                "bookmark".to_string(),
+                "note".to_string(),
+                "turn".to_string(),
+            ],
+            Command::Bookmarks => vec![
+                "bookmarks".to_string(),
+                "jump".to_string(),
+                "turn".to_string(),
+            ],
+            Command::Artifacts => vec![
+                "artifacts".to_string(),
+                "files".to_string(),
+                "generated".to_string(),
+            ],
+            Command::Template(template) => vec!["template".to_string(), template.name.clone()],
         }
     }
 }
@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 
+use agnt_core::{AGNTIGNORE_FILENAME, Attachment, is_agntignored};
 use ignore::Match;
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
@@ -11,6 +12,15 @@ pub enum Mention {
     File(PathBuf),
 }
 
+impl Mention {
+    /// The text inserted into the composer for this mention.
+    pub fn token(&self) -> String {
+        match self {
+            Mention::File(path) => mention_text(&path_text(path)),
+        }
+    }
+}
+
 impl TypeaheadItem for Mention {
     fn token_text(&self) -> String {
         match self {
@@ -138,6 +148,15 @@ fn file_match_score(entry: &FileEntry, query: &str) -> Option<u8> {
     None
 }
 
+/// Every file under `root` that could be mentioned, relative to it and
+/// sorted.
+pub fn project_files(root: &Path) -> Vec<String> {
+    collect_file_entries(root)
+        .into_iter()
+        .map(|entry| entry.display)
+        .collect()
+}
+
 fn collect_file_entries(root: &Path) -> Vec<FileEntry> {
     let mut entries = Vec::new();
     walk_dir_with_scoped_ignores(root, &mut entries);
@@ -150,7 +169,7 @@ fn walk_dir_with_scoped_ignores(root: &Path, entries: &mut Vec<FileEntry>) {
     queue.push_back((root.to_path_buf(), Vec::<Gitignore>::new()));
 
     while let Some((dir, mut ignore_stack)) = queue.pop_front() {
-        if dir.file_name().is_some_and(|name| name == ".git") {
+        if is_vcs_dir(&dir) {
             continue;
         }
         if is_ignored_by_stack(&ignore_stack, &dir, true) {
@@ -197,7 +216,7 @@ fn walk_dir_with_scoped_ignores(root: &Path, entries: &mut Vec<FileEntry>) {
         }
 
         for child_dir in child_dirs {
-            if child_dir.file_name().is_some_and(|name| name == ".git") {
+            if is_vcs_dir(&child_dir) {
                 continue;
             }
             queue.push_back((child_dir, ignore_stack.clone()));
@@ -224,14 +243,27 @@ fn walk_dir_with_scoped_ignores(root: &Path, entries: &mut Vec<FileEntry>) {
     }
 }
 
+/// Repository metadata of git or jj (Jujutsu), never offered as mentions.
+fn is_vcs_dir(dir: &Path) -> bool {
+    dir.file_name()
+        .is_some_and(|name| name == ".git" || name == ".jj")
+}
+
+/// Rules from the directory's `.gitignore` and `.agntignore`; the latter wins
+/// where they disagree.
 fn load_local_gitignore(dir: &Path) -> Option<Gitignore> {
-    let gitignore_path = dir.join(".gitignore");
-    if !gitignore_path.is_file() {
+    let ignore_paths = [dir.join(".gitignore"), dir.join(AGNTIGNORE_FILENAME)]
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    if ignore_paths.is_empty() {
         return None;
     }
 
     let mut builder = GitignoreBuilder::new(dir);
-    let _ = builder.add(&gitignore_path);
+    for path in &ignore_paths {
+        let _ = builder.add(path);
+    }
     builder.build().ok()
 }
 
@@ -251,12 +283,82 @@ fn path_text(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
 
+// ---------------------------------------------------------------------------
+// Mention attachments
+// ---------------------------------------------------------------------------
+
+/// Maximum number of mentioned files attached to a single message.
+const MAX_MENTION_ATTACHMENTS: usize = 8;
+
+/// Paths referenced as `@path` or `@"path with spaces"` tokens in `text`,
+/// in order of first appearance.
+fn mentioned_paths(text: &str) -> Vec<&str> {
+    let mut paths: Vec<&str> = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = text[start..].find('@') {
+        let at = start + offset;
+        start = at + 1;
+        // Only at the start of a word, so `name@host` isn't a mention.
+        if text[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_whitespace())
+        {
+            continue;
+        }
+        let rest = &text[at + 1..];
+        let path = if let Some(quoted) = rest.strip_prefix('"') {
+            let Some(end) = quoted.find('"') else {
+                continue;
+            };
+            start = at + end + 3;
+            &quoted[..end]
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            rest[..end].trim_end_matches([',', '.', ';', ':', ')', '!', '?'])
+        };
+        if !path.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// `@path`, quoted when the path contains whitespace so that it parses back
+/// as a single mention.
+fn mention_text(path: &str) -> String {
+    if path.contains(char::is_whitespace) {
+        format!("@\"{path}\"")
+    } else {
+        format!("@{path}")
+    }
+}
+
+/// Mention token for `path`, relative to `root` when it lives inside it.
+pub fn mention_token(path: &Path, root: &Path) -> String {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    mention_text(&path_text(path))
+}
+
+/// Load the files mentioned in `text` (relative to `root`) as attachments.
+///
+/// Tokens that don't name a readable text file, or name one hidden by
+/// `.agntignore`, are left as plain text.
+pub fn mention_attachments(text: &str, root: &Path) -> Vec<Attachment> {
+    mentioned_paths(text)
+        .into_iter()
+        .filter(|path| root.join(path).is_file() && !is_agntignored(root, &root.join(path)))
+        .filter_map(|path| Attachment::file(root, path).ok())
+        .take(MAX_MENTION_ATTACHMENTS)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::{Path, PathBuf};
     use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-    use super::{FileMentionSource, Mention};
+    use super::{FileMentionSource, Mention, mention_attachments, mention_token, mentioned_paths};
     use crate::typeahead::TypeaheadProvider;
 
     async fn wait_until_ready(
@@ -401,4 +503,65 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&root);
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn agntignore_hides_paths_from_file_source_and_attachments() {
+        let root = std::env::temp_dir().join(format!(
+            "agnt-typeahead-test-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("time")
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(root.join("src")).expect("create src");
+        std::fs::create_dir_all(root.join("fixtures")).expect("create fixtures");
+
+        std::fs::write(root.join(".agntignore"), "/fixtures\n*.secret\n")
+            .expect("write agntignore");
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").expect("write src file");
+        std::fs::write(root.join("src/api.secret"), "nope\n").expect("write secret file");
+        std::fs::write(root.join("fixtures/blob.txt"), "nope\n").expect("write fixture");
+
+        let mut provider = TypeaheadProvider::new('@', FileMentionSource::new(root.clone()));
+        let all = wait_until_ready(&mut provider, "").await;
+        let paths = all
+            .into_iter()
+            .map(|mention| {
+                let Mention::File(path) = mention;
+                path.to_string_lossy().replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            paths.iter().any(|p| p == "src/main.rs"),
+            "expected src/main.rs to be visible; got {paths:?}"
+        );
+        assert!(
+            !paths
+                .iter()
+                .any(|p| p.starts_with("fixtures/") || p.ends_with(".secret")),
+            ".agntignore'd paths leaked into file source: {paths:?}"
+        );
+
+        let attachments = mention_attachments("@src/main.rs @src/api.secret", &root);
+        assert_eq!(attachments.len(), 1, "only src/main.rs should be attached");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn mentioned_paths_strip_punctuation_and_dedupe() {
+        let paths =
+            mentioned_paths("see @src/main.rs, then @Cargo.toml and @src/main.rs. email@x @");
+        assert_eq!(paths, vec!["src/main.rs", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn paths_with_spaces_are_quoted_and_parsed_back() {
+        let token = mention_token(Path::new("/repo/docs/release notes.md"), Path::new("/repo"));
+        assert_eq!(token, "@\"docs/release notes.md\"");
+        let paths = mentioned_paths(&format!("summarize {token}, then @src/main.rs @\"unclosed"));
+        assert_eq!(paths, vec!["docs/release notes.md", "src/main.rs"]);
+    }
 }
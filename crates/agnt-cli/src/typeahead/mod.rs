@@ -3,8 +3,8 @@ pub mod mentions;
 pub mod provider;
 pub mod state;
 
-pub use commands::Command;
-pub use mentions::{FileMentionSource, Mention};
+pub use commands::{Command, available_commands};
+pub use mentions::{FileMentionSource, Mention, mention_attachments, mention_token, project_files};
 pub use provider::{
     CachedPrefixSource, TypeaheadItem, TypeaheadMatchSet, TypeaheadProvider, TypeaheadSource,
     extract_query_token,
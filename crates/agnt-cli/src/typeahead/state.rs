@@ -5,7 +5,7 @@ use tokio::sync::watch;
 
 use crate::typeahead::{
     CachedPrefixSource, Command, FileMentionSource, Mention, TypeaheadItem, TypeaheadMatchSet,
-    TypeaheadProvider, TypeaheadSource, extract_query_token,
+    TypeaheadProvider, TypeaheadSource, available_commands, extract_query_token,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -120,8 +120,7 @@ impl TypeaheadState {
     }
 
     pub fn new(project_root: PathBuf) -> Self {
-        let command_source: CachedPrefixSource<Command> =
-            vec![Command::NewSession, Command::ResumeSession].into();
+        let command_source: CachedPrefixSource<Command> = available_commands(&project_root).into();
         let command_typeahead = TypeaheadProvider::new('/', command_source);
         let mention_typeahead = TypeaheadProvider::new('@', FileMentionSource::new(project_root));
 
@@ -0,0 +1,135 @@
+//! Version control awareness: which repository a directory belongs to, and
+//! the optional jj (Jujutsu) change per turn.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Where sessions for a directory are kept.
+pub struct ProjectIdentity {
+    /// The directory in the repository's main checkout; shared by every
+    /// worktree of that repository.
+    pub root: PathBuf,
+    /// The directory itself, when it is in a linked worktree or a submodule
+    /// checkout other than the main one.
+    pub worktree: Option<PathBuf>,
+}
+
+/// Resolve `dir` to the same place in its repository's main checkout, so
+/// sessions started in any worktree of a repository are kept together.
+/// Directories outside git, or in the main checkout, are their own root.
+pub fn project_identity(dir: &Path) -> ProjectIdentity {
+    let canonical = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let main = canonical
+        .ancestors()
+        .find(|checkout| checkout.join(".git").exists())
+        .and_then(|checkout| {
+            let git_dir = linked_git_dir(checkout)?;
+            let main_checkout = main_checkout(&git_dir)?.canonicalize().ok()?;
+            let relative = canonical.strip_prefix(checkout).ok()?;
+            Some(if relative.as_os_str().is_empty() {
+                main_checkout
+            } else {
+                main_checkout.join(relative)
+            })
+        });
+
+    match main {
+        Some(root) if root != canonical => ProjectIdentity {
+            root,
+            worktree: Some(canonical),
+        },
+        _ => ProjectIdentity {
+            root: dir.to_path_buf(),
+            worktree: None,
+        },
+    }
+}
+
+/// The git directory a checkout's `.git` file points to. `None` when
+/// `.git` is missing or a directory, i.e. for main checkouts.
+fn linked_git_dir(checkout: &Path) -> Option<PathBuf> {
+    let dot_git = checkout.join(".git");
+    if !dot_git.is_file() {
+        return None;
+    }
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let target = contents.trim().strip_prefix("gitdir:")?.trim();
+    Some(normalize(&checkout.join(target)))
+}
+
+/// The main checkout of the repository `git_dir` belongs to.
+fn main_checkout(git_dir: &Path) -> Option<PathBuf> {
+    // Linked worktrees name the main repository's git directory in
+    // `commondir`.
+    if let Ok(common) = std::fs::read_to_string(git_dir.join("commondir")) {
+        let common = normalize(&git_dir.join(common.trim()));
+        return if common.file_name()? == ".git" {
+            common.parent().map(Path::to_path_buf)
+        } else {
+            // Worktrees of a bare repository have no main checkout; the
+            // repository itself identifies them.
+            Some(common)
+        };
+    }
+    // Submodules keep their git directory under the superproject's and
+    // point back at their checkout with `core.worktree`.
+    let config = std::fs::read_to_string(git_dir.join("config")).ok()?;
+    let worktree = config.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "worktree").then(|| value.trim().to_string())
+    })?;
+    Some(normalize(&git_dir.join(worktree)))
+}
+
+/// Resolve `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// The root of the jj repository `dir` is in, if any.
+pub fn jj_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".jj").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Describe the working-copy change of the jj repository at `root` with
+/// `description` and start a new change on top, so the edits of a turn end
+/// up in a change of their own. Returns whether there was anything to
+/// describe; a turn that changed no files leaves the working copy alone.
+pub fn jj_close_change(root: &Path, description: &str) -> Result<bool, String> {
+    let empty = jj(root, &["log", "-r", "@", "--no-graph", "-T", "empty"])?;
+    if empty.trim() == "true" {
+        return Ok(false);
+    }
+    jj(root, &["describe", "-m", description])?;
+    jj(root, &["new"])?;
+    Ok(true)
+}
+
+fn jj(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("jj")
+        .arg("--no-pager")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|err| format!("could not run jj: {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "jj {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
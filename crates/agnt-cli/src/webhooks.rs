@@ -0,0 +1,71 @@
+//! Status webhooks: a POST to each configured URL when a turn completes or
+//! fails, or a provider budget nears its limit, so a long unattended run
+//! can be followed from elsewhere.
+//!
+//! Generic hooks receive `{"event": ..., "message": ..., "session": {...}}`
+//! with the session's id, title, project root and model. Slack and Discord
+//! hooks receive a one-line summary in the `text` / `content` field their
+//! incoming webhooks expect. Requests are sent in the background and their
+//! failures dropped, so a dead endpoint never holds up a turn.
+
+use std::time::Duration;
+
+use agnt_app::{UserConfig, WebhookConfig, WebhookEvent, WebhookFormat};
+use serde_json::{Value, json};
+use tokio::runtime::Handle;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Webhooks {
+    hooks: Vec<WebhookConfig>,
+    client: reqwest::Client,
+    /// Requests are spawned here, as the GUI calls in from outside the
+    /// runtime.
+    runtime: Handle,
+}
+
+impl Webhooks {
+    /// `None` when no webhooks are configured, or in offline mode. Must be
+    /// called from within the tokio runtime.
+    pub fn new(config: &UserConfig, offline: bool) -> Option<Self> {
+        if offline || config.webhooks.is_empty() {
+            return None;
+        }
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .ok()?;
+        Some(Self {
+            hooks: config.webhooks.clone(),
+            client,
+            runtime: Handle::current(),
+        })
+    }
+
+    /// Send `event` to every hook subscribed to it. `session` is the
+    /// metadata object of the generic payload.
+    pub fn send(&self, event: WebhookEvent, message: &str, session: Value) {
+        let summary = match session.get("title").and_then(Value::as_str) {
+            Some(title) => format!("agnt · {title}: {message}"),
+            None => format!("agnt: {message}"),
+        };
+        for hook in &self.hooks {
+            if !hook.events.is_empty() && !hook.events.contains(&event) {
+                continue;
+            }
+            let body = match hook.format {
+                WebhookFormat::Generic => json!({
+                    "event": event.as_str(),
+                    "message": message,
+                    "session": session,
+                }),
+                WebhookFormat::Slack => json!({ "text": summary }),
+                WebhookFormat::Discord => json!({ "content": summary }),
+            };
+            let request = self.client.post(&hook.url).json(&body);
+            self.runtime.spawn(async move {
+                let _ = request.send().await;
+            });
+        }
+    }
+}
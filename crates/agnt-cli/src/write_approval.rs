@@ -0,0 +1,61 @@
+//! Asks the user before the agent writes a path the project's settings
+//! protect, as a question each UI shows until it is answered.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use agnt_core::{ProtectedWrite, WriteApprover};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::i18n::{Msg, t};
+
+/// A protected write waiting for the user's answer. Dropping it refuses
+/// the write.
+pub struct PendingWrite {
+    write: ProtectedWrite,
+    reply: oneshot::Sender<bool>,
+}
+
+impl PendingWrite {
+    pub fn answer(self, allowed: bool) {
+        let _ = self.reply.send(allowed);
+    }
+
+    /// What the agent wants to write, to ask the user about.
+    pub fn question(&self) -> String {
+        let paths = self
+            .write
+            .paths
+            .iter()
+            .map(|path| format!("`{path}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("`{}` wants to write protected {paths}", self.write.tool)
+    }
+
+    /// The question, with the keys that answer it.
+    pub fn hint(&self) -> String {
+        format!("{}; {}", self.question(), t(Msg::WriteApprovalHint))
+    }
+}
+
+/// Hands each protected write to the UI holding the receiving end.
+struct ChannelApprover {
+    writes: mpsc::UnboundedSender<PendingWrite>,
+}
+
+impl WriteApprover for ChannelApprover {
+    fn approve(&self, write: ProtectedWrite) -> Pin<Box<dyn Future<Output = bool> + Send>> {
+        let (reply, answer) = oneshot::channel();
+        let asked = self.writes.send(PendingWrite { write, reply }).is_ok();
+        Box::pin(async move { asked && answer.await.unwrap_or(false) })
+    }
+}
+
+/// Have `agent` ask through the returned receiver before writing protected
+/// paths.
+pub fn ask_through_ui(agent: &mut agnt_core::Agent) -> mpsc::UnboundedReceiver<PendingWrite> {
+    let (writes, pending) = mpsc::unbounded_channel();
+    agent.write_approver(ChannelApprover { writes });
+    pending
+}
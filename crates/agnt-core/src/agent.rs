@@ -1,17 +1,39 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
-use agnt_llm::stream::{FinishReason, StreamEvent, Usage};
-use agnt_llm::{LanguageModel, Message, RequestBuilder, ToolDefinition};
+use agnt_llm::stream::{FinishReason, HostedToolCall, StreamEvent, Usage};
+use agnt_llm::{
+    AssistantPart, GenerateRequest, LanguageModel, Message, RequestBuilder, ToolDefinition,
+};
 use handlebars::Handlebars;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 
-use crate::event::AgentEvent;
-use crate::tool::{ErasedTool, Tool};
-use crate::tools::{BashTool, EditTool, ReadTool, SkillTool};
+use crate::artifacts::ArtifactsDirSlot;
+use crate::attachment::Attachment;
+use crate::context::{ContextItem, EditorContext};
+use crate::event::{
+    AgentEvent, CallApproval, DisplayBody, EventTiming, ToolCallDisplay, ToolCallRecord,
+    ToolResultDisplay, TurnStats,
+};
+use crate::filter::{
+    ContentFilter, FilterDirection, PatternFilter, apply_filters, filter_attachment,
+};
+use crate::format::Formatters;
+use crate::governor::ResourceGovernor;
+use crate::protect::{CallApprovalSlot, ProtectedPaths, WriteApprover, WriteApproverSlot};
+use crate::redact::Redactor;
+use crate::review::{EditReviewer, EditReviewerSlot, RecordingReviewer};
+use crate::settings::{AgentSettings, ApprovalPolicy, IgnoredPaths};
+use crate::summary::{ConversationSummary, SUMMARY_PROMPT};
+use crate::tool::{ErasedTool, Tool, ToolExecResult, cap_tool_result};
+use crate::tools::{
+    ArtifactTool, BashTool, CommandChecker, EditTool, PluginTool, ReadTool, SkillTool,
+};
 
 // ---------------------------------------------------------------------------
 // Agent state (shared between handle and spawned task)
@@ -21,8 +43,57 @@ struct AgentState {
     messages: Vec<Message>,
     tools: Vec<Box<dyn ErasedTool>>,
     agents_md: Option<String>,
+    redactor: Redactor,
+    approval_policy: ApprovalPolicy,
+    filters: Vec<Arc<dyn ContentFilter>>,
+    /// Calls with unparseable arguments so far, by tool name.
+    malformed_tool_calls: BTreeMap<String, usize>,
+    /// Reuse results of identical cacheable tool calls within a turn.
+    cache_tool_results: bool,
+    /// Times per turn an answer cut off at the token limit is continued.
+    max_continuations: usize,
+    edit_reviewer: EditReviewerSlot,
+    write_approver: WriteApproverSlot,
+    /// What the user decided about the running tool call.
+    call_approval: CallApprovalSlot,
+    /// Paths the tools only write with the user's approval, checked before
+    /// each call runs.
+    protected: Option<ProtectedPaths>,
+    artifacts_dir: ArtifactsDirSlot,
+    governor: ResourceGovernor,
+    /// The request last sent to the model.
+    last_request: Option<GenerateRequest>,
+    /// Items pinned with [`Agent::pin_context`], in the order pinned.
+    context: Vec<ContextItem>,
+    /// `context` as sent to the model, read when it last changed or a turn
+    /// started.
+    context_parts: Vec<agnt_llm::UserPart>,
+    /// Set with [`Agent::editor_context`]; sent with `context`.
+    editor: Option<EditorContext>,
+    /// Directory pinned files are relative to.
+    context_root: PathBuf,
 }
 
+/// Heads the system message carrying the project's AGENTS.md.
+const AGENTS_MD_HEADER: &str = "Repository instructions from AGENTS.md:\n\n";
+/// What [`Agent::remove_tool_results`] leaves in place of a result.
+const REMOVED_TOOL_RESULT: &str = "[tool result removed]";
+/// Malformed tool calls per turn that are fed back to the model without
+/// showing the failure, before further ones are surfaced.
+const MAX_SILENT_TOOL_CALL_RETRIES: usize = 2;
+/// Times per turn an answer cut off at the output token limit is continued,
+/// unless set with [`Agent::max_continuations`].
+pub const DEFAULT_MAX_CONTINUATIONS: usize = 2;
+/// Sent, without being added to the conversation, to continue an answer
+/// that was cut off.
+const CONTINUE_PROMPT: &str = "Your answer was cut off at the output token limit. Continue \
+exactly where it stopped, without repeating anything.";
+const REFUSAL_GUIDANCE: &str = "The provider's content policy stopped this answer. Rephrase \
+the request or leave out what it objects to, or try another model.";
+/// Malformed calls to one tool after which the system prompt gets a hint on
+/// calling it correctly.
+const MALFORMED_TOOL_CALL_HINT_AFTER: usize = 2;
+
 // ---------------------------------------------------------------------------
 // Agent
 // ---------------------------------------------------------------------------
@@ -41,6 +112,31 @@ pub struct Agent {
     configure_request: Option<Arc<ConfigureRequest>>,
 }
 
+/// A request as the provider receives it, for debugging.
+#[derive(Debug, Clone)]
+pub struct RequestInspection {
+    /// Whether the request was sent, rather than built the way the next one
+    /// would be.
+    pub sent: bool,
+    /// The JSON body, pretty-printed, with secrets redacted.
+    pub body: String,
+}
+
+/// What a request is made of, section by section, for showing what takes
+/// up the context window.
+#[derive(Debug, Clone)]
+pub struct RequestSections {
+    /// The system prompt, with the model's preamble.
+    pub system_prompt: String,
+    /// The project's AGENTS.md, sent from the first turn on.
+    pub agents_md: Option<String>,
+    /// Pinned context and what the editor has open.
+    pub context: Vec<agnt_llm::UserPart>,
+    /// The conversation, without the AGENTS.md instructions.
+    pub history: Vec<Message>,
+    pub tools: Vec<ToolDefinition>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationState {
     pub messages: Vec<Message>,
@@ -56,32 +152,113 @@ impl Agent {
                 messages: Vec::new(),
                 tools: Vec::new(),
                 agents_md: None,
+                redactor: Redactor::default(),
+                approval_policy: ApprovalPolicy::default(),
+                filters: Vec::new(),
+                malformed_tool_calls: BTreeMap::new(),
+                cache_tool_results: false,
+                max_continuations: DEFAULT_MAX_CONTINUATIONS,
+                edit_reviewer: EditReviewerSlot::default(),
+                write_approver: WriteApproverSlot::default(),
+                call_approval: CallApprovalSlot::default(),
+                protected: None,
+                artifacts_dir: ArtifactsDirSlot::default(),
+                governor: ResourceGovernor::default(),
+                last_request: None,
+                context: Vec::new(),
+                context_parts: Vec::new(),
+                editor: None,
+                context_root: PathBuf::from("."),
             })),
             configure_request: None,
         }
     }
 
-    /// Create an agent with the default coding tools (read, edit, skill, bash)
-    /// and a system prompt that turns it into a coding assistant.
+    /// Create an agent with the default coding tools (read, edit, skill,
+    /// artifact, bash) and a system prompt that turns it into a coding assistant.
     ///
     /// `cwd` is the working directory that file and bash tools operate in.
     pub fn with_defaults(model: LanguageModel, cwd: PathBuf) -> Self {
+        Self::with_settings(model, cwd, &AgentSettings::default())
+    }
+
+    /// Like [`Agent::with_defaults`], with the tool set narrowed by
+    /// `settings`: a read-only approval policy drops the `edit`, `rename`,
+    /// `artifact` and `bash` tools, ignored paths and `.agntignore` files
+    /// put paths off limits to `read` and `edit`, protected paths are only
+    /// written with the user's approval, formatters run on what `edit`
+    /// writes, the redaction allowlist is applied to tool output, and
+    /// blocked and scrubbed patterns become a [`PatternFilter`].
+    pub fn with_settings(model: LanguageModel, cwd: PathBuf, settings: &AgentSettings) -> Self {
         let workspace_root = find_workspace_root(&cwd);
         let agents_md = load_agents_md(&workspace_root);
         let skills_dir = workspace_root.join(".agents").join("skills");
+        let ignored = IgnoredPaths::new(&workspace_root, &settings.ignored_paths);
+        let read_only = settings.approval_policy == ApprovalPolicy::ReadOnly;
 
         let mut agent = Self::new(model);
-        agent.system(system_prompt(&cwd, &workspace_root));
+        agent.system(system_prompt(&cwd, &workspace_root, read_only));
 
         {
             let mut s = agent.state.lock();
             s.agents_md = agents_md;
+            s.redactor = Redactor::new(settings.redaction_allowlist.clone());
+            s.approval_policy = settings.approval_policy;
+            s.governor = ResourceGovernor::new(settings.resource_limits);
+            s.context_root = cwd.clone();
+        }
+        if !settings.blocked_patterns.is_empty() || !settings.scrubbed_patterns.is_empty() {
+            // Patterns are validated when the settings are saved.
+            if let Ok(filter) =
+                PatternFilter::new(&settings.blocked_patterns, &settings.scrubbed_patterns)
+            {
+                agent.content_filter(filter);
+            }
         }
 
-        agent.tool(ReadTool { cwd: cwd.clone() });
-        agent.tool(EditTool { cwd: cwd.clone() });
+        agent.tool(ReadTool {
+            cwd: cwd.clone(),
+            ignored: ignored.clone(),
+        });
+        let governor = agent.state.lock().governor.clone();
+        if !settings.protected_paths.is_empty() {
+            let mut s = agent.state.lock();
+            s.protected = Some(ProtectedPaths::new(
+                &workspace_root,
+                &settings.protected_paths,
+                s.write_approver.clone(),
+                s.call_approval.clone(),
+            ));
+        }
+        if !read_only {
+            let reviewer = agent.state.lock().edit_reviewer.clone();
+            #[cfg(feature = "symbols")]
+            agent.tool(crate::tools::RenameTool {
+                cwd: cwd.clone(),
+                ignored: ignored.clone(),
+                reviewer: reviewer.clone(),
+                governor: governor.clone(),
+            });
+            agent.tool(EditTool {
+                cwd: cwd.clone(),
+                ignored,
+                reviewer,
+                governor: governor.clone(),
+                formatters: Formatters::new(&workspace_root, &settings.formatters),
+            });
+        }
         agent.tool(SkillTool::new(skills_dir));
-        agent.tool(BashTool { cwd });
+        if !read_only {
+            let dir = agent.state.lock().artifacts_dir.clone();
+            agent.tool(ArtifactTool { dir });
+            let checker = (!settings.skip_command_checks)
+                .then(|| CommandChecker::new(cwd.clone(), workspace_root.clone()));
+            agent.tool(BashTool {
+                cwd,
+                governor,
+                checker,
+            });
+        }
 
         agent
     }
@@ -112,12 +289,105 @@ impl Agent {
         self
     }
 
+    /// Add `f` to the request configuration, run after what was configured
+    /// before, e.g. to ask for another reasoning effort from now on.
+    pub fn reconfigure_request(
+        &mut self,
+        f: impl Fn(&mut RequestBuilder) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let previous = self.configure_request.take();
+        self.configure_request = Some(Arc::new(move |req: &mut RequestBuilder| {
+            if let Some(previous) = &previous {
+                previous(req);
+            }
+            f(req);
+        }));
+        self
+    }
+
+    /// Generate with `model` from the next turn on.
+    pub fn set_model(&mut self, model: LanguageModel) -> &mut Self {
+        self.model = Arc::new(model);
+        self
+    }
+
     /// Register a tool the model can call.
     pub fn tool(&mut self, tool: impl Tool) -> &mut Self {
         self.state.lock().tools.push(Box::new(tool));
         self
     }
 
+    /// Register a tool provided by a plugin executable. Fails when a tool
+    /// of that name is already registered.
+    pub fn plugin_tool(&mut self, tool: PluginTool) -> Result<&mut Self, String> {
+        self.external_tool(Box::new(tool))
+    }
+
+    /// Register a tool provided by a WASM component. Fails when a tool of
+    /// that name is already registered.
+    #[cfg(feature = "wasm")]
+    pub fn wasm_tool(&mut self, tool: crate::tools::WasmTool) -> Result<&mut Self, String> {
+        self.external_tool(Box::new(tool))
+    }
+
+    fn external_tool(&mut self, tool: Box<dyn ErasedTool>) -> Result<&mut Self, String> {
+        {
+            let mut s = self.state.lock();
+            let name = tool.definition().name;
+            if s.tools.iter().any(|t| t.definition().name == name) {
+                return Err(format!("a tool named `{name}` already exists"));
+            }
+            s.tools.push(tool);
+        }
+        Ok(self)
+    }
+
+    /// Add a guardrail that checks prompts and completions. While any filter
+    /// is installed, completion text is held back until each text part is
+    /// complete instead of being streamed.
+    pub fn content_filter(&mut self, filter: impl ContentFilter) -> &mut Self {
+        self.state.lock().filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Have `reviewer` approve every change of the `edit` tool before it is
+    /// written.
+    pub fn edit_reviewer(&mut self, reviewer: impl EditReviewer) -> &mut Self {
+        let s = self.state.lock();
+        *s.edit_reviewer.write() = Some(Arc::new(RecordingReviewer {
+            reviewer: Arc::new(reviewer),
+            decision: s.call_approval.clone(),
+        }));
+        self
+    }
+
+    /// Have `approver` decide whether a tool may write the paths the
+    /// settings protect; without one, such writes are refused.
+    pub fn write_approver(&mut self, approver: impl WriteApprover) -> &mut Self {
+        *self.state.lock().write_approver.write() = Some(Arc::new(approver));
+        self
+    }
+
+    /// Save the `artifact` tool's files in `dir`, normally the active
+    /// session's; without one the tool fails.
+    pub fn artifacts_dir(&self, dir: Option<PathBuf>) {
+        *self.state.lock().artifacts_dir.write() = dir;
+    }
+
+    /// Let repeated calls of a [cacheable](Tool::cacheable) tool with the
+    /// same arguments reuse the first result for the rest of the turn.
+    pub fn cache_tool_results(&mut self, enabled: bool) -> &mut Self {
+        self.state.lock().cache_tool_results = enabled;
+        self
+    }
+
+    /// Continue an answer cut off at the output token limit up to `max`
+    /// times per turn; `0` leaves it cut off.
+    pub fn max_continuations(&mut self, max: usize) -> &mut Self {
+        self.state.lock().max_continuations = max;
+        self
+    }
+
     /// Access the conversation history (completed messages only).
     pub fn messages(&self) -> Vec<Message> {
         self.state.lock().messages.clone()
@@ -132,7 +402,209 @@ impl Agent {
 
     /// Replace in-memory conversation state with a previously saved snapshot.
     pub fn restore_conversation_state(&self, state: ConversationState) {
-        self.state.lock().messages = state.messages;
+        let mut s = self.state.lock();
+        s.messages = state.messages;
+        s.last_request = None;
+    }
+
+    /// Drop every message after the one at `index` in
+    /// [`messages`](Agent::messages), e.g. to rewind to an earlier point of
+    /// the conversation. Cutting between an assistant message and the tool
+    /// results answering it leaves its calls unanswered, which providers
+    /// reject; cut after the results instead.
+    pub fn truncate_after(&self, index: usize) {
+        self.state.lock().messages.truncate(index.saturating_add(1));
+    }
+
+    /// Replace the content of the tool results `filter` picks, given the call
+    /// each answers, with a note that it was removed; the calls keep a
+    /// result, as providers require one for each. Returns how many were
+    /// removed.
+    pub fn remove_tool_results(
+        &self,
+        filter: impl Fn(&agnt_llm::ToolCallPart, &agnt_llm::ToolResultPart) -> bool,
+    ) -> usize {
+        let mut s = self.state.lock();
+        let calls: HashMap<String, agnt_llm::ToolCallPart> = s
+            .messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Assistant { parts } => Some(parts),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                agnt_llm::AssistantPart::ToolCall(call) => Some((call.id.clone(), call.clone())),
+                _ => None,
+            })
+            .collect();
+        let mut removed = 0;
+        for message in &mut s.messages {
+            let Message::Tool { parts } = message else {
+                continue;
+            };
+            for result in parts {
+                if result.content != REMOVED_TOOL_RESULT
+                    && let Some(call) = calls.get(&result.tool_call_id)
+                    && filter(call, result)
+                {
+                    result.content = REMOVED_TOOL_RESULT.to_string();
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Add `parts` to the conversation as a user message without starting a
+    /// turn, so the model sees them with the next prompt. Call it between
+    /// turns; a running turn doesn't pick them up until its next request.
+    pub fn inject_context(&self, parts: impl IntoIterator<Item = agnt_llm::UserPart>) {
+        let parts: Vec<agnt_llm::UserPart> = parts.into_iter().collect();
+        if parts.is_empty() {
+            return;
+        }
+        self.state.lock().messages.push(Message::User { parts });
+    }
+
+    /// Pin `item` into context: it is attached to every request, ahead of
+    /// the conversation, until unpinned. Pinning an item again does nothing.
+    pub fn pin_context(&self, item: ContextItem) {
+        let mut s = self.state.lock();
+        if !s.context.contains(&item) {
+            s.context.push(item);
+            refresh_context(&mut s);
+        }
+    }
+
+    /// Unpin the item at `index` of [`pinned_context`](Agent::pinned_context).
+    pub fn unpin_context(&self, index: usize) -> Option<ContextItem> {
+        let mut s = self.state.lock();
+        if index >= s.context.len() {
+            return None;
+        }
+        let item = s.context.remove(index);
+        refresh_context(&mut s);
+        Some(item)
+    }
+
+    /// Tell the agent what the editor driving it has open: the files and the
+    /// selection, so a prompt can say "this function". Replaces what was set
+    /// before and is sent with every request from then on, without becoming
+    /// part of the conversation; `None` clears it. Editor integrations call
+    /// it before each submit.
+    pub fn editor_context(&self, context: Option<EditorContext>) {
+        let mut s = self.state.lock();
+        s.editor = context.filter(|context| !context.is_empty());
+        refresh_context(&mut s);
+    }
+
+    /// Items currently pinned into context, in the order pinned.
+    pub fn pinned_context(&self) -> Vec<ContextItem> {
+        self.state.lock().context.clone()
+    }
+
+    /// The request last sent to the model, or with none sent since the
+    /// conversation was restored, the one the conversation so far would
+    /// make (without a new prompt). `None` when the provider can't show its
+    /// request bodies.
+    pub fn inspect_request(&self) -> Option<RequestInspection> {
+        let s = self.state.lock();
+        let (sent, request) = match &s.last_request {
+            Some(request) => (true, request.clone()),
+            None => (
+                false,
+                build_request(
+                    &self.model,
+                    &s.messages,
+                    &s,
+                    self.system_prompt.as_deref(),
+                    self.configure_request.as_deref(),
+                    Vec::new(),
+                ),
+            ),
+        };
+        let body = self.model.request_body(request)?;
+        let body = serde_json::to_string_pretty(&body).ok()?;
+        Some(RequestInspection {
+            sent,
+            body: s.redactor.redact(&body),
+        })
+    }
+
+    /// The sections of the next request, without a new prompt. Unlike
+    /// [`inspect_request`](Agent::inspect_request) this works with any
+    /// provider.
+    pub fn request_sections(&self) -> RequestSections {
+        let s = self.state.lock();
+        let mut agents_md = s.agents_md.clone();
+        let mut history = Vec::with_capacity(s.messages.len());
+        for message in &s.messages {
+            if let Message::System { parts } = message
+                && let [agnt_llm::SystemPart::Text(text)] = parts.as_slice()
+                && let Some(instructions) = text.text.strip_prefix(AGENTS_MD_HEADER)
+            {
+                agents_md = Some(instructions.to_string());
+                continue;
+            }
+            history.push(message.clone());
+        }
+        RequestSections {
+            system_prompt: system_text(&self.model, &s, self.system_prompt.as_deref()),
+            agents_md,
+            context: s.context_parts.clone(),
+            history,
+            tools: s.tools.iter().map(|t| t.definition()).collect(),
+        }
+    }
+
+    /// Ask the model for a [`ConversationSummary`] of `state`, e.g. this
+    /// agent's [`conversation_state`](Agent::conversation_state) before it is
+    /// replaced or one restored from storage. `None` for an empty
+    /// conversation or an empty answer.
+    pub fn summarize(
+        &self,
+        state: ConversationState,
+    ) -> impl Future<Output = Result<Option<ConversationSummary>, agnt_llm::Error>> + Send + 'static
+    {
+        let model = Arc::clone(&self.model);
+        let request = (!state.messages.is_empty()).then(|| {
+            let mut req = agnt_llm::request();
+            let mut messages = state.messages;
+            messages.push(Message::User {
+                parts: vec![agnt_llm::UserPart::Text(agnt_llm::TextPart {
+                    text: SUMMARY_PROMPT.to_string(),
+                    metadata: HashMap::new(),
+                })],
+            });
+            req.messages(messages);
+            // Providers reject tool calls in the history without the tools
+            // they call; the model is asked not to call any.
+            let tool_defs: Vec<ToolDefinition> = self
+                .state
+                .lock()
+                .tools
+                .iter()
+                .map(|t| t.definition())
+                .collect();
+            req.tools(tool_defs);
+            if let Some(configure) = self.configure_request.as_deref() {
+                configure(&mut req);
+            }
+            let mut request = req.build();
+            request.options.tool_choice = agnt_llm::ToolChoice::None;
+            if let Some(behavior) = model.behavior() {
+                behavior.transform_messages(&mut request.messages);
+            }
+            request
+        });
+        async move {
+            let Some(request) = request else {
+                return Ok(None);
+            };
+            let result = model.generate(request).into_result().await?;
+            Ok(ConversationSummary::parse(&result.text))
+        }
     }
 
     /// Submit user input and get back a stream of events.
@@ -143,6 +615,16 @@ impl Agent {
     ///
     /// Dropping the `AgentStream` cancels the generation.
     pub fn submit(&self, content: impl Into<String>) -> AgentStream {
+        self.submit_with_attachments(content, Vec::new())
+    }
+
+    /// Like [`submit`](Agent::submit), but sends `attachments` as extra
+    /// context parts of the same user message.
+    pub fn submit_with_attachments(
+        &self,
+        content: impl Into<String>,
+        attachments: Vec<Attachment>,
+    ) -> AgentStream {
         let content = content.into();
         let (tx, rx) = mpsc::channel(64);
 
@@ -152,7 +634,101 @@ impl Agent {
         let configure_request = self.configure_request.clone();
 
         tokio::spawn(async move {
-            generation_loop(model, state, system_prompt, configure_request, content, tx).await;
+            generation_loop(
+                model,
+                state,
+                system_prompt,
+                configure_request,
+                content,
+                attachments,
+                tx,
+            )
+            .await;
+        });
+
+        AgentStream { rx }
+    }
+
+    /// Pick up a turn whose response was still generating in the background
+    /// when an earlier process exited. Restore the conversation state saved
+    /// with [`AgentEvent::ResponsePending`] first; `token` is the one it
+    /// carried.
+    pub fn resume(&self, token: impl Into<String>) -> AgentStream {
+        let token = token.into();
+        let (tx, rx) = mpsc::channel(64);
+
+        let model = Arc::clone(&self.model);
+        let state = Arc::clone(&self.state);
+        let system_prompt = self.system_prompt.clone();
+        let configure_request = self.configure_request.clone();
+
+        tokio::spawn(async move {
+            run_turn(
+                model,
+                state,
+                system_prompt,
+                configure_request,
+                Some(token),
+                tx,
+            )
+            .await;
+        });
+
+        AgentStream { rx }
+    }
+
+    /// Answer `content` with `model` after `history`, e.g. the agent's
+    /// [`messages`](Agent::messages) or the ones before its last prompt to
+    /// ask that again. This is a side request next to the agent's own: the
+    /// system prompt, pinned context and tools are the agent's, but tools may
+    /// not be called and nothing joins the conversation. `configure` takes
+    /// the place of the agent's request configuration, e.g. to ask for
+    /// another reasoning effort. The stream yields text and reasoning deltas,
+    /// then `TurnComplete` or `Error`.
+    pub fn submit_detached(
+        &self,
+        model: LanguageModel,
+        history: Vec<Message>,
+        content: impl Into<String>,
+        configure: impl Fn(&mut RequestBuilder) + Send + Sync + 'static,
+    ) -> AgentStream {
+        let content = content.into();
+        let (tx, rx) = mpsc::channel(64);
+
+        let request = {
+            let s = self.state.lock();
+            apply_filters(&s.filters, FilterDirection::Input, content).map(|content| {
+                let mut prompt = Vec::new();
+                if history.is_empty()
+                    && let Some(agents_md) = &s.agents_md
+                {
+                    prompt.push(Message::system(format!("{AGENTS_MD_HEADER}{agents_md}")));
+                }
+                prompt.push(Message::user(content));
+                let mut request = build_request(
+                    &model,
+                    &history,
+                    &s,
+                    self.system_prompt.as_deref(),
+                    Some(&configure),
+                    prompt,
+                );
+                request.options.tool_choice = agnt_llm::ToolChoice::None;
+                (request, s.filters.clone())
+            })
+        };
+
+        tokio::spawn(async move {
+            match request {
+                Ok((request, filters)) => detached_turn(model, request, filters, tx).await,
+                Err(reason) => {
+                    let _ = tx
+                        .send(AgentEvent::Error {
+                            error: format!("prompt blocked by content filter: {reason}"),
+                        })
+                        .await;
+                }
+            }
         });
 
         AgentStream { rx }
@@ -188,23 +764,67 @@ async fn generation_loop(
     system_prompt: Option<String>,
     configure_request: Option<Arc<ConfigureRequest>>,
     content: String,
+    attachments: Vec<Attachment>,
     tx: mpsc::Sender<AgentEvent>,
 ) {
+    let attachment_summaries: Vec<String> = attachments.iter().map(Attachment::summary).collect();
+    let filters = state.lock().filters.clone();
+    state.lock().governor.start_turn();
+    let content = match apply_filters(&filters, FilterDirection::Input, content) {
+        Ok(content) => content,
+        Err(reason) => {
+            let _ = tx
+                .send(AgentEvent::Error {
+                    error: format!("prompt blocked by content filter: {reason}"),
+                })
+                .await;
+            return;
+        }
+    };
+    let attachments = match attachments
+        .into_iter()
+        .map(|attachment| {
+            let label = attachment.label.clone();
+            filter_attachment(&filters, attachment)
+                .map_err(|reason| format!("attachment {label} blocked by content filter: {reason}"))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(attachments) => attachments,
+        Err(error) => {
+            let _ = tx.send(AgentEvent::Error { error }).await;
+            return;
+        }
+    };
+
     // 1. Record user message and inject AGENTS.md once on first turn.
     {
         let mut s = state.lock();
         if s.messages.is_empty()
             && let Some(agents_md) = s.agents_md.take()
         {
-            s.messages.push(Message::system(format!(
-                "Repository instructions from AGENTS.md:\n\n{agents_md}"
-            )));
+            s.messages
+                .push(Message::system(format!("{AGENTS_MD_HEADER}{agents_md}")));
+        }
+        let mut message = Message::user(&content);
+        if let Message::User { parts } = &mut message {
+            // A message of attachments alone, e.g. a long paste, has no text
+            // part of its own.
+            if content.is_empty() && !attachments.is_empty() {
+                parts.clear();
+            }
+            parts.extend(
+                attachments
+                    .into_iter()
+                    .flat_map(Attachment::into_user_parts),
+            );
         }
-        s.messages.push(Message::user(&content));
+        s.messages.push(message);
     }
     if tx
         .send(AgentEvent::UserMessage {
             content: content.clone(),
+            attachments: attachment_summaries,
         })
         .await
         .is_err()
@@ -212,37 +832,284 @@ async fn generation_loop(
         return; // receiver dropped
     }
 
-    let mut cumulative_usage = Usage::default();
+    run_turn(model, state, system_prompt, configure_request, None, tx).await;
+}
 
-    // 2. Generation loop (may iterate for tool calls)
-    loop {
-        // Build request from current state
-        let request = {
-            let s = state.lock();
-            let mut req = agnt_llm::request();
-            if let Some(ref system) = system_prompt {
-                req.system(system.as_str());
-            }
-            req.messages(s.messages.clone());
+/// The request for the next generation step, from `history` (the
+/// conversation so far, unless asking aside) followed by `prompt`, which
+/// isn't part of it.
+fn build_request(
+    model: &LanguageModel,
+    history: &[Message],
+    s: &AgentState,
+    system_prompt: Option<&str>,
+    configure_request: Option<&ConfigureRequest>,
+    prompt: Vec<Message>,
+) -> GenerateRequest {
+    let mut req = agnt_llm::request();
+    let behavior = model.behavior();
+    let system = system_text(model, s, system_prompt);
+    if !system.is_empty() {
+        req.system(system);
+    }
+    let mut messages = history.to_vec();
+    if !s.context_parts.is_empty() {
+        let after_system = messages
+            .iter()
+            .take_while(|message| matches!(message, Message::System { .. }))
+            .count();
+        messages.insert(
+            after_system,
+            Message::User {
+                parts: s.context_parts.clone(),
+            },
+        );
+    }
+    messages.extend(prompt);
+    req.messages(messages);
 
-            let tool_defs: Vec<ToolDefinition> = s.tools.iter().map(|t| t.definition()).collect();
-            req.tools(tool_defs);
+    let tool_defs: Vec<ToolDefinition> = s.tools.iter().map(|t| t.definition()).collect();
+    req.tools(tool_defs);
 
-            // Apply caller-provided request configuration (e.g. reasoning options).
-            if let Some(ref configure) = configure_request {
-                configure(&mut req);
-            }
+    // Apply caller-provided request configuration (e.g. reasoning options).
+    if let Some(configure) = configure_request {
+        configure(&mut req);
+    }
 
-            req.build()
+    let mut request = req.build();
+    if let Some(behavior) = behavior {
+        behavior.transform_messages(&mut request.messages);
+    }
+    request
+}
+
+/// The system prompt as sent: the model's preamble, the agent's prompt and
+/// hints about tool calls the model got wrong.
+fn system_text(model: &LanguageModel, s: &AgentState, system_prompt: Option<&str>) -> String {
+    let mut system = system_prompt.unwrap_or_default().to_string();
+    if let Some(preamble) = model
+        .behavior()
+        .and_then(|behavior| behavior.system_preamble())
+    {
+        system = format!("{preamble}\n\n{system}");
+    }
+    system.push_str(&malformed_tool_call_hints(&s.malformed_tool_calls));
+    system
+}
+
+/// Read the pinned context, and the editor's, into the parts sent with
+/// every request.
+fn refresh_context(s: &mut AgentState) {
+    let mut parts = Vec::new();
+    if !s.context.is_empty() {
+        parts.push(agnt_llm::UserPart::Text(agnt_llm::TextPart {
+            text: "Context pinned by the user, current as of this turn:".to_string(),
+            metadata: HashMap::new(),
+        }));
+        for item in &s.context {
+            let attachment = filtered_context(&s.filters, item.attachment(&s.context_root));
+            parts.extend(attachment.into_user_parts());
+        }
+    }
+    if let Some(editor) = &s.editor {
+        parts.push(agnt_llm::UserPart::Text(agnt_llm::TextPart {
+            text: "What the user has open in their editor right now:".to_string(),
+            metadata: HashMap::new(),
+        }));
+        parts.extend(filtered_context(&s.filters, editor.attachment()).into_user_parts());
+    }
+    s.context_parts = parts;
+}
+
+/// `attachment` run through the content filters like a prompt; one they
+/// block is replaced by a note saying so.
+fn filtered_context(filters: &[Arc<dyn ContentFilter>], attachment: Attachment) -> Attachment {
+    let label = attachment.label.clone();
+    filter_attachment(filters, attachment).unwrap_or_else(|reason| {
+        Attachment::note(
+            label.clone(),
+            format!("{label} was left out by a content filter: {reason}"),
+        )
+    })
+}
+
+fn hosted_tool_title(name: &str) -> &str {
+    match name {
+        "web_search" => "Search the web",
+        "code_interpreter" => "Run code",
+        "image_generation" => "Generate an image",
+        other => other,
+    }
+}
+
+/// How to show a finished provider-hosted tool call: what it was asked and
+/// what it returned.
+fn hosted_tool_displays(call: HostedToolCall) -> (ToolCallDisplay, ToolResultDisplay) {
+    let title = hosted_tool_title(&call.name);
+    let display = match call.name.as_str() {
+        _ if call.input.is_empty() => ToolCallDisplay {
+            title: title.to_string(),
+            body: None,
+        },
+        "code_interpreter" => ToolCallDisplay {
+            title: title.to_string(),
+            body: Some(DisplayBody::Code {
+                language: Some("python".to_string()),
+                content: call.input,
+            }),
+        },
+        _ => ToolCallDisplay {
+            title: format!("{title}: {}", call.input),
+            body: None,
+        },
+    };
+    let output = call.output.filter(|output| !output.trim().is_empty());
+    let result_title = if !call.succeeded {
+        "failed".to_string()
+    } else {
+        match (call.name.as_str(), &output) {
+            ("web_search", Some(sources)) => format!("{} sources", sources.lines().count()),
+            (_, Some(output)) => format!("{} lines of output", output.lines().count()),
+            (_, None) => "done".to_string(),
+        }
+    };
+    let result = ToolResultDisplay {
+        title: result_title,
+        body: output.map(DisplayBody::Text),
+    };
+    (display, result)
+}
+
+/// Stream the answer to a request of [`Agent::submit_detached`]. With
+/// content filters, the text is held back until the answer is complete and
+/// has been checked.
+async fn detached_turn(
+    model: LanguageModel,
+    request: GenerateRequest,
+    filters: Vec<Arc<dyn ContentFilter>>,
+    tx: mpsc::Sender<AgentEvent>,
+) {
+    let started = Instant::now();
+    let mut stream = model.generate(request).events();
+    let mut held = String::new();
+    let mut usage = Usage::default();
+    while let Some(event) = stream.next().await {
+        let event = match event {
+            Ok(StreamEvent::TextDelta(delta)) if filters.is_empty() => {
+                AgentEvent::TextDelta { delta }
+            }
+            Ok(StreamEvent::TextDelta(delta)) => {
+                held.push_str(&delta);
+                continue;
+            }
+            Ok(StreamEvent::ReasoningDelta(delta)) => AgentEvent::ReasoningDelta { delta },
+            Ok(StreamEvent::Image(image)) => AgentEvent::Image { url: image.url },
+            Ok(StreamEvent::Refusal(message)) => AgentEvent::Refusal {
+                message: Some(message).filter(|message| !message.is_empty()),
+                guidance: REFUSAL_GUIDANCE.to_string(),
+            },
+            Ok(StreamEvent::Finish { usage: Some(u), .. }) => {
+                usage = u;
+                continue;
+            }
+            Ok(StreamEvent::Error(error)) => AgentEvent::Error { error },
+            Ok(_) => continue,
+            Err(err) => AgentEvent::Error {
+                error: err.to_string(),
+            },
         };
+        let failed = matches!(event, AgentEvent::Error { .. });
+        if tx.send(event).await.is_err() || failed {
+            return;
+        }
+    }
+    if !held.is_empty() {
+        let event = match apply_filters(&filters, FilterDirection::Output, held) {
+            Ok(delta) => AgentEvent::TextDelta { delta },
+            Err(reason) => AgentEvent::Error {
+                error: format!("response blocked by content filter: {reason}"),
+            },
+        };
+        let failed = matches!(event, AgentEvent::Error { .. });
+        if tx.send(event).await.is_err() || failed {
+            return;
+        }
+    }
+    let _ = tx
+        .send(AgentEvent::TurnComplete {
+            usage,
+            stats: TurnStats {
+                model: model.model_id().to_string(),
+                duration: started.elapsed(),
+                tool_calls: 0,
+                timings: Vec::new(),
+            },
+        })
+        .await;
+}
+
+/// Generate until the model answers without tool calls. With
+/// `resume_token`, the first response is one started by an earlier process
+/// instead of a new request.
+async fn run_turn(
+    model: Arc<LanguageModel>,
+    state: Arc<Mutex<AgentState>>,
+    system_prompt: Option<String>,
+    configure_request: Option<Arc<ConfigureRequest>>,
+    mut resume_token: Option<String>,
+    tx: mpsc::Sender<AgentEvent>,
+) {
+    let (filters, max_continuations) = {
+        let s = state.lock();
+        (s.filters.clone(), s.max_continuations)
+    };
+    let started = Instant::now();
+    let mut cumulative_usage = Usage::default();
+    let mut tool_call_count = 0;
+    let mut timings = Vec::new();
+    let mut silent_retries = 0;
+    let mut continuations = 0;
+    // Whether this response continues an answer cut off at the token limit.
+    let mut continuing = false;
+    let mut tool_cache: HashMap<(String, String), ToolExecResult> = HashMap::new();
+    // Pinned files may have changed since the last turn.
+    refresh_context(&mut state.lock());
 
+    // 2. Generation loop (may iterate for tool calls)
+    loop {
         // Stream the response. We collect AssistantParts in arrival order
         // so interleaved reasoning/text/tool-calls are preserved exactly.
-        let mut stream = model.generate(request).events();
+        let response_start = started.elapsed();
+        let response = match resume_token.take() {
+            Some(token) => model.resume(&token),
+            None => {
+                let request = {
+                    let mut s = state.lock();
+                    let request = build_request(
+                        &model,
+                        &s.messages,
+                        &s,
+                        system_prompt.as_deref(),
+                        configure_request.as_deref(),
+                        if continuing {
+                            vec![Message::user(CONTINUE_PROMPT)]
+                        } else {
+                            Vec::new()
+                        },
+                    );
+                    s.last_request = Some(request.clone());
+                    request
+                };
+                model.generate(request)
+            }
+        };
+        let mut stream = response.events();
         let mut parts: Vec<agnt_llm::AssistantPart> = Vec::new();
         let mut text = String::new();
         let mut tool_calls: Vec<agnt_llm::ToolCallPart> = Vec::new();
         let mut finish_reason = FinishReason::Stop;
+        let mut refusal = None;
+        let mut first_token = None;
 
         // Helper: flush accumulated text deltas into a Text part with
         // optional metadata (e.g. the message item ID for roundtripping).
@@ -252,8 +1119,34 @@ async fn generation_loop(
             };
             ($parts:expr, $text:expr, $meta:expr) => {
                 if !$text.is_empty() {
+                    let mut flushed = std::mem::take(&mut $text);
+                    // Filtered text was held back; show it now it's checked.
+                    if !filters.is_empty() {
+                        flushed = match apply_filters(&filters, FilterDirection::Output, flushed) {
+                            Ok(flushed) => flushed,
+                            Err(reason) => {
+                                let _ = tx
+                                    .send(AgentEvent::Error {
+                                        error: format!(
+                                            "response blocked by content filter: {reason}"
+                                        ),
+                                    })
+                                    .await;
+                                return;
+                            }
+                        };
+                        if tx
+                            .send(AgentEvent::TextDelta {
+                                delta: flushed.clone(),
+                            })
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
                     $parts.push(agnt_llm::AssistantPart::Text(agnt_llm::TextPart {
-                        text: std::mem::take(&mut $text),
+                        text: flushed,
                         metadata: $meta,
                     }));
                 }
@@ -261,15 +1154,28 @@ async fn generation_loop(
         }
 
         while let Some(event) = stream.next().await {
+            // Only output counts; a resume token or an error isn't the
+            // model answering.
+            if matches!(
+                event,
+                Ok(StreamEvent::TextDelta(_)
+                    | StreamEvent::ReasoningDelta(_)
+                    | StreamEvent::ToolCallBegin { .. }
+                    | StreamEvent::Refusal(_)
+                    | StreamEvent::Image(_))
+            ) {
+                first_token.get_or_insert_with(|| started.elapsed() - response_start);
+            }
             match event {
                 Ok(StreamEvent::TextDelta(delta)) => {
                     text.push_str(&delta);
-                    if tx
-                        .send(AgentEvent::TextDelta {
-                            delta: delta.clone(),
-                        })
-                        .await
-                        .is_err()
+                    if filters.is_empty()
+                        && tx
+                            .send(AgentEvent::TextDelta {
+                                delta: delta.clone(),
+                            })
+                            .await
+                            .is_err()
                     {
                         return;
                     }
@@ -280,6 +1186,26 @@ async fn generation_loop(
                     // the message item ID needed for roundtripping).
                     flush_text!(parts, text, metadata);
                 }
+                Ok(StreamEvent::Refusal(message)) => {
+                    flush_text!(parts, text);
+                    // Kept as the answer, so the conversation shows it.
+                    if !message.is_empty() {
+                        parts.push(agnt_llm::AssistantPart::Text(agnt_llm::TextPart {
+                            text: message.clone(),
+                            metadata: HashMap::new(),
+                        }));
+                    }
+                    refusal = Some(message);
+                }
+                Ok(StreamEvent::ReasoningSectionStart { index }) => {
+                    if tx
+                        .send(AgentEvent::ReasoningSectionStart { index })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
                 Ok(StreamEvent::ReasoningDelta(delta)) => {
                     if tx
                         .send(AgentEvent::ReasoningDelta {
@@ -308,6 +1234,19 @@ async fn generation_loop(
                     tool_calls.push(call.clone());
                     parts.push(agnt_llm::AssistantPart::ToolCall(call));
                 }
+                Ok(StreamEvent::Image(image)) => {
+                    flush_text!(parts, text);
+                    if tx
+                        .send(AgentEvent::Image {
+                            url: image.url.clone(),
+                        })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    parts.push(agnt_llm::AssistantPart::Image(image));
+                }
                 Ok(StreamEvent::Finish { reason, usage }) => {
                     finish_reason = reason;
                     if let Some(u) = usage {
@@ -321,6 +1260,43 @@ async fn generation_loop(
                         }
                     }
                 }
+                Ok(StreamEvent::HostedToolBegin { id, name }) => {
+                    let display = ToolCallDisplay {
+                        title: hosted_tool_title(&name).to_string(),
+                        body: None,
+                    };
+                    if tx
+                        .send(AgentEvent::HostedToolStart { id, display })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                Ok(StreamEvent::HostedToolEnd(call)) => {
+                    let id = call.id.clone();
+                    let (display, result) = hosted_tool_displays(call);
+                    if tx
+                        .send(AgentEvent::HostedToolDone {
+                            id,
+                            display,
+                            result,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                Ok(StreamEvent::Resumable { token }) => {
+                    if tx
+                        .send(AgentEvent::ResponsePending { token })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
                 Ok(StreamEvent::Error(msg)) => {
                     let _ = tx.send(AgentEvent::Error { error: msg }).await;
                     return;
@@ -338,12 +1314,62 @@ async fn generation_loop(
 
         // Flush any trailing text
         flush_text!(parts, text);
+        let duration = started.elapsed() - response_start;
+        timings.push(EventTiming::Response {
+            start: response_start,
+            first_token: first_token.unwrap_or(duration),
+            duration,
+        });
 
         // Record the assistant message with parts in arrival order
         {
             let mut s = state.lock();
             if !parts.is_empty() {
-                s.messages.push(Message::Assistant { parts });
+                // A continuation completes the answer it continues.
+                match s.messages.last_mut() {
+                    Some(Message::Assistant { parts: answer }) if continuing => {
+                        stitch(answer, parts)
+                    }
+                    _ => s.messages.push(Message::Assistant { parts }),
+                }
+            }
+        }
+        continuing = false;
+
+        if tool_calls.is_empty() {
+            let event = match finish_reason {
+                FinishReason::ContentFilter => Some(AgentEvent::Refusal {
+                    message: refusal.filter(|message| !message.is_empty()),
+                    guidance: REFUSAL_GUIDANCE.to_string(),
+                }),
+                FinishReason::Length if continuations < max_continuations => {
+                    continuations += 1;
+                    continuing = true;
+                    Some(AgentEvent::Truncated {
+                        continuing: true,
+                        guidance: "Asking the model to continue where it stopped.".to_string(),
+                    })
+                }
+                FinishReason::Length => Some(AgentEvent::Truncated {
+                    continuing: false,
+                    guidance: if max_continuations == 0 {
+                        "Ask for the rest, or for a shorter answer.".to_string()
+                    } else {
+                        format!(
+                            "Still cut off after {max_continuations} continuations; ask for \
+                             the rest, or for a shorter answer."
+                        )
+                    },
+                }),
+                _ => None,
+            };
+            if let Some(event) = event
+                && tx.send(event).await.is_err()
+            {
+                return;
+            }
+            if continuing {
+                continue;
             }
         }
 
@@ -352,21 +1378,55 @@ async fn generation_loop(
             let _ = tx
                 .send(AgentEvent::TurnComplete {
                     usage: cumulative_usage,
+                    stats: TurnStats {
+                        model: model.model_id().to_string(),
+                        duration: started.elapsed(),
+                        tool_calls: tool_call_count,
+                        timings,
+                    },
                 })
                 .await;
             return;
         }
 
         // Execute tool calls: prepare → emit ToolCallStart → await → emit ToolCallDone
+        tool_call_count += tool_calls.len();
         for tc in &tool_calls {
+            // Filled in with the tool's audit once it succeeds.
+            let mut record = ToolCallRecord {
+                name: tc.name.clone(),
+                arguments: tc.arguments.clone(),
+                files: Vec::new(),
+                exit_code: None,
+                succeeded: false,
+                approval: state.lock().approval_policy,
+                decision: CallApproval::Auto,
+            };
+
             // Prepare the tool call (parse args, render input) while holding
             // the lock, then drop the lock before awaiting.
-            let prepared = {
+            let (prepared, schema, cacheable) = {
                 let s = state.lock();
                 let tool = s.tools.iter().find(|t| t.definition().name == tc.name);
                 match tool {
-                    Some(t) => t.prepare(&tc.arguments),
-                    None => Err(agnt_llm::Error::Other(format!("unknown tool: {}", tc.name))),
+                    Some(t) => (
+                        t.prepare(&tc.arguments),
+                        Some(t.definition().parameters.to_json_schema()),
+                        s.cache_tool_results && t.cacheable(),
+                    ),
+                    None => (
+                        Err(agnt_llm::Error::Other(format!(
+                            "unknown tool: {} (available: {})",
+                            tc.name,
+                            s.tools
+                                .iter()
+                                .map(|t| t.definition().name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))),
+                        None,
+                        false,
+                    ),
                 }
                 // lock drops here
             };
@@ -395,10 +1455,62 @@ async fn generation_loop(
                         return;
                     }
 
-                    // Execute the tool.
-                    match prepared.future.await {
+                    // Execute the tool, unless the same call already ran
+                    // this turn.
+                    let cache_key = cacheable.then(|| tool_cache_key(&tc.name, &tc.arguments));
+                    let cached = cache_key
+                        .as_ref()
+                        .and_then(|key| tool_cache.get(key))
+                        .cloned();
+                    let tool_start = started.elapsed();
+                    let executed = match cached {
+                        Some(mut result) => {
+                            result.output_display.title.push_str(" (cached)");
+                            Ok(result)
+                        }
+                        None => {
+                            if cache_key.is_none() {
+                                tool_cache.clear();
+                            }
+                            let protected = state.lock().protected.clone();
+                            let allowed = match protected {
+                                Some(protected) => {
+                                    protected.check(&tc.name, &prepared.writes.await).await
+                                }
+                                None => Ok(()),
+                            };
+                            let executed = match allowed {
+                                Ok(()) => prepared.future.await,
+                                Err(err) => Err(err),
+                            };
+                            if let (Some(key), Ok(result)) = (cache_key, &executed) {
+                                tool_cache.insert(key, result.clone());
+                            }
+                            executed
+                        }
+                    };
+                    record.decision = std::mem::take(&mut *state.lock().call_approval.lock());
+                    timings.push(EventTiming::Tool {
+                        name: tc.name.clone(),
+                        start: tool_start,
+                        duration: started.elapsed() - tool_start,
+                    });
+                    match executed {
                         Ok(result) => {
-                            let output_display = result.output_display.clone();
+                            // Secrets in tool output must not reach the UI,
+                            // the session store or the provider.
+                            let (llm_output, output_display) = {
+                                let s = state.lock();
+                                let mut output_display = result.output_display;
+                                s.redactor.redact_display(&mut output_display);
+                                (s.redactor.redact(&result.llm_output), output_display)
+                            };
+                            // Huge outputs are cut down for the history; the
+                            // UI gets the full text to keep.
+                            let (llm_output, raw_output) = match cap_tool_result(&llm_output) {
+                                Some(capped) => (capped, Some(llm_output)),
+                                None => (llm_output, None),
+                            };
                             {
                                 let mut s = state.lock();
                                 set_tool_call_display_result(
@@ -413,6 +1525,13 @@ async fn generation_loop(
                                 .send(AgentEvent::ToolCallDone {
                                     id: tc.id.clone(),
                                     display: output_display,
+                                    record: ToolCallRecord {
+                                        files: result.audit.files,
+                                        exit_code: result.audit.exit_code,
+                                        succeeded: true,
+                                        ..record
+                                    },
+                                    raw_output,
                                 })
                                 .await
                                 .is_err()
@@ -423,12 +1542,12 @@ async fn generation_loop(
                             // Add LLM-formatted result to conversation history.
                             {
                                 let mut s = state.lock();
-                                s.messages
-                                    .push(Message::tool_result(&tc.id, &result.llm_output));
+                                s.messages.push(Message::tool_result(&tc.id, &llm_output));
                             }
                         }
                         Err(e) => {
-                            let error_text = format!("tool error: {e}");
+                            let error_text =
+                                state.lock().redactor.redact(&format!("tool error: {e}"));
                             let output_display = crate::event::ToolResultDisplay {
                                 title: "error".to_string(),
                                 body: Some(crate::event::DisplayBody::Text(error_text.clone())),
@@ -446,6 +1565,8 @@ async fn generation_loop(
                                 .send(AgentEvent::ToolCallDone {
                                     id: tc.id.clone(),
                                     display: output_display,
+                                    record,
+                                    raw_output: None,
                                 })
                                 .await
                                 .is_err()
@@ -463,8 +1584,34 @@ async fn generation_loop(
                     }
                 }
                 Err(e) => {
-                    // Parsing / preparation failed.
-                    let error_text = format!("tool error: {e}");
+                    // Parsing / preparation failed. Tell the model what a
+                    // valid call looks like so it can correct itself.
+                    let mut feedback = format!("tool error: {e}");
+                    if let Some(schema) = &schema {
+                        feedback.push_str(&format!(
+                            "\n\nThe arguments must be a JSON object matching this schema:\n{schema}\n\nCall the tool again with corrected arguments."
+                        ));
+                    }
+                    let (feedback, error_text) = {
+                        let mut s = state.lock();
+                        *s.malformed_tool_calls.entry(tc.name.clone()).or_default() += 1;
+                        (
+                            s.redactor.redact(&feedback),
+                            s.redactor.redact(&format!("tool error: {e}")),
+                        )
+                    };
+
+                    // The first few in a turn are retried without bothering
+                    // the user.
+                    if silent_retries < MAX_SILENT_TOOL_CALL_RETRIES {
+                        silent_retries += 1;
+                        state
+                            .lock()
+                            .messages
+                            .push(Message::tool_result(&tc.id, &feedback));
+                        continue;
+                    }
+
                     let output_display = crate::event::ToolResultDisplay {
                         title: "error".to_string(),
                         body: Some(crate::event::DisplayBody::Text(error_text.clone())),
@@ -482,6 +1629,8 @@ async fn generation_loop(
                         .send(AgentEvent::ToolCallDone {
                             id: tc.id.clone(),
                             display: output_display,
+                            record,
+                            raw_output: None,
                         })
                         .await
                         .is_err()
@@ -491,7 +1640,7 @@ async fn generation_loop(
 
                     {
                         let mut s = state.lock();
-                        s.messages.push(Message::tool_result(&tc.id, &error_text));
+                        s.messages.push(Message::tool_result(&tc.id, &feedback));
                     }
                 }
             }
@@ -501,19 +1650,70 @@ async fn generation_loop(
     }
 }
 
+/// Append a continuation's `parts` to the `answer` it continues, joining its
+/// text onto the answer's trailing text part so the answer reads as one.
+/// Other parts (reasoning) go before that text, where a single response
+/// would have put them.
+fn stitch(answer: &mut Vec<AssistantPart>, parts: Vec<AssistantPart>) {
+    let Some(AssistantPart::Text(_)) = answer.last() else {
+        answer.extend(parts);
+        return;
+    };
+    let mut text_at = answer.len() - 1;
+    for part in parts {
+        match (part, &mut answer[text_at]) {
+            (AssistantPart::Text(continued), AssistantPart::Text(text)) => {
+                text.text.push_str(&continued.text)
+            }
+            (AssistantPart::ToolCall(call), _) => answer.push(AssistantPart::ToolCall(call)),
+            (part, _) => {
+                answer.insert(text_at, part);
+                text_at += 1;
+            }
+        }
+    }
+}
+
+/// Identifies a tool call by tool and arguments, ignoring how the arguments
+/// JSON is formatted.
+fn tool_cache_key(name: &str, arguments: &str) -> (String, String) {
+    let arguments = serde_json::from_str::<serde_json::Value>(arguments)
+        .map(|value| value.to_string())
+        .unwrap_or_else(|_| arguments.to_string());
+    (name.to_string(), arguments)
+}
+
+/// System prompt addendum for tools the model keeps calling with malformed
+/// arguments.
+fn malformed_tool_call_hints(malformed_tool_calls: &BTreeMap<String, usize>) -> String {
+    malformed_tool_calls
+        .iter()
+        .filter(|(_, count)| **count >= MALFORMED_TOOL_CALL_HINT_AFTER)
+        .map(|(name, count)| {
+            format!(
+                "\n\nCalls to `{name}` have had malformed arguments {count} times in this session. \
+                 Send its arguments as a single JSON object matching its parameter schema exactly: \
+                 include every required field, use the documented names and types, and escape \
+                 quotes and newlines inside strings."
+            )
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Default system prompt
 // ---------------------------------------------------------------------------
 
 const SYSTEM_PROMPT_TEMPLATE: &str = include_str!("../resources/SYSTEM_PROMPT.md");
 
-fn system_prompt(cwd: &Path, workspace_root: &Path) -> String {
+fn system_prompt(cwd: &Path, workspace_root: &Path, read_only: bool) -> String {
     let mut handlebars = Handlebars::new();
     handlebars.set_strict_mode(true);
 
     let data = serde_json::json!({
         "cwd": cwd.display().to_string(),
         "workspace_root": workspace_root.display().to_string(),
+        "read_only": read_only,
     });
 
     handlebars
@@ -521,10 +1721,12 @@ fn system_prompt(cwd: &Path, workspace_root: &Path) -> String {
         .unwrap_or_else(|_| SYSTEM_PROMPT_TEMPLATE.to_string())
 }
 
-fn find_workspace_root(cwd: &Path) -> PathBuf {
+/// The closest directory above `cwd` that is a git or jj (Jujutsu)
+/// repository root, or `cwd` itself outside of one.
+pub fn find_workspace_root(cwd: &Path) -> PathBuf {
     let mut current = cwd.to_path_buf();
     loop {
-        if current.join(".git").exists() {
+        if current.join(".git").exists() || current.join(".jj").is_dir() {
             return current;
         }
         if !current.pop() {
@@ -0,0 +1,129 @@
+//! Files the agent generates for the user outside the workspace, such as
+//! reports, one-off scripts or images. They are kept in a directory of the
+//! session's instead of littering the repository or `/tmp`, each under a
+//! short ID (`a1`, `a2`, ...) the transcript refers to it by.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// A file saved with [`save`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    /// Stable within the session: saving under the same name again keeps
+    /// it.
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Directory of the session's artifacts, shared between the agent and its
+/// `artifact` tool so it can follow the active session. `None` until a
+/// session is active.
+pub(crate) type ArtifactsDirSlot = Arc<RwLock<Option<PathBuf>>>;
+
+/// The artifacts in `dir`, in the order they were first saved. A missing
+/// directory has none.
+pub fn list(dir: &Path) -> io::Result<Vec<Artifact>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut artifacts = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some((id, name)) = file_name.split_once('-') else {
+            continue;
+        };
+        if number(id).is_none() || !entry.file_type()?.is_file() {
+            continue;
+        }
+        artifacts.push(Artifact {
+            id: id.to_string(),
+            name: name.to_string(),
+            path: entry.path(),
+            size: entry.metadata()?.len(),
+        });
+    }
+    artifacts.sort_by_key(|artifact| number(&artifact.id));
+    Ok(artifacts)
+}
+
+/// The artifact `id` in `dir`, if there is one.
+pub fn find(dir: &Path, id: &str) -> io::Result<Option<Artifact>> {
+    Ok(list(dir)?.into_iter().find(|artifact| artifact.id == id))
+}
+
+/// Save `content` as the artifact `name` in `dir`, replacing the one of
+/// that name if there is one.
+pub fn save(dir: &Path, name: &str, content: &[u8]) -> io::Result<Artifact> {
+    let name = name.trim();
+    let valid =
+        !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0']);
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid artifact name {name:?}: use a plain file name such as report.md"),
+        ));
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let existing = list(dir)?;
+    let id = match existing.iter().find(|artifact| artifact.name == name) {
+        Some(artifact) => artifact.id.clone(),
+        None => {
+            let next = existing
+                .iter()
+                .filter_map(|artifact| number(&artifact.id))
+                .max()
+                .unwrap_or(0)
+                + 1;
+            format!("a{next}")
+        }
+    };
+    let path = dir.join(format!("{id}-{name}"));
+    std::fs::write(&path, content)?;
+    Ok(Artifact {
+        id,
+        name: name.to_string(),
+        path,
+        size: content.len() as u64,
+    })
+}
+
+/// `3` for the ID `a3`.
+fn number(id: &str) -> Option<u32> {
+    id.strip_prefix('a')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn keeps_ids_stable_across_saves() {
+        let temp = TempDir::new("artifacts");
+        let dir = temp.path().join("artifacts");
+
+        let report = save(&dir, "report.md", b"# Report").unwrap();
+        let script = save(&dir, "fix-up.sh", b"#!/bin/sh").unwrap();
+        assert_eq!((report.id.as_str(), script.id.as_str()), ("a1", "a2"));
+
+        let updated = save(&dir, "report.md", b"# Report, v2").unwrap();
+        assert_eq!(updated.id, "a1");
+        let listed = list(&dir).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].name, "fix-up.sh");
+        assert_eq!(
+            std::fs::read_to_string(&find(&dir, "a1").unwrap().unwrap().path).unwrap(),
+            "# Report, v2"
+        );
+        assert!(save(&dir, "../escape.md", b"").is_err());
+    }
+}
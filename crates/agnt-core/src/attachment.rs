@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use agnt_llm::{AssistantPart, ImagePart, Message, TextPart, UserPart};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// Metadata key marking a user part as an attachment. The value is the
+/// attachment summary shown to the user in place of the full content.
+pub const ATTACHMENT_METADATA_KEY: &str = "agnt:attachment";
+
+/// Maximum number of bytes of a single text file attached to a message.
+pub const MAX_ATTACHMENT_BYTES: usize = 64 * 1024;
+
+/// Maximum size of a single attached image.
+pub const MAX_IMAGE_ATTACHMENT_BYTES: usize = 5 * 1024 * 1024;
+
+/// Extra context sent along with a user message (e.g. the contents of a
+/// file mentioned in the prompt).
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    /// Short name shown to the user, e.g. the mentioned path.
+    pub label: String,
+    /// What is sent to the model.
+    pub content: AttachmentContent,
+    /// Whether `content` was cut short to stay within the size limit.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum AttachmentContent {
+    /// Text inlined into the message.
+    Text(String),
+    /// Image encoded as a `data:` URL.
+    Image(String),
+    /// A notice from agnt itself rather than a file, sent as-is.
+    Note(String),
+}
+
+impl Attachment {
+    /// Read `path` (relative to `root`) as an attachment.
+    ///
+    /// Images are sent as-is; anything else must be UTF-8 text and is cut
+    /// to at most [`MAX_ATTACHMENT_BYTES`] worth of whole lines.
+    pub fn file(root: &Path, path: &str) -> std::io::Result<Self> {
+        let resolved = root.join(path);
+        if resolved.is_dir() {
+            return Err(std::io::Error::other(format!("{path} is a directory")));
+        }
+
+        let label = path.replace('\\', "/");
+        let bytes = std::fs::read(&resolved)?;
+
+        if let Some(media_type) = image_media_type(&resolved) {
+            if bytes.len() > MAX_IMAGE_ATTACHMENT_BYTES {
+                return Err(std::io::Error::other(format!(
+                    "{path} is larger than {} MiB",
+                    MAX_IMAGE_ATTACHMENT_BYTES / (1024 * 1024)
+                )));
+            }
+            let url = format!("data:{media_type};base64,{}", STANDARD.encode(&bytes));
+            return Ok(Self {
+                label,
+                content: AttachmentContent::Image(url),
+                truncated: false,
+            });
+        }
+
+        let (bytes, truncated) = if bytes.len() > MAX_ATTACHMENT_BYTES {
+            let cut = bytes[..MAX_ATTACHMENT_BYTES]
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(MAX_ATTACHMENT_BYTES, |idx| idx + 1);
+            (&bytes[..cut], true)
+        } else {
+            (&bytes[..], false)
+        };
+
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            // The byte cut may have split a multi-byte character.
+            Err(err) if truncated && err.error_len().is_none() => {
+                String::from_utf8_lossy(&bytes[..err.valid_up_to()]).into_owned()
+            }
+            Err(_) => {
+                return Err(std::io::Error::other(format!("{path} is not a text file")));
+            }
+        };
+
+        Ok(Self {
+            label,
+            content: AttachmentContent::Text(text),
+            truncated,
+        })
+    }
+
+    /// A notice for the model, shown to the user as `label`.
+    pub fn note(label: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            content: AttachmentContent::Note(text.into()),
+            truncated: false,
+        }
+    }
+
+    /// Text pasted into the composer that is too long to keep inline, sent
+    /// as a separate part and shown to the user by its length only.
+    pub fn pasted(text: &str) -> Self {
+        let lines = text.lines().count();
+        let label = if lines == 1 {
+            "pasted content (1 line)".to_string()
+        } else {
+            format!("pasted content ({lines} lines)")
+        };
+        Self::note(
+            label,
+            format!("<pasted-content>\n{text}\n</pasted-content>"),
+        )
+    }
+
+    /// The result of the most recent tool call in `messages`, so the user
+    /// can ask about output they just watched scroll by. Long output keeps
+    /// its end, where errors usually are.
+    pub fn last_tool_output(messages: &[Message]) -> Option<Self> {
+        let result = messages.iter().rev().find_map(|message| match message {
+            Message::Tool { parts } => parts.last(),
+            _ => None,
+        })?;
+        let title = messages
+            .iter()
+            .rev()
+            .filter_map(|message| match message {
+                Message::Assistant { parts } => Some(parts),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|part| match part {
+                AssistantPart::ToolCall(call) if call.id == result.tool_call_id => Some(
+                    call.display
+                        .as_ref()
+                        .map_or_else(|| call.name.clone(), |display| display.title.clone()),
+                ),
+                _ => None,
+            })
+            .unwrap_or_else(|| "tool call".to_string());
+
+        let mut output = result.content.as_str();
+        let truncated = output.len() > MAX_ATTACHMENT_BYTES;
+        if truncated {
+            let mut start = output.len() - MAX_ATTACHMENT_BYTES;
+            while !output.is_char_boundary(start) {
+                start += 1;
+            }
+            output = &output[start..];
+        }
+        Some(Self {
+            label: format!("output of {title}"),
+            content: AttachmentContent::Note(format!(
+                "The user is referring to this tool output from earlier in the \
+                 conversation.\n<tool-output call=\"{title}\"{}>\n{output}\n</tool-output>",
+                if truncated { " truncated=\"true\"" } else { "" }
+            )),
+            truncated,
+        })
+    }
+
+    /// One-line description used as the visible indicator in frontends.
+    pub fn summary(&self) -> String {
+        let detail = match &self.content {
+            AttachmentContent::Text(text) => {
+                let lines = text.lines().count();
+                if lines == 1 {
+                    "1 line".to_string()
+                } else {
+                    format!("{lines} lines")
+                }
+            }
+            AttachmentContent::Image(_) => "image".to_string(),
+            AttachmentContent::Note(_) => return self.label.clone(),
+        };
+        if self.truncated {
+            format!("{} ({detail}, truncated)", self.label)
+        } else {
+            format!("{} ({detail})", self.label)
+        }
+    }
+
+    /// Convert into message parts. The first part always carries the
+    /// summary under [`ATTACHMENT_METADATA_KEY`].
+    pub(crate) fn into_user_parts(self) -> Vec<UserPart> {
+        let metadata = HashMap::from([(ATTACHMENT_METADATA_KEY.to_string(), self.summary())]);
+        match self.content {
+            AttachmentContent::Text(content) => {
+                let truncated = if self.truncated {
+                    " truncated=\"true\""
+                } else {
+                    ""
+                };
+                let mut text = format!("<file path=\"{}\"{truncated}>\n", self.label);
+                text.push_str(&content);
+                if !content.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str("</file>");
+
+                vec![UserPart::Text(TextPart { text, metadata })]
+            }
+            AttachmentContent::Image(url) => vec![
+                UserPart::Text(TextPart {
+                    text: format!("<image path=\"{}\" />", self.label),
+                    metadata,
+                }),
+                UserPart::Image(ImagePart { url }),
+            ],
+            AttachmentContent::Note(text) => vec![UserPart::Text(TextPart { text, metadata })],
+        }
+    }
+}
+
+/// If `part` was produced from an [`Attachment`], return its summary.
+pub fn attachment_summary(part: &UserPart) -> Option<&str> {
+    match part {
+        UserPart::Text(text) => text
+            .metadata
+            .get(ATTACHMENT_METADATA_KEY)
+            .map(String::as_str),
+        UserPart::Image(_) => None,
+    }
+}
+
+fn image_media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
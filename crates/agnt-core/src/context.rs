@@ -0,0 +1,145 @@
+//! Context pinned to an agent: files, fetched pages and notes that are
+//! attached to every request until they are unpinned, rather than to a
+//! single message. An editor driving the agent can add what it has open in
+//! the same way, replacing it whenever that changes.
+
+use std::path::Path;
+
+use crate::attachment::{Attachment, MAX_ATTACHMENT_BYTES};
+
+/// One item of an agent's pinned context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextItem {
+    /// A file, relative to the agent's working directory. Re-read at the
+    /// start of every turn, so the model sees it as it is now.
+    File(String),
+    /// The body of a page, as fetched when it was pinned.
+    Url { url: String, text: String },
+    /// A note to keep in front of the model.
+    Memory(String),
+}
+
+impl ContextItem {
+    /// A fetched page, cut to at most [`MAX_ATTACHMENT_BYTES`].
+    pub fn url(url: impl Into<String>, text: &str) -> Self {
+        let mut cut = text.len().min(MAX_ATTACHMENT_BYTES);
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        Self::Url {
+            url: url.into(),
+            text: text[..cut].to_string(),
+        }
+    }
+
+    /// Short name shown to the user.
+    pub fn label(&self) -> String {
+        match self {
+            Self::File(path) => path.clone(),
+            Self::Url { url, .. } => url.clone(),
+            Self::Memory(text) => {
+                let first_line = text.lines().next().unwrap_or_default();
+                if first_line.chars().count() > 40 || text.lines().nth(1).is_some() {
+                    let short: String = first_line.chars().take(40).collect();
+                    format!("note: {short}...")
+                } else {
+                    format!("note: {first_line}")
+                }
+            }
+        }
+    }
+
+    /// The item as sent to the model. A file that can't be read is sent as
+    /// a note saying so.
+    pub(crate) fn attachment(&self, root: &Path) -> Attachment {
+        match self {
+            Self::File(path) => Attachment::file(root, path).unwrap_or_else(|err| {
+                Attachment::note(
+                    format!("{path} (unreadable)"),
+                    format!("<file path=\"{path}\" error=\"{err}\" />"),
+                )
+            }),
+            Self::Url { url, text } => Attachment::note(
+                self.label(),
+                format!("<page url=\"{url}\">\n{text}\n</page>"),
+            ),
+            Self::Memory(text) => {
+                Attachment::note(self.label(), format!("<memory>\n{text}\n</memory>"))
+            }
+        }
+    }
+}
+
+/// What the editor driving the agent has open, set with
+/// [`Agent::editor_context`](crate::Agent::editor_context). Sent with every
+/// request like pinned context, but never stored in the conversation, so
+/// each turn sees only the latest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorContext {
+    /// Paths of the open files, relative to the agent's working directory
+    /// where possible.
+    pub open_files: Vec<String>,
+    /// The file with focus, if any.
+    pub active_file: Option<String>,
+    pub selection: Option<EditorSelection>,
+}
+
+/// Text selected in the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorSelection {
+    pub path: String,
+    /// 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+impl EditorContext {
+    pub fn is_empty(&self) -> bool {
+        self.open_files.is_empty() && self.active_file.is_none() && self.selection.is_none()
+    }
+
+    /// The context as sent to the model. Open files are listed by path
+    /// only; the model reads the ones it needs. A long selection is cut to
+    /// [`MAX_ATTACHMENT_BYTES`].
+    pub(crate) fn attachment(&self) -> Attachment {
+        let mut text = String::from("<editor>\n");
+        for path in &self.open_files {
+            let active = if self.active_file.as_deref() == Some(path.as_str()) {
+                " active=\"true\""
+            } else {
+                ""
+            };
+            text.push_str(&format!("<open_file path=\"{path}\"{active} />\n"));
+        }
+        if let Some(path) = self
+            .active_file
+            .as_deref()
+            .filter(|path| !self.open_files.iter().any(|open| open == path))
+        {
+            text.push_str(&format!("<open_file path=\"{path}\" active=\"true\" />\n"));
+        }
+        if let Some(selection) = &self.selection {
+            let mut cut = selection.text.len().min(MAX_ATTACHMENT_BYTES);
+            while !selection.text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            text.push_str(&format!(
+                "<selection path=\"{}\" lines=\"{}-{}\">\n{}\n</selection>\n",
+                selection.path,
+                selection.start_line,
+                selection.end_line,
+                &selection.text[..cut]
+            ));
+        }
+        text.push_str("</editor>");
+        let label = match &self.selection {
+            Some(selection) => format!(
+                "editor: {}:{}-{}",
+                selection.path, selection.start_line, selection.end_line
+            ),
+            None => format!("editor: {} open file(s)", self.open_files.len()),
+        };
+        Attachment::note(label, text)
+    }
+}
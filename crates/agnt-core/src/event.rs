@@ -1,11 +1,27 @@
+//! The events an agent emits during a turn, and their JSON form.
+//!
+//! Events serialize as objects tagged with their `type`. The form is
+//! versioned by [`EVENT_SCHEMA_VERSION`]: fields may be added within a
+//! version, while renaming or removing one bumps it. [`json_schema`]
+//! describes the current version.
+
+use std::time::Duration;
+
 use agnt_llm::stream::Usage;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Value, json};
+
+use crate::settings::ApprovalPolicy;
+
+/// Version of the JSON form of [`AgentEvent`].
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
 
 // ---------------------------------------------------------------------------
 // Display types — tool-agnostic rendering protocol
 // ---------------------------------------------------------------------------
 
 /// How to display a tool invocation (the input side) to the user.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCallDisplay {
     /// Short summary, e.g. "Read src/main.rs", "Run `cargo build`".
     pub title: String,
@@ -14,7 +30,7 @@ pub struct ToolCallDisplay {
 }
 
 /// How to display a tool result (the output side) to the user.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResultDisplay {
     /// Short summary, e.g. "55 lines", "exit code 0".
     pub title: String,
@@ -24,7 +40,8 @@ pub struct ToolResultDisplay {
 
 /// Structured content for display. Frontends can use this to apply
 /// syntax highlighting, diff rendering, etc.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
 pub enum DisplayBody {
     /// Plain text.
     Text(String),
@@ -37,6 +54,98 @@ pub enum DisplayBody {
     Diff(String),
 }
 
+/// What a finished tool call did, for the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub name: String,
+    /// The raw arguments JSON the model sent.
+    pub arguments: String,
+    /// Paths the call read or wrote, as given to the tool.
+    pub files: Vec<String>,
+    /// Exit code of a command the call ran.
+    pub exit_code: Option<i32>,
+    pub succeeded: bool,
+    /// Approval policy of the session the call ran in.
+    pub approval: ApprovalPolicy,
+    /// Whether the user was asked about this call, and what they said.
+    #[serde(default)]
+    pub decision: CallApproval,
+}
+
+/// What happened when a tool call needed the user's approval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallApproval {
+    /// The call ran without asking.
+    #[default]
+    Auto,
+    /// The user allowed it.
+    UserApproved,
+    /// The user refused it.
+    UserRefused,
+    /// It was refused without asking, since no one could be asked.
+    Refused,
+}
+
+impl CallApproval {
+    pub const ALL: [Self; 4] = [
+        Self::Auto,
+        Self::UserApproved,
+        Self::UserRefused,
+        Self::Refused,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::UserApproved => "user_approved",
+            Self::UserRefused => "user_refused",
+            Self::Refused => "refused",
+        }
+    }
+}
+
+/// What a completed turn took, besides tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnStats {
+    /// Id of the model that generated the turn.
+    pub model: String,
+    /// Wall-clock time from the first request to the final answer, in
+    /// milliseconds in JSON.
+    #[serde(rename = "duration_ms", with = "duration_ms")]
+    pub duration: Duration,
+    /// Tool calls executed during the turn.
+    pub tool_calls: usize,
+    /// The model responses and tool calls of the turn, in order.
+    #[serde(default)]
+    pub timings: Vec<EventTiming>,
+}
+
+/// When a step of a turn started, relative to the start of the turn, and
+/// how long it took; in milliseconds in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventTiming {
+    /// A model response. `first_token` is the latency until its first
+    /// event.
+    Response {
+        #[serde(rename = "start_ms", with = "duration_ms")]
+        start: Duration,
+        #[serde(rename = "first_token_ms", with = "duration_ms")]
+        first_token: Duration,
+        #[serde(rename = "duration_ms", with = "duration_ms")]
+        duration: Duration,
+    },
+    /// A tool call the agent executed.
+    Tool {
+        name: String,
+        #[serde(rename = "start_ms", with = "duration_ms")]
+        start: Duration,
+        #[serde(rename = "duration_ms", with = "duration_ms")]
+        duration: Duration,
+    },
+}
+
 // ---------------------------------------------------------------------------
 // Agent events — the render-oriented protocol from agent to UI
 // ---------------------------------------------------------------------------
@@ -52,14 +161,27 @@ pub enum DisplayBody {
 /// (TextDelta)*                  ← final answer after tools
 /// TurnComplete
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Tools the provider runs itself add `HostedToolStart HostedToolDone`
+/// pairs between the text deltas, and generated images `Image` events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum AgentEvent {
     /// The user's message was recorded in conversation history.
-    UserMessage { content: String },
+    /// `attachments` holds a summary of each context part sent with it.
+    UserMessage {
+        content: String,
+        attachments: Vec<String>,
+    },
 
     /// A chunk of assistant text arrived.
     TextDelta { delta: String },
 
+    /// A new section of the reasoning summary starts; the reasoning deltas
+    /// that follow belong to it, so it can be shown as a block of its own.
+    /// `index` counts the sections of one reasoning item from 0.
+    ReasoningSectionStart { index: usize },
+
     /// A chunk of reasoning/thinking text arrived.
     ReasoningDelta { delta: String },
 
@@ -70,15 +192,258 @@ pub enum AgentEvent {
         display: ToolCallDisplay,
     },
 
-    /// A tool has finished executing. Contains a rendered display of the result.
+    /// A tool has finished executing. Contains a rendered display of the
+    /// result and a record of the call for auditing.
     ToolCallDone {
         id: String,
         display: ToolResultDisplay,
+        record: ToolCallRecord,
+        /// The full result, when the one placed in conversation history was
+        /// truncated to [`MAX_TOOL_RESULT_BYTES`](crate::tool::MAX_TOOL_RESULT_BYTES).
+        raw_output: Option<String>,
     },
 
+    /// The provider started a tool it runs itself, such as a web search.
+    /// It isn't executed locally and doesn't go through approval.
+    HostedToolStart {
+        id: String,
+        display: ToolCallDisplay,
+    },
+
+    /// A provider-hosted tool finished. `display` shows what it was asked,
+    /// now that it is known, and `result` what it returned.
+    HostedToolDone {
+        id: String,
+        display: ToolCallDisplay,
+        result: ToolResultDisplay,
+    },
+
+    /// The model generated an image, or a tool the provider ran produced
+    /// one, such as a plot. `url` is a `data:` URL with the image inline, or
+    /// a remote one.
+    Image { url: String },
+
+    /// The provider declined to answer, or stopped the answer under its
+    /// content policy. `message` is what the model said instead, if
+    /// anything; `guidance` suggests what to try. The turn still completes.
+    Refusal {
+        message: Option<String>,
+        guidance: String,
+    },
+
+    /// The answer reached the output token limit. With `continuing`, the
+    /// model is asked to pick up where it stopped and more of the answer
+    /// follows; otherwise it stays cut off and the turn completes.
+    Truncated { continuing: bool, guidance: String },
+
+    /// The model's response is generating in the background and outlives
+    /// this process. Save `token` with the conversation state until the turn
+    /// completes, to pick it up with [`Agent::resume`](crate::Agent::resume)
+    /// after a restart.
+    ResponsePending { token: String },
+
     /// The entire turn is complete (no more tool loops).
-    TurnComplete { usage: Usage },
+    TurnComplete { usage: Usage, stats: TurnStats },
 
     /// An error occurred during the turn.
     Error { error: String },
 }
+
+/// An event together with the version of its JSON form, as written to
+/// consumers outside the process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedEvent {
+    pub version: u32,
+    #[serde(flatten)]
+    pub event: AgentEvent,
+}
+
+impl From<AgentEvent> for VersionedEvent {
+    fn from(event: AgentEvent) -> Self {
+        Self {
+            version: EVENT_SCHEMA_VERSION,
+            event,
+        }
+    }
+}
+
+mod duration_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// JSON Schema (draft 2020-12) of a [`VersionedEvent`] in the current
+/// [`EVENT_SCHEMA_VERSION`].
+pub fn json_schema() -> Value {
+    let string = json!({ "type": "string" });
+    let optional_string = json!({ "type": ["string", "null"] });
+    let count = json!({ "type": "integer", "minimum": 0 });
+    let object = |properties: Value, required: &[&str]| {
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    };
+    let event = |name: &str, mut properties: Value, required: &[&str]| {
+        properties["type"] = json!({ "const": name });
+        let mut required = required.to_vec();
+        required.push("type");
+        object(properties, &required)
+    };
+    let display = object(
+        json!({
+            "title": string,
+            "body": { "anyOf": [{ "$ref": "#/$defs/display_body" }, { "type": "null" }] },
+        }),
+        &["title"],
+    );
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "agnt agent event",
+        "type": "object",
+        "properties": {
+            "version": { "const": EVENT_SCHEMA_VERSION },
+        },
+        "required": ["version"],
+        "oneOf": [
+            event(
+                "user_message",
+                json!({ "content": string, "attachments": { "type": "array", "items": string } }),
+                &["content", "attachments"],
+            ),
+            event("text_delta", json!({ "delta": string }), &["delta"]),
+            event("reasoning_section_start", json!({ "index": count }), &["index"]),
+            event("reasoning_delta", json!({ "delta": string }), &["delta"]),
+            event(
+                "tool_call_start",
+                json!({ "id": string, "display": { "$ref": "#/$defs/display" } }),
+                &["id", "display"],
+            ),
+            event(
+                "tool_call_done",
+                json!({
+                    "id": string,
+                    "display": { "$ref": "#/$defs/display" },
+                    "record": { "$ref": "#/$defs/tool_call_record" },
+                    "raw_output": optional_string,
+                }),
+                &["id", "display", "record"],
+            ),
+            event(
+                "hosted_tool_start",
+                json!({ "id": string, "display": { "$ref": "#/$defs/display" } }),
+                &["id", "display"],
+            ),
+            event(
+                "hosted_tool_done",
+                json!({
+                    "id": string,
+                    "display": { "$ref": "#/$defs/display" },
+                    "result": { "$ref": "#/$defs/display" },
+                }),
+                &["id", "display", "result"],
+            ),
+            event("image", json!({ "url": string }), &["url"]),
+            event(
+                "refusal",
+                json!({ "message": optional_string, "guidance": string }),
+                &["guidance"],
+            ),
+            event(
+                "truncated",
+                json!({ "continuing": { "type": "boolean" }, "guidance": string }),
+                &["continuing", "guidance"],
+            ),
+            event("response_pending", json!({ "token": string }), &["token"]),
+            event(
+                "turn_complete",
+                json!({
+                    "usage": { "$ref": "#/$defs/usage" },
+                    "stats": { "$ref": "#/$defs/turn_stats" },
+                }),
+                &["usage", "stats"],
+            ),
+            event("error", json!({ "error": string }), &["error"]),
+        ],
+        "$defs": {
+            "display": display,
+            "display_body": {
+                "oneOf": [
+                    object(json!({ "kind": { "const": "text" }, "value": string }), &["kind", "value"]),
+                    object(json!({ "kind": { "const": "diff" }, "value": string }), &["kind", "value"]),
+                    object(
+                        json!({
+                            "kind": { "const": "code" },
+                            "value": object(
+                                json!({ "language": optional_string, "content": string }),
+                                &["content"],
+                            ),
+                        }),
+                        &["kind", "value"],
+                    ),
+                ],
+            },
+            "tool_call_record": object(
+                json!({
+                    "name": string,
+                    "arguments": { "type": "string", "description": "The raw arguments JSON the model sent." },
+                    "files": { "type": "array", "items": string },
+                    "exit_code": { "type": ["integer", "null"] },
+                    "succeeded": { "type": "boolean" },
+                    "approval": { "enum": ApprovalPolicy::ALL.map(ApprovalPolicy::as_str) },
+                    "decision": { "enum": CallApproval::ALL.map(CallApproval::as_str) },
+                }),
+                &["name", "arguments", "files", "succeeded", "approval"],
+            ),
+            "usage": object(
+                json!({
+                    "input_tokens": count,
+                    "output_tokens": count,
+                    "reasoning_tokens": count,
+                    "cached_tokens": count,
+                }),
+                &["input_tokens", "output_tokens"],
+            ),
+            "turn_stats": object(
+                json!({
+                    "model": string,
+                    "duration_ms": count,
+                    "tool_calls": count,
+                    "timings": { "type": "array", "items": { "$ref": "#/$defs/event_timing" } },
+                }),
+                &["model", "duration_ms", "tool_calls"],
+            ),
+            "event_timing": {
+                "oneOf": [
+                    object(
+                        json!({
+                            "kind": { "const": "response" },
+                            "start_ms": count,
+                            "first_token_ms": count,
+                            "duration_ms": count,
+                        }),
+                        &["kind", "start_ms", "first_token_ms", "duration_ms"],
+                    ),
+                    object(
+                        json!({
+                            "kind": { "const": "tool" },
+                            "name": string,
+                            "start_ms": count,
+                            "duration_ms": count,
+                        }),
+                        &["kind", "name", "start_ms", "duration_ms"],
+                    ),
+                ],
+            },
+        },
+    })
+}
@@ -0,0 +1,119 @@
+use regex::Regex;
+
+use crate::attachment::{Attachment, AttachmentContent};
+
+/// Which way text passes through a [`ContentFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterDirection {
+    /// A prompt the user is about to send to the model.
+    Input,
+    /// Text the model answered with.
+    Output,
+}
+
+/// What a [`ContentFilter`] decided about a piece of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterVerdict {
+    Pass,
+    /// Use this text instead.
+    Rewrite(String),
+    /// Drop the text and end the turn.
+    Block {
+        reason: String,
+    },
+}
+
+/// Guardrail applied to every prompt before it reaches the model and every
+/// completion before it is shown or stored.
+///
+/// Register with [`Agent::content_filter`](crate::Agent::content_filter).
+/// Filters run in registration order, each seeing the previous one's
+/// rewrite.
+pub trait ContentFilter: Send + Sync + 'static {
+    fn check(&self, direction: FilterDirection, text: &str) -> FilterVerdict;
+}
+
+/// Built-in filter: blocks text matching a denylist pattern and replaces
+/// matches of scrub patterns (e.g. PII) with `[FILTERED]`, both ways.
+#[derive(Debug, Clone)]
+pub struct PatternFilter {
+    blocked: Vec<Regex>,
+    scrubbed: Vec<Regex>,
+}
+
+impl PatternFilter {
+    pub fn new(blocked: &[String], scrubbed: &[String]) -> Result<Self, regex::Error> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            blocked: compile(blocked)?,
+            scrubbed: compile(scrubbed)?,
+        })
+    }
+}
+
+impl ContentFilter for PatternFilter {
+    fn check(&self, direction: FilterDirection, text: &str) -> FilterVerdict {
+        if let Some(pattern) = self.blocked.iter().find(|pattern| pattern.is_match(text)) {
+            let what = match direction {
+                FilterDirection::Input => "prompt",
+                FilterDirection::Output => "response",
+            };
+            return FilterVerdict::Block {
+                reason: format!("{what} matches blocked pattern `{pattern}`"),
+            };
+        }
+
+        let mut scrubbed = text.to_string();
+        for pattern in &self.scrubbed {
+            scrubbed = pattern.replace_all(&scrubbed, "[FILTERED]").into_owned();
+        }
+        if scrubbed == text {
+            FilterVerdict::Pass
+        } else {
+            FilterVerdict::Rewrite(scrubbed)
+        }
+    }
+}
+
+/// Run `text` through `filters` in order. `Err` holds the reason of the
+/// filter that blocked it.
+pub(crate) fn apply_filters(
+    filters: &[std::sync::Arc<dyn ContentFilter>],
+    direction: FilterDirection,
+    mut text: String,
+) -> Result<String, String> {
+    for filter in filters {
+        match filter.check(direction, &text) {
+            FilterVerdict::Pass => {}
+            FilterVerdict::Rewrite(rewritten) => text = rewritten,
+            FilterVerdict::Block { reason } => return Err(reason),
+        }
+    }
+    Ok(text)
+}
+
+/// Run the text of `attachment` through `filters` like a prompt. Images pass
+/// unchanged. `Err` holds the reason of the filter that blocked it.
+pub(crate) fn filter_attachment(
+    filters: &[std::sync::Arc<dyn ContentFilter>],
+    attachment: Attachment,
+) -> Result<Attachment, String> {
+    let content = match attachment.content {
+        AttachmentContent::Text(text) => {
+            AttachmentContent::Text(apply_filters(filters, FilterDirection::Input, text)?)
+        }
+        AttachmentContent::Note(text) => {
+            AttachmentContent::Note(apply_filters(filters, FilterDirection::Input, text)?)
+        }
+        image @ AttachmentContent::Image(_) => image,
+    };
+    Ok(Attachment {
+        content,
+        ..attachment
+    })
+}
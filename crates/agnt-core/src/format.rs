@@ -0,0 +1,141 @@
+//! The project's formatters, run on each file the `edit` tool writes. The
+//! model gets the formatted file's diff back, so the anchors it uses next
+//! match what's on disk.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use tokio::process::Command;
+
+/// How long a formatter may take before the file is left as written.
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A formatter command for the files a gitignore-style pattern matches,
+/// written `pattern: command`, e.g. `*.rs: rustfmt --edition 2024`. The
+/// file's path is appended to the command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatter {
+    pub pattern: String,
+    pub command: String,
+}
+
+impl FromStr for Formatter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, command) = s
+            .split_once(':')
+            .map(|(pattern, command)| (pattern.trim(), command.trim()))
+            .filter(|(pattern, command)| !pattern.is_empty() && !command.is_empty())
+            .ok_or_else(|| format!("expected `pattern: command`, got `{}`", s.trim()))?;
+        GitignoreBuilder::new("")
+            .add_line(None, pattern)
+            .map_err(|err| format!("invalid pattern `{pattern}`: {err}"))?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            command: command.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pattern, self.command)
+    }
+}
+
+/// Compiled [`AgentSettings::formatters`](crate::AgentSettings::formatters),
+/// patterns relative to the workspace root.
+#[derive(Clone, Default)]
+pub(crate) struct Formatters {
+    root: PathBuf,
+    rules: Vec<(Gitignore, String)>,
+}
+
+impl Formatters {
+    pub(crate) fn new(root: &Path, formatters: &[Formatter]) -> Self {
+        let rules = formatters
+            .iter()
+            .filter_map(|formatter| {
+                let mut builder = GitignoreBuilder::new(root);
+                builder.add_line(None, &formatter.pattern).ok()?;
+                Some((builder.build().ok()?, formatter.command.clone()))
+            })
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// Run the first formatter matching `path` on it, returning its command
+    /// and whether it succeeded. `None` when no formatter matches.
+    pub(crate) async fn run(&self, path: &Path) -> Option<(String, Result<(), String>)> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let command = self.rules.iter().find_map(|(matcher, command)| {
+            matcher
+                .matched_path_or_any_parents(relative, false)
+                .is_ignore()
+                .then_some(command)
+        })?;
+
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!("{command} \"$1\""))
+            .arg("agnt-format")
+            .arg(path)
+            .current_dir(&self.root)
+            .kill_on_drop(true)
+            .output();
+        let result = match tokio::time::timeout(FORMAT_TIMEOUT, output).await {
+            Err(_) => Err(format!("took longer than {}s", FORMAT_TIMEOUT.as_secs())),
+            Ok(Err(err)) => Err(format!("failed to run: {err}")),
+            Ok(Ok(output)) if !output.status.success() => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(format!("{}: {}", output.status, stderr.trim()))
+            }
+            Ok(Ok(_)) => Ok(()),
+        };
+        Some((command.clone(), result))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, runtime};
+
+    #[test]
+    fn runs_the_first_matching_formatter() {
+        assert!("*.rs".parse::<Formatter>().is_err());
+        let temp = TempDir::new("format");
+        let dir = temp.path();
+        std::fs::write(dir.join("notes.txt"), "draft\n").unwrap();
+        let formatters = Formatters::new(
+            dir,
+            &[
+                "*.rs: false".parse().unwrap(),
+                "*.txt: sed -i s/draft/final/".parse().unwrap(),
+            ],
+        );
+        let runtime = runtime();
+
+        let (command, result) = runtime
+            .block_on(formatters.run(&dir.join("notes.txt")))
+            .unwrap();
+        assert_eq!(command, "sed -i s/draft/final/");
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("notes.txt")).unwrap(),
+            "final\n"
+        );
+        assert!(
+            runtime
+                .block_on(formatters.run(&dir.join("a.md")))
+                .is_none()
+        );
+    }
+}
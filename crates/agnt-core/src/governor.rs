@@ -0,0 +1,197 @@
+//! Limits on what the `bash` and `edit` tools may consume, so a model stuck
+//! in a loop can't exhaust the machine during an unattended run.
+//!
+//! CPU time is measured from the resource usage of each command's shell and
+//! the processes it waited for, and enforced with `ulimit -t`, which bounds
+//! each process a command starts on its own; a command is refused once the
+//! turn's total is used up. Disk writes are counted for `edit` only.
+
+use std::io::{self, Read};
+use std::pin::pin;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// Limits for one agent. `None` leaves a limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Commands running at the same time, counted across every agent in
+    /// the process.
+    pub max_concurrent_commands: Option<usize>,
+    /// CPU time of all commands run in a turn, children included.
+    pub max_command_cpu: Option<Duration>,
+    /// Bytes the `edit` tool writes in a turn.
+    pub max_write_bytes: Option<u64>,
+}
+
+/// Commands running in this process.
+static RUNNING_COMMANDS: AtomicUsize = AtomicUsize::new(0);
+static COMMAND_FINISHED: Notify = Notify::const_new();
+
+/// Enforces an agent's [`ResourceLimits`], shared by its tools.
+#[derive(Clone, Default)]
+pub(crate) struct ResourceGovernor {
+    limits: ResourceLimits,
+    used: Arc<Mutex<TurnUsage>>,
+}
+
+#[derive(Default)]
+struct TurnUsage {
+    command_cpu: Duration,
+    written_bytes: u64,
+}
+
+/// A running command's place in the concurrency limit, given back on drop.
+pub(crate) struct CommandSlot;
+
+impl Drop for CommandSlot {
+    fn drop(&mut self) {
+        RUNNING_COMMANDS.fetch_sub(1, Ordering::AcqRel);
+        COMMAND_FINISHED.notify_waiters();
+    }
+}
+
+impl ResourceGovernor {
+    pub(crate) fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            used: Arc::default(),
+        }
+    }
+
+    /// Forget what the previous turn used.
+    pub(crate) fn start_turn(&self) {
+        *self.used.lock() = TurnUsage::default();
+    }
+
+    /// Wait until fewer commands than the limit are running.
+    pub(crate) async fn command_slot(&self) -> CommandSlot {
+        let limit = self.limits.max_concurrent_commands.unwrap_or(usize::MAX);
+        loop {
+            // Registered before checking, so a slot freed in between still
+            // wakes this waiter.
+            let mut finished = pin!(COMMAND_FINISHED.notified());
+            finished.as_mut().enable();
+            let running = RUNNING_COMMANDS.load(Ordering::Acquire);
+            if running < limit.max(1)
+                && RUNNING_COMMANDS
+                    .compare_exchange(running, running + 1, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            {
+                return CommandSlot;
+            }
+            finished.await;
+        }
+    }
+
+    /// `ulimit` line to run before a command, limiting it to the CPU seconds
+    /// left this turn. Fails once the CPU time is used up.
+    pub(crate) fn command_ulimits(&self) -> Result<String, agnt_llm::Error> {
+        let used = self.used.lock();
+        let mut ulimits = String::new();
+        if let Some(max) = self.limits.max_command_cpu {
+            let left = max.saturating_sub(used.command_cpu);
+            if left.is_zero() {
+                return Err(agnt_llm::Error::Other(format!(
+                    "this turn's commands used their {}s of CPU time; no more commands can run until the next turn",
+                    max.as_secs()
+                )));
+            }
+            ulimits.push_str(&format!("ulimit -t {}\n", left.as_secs().max(1)));
+        }
+        Ok(ulimits)
+    }
+
+    pub(crate) fn add_command_cpu(&self, cpu: Duration) {
+        self.used.lock().command_cpu += cpu;
+    }
+
+    /// Count `bytes` about to be written, failing if that goes past the
+    /// limit.
+    pub(crate) fn reserve_write(&self, bytes: u64) -> Result<(), agnt_llm::Error> {
+        let mut used = self.used.lock();
+        if let Some(max) = self.limits.max_write_bytes
+            && used.written_bytes + bytes > max
+        {
+            return Err(agnt_llm::Error::Other(format!(
+                "writing {bytes} bytes would go past this turn's limit of {max} bytes written ({} used)",
+                used.written_bytes
+            )));
+        }
+        used.written_bytes += bytes;
+        Ok(())
+    }
+}
+
+/// Run `command` to completion like [`Command::output`], also returning the
+/// CPU time (user and system) used by it and the processes it waited for.
+/// Blocks, so call it off the async runtime.
+pub(crate) fn output_with_cpu(mut command: Command) -> io::Result<(Output, Duration)> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let (Some(mut stdout_pipe), Some(mut stderr_pipe)) = (child.stdout.take(), child.stderr.take())
+    else {
+        return Err(io::Error::other("command output is not piped"));
+    };
+    let stderr_reader = std::thread::spawn(move || {
+        let mut stderr = Vec::new();
+        stderr_pipe.read_to_end(&mut stderr).map(|_| stderr)
+    });
+    let mut stdout = Vec::new();
+    stdout_pipe.read_to_end(&mut stdout)?;
+    let stderr = stderr_reader
+        .join()
+        .map_err(|_| io::Error::other("reading stderr panicked"))??;
+
+    let (status, cpu) = wait_with_cpu(&mut child)?;
+    Ok((
+        Output {
+            status,
+            stdout,
+            stderr,
+        },
+        cpu,
+    ))
+}
+
+#[cfg(unix)]
+fn wait_with_cpu(child: &mut std::process::Child) -> io::Result<(ExitStatus, Duration)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    // SAFETY: `rusage` is plain data, for which all zeroes is valid.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: `pid` is a child of this process that nothing else waits
+        // for, and `status` and `usage` are valid for writes.
+        if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } == pid {
+            break;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+    let time = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec.max(0) as u64)
+            + Duration::from_micros(time.tv_usec.max(0) as u64)
+    };
+    Ok((
+        ExitStatus::from_raw(status),
+        time(usage.ru_utime) + time(usage.ru_stime),
+    ))
+}
+
+/// Without `wait4` there's nothing to measure; the command counts as free.
+#[cfg(not(unix))]
+fn wait_with_cpu(child: &mut std::process::Child) -> io::Result<(ExitStatus, Duration)> {
+    Ok((child.wait()?, Duration::ZERO))
+}
@@ -1,9 +1,44 @@
 pub mod agent;
+pub mod artifacts;
+pub mod attachment;
+pub mod context;
 pub mod event;
+pub mod filter;
+pub mod format;
+pub mod governor;
+pub mod protect;
+pub mod redact;
+pub mod review;
+pub mod settings;
+pub mod summary;
+pub mod template;
+#[cfg(test)]
+mod test_support;
 pub mod tool;
 pub mod tools;
 
-pub use agent::{Agent, AgentStream, ConversationState};
-pub use event::{AgentEvent, DisplayBody, ToolCallDisplay, ToolResultDisplay};
-pub use tool::{Tool, ToolOutput};
-pub use tools::{BashTool, EditTool, ReadTool, SkillTool};
+pub use agent::{Agent, AgentStream, ConversationState, RequestInspection, RequestSections};
+pub use artifacts::Artifact;
+pub use attachment::{Attachment, AttachmentContent, attachment_summary};
+pub use context::{ContextItem, EditorContext, EditorSelection};
+pub use event::{
+    AgentEvent, CallApproval, DisplayBody, EVENT_SCHEMA_VERSION, EventTiming, ToolCallDisplay,
+    ToolCallRecord, ToolResultDisplay, TurnStats, VersionedEvent,
+};
+pub use filter::{ContentFilter, FilterDirection, FilterVerdict, PatternFilter};
+pub use format::Formatter;
+pub use governor::ResourceLimits;
+pub use protect::{ProtectedWrite, WriteApprover};
+pub use redact::Redactor;
+pub use review::{EditReview, EditReviewer, ProposedEdit};
+pub use settings::{AGNTIGNORE_FILENAME, AgentSettings, ApprovalPolicy, is_agntignored};
+pub use summary::ConversationSummary;
+pub use template::{PromptTemplate, discover_templates};
+pub use tool::{MAX_TOOL_RESULT_BYTES, Tool, ToolAudit, ToolOutput};
+#[cfg(feature = "symbols")]
+pub use tools::RenameTool;
+pub use tools::{
+    ArtifactTool, BashTool, CommandWarning, EditTool, PluginTool, ReadTool, SkillTool,
+};
+#[cfg(feature = "wasm")]
+pub use tools::{WasmTool, WasmToolGrant};
@@ -0,0 +1,255 @@
+//! Write-protected paths: files such as `Cargo.lock` or `**/migrations/**`
+//! that tools only write once the user approves it. The agent checks the
+//! files each call writes ([`Tool::writes`](crate::Tool::writes)) here
+//! before running it, so no tool can skip the check or disagree on what is
+//! protected.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use parking_lot::{Mutex, RwLock};
+
+use crate::event::CallApproval;
+use crate::settings::normalize;
+
+/// A tool call about to write protected paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectedWrite {
+    /// Name of the tool making the call.
+    pub tool: String,
+    /// The protected paths it writes, relative to the workspace root.
+    pub paths: Vec<String>,
+}
+
+/// Asks the user whether a tool may write protected paths.
+///
+/// Register with [`Agent::write_approver`](crate::Agent::write_approver).
+/// Without one, writes to protected paths are refused.
+pub trait WriteApprover: Send + Sync + 'static {
+    fn approve(&self, write: ProtectedWrite) -> Pin<Box<dyn Future<Output = bool> + Send>>;
+}
+
+/// Approver shared between the agent and its tools, so it can be set after
+/// the tools are registered.
+pub(crate) type WriteApproverSlot = Arc<RwLock<Option<Arc<dyn WriteApprover>>>>;
+
+/// What the user decided about the running tool call; the agent takes it
+/// for the call's [`ToolCallRecord`](crate::ToolCallRecord) once it's done.
+pub(crate) type CallApprovalSlot = Arc<Mutex<CallApproval>>;
+
+/// Compiled [`AgentSettings::protected_paths`](crate::AgentSettings::protected_paths),
+/// shared by the tools that write files.
+#[derive(Clone)]
+pub(crate) struct ProtectedPaths {
+    root: PathBuf,
+    matcher: Gitignore,
+    approver: WriteApproverSlot,
+    decision: CallApprovalSlot,
+}
+
+impl ProtectedPaths {
+    /// Compile `patterns` relative to `root`. Invalid patterns are skipped.
+    /// What `approver` decides is noted in `decision`.
+    pub(crate) fn new(
+        root: &Path,
+        patterns: &[String],
+        approver: WriteApproverSlot,
+        decision: CallApprovalSlot,
+    ) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        Self {
+            root: normalize(root),
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            approver,
+            decision,
+        }
+    }
+
+    /// `path` (absolute) relative to the workspace root, if it or one of its
+    /// parent directories is protected.
+    fn protected(&self, path: &Path) -> Option<String> {
+        let path = normalize(path);
+        let relative = path.strip_prefix(&self.root).ok()?;
+        (!relative.as_os_str().is_empty()
+            && self
+                .matcher
+                .matched_path_or_any_parents(relative, path.is_dir())
+                .is_ignore())
+        .then(|| relative.display().to_string())
+    }
+
+    /// Let `tool` write `paths` (absolute), asking the user first when any
+    /// of them is protected. Fails when the user refuses or can't be asked.
+    pub(crate) async fn check(&self, tool: &str, paths: &[PathBuf]) -> Result<(), agnt_llm::Error> {
+        let mut protected = Vec::new();
+        for path in paths.iter().filter_map(|path| self.protected(path)) {
+            if !protected.contains(&path) {
+                protected.push(path);
+            }
+        }
+        if protected.is_empty() {
+            return Ok(());
+        }
+
+        let listed = protected
+            .iter()
+            .map(|path| format!("`{path}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let verb = if protected.len() == 1 { "is" } else { "are" };
+        let approver = self.approver.read().clone();
+        let Some(approver) = approver else {
+            *self.decision.lock() = CallApproval::Refused;
+            return Err(agnt_llm::Error::Other(format!(
+                "{listed} {verb} write-protected by the project's settings; ask the user to make this change"
+            )));
+        };
+        let approved = approver
+            .approve(ProtectedWrite {
+                tool: tool.to_string(),
+                paths: protected,
+            })
+            .await;
+        *self.decision.lock() = if approved {
+            CallApproval::UserApproved
+        } else {
+            CallApproval::UserRefused
+        };
+        if approved {
+            Ok(())
+        } else {
+            Err(agnt_llm::Error::Other(format!(
+                "{listed} {verb} write-protected and the user did not allow this change; leave it alone or ask the user about it"
+            )))
+        }
+    }
+}
+
+/// The files a `bash` command evidently writes: redirect targets and the
+/// file arguments of `rm`, `mv`, `cp`, `tee`, `sed -i` and the like. Only
+/// what can be read off the command line; a script it runs may write
+/// anything.
+pub(crate) fn written_paths(command: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for segment in command.split(['\n', ';', '|', '&', '(', ')']) {
+        let mut words = Vec::new();
+        let mut redirect = false;
+        for word in segment.split_whitespace() {
+            let word = word.trim_matches(['"', '\'']);
+            if redirect {
+                paths.push(word.to_string());
+                redirect = false;
+                continue;
+            }
+            let unnumbered = word.trim_start_matches(|c: char| c.is_ascii_digit());
+            if let Some(target) = unnumbered.strip_prefix('>') {
+                let target = target.trim_start_matches(['>', '|']);
+                match target {
+                    "" => redirect = true,
+                    // Duplicating a file descriptor writes no file.
+                    _ if target.starts_with('&') => {}
+                    _ => paths.push(target.to_string()),
+                }
+                continue;
+            }
+            words.push(word);
+        }
+
+        let words = words
+            .into_iter()
+            .skip_while(|word| {
+                matches!(*word, "sudo" | "time" | "env" | "exec" | "nice" | "command")
+                    || word.contains('=') && !word.starts_with('-')
+            })
+            .collect::<Vec<_>>();
+        let Some((program, args)) = words.split_first() else {
+            continue;
+        };
+        let mut operands = args
+            .iter()
+            .filter(|arg| !arg.starts_with('-'))
+            .map(|arg| arg.to_string());
+        match *program {
+            "rm" | "rmdir" | "unlink" | "shred" | "touch" | "truncate" | "tee" | "mv" | "mkdir" => {
+                paths.extend(operands)
+            }
+            "cp" | "ln" | "install" | "rsync" => paths.extend(operands.next_back()),
+            "sed" | "perl" if args.iter().any(|arg| arg.starts_with("-i")) => {
+                // The first operand is the script, unless given with `-e`.
+                let scripted = args.contains(&"-e");
+                paths.extend(operands.skip(usize::from(!scripted)));
+            }
+            _ => {}
+        }
+    }
+    paths.retain(|path| !path.is_empty() && !path.starts_with("/dev/"));
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Refuse;
+
+    impl WriteApprover for Refuse {
+        fn approve(&self, _write: ProtectedWrite) -> Pin<Box<dyn Future<Output = bool> + Send>> {
+            Box::pin(async { false })
+        }
+    }
+
+    #[test]
+    fn finds_the_files_a_command_writes() {
+        assert_eq!(
+            written_paths("cargo update > Cargo.lock 2>&1 && rm -rf db/migrations/001.sql"),
+            ["Cargo.lock", "db/migrations/001.sql"]
+        );
+        assert_eq!(
+            written_paths("sed -i 's/a/b/' src/lib.rs | tee -a log.txt; cp a.txt b.txt"),
+            ["src/lib.rs", "log.txt", "b.txt"]
+        );
+        assert!(written_paths("cat Cargo.lock 2>/dev/null | grep serde").is_empty());
+    }
+
+    #[test]
+    fn refuses_protected_writes_without_approval() {
+        let root = PathBuf::from("/workspace");
+        let approver = WriteApproverSlot::default();
+        let decision = CallApprovalSlot::default();
+        let protected = ProtectedPaths::new(
+            &root,
+            &["**/migrations/**".to_string(), "/Cargo.lock".to_string()],
+            approver.clone(),
+            decision.clone(),
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let check = |paths: &[&str]| {
+            let paths = paths.iter().map(|path| root.join(path)).collect::<Vec<_>>();
+            runtime.block_on(protected.check("edit", &paths))
+        };
+
+        assert!(check(&["src/lib.rs", "crates/a/Cargo.lock"]).is_ok());
+        assert_eq!(*decision.lock(), CallApproval::Auto);
+        let err = check(&["src/../Cargo.lock", "db/migrations/001.sql"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`Cargo.lock`, `db/migrations/001.sql` are write-protected")
+        );
+        *approver.write() = Some(Arc::new(Refuse));
+        assert!(
+            check(&["Cargo.lock"])
+                .unwrap_err()
+                .to_string()
+                .contains("did not allow")
+        );
+        assert_eq!(*decision.lock(), CallApproval::UserRefused);
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::event::{DisplayBody, ToolResultDisplay};
+
+struct Rule {
+    kind: &'static str,
+    /// Matches a secret. When the pattern has a capture group, only the
+    /// group is replaced (e.g. the value of a `KEY=value` line).
+    pattern: Regex,
+}
+
+/// Secret patterns, most specific first so a key is reported by its
+/// provider rather than as a generic assignment.
+static RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    [
+        (
+            "private_key",
+            r"-----BEGIN [A-Z0-9 ]*PRIVATE KEY-----[\s\S]*?(?:-----END [A-Z0-9 ]*PRIVATE KEY-----|$)",
+        ),
+        ("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+        ("github_token", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})"),
+        ("anthropic_key", r"\bsk-ant-[A-Za-z0-9_-]{20,}"),
+        ("openai_key", r"\bsk-(?:proj-|svcacct-|admin-)?[A-Za-z0-9_-]{20,}"),
+        ("stripe_key", r"\b[rs]k_(?:live|test)_[0-9A-Za-z]{16,}"),
+        ("slack_token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+        ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}"),
+        (
+            "jwt",
+            r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+        ),
+        // `.env`-style lines, optionally behind a `read` tool line prefix.
+        (
+            "secret_assignment",
+            r#"(?m)^(?:\d+:[0-9a-f]+\|)?\s*(?:export\s+)?[A-Z0-9_]*(?:SECRET|TOKEN|PASSWORD|PASSWD|API_KEY|APIKEY|ACCESS_KEY|PRIVATE_KEY|CREDENTIALS?)[A-Z0-9_]*\s*[=:]\s*["']?([^\s"'#\[][^\s"'#]{7,})"#,
+        ),
+    ]
+    .into_iter()
+    .map(|(kind, pattern)| Rule {
+        kind,
+        pattern: Regex::new(pattern).expect("secret patterns are valid"),
+    })
+    .collect()
+});
+
+/// Replaces secrets in tool output with `[REDACTED:<type>]` before it is
+/// shown, stored or sent to the model.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    /// Exact values to leave alone, or secret types (e.g. `jwt`) to not scan
+    /// for at all.
+    allowlist: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(allowlist: Vec<String>) -> Self {
+        Self { allowlist }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for rule in RULES.iter() {
+            if self.is_allowed(rule.kind) || !rule.pattern.is_match(&text) {
+                continue;
+            }
+
+            let mut redacted = String::with_capacity(text.len());
+            let mut last = 0;
+            for captures in rule.pattern.captures_iter(&text) {
+                let Some(secret) = captures.get(1).or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                if self.is_allowed(secret.as_str()) {
+                    continue;
+                }
+                redacted.push_str(&text[last..secret.start()]);
+                redacted.push_str(&format!("[REDACTED:{}]", rule.kind));
+                last = secret.end();
+            }
+            redacted.push_str(&text[last..]);
+            text = redacted;
+        }
+        text
+    }
+
+    pub(crate) fn redact_display(&self, display: &mut ToolResultDisplay) {
+        display.title = self.redact(&display.title);
+        match &mut display.body {
+            Some(DisplayBody::Text(text) | DisplayBody::Diff(text)) => *text = self.redact(text),
+            Some(DisplayBody::Code { content, .. }) => *content = self.redact(content),
+            None => {}
+        }
+    }
+
+    fn is_allowed(&self, value: &str) -> bool {
+        self.allowlist.iter().any(|entry| entry == value)
+    }
+}
@@ -0,0 +1,72 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use crate::event::CallApproval;
+use crate::protect::CallApprovalSlot;
+
+/// A change the `edit` tool is about to write.
+#[derive(Debug, Clone)]
+pub struct ProposedEdit {
+    /// Path the edit was requested for, relative to the working directory.
+    pub path: String,
+    /// Path the file ends up at; differs from `path` for moves.
+    pub new_path: String,
+    /// Current contents, `None` for a new file.
+    pub original: Option<String>,
+    /// Contents after the edit, `None` when the file is deleted.
+    pub proposed: Option<String>,
+    /// Unified diff from `original` to `proposed`.
+    pub diff: String,
+}
+
+/// What the reviewer decided about a [`ProposedEdit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditReview {
+    Accept,
+    /// Leave the file alone; `reason` is reported back to the model.
+    Reject {
+        reason: String,
+    },
+}
+
+/// Shows proposed edits to the user, e.g. in a connected editor's diff
+/// view, before the `edit` tool writes them.
+///
+/// Register with [`Agent::edit_reviewer`](crate::Agent::edit_reviewer).
+/// Without one, edits are written right away.
+pub trait EditReviewer: Send + Sync + 'static {
+    fn review(&self, edit: ProposedEdit) -> Pin<Box<dyn Future<Output = EditReview> + Send>>;
+}
+
+/// Reviewer shared between the agent and its `edit` tool, so it can be set
+/// after the tool is registered.
+pub(crate) type EditReviewerSlot = Arc<RwLock<Option<Arc<dyn EditReviewer>>>>;
+
+/// Notes what the user decided about the edits of the running tool call,
+/// so a rejected edit shows in its [`ToolCallRecord`](crate::ToolCallRecord).
+pub(crate) struct RecordingReviewer {
+    pub(crate) reviewer: Arc<dyn EditReviewer>,
+    pub(crate) decision: CallApprovalSlot,
+}
+
+impl EditReviewer for RecordingReviewer {
+    fn review(&self, edit: ProposedEdit) -> Pin<Box<dyn Future<Output = EditReview> + Send>> {
+        let review = self.reviewer.review(edit);
+        let decision = self.decision.clone();
+        Box::pin(async move {
+            let review = review.await;
+            let mut decision = decision.lock();
+            match review {
+                EditReview::Accept if *decision == CallApproval::Auto => {
+                    *decision = CallApproval::UserApproved;
+                }
+                EditReview::Accept => {}
+                EditReview::Reject { .. } => *decision = CallApproval::UserRefused,
+            }
+            review
+        })
+    }
+}
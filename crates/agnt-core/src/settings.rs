@@ -0,0 +1,203 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::format::Formatter;
+use crate::governor::ResourceLimits;
+
+/// Per-workspace knobs applied when building an agent with
+/// [`Agent::with_settings`](crate::Agent::with_settings).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentSettings {
+    pub approval_policy: ApprovalPolicy,
+    /// Gitignore-style patterns, relative to the workspace root, for files
+    /// the `read` and `edit` tools must not touch.
+    pub ignored_paths: Vec<String>,
+    /// Gitignore-style patterns, relative to the workspace root, for files
+    /// the `edit`, `rename` and `bash` tools only write with the user's
+    /// approval.
+    pub protected_paths: Vec<String>,
+    /// Values, or secret types such as `jwt`, that are not redacted from
+    /// tool output.
+    pub redaction_allowlist: Vec<String>,
+    /// Regexes that block a prompt or completion they match.
+    pub blocked_patterns: Vec<String>,
+    /// Regexes whose matches in prompts and completions are replaced with
+    /// `[FILTERED]`.
+    pub scrubbed_patterns: Vec<String>,
+    /// Limits on the CPU time, disk writes and concurrency of the `bash`
+    /// and `edit` tools.
+    pub resource_limits: ResourceLimits,
+    /// Run `bash` commands as given, without first flagging the ones that
+    /// can't work in the workspace's kind of project.
+    pub skip_command_checks: bool,
+    /// Formatters run on the files `edit` writes, the first matching one
+    /// per file.
+    pub formatters: Vec<Formatter>,
+}
+
+/// Which actions the agent may take without the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApprovalPolicy {
+    /// All tools are available.
+    #[default]
+    Auto,
+    /// Only tools that cannot modify the workspace are available.
+    ReadOnly,
+}
+
+impl ApprovalPolicy {
+    pub const ALL: [Self; 2] = [Self::Auto, Self::ReadOnly];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::ReadOnly => "read-only",
+        }
+    }
+}
+
+impl fmt::Display for ApprovalPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApprovalPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.as_str() == s.trim())
+            .ok_or_else(|| {
+                let known = Self::ALL.map(Self::as_str).join(", ");
+                format!(
+                    "unknown approval policy `{}` (expected one of: {known})",
+                    s.trim()
+                )
+            })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Ignored paths
+// ---------------------------------------------------------------------------
+
+/// Gitignore-syntax file hiding paths from the agent's file tools and the
+/// file typeahead. Applies to its own directory and everything below it.
+pub const AGNTIGNORE_FILENAME: &str = ".agntignore";
+
+/// Compiled [`AgentSettings::ignored_paths`] plus the workspace's
+/// [`AGNTIGNORE_FILENAME`] files, shared by the file tools.
+#[derive(Clone)]
+pub(crate) struct IgnoredPaths {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl IgnoredPaths {
+    /// Compile `patterns` relative to `root`. Invalid patterns are skipped.
+    pub(crate) fn new(root: &Path, patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        Self {
+            root: normalize(root),
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    /// Whether `path` (absolute) or one of its parent directories matches an
+    /// ignored pattern. Paths outside the workspace root never match.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+        if !self.matcher.is_empty()
+            && self
+                .matcher
+                .matched_path_or_any_parents(relative, path.is_dir())
+                .is_ignore()
+        {
+            return true;
+        }
+        is_agntignored(&self.root, &path)
+    }
+
+    /// Error returned by the file tools when `path` is ignored.
+    pub(crate) fn check(&self, path: &Path, display: &str) -> Result<(), agnt_llm::Error> {
+        if self.is_ignored(path) {
+            return Err(agnt_llm::Error::Other(format!(
+                "`{display}` is excluded by the project's ignored paths or {AGNTIGNORE_FILENAME}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `path` is hidden by an [`AGNTIGNORE_FILENAME`] file in `root` or
+/// one of the directories between `root` and `path`. Deeper files take
+/// precedence, so they can re-include what a parent excluded.
+pub fn is_agntignored(root: &Path, path: &Path) -> bool {
+    let root = normalize(root);
+    let path = normalize(path);
+    let Ok(relative) = path.strip_prefix(&root) else {
+        return false;
+    };
+    let Some(parent) = relative.parent() else {
+        return false;
+    };
+    let is_dir = path.is_dir();
+
+    // From `root` down to the directory holding `path`.
+    let mut dirs = parent.ancestors().collect::<Vec<_>>();
+    dirs.reverse();
+
+    let mut ignored = false;
+    for dir in dirs {
+        let dir = root.join(dir);
+        let file = dir.join(AGNTIGNORE_FILENAME);
+        if !file.is_file() {
+            continue;
+        }
+        let mut builder = GitignoreBuilder::new(&dir);
+        let _ = builder.add(&file);
+        if let Ok(matcher) = builder.build()
+            && let Ok(scoped) = path.strip_prefix(&dir)
+        {
+            match matcher.matched_path_or_any_parents(scoped, is_dir) {
+                Match::Ignore(_) => ignored = true,
+                Match::Whitelist(_) => ignored = false,
+                Match::None => {}
+            }
+        }
+    }
+    ignored
+}
+
+/// Resolve `.` and `..` components without touching the filesystem, so
+/// `src/../secrets` cannot slip past a `secrets/` pattern.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,52 @@
+//! Short summaries of a conversation, written when a session is left and
+//! shown again when it is picked back up.
+
+use serde::{Deserialize, Serialize};
+
+/// What a conversation was about and what was settled in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub summary: String,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+}
+
+pub(crate) const SUMMARY_PROMPT: &str = "The user has left this conversation. Summarize it for \
+whoever picks it up later, which may be you after a long break: what was being worked on, where \
+it stands and what is left to do, in at most four sentences. Then list the decisions that were \
+made (approaches chosen, things ruled out, conventions agreed on), one short sentence each. \
+Answer with JSON only, in the form {\"summary\": \"...\", \"decisions\": [\"...\"]}.";
+
+impl ConversationSummary {
+    /// Parse the model's answer to [`SUMMARY_PROMPT`]. An answer that isn't
+    /// the asked-for JSON is kept whole as the summary.
+    pub(crate) fn parse(answer: &str) -> Option<Self> {
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return None;
+        }
+        let json = match (answer.find('{'), answer.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &answer[start..=end],
+            _ => answer,
+        };
+        let summary = serde_json::from_str::<Self>(json).unwrap_or_else(|_| Self {
+            summary: answer.to_string(),
+            decisions: Vec::new(),
+        });
+        (!summary.summary.trim().is_empty()).then_some(summary)
+    }
+
+    /// The summary as a note for the model, for a session resumed after a
+    /// while.
+    pub fn to_note(&self) -> String {
+        let mut note = format!("<session-summary>\n{}\n", self.summary.trim());
+        if !self.decisions.is_empty() {
+            note.push_str("\nDecisions:\n");
+            for decision in &self.decisions {
+                note.push_str(&format!("- {}\n", decision.trim()));
+            }
+        }
+        note.push_str("</session-summary>");
+        note
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use handlebars::Handlebars;
+use serde::Deserialize;
+
+use crate::tools::split_frontmatter;
+
+/// A reusable prompt loaded from `.agnt/templates/<name>.md`.
+///
+/// The body is a handlebars template; every `{{variable}}` placeholder must
+/// be given a value before the prompt can be rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+    /// Placeholder names in order of first appearance.
+    pub variables: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TemplateFrontmatter {
+    description: Option<String>,
+}
+
+impl PromptTemplate {
+    /// Parse a template from its source, with an optional YAML frontmatter
+    /// block providing a `description`.
+    pub fn parse(name: impl Into<String>, source: &str) -> Self {
+        let (frontmatter, body) = match split_frontmatter(source) {
+            Some((yaml, body)) => (serde_yaml::from_str::<TemplateFrontmatter>(yaml).ok(), body),
+            None => (None, source),
+        };
+
+        let description = frontmatter
+            .and_then(|f| f.description)
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        let body = body.trim().to_string();
+        let variables = template_variables(&body);
+
+        Self {
+            name: name.into(),
+            description,
+            body,
+            variables,
+        }
+    }
+
+    /// Render the template. Fails if a placeholder has no value.
+    pub fn render(
+        &self,
+        values: &HashMap<String, String>,
+    ) -> Result<String, handlebars::RenderError> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        handlebars.register_escape_fn(handlebars::no_escape);
+        handlebars.render_template(&self.body, values)
+    }
+}
+
+/// Load all `*.md` templates in `dir`, sorted by name. A missing directory
+/// yields no templates; unreadable files are skipped.
+pub fn discover_templates(dir: &Path) -> Vec<PromptTemplate> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut templates: Vec<PromptTemplate> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let source = std::fs::read_to_string(&path).ok()?;
+            Some(PromptTemplate::parse(name, &source))
+        })
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+/// Collect simple `{{name}}` / `{{{name}}}` placeholders, ignoring block
+/// helpers, comments and partials.
+fn template_variables(body: &str) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim_matches(['{', '}', '~']).trim();
+        let is_simple = !name.is_empty()
+            && name != "else"
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'));
+        if is_simple && !variables.iter().any(|v| v == name) {
+            variables.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    variables
+}
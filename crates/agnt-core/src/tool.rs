@@ -1,6 +1,7 @@
-use agnt_llm::{Describe, ToolDefinition};
+use agnt_llm::{Describe, Schema, ToolDefinition};
 use serde::de::DeserializeOwned;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
 
 use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
@@ -57,6 +58,12 @@ impl ToolOutput for String {
 /// # Example
 ///
 /// ```ignore
+/// #[derive(Clone, Deserialize, Describe)]
+/// struct ReadFileInput {
+///     /// File path to read.
+///     path: String,
+/// }
+///
 /// #[derive(Clone)]
 /// struct ReadFile { cwd: PathBuf }
 ///
@@ -117,6 +124,44 @@ pub trait Tool: Clone + Send + Sync + 'static {
     fn render_llm_output(&self, _input: &Self::Input, output: &Self::Output) -> String {
         output.to_llm()
     }
+
+    /// What the call did, for the audit log.
+    ///
+    /// Override to report the files a call touched or the exit code of a
+    /// command it ran.
+    /// Default: nothing beyond the tool name and arguments.
+    fn audit(&self, _input: &Self::Input, _output: &Self::Output) -> ToolAudit {
+        ToolAudit::default()
+    }
+
+    /// The files a call with `input` writes, as absolute paths, which the
+    /// agent checks against the protected paths before the call runs.
+    ///
+    /// Override in tools that change files.
+    /// Default: none.
+    fn writes(&self, _input: &Self::Input) -> impl Future<Output = Vec<PathBuf>> + Send {
+        std::future::ready(Vec::new())
+    }
+
+    /// Whether a repeated call with identical arguments may reuse the first
+    /// result instead of running again, when the agent caches tool results.
+    ///
+    /// Only for tools without side effects. The cache is dropped whenever a
+    /// tool that isn't cacheable runs, as it may have changed what the
+    /// cached calls would return.
+    /// Default: `false`.
+    fn cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// Side effects of a tool call reported by [`Tool::audit`].
+#[derive(Debug, Clone, Default)]
+pub struct ToolAudit {
+    /// Paths the call read or wrote, as given to the tool.
+    pub files: Vec<String>,
+    /// Exit code of a command the call ran.
+    pub exit_code: Option<i32>,
 }
 
 // ---------------------------------------------------------------------------
@@ -124,11 +169,13 @@ pub trait Tool: Clone + Send + Sync + 'static {
 // ---------------------------------------------------------------------------
 
 /// The result of executing a prepared tool call.
+#[derive(Clone)]
 pub(crate) struct ToolExecResult {
     /// The text that goes into conversation history for the LLM.
     pub llm_output: String,
     /// How the result should be displayed to the user.
     pub output_display: ToolResultDisplay,
+    pub audit: ToolAudit,
 }
 
 /// A parsed, ready-to-execute tool call. Holds the input display (which the
@@ -137,6 +184,8 @@ pub(crate) struct PreparedToolCall {
     /// How the invocation should be displayed to the user — available
     /// immediately, before execution.
     pub input_display: ToolCallDisplay,
+    /// The files the call writes, resolved before it executes.
+    pub writes: Pin<Box<dyn Future<Output = Vec<PathBuf>> + Send>>,
     /// The future that executes the tool and produces the result.
     pub future: Pin<Box<dyn Future<Output = Result<ToolExecResult, agnt_llm::Error>> + Send>>,
 }
@@ -150,10 +199,13 @@ pub(crate) trait ErasedTool: Send + Sync {
 
     /// Parse arguments and produce a [`PreparedToolCall`].
     ///
-    /// This is synchronous — it parses JSON and calls `render_input`, but
-    /// does **not** execute the tool. The caller can inspect `input_display`
-    /// immediately, then `.await` the `future` when ready.
+    /// This is synchronous — it parses JSON, checks it against the input's
+    /// schema and calls `render_input`, but does **not** execute the tool.
+    /// The caller can inspect `input_display` immediately, then `.await`
+    /// the `future` when ready.
     fn prepare(&self, arguments: &str) -> Result<PreparedToolCall, agnt_llm::Error>;
+
+    fn cacheable(&self) -> bool;
 }
 
 impl<T: Tool> ErasedTool for T {
@@ -166,27 +218,175 @@ impl<T: Tool> ErasedTool for T {
     }
 
     fn prepare(&self, arguments: &str) -> Result<PreparedToolCall, agnt_llm::Error> {
-        let input: T::Input =
+        let arguments: serde_json::Value =
             serde_json::from_str(arguments).map_err(|e| agnt_llm::Error::Other(e.to_string()))?;
+        validate_arguments(&T::Input::describe(), &arguments)?;
+        let input: T::Input =
+            serde_json::from_value(arguments).map_err(|e| agnt_llm::Error::Other(e.to_string()))?;
 
         let input_display = self.render_input(&input);
 
-        // Clone self + input so the future is 'static.
+        // Clone self + input so the futures are 'static.
+        let this = self.clone();
+        let input_for_writes = input.clone();
+        let writes = Box::pin(async move { this.writes(&input_for_writes).await });
         let this = self.clone();
         let input_for_call = input.clone();
         let future = Box::pin(async move {
             let output = this.call(input_for_call.clone()).await?;
             let llm_output = this.render_llm_output(&input_for_call, &output);
             let output_display = this.render_output(&input_for_call, &output);
+            let audit = this.audit(&input_for_call, &output);
             Ok(ToolExecResult {
                 llm_output,
                 output_display,
+                audit,
             })
         });
 
         Ok(PreparedToolCall {
             input_display,
+            writes,
             future,
         })
     }
+
+    fn cacheable(&self) -> bool {
+        Tool::cacheable(self)
+    }
+}
+
+/// Check tool arguments against the tool's declared schema, reporting every
+/// problem with where it is, e.g. `/operations: [] has less than 1 item`,
+/// rather than only the first thing serde trips over.
+pub(crate) fn validate_arguments(
+    schema: &Schema,
+    arguments: &serde_json::Value,
+) -> Result<(), agnt_llm::Error> {
+    // A schema the validator can't compile still gets serde's checks.
+    let Ok(validator) = jsonschema::validator_for(&schema.to_json_schema()) else {
+        return Ok(());
+    };
+    // Models often send `null` for optional fields, which serde reads as
+    // absent; the schema doesn't allow for it.
+    let arguments = without_nulls(arguments);
+    let problems: Vec<String> = validator
+        .iter_errors(&arguments)
+        .map(|error| {
+            let path = error.instance_path().to_string();
+            let path = if path.is_empty() { "arguments" } else { &path };
+            format!("{path}: {}", error.masked())
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(agnt_llm::Error::Other(format!(
+            "invalid arguments: {}",
+            problems.join("; ")
+        )))
+    }
+}
+
+fn without_nulls(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => object
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| (key.clone(), without_nulls(value)))
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(without_nulls).collect(),
+        value => value.clone(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Result capping
+// ---------------------------------------------------------------------------
+
+/// Tool results longer than this are cut down before they go into the
+/// conversation history, so one huge output can't fill the context window.
+pub const MAX_TOOL_RESULT_BYTES: usize = 32 * 1024;
+
+/// Keep the start and, with a bit more room, the end of `output` (where
+/// errors and summaries tend to be), cut at line boundaries, with a note on
+/// what was left out. `None` when it already fits.
+pub(crate) fn cap_tool_result(output: &str) -> Option<String> {
+    if output.len() <= MAX_TOOL_RESULT_BYTES {
+        return None;
+    }
+
+    let head_budget = MAX_TOOL_RESULT_BYTES * 2 / 5;
+    let tail_budget = MAX_TOOL_RESULT_BYTES - head_budget;
+
+    let mut head_end = head_budget;
+    while !output.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    if let Some(newline) = output[..head_end].rfind('\n') {
+        head_end = newline + 1;
+    }
+
+    let mut tail_start = output.len() - tail_budget;
+    while !output.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    if let Some(newline) = output[tail_start..].find('\n') {
+        tail_start += newline + 1;
+    }
+
+    let omitted = &output[head_end..tail_start];
+    Some(format!(
+        "{}\n[... {} lines ({} bytes) omitted; narrow the command, e.g. with grep, head or tail, to see them ...]\n\n{}",
+        &output[..head_end],
+        omitted.lines().count(),
+        omitted.len(),
+        &output[tail_start..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agnt_llm::Property;
+
+    #[test]
+    fn reports_each_invalid_argument_with_its_path() {
+        let schema = Schema::Object {
+            description: None,
+            properties: vec![
+                Property {
+                    name: "path".into(),
+                    schema: Schema::String {
+                        description: None,
+                        enumeration: None,
+                    },
+                },
+                Property {
+                    name: "limit".into(),
+                    schema: Schema::Integer { description: None },
+                },
+                Property {
+                    name: "lines".into(),
+                    schema: Schema::Raw(serde_json::json!({
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "minItems": 1
+                    })),
+                },
+            ],
+            required: vec!["path".into()],
+        };
+
+        let valid = serde_json::json!({ "path": "src/main.rs", "limit": null });
+        assert!(validate_arguments(&schema, &valid).is_ok());
+
+        let invalid = serde_json::json!({ "limit": "ten", "lines": [] });
+        let message = validate_arguments(&schema, &invalid)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("arguments: \"path\" is a required property"));
+        assert!(message.contains("/limit: value is not of type \"integer\""));
+        assert!(message.contains("/lines: value has less than 1 item"));
+    }
 }
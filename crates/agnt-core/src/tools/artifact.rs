@@ -0,0 +1,86 @@
+use agnt_llm::Describe;
+use serde::Deserialize;
+
+use super::read::lang_from_ext;
+use crate::artifacts::{self, Artifact, ArtifactsDirSlot};
+use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
+use crate::tool::{Tool, ToolOutput};
+
+const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/artifact.md");
+
+#[derive(Clone, Deserialize, Describe)]
+pub struct ArtifactInput {
+    /// File name of the artifact, with an extension, e.g. `report.md`.
+    #[describe(min_length = 1)]
+    pub name: String,
+    /// Full content of the file.
+    pub content: String,
+}
+
+impl ToolOutput for Artifact {
+    fn to_llm(&self) -> String {
+        format!(
+            "saved artifact {} ({}, {} bytes) at {}",
+            self.id,
+            self.name,
+            self.size,
+            self.path.display()
+        )
+    }
+}
+
+/// Tool that saves files for the user in the active session's artifacts
+/// directory.
+#[derive(Clone)]
+pub struct ArtifactTool {
+    pub(crate) dir: ArtifactsDirSlot,
+}
+
+impl Tool for ArtifactTool {
+    type Input = ArtifactInput;
+    type Output = Artifact;
+
+    fn name(&self) -> &str {
+        "artifact"
+    }
+
+    fn description(&self) -> &str {
+        TOOL_DESCRIPTION
+    }
+
+    async fn call(&self, input: ArtifactInput) -> Result<Artifact, agnt_llm::Error> {
+        let dir =
+            self.dir.read().clone().ok_or_else(|| {
+                agnt_llm::Error::Other("no session to save artifacts in".to_string())
+            })?;
+        artifacts::save(&dir, &input.name, input.content.as_bytes())
+            .map_err(|e| agnt_llm::Error::Other(e.to_string()))
+    }
+
+    async fn writes(&self, input: &ArtifactInput) -> Vec<std::path::PathBuf> {
+        // Saved as `<id>-<name>`, which patterns on the directory or the
+        // extension match alike. Only protected when the session's
+        // directory is in the workspace.
+        let dir = self.dir.read().clone();
+        dir.map(|dir| dir.join(input.name.trim()))
+            .into_iter()
+            .collect()
+    }
+
+    fn render_input(&self, input: &ArtifactInput) -> ToolCallDisplay {
+        ToolCallDisplay {
+            title: format!("Save artifact {}", input.name.trim()),
+            body: None,
+        }
+    }
+
+    fn render_output(&self, input: &ArtifactInput, output: &Artifact) -> ToolResultDisplay {
+        ToolResultDisplay {
+            title: format!("Saved artifact {}: {}", output.id, output.name),
+            body: Some(DisplayBody::Code {
+                language: lang_from_ext(&output.name),
+                content: input.content.clone(),
+            }),
+        }
+    }
+}
@@ -1,43 +1,41 @@
-use agnt_llm::{Describe, Property, Schema};
+use agnt_llm::Describe;
 use serde::Deserialize;
-use tokio::process::Command;
 
 use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
-use crate::tool::{Tool, ToolOutput};
+use crate::governor::{ResourceGovernor, output_with_cpu};
+use crate::protect::written_paths;
+use crate::tool::{Tool, ToolAudit, ToolOutput};
+use crate::tools::{CommandChecker, CommandWarning};
 
 const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/bash.md");
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Describe)]
 pub struct BashInput {
     /// The bash command to run.
     pub command: String,
 }
 
-impl Describe for BashInput {
-    fn describe() -> Schema {
-        Schema::Object {
-            description: None,
-            properties: vec![Property {
-                name: "command".into(),
-                schema: Schema::String {
-                    description: Some("The bash command to run".into()),
-                    enumeration: None,
-                },
-            }],
-            required: vec!["command".into()],
-        }
-    }
-}
-
 /// Structured output from running a bash command.
 pub struct BashOutput {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: Option<i32>,
+    /// Set when the command was not run because it can't work in this
+    /// project.
+    pub warning: Option<CommandWarning>,
 }
 
 impl ToolOutput for BashOutput {
     fn to_llm(&self) -> String {
+        if let Some(warning) = &self.warning {
+            let mut result = format!("[not run: {}.", warning.problem);
+            if let Some(suggestion) = &warning.suggestion {
+                result.push_str(&format!(" Try `{suggestion}` instead."));
+            }
+            result.push_str(" Run the same command again to run it anyway.]");
+            return result;
+        }
+
         let mut result = String::new();
 
         if !self.stdout.is_empty() {
@@ -70,6 +68,10 @@ impl ToolOutput for BashOutput {
 #[derive(Clone)]
 pub struct BashTool {
     pub(crate) cwd: std::path::PathBuf,
+    pub(crate) governor: ResourceGovernor,
+    /// Checks commands against the workspace before they run; `None` runs
+    /// them as given.
+    pub(crate) checker: Option<CommandChecker>,
 }
 
 impl Tool for BashTool {
@@ -85,18 +87,37 @@ impl Tool for BashTool {
     }
 
     async fn call(&self, input: BashInput) -> Result<BashOutput, agnt_llm::Error> {
-        let output = Command::new("bash")
+        if let Some(warning) = self
+            .checker
+            .as_ref()
+            .and_then(|checker| checker.check(&input.command))
+        {
+            return Ok(BashOutput {
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: None,
+                warning: Some(warning),
+            });
+        }
+        let _slot = self.governor.command_slot().await;
+        let ulimits = self.governor.command_ulimits()?;
+        let mut command = std::process::Command::new("bash");
+        command
             .arg("-c")
-            .arg(&input.command)
-            .current_dir(&self.cwd)
-            .output()
+            .arg(format!("{ulimits}{}", input.command))
+            .current_dir(&self.cwd);
+
+        let (output, cpu) = tokio::task::spawn_blocking(move || output_with_cpu(command))
             .await
+            .map_err(|e| agnt_llm::Error::Other(format!("bash task failed: {e}")))?
             .map_err(|e| agnt_llm::Error::Other(format!("failed to spawn bash: {e}")))?;
+        self.governor.add_command_cpu(cpu);
 
         Ok(BashOutput {
             stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
             stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
             exit_code: output.status.code(),
+            warning: None,
         })
     }
 
@@ -108,6 +129,16 @@ impl Tool for BashTool {
     }
 
     fn render_output(&self, _input: &BashInput, output: &BashOutput) -> ToolResultDisplay {
+        if let Some(warning) = &output.warning {
+            let mut text = warning.problem.clone();
+            if let Some(suggestion) = &warning.suggestion {
+                text.push_str(&format!("\nsuggested: {suggestion}"));
+            }
+            return ToolResultDisplay {
+                title: "not run".to_string(),
+                body: Some(DisplayBody::Text(text)),
+            };
+        }
         let title = match output.exit_code {
             Some(0) => "exit code 0".to_string(),
             Some(code) => format!("exit code {code}"),
@@ -137,4 +168,18 @@ impl Tool for BashTool {
 
         ToolResultDisplay { title, body }
     }
+
+    fn audit(&self, _input: &BashInput, output: &BashOutput) -> ToolAudit {
+        ToolAudit {
+            files: Vec::new(),
+            exit_code: output.exit_code,
+        }
+    }
+
+    async fn writes(&self, input: &BashInput) -> Vec<std::path::PathBuf> {
+        written_paths(&input.command)
+            .into_iter()
+            .map(|path| self.cwd.join(path))
+            .collect()
+    }
 }
@@ -0,0 +1,419 @@
+//! Checks a `bash` command against the kinds of project around the working
+//! directory before it runs, catching the obviously wrong ones: `npm test`
+//! in a Cargo-only repository, `npm install` where the lockfile is pnpm's,
+//! `make` without a Makefile.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Why a command was not run, and what to run instead if that is clear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandWarning {
+    pub problem: String,
+    pub suggestion: Option<String>,
+}
+
+/// Flags commands that can't work in the project around `cwd`. A flagged
+/// command that the model runs again as-is is let through, in case the
+/// check was wrong.
+#[derive(Clone)]
+pub(crate) struct CommandChecker {
+    cwd: PathBuf,
+    workspace_root: PathBuf,
+    flagged: Arc<Mutex<HashSet<String>>>,
+}
+
+impl CommandChecker {
+    pub(crate) fn new(cwd: PathBuf, workspace_root: PathBuf) -> Self {
+        Self {
+            cwd,
+            workspace_root,
+            flagged: Arc::default(),
+        }
+    }
+
+    /// The warning to return instead of running `command`, if any.
+    pub(crate) fn check(&self, command: &str) -> Option<CommandWarning> {
+        let command = command.trim();
+        if self.flagged.lock().remove(command) {
+            return None;
+        }
+        // Where a command that changes directory runs is anyone's guess.
+        if command
+            .split_whitespace()
+            .any(|word| matches!(word, "cd" | "pushd"))
+        {
+            return None;
+        }
+        // Outside the workspace the project files would be looked for all
+        // the way up to the file system root.
+        if !self.cwd.starts_with(&self.workspace_root) {
+            return None;
+        }
+        let project = Project::detect(&self.cwd, &self.workspace_root);
+        let warning = segments(command).find_map(|segment| project.check(&segment))?;
+        self.flagged.lock().insert(command.to_string());
+        Some(warning)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+impl PackageManager {
+    const ALL: [Self; 4] = [Self::Npm, Self::Yarn, Self::Pnpm, Self::Bun];
+
+    fn from_command(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pm| pm.command() == name)
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Yarn => "yarn",
+            Self::Pnpm => "pnpm",
+            Self::Bun => "bun",
+        }
+    }
+
+    fn lockfiles(self) -> &'static [&'static str] {
+        match self {
+            Self::Npm => &["package-lock.json", "npm-shrinkwrap.json"],
+            Self::Yarn => &["yarn.lock"],
+            Self::Pnpm => &["pnpm-lock.yaml"],
+            Self::Bun => &["bun.lockb", "bun.lock"],
+        }
+    }
+}
+
+/// The project files found between the working directory and the
+/// workspace root.
+#[derive(Debug, Default)]
+struct Project {
+    cargo: bool,
+    node: bool,
+    /// Package manager whose lockfile is the only one present.
+    package_manager: Option<PackageManager>,
+    go: bool,
+    python: bool,
+    make: bool,
+}
+
+impl Project {
+    fn detect(cwd: &Path, workspace_root: &Path) -> Self {
+        let mut project = Self::default();
+        let mut lockfiles = HashSet::new();
+        for dir in cwd.ancestors() {
+            let has = |name: &str| dir.join(name).is_file();
+            project.cargo |= has("Cargo.toml");
+            project.node |= has("package.json");
+            project.go |= has("go.mod") || has("go.work");
+            project.python |= [
+                "pyproject.toml",
+                "setup.py",
+                "setup.cfg",
+                "requirements.txt",
+            ]
+            .into_iter()
+            .any(has);
+            // Only the innermost Makefile is used by a plain `make`.
+            project.make |=
+                dir == cwd && ["Makefile", "makefile", "GNUmakefile"].into_iter().any(has);
+            lockfiles.extend(
+                PackageManager::ALL
+                    .into_iter()
+                    .filter(|pm| pm.lockfiles().iter().any(|name| has(name))),
+            );
+            if dir == workspace_root {
+                break;
+            }
+        }
+        if lockfiles.len() == 1 {
+            project.package_manager = lockfiles.into_iter().next();
+        }
+        project
+    }
+
+    fn check(&self, segment: &[&str]) -> Option<CommandWarning> {
+        let (&program, args) = segment.split_first()?;
+        let subcommand = args.iter().copied().find(|arg| !arg.starts_with('-'));
+        let wants_help = args
+            .iter()
+            .any(|arg| matches!(*arg, "--help" | "-h" | "--version" | "-V"));
+        if wants_help {
+            return None;
+        }
+
+        if program == "cargo" {
+            let creates = matches!(
+                subcommand,
+                None | Some("new" | "init" | "install" | "uninstall" | "search" | "help" | "login")
+            );
+            let elsewhere = args.iter().any(|arg| arg.starts_with("--manifest-path"));
+            if !self.cargo && !creates && !elsewhere {
+                return Some(self.missing("Cargo.toml", "cargo", subcommand));
+            }
+        } else if let Some(pm) = PackageManager::from_command(program) {
+            let global = args.iter().any(|arg| matches!(*arg, "-g" | "--global"));
+            let elsewhere = args.iter().any(|arg| {
+                matches!(*arg, "--prefix" | "--cwd" | "--dir" | "-C")
+                    || arg.starts_with("--prefix=")
+            });
+            let creates = matches!(
+                subcommand,
+                None | Some(
+                    "init"
+                        | "create"
+                        | "exec"
+                        | "x"
+                        | "dlx"
+                        | "help"
+                        | "view"
+                        | "info"
+                        | "config"
+                        | "login"
+                        | "whoami"
+                        | "search"
+                )
+            );
+            if global || elsewhere || creates {
+                return None;
+            }
+            if !self.node {
+                return Some(self.missing("package.json", program, subcommand));
+            }
+            if let Some(expected) = self.package_manager
+                && expected != pm
+            {
+                let names_packages = args
+                    .iter()
+                    .filter(|arg| !arg.starts_with('-'))
+                    .nth(1)
+                    .is_some();
+                let rest = match subcommand {
+                    // Only npm has `ci`; a frozen install is the same thing.
+                    Some("ci") if expected != PackageManager::Npm => {
+                        "install --frozen-lockfile".to_string()
+                    }
+                    // Only npm adds a dependency with `install <pkg>`; the
+                    // others want `add <pkg>`.
+                    Some(verb @ ("install" | "i" | "add")) if names_packages => {
+                        let verb_for = if expected == PackageManager::Npm {
+                            "install"
+                        } else {
+                            "add"
+                        };
+                        let at = args.iter().position(|arg| *arg == verb).unwrap_or(0);
+                        let mut args = args.to_vec();
+                        args[at] = verb_for;
+                        args.join(" ")
+                    }
+                    _ => args.join(" "),
+                };
+                return Some(CommandWarning {
+                    problem: format!(
+                        "this project is managed with {}, going by its lockfile, not {program}",
+                        expected.command(),
+                    ),
+                    suggestion: Some(format!("{} {rest}", expected.command())),
+                });
+            }
+        } else if program == "go" {
+            let needs_module = match subcommand {
+                Some("build" | "test" | "vet" | "generate" | "list") => true,
+                Some("mod") => args.get(1).is_some_and(|arg| *arg != "init"),
+                // `go run main.go` works outside a module.
+                Some("run") => !args.iter().any(|arg| arg.ends_with(".go")),
+                _ => false,
+            };
+            if !self.go && needs_module {
+                return Some(self.missing("go.mod", "go", subcommand));
+            }
+        } else if program == "make" {
+            let elsewhere = args.iter().any(|arg| {
+                matches!(*arg, "-f" | "-C" | "--file" | "--directory")
+                    || arg.starts_with("--file=")
+                    || arg.starts_with("--directory=")
+            });
+            if !self.make && !elsewhere {
+                return Some(self.missing("Makefile", "make", subcommand));
+            }
+        }
+        None
+    }
+
+    /// `program` needs a `manifest` there isn't; suggest the project's own
+    /// command for the same job.
+    fn missing(&self, manifest: &str, program: &str, subcommand: Option<&str>) -> CommandWarning {
+        let kinds = self.kinds();
+        let problem = if kinds.is_empty() {
+            format!("there is no {manifest} here, so `{program}` has no project to work on")
+        } else {
+            format!(
+                "there is no {manifest} here, so `{program}` has no project to work on; this is a {} project",
+                kinds.join(" and ")
+            )
+        };
+        CommandWarning {
+            problem,
+            suggestion: self.equivalent(subcommand.unwrap_or("build")),
+        }
+    }
+
+    fn kinds(&self) -> Vec<&'static str> {
+        [
+            (self.cargo, "Cargo"),
+            (self.node, "Node"),
+            (self.go, "Go"),
+            (self.python, "Python"),
+        ]
+        .into_iter()
+        .filter_map(|(present, kind)| present.then_some(kind))
+        .collect()
+    }
+
+    /// The command for `job` (`test`, `build`, ...) in this project, if it
+    /// is of a single kind.
+    fn equivalent(&self, job: &str) -> Option<String> {
+        let job = match job {
+            "t" => "test",
+            "b" => "build",
+            "r" | "start" => "run",
+            job => job,
+        };
+        if self.kinds().len() > 1 {
+            return None;
+        }
+        if self.cargo {
+            matches!(job, "test" | "build" | "run" | "check").then(|| format!("cargo {job}"))
+        } else if self.node {
+            let pm = self
+                .package_manager
+                .unwrap_or(PackageManager::Npm)
+                .command();
+            match job {
+                "test" => Some(format!("{pm} test")),
+                "install" | "i" | "add" => Some(format!("{pm} install")),
+                "build" | "run" => Some(format!("{pm} run {job}")),
+                _ => None,
+            }
+        } else if self.go {
+            matches!(job, "test" | "build" | "vet").then(|| format!("go {job} ./..."))
+        } else if self.python {
+            (job == "test").then(|| "python -m pytest".to_string())
+        } else if self.make {
+            Some(if job == "build" {
+                "make".to_string()
+            } else {
+                format!("make {job}")
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// The simple commands in `command`, as words, with leading variable
+/// assignments and wrappers such as `sudo` dropped.
+fn segments(command: &str) -> impl Iterator<Item = Vec<&str>> {
+    command
+        .split(['\n', ';', '|', '&', '(', ')'])
+        .map(|segment| {
+            segment
+                .split_whitespace()
+                .skip_while(|word| {
+                    matches!(*word, "sudo" | "time" | "env" | "exec" | "nice")
+                        || word.contains('=') && !word.starts_with('-')
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory holding empty `files`.
+    fn workspace(files: &[&str]) -> PathBuf {
+        static SEQ: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "agnt-command-check-{}-{}",
+            std::process::id(),
+            SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    fn checker(dir: &Path) -> CommandChecker {
+        CommandChecker::new(dir.to_path_buf(), dir.to_path_buf())
+    }
+
+    #[test]
+    fn flags_npm_in_a_cargo_project() {
+        let dir = workspace(&["Cargo.toml"]);
+        let warning = checker(&dir).check("npm test").unwrap();
+        assert!(warning.problem.contains("package.json"));
+        assert_eq!(warning.suggestion.as_deref(), Some("cargo test"));
+    }
+
+    #[test]
+    fn suggests_the_lockfile_package_manager() {
+        let dir = workspace(&["package.json", "pnpm-lock.yaml"]);
+        let warning = checker(&dir).check("npm install && npm run build").unwrap();
+        assert_eq!(warning.suggestion.as_deref(), Some("pnpm install"));
+        assert_eq!(checker(&dir).check("pnpm run build"), None);
+
+        let warning = checker(&dir).check("npm install -D vitest").unwrap();
+        assert_eq!(warning.suggestion.as_deref(), Some("pnpm add -D vitest"));
+        let dir = workspace(&["package.json", "package-lock.json"]);
+        let warning = checker(&dir).check("yarn add lodash").unwrap();
+        assert_eq!(warning.suggestion.as_deref(), Some("npm install lodash"));
+    }
+
+    #[test]
+    fn skips_a_working_directory_outside_the_workspace() {
+        let dir = workspace(&[]);
+        let other = workspace(&[]);
+        let checker = CommandChecker::new(other, dir);
+        assert_eq!(checker.check("make test"), None);
+    }
+
+    #[test]
+    fn lets_commands_through_that_can_work() {
+        let dir = workspace(&["Cargo.toml"]);
+        let checker = checker(&dir);
+        for command in [
+            "cargo test -p agnt-core",
+            "cargo new other",
+            "npm install -g typescript",
+            "npx prettier --check .",
+            "cd web && npm test",
+            "RUST_LOG=debug cargo run",
+            "go run main.go",
+        ] {
+            assert_eq!(checker.check(command), None, "{command}");
+        }
+    }
+
+    #[test]
+    fn runs_a_flagged_command_when_repeated() {
+        let dir = workspace(&[]);
+        let checker = checker(&dir);
+        assert!(checker.check("make test").is_some());
+        assert_eq!(checker.check("make test"), None);
+    }
+}
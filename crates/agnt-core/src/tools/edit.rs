@@ -1,60 +1,66 @@
 use std::io::ErrorKind;
 
-use agnt_llm::{Describe, Schema};
+use agnt_llm::Describe;
 use serde::Deserialize;
 use similar::{ChangeTag, TextDiff};
 
 use super::hashline::{FileLines, hashline, replacement_lines, resolve_anchor};
 use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
-use crate::tool::{Tool, ToolOutput};
+use crate::format::Formatters;
+use crate::governor::ResourceGovernor;
+use crate::review::{EditReview, EditReviewerSlot, ProposedEdit};
+use crate::settings::IgnoredPaths;
+use crate::tool::{Tool, ToolAudit, ToolOutput};
 
 const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/edit.md");
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Describe)]
 pub struct EditInput {
     /// The file path to edit, relative to the working directory.
+    #[describe(min_length = 1)]
     pub path: String,
     /// Ordered edit operations.
+    #[describe(min_items = 1)]
     pub operations: Vec<EditOperation>,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Describe)]
 #[serde(tag = "op", rename_all = "snake_case")]
 pub enum EditOperation {
     Replace {
-        /// Hashline anchor in `line:hash` format.
+        /// Hashline anchor (`line:hash`) or symbol anchor (e.g. `fn parse_config`).
         anchor: String,
         /// Replacement content (can be multi-line).
         content: String,
     },
     InsertBefore {
-        /// Hashline anchor in `line:hash` format.
+        /// Hashline anchor (`line:hash`) or symbol anchor (e.g. `fn parse_config`).
         anchor: String,
         /// Content to insert before the anchor line (can be multi-line).
         content: String,
     },
     InsertAfter {
-        /// Hashline anchor in `line:hash` format.
+        /// Hashline anchor (`line:hash`) or symbol anchor (e.g. `fn parse_config`).
         anchor: String,
         /// Content to insert after the anchor line (can be multi-line).
         content: String,
     },
     Delete {
-        /// Hashline anchor in `line:hash` format.
+        /// Hashline anchor (`line:hash`) or symbol anchor (e.g. `fn parse_config`).
         anchor: String,
     },
     ReplaceRange {
-        /// Start hashline anchor in `line:hash` format.
+        /// Start anchor, hashline (`line:hash`) or symbol.
         start: String,
-        /// End hashline anchor in `line:hash` format.
+        /// End anchor, hashline (`line:hash`) or symbol.
         end: String,
         /// Replacement content (can be multi-line).
         content: String,
     },
     DeleteRange {
-        /// Start hashline anchor in `line:hash` format.
+        /// Start anchor, hashline (`line:hash`) or symbol.
         start: String,
-        /// End hashline anchor in `line:hash` format.
+        /// End anchor, hashline (`line:hash`) or symbol.
         end: String,
     },
     RewriteFile {
@@ -84,116 +90,6 @@ impl EditOperation {
     }
 }
 
-impl Describe for EditInput {
-    fn describe() -> Schema {
-        Schema::Raw(serde_json::json!({
-            "type": "object",
-            "additionalProperties": false,
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "File path to edit, relative to the working directory"
-                },
-                "operations": {
-                    "type": "array",
-                    "description": "Ordered list of edit operations",
-                    "items": {
-                        "oneOf": [
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["replace"] },
-                                    "anchor": { "type": "string", "description": "Hashline anchor in line:hash format" },
-                                    "content": { "type": "string", "description": "Replacement content (multi-line allowed)" }
-                                },
-                                "required": ["op", "anchor", "content"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["insert_before"] },
-                                    "anchor": { "type": "string", "description": "Hashline anchor in line:hash format" },
-                                    "content": { "type": "string", "description": "Content to insert (multi-line allowed)" }
-                                },
-                                "required": ["op", "anchor", "content"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["insert_after"] },
-                                    "anchor": { "type": "string", "description": "Hashline anchor in line:hash format" },
-                                    "content": { "type": "string", "description": "Content to insert (multi-line allowed)" }
-                                },
-                                "required": ["op", "anchor", "content"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["delete"] },
-                                    "anchor": { "type": "string", "description": "Hashline anchor in line:hash format" }
-                                },
-                                "required": ["op", "anchor"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["replace_range"] },
-                                    "start": { "type": "string", "description": "Start hashline anchor in line:hash format" },
-                                    "end": { "type": "string", "description": "End hashline anchor in line:hash format" },
-                                    "content": { "type": "string", "description": "Replacement content (multi-line allowed)" }
-                                },
-                                "required": ["op", "start", "end", "content"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["delete_range"] },
-                                    "start": { "type": "string", "description": "Start hashline anchor in line:hash format" },
-                                    "end": { "type": "string", "description": "End hashline anchor in line:hash format" }
-                                },
-                                "required": ["op", "start", "end"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["rewrite_file"] },
-                                    "content": { "type": "string", "description": "Full file content to write" }
-                                },
-                                "required": ["op", "content"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["move_file"] },
-                                    "to": { "type": "string", "description": "Destination path, relative to the working directory" }
-                                },
-                                "required": ["op", "to"]
-                            },
-                            {
-                                "type": "object",
-                                "additionalProperties": false,
-                                "properties": {
-                                    "op": { "type": "string", "enum": ["delete_file"] }
-                                },
-                                "required": ["op"]
-                            }
-                        ]
-                    }
-                }
-            },
-            "required": ["path", "operations"]
-        }))
-    }
-}
-
 /// Structured output from editing a file.
 pub struct EditOutput {
     pub input_path: String,
@@ -202,11 +98,13 @@ pub struct EditOutput {
     pub operations_applied: usize,
     pub final_diff_for_llm: String,
     pub final_diff_for_display: String,
+    /// What the project's formatter did to the written file, if one ran.
+    pub format_note: Option<String>,
 }
 
 impl ToolOutput for EditOutput {
     fn to_llm(&self) -> String {
-        let summary = if self.deleted {
+        let mut summary = if self.deleted {
             format!("deleted {}", self.path)
         } else if self.input_path != self.path {
             format!(
@@ -219,6 +117,9 @@ impl ToolOutput for EditOutput {
                 self.path, self.operations_applied
             )
         };
+        if let Some(note) = &self.format_note {
+            summary.push_str(&format!("; {note}"));
+        }
 
         if self.final_diff_for_llm.is_empty() {
             summary
@@ -235,6 +136,10 @@ impl ToolOutput for EditOutput {
 #[derive(Clone)]
 pub struct EditTool {
     pub(crate) cwd: std::path::PathBuf,
+    pub(crate) ignored: IgnoredPaths,
+    pub(crate) reviewer: EditReviewerSlot,
+    pub(crate) governor: ResourceGovernor,
+    pub(crate) formatters: Formatters,
 }
 
 impl Tool for EditTool {
@@ -250,19 +155,15 @@ impl Tool for EditTool {
     }
 
     async fn call(&self, input: EditInput) -> Result<EditOutput, agnt_llm::Error> {
-        if input.operations.is_empty() {
-            return Err(agnt_llm::Error::Other(
-                "operations must contain at least one entry".to_string(),
-            ));
-        }
-
         let input_path = input.path.trim();
         if input_path.is_empty() {
             return Err(agnt_llm::Error::Other("path cannot be empty".to_string()));
         }
 
+        self.ignored.check(&self.cwd.join(input_path), input_path)?;
         let mut state = EditState::load(self.cwd.clone(), input_path).await?;
         let initial_snapshot = snapshot_state(&state);
+        let original = state.file.as_ref().map(FileLines::render);
         for (idx, operation) in input.operations.iter().enumerate() {
             apply_operation(operation, &mut state).map_err(|err| {
                 agnt_llm::Error::Other(format!(
@@ -275,13 +176,66 @@ impl Tool for EditTool {
 
         let deleted = state.file.is_none();
         let final_path = state.current_path.clone();
+        self.ignored
+            .check(&self.cwd.join(&final_path), &final_path)?;
         let final_snapshot = snapshot_state(&state);
-        let final_diff_for_llm =
+        let mut final_diff_for_llm =
             render_unified_patch(&initial_snapshot, &final_snapshot, DiffLineFormat::Hashline);
-        let final_diff_for_display =
+        let mut final_diff_for_display =
             render_unified_patch(&initial_snapshot, &final_snapshot, DiffLineFormat::Raw);
+
+        let reviewer = self.reviewer.read().clone();
+        if let Some(reviewer) = reviewer {
+            let review = reviewer
+                .review(ProposedEdit {
+                    path: input_path.to_string(),
+                    new_path: final_path.clone(),
+                    original,
+                    proposed: state.file.as_ref().map(FileLines::render),
+                    diff: final_diff_for_display.clone(),
+                })
+                .await;
+            if let EditReview::Reject { reason } = review {
+                return Err(agnt_llm::Error::Other(format!(
+                    "edit rejected by the user: {reason}"
+                )));
+            }
+        }
+        let written = state.file.as_ref().map_or(0, |file| file.render().len());
+        self.governor.reserve_write(written as u64)?;
         state.persist().await?;
 
+        // Format what was written and diff the formatted file instead, so
+        // the anchors the model takes from the diff match the disk.
+        let mut format_note = None;
+        let final_abs = self.cwd.join(&final_path);
+        if !deleted && let Some((command, result)) = self.formatters.run(&final_abs).await {
+            format_note = Some(match result {
+                Ok(()) => {
+                    if let Some(formatted) = read_file_if_exists(&final_abs).await?
+                        && state.file.as_ref().map(|file| &file.lines) != Some(&formatted.lines)
+                    {
+                        state.file = Some(formatted);
+                        let formatted_snapshot = snapshot_state(&state);
+                        final_diff_for_llm = render_unified_patch(
+                            &initial_snapshot,
+                            &formatted_snapshot,
+                            DiffLineFormat::Hashline,
+                        );
+                        final_diff_for_display = render_unified_patch(
+                            &initial_snapshot,
+                            &formatted_snapshot,
+                            DiffLineFormat::Raw,
+                        );
+                        format!("formatted with `{command}`, included in the diff")
+                    } else {
+                        format!("already formatted per `{command}`")
+                    }
+                }
+                Err(err) => format!("formatter `{command}` failed, file left as written: {err}"),
+            });
+        }
+
         Ok(EditOutput {
             input_path: input_path.to_string(),
             path: final_path,
@@ -289,6 +243,7 @@ impl Tool for EditTool {
             operations_applied: input.operations.len(),
             final_diff_for_llm,
             final_diff_for_display,
+            format_note,
         })
     }
 
@@ -321,6 +276,31 @@ impl Tool for EditTool {
         let body = render_diff_body(&output.final_diff_for_display);
         ToolResultDisplay { title, body }
     }
+
+    fn audit(&self, _input: &EditInput, output: &EditOutput) -> ToolAudit {
+        let mut files = vec![output.input_path.clone()];
+        if output.path != output.input_path {
+            files.push(output.path.clone());
+        }
+        ToolAudit {
+            files,
+            exit_code: None,
+        }
+    }
+
+    async fn writes(&self, input: &EditInput) -> Vec<std::path::PathBuf> {
+        let moved_to = input
+            .operations
+            .iter()
+            .filter_map(|operation| match operation {
+                EditOperation::MoveFile { to } => Some(to.trim()),
+                _ => None,
+            });
+        std::iter::once(input.path.trim())
+            .chain(moved_to)
+            .map(|path| self.cwd.join(path))
+            .collect()
+    }
 }
 
 struct EditState {
@@ -388,10 +368,10 @@ impl EditState {
 }
 
 #[derive(Clone)]
-struct FileSnapshot {
-    path: String,
-    exists: bool,
-    lines: Vec<String>,
+pub(super) struct FileSnapshot {
+    pub(super) path: String,
+    pub(super) exists: bool,
+    pub(super) lines: Vec<String>,
 }
 
 fn snapshot_state(state: &EditState) -> FileSnapshot {
@@ -409,7 +389,7 @@ fn snapshot_state(state: &EditState) -> FileSnapshot {
     }
 }
 
-fn render_diff_body(diff: &str) -> Option<DisplayBody> {
+pub(super) fn render_diff_body(diff: &str) -> Option<DisplayBody> {
     if diff.is_empty() {
         None
     } else {
@@ -421,12 +401,12 @@ fn render_diff_body(diff: &str) -> Option<DisplayBody> {
 const HUNK_CONTEXT_LINES: usize = 5;
 
 #[derive(Copy, Clone)]
-enum DiffLineFormat {
+pub(super) enum DiffLineFormat {
     Hashline,
     Raw,
 }
 
-fn render_unified_patch(
+pub(super) fn render_unified_patch(
     before: &FileSnapshot,
     after: &FileSnapshot,
     line_format: DiffLineFormat,
@@ -538,7 +518,7 @@ fn apply_operation(operation: &EditOperation, state: &mut EditState) -> Result<(
                 .file
                 .as_mut()
                 .ok_or_else(|| format!("`{}` does not exist", state.current_path))?;
-            apply_line_operation(operation, &mut file.lines)
+            apply_line_operation(operation, &state.current_path, &mut file.lines)
         }
         EditOperation::RewriteFile { content } => {
             state.file = Some(FileLines::parse(content));
@@ -568,34 +548,38 @@ fn apply_operation(operation: &EditOperation, state: &mut EditState) -> Result<(
     }
 }
 
-fn apply_line_operation(operation: &EditOperation, lines: &mut Vec<String>) -> Result<(), String> {
+fn apply_line_operation(
+    operation: &EditOperation,
+    path: &str,
+    lines: &mut Vec<String>,
+) -> Result<(), String> {
     match operation {
         EditOperation::Replace { anchor, content } => {
-            let idx = resolve_anchor(anchor, lines)?;
-            lines.splice(idx..=idx, replacement_lines(content));
+            let (start, end) = resolve_span(anchor, path, lines)?;
+            lines.splice(start..=end, replacement_lines(content));
         }
         EditOperation::InsertBefore { anchor, content } => {
-            let idx = resolve_anchor(anchor, lines)?;
-            lines.splice(idx..idx, replacement_lines(content));
+            let (start, _) = resolve_span(anchor, path, lines)?;
+            lines.splice(start..start, replacement_lines(content));
         }
         EditOperation::InsertAfter { anchor, content } => {
-            let idx = resolve_anchor(anchor, lines)?;
-            lines.splice(idx + 1..idx + 1, replacement_lines(content));
+            let (_, end) = resolve_span(anchor, path, lines)?;
+            lines.splice(end + 1..end + 1, replacement_lines(content));
         }
         EditOperation::Delete { anchor } => {
-            let idx = resolve_anchor(anchor, lines)?;
-            lines.remove(idx);
+            let (start, end) = resolve_span(anchor, path, lines)?;
+            lines.drain(start..=end);
         }
         EditOperation::ReplaceRange {
             start,
             end,
             content,
         } => {
-            let (start_idx, end_idx) = resolve_range(start, end, lines)?;
+            let (start_idx, end_idx) = resolve_range(start, end, path, lines)?;
             lines.splice(start_idx..=end_idx, replacement_lines(content));
         }
         EditOperation::DeleteRange { start, end } => {
-            let (start_idx, end_idx) = resolve_range(start, end, lines)?;
+            let (start_idx, end_idx) = resolve_range(start, end, path, lines)?;
             lines.drain(start_idx..=end_idx);
         }
         _ => unreachable!("file-level operation routed to line-operation handler"),
@@ -604,9 +588,42 @@ fn apply_line_operation(operation: &EditOperation, lines: &mut Vec<String>) -> R
     Ok(())
 }
 
-fn resolve_range(start: &str, end: &str, lines: &[String]) -> Result<(usize, usize), String> {
-    let start_idx = resolve_anchor(start, lines)?;
-    let end_idx = resolve_anchor(end, lines)?;
+/// The lines an anchor addresses: the one line of a hashline anchor, or
+/// every line of the definition a symbol anchor names.
+#[cfg_attr(not(feature = "symbols"), allow(unused_variables))]
+fn resolve_span(anchor: &str, path: &str, lines: &[String]) -> Result<(usize, usize), String> {
+    let is_hashline = anchor
+        .trim()
+        .split_once(':')
+        .is_some_and(|(line_no, _)| line_no.parse::<usize>().is_ok());
+    if is_hashline {
+        let resolved = resolve_anchor(anchor, lines);
+        #[cfg(feature = "symbols")]
+        let resolved = resolved.map_err(|err| {
+            if super::symbols::supported(path) {
+                format!("{err}; re-read the file, or anchor on a symbol such as `fn name`")
+            } else {
+                err
+            }
+        });
+        return resolved.map(|idx| (idx, idx));
+    }
+    #[cfg(feature = "symbols")]
+    return super::symbols::resolve_symbol(anchor, path, lines);
+    #[cfg(not(feature = "symbols"))]
+    Err(format!(
+        "invalid anchor `{anchor}` (expected `line:hash`; symbol anchors need the `symbols` feature)"
+    ))
+}
+
+fn resolve_range(
+    start: &str,
+    end: &str,
+    path: &str,
+    lines: &[String],
+) -> Result<(usize, usize), String> {
+    let (start_idx, _) = resolve_span(start, path, lines)?;
+    let (_, end_idx) = resolve_span(end, path, lines)?;
     if start_idx > end_idx {
         return Err(format!(
             "range anchors are reversed (`{start}` resolves after `{end}`)"
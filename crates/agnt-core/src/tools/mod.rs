@@ -1,10 +1,28 @@
+mod artifact;
 mod bash;
+mod command_check;
 mod edit;
 mod hashline;
+mod plugin;
 mod read;
+#[cfg(feature = "symbols")]
+mod rename;
 mod skill;
+#[cfg(feature = "symbols")]
+mod symbols;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+pub use artifact::ArtifactTool;
 pub use bash::BashTool;
+pub(crate) use command_check::CommandChecker;
+pub use command_check::CommandWarning;
 pub use edit::EditTool;
+pub use plugin::{PLUGIN_PREFIX, PluginTool, plugin_paths};
 pub use read::ReadTool;
+#[cfg(feature = "symbols")]
+pub use rename::RenameTool;
 pub use skill::SkillTool;
+pub(crate) use skill::split_frontmatter;
+#[cfg(feature = "wasm")]
+pub use wasm::{WASM_TOOLS_DIR, WasmTool, WasmToolGrant, wasm_tool_paths};
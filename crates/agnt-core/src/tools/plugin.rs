@@ -0,0 +1,398 @@
+//! Tools provided by external executables, so they can be distributed
+//! without rebuilding agnt.
+//!
+//! Executables named `agnt-tool-*` on `PATH` are found by [`plugin_paths`];
+//! others can be listed explicitly. Each one speaks JSON over stdio:
+//!
+//! - `agnt-tool-x --describe` prints `{"name", "description", "parameters"}`,
+//!   where `parameters` is the JSON Schema of its input, and optionally
+//!   `"read_only": true` for tools that can't change the workspace.
+//! - Run without arguments, it reads the call's arguments as a JSON object
+//!   on stdin and prints `{"output": "..."}`, or `{"error": "..."}` when
+//!   the call failed.
+//!
+//! [WASM tools](super::wasm) speak the same protocol.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use agnt_llm::{Schema, ToolDefinition};
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
+use crate::tool::{ErasedTool, PreparedToolCall, ToolAudit, ToolExecResult, validate_arguments};
+
+/// File name prefix of plugin executables found on `PATH`.
+pub const PLUGIN_PREFIX: &str = "agnt-tool-";
+
+/// How long `--describe` may take before the plugin is skipped.
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Wall-clock time a single call may take before the plugin is killed.
+const MAX_RUN_TIME: Duration = Duration::from_secs(120);
+
+/// What a plugin says about itself in the `--describe` handshake.
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct Manifest {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl Manifest {
+    /// Read and check the output of `--describe`.
+    pub(super) fn parse(stdout: &[u8]) -> Result<Self, String> {
+        let manifest: Manifest = serde_json::from_slice(stdout)
+            .map_err(|err| format!("invalid --describe output: {err}"))?;
+        let valid_name = !manifest.name.is_empty()
+            && manifest.name.len() <= 64
+            && manifest
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err(format!(
+                "tool name `{}` must be 1-64 letters, digits, `_` or `-`",
+                manifest.name
+            ));
+        }
+        if !manifest.parameters.is_object() {
+            return Err("parameters must be a JSON Schema object".to_string());
+        }
+        Ok(manifest)
+    }
+
+    pub(super) fn schema(&self) -> Schema {
+        Schema::Raw(self.parameters.clone())
+    }
+
+    pub(super) fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.schema(),
+        }
+    }
+
+    /// Check `arguments` and prepare a call that `run` carries out with
+    /// them, returning the tool's stdout. A tool that may write `writes_in`
+    /// is taken to write the paths its arguments name there.
+    pub(super) fn prepare<F>(
+        &self,
+        arguments: &str,
+        writes_in: Option<&Path>,
+        run: impl FnOnce(serde_json::Value) -> F,
+    ) -> Result<PreparedToolCall, agnt_llm::Error>
+    where
+        F: Future<Output = Result<Vec<u8>, String>> + Send + 'static,
+    {
+        let arguments: serde_json::Value =
+            serde_json::from_str(arguments).map_err(|e| agnt_llm::Error::Other(e.to_string()))?;
+        validate_arguments(&self.schema(), &arguments)?;
+
+        let input_display = ToolCallDisplay {
+            title: self.name.clone(),
+            body: Some(DisplayBody::Code {
+                language: Some("json".to_string()),
+                content: serde_json::to_string_pretty(&arguments).unwrap_or_default(),
+            }),
+        };
+        let mut writes = Vec::new();
+        if let Some(dir) = writes_in {
+            named_paths(&arguments, dir, &mut writes);
+        }
+        let name = self.name.clone();
+        let stdout = run(arguments);
+        let future = Box::pin(async move {
+            let stdout = stdout
+                .await
+                .map_err(|err| agnt_llm::Error::Other(format!("{name}: {err}")))?;
+            let output = match serde_json::from_slice::<Response>(&stdout) {
+                Ok(Response {
+                    error: Some(error), ..
+                }) => return Err(agnt_llm::Error::Other(error)),
+                Ok(Response {
+                    output: Some(output),
+                    ..
+                }) => output,
+                _ => {
+                    return Err(agnt_llm::Error::Other(format!(
+                        "{name} printed no JSON response"
+                    )));
+                }
+            };
+            Ok(ToolExecResult {
+                llm_output: output.clone(),
+                output_display: ToolResultDisplay {
+                    title: name,
+                    body: Some(DisplayBody::Text(output)),
+                },
+                audit: ToolAudit::default(),
+            })
+        });
+        Ok(PreparedToolCall {
+            input_display,
+            writes: Box::pin(std::future::ready(writes)),
+            future,
+        })
+    }
+}
+
+/// The string values in `arguments` that could be paths, resolved against
+/// `dir`. What a plugin does with its arguments is unknown, so any of them
+/// may be a file it writes.
+fn named_paths(arguments: &serde_json::Value, dir: &Path, paths: &mut Vec<PathBuf>) {
+    match arguments {
+        serde_json::Value::String(value)
+            if !value.trim().is_empty() && !value.contains('\n') && value.len() < 4096 =>
+        {
+            paths.push(dir.join(value.trim()));
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                named_paths(item, dir, paths);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for value in fields.values() {
+                named_paths(value, dir, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What a plugin prints for a call.
+#[derive(Deserialize)]
+struct Response {
+    output: Option<String>,
+    error: Option<String>,
+}
+
+/// A tool backed by a plugin executable.
+#[derive(Debug, Clone)]
+pub struct PluginTool {
+    path: PathBuf,
+    cwd: PathBuf,
+    manifest: Manifest,
+}
+
+impl PluginTool {
+    /// Ask the executable at `path` to describe itself. Calls run in `cwd`.
+    pub fn load(path: &Path, cwd: PathBuf) -> Result<Self, String> {
+        let manifest = Manifest::parse(&describe(path)?)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            cwd,
+            manifest,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the plugin says it can't change the workspace, keeping it
+    /// available in read-only mode.
+    pub fn read_only(&self) -> bool {
+        self.manifest.read_only
+    }
+
+    /// Run the executable with `arguments` on stdin, returning its stdout.
+    /// It's killed once it runs longer than [`MAX_RUN_TIME`].
+    async fn call(self, arguments: serde_json::Value) -> Result<Vec<u8>, String> {
+        let mut child = Command::new(&self.path)
+            .current_dir(&self.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("failed to start: {e}"))?;
+        let run = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(arguments.to_string().as_bytes())
+                    .await
+                    .map_err(|e| format!("failed to write the arguments: {e}"))?;
+            }
+            child.wait_with_output().await.map_err(|e| e.to_string())
+        };
+        let output = match tokio::time::timeout(MAX_RUN_TIME, run).await {
+            Ok(output) => output?,
+            Err(_) => {
+                return Err(format!(
+                    "ran longer than {} seconds",
+                    MAX_RUN_TIME.as_secs()
+                ));
+            }
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.stdout.is_empty() && !stderr.trim().is_empty() {
+            return Err(format!("exited with {}: {}", output.status, stderr.trim()));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl ErasedTool for PluginTool {
+    fn definition(&self) -> ToolDefinition {
+        self.manifest.definition()
+    }
+
+    fn prepare(&self, arguments: &str) -> Result<PreparedToolCall, agnt_llm::Error> {
+        let this = self.clone();
+        // Nothing keeps a plugin to what its manifest says, so even a
+        // read-only one is checked.
+        self.manifest
+            .prepare(arguments, Some(&self.cwd), move |arguments| {
+                this.call(arguments)
+            })
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// Run `path --describe`, giving up after [`DESCRIBE_TIMEOUT`]. Its stdout
+/// is read while it runs, so a manifest larger than the pipe's buffer
+/// doesn't block it.
+fn describe(path: &Path) -> Result<Vec<u8>, String> {
+    let mut child = std::process::Command::new(path)
+        .arg("--describe")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("failed to start: {err}"))?;
+    let reader = child.stdout.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut stdout = Vec::new();
+            pipe.read_to_end(&mut stdout).map(|_| stdout)
+        })
+    });
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|err| err.to_string())? {
+            break status;
+        }
+        if started.elapsed() > DESCRIBE_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!(
+                "--describe took longer than {}s",
+                DESCRIBE_TIMEOUT.as_secs()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    if !status.success() {
+        return Err(format!("--describe exited with {status}"));
+    }
+    match reader {
+        Some(reader) => reader
+            .join()
+            .map_err(|_| "failed to read --describe output".to_string())?
+            .map_err(|err| err.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The `agnt-tool-*` executables on `PATH`, the first of each name as a
+/// shell would pick it, followed by `extra`.
+pub fn plugin_paths(extra: &[PathBuf]) -> Vec<PathBuf> {
+    let mut names = HashSet::new();
+    let mut paths = Vec::new();
+    for dir in std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+    {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(PLUGIN_PREFIX))
+                    && is_executable(path)
+            })
+            .collect();
+        found.sort();
+        for path in found {
+            if names.insert(path.file_name().map(|name| name.to_os_string())) {
+                paths.push(path);
+            }
+        }
+    }
+    for path in extra {
+        if !paths.contains(path) {
+            paths.push(path.clone());
+        }
+    }
+    paths
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, runtime};
+
+    #[test]
+    fn describes_and_calls_a_plugin() {
+        let temp = TempDir::new("plugin");
+        let dir = temp.path();
+        let path = dir.join("agnt-tool-echo");
+        std::fs::write(
+            &path,
+            r#"#!/bin/sh
+if [ "$1" = "--describe" ]; then
+  echo '{"name": "echo", "description": "Echo", "parameters": {"type": "object", "properties": {"text": {"type": "string"}}, "required": ["text"]}}'
+else
+  input=$(cat)
+  printf '{"output": %s}' "$(printf '%s' "$input" | sed 's/.*"text":\("[^"]*"\).*/\1/')"
+fi
+"#,
+        )
+        .unwrap();
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let tool = PluginTool::load(&path, dir.to_path_buf()).unwrap();
+        assert_eq!(tool.name(), "echo");
+        assert!(!tool.read_only());
+        assert!(tool.prepare(r#"{"text": 1}"#).is_err());
+
+        let prepared = tool.prepare(r#"{"text": "hi"}"#).unwrap();
+        let result = runtime().block_on(prepared.future).unwrap();
+        assert_eq!(result.llm_output, "hi");
+    }
+}
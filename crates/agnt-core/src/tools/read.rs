@@ -1,56 +1,25 @@
-use agnt_llm::{Describe, Property, Schema};
+use agnt_llm::Describe;
 use serde::Deserialize;
 
 use super::hashline::{FileLines, MAX_READ_LIMIT, hashline};
 use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
-use crate::tool::{Tool, ToolOutput};
+use crate::settings::IgnoredPaths;
+use crate::tool::{Tool, ToolAudit, ToolOutput};
 
 const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/read.md");
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Describe)]
 pub struct ReadInput {
     /// The file path to read, relative to the working directory.
     pub path: String,
     /// 0-based line offset to start reading from.
     pub offset: Option<usize>,
-    /// Max number of lines to return.
+    /// Maximum number of lines to return. If omitted, reads through end of
+    /// file. If provided, values above 20000 are clamped.
+    #[describe(minimum = 1)]
     pub limit: Option<usize>,
 }
 
-impl Describe for ReadInput {
-    fn describe() -> Schema {
-        Schema::Object {
-            description: None,
-            properties: vec![
-                Property {
-                    name: "path".into(),
-                    schema: Schema::String {
-                        description: Some(
-                            "File path to read, relative to the working directory".into(),
-                        ),
-                        enumeration: None,
-                    },
-                },
-                Property {
-                    name: "offset".into(),
-                    schema: Schema::Integer {
-                        description: Some("0-based line offset to start reading from".into()),
-                    },
-                },
-                Property {
-                    name: "limit".into(),
-                    schema: Schema::Integer {
-                        description: Some(format!(
-                            "Maximum number of lines to return. If omitted, reads through end of file. If provided, values above {MAX_READ_LIMIT} are clamped."
-                        )),
-                    },
-                },
-            ],
-            required: vec!["path".into()],
-        }
-    }
-}
-
 /// Structured output from reading a file.
 pub struct ReadOutput {
     pub path: String,
@@ -96,6 +65,7 @@ impl ToolOutput for ReadOutput {
 #[derive(Clone)]
 pub struct ReadTool {
     pub(crate) cwd: std::path::PathBuf,
+    pub(crate) ignored: IgnoredPaths,
 }
 
 impl Tool for ReadTool {
@@ -112,6 +82,7 @@ impl Tool for ReadTool {
 
     async fn call(&self, input: ReadInput) -> Result<ReadOutput, agnt_llm::Error> {
         let path = self.cwd.join(&input.path);
+        self.ignored.check(&path, &input.path)?;
         let content = tokio::fs::read_to_string(&path)
             .await
             .map_err(|e| agnt_llm::Error::Other(format!("{}: {e}", path.display())))?;
@@ -120,11 +91,6 @@ impl Tool for ReadTool {
         let total_lines = lines.len();
         let offset = input.offset.unwrap_or(0).min(total_lines);
         let limit = match input.limit {
-            Some(0) => {
-                return Err(agnt_llm::Error::Other(
-                    "limit must be at least 1".to_string(),
-                ));
-            }
             Some(requested_limit) => requested_limit.min(MAX_READ_LIMIT),
             None => total_lines.saturating_sub(offset),
         };
@@ -188,10 +154,20 @@ impl Tool for ReadTool {
             }),
         }
     }
+
+    fn audit(&self, input: &ReadInput, _output: &ReadOutput) -> ToolAudit {
+        ToolAudit {
+            files: vec![input.path.clone()],
+            exit_code: None,
+        }
+    }
+    fn cacheable(&self) -> bool {
+        true
+    }
 }
 
 /// Guess a language name from a file extension for syntax highlighting.
-fn lang_from_ext(path: &str) -> Option<String> {
+pub(super) fn lang_from_ext(path: &str) -> Option<String> {
     let ext = path.rsplit('.').next()?;
     let lang = match ext {
         "rs" => "rust",
@@ -0,0 +1,462 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use agnt_llm::Describe;
+use serde::Deserialize;
+
+use super::edit::{DiffLineFormat, FileSnapshot, render_diff_body, render_unified_patch};
+use super::hashline::FileLines;
+use super::symbols;
+use crate::event::{ToolCallDisplay, ToolResultDisplay};
+use crate::governor::ResourceGovernor;
+use crate::review::{EditReview, EditReviewerSlot, ProposedEdit};
+use crate::settings::IgnoredPaths;
+use crate::tool::{Tool, ToolAudit, ToolOutput};
+
+const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/rename.md");
+
+/// Files larger than this are skipped when looking for uses.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Conflicting definitions listed when a rename is refused.
+const MAX_CONFLICTS_LISTED: usize = 5;
+
+#[derive(Clone, Deserialize, Describe)]
+pub struct RenameInput {
+    /// The file defining the symbol, relative to the working directory.
+    #[describe(min_length = 1)]
+    pub path: String,
+    /// The symbol to rename, e.g. `fn parse_config` or `Config::load`.
+    #[describe(min_length = 1)]
+    pub symbol: String,
+    /// The new name.
+    #[describe(min_length = 1)]
+    pub new_name: String,
+    /// Files or directories to rename in, relative to the working
+    /// directory. Defaults to all of it.
+    #[serde(default)]
+    pub scope: Vec<String>,
+}
+
+/// Structured output from renaming a symbol.
+pub struct RenameOutput {
+    pub old_name: String,
+    pub new_name: String,
+    pub files: Vec<String>,
+    pub occurrences: usize,
+    pub final_diff_for_llm: String,
+    pub final_diff_for_display: String,
+}
+
+impl ToolOutput for RenameOutput {
+    fn to_llm(&self) -> String {
+        format!(
+            "renamed `{}` to `{}`: {} occurrence(s) in {} file(s)\n\nfinal diff (hashline-formatted):\n{}",
+            self.old_name,
+            self.new_name,
+            self.occurrences,
+            self.files.len(),
+            self.final_diff_for_llm
+        )
+    }
+}
+
+/// Tool that renames a symbol and its uses across the workspace.
+#[derive(Clone)]
+pub struct RenameTool {
+    pub(crate) cwd: PathBuf,
+    pub(crate) ignored: IgnoredPaths,
+    pub(crate) reviewer: EditReviewerSlot,
+    pub(crate) governor: ResourceGovernor,
+}
+
+/// A file the rename changes.
+struct RenamedFile {
+    path: String,
+    occurrences: usize,
+    original: FileLines,
+    renamed: FileLines,
+}
+
+impl Tool for RenameTool {
+    type Input = RenameInput;
+    type Output = RenameOutput;
+
+    fn name(&self) -> &str {
+        "rename"
+    }
+
+    fn description(&self) -> &str {
+        TOOL_DESCRIPTION
+    }
+
+    async fn call(&self, input: RenameInput) -> Result<RenameOutput, agnt_llm::Error> {
+        let path = input.path.trim().to_string();
+        self.ignored.check(&self.cwd.join(&path), &path)?;
+        let old_name = symbols::anchor_name(&input.symbol).to_string();
+        let new_name = input.new_name.trim().to_string();
+        let valid_name = new_name
+            .chars()
+            .next()
+            .is_some_and(|first| !first.is_ascii_digit())
+            && new_name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+        if !valid_name {
+            return Err(agnt_llm::Error::Other(format!(
+                "`{new_name}` is not a valid identifier"
+            )));
+        }
+        if new_name == old_name {
+            return Err(agnt_llm::Error::Other(format!(
+                "`{old_name}` already has that name"
+            )));
+        }
+
+        let this = self.clone();
+        let symbol = input.symbol.clone();
+        let scope = input.scope.clone();
+        let names = (old_name.clone(), new_name.clone());
+        let files = tokio::task::spawn_blocking(move || {
+            this.plan(&path, &symbol, &names.0, &names.1, &scope)
+        })
+        .await
+        .map_err(|err| agnt_llm::Error::Other(err.to_string()))?
+        .map_err(agnt_llm::Error::Other)?;
+
+        let mut final_diff_for_llm = String::new();
+        let mut final_diff_for_display = String::new();
+        let mut occurrences = 0;
+        let reviewer = self.reviewer.read().clone();
+        for file in &files {
+            let before = FileSnapshot {
+                path: file.path.clone(),
+                exists: true,
+                lines: file.original.lines.clone(),
+            };
+            let after = FileSnapshot {
+                path: file.path.clone(),
+                exists: true,
+                lines: file.renamed.lines.clone(),
+            };
+            let diff = render_unified_patch(&before, &after, DiffLineFormat::Raw);
+            final_diff_for_llm.push_str(&render_unified_patch(
+                &before,
+                &after,
+                DiffLineFormat::Hashline,
+            ));
+            final_diff_for_display.push_str(&diff);
+            occurrences += file.occurrences;
+
+            if let Some(reviewer) = &reviewer {
+                let review = reviewer
+                    .review(ProposedEdit {
+                        path: file.path.clone(),
+                        new_path: file.path.clone(),
+                        original: Some(file.original.render()),
+                        proposed: Some(file.renamed.render()),
+                        diff,
+                    })
+                    .await;
+                if let EditReview::Reject { reason } = review {
+                    return Err(agnt_llm::Error::Other(format!(
+                        "rename rejected by the user at {}: {reason}",
+                        file.path
+                    )));
+                }
+            }
+        }
+
+        let written: usize = files.iter().map(|file| file.renamed.render().len()).sum();
+        self.governor.reserve_write(written as u64)?;
+        self.write_all(&files).await?;
+
+        Ok(RenameOutput {
+            old_name,
+            new_name,
+            files: files.into_iter().map(|file| file.path).collect(),
+            occurrences,
+            final_diff_for_llm,
+            final_diff_for_display,
+        })
+    }
+
+    fn render_input(&self, input: &RenameInput) -> ToolCallDisplay {
+        ToolCallDisplay {
+            title: format!(
+                "Rename {} -> {}",
+                symbols::anchor_name(&input.symbol),
+                input.new_name
+            ),
+            body: None,
+        }
+    }
+
+    fn render_output(&self, _input: &RenameInput, output: &RenameOutput) -> ToolResultDisplay {
+        ToolResultDisplay {
+            title: format!(
+                "Renamed {} -> {} ({} occurrences in {} files)",
+                output.old_name,
+                output.new_name,
+                output.occurrences,
+                output.files.len()
+            ),
+            body: render_diff_body(&output.final_diff_for_display),
+        }
+    }
+
+    fn audit(&self, _input: &RenameInput, output: &RenameOutput) -> ToolAudit {
+        ToolAudit {
+            files: output.files.clone(),
+            exit_code: None,
+        }
+    }
+
+    /// The files in scope that mention the old name, which the rename
+    /// changes at most.
+    async fn writes(&self, input: &RenameInput) -> Vec<PathBuf> {
+        let path = input.path.trim().to_string();
+        let Some(family) = symbols::family(&path) else {
+            return Vec::new();
+        };
+        let this = self.clone();
+        let old_name = symbols::anchor_name(&input.symbol).to_string();
+        let scope = input.scope.clone();
+        tokio::task::spawn_blocking(move || {
+            this.files_in_scope(&scope, family)
+                .into_iter()
+                .map(|file| this.cwd.join(file))
+                .filter(|file| {
+                    std::fs::read_to_string(file).is_ok_and(|content| content.contains(&old_name))
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_default()
+    }
+}
+
+impl RenameTool {
+    /// Find the files renaming `old_name` to `new_name` changes, after
+    /// checking `symbol` is defined in `path`, nothing else of that name is
+    /// defined in `scope` and `new_name` isn't taken next to it.
+    fn plan(
+        &self,
+        path: &str,
+        symbol: &str,
+        old_name: &str,
+        new_name: &str,
+        scope: &[String],
+    ) -> Result<Vec<RenamedFile>, String> {
+        let family =
+            symbols::family(path).ok_or_else(|| format!("renaming isn't supported in `{path}`"))?;
+        let source =
+            std::fs::read_to_string(self.cwd.join(path)).map_err(|err| format!("{path}: {err}"))?;
+        let target =
+            symbols::rename_target(symbol, path, &FileLines::parse(&source).lines.join("\n"))?;
+
+        let mut renamed = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut clashes = Vec::new();
+        for file_path in self.files_in_scope(scope, family) {
+            let Ok(content) = std::fs::read_to_string(self.cwd.join(&file_path)) else {
+                continue;
+            };
+            let original = FileLines::parse(&content);
+            let source = original.lines.join("\n");
+            let Ok(found) = symbols::occurrences(&file_path, &source, &target, new_name) else {
+                continue;
+            };
+            conflicts.extend(
+                found
+                    .definitions
+                    .into_iter()
+                    .filter(|(_, line)| !(file_path == path && *line == target.line))
+                    .map(|(label, line)| format!("{label} at {file_path}:{}", line + 1)),
+            );
+            clashes.extend(
+                found
+                    .clashes
+                    .into_iter()
+                    .map(|(label, line)| format!("{label} at {file_path}:{}", line + 1)),
+            );
+            if found.identifiers.is_empty() {
+                continue;
+            }
+            let occurrences = found.identifiers.len();
+            let mut text = source;
+            for range in found.identifiers.into_iter().rev() {
+                text.replace_range(range, new_name);
+            }
+            renamed.push(RenamedFile {
+                path: file_path,
+                occurrences,
+                renamed: FileLines {
+                    lines: text.split('\n').map(str::to_string).collect(),
+                    line_ending: original.line_ending.clone(),
+                    trailing_newline: original.trailing_newline,
+                },
+                original,
+            });
+        }
+
+        if !conflicts.is_empty() {
+            let more = conflicts.len().saturating_sub(MAX_CONFLICTS_LISTED);
+            conflicts.truncate(MAX_CONFLICTS_LISTED);
+            return Err(format!(
+                "`{old_name}` is also defined as {}{}; narrow `scope` or use `edit`",
+                conflicts.join(", "),
+                if more > 0 {
+                    format!(" and {more} more")
+                } else {
+                    String::new()
+                }
+            ));
+        }
+        if !clashes.is_empty() {
+            clashes.truncate(MAX_CONFLICTS_LISTED);
+            return Err(format!(
+                "`{new_name}` is already defined in the same scope as {}",
+                clashes.join(", ")
+            ));
+        }
+        if !renamed.iter().any(|file| file.path == path) {
+            return Err(format!("`{path}` is outside the scope of the rename"));
+        }
+        Ok(renamed)
+    }
+
+    /// Write all renamed files or none: each is staged next to the file
+    /// first and moved over it once all are staged, and files already
+    /// replaced get their original content back if a move fails.
+    async fn write_all(&self, files: &[RenamedFile]) -> Result<(), agnt_llm::Error> {
+        let mut staged = Vec::new();
+        for file in files {
+            let abs_path = self.cwd.join(&file.path);
+            let name = abs_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let staging = abs_path.with_file_name(format!(".{name}.agnt-rename"));
+            let result = async {
+                tokio::fs::write(&staging, file.renamed.render()).await?;
+                let permissions = tokio::fs::metadata(&abs_path).await?.permissions();
+                tokio::fs::set_permissions(&staging, permissions).await
+            }
+            .await;
+            staged.push((staging, abs_path));
+            if let Err(err) = result {
+                for (staging, _) in &staged {
+                    let _ = tokio::fs::remove_file(staging).await;
+                }
+                return Err(agnt_llm::Error::Other(format!("{}: {err}", file.path)));
+            }
+        }
+
+        for (index, (staging, abs_path)) in staged.iter().enumerate() {
+            if let Err(err) = tokio::fs::rename(staging, abs_path).await {
+                for (staging, _) in &staged[index..] {
+                    let _ = tokio::fs::remove_file(staging).await;
+                }
+                for (file, (_, abs_path)) in files.iter().zip(&staged[..index]) {
+                    let _ = tokio::fs::write(abs_path, file.original.render()).await;
+                }
+                return Err(agnt_llm::Error::Other(format!(
+                    "{}: {err}; no file was renamed",
+                    files[index].path
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Files of `family` under the scope paths, relative to the working
+    /// directory, skipping ignored, hidden and large files.
+    fn files_in_scope(&self, scope: &[String], family: &str) -> BTreeSet<String> {
+        let roots: Vec<PathBuf> = if scope.is_empty() {
+            vec![self.cwd.clone()]
+        } else {
+            scope
+                .iter()
+                .map(|path| self.cwd.join(path.trim()))
+                .collect()
+        };
+        let mut files = BTreeSet::new();
+        for root in roots {
+            for entry in ignore::WalkBuilder::new(&root).build().flatten() {
+                let abs_path = entry.path();
+                let Some(relative) = relative_path(&self.cwd, abs_path) else {
+                    continue;
+                };
+                let small_file = entry
+                    .metadata()
+                    .is_ok_and(|metadata| metadata.is_file() && metadata.len() <= MAX_FILE_BYTES);
+                if small_file
+                    && symbols::family(&relative) == Some(family)
+                    && !self.ignored.is_ignored(abs_path)
+                {
+                    files.insert(relative);
+                }
+            }
+        }
+        files
+    }
+}
+
+fn relative_path(cwd: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(cwd).ok()?.to_str()?;
+    Some(relative.replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, runtime};
+
+    #[test]
+    fn renames_a_function_across_files() {
+        let temp = TempDir::new("rename");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("src/config.rs"),
+            "pub fn parse_config() -> u32 {\n    1\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("src/main.rs"),
+            "use crate::config::parse_config;\n\nfn main() {\n    let parse_config_count = parse_config();\n}\n",
+        )
+        .unwrap();
+        let tool = RenameTool {
+            cwd: dir.to_path_buf(),
+            ignored: IgnoredPaths::new(dir, &[]),
+            reviewer: EditReviewerSlot::default(),
+            governor: ResourceGovernor::default(),
+        };
+        let runtime = runtime();
+
+        let output = runtime
+            .block_on(tool.call(RenameInput {
+                path: "src/config.rs".to_string(),
+                symbol: "fn parse_config".to_string(),
+                new_name: "load_config".to_string(),
+                scope: Vec::new(),
+            }))
+            .unwrap();
+        assert_eq!(output.occurrences, 3);
+        assert_eq!(output.files, ["src/config.rs", "src/main.rs"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("src/main.rs")).unwrap(),
+            "use crate::config::load_config;\n\nfn main() {\n    let parse_config_count = load_config();\n}\n"
+        );
+
+        std::fs::write(dir.join("src/other.rs"), "fn load_config() {}\n").unwrap();
+        let conflict = runtime.block_on(tool.call(RenameInput {
+            path: "src/config.rs".to_string(),
+            symbol: "load_config".to_string(),
+            new_name: "read_config".to_string(),
+            scope: Vec::new(),
+        }));
+        assert!(conflict.is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use agnt_llm::{Describe, Property, Schema};
+use agnt_llm::Describe;
 use serde::Deserialize;
 
 use crate::event::{DisplayBody, ToolCallDisplay, ToolResultDisplay};
@@ -9,28 +9,12 @@ use crate::tool::Tool;
 
 const TOOL_DESCRIPTION: &str = include_str!("../../resources/tools/skill.md");
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Describe)]
 pub struct SkillInput {
     /// Skill name to load from `.agents/skills`.
     pub name: String,
 }
 
-impl Describe for SkillInput {
-    fn describe() -> Schema {
-        Schema::Object {
-            description: None,
-            properties: vec![Property {
-                name: "name".into(),
-                schema: Schema::String {
-                    description: Some("Skill name to load from .agents/skills".into()),
-                    enumeration: None,
-                },
-            }],
-            required: vec!["name".into()],
-        }
-    }
-}
-
 #[derive(Clone)]
 struct SkillEntry {
     name: String,
@@ -101,6 +85,9 @@ impl Tool for SkillTool {
             }),
         }
     }
+    fn cacheable(&self) -> bool {
+        true
+    }
 }
 
 fn build_tool_description(skills_dir: &Path) -> String {
@@ -195,7 +182,7 @@ fn parse_skill_metadata(content: &str, fallback_name: &str) -> (String, String)
     (name, description)
 }
 
-fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+pub(crate) fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
     let body_start = content
         .strip_prefix("---\n")
         .or_else(|| content.strip_prefix("---\r\n"))?;
@@ -0,0 +1,621 @@
+//! Symbol anchors for the `edit` tool: `fn parse_config` or
+//! `Config::load` address a definition by name instead of by line, so an
+//! edit still lands when lines moved since the file was read. Definitions
+//! are found by parsing the file with tree-sitter, which also finds the
+//! identifiers the `rename` tool rewrites.
+
+use std::ops::Range;
+
+use tree_sitter::{Language, Node, Parser, Tree};
+
+/// A definition's keyword, the names it's nested in and its own name, and
+/// its line span.
+struct Symbol {
+    keyword: &'static str,
+    path: Vec<String>,
+    /// The type, trait or class the definition is directly an item of.
+    owner: Option<String>,
+    /// Whether it's an item of a trait or of a trait's `impl` block.
+    in_trait: bool,
+    /// Whether it's a method, called on a receiver.
+    method: bool,
+    start: usize,
+    end: usize,
+}
+
+impl Symbol {
+    fn label(&self) -> String {
+        format!("{} {}", self.keyword, self.path.join("::"))
+    }
+}
+
+/// Node kinds that define a symbol, with the keyword anchors name them by.
+type Definitions = &'static [(&'static str, &'static str)];
+
+const RUST: Definitions = &[
+    ("function_item", "fn"),
+    ("function_signature_item", "fn"),
+    ("struct_item", "struct"),
+    ("enum_item", "enum"),
+    ("union_item", "union"),
+    ("trait_item", "trait"),
+    ("impl_item", "impl"),
+    ("mod_item", "mod"),
+    ("const_item", "const"),
+    ("static_item", "static"),
+    ("type_item", "type"),
+    ("macro_definition", "macro_rules!"),
+];
+
+const PYTHON: Definitions = &[
+    ("function_definition", "def"),
+    ("class_definition", "class"),
+];
+
+const JAVASCRIPT: Definitions = &[
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("class_declaration", "class"),
+    ("method_definition", "method"),
+];
+
+const TYPESCRIPT: Definitions = &[
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("class_declaration", "class"),
+    ("abstract_class_declaration", "class"),
+    ("method_definition", "method"),
+    ("interface_declaration", "interface"),
+    ("type_alias_declaration", "type"),
+    ("enum_declaration", "enum"),
+];
+
+const GO: Definitions = &[
+    ("function_declaration", "func"),
+    ("method_declaration", "func"),
+    ("type_spec", "type"),
+];
+
+/// Definition kinds whose items are referred to through them, like
+/// `Config::new` or `self.load`.
+const OWNERS: &[&str] = &[
+    "impl_item",
+    "trait_item",
+    "class_definition",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+];
+
+/// Identifier kinds that name a field or property of a value, never an
+/// item defined on its own.
+const MEMBER_IDENTIFIERS: &[&str] = &[
+    "field_identifier",
+    "shorthand_field_identifier",
+    "property_identifier",
+    "shorthand_property_identifier",
+];
+
+/// The grammar, definitions and [family](family) for `path`, by its
+/// extension.
+fn language(path: &str) -> Option<(Language, Definitions, &'static str)> {
+    let extension = std::path::Path::new(path).extension()?.to_str()?;
+    Some(match extension {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), RUST, "rust"),
+        "py" | "pyi" => (tree_sitter_python::LANGUAGE.into(), PYTHON, "python"),
+        "js" | "jsx" | "mjs" | "cjs" => (
+            tree_sitter_javascript::LANGUAGE.into(),
+            JAVASCRIPT,
+            "javascript",
+        ),
+        "ts" | "mts" | "cts" => (
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            TYPESCRIPT,
+            "javascript",
+        ),
+        "tsx" => (
+            tree_sitter_typescript::LANGUAGE_TSX.into(),
+            TYPESCRIPT,
+            "javascript",
+        ),
+        "go" => (tree_sitter_go::LANGUAGE.into(), GO, "go"),
+        _ => return None,
+    })
+}
+
+/// Whether symbol anchors work in `path`.
+pub(crate) fn supported(path: &str) -> bool {
+    language(path).is_some()
+}
+
+/// Files that can refer to each other's symbols share a family:
+/// JavaScript and TypeScript do, other languages are on their own.
+pub(crate) fn family(path: &str) -> Option<&'static str> {
+    language(path).map(|(_, _, family)| family)
+}
+
+fn parse(path: &str, source: &str) -> Result<(Tree, Definitions), String> {
+    let (language, definitions, _) =
+        language(path).ok_or_else(|| format!("symbol anchors aren't supported in `{path}`"))?;
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|err| err.to_string())?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| format!("failed to parse `{path}`"))?;
+    Ok((tree, definitions))
+}
+
+/// The keyword an anchor leads with, if it's one of `definitions`, and the
+/// names it's made of, outermost first.
+fn parse_anchor(
+    anchor: &str,
+    definitions: Definitions,
+) -> Result<(Option<&str>, Vec<&str>), String> {
+    let anchor = anchor.trim();
+    let (keyword, name) = match anchor.split_once(char::is_whitespace) {
+        Some((keyword, name)) if definitions.iter().any(|(_, k)| *k == keyword) => {
+            (Some(keyword), name.trim())
+        }
+        _ => (None, anchor),
+    };
+    let query: Vec<&str> = name.split("::").flat_map(|part| part.split('.')).collect();
+    if query.iter().any(|part| part.is_empty()) {
+        return Err(format!("invalid symbol anchor `{anchor}`"));
+    }
+    Ok((keyword, query))
+}
+
+/// The name a symbol anchor ends with, e.g. `load` for `Config::load`.
+pub(crate) fn anchor_name(anchor: &str) -> &str {
+    anchor
+        .trim()
+        .rsplit(|c: char| c.is_whitespace() || c == ':' || c == '.')
+        .next()
+        .unwrap_or_default()
+}
+
+/// A definition to rename, with what its references look like.
+pub(crate) struct Target {
+    /// The file defining it.
+    file: String,
+    name: String,
+    /// Line index of the definition.
+    pub line: usize,
+    /// The type it's an item of, e.g. `Config` for `Config::new`.
+    owner: Option<String>,
+    /// The names it's nested in.
+    scope: Vec<String>,
+    /// The Go package or Python module defining it, which qualifies
+    /// references from other files.
+    module: Option<String>,
+}
+
+/// Resolve `anchor` in `source`, the file at `path`, to a definition the
+/// `rename` tool can rename. Methods and trait items are refused: which
+/// calls refer to them depends on the receiver's type, which needs a
+/// language server to know.
+pub(crate) fn rename_target(anchor: &str, path: &str, source: &str) -> Result<Target, String> {
+    let (tree, definitions) = parse(path, source)?;
+    let symbol = resolve(anchor, path, source, &tree, definitions)?;
+    let label = symbol.label();
+    if symbol.keyword == "impl" {
+        return Err(format!(
+            "`{label}` is an `impl` block; rename the type it implements instead"
+        ));
+    }
+    if symbol.method {
+        return Err(format!(
+            "`{label}` is a method; which calls refer to it depends on their receiver's \
+             type, so rename it with `edit`"
+        ));
+    }
+    if symbol.in_trait {
+        return Err(format!(
+            "`{label}` belongs to a trait; its implementations have to be renamed with \
+             it, so use `edit`"
+        ));
+    }
+    let module = match family(path) {
+        Some("go") => {
+            let root = tree.root_node();
+            let mut cursor = root.walk();
+            root.named_children(&mut cursor)
+                .find(|child| child.kind() == "package_clause")
+                .and_then(|clause| clause.named_child(0))
+                .and_then(|name| name.utf8_text(source.as_bytes()).ok())
+                .map(str::to_string)
+        }
+        Some("python") => std::path::Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string),
+        _ => None,
+    };
+    let mut scope = symbol.path;
+    let name = scope.pop().unwrap_or_default();
+    Ok(Target {
+        file: path.to_string(),
+        name,
+        line: symbol.start,
+        owner: symbol.owner,
+        scope,
+        module,
+    })
+}
+
+/// Where the rename of `target` to `new_name` lands in `source`, the file
+/// at `path`.
+pub(crate) struct Occurrences {
+    /// Other definitions of the target's name that references can't be
+    /// told apart from, labelled like `fn name`, with their line index.
+    /// `impl` blocks don't count.
+    pub definitions: Vec<(String, usize)>,
+    /// Definitions of `new_name` in the same scope as the target.
+    pub clashes: Vec<(String, usize)>,
+    /// Byte ranges of the identifiers referring to the target.
+    pub identifiers: Vec<Range<usize>>,
+}
+
+pub(crate) fn occurrences(
+    path: &str,
+    source: &str,
+    target: &Target,
+    new_name: &str,
+) -> Result<Occurrences, String> {
+    let (tree, definitions) = parse(path, source)?;
+    let mut symbols = Vec::new();
+    collect(
+        tree.root_node(),
+        source.as_bytes(),
+        definitions,
+        None,
+        &mut Vec::new(),
+        &mut symbols,
+    );
+    let mut identifiers = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if node.child_count() == 0
+            && node.kind().contains("identifier")
+            && node.utf8_text(source.as_bytes()) == Ok(target.name.as_str())
+            && refers_to(node, source.as_bytes(), definitions, path, target)
+        {
+            identifiers.push(node.byte_range());
+        }
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+        while cursor.goto_parent() {
+            if cursor.goto_next_sibling() {
+                continue 'walk;
+            }
+        }
+        break;
+    }
+
+    let same_module = path == target.file
+        || (family(path) == Some("go")
+            && std::path::Path::new(path).parent() == std::path::Path::new(&target.file).parent());
+    let clashes = symbols
+        .iter()
+        .filter(|symbol| symbol.path.last().is_some_and(|own| own == new_name))
+        .filter(|symbol| match &target.owner {
+            Some(_) => symbol.owner == target.owner,
+            None => same_module && symbol.path[..symbol.path.len() - 1] == target.scope[..],
+        })
+        .map(|symbol| (symbol.label(), symbol.start))
+        .collect();
+    Ok(Occurrences {
+        definitions: symbols
+            .into_iter()
+            .filter(|symbol| {
+                symbol.keyword != "impl"
+                    && symbol.owner == target.owner
+                    && symbol.path.last() == Some(&target.name)
+            })
+            .map(|symbol| (format!("{} {}", symbol.keyword, target.name), symbol.start))
+            .collect(),
+        clashes,
+        identifiers,
+    })
+}
+
+/// Whether the identifier `node`, spelled like the target, refers to it:
+/// a member access like `x.name` never does, a path like `Vec::new` only
+/// when it's qualified by the target's type, a definition only when it's
+/// the target, and an item of a type only through a path.
+fn refers_to(
+    node: Node,
+    source: &[u8],
+    definitions: Definitions,
+    path: &str,
+    target: &Target,
+) -> bool {
+    let Some(parent) = node.parent() else {
+        return target.owner.is_none();
+    };
+    let text = |node: Node| node.utf8_text(source).unwrap_or_default().to_string();
+    let is_field = |field: &str| parent.child_by_field_name(field) == Some(node);
+    let qualified_by_module = |field: &str| {
+        let qualifier = parent
+            .child_by_field_name(field)
+            .filter(|qualifier| matches!(qualifier.kind(), "identifier" | "package_identifier"))
+            .map(text);
+        target.owner.is_none() && target.module.is_some() && qualifier == target.module
+    };
+
+    // Package or module qualified, like `config.Parse` in Go or
+    // `config.parse` in Python.
+    if parent.kind() == "selector_expression" && is_field("field") {
+        return qualified_by_module("operand");
+    }
+    if parent.kind() == "qualified_type" && is_field("name") {
+        return qualified_by_module("package");
+    }
+    if parent.kind() == "attribute" && is_field("attribute") {
+        return qualified_by_module("object");
+    }
+    if MEMBER_IDENTIFIERS.contains(&node.kind())
+        || (parent.kind() == "keyword_argument" && is_field("name"))
+    {
+        return false;
+    }
+
+    if matches!(
+        parent.kind(),
+        "scoped_identifier" | "scoped_type_identifier"
+    ) && is_field("name")
+    {
+        let Some(qualifier) = parent.child_by_field_name("path").map(text) else {
+            return target.owner.is_none();
+        };
+        let qualifier = qualifier.split('<').next().unwrap_or_default();
+        let qualifier = qualifier
+            .trim_end_matches(':')
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .trim();
+        return match &target.owner {
+            Some(owner) => {
+                qualifier == owner || (qualifier == "Self" && inside_impl_of(node, source, owner))
+            }
+            None => qualifier != "Self" && !qualifier.starts_with(char::is_uppercase),
+        };
+    }
+
+    if is_field("name") && definitions.iter().any(|(kind, _)| *kind == parent.kind()) {
+        return path == target.file && node.start_position().row == target.line;
+    }
+    target.owner.is_none()
+}
+
+/// Whether `node` is inside an `impl` block of `owner`.
+fn inside_impl_of(node: Node, source: &[u8], owner: &str) -> bool {
+    let mut ancestor = node.parent();
+    while let Some(node) = ancestor {
+        if node.kind() == "impl_item" {
+            return symbol_name(node, source).as_deref() == Some(owner);
+        }
+        ancestor = node.parent();
+    }
+    false
+}
+
+/// The first and last line index of the definition `anchor` names in
+/// `lines` of the file at `path`. An anchor is a name, optionally led by a
+/// keyword (`fn`, `class`, ...) and qualified by the names it's nested in
+/// (`Config::load` or `Config.load`).
+pub(crate) fn resolve_symbol(
+    anchor: &str,
+    path: &str,
+    lines: &[String],
+) -> Result<(usize, usize), String> {
+    let source = lines.join("\n");
+    let (tree, definitions) = parse(path, &source)?;
+    let symbol = resolve(anchor, path, &source, &tree, definitions)?;
+    Ok((symbol.start, symbol.end))
+}
+
+fn resolve(
+    anchor: &str,
+    path: &str,
+    source: &str,
+    tree: &Tree,
+    definitions: Definitions,
+) -> Result<Symbol, String> {
+    let anchor = anchor.trim();
+    let (keyword, query) = parse_anchor(anchor, definitions)?;
+    let mut symbols = Vec::new();
+    collect(
+        tree.root_node(),
+        source.as_bytes(),
+        definitions,
+        None,
+        &mut Vec::new(),
+        &mut symbols,
+    );
+
+    let mut matches: Vec<Symbol> = symbols
+        .into_iter()
+        .filter(|symbol| keyword.is_none_or(|keyword| symbol.keyword == keyword))
+        .filter(|symbol| matches_path(&symbol.path, &query))
+        .collect();
+    match matches.len() {
+        0 => Err(format!("symbol `{anchor}` not found in `{path}`")),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let candidates = matches
+                .iter()
+                .take(4)
+                .map(|symbol| format!("{} at line {}", symbol.label(), symbol.start + 1))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(format!(
+                "symbol `{anchor}` is ambiguous ({candidates}); qualify it with the \
+                 enclosing name, e.g. `Type::name`"
+            ))
+        }
+    }
+}
+
+/// Whether `query` ends with the symbol's own name and the rest of it
+/// names enclosing definitions, innermost last.
+fn matches_path(path: &[String], query: &[&str]) -> bool {
+    let Some((name, outer)) = query.split_last() else {
+        return false;
+    };
+    let Some((own, enclosing)) = path.split_last() else {
+        return false;
+    };
+    if own != name {
+        return false;
+    }
+    let mut enclosing = enclosing.iter();
+    outer
+        .iter()
+        .all(|part| enclosing.any(|candidate| candidate == part))
+}
+
+fn collect<'tree>(
+    node: Node<'tree>,
+    source: &[u8],
+    definitions: Definitions,
+    enclosing: Option<Node<'tree>>,
+    path: &mut Vec<String>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let definition = definitions
+        .iter()
+        .find(|(kind, _)| *kind == node.kind())
+        .and_then(|(_, keyword)| Some((*keyword, symbol_name(node, source)?)));
+    if let Some((keyword, name)) = &definition {
+        path.push(name.clone());
+        let owner = enclosing.filter(|enclosing| OWNERS.contains(&enclosing.kind()));
+        let end = node.end_position();
+        symbols.push(Symbol {
+            keyword,
+            path: path.clone(),
+            owner: owner.and_then(|owner| symbol_name(owner, source)),
+            in_trait: owner.is_some_and(|owner| {
+                owner.kind() == "trait_item"
+                    || (owner.kind() == "impl_item" && owner.child_by_field_name("trait").is_some())
+            }),
+            method: is_method(node, owner),
+            start: node.start_position().row,
+            // A node ending at the start of a line ends on the one before.
+            end: if end.column == 0 && end.row > node.start_position().row {
+                end.row - 1
+            } else {
+                end.row
+            },
+        });
+    }
+    let enclosing = if definition.is_some() {
+        Some(node)
+    } else {
+        enclosing
+    };
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect(child, source, definitions, enclosing, path, symbols);
+    }
+    if definition.is_some() {
+        path.pop();
+    }
+}
+
+/// Whether the definition `node`, directly in `owner`, is a method: a
+/// Rust function taking `self`, or any function of a class.
+fn is_method(node: Node, owner: Option<Node>) -> bool {
+    match node.kind() {
+        "method_definition" | "method_declaration" => true,
+        "function_definition" => owner.is_some_and(|owner| owner.kind() == "class_definition"),
+        "function_item" | "function_signature_item" => node
+            .child_by_field_name("parameters")
+            .is_some_and(|parameters| {
+                let mut cursor = parameters.walk();
+                parameters
+                    .named_children(&mut cursor)
+                    .any(|parameter| parameter.kind() == "self_parameter")
+            }),
+        _ => false,
+    }
+}
+
+/// The name a definition is referred to by; an `impl` block goes by the
+/// type it implements, without generics.
+fn symbol_name(node: Node, source: &[u8]) -> Option<String> {
+    if node.kind() == "impl_item" {
+        let ty = node.child_by_field_name("type")?.utf8_text(source).ok()?;
+        return Some(ty.split('<').next().unwrap_or(ty).trim().to_string());
+    }
+    let name = node.child_by_field_name("name")?.utf8_text(source).ok()?;
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_qualified_and_ambiguous_symbols() {
+        let lines: Vec<String> = "struct Config;\n\nimpl Config {\n    fn new() -> Self {\n        Config\n    }\n}\n\nimpl Other {\n    fn new() {}\n}\n\nfn parse_config() {}"
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        assert_eq!(
+            resolve_symbol("fn parse_config", "src/lib.rs", &lines),
+            Ok((12, 12))
+        );
+        assert_eq!(
+            resolve_symbol("impl Config", "src/lib.rs", &lines),
+            Ok((2, 6))
+        );
+        assert_eq!(
+            resolve_symbol("Config::new", "src/lib.rs", &lines),
+            Ok((3, 5))
+        );
+        assert!(
+            resolve_symbol("fn new", "src/lib.rs", &lines)
+                .unwrap_err()
+                .contains("ambiguous")
+        );
+        assert!(resolve_symbol("fn missing", "src/lib.rs", &lines).is_err());
+        assert!(resolve_symbol("parse_config", "notes.txt", &lines).is_err());
+    }
+
+    #[test]
+    fn renames_only_references_to_the_target() {
+        let source = "struct Config;\n\nimpl Config {\n    fn new() -> Self {\n        Config\n    }\n\n    fn len(&self) -> usize {\n        0\n    }\n}\n\nfn main() {\n    let names = Vec::new();\n    let config = Config::new();\n    config.len();\n}";
+
+        let target = rename_target("Config::new", "src/lib.rs", source).unwrap();
+        let found = occurrences("src/lib.rs", source, &target, "create").unwrap();
+        let renamed: Vec<usize> = found.identifiers.iter().map(|range| range.start).collect();
+        assert_eq!(
+            renamed,
+            [
+                source.find("new()").unwrap(),
+                source.rfind("new()").unwrap()
+            ]
+        );
+        assert!(found.clashes.is_empty());
+        assert_eq!(
+            occurrences("src/lib.rs", source, &target, "len")
+                .unwrap()
+                .clashes
+                .len(),
+            1
+        );
+        assert!(
+            rename_target("Config::len", "src/lib.rs", source)
+                .err()
+                .unwrap()
+                .contains("method")
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Tools packaged as WASM components and run in a wasmtime sandbox, a safer
+//! alternative to [plugin executables](super::plugin) for tools that don't
+//! need a shell.
+//!
+//! Components are `wasi:cli/command` programs found in `.agnt/tools/*.wasm`
+//! under the workspace root, and speak the plugin protocol: run with
+//! `--describe` they print their manifest, run without arguments they read
+//! a call's arguments on stdin and print `{"output"}` or `{"error"}`.
+//!
+//! Components come with the repository, so none is loaded until the user
+//! approves it in the `wasm_tools` project setting, which also says what it
+//! may see of the host ([`WasmToolGrant`]):
+//!
+//! ```text
+//! wasm_tools = lint: read, fetch: network, fmt: write
+//! ```
+//!
+//! `read` and `write` open the workspace root as the component's `.`;
+//! `network` allows sockets and name lookups. A component granted neither
+//! is listed as `name: none`. Tools without write access stay available in
+//! read-only mode, whatever their manifest says.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use agnt_llm::ToolDefinition;
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder, UpdateDeadline};
+use wasmtime_wasi::bindings::sync::Command;
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::{DirPerms, FilePerms, IoView, WasiCtx, WasiCtxBuilder, WasiView};
+
+use super::plugin::Manifest;
+use crate::tool::{ErasedTool, PreparedToolCall};
+
+/// Directory under the workspace root that WASM tools are loaded from.
+pub const WASM_TOOLS_DIR: &str = ".agnt/tools";
+
+/// Instructions a single run may execute, roughly.
+const FUEL: u64 = 50_000_000_000;
+/// Wall-clock time a single run may take. Fuel counts instructions, not
+/// the time spent in them or waiting on the host.
+const MAX_RUN_TIME: Duration = Duration::from_secs(120);
+/// Linear memory a single run may use.
+const MAX_MEMORY_BYTES: usize = 512 * 1024 * 1024;
+/// Output kept from a single run.
+const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// What a component may access.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Grants {
+    filesystem: FilesystemAccess,
+    network: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum FilesystemAccess {
+    #[default]
+    None,
+    Read,
+    Write,
+}
+
+/// A WASM tool the user approved, by the name of its `.wasm` file without
+/// the extension, and what it may access: `name: read network`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmToolGrant {
+    pub name: String,
+    grants: Grants,
+}
+
+impl FromStr for WasmToolGrant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, access) = s
+            .split_once(':')
+            .map(|(name, access)| (name.trim(), access.trim()))
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or_else(|| format!("expected `name: access`, got `{}`", s.trim()))?;
+        let mut grants = Grants::default();
+        for word in access.split_whitespace() {
+            match word {
+                "none" => {}
+                "read" if grants.filesystem == FilesystemAccess::None => {
+                    grants.filesystem = FilesystemAccess::Read
+                }
+                "read" => {}
+                "write" => grants.filesystem = FilesystemAccess::Write,
+                "network" => grants.network = true,
+                word => {
+                    return Err(format!(
+                        "unknown access `{word}` for {name} (expected none, read, write or network)"
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            grants,
+        })
+    }
+}
+
+impl fmt::Display for WasmToolGrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut access = Vec::new();
+        match self.grants.filesystem {
+            FilesystemAccess::None => {}
+            FilesystemAccess::Read => access.push("read"),
+            FilesystemAccess::Write => access.push("write"),
+        }
+        if self.grants.network {
+            access.push("network");
+        }
+        if access.is_empty() {
+            access.push("none");
+        }
+        write!(f, "{}: {}", self.name, access.join(" "))
+    }
+}
+
+/// A tool backed by a WASM component.
+#[derive(Clone)]
+pub struct WasmTool {
+    path: PathBuf,
+    root: PathBuf,
+    grants: Grants,
+    manifest: Manifest,
+    engine: Engine,
+    component: Component,
+}
+
+impl std::fmt::Debug for WasmTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmTool")
+            .field("path", &self.path)
+            .field("root", &self.root)
+            .field("grants", &self.grants)
+            .field("manifest", &self.manifest)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Per-run host state.
+struct Host {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl IoView for Host {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for Host {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+}
+
+impl WasmTool {
+    /// Compile the component at `path` and ask it to describe itself. It
+    /// is granted access to `root` according to `grant`.
+    pub fn load(path: &Path, root: PathBuf, grant: &WasmToolGrant) -> Result<Self, String> {
+        let grants = grant.grants;
+        let mut config = Config::new();
+        config
+            .wasm_component_model(true)
+            .consume_fuel(true)
+            .epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|err| err.to_string())?;
+        let component = Component::from_file(&engine, path).map_err(|err| format!("{err:#}"))?;
+        let mut tool = Self {
+            path: path.to_path_buf(),
+            root,
+            grants,
+            manifest: Manifest {
+                name: String::new(),
+                description: String::new(),
+                parameters: serde_json::Value::Null,
+                read_only: false,
+            },
+            engine,
+            component,
+        };
+        // The sync WASI bindings block on a runtime of their own, which they
+        // can't do from within the caller's.
+        let stdout = std::thread::scope(|scope| {
+            scope
+                .spawn(|| tool.run(&["--describe"], Vec::new()))
+                .join()
+                .map_err(|_| "--describe panicked".to_string())
+        })??;
+        tool.manifest = Manifest::parse(&stdout)?;
+        Ok(tool)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the component isn't granted write access, keeping it
+    /// available in read-only mode.
+    pub fn read_only(&self) -> bool {
+        self.grants.filesystem != FilesystemAccess::Write
+    }
+
+    /// Run the component with `args` and `stdin` in a fresh sandbox,
+    /// returning its stdout.
+    fn run(&self, args: &[&str], stdin: Vec<u8>) -> Result<Vec<u8>, String> {
+        let stdout = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+        let program = self
+            .path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("tool");
+        let mut argv = vec![program];
+        argv.extend_from_slice(args);
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .args(&argv)
+            .stdin(MemoryInputPipe::new(stdin))
+            .stdout(stdout.clone());
+        match self.grants.filesystem {
+            FilesystemAccess::None => {}
+            FilesystemAccess::Read => {
+                builder
+                    .preopened_dir(&self.root, ".", DirPerms::READ, FilePerms::READ)
+                    .map_err(|err| format!("failed to open {}: {err}", self.root.display()))?;
+            }
+            FilesystemAccess::Write => {
+                builder
+                    .preopened_dir(&self.root, ".", DirPerms::all(), FilePerms::all())
+                    .map_err(|err| format!("failed to open {}: {err}", self.root.display()))?;
+            }
+        }
+        if self.grants.network {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        }
+
+        let mut store = Store::new(
+            &self.engine,
+            Host {
+                ctx: builder.build(),
+                table: ResourceTable::new(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(MAX_MEMORY_BYTES)
+                    .build(),
+            },
+        );
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL).map_err(|err| err.to_string())?;
+        // Runs on the same engine share its epoch, so each checks its own
+        // deadline when any of their timers ticks it.
+        let deadline = Instant::now() + MAX_RUN_TIME;
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| {
+            if Instant::now() < deadline {
+                Ok(UpdateDeadline::Continue(1))
+            } else {
+                Err(wasmtime::Trap::Interrupt.into())
+            }
+        });
+        let (done, timer) = mpsc::channel::<()>();
+        let engine = self.engine.clone();
+        std::thread::spawn(move || {
+            if timer.recv_timeout(MAX_RUN_TIME) == Err(mpsc::RecvTimeoutError::Timeout) {
+                engine.increment_epoch();
+            }
+        });
+
+        let mut linker = Linker::new(&self.engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker).map_err(|err| err.to_string())?;
+        let result = Command::instantiate(&mut store, &self.component, &linker)
+            .and_then(|command| command.wasi_cli_run().call_run(&mut store));
+        drop(store);
+        drop(done);
+
+        let stdout = stdout.contents().to_vec();
+        match result {
+            Ok(Ok(())) => Ok(stdout),
+            // A failing exit may still have printed an error response.
+            _ if !stdout.is_empty() => Ok(stdout),
+            Ok(Err(())) => Err("exited with an error".to_string()),
+            Err(err) if err.downcast_ref() == Some(&wasmtime::Trap::OutOfFuel) => {
+                Err("ran longer than allowed".to_string())
+            }
+            Err(err) if err.downcast_ref() == Some(&wasmtime::Trap::Interrupt) => Err(format!(
+                "ran longer than {} seconds",
+                MAX_RUN_TIME.as_secs()
+            )),
+            Err(err) => Err(format!("{err:#}")),
+        }
+    }
+}
+
+impl ErasedTool for WasmTool {
+    fn definition(&self) -> ToolDefinition {
+        self.manifest.definition()
+    }
+
+    fn prepare(&self, arguments: &str) -> Result<PreparedToolCall, agnt_llm::Error> {
+        let this = self.clone();
+        let writes_in = (!self.read_only()).then_some(self.root.as_path());
+        self.manifest
+            .prepare(arguments, writes_in, move |arguments| async move {
+                let run = tokio::task::spawn_blocking(move || {
+                    this.run(&[], arguments.to_string().into_bytes())
+                });
+                // A run blocked in a host call only sees its deadline once it
+                // returns to the component, so stop waiting on it here too.
+                match tokio::time::timeout(MAX_RUN_TIME + Duration::from_secs(5), run).await {
+                    Ok(joined) => joined.map_err(|err| err.to_string())?,
+                    Err(_) => Err(format!(
+                        "ran longer than {} seconds",
+                        MAX_RUN_TIME.as_secs()
+                    )),
+                }
+            })
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// The `.wasm` files in [`WASM_TOOLS_DIR`] under `root`, by name.
+pub fn wasm_tool_paths(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root.join(WASM_TOOLS_DIR)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wasm"))
+        .collect();
+    paths.sort();
+    paths
+}
@@ -0,0 +1,35 @@
+-- Full-text index over the text of each turn. Text is pulled out of the
+-- parts JSON generically: every string stored under a "text" key.
+CREATE VIRTUAL TABLE turns_fts USING fts5(
+    turn_id UNINDEXED,
+    user_text,
+    assistant_text
+);
+
+INSERT INTO turns_fts (turn_id, user_text, assistant_text)
+SELECT
+    t.id,
+    (SELECT coalesce(group_concat(value, ' '), '')
+     FROM json_tree(t.user_parts_json)
+     WHERE key = 'text' AND type = 'text'),
+    (SELECT coalesce(group_concat(value, ' '), '')
+     FROM json_tree(t.assistant_parts_json)
+     WHERE key = 'text' AND type = 'text')
+FROM turns t;
+
+CREATE TRIGGER turns_fts_after_insert AFTER INSERT ON turns BEGIN
+    INSERT INTO turns_fts (turn_id, user_text, assistant_text)
+    VALUES (
+        NEW.id,
+        (SELECT coalesce(group_concat(value, ' '), '')
+         FROM json_tree(NEW.user_parts_json)
+         WHERE key = 'text' AND type = 'text'),
+        (SELECT coalesce(group_concat(value, ' '), '')
+         FROM json_tree(NEW.assistant_parts_json)
+         WHERE key = 'text' AND type = 'text')
+    );
+END;
+
+CREATE TRIGGER turns_fts_after_delete AFTER DELETE ON turns BEGIN
+    DELETE FROM turns_fts WHERE turn_id = OLD.id;
+END;
@@ -0,0 +1,10 @@
+-- Advisory locks so only one agnt process writes to a session at a time.
+-- A lock whose heartbeat is older than the caller's staleness window is
+-- considered abandoned and may be taken over.
+CREATE TABLE session_locks (
+    session_id TEXT PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,
+    owner TEXT NOT NULL,
+    pid INTEGER NOT NULL,
+    acquired_at_ms INTEGER NOT NULL,
+    heartbeat_at_ms INTEGER NOT NULL
+);
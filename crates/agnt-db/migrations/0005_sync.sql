@@ -0,0 +1,13 @@
+-- Remote sync. Ops pulled from another device keep that device's id and
+-- sequence number, so they are applied once and never pushed back.
+ALTER TABLE session_ops ADD COLUMN origin TEXT;
+ALTER TABLE session_ops ADD COLUMN origin_seq INTEGER;
+
+CREATE UNIQUE INDEX idx_session_ops_origin
+    ON session_ops(origin, origin_seq)
+    WHERE origin IS NOT NULL;
+
+CREATE TABLE sync_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
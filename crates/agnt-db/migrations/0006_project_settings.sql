@@ -0,0 +1,4 @@
+-- Per-project settings (default model, profile, approval policy, ignored
+-- paths) as a JSON object.
+ALTER TABLE projects ADD COLUMN settings_json TEXT NOT NULL DEFAULT '{}'
+    CHECK (json_valid(settings_json));
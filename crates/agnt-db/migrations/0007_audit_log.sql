@@ -0,0 +1,30 @@
+-- Append-only record of the tool calls the agent executed. Each entry's hash
+-- covers its fields and the previous entry's hash, so changing or removing
+-- an entry breaks the chain from there on. Sessions may be pruned; their
+-- audit entries stay.
+CREATE TABLE audit_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT,
+    project_root TEXT NOT NULL,
+    tool_name TEXT NOT NULL,
+    arguments_hash TEXT NOT NULL,
+    files_json TEXT NOT NULL CHECK (json_valid(files_json)),
+    exit_code INTEGER,
+    succeeded INTEGER NOT NULL,
+    approval TEXT NOT NULL,
+    created_at_ms INTEGER NOT NULL,
+    prev_hash TEXT NOT NULL,
+    hash TEXT NOT NULL
+);
+
+CREATE INDEX idx_audit_log_session ON audit_log(session_id, seq);
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
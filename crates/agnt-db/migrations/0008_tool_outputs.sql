@@ -0,0 +1,10 @@
+-- Full output of tool calls whose result was truncated before it went into
+-- the conversation, so it can still be shown on request.
+CREATE TABLE tool_outputs (
+    tool_call_id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    created_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX idx_tool_outputs_session ON tool_outputs(session_id);
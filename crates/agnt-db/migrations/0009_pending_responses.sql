@@ -0,0 +1,8 @@
+-- Background responses still generating when the process that started them
+-- exited, so the turn can be picked up again on the next run.
+CREATE TABLE pending_responses (
+    session_id TEXT PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,
+    token TEXT NOT NULL,
+    conversation_state_json TEXT NOT NULL,
+    created_at_ms INTEGER NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Checkout a session was started in when it is a linked git worktree (or
+-- submodule checkout) of the project's main repository.
+ALTER TABLE sessions ADD COLUMN worktree_dir TEXT;
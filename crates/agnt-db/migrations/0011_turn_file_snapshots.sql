@@ -0,0 +1,3 @@
+-- Content hashes (and optionally contents) of the files a turn read or
+-- edited, as they were when the turn was stored.
+ALTER TABLE turns ADD COLUMN file_snapshots_json TEXT;
@@ -0,0 +1,8 @@
+-- Unsent composer text per session, so a draft survives quitting and
+-- resuming. Cursor is a byte offset into the text.
+CREATE TABLE session_drafts (
+    session_id TEXT PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,
+    text TEXT NOT NULL,
+    cursor INTEGER NOT NULL,
+    updated_at_ms INTEGER NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Prompts queued with `agnt queue add` and run one at a time, without a
+-- UI, by `agnt queue run`. Status goes from queued to running to done or
+-- failed; each run is stored as a session of its own.
+CREATE TABLE queued_tasks (
+    id TEXT PRIMARY KEY,
+    project_root TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    profile TEXT,
+    status TEXT NOT NULL,
+    session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+    error TEXT,
+    created_at_ms INTEGER NOT NULL,
+    started_at_ms INTEGER,
+    finished_at_ms INTEGER
+);
+
+CREATE INDEX queued_tasks_status_created ON queued_tasks(status, created_at_ms);
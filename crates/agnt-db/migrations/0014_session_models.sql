@@ -0,0 +1,5 @@
+-- Model a session generates with and its reasoning effort, so resuming it
+-- continues on the same model rather than the current default.
+ALTER TABLE sessions ADD COLUMN provider_id TEXT;
+ALTER TABLE sessions ADD COLUMN model_id TEXT;
+ALTER TABLE sessions ADD COLUMN reasoning_effort TEXT;
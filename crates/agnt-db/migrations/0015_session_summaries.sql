@@ -0,0 +1,5 @@
+-- Summary of a session and the decisions made in it, written once the
+-- session is left, with the turn it was written at so a stale one can be
+-- told apart.
+ALTER TABLE sessions ADD COLUMN summary_json TEXT;
+ALTER TABLE sessions ADD COLUMN summary_turn_id TEXT;
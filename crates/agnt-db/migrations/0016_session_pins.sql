@@ -0,0 +1,2 @@
+-- Sessions pinned by the user, listed before all others in resume pickers.
+ALTER TABLE sessions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+-- Turns the user bookmarked, with an optional note, to jump back to them
+-- from any session. A bookmark goes away with its turn.
+CREATE TABLE turn_bookmarks (
+    turn_id TEXT PRIMARY KEY REFERENCES turns(id) ON DELETE CASCADE,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    note TEXT,
+    created_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX turn_bookmarks_created ON turn_bookmarks(created_at_ms);
@@ -0,0 +1,9 @@
+-- The prompt of a turn that was sent but not stored yet, written when it
+-- is submitted and removed once the turn is stored or abandoned. One left
+-- over means the process died mid-turn.
+CREATE TABLE pending_turns (
+    session_id TEXT PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,
+    parent_turn_id TEXT,
+    prompt TEXT NOT NULL,
+    created_at_ms INTEGER NOT NULL
+);
@@ -0,0 +1,40 @@
+-- Index only the text of each message: user text parts that aren't
+-- attachments (files, pastes, injected context) and assistant text parts.
+-- Tool calls, reasoning and attachment bodies are left out. Sealed turns
+-- hold a JSON string instead of parts and index as empty.
+DROP TRIGGER turns_fts_after_insert;
+
+CREATE TRIGGER turns_fts_after_insert AFTER INSERT ON turns BEGIN
+    INSERT INTO turns_fts (turn_id, user_text, assistant_text)
+    VALUES (
+        NEW.id,
+        (SELECT coalesce(group_concat(part_text, ' '), '')
+         FROM (SELECT CASE WHEN type = 'object' THEN
+                          CASE WHEN json_extract(value, '$.Text.metadata."agnt:attachment"') IS NULL
+                          THEN json_extract(value, '$.Text.text') END
+                      END AS part_text
+               FROM json_each(NEW.user_parts_json))),
+        (SELECT coalesce(group_concat(part_text, ' '), '')
+         FROM (SELECT CASE WHEN type = 'object' THEN json_extract(value, '$.Text.text') END
+                      AS part_text
+               FROM json_each(NEW.assistant_parts_json)))
+    );
+END;
+
+DELETE FROM turns_fts;
+
+INSERT INTO turns_fts (turn_id, user_text, assistant_text)
+SELECT
+    t.id,
+    (SELECT coalesce(group_concat(part_text, ' '), '')
+     FROM (SELECT CASE WHEN type = 'object' THEN
+                      CASE WHEN json_extract(value, '$.Text.metadata."agnt:attachment"') IS NULL
+                      THEN json_extract(value, '$.Text.text') END
+                  END AS part_text
+           FROM json_each(t.user_parts_json))),
+    (SELECT coalesce(group_concat(part_text, ' '), '')
+     FROM (SELECT CASE WHEN type = 'object' THEN json_extract(value, '$.Text.text') END
+                  AS part_text
+           FROM json_each(t.assistant_parts_json)))
+FROM turns t
+WHERE json_type(t.user_parts_json) != 'text';
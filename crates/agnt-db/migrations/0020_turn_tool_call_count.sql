@@ -0,0 +1,12 @@
+-- Number of tool calls in each turn's assistant parts, stored on write so
+-- usage stats don't have to open (and decrypt) the parts. Sealed turns from
+-- before this column stay NULL and are counted when read.
+ALTER TABLE turns ADD COLUMN tool_call_count INTEGER;
+
+UPDATE turns
+SET tool_call_count = (
+    SELECT count(*)
+    FROM json_each(assistant_parts_json)
+    WHERE type = 'object' AND json_type(value, '$.ToolCall') IS NOT NULL
+)
+WHERE json_type(assistant_parts_json) = 'array';
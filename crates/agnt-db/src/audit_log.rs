@@ -0,0 +1,217 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use ring::digest::{SHA256, digest};
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::Result;
+use crate::sessions::now_ms;
+
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One executed tool call, as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub session_id: Option<String>,
+    pub project_root: PathBuf,
+    pub tool_name: String,
+    /// SHA-256 of the raw arguments JSON. The arguments themselves are not
+    /// kept, as they may contain file contents.
+    pub arguments_hash: String,
+    /// Paths the call read or wrote, as passed to the tool.
+    pub files: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub succeeded: bool,
+    /// What the user decided about the call: `auto` when it ran without
+    /// asking, `user_approved` or `user_refused` when they were asked about
+    /// a protected write or an edit, `refused` when no one could be asked.
+    pub approval: String,
+    pub created_at_ms: i64,
+    /// Local time the call was recorded, as `YYYY-MM-DD HH:MM:SS`.
+    pub time: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppendAuditInput {
+    pub session_id: Option<String>,
+    pub project_root: PathBuf,
+    pub tool_name: String,
+    pub arguments: String,
+    pub files: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub succeeded: bool,
+    pub approval: String,
+}
+
+pub struct AuditLog<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+impl AuditLog<'_> {
+    /// Append an entry, chaining its hash to the latest one.
+    pub fn append(&mut self, input: AppendAuditInput) -> Result<AuditEntry> {
+        let tx = self.db.conn.transaction()?;
+        let prev_hash = tx
+            .query_row(
+                "SELECT hash FROM audit_log ORDER BY seq DESC LIMIT 1",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        let mut entry = AuditEntry {
+            seq: 0,
+            session_id: input.session_id,
+            project_root: input.project_root,
+            tool_name: input.tool_name,
+            arguments_hash: sha256_hex(input.arguments.as_bytes()),
+            files: input.files,
+            exit_code: input.exit_code,
+            succeeded: input.succeeded,
+            approval: input.approval,
+            created_at_ms: now_ms(),
+            time: String::new(),
+            hash: String::new(),
+        };
+        let files_json = serde_json::to_string(&entry.files)?;
+        entry.hash = entry_hash(&prev_hash, &entry, &files_json);
+
+        tx.execute(
+            "INSERT INTO audit_log (
+                session_id, project_root, tool_name, arguments_hash, files_json,
+                exit_code, succeeded, approval, created_at_ms, prev_hash, hash
+             )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                entry.session_id,
+                entry.project_root.to_string_lossy(),
+                entry.tool_name,
+                entry.arguments_hash,
+                files_json,
+                entry.exit_code,
+                entry.succeeded,
+                entry.approval,
+                entry.created_at_ms,
+                prev_hash,
+                entry.hash,
+            ],
+        )?;
+        entry.seq = tx.last_insert_rowid();
+        entry.time = tx.query_row(
+            "SELECT datetime(created_at_ms / 1000, 'unixepoch', 'localtime')
+             FROM audit_log WHERE seq = ?1",
+            params![entry.seq],
+            |row| row.get(0),
+        )?;
+        tx.commit()?;
+        Ok(entry)
+    }
+
+    /// The most recent `limit` entries, optionally for one session, oldest
+    /// first.
+    pub fn entries(&self, session_id: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT seq, session_id, project_root, tool_name, arguments_hash, files_json,
+                    exit_code, succeeded, approval, created_at_ms, hash,
+                    datetime(created_at_ms / 1000, 'unixepoch', 'localtime')
+             FROM audit_log
+             WHERE ?1 IS NULL OR session_id = ?1
+             ORDER BY seq DESC
+             LIMIT ?2",
+        )?;
+        let mut entries = stmt
+            .query_map(params![session_id, limit as i64], |row| {
+                Ok((row_to_entry(row)?, row.get::<_, String>(5)?))
+            })?
+            .map(|row| {
+                let (mut entry, files_json) = row?;
+                entry.files = serde_json::from_str(&files_json)?;
+                Ok(entry)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Recompute the hash chain. Returns the `seq` of the first entry that
+    /// was changed, or that follows a removed entry, if any.
+    pub fn verify(&self) -> Result<Option<i64>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT seq, session_id, project_root, tool_name, arguments_hash, files_json,
+                    exit_code, succeeded, approval, created_at_ms, hash,
+                    datetime(created_at_ms / 1000, 'unixepoch', 'localtime'), prev_hash
+             FROM audit_log
+             ORDER BY seq ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut expected_prev = GENESIS_HASH.to_string();
+        while let Some(row) = rows.next()? {
+            let entry = row_to_entry(row)?;
+            let files_json: String = row.get(5)?;
+            let prev_hash: String = row.get(12)?;
+            if prev_hash != expected_prev
+                || entry_hash(&prev_hash, &entry, &files_json) != entry.hash
+            {
+                return Ok(Some(entry.seq));
+            }
+            expected_prev = entry.hash;
+        }
+        Ok(None)
+    }
+}
+
+/// Everything but `files`, which the caller parses from column 5.
+fn row_to_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        seq: row.get(0)?,
+        session_id: row.get(1)?,
+        project_root: PathBuf::from(row.get::<_, String>(2)?),
+        tool_name: row.get(3)?,
+        arguments_hash: row.get(4)?,
+        files: Vec::new(),
+        exit_code: row.get(6)?,
+        succeeded: row.get(7)?,
+        approval: row.get(8)?,
+        created_at_ms: row.get(9)?,
+        hash: row.get(10)?,
+        time: row.get(11)?,
+    })
+}
+
+fn entry_hash(prev_hash: &str, entry: &AuditEntry, files_json: &str) -> String {
+    let exit_code = entry.exit_code.map(|code| code.to_string());
+    let fields = [
+        prev_hash,
+        entry.session_id.as_deref().unwrap_or(""),
+        &entry.project_root.to_string_lossy(),
+        &entry.tool_name,
+        &entry.arguments_hash,
+        files_json,
+        exit_code.as_deref().unwrap_or(""),
+        if entry.succeeded { "1" } else { "0" },
+        &entry.approval,
+        &entry.created_at_ms.to_string(),
+    ];
+    // Length-prefix every field so no two entries serialize the same way.
+    let mut data = String::new();
+    for field in fields {
+        let _ = write!(data, "{}:{field};", field.len());
+    }
+    sha256_hex(data.as_bytes())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
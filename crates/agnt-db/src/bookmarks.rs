@@ -0,0 +1,105 @@
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::{open_text, seal_text};
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::sessions::{now_ms, title_context};
+
+/// A turn the user bookmarked to jump back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnBookmark {
+    pub turn_id: String,
+    pub session_id: String,
+    pub session_title: Option<String>,
+    pub note: Option<String>,
+    pub created_at_ms: i64,
+}
+
+pub struct Bookmarks<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+impl Bookmarks<'_> {
+    /// Bookmark `turn_id`, replacing the note of an existing bookmark. A
+    /// blank note is dropped.
+    pub fn set(&mut self, turn_id: &str, note: Option<&str>) -> Result<()> {
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        let session_id = tx
+            .query_row(
+                "SELECT session_id FROM turns WHERE id = ?1",
+                params![turn_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .ok_or_else(|| Error::TurnNotFound(turn_id.to_string()))?;
+        let note = note
+            .map(|note| seal_text(cipher, note, &bookmark_note_context(turn_id)))
+            .transpose()?;
+        tx.execute(
+            "INSERT INTO turn_bookmarks (turn_id, session_id, note, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(turn_id) DO UPDATE SET note = excluded.note",
+            params![turn_id, session_id, note, now_ms()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove the bookmark on `turn_id`. Returns whether there was one.
+    pub fn remove(&mut self, turn_id: &str) -> Result<bool> {
+        let removed = self.db.conn.execute(
+            "DELETE FROM turn_bookmarks WHERE turn_id = ?1",
+            params![turn_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// The most recent bookmarks in `project_id`'s sessions, or in all
+    /// projects when it is `None`, newest first.
+    pub fn list(&self, project_id: Option<&str>, limit: usize) -> Result<Vec<TurnBookmark>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT b.turn_id, b.session_id, s.title, b.note, b.created_at_ms
+             FROM turn_bookmarks b
+             JOIN sessions s ON s.id = b.session_id
+             WHERE ?1 IS NULL OR s.project_id = ?1
+             ORDER BY b.created_at_ms DESC, b.rowid DESC
+             LIMIT ?2",
+        )?;
+        let bookmarks = stmt
+            .query_map(params![project_id, limit as i64], |row| {
+                Ok(TurnBookmark {
+                    turn_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    session_title: row.get(2)?,
+                    note: row.get(3)?,
+                    created_at_ms: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let cipher = self.db.cipher.as_ref();
+        bookmarks
+            .into_iter()
+            .map(|mut bookmark| {
+                bookmark.session_title = bookmark
+                    .session_title
+                    .take()
+                    .map(|title| open_text(cipher, title, &title_context(&bookmark.session_id)))
+                    .transpose()?;
+                bookmark.note = bookmark
+                    .note
+                    .take()
+                    .map(|note| open_text(cipher, note, &bookmark_note_context(&bookmark.turn_id)))
+                    .transpose()?;
+                Ok(bookmark)
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn bookmark_note_context(turn_id: &str) -> String {
+    format!("turn_bookmark:{turn_id}:note")
+}
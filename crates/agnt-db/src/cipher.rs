@@ -0,0 +1,129 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use rand::random;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+
+use crate::error::{Error, Result};
+
+/// Prefix marking a column value sealed by [`SessionCipher`]. Sealed JSON
+/// columns hold the sealed text as a JSON string, so `json_valid` checks
+/// still pass and plaintext rows written before encryption stay readable.
+const SEALED_PREFIX: &str = "agnt-aes-256-gcm-v1:";
+
+/// Application-level encryption for session content (turn parts, state,
+/// titles and op payloads) using AES-256-GCM.
+pub struct SessionCipher {
+    key: LessSafeKey,
+}
+
+impl SessionCipher {
+    pub fn new(key: [u8; 32]) -> Result<Self> {
+        let unbound = UnboundKey::new(&AES_256_GCM, &key)
+            .map_err(|_| Error::Encryption("invalid encryption key material".to_string()))?;
+        Ok(Self {
+            key: LessSafeKey::new(unbound),
+        })
+    }
+
+    /// Encrypt `plaintext`, binding it to `context` (row id and column) so a
+    /// sealed value cannot be moved to another row.
+    pub(crate) fn seal(&self, plaintext: &str, context: &str) -> Result<String> {
+        let nonce: [u8; NONCE_LEN] = random();
+        let mut in_out = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| Error::Encryption("failed to encrypt session data".to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&in_out);
+        Ok(format!("{SEALED_PREFIX}{}", STANDARD_NO_PAD.encode(sealed)))
+    }
+
+    pub(crate) fn open(&self, sealed: &str, context: &str) -> Result<String> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| Error::Encryption("value is not sealed".to_string()))?;
+        let mut bytes = STANDARD_NO_PAD
+            .decode(encoded)
+            .map_err(|err| Error::Encryption(format!("invalid sealed value encoding: {err}")))?;
+        if bytes.len() < NONCE_LEN {
+            return Err(Error::Encryption("sealed value is truncated".to_string()));
+        }
+
+        let mut ciphertext = bytes.split_off(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = bytes
+            .try_into()
+            .map_err(|_| Error::Encryption("invalid nonce length".to_string()))?;
+        let plaintext = self
+            .key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context.as_bytes()),
+                &mut ciphertext,
+            )
+            .map_err(|_| Error::Encryption("failed to decrypt session data".to_string()))?;
+
+        String::from_utf8(plaintext.to_vec())
+            .map_err(|err| Error::Encryption(format!("invalid decrypted text: {err}")))
+    }
+}
+
+pub(crate) fn is_sealed(text: &str) -> bool {
+    text.starts_with(SEALED_PREFIX)
+}
+
+/// Seal `text` when a cipher is configured; otherwise store it as-is.
+pub(crate) fn seal_text(
+    cipher: Option<&SessionCipher>,
+    text: &str,
+    context: &str,
+) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.seal(text, context),
+        None => Ok(text.to_string()),
+    }
+}
+
+pub(crate) fn open_text(
+    cipher: Option<&SessionCipher>,
+    text: String,
+    context: &str,
+) -> Result<String> {
+    if !is_sealed(&text) {
+        return Ok(text);
+    }
+    cipher
+        .ok_or(Error::MissingEncryptionKey)?
+        .open(&text, context)
+}
+
+/// Serialize `value` for a JSON column, sealing it when a cipher is
+/// configured.
+pub(crate) fn seal_json(
+    cipher: Option<&SessionCipher>,
+    value: &serde_json::Value,
+    context: &str,
+) -> Result<String> {
+    let json = serde_json::to_string(value)?;
+    match cipher {
+        Some(cipher) => Ok(serde_json::to_string(&cipher.seal(&json, context)?)?),
+        None => Ok(json),
+    }
+}
+
+pub(crate) fn open_json(
+    cipher: Option<&SessionCipher>,
+    value: serde_json::Value,
+    context: &str,
+) -> Result<serde_json::Value> {
+    match value {
+        serde_json::Value::String(text) if is_sealed(&text) => {
+            Ok(serde_json::from_str(&open_text(cipher, text, context)?)?)
+        }
+        value => Ok(value),
+    }
+}
@@ -1,12 +1,15 @@
 use std::path::Path;
+use std::time::Duration;
 
-use rusqlite::Connection;
+use rusqlite::{Connection, TransactionBehavior};
 
+use crate::cipher::SessionCipher;
 use crate::error::Result;
 use crate::migration;
 
 pub(crate) struct Database {
     pub(crate) conn: Connection,
+    pub(crate) cipher: Option<SessionCipher>,
 }
 
 impl Database {
@@ -14,18 +17,18 @@ impl Database {
         prepare_db_file(path)?;
 
         let mut conn = Connection::open(path)?;
-        configure_connection(&conn)?;
+        configure_connection(&mut conn)?;
         migration::apply(&mut conn)?;
 
-        Ok(Self { conn })
+        Ok(Self { conn, cipher: None })
     }
 
     pub(crate) fn open_in_memory() -> Result<Self> {
         let mut conn = Connection::open_in_memory()?;
-        configure_connection(&conn)?;
+        configure_connection(&mut conn)?;
         migration::apply(&mut conn)?;
 
-        Ok(Self { conn })
+        Ok(Self { conn, cipher: None })
     }
 }
 
@@ -38,7 +41,16 @@ fn prepare_db_file(path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn configure_connection(conn: &Connection) -> Result<()> {
+/// How long a connection waits on a lock held by another agnt process
+/// before failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn configure_connection(conn: &mut Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // Take the write lock when a transaction starts rather than on its first
+    // write, so concurrent processes wait on the busy timeout instead of
+    // failing to upgrade a read lock mid-transaction.
+    conn.set_transaction_behavior(TransactionBehavior::Immediate);
     conn.execute_batch(
         "PRAGMA foreign_keys = ON;
          PRAGMA journal_mode = WAL;
@@ -26,6 +26,22 @@ pub enum Error {
         session_id: String,
         parent_turn_id: String,
     },
+
+    #[error("session '{session_id}' moved to turn {actual:?} while turn '{expected}' was expected")]
+    CurrentTurnConflict {
+        session_id: String,
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("session '{session_id}' is open in another agnt process (pid {pid})")]
+    SessionLocked { session_id: String, pid: u32 },
+
+    #[error("session data is encrypted but no encryption key is configured")]
+    MissingEncryptionKey,
+
+    #[error("encryption error: {0}")]
+    Encryption(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
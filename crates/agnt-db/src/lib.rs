@@ -1,13 +1,28 @@
+pub mod audit_log;
+pub mod bookmarks;
+pub mod cipher;
 mod database;
 pub mod error;
+pub mod maintenance;
 mod migration;
 pub mod provider_credentials;
 pub mod sessions;
 pub mod store;
+pub mod sync_log;
+pub mod task_queue;
+pub mod usage_log;
 
+pub use audit_log::{AppendAuditInput, AuditEntry, AuditLog};
+pub use bookmarks::{Bookmarks, TurnBookmark};
+pub use cipher::SessionCipher;
 pub use error::{Error, Result};
+pub use maintenance::{DatabaseStats, Maintenance, RetentionPolicy, TableSize};
 pub use provider_credentials::{ProviderCredential, ProviderCredentials};
 pub use sessions::{
-    AppendTurnInput, CreateSessionInput, Project, Session, SessionOp, Sessions, Turn, TurnPathItem,
+    AppendTurnInput, CreateSessionInput, PendingResponse, PendingTurn, Project, ProjectSettings,
+    Session, SessionLock, SessionOp, Sessions, Turn, TurnPathItem, TurnSearchHit,
 };
 pub use store::Store;
+pub use sync_log::{RemoteOp, SyncLog};
+pub use task_queue::{QueuedTask, TaskQueue, TaskStatus};
+pub use usage_log::{TurnUsage, UsageLog};
@@ -0,0 +1,554 @@
+use std::time::Duration;
+
+use rusqlite::{Transaction, params};
+
+use crate::bookmarks::bookmark_note_context;
+use crate::cipher::{SessionCipher, is_sealed, open_json, open_text, seal_json, seal_text};
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::sessions::{
+    draft_context, now_ms, op_context, pending_response_context, pending_turn_context,
+    summary_context, title_context, tool_output_context, turn_context,
+};
+use crate::task_queue::task_prompt_context;
+
+/// Rebuilds the search index from the turns table, matching the insert
+/// trigger: only message text, not attachments, tool calls or reasoning.
+/// Sealed turns hold a JSON string rather than parts, so they contribute no
+/// text.
+const REINDEX_TURNS_SQL: &str = "
+    DELETE FROM turns_fts;
+    INSERT INTO turns_fts (turn_id, user_text, assistant_text)
+    SELECT
+        t.id,
+        (SELECT coalesce(group_concat(part_text, ' '), '')
+         FROM (SELECT CASE WHEN type = 'object' THEN
+                          CASE WHEN json_extract(value, '$.Text.metadata.\"agnt:attachment\"') IS NULL
+                          THEN json_extract(value, '$.Text.text') END
+                      END AS part_text
+               FROM json_each(t.user_parts_json))),
+        (SELECT coalesce(group_concat(part_text, ' '), '')
+         FROM (SELECT CASE WHEN type = 'object' THEN json_extract(value, '$.Text.text') END
+                      AS part_text
+               FROM json_each(t.assistant_parts_json)))
+    FROM turns t
+    WHERE json_type(t.user_parts_json) != 'text';
+";
+
+pub struct Maintenance<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseStats {
+    pub journal_mode: String,
+    pub page_size: u64,
+    pub total_bytes: u64,
+    /// Bytes held by free pages that a VACUUM would reclaim.
+    pub free_bytes: u64,
+    /// Space used per table, including its indexes, largest first.
+    pub tables: Vec<TableSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSize {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// Which sessions to delete when pruning the database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Delete sessions not updated within this window.
+    pub max_age: Option<Duration>,
+    /// Keep at most this many of the most recently updated sessions per project.
+    pub max_sessions_per_project: Option<usize>,
+}
+
+impl Maintenance<'_> {
+    pub fn stats(&self) -> Result<DatabaseStats> {
+        let conn = &self.db.conn;
+        let journal_mode: String =
+            conn.pragma_query_value(None, "journal_mode", |row| row.get(0))?;
+        let page_size: i64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+        let page_count: i64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        let freelist_count: i64 =
+            conn.pragma_query_value(None, "freelist_count", |row| row.get(0))?;
+
+        // dbstat reports every b-tree separately; fold indexes (and
+        // autoindexes) into the table they belong to.
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(s.tbl_name, d.name) AS table_name, SUM(d.pgsize) AS bytes
+             FROM dbstat AS d
+             LEFT JOIN sqlite_schema AS s ON s.name = d.name
+             GROUP BY table_name
+             ORDER BY bytes DESC, table_name ASC",
+        )?;
+        let tables = stmt
+            .query_map([], |row| {
+                Ok(TableSize {
+                    name: row.get(0)?,
+                    bytes: row.get::<_, i64>(1)?.max(0) as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(DatabaseStats {
+            journal_mode,
+            page_size: page_size.max(0) as u64,
+            total_bytes: (page_size * page_count).max(0) as u64,
+            free_bytes: (page_size * freelist_count).max(0) as u64,
+            tables,
+        })
+    }
+
+    /// Run SQLite's quick integrity check. Returns the problems found, or an
+    /// empty list when the database is intact.
+    pub fn quick_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.db.conn.prepare("PRAGMA quick_check")?;
+        let messages = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages
+            .into_iter()
+            .filter(|message| message != "ok")
+            .collect())
+    }
+
+    /// Merge the search index, checkpoint the WAL and rebuild the database
+    /// file to reclaim free pages.
+    pub fn vacuum(&mut self) -> Result<()> {
+        self.db.conn.execute_batch(
+            "INSERT INTO turns_fts(turns_fts) VALUES ('optimize');
+             PRAGMA wal_checkpoint(TRUNCATE);
+             VACUUM;",
+        )?;
+        Ok(())
+    }
+
+    /// Delete sessions outside the retention policy. Pinned sessions are
+    /// kept and don't count towards `max_sessions_per_project`. Returns how
+    /// many sessions were removed; their turns and ops are removed with them.
+    pub fn apply_retention(&mut self, policy: &RetentionPolicy) -> Result<usize> {
+        let tx = self.db.conn.transaction()?;
+        let mut deleted = 0;
+
+        if let Some(max_age) = policy.max_age {
+            let cutoff_ms = now_ms().saturating_sub(max_age.as_millis() as i64);
+            deleted += tx.execute(
+                "DELETE FROM sessions WHERE updated_at_ms < ?1 AND NOT pinned",
+                params![cutoff_ms],
+            )?;
+        }
+
+        if let Some(keep) = policy.max_sessions_per_project {
+            deleted += tx.execute(
+                "DELETE FROM sessions
+                 WHERE id IN (
+                     SELECT id FROM (
+                         SELECT id, ROW_NUMBER() OVER (
+                             PARTITION BY project_id
+                             ORDER BY updated_at_ms DESC, id DESC
+                         ) AS position
+                         FROM sessions
+                         WHERE NOT pinned
+                     )
+                     WHERE position > ?1
+                 )",
+                params![keep as i64],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Encrypt all stored session content with the configured cipher and
+    /// drop it from the search index. Returns the number of rows rewritten.
+    ///
+    /// Old plaintext may linger in free pages until [`Self::vacuum`] runs.
+    pub fn encrypt_existing(&mut self) -> Result<usize> {
+        self.rewrite_session_content(true)
+    }
+
+    /// Decrypt all stored session content and index it for search again.
+    /// Returns the number of rows rewritten.
+    pub fn decrypt_existing(&mut self) -> Result<usize> {
+        self.rewrite_session_content(false)
+    }
+
+    fn rewrite_session_content(&mut self, seal: bool) -> Result<usize> {
+        let cipher = self.db.cipher.as_ref().ok_or(Error::MissingEncryptionKey)?;
+        let tx = self.db.conn.transaction()?;
+
+        let rewritten = rewrite_turns(&tx, cipher, seal)?
+            + rewrite_titles(&tx, cipher, seal)?
+            + rewrite_summaries(&tx, cipher, seal)?
+            + rewrite_ops(&tx, cipher, seal)?
+            + rewrite_tool_outputs(&tx, cipher, seal)?
+            + rewrite_pending_responses(&tx, cipher, seal)?
+            + rewrite_drafts(&tx, cipher, seal)?
+            + rewrite_pending_turns(&tx, cipher, seal)?
+            + rewrite_queued_tasks(&tx, cipher, seal)?
+            + rewrite_bookmark_notes(&tx, cipher, seal)?;
+        tx.execute_batch(REINDEX_TURNS_SQL)?;
+
+        tx.commit()?;
+        Ok(rewritten)
+    }
+}
+
+fn rewrite_turns(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare(
+            "SELECT id, user_parts_json, assistant_parts_json, conversation_state_json, usage_json,
+                file_snapshots_json
+             FROM turns",
+        )?;
+        stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (id, user_parts, assistant_parts, conversation_state, usage, file_snapshots) in rows {
+        if json_column_is_sealed(&user_parts)? == seal {
+            continue;
+        }
+        let convert = |raw: String, column: &str| {
+            convert_json_column(cipher, raw, &turn_context(&id, column), seal)
+        };
+        let user_parts = convert(user_parts, "user_parts")?;
+        let assistant_parts = convert(assistant_parts, "assistant_parts")?;
+        let conversation_state = convert(conversation_state, "conversation_state")?;
+        let usage = usage.map(|usage| convert(usage, "usage")).transpose()?;
+        let file_snapshots = file_snapshots
+            .map(|snapshots| convert(snapshots, "file_snapshots"))
+            .transpose()?;
+
+        tx.execute(
+            "UPDATE turns
+             SET user_parts_json = ?2, assistant_parts_json = ?3,
+                 conversation_state_json = ?4, usage_json = ?5, file_snapshots_json = ?6
+             WHERE id = ?1",
+            params![
+                id,
+                user_parts,
+                assistant_parts,
+                conversation_state,
+                usage,
+                file_snapshots
+            ],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_titles(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare("SELECT id, title FROM sessions WHERE title IS NOT NULL")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (id, title) in rows {
+        if is_sealed(&title) == seal {
+            continue;
+        }
+        let context = title_context(&id);
+        let title = open_text(Some(cipher), title, &context)?;
+        let title = if seal {
+            seal_text(Some(cipher), &title, &context)?
+        } else {
+            title
+        };
+        tx.execute(
+            "UPDATE sessions SET title = ?2 WHERE id = ?1",
+            params![id, title],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_summaries(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt =
+            tx.prepare("SELECT id, summary_json FROM sessions WHERE summary_json IS NOT NULL")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (id, summary) in rows {
+        if json_column_is_sealed(&summary)? == seal {
+            continue;
+        }
+        let summary = convert_json_column(cipher, summary, &summary_context(&id), seal)?;
+        tx.execute(
+            "UPDATE sessions SET summary_json = ?2 WHERE id = ?1",
+            params![id, summary],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_tool_outputs(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare("SELECT tool_call_id, content FROM tool_outputs")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (tool_call_id, content) in rows {
+        if is_sealed(&content) == seal {
+            continue;
+        }
+        let context = tool_output_context(&tool_call_id);
+        let content = open_text(Some(cipher), content, &context)?;
+        let content = if seal {
+            seal_text(Some(cipher), &content, &context)?
+        } else {
+            content
+        };
+        tx.execute(
+            "UPDATE tool_outputs SET content = ?2 WHERE tool_call_id = ?1",
+            params![tool_call_id, content],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_pending_responses(
+    tx: &Transaction<'_>,
+    cipher: &SessionCipher,
+    seal: bool,
+) -> Result<usize> {
+    let rows = {
+        let mut stmt =
+            tx.prepare("SELECT session_id, conversation_state_json FROM pending_responses")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (session_id, conversation_state) in rows {
+        if json_column_is_sealed(&conversation_state)? == seal {
+            continue;
+        }
+        let conversation_state = convert_json_column(
+            cipher,
+            conversation_state,
+            &pending_response_context(&session_id),
+            seal,
+        )?;
+        tx.execute(
+            "UPDATE pending_responses SET conversation_state_json = ?2 WHERE session_id = ?1",
+            params![session_id, conversation_state],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_drafts(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare("SELECT session_id, text FROM session_drafts")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (session_id, text) in rows {
+        if is_sealed(&text) == seal {
+            continue;
+        }
+        let context = draft_context(&session_id);
+        let text = open_text(Some(cipher), text, &context)?;
+        let text = if seal {
+            seal_text(Some(cipher), &text, &context)?
+        } else {
+            text
+        };
+        tx.execute(
+            "UPDATE session_drafts SET text = ?2 WHERE session_id = ?1",
+            params![session_id, text],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_pending_turns(
+    tx: &Transaction<'_>,
+    cipher: &SessionCipher,
+    seal: bool,
+) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare("SELECT session_id, prompt FROM pending_turns")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (session_id, prompt) in rows {
+        if is_sealed(&prompt) == seal {
+            continue;
+        }
+        let context = pending_turn_context(&session_id);
+        let prompt = open_text(Some(cipher), prompt, &context)?;
+        let prompt = if seal {
+            seal_text(Some(cipher), &prompt, &context)?
+        } else {
+            prompt
+        };
+        tx.execute(
+            "UPDATE pending_turns SET prompt = ?2 WHERE session_id = ?1",
+            params![session_id, prompt],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_queued_tasks(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt = tx.prepare("SELECT id, prompt FROM queued_tasks")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (id, prompt) in rows {
+        if is_sealed(&prompt) == seal {
+            continue;
+        }
+        let context = task_prompt_context(&id);
+        let prompt = open_text(Some(cipher), prompt, &context)?;
+        let prompt = if seal {
+            seal_text(Some(cipher), &prompt, &context)?
+        } else {
+            prompt
+        };
+        tx.execute(
+            "UPDATE queued_tasks SET prompt = ?2 WHERE id = ?1",
+            params![id, prompt],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_bookmark_notes(
+    tx: &Transaction<'_>,
+    cipher: &SessionCipher,
+    seal: bool,
+) -> Result<usize> {
+    let rows = {
+        let mut stmt =
+            tx.prepare("SELECT turn_id, note FROM turn_bookmarks WHERE note IS NOT NULL")?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (turn_id, note) in rows {
+        if is_sealed(&note) == seal {
+            continue;
+        }
+        let context = bookmark_note_context(&turn_id);
+        let note = open_text(Some(cipher), note, &context)?;
+        let note = if seal {
+            seal_text(Some(cipher), &note, &context)?
+        } else {
+            note
+        };
+        tx.execute(
+            "UPDATE turn_bookmarks SET note = ?2 WHERE turn_id = ?1",
+            params![turn_id, note],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_ops(tx: &Transaction<'_>, cipher: &SessionCipher, seal: bool) -> Result<usize> {
+    let rows = {
+        let mut stmt =
+            tx.prepare("SELECT seq, session_id, op_type, payload_json FROM session_ops")?;
+        stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut rewritten = 0;
+    for (seq, session_id, op_type, payload) in rows {
+        if json_column_is_sealed(&payload)? == seal {
+            continue;
+        }
+        let payload =
+            convert_json_column(cipher, payload, &op_context(&session_id, &op_type), seal)?;
+        tx.execute(
+            "UPDATE session_ops SET payload_json = ?2 WHERE seq = ?1",
+            params![seq, payload],
+        )?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+fn json_column_is_sealed(raw: &str) -> Result<bool> {
+    Ok(matches!(
+        serde_json::from_str::<serde_json::Value>(raw)?,
+        serde_json::Value::String(text) if is_sealed(&text)
+    ))
+}
+
+fn convert_json_column(
+    cipher: &SessionCipher,
+    raw: String,
+    context: &str,
+    seal: bool,
+) -> Result<String> {
+    let value = open_json(Some(cipher), serde_json::from_str(&raw)?, context)?;
+    if seal {
+        seal_json(Some(cipher), &value, context)
+    } else {
+        Ok(serde_json::to_string(&value)?)
+    }
+}
@@ -16,6 +16,78 @@ const MIGRATIONS: &[Migration] = &[
         version: 2,
         sql: include_str!("../migrations/0002_provider_credentials.sql"),
     },
+    Migration {
+        version: 3,
+        sql: include_str!("../migrations/0003_turn_search.sql"),
+    },
+    Migration {
+        version: 4,
+        sql: include_str!("../migrations/0004_session_locks.sql"),
+    },
+    Migration {
+        version: 5,
+        sql: include_str!("../migrations/0005_sync.sql"),
+    },
+    Migration {
+        version: 6,
+        sql: include_str!("../migrations/0006_project_settings.sql"),
+    },
+    Migration {
+        version: 7,
+        sql: include_str!("../migrations/0007_audit_log.sql"),
+    },
+    Migration {
+        version: 8,
+        sql: include_str!("../migrations/0008_tool_outputs.sql"),
+    },
+    Migration {
+        version: 9,
+        sql: include_str!("../migrations/0009_pending_responses.sql"),
+    },
+    Migration {
+        version: 10,
+        sql: include_str!("../migrations/0010_session_worktrees.sql"),
+    },
+    Migration {
+        version: 11,
+        sql: include_str!("../migrations/0011_turn_file_snapshots.sql"),
+    },
+    Migration {
+        version: 12,
+        sql: include_str!("../migrations/0012_session_drafts.sql"),
+    },
+    Migration {
+        version: 13,
+        sql: include_str!("../migrations/0013_task_queue.sql"),
+    },
+    Migration {
+        version: 14,
+        sql: include_str!("../migrations/0014_session_models.sql"),
+    },
+    Migration {
+        version: 15,
+        sql: include_str!("../migrations/0015_session_summaries.sql"),
+    },
+    Migration {
+        version: 16,
+        sql: include_str!("../migrations/0016_session_pins.sql"),
+    },
+    Migration {
+        version: 17,
+        sql: include_str!("../migrations/0017_turn_bookmarks.sql"),
+    },
+    Migration {
+        version: 18,
+        sql: include_str!("../migrations/0018_pending_turns.sql"),
+    },
+    Migration {
+        version: 19,
+        sql: include_str!("../migrations/0019_turn_search_message_text.sql"),
+    },
+    Migration {
+        version: 20,
+        sql: include_str!("../migrations/0020_turn_tool_call_count.sql"),
+    },
 ];
 
 pub(crate) fn apply(conn: &mut Connection) -> Result<()> {
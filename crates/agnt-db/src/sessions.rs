@@ -1,11 +1,12 @@
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use rusqlite::types::Type;
 use rusqlite::{OptionalExtension, Row, Transaction, params};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::cipher::{SessionCipher, open_json, open_text, seal_json, seal_text};
 use crate::database::Database;
 use crate::error::{Error, Result};
 
@@ -14,10 +15,57 @@ pub struct Project {
     pub id: String,
     pub root_dir: PathBuf,
     pub name: Option<String>,
+    pub settings: ProjectSettings,
     pub created_at_ms: i64,
     pub updated_at_ms: i64,
 }
 
+/// Settings that apply to every session in a project. Unset fields fall back
+/// to the application defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// Model to start sessions with, as `provider/model`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// Named preset for request options such as reasoning effort.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Which tools the agent may use without asking, e.g. `read-only`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<String>,
+    /// Gitignore-style patterns, relative to the project root, that the
+    /// agent's file tools must not touch.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored_paths: Vec<String>,
+    /// Gitignore-style patterns, relative to the project root, for files
+    /// the agent only writes with the user's approval.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protected_paths: Vec<String>,
+    /// Values, or secret types such as `jwt`, exempt from redaction of tool
+    /// output.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redaction_allowlist: Vec<String>,
+    /// Regexes that block a prompt or completion they match.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_patterns: Vec<String>,
+    /// Regexes whose matches in prompts and completions are replaced with
+    /// `[FILTERED]`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scrubbed_patterns: Vec<String>,
+    /// Formatters for the files the agent edits, as `pattern: command`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formatters: Vec<String>,
+    /// Named accounts whose credentials the project uses, as
+    /// `provider: account`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    /// WASM tools from the repository the user approved, with what they
+    /// may access, as `name: access`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub wasm_tools: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
@@ -27,6 +75,28 @@ pub struct Session {
     pub current_turn_id: Option<String>,
     pub created_at_ms: i64,
     pub updated_at_ms: i64,
+    /// Checkout the session was started in, when that is a linked worktree
+    /// of the project's repository rather than the project root itself.
+    #[serde(default)]
+    pub worktree_dir: Option<PathBuf>,
+    /// Provider and model of the session's latest turn.
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    #[serde(default)]
+    pub model_id: Option<String>,
+    /// Reasoning effort the latest turn was requested with, e.g. `high`.
+    #[serde(default)]
+    pub reasoning_effort: Option<String>,
+    /// Summary of the conversation, written once the session was left.
+    #[serde(default)]
+    pub summary: Option<serde_json::Value>,
+    /// Turn the summary was written at; behind `current_turn_id` when the
+    /// session went on after it.
+    #[serde(default)]
+    pub summary_turn_id: Option<String>,
+    /// Listed before unpinned sessions, whatever their age.
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +109,9 @@ pub struct Turn {
     pub conversation_state: serde_json::Value,
     pub usage: Option<serde_json::Value>,
     pub created_at_ms: i64,
+    /// Files the turn read or edited, as they were when it was stored.
+    #[serde(default)]
+    pub file_snapshots: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,10 +129,24 @@ pub struct TurnPathItem {
     pub depth: u32,
 }
 
+/// A turn matching a full-text search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnSearchHit {
+    pub session_id: String,
+    pub session_title: Option<String>,
+    pub turn_id: String,
+    /// Excerpt around the match, with matched terms wrapped in `[` `]`.
+    pub snippet: String,
+    pub created_at_ms: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSessionInput {
     pub project_id: String,
     pub title: Option<String>,
+    /// Linked worktree the session is started in, if any.
+    #[serde(default)]
+    pub worktree_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,10 +154,50 @@ pub struct AppendTurnInput {
     pub session_id: String,
     /// Parent to branch from. `None` uses the session's current checkout turn.
     pub parent_turn_id: Option<String>,
+    /// Fail with [`Error::CurrentTurnConflict`] unless the session is still
+    /// checked out at this turn, e.g. because another process moved it.
+    /// `None` skips the check.
+    #[serde(default)]
+    pub expected_current_turn_id: Option<String>,
     pub user_parts: serde_json::Value,
     pub assistant_parts: serde_json::Value,
     pub conversation_state: serde_json::Value,
     pub usage: Option<serde_json::Value>,
+    #[serde(default)]
+    pub file_snapshots: Option<serde_json::Value>,
+}
+
+/// Advisory lock marking the process that currently writes to a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLock {
+    pub session_id: String,
+    pub owner: String,
+    pub pid: u32,
+    pub acquired_at_ms: i64,
+    pub heartbeat_at_ms: i64,
+}
+
+/// A response still generating in the background when the process that
+/// started it exited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingResponse {
+    pub session_id: String,
+    /// Provider token to reattach to the response with.
+    pub token: String,
+    /// Conversation state the response continues from.
+    pub conversation_state: serde_json::Value,
+    pub created_at_ms: i64,
+}
+
+/// The prompt of a turn that was submitted but not stored yet. One still
+/// there when the session is next opened was cut off by the process dying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTurn {
+    pub session_id: String,
+    /// The session's current turn when the prompt was submitted.
+    pub parent_turn_id: Option<String>,
+    pub prompt: String,
+    pub created_at_ms: i64,
 }
 
 pub struct Sessions<'db> {
@@ -90,7 +217,7 @@ impl Sessions<'_> {
 
         let existing = tx
             .query_row(
-                "SELECT id, root_dir, name, created_at_ms, updated_at_ms
+                "SELECT id, root_dir, name, created_at_ms, updated_at_ms, settings_json
                  FROM projects
                  WHERE root_dir = ?1",
                 params![root_dir],
@@ -121,6 +248,7 @@ impl Sessions<'_> {
                 id,
                 root_dir: PathBuf::from(root_dir),
                 name,
+                settings: ProjectSettings::default(),
                 created_at_ms: now,
                 updated_at_ms: now,
             }
@@ -135,7 +263,7 @@ impl Sessions<'_> {
         self.db
             .conn
             .query_row(
-                "SELECT id, root_dir, name, created_at_ms, updated_at_ms
+                "SELECT id, root_dir, name, created_at_ms, updated_at_ms, settings_json
                  FROM projects
                  WHERE root_dir = ?1",
                 params![root_dir],
@@ -149,7 +277,7 @@ impl Sessions<'_> {
         self.db
             .conn
             .query_row(
-                "SELECT id, root_dir, name, created_at_ms, updated_at_ms
+                "SELECT id, root_dir, name, created_at_ms, updated_at_ms, settings_json
                  FROM projects
                  WHERE id = ?1",
                 params![project_id],
@@ -159,22 +287,65 @@ impl Sessions<'_> {
             .map_err(Error::from)
     }
 
+    /// Replace a project's settings.
+    pub fn update_project_settings(
+        &mut self,
+        project_id: &str,
+        settings: &ProjectSettings,
+    ) -> Result<Project> {
+        let now = now_ms();
+        let settings_json = serde_json::to_string(settings)?;
+
+        let tx = self.db.conn.transaction()?;
+        ensure_project_exists(&tx, project_id)?;
+        tx.execute(
+            "UPDATE projects
+             SET settings_json = ?2, updated_at_ms = ?3
+             WHERE id = ?1",
+            params![project_id, settings_json, now],
+        )?;
+        let project = tx.query_row(
+            "SELECT id, root_dir, name, created_at_ms, updated_at_ms, settings_json
+             FROM projects
+             WHERE id = ?1",
+            params![project_id],
+            row_to_project,
+        )?;
+        tx.commit()?;
+        Ok(project)
+    }
+
     pub fn create_session(&mut self, input: CreateSessionInput) -> Result<Session> {
         let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
         let tx = self.db.conn.transaction()?;
 
         ensure_project_exists(&tx, &input.project_id)?;
 
         let id = generate_id(&tx, "sess")?;
+        let title = input
+            .title
+            .as_deref()
+            .map(|title| seal_text(cipher, title, &title_context(&id)))
+            .transpose()?;
         tx.execute(
             "INSERT INTO sessions (
-                id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
-            ) VALUES (?1, ?2, ?3, NULL, NULL, ?4, ?5)",
-            params![id, input.project_id, input.title, now, now],
+                id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                worktree_dir
+            ) VALUES (?1, ?2, ?3, NULL, NULL, ?4, ?5, ?6)",
+            params![
+                id,
+                input.project_id,
+                title,
+                now,
+                now,
+                input.worktree_dir.as_deref().map(path_to_string)
+            ],
         )?;
 
         insert_session_op(
             &tx,
+            cipher,
             &id,
             "session.created",
             &json!({
@@ -186,7 +357,9 @@ impl Sessions<'_> {
         )?;
 
         let session = tx.query_row(
-            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
              FROM sessions
              WHERE id = ?1",
             params![id],
@@ -194,21 +367,24 @@ impl Sessions<'_> {
         )?;
 
         tx.commit()?;
-        Ok(session)
+        open_session(cipher, session)
     }
 
     pub fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
         self.db
             .conn
             .query_row(
-                "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+                "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
                  FROM sessions
                  WHERE id = ?1",
                 params![session_id],
                 row_to_session,
             )
-            .optional()
-            .map_err(Error::from)
+            .optional()?
+            .map(|session| open_session(self.db.cipher.as_ref(), session))
+            .transpose()
     }
 
     pub fn list_sessions_for_project(
@@ -216,16 +392,95 @@ impl Sessions<'_> {
         project_id: &str,
         limit: usize,
     ) -> Result<Vec<Session>> {
-        let mut stmt = self.db.conn.prepare(
-            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+        self.list_sessions(project_id, limit, "updated_at_ms DESC")
+    }
+
+    /// Like [`Self::list_sessions_for_project`], with pinned sessions
+    /// before all others, as session pickers list them.
+    pub fn list_sessions_pinned_first(
+        &self,
+        project_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Session>> {
+        self.list_sessions(project_id, limit, "pinned DESC, updated_at_ms DESC")
+    }
+
+    fn list_sessions(&self, project_id: &str, limit: usize, order: &str) -> Result<Vec<Session>> {
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
              FROM sessions
              WHERE project_id = ?1
-             ORDER BY updated_at_ms DESC
-             LIMIT ?2",
-        )?;
+             ORDER BY {order}
+             LIMIT ?2"
+        ))?;
 
         let iter = stmt.query_map(params![project_id, limit as i64], row_to_session)?;
-        collect_rows(iter)
+        collect_rows(iter)?
+            .into_iter()
+            .map(|session| open_session(self.db.cipher.as_ref(), session))
+            .collect()
+    }
+
+    /// Full-text search over the user and assistant text of stored turns,
+    /// best matches first. `project_id` restricts hits to one project.
+    ///
+    /// Every whitespace-separated word of `query` must match; FTS query
+    /// syntax is not interpreted. Encrypted turns are not indexed.
+    pub fn search_turns(
+        &self,
+        project_id: Option<&str>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<TurnSearchHit>> {
+        let match_expr = query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if match_expr.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = self.db.conn.prepare(
+            "SELECT t.session_id, s.title, t.id,
+                    snippet(turns_fts, -1, '[', ']', '…', 12), t.created_at_ms
+             FROM turns_fts
+             JOIN turns t ON t.id = turns_fts.turn_id
+             JOIN sessions s ON s.id = t.session_id
+             WHERE turns_fts MATCH ?1
+               AND (?2 IS NULL OR s.project_id = ?2)
+             ORDER BY bm25(turns_fts)
+             LIMIT ?3",
+        )?;
+
+        let iter = stmt.query_map(params![match_expr, project_id, limit as i64], |row| {
+            Ok(TurnSearchHit {
+                session_id: row.get(0)?,
+                session_title: row.get(1)?,
+                turn_id: row.get(2)?,
+                snippet: row.get(3)?,
+                created_at_ms: row.get(4)?,
+            })
+        })?;
+        collect_rows(iter)?
+            .into_iter()
+            .map(|mut hit| {
+                hit.session_title = hit
+                    .session_title
+                    .take()
+                    .map(|title| {
+                        open_text(
+                            self.db.cipher.as_ref(),
+                            title,
+                            &title_context(&hit.session_id),
+                        )
+                    })
+                    .transpose()?;
+                Ok(hit)
+            })
+            .collect()
     }
 
     pub fn set_session_title_if_missing(&mut self, session_id: &str, title: &str) -> Result<()> {
@@ -235,21 +490,24 @@ impl Sessions<'_> {
         }
 
         let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
         let tx = self.db.conn.transaction()?;
 
         ensure_session_exists(&tx, session_id)?;
 
+        let stored_title = seal_text(cipher, title, &title_context(session_id))?;
         let changed = tx.execute(
             "UPDATE sessions
              SET title = ?2, updated_at_ms = ?3
              WHERE id = ?1
                AND (title IS NULL OR trim(title) = '')",
-            params![session_id, title, now],
+            params![session_id, stored_title, now],
         )?;
 
         if changed > 0 {
             insert_session_op(
                 &tx,
+                cipher,
                 session_id,
                 "session.title_set",
                 &json!({ "title": title }),
@@ -261,13 +519,167 @@ impl Sessions<'_> {
         Ok(())
     }
 
+    /// Replace the session's title. A blank title clears it.
+    pub fn rename_session(&mut self, session_id: &str, title: &str) -> Result<Session> {
+        let title = Some(title.trim()).filter(|title| !title.is_empty());
+        let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        let stored_title = title
+            .map(|title| seal_text(cipher, title, &title_context(session_id)))
+            .transpose()?;
+        tx.execute(
+            "UPDATE sessions
+             SET title = ?2, updated_at_ms = ?3
+             WHERE id = ?1",
+            params![session_id, stored_title, now],
+        )?;
+
+        insert_session_op(
+            &tx,
+            cipher,
+            session_id,
+            "session.renamed",
+            &json!({ "title": title }),
+            now,
+        )?;
+
+        let session = tx.query_row(
+            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
+             FROM sessions
+             WHERE id = ?1",
+            params![session_id],
+            row_to_session,
+        )?;
+        tx.commit()?;
+        open_session(cipher, session)
+    }
+
+    /// Record the model the session generates with, so resuming it picks
+    /// the same one. Does nothing when it is already recorded.
+    pub fn set_session_model(
+        &mut self,
+        session_id: &str,
+        provider_id: &str,
+        model_id: &str,
+        reasoning_effort: Option<&str>,
+    ) -> Result<()> {
+        let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        let changed = tx.execute(
+            "UPDATE sessions
+             SET provider_id = ?2, model_id = ?3, reasoning_effort = ?4
+             WHERE id = ?1
+               AND (provider_id IS NOT ?2 OR model_id IS NOT ?3 OR reasoning_effort IS NOT ?4)",
+            params![session_id, provider_id, model_id, reasoning_effort],
+        )?;
+        if changed > 0 {
+            insert_session_op(
+                &tx,
+                cipher,
+                session_id,
+                "session.model_set",
+                &json!({
+                    "provider_id": provider_id,
+                    "model_id": model_id,
+                    "reasoning_effort": reasoning_effort,
+                }),
+                now,
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the summary of the session as of `turn_id`, replacing any
+    /// earlier one.
+    pub fn set_session_summary(
+        &mut self,
+        session_id: &str,
+        summary: &serde_json::Value,
+        turn_id: &str,
+    ) -> Result<()> {
+        let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        let stored = seal_json(cipher, summary, &summary_context(session_id))?;
+        tx.execute(
+            "UPDATE sessions SET summary_json = ?2, summary_turn_id = ?3 WHERE id = ?1",
+            params![session_id, stored, turn_id],
+        )?;
+        insert_session_op(
+            &tx,
+            cipher,
+            session_id,
+            "session.summarized",
+            &json!({ "summary": summary, "turn_id": turn_id }),
+            now,
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Pin the session to the top of session lists, or unpin it.
+    pub fn set_session_pinned(&mut self, session_id: &str, pinned: bool) -> Result<()> {
+        let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        let changed = tx.execute(
+            "UPDATE sessions SET pinned = ?2 WHERE id = ?1 AND pinned IS NOT ?2",
+            params![session_id, pinned],
+        )?;
+        if changed > 0 {
+            insert_session_op(
+                &tx,
+                cipher,
+                session_id,
+                "session.pinned",
+                &json!({ "pinned": pinned }),
+                now,
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete the session together with its turns, ops, lock and stored
+    /// tool outputs. Deletions are not synced to other devices.
+    pub fn delete_session(&mut self, session_id: &str) -> Result<()> {
+        let deleted = self
+            .db
+            .conn
+            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
+        if deleted == 0 {
+            return Err(Error::SessionNotFound(session_id.to_string()));
+        }
+        Ok(())
+    }
+
     pub fn append_turn(&mut self, input: AppendTurnInput) -> Result<Turn> {
         let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
         let tx = self.db.conn.transaction()?;
 
         let session = tx
             .query_row(
-                "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+                "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
                  FROM sessions
                  WHERE id = ?1",
                 params![input.session_id],
@@ -276,6 +688,16 @@ impl Sessions<'_> {
             .optional()?
             .ok_or_else(|| Error::SessionNotFound(input.session_id.clone()))?;
 
+        if let Some(expected) = input.expected_current_turn_id.as_deref()
+            && session.current_turn_id.as_deref() != Some(expected)
+        {
+            return Err(Error::CurrentTurnConflict {
+                session_id: input.session_id.clone(),
+                expected: expected.to_string(),
+                actual: session.current_turn_id.clone(),
+            });
+        }
+
         let parent_turn_id = input.parent_turn_id.or(session.current_turn_id.clone());
         if let Some(parent_turn_id) = parent_turn_id.as_deref() {
             match ensure_turn_belongs_to_session(&tx, &input.session_id, parent_turn_id) {
@@ -294,20 +716,40 @@ impl Sessions<'_> {
         }
 
         let turn_id = generate_id(&tx, "turn")?;
-        let user_parts_json = serde_json::to_string(&input.user_parts)?;
-        let assistant_parts_json = serde_json::to_string(&input.assistant_parts)?;
-        let conversation_state_json = serde_json::to_string(&input.conversation_state)?;
+        let user_parts_json = seal_json(
+            cipher,
+            &input.user_parts,
+            &turn_context(&turn_id, "user_parts"),
+        )?;
+        let assistant_parts_json = seal_json(
+            cipher,
+            &input.assistant_parts,
+            &turn_context(&turn_id, "assistant_parts"),
+        )?;
+        let conversation_state_json = seal_json(
+            cipher,
+            &input.conversation_state,
+            &turn_context(&turn_id, "conversation_state"),
+        )?;
         let usage_json = input
             .usage
             .as_ref()
-            .map(serde_json::to_string)
+            .map(|usage| seal_json(cipher, usage, &turn_context(&turn_id, "usage")))
+            .transpose()?;
+        let file_snapshots_json = input
+            .file_snapshots
+            .as_ref()
+            .map(|snapshots| {
+                seal_json(cipher, snapshots, &turn_context(&turn_id, "file_snapshots"))
+            })
             .transpose()?;
 
         tx.execute(
             "INSERT INTO turns (
                 id, session_id, parent_turn_id,
-                user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms
-             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms,
+                file_snapshots_json, tool_call_count
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
                 turn_id,
                 input.session_id,
@@ -316,7 +758,9 @@ impl Sessions<'_> {
                 assistant_parts_json,
                 conversation_state_json,
                 usage_json,
-                now
+                now,
+                file_snapshots_json,
+                count_tool_calls(&input.assistant_parts)
             ],
         )?;
 
@@ -328,8 +772,16 @@ impl Sessions<'_> {
             params![input.session_id, root_turn_id, turn_id, now],
         )?;
 
+        // The turn is stored, so the prompt it started from is no longer at
+        // risk.
+        tx.execute(
+            "DELETE FROM pending_turns WHERE session_id = ?1",
+            params![input.session_id],
+        )?;
+
         insert_session_op(
             &tx,
+            cipher,
             &input.session_id,
             "turn.appended",
             &json!({
@@ -339,6 +791,7 @@ impl Sessions<'_> {
                 "assistant_parts": input.assistant_parts.clone(),
                 "conversation_state": input.conversation_state.clone(),
                 "usage": input.usage.clone(),
+                "file_snapshots": input.file_snapshots.clone(),
             }),
             now,
         )?;
@@ -346,7 +799,8 @@ impl Sessions<'_> {
         let turn = tx.query_row(
             "SELECT
                 id, session_id, parent_turn_id,
-                user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms
+                user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms,
+                file_snapshots_json
              FROM turns
              WHERE id = ?1",
             params![turn_id],
@@ -354,7 +808,7 @@ impl Sessions<'_> {
         )?;
 
         tx.commit()?;
-        Ok(turn)
+        open_turn(cipher, turn)
     }
 
     pub fn get_turn(&self, turn_id: &str) -> Result<Option<Turn>> {
@@ -363,18 +817,21 @@ impl Sessions<'_> {
             .query_row(
                 "SELECT
                     id, session_id, parent_turn_id,
-                    user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms
+                    user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms,
+                    file_snapshots_json
                  FROM turns
                  WHERE id = ?1",
                 params![turn_id],
                 row_to_turn,
             )
-            .optional()
-            .map_err(Error::from)
+            .optional()?
+            .map(|turn| open_turn(self.db.cipher.as_ref(), turn))
+            .transpose()
     }
 
     pub fn checkout_turn(&mut self, session_id: &str, turn_id: &str) -> Result<Session> {
         let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
         let tx = self.db.conn.transaction()?;
 
         ensure_session_exists(&tx, session_id)?;
@@ -389,6 +846,7 @@ impl Sessions<'_> {
 
         insert_session_op(
             &tx,
+            cipher,
             session_id,
             "session.checkout",
             &json!({ "turn_id": turn_id }),
@@ -396,14 +854,54 @@ impl Sessions<'_> {
         )?;
 
         let session = tx.query_row(
-            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
+             FROM sessions
+             WHERE id = ?1",
+            params![session_id],
+            row_to_session,
+        )?;
+        tx.commit()?;
+        open_session(cipher, session)
+    }
+
+    /// Point the session at no turn, so the next appended turn starts a new
+    /// root. Existing turns are kept.
+    pub fn clear_current_turn(&mut self, session_id: &str) -> Result<Session> {
+        let now = now_ms();
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        tx.execute(
+            "UPDATE sessions
+             SET current_turn_id = NULL, updated_at_ms = ?2
+             WHERE id = ?1",
+            params![session_id, now],
+        )?;
+
+        insert_session_op(
+            &tx,
+            cipher,
+            session_id,
+            "session.checkout",
+            &json!({ "turn_id": null }),
+            now,
+        )?;
+
+        let session = tx.query_row(
+            "SELECT id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms,
+                    worktree_dir, provider_id, model_id, reasoning_effort, summary_json, summary_turn_id,
+                    pinned
              FROM sessions
              WHERE id = ?1",
             params![session_id],
             row_to_session,
         )?;
         tx.commit()?;
-        Ok(session)
+        open_session(cipher, session)
     }
 
     pub fn current_turn(&self, session_id: &str) -> Result<Option<Turn>> {
@@ -412,15 +910,17 @@ impl Sessions<'_> {
             .query_row(
                 "SELECT
                     t.id, t.session_id, t.parent_turn_id,
-                    t.user_parts_json, t.assistant_parts_json, t.conversation_state_json, t.usage_json, t.created_at_ms
+                    t.user_parts_json, t.assistant_parts_json, t.conversation_state_json, t.usage_json, t.created_at_ms,
+                    t.file_snapshots_json
                  FROM sessions s
                  JOIN turns t ON t.id = s.current_turn_id
                  WHERE s.id = ?1",
                 params![session_id],
                 row_to_turn,
             )
-            .optional()
-            .map_err(Error::from)
+            .optional()?
+            .map(|turn| open_turn(self.db.cipher.as_ref(), turn))
+            .transpose()
     }
 
     pub fn turn_path_to_current(&self, session_id: &str) -> Result<Vec<TurnPathItem>> {
@@ -438,6 +938,7 @@ impl Sessions<'_> {
              SELECT
                 t.id, t.session_id, t.parent_turn_id,
                 t.user_parts_json, t.assistant_parts_json, t.conversation_state_json, t.usage_json, t.created_at_ms,
+                t.file_snapshots_json,
                 chain.depth
              FROM chain
              JOIN turns t ON t.id = chain.id
@@ -446,13 +947,19 @@ impl Sessions<'_> {
 
         let iter = stmt.query_map(params![session_id], |row| {
             let turn = row_to_turn(row)?;
-            let depth: i64 = row.get(8)?;
+            let depth: i64 = row.get(9)?;
             Ok(TurnPathItem {
                 turn,
                 depth: depth as u32,
             })
         })?;
-        collect_rows(iter)
+        collect_rows(iter)?
+            .into_iter()
+            .map(|mut item| {
+                item.turn = open_turn(self.db.cipher.as_ref(), item.turn)?;
+                Ok(item)
+            })
+            .collect()
     }
 
     pub fn list_session_ops(
@@ -473,7 +980,351 @@ impl Sessions<'_> {
             params![session_id, after_seq, limit as i64],
             row_to_session_op,
         )?;
-        collect_rows(iter)
+        collect_rows(iter)?
+            .into_iter()
+            .map(|mut op| {
+                op.payload = open_json(
+                    self.db.cipher.as_ref(),
+                    op.payload,
+                    &op_context(&op.session_id, &op.op_type),
+                )?;
+                Ok(op)
+            })
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Advisory locks
+// ---------------------------------------------------------------------------
+
+impl Sessions<'_> {
+    /// Take the advisory lock on `session_id` for `owner`.
+    ///
+    /// Succeeds if the session is unlocked, already held by `owner`, or held
+    /// by a lock whose heartbeat is older than `stale_after` (its process is
+    /// assumed dead). Otherwise fails with [`Error::SessionLocked`].
+    pub fn lock_session(
+        &mut self,
+        session_id: &str,
+        owner: &str,
+        pid: u32,
+        stale_after: Duration,
+    ) -> Result<SessionLock> {
+        let now = now_ms();
+        let tx = self.db.conn.transaction()?;
+
+        ensure_session_exists(&tx, session_id)?;
+
+        let existing = tx
+            .query_row(
+                "SELECT session_id, owner, pid, acquired_at_ms, heartbeat_at_ms
+                 FROM session_locks
+                 WHERE session_id = ?1",
+                params![session_id],
+                row_to_session_lock,
+            )
+            .optional()?;
+
+        let stale_before_ms = now.saturating_sub(stale_after.as_millis() as i64);
+        if let Some(existing) = &existing
+            && existing.owner != owner
+            && existing.heartbeat_at_ms >= stale_before_ms
+        {
+            return Err(Error::SessionLocked {
+                session_id: session_id.to_string(),
+                pid: existing.pid,
+            });
+        }
+
+        let acquired_at_ms = existing
+            .filter(|existing| existing.owner == owner)
+            .map_or(now, |existing| existing.acquired_at_ms);
+        tx.execute(
+            "INSERT INTO session_locks (session_id, owner, pid, acquired_at_ms, heartbeat_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(session_id) DO UPDATE SET
+                owner = excluded.owner,
+                pid = excluded.pid,
+                acquired_at_ms = excluded.acquired_at_ms,
+                heartbeat_at_ms = excluded.heartbeat_at_ms",
+            params![session_id, owner, pid, acquired_at_ms, now],
+        )?;
+
+        tx.commit()?;
+        Ok(SessionLock {
+            session_id: session_id.to_string(),
+            owner: owner.to_string(),
+            pid,
+            acquired_at_ms,
+            heartbeat_at_ms: now,
+        })
+    }
+
+    /// Refresh the heartbeat of `owner`'s lock. Returns `false` if the lock
+    /// is no longer held by `owner` (released, or taken over as stale).
+    pub fn refresh_session_lock(&mut self, session_id: &str, owner: &str) -> Result<bool> {
+        let changed = self.db.conn.execute(
+            "UPDATE session_locks
+             SET heartbeat_at_ms = ?3
+             WHERE session_id = ?1 AND owner = ?2",
+            params![session_id, owner, now_ms()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Release `owner`'s lock on `session_id`, if it still holds it.
+    pub fn unlock_session(&mut self, session_id: &str, owner: &str) -> Result<()> {
+        self.db.conn.execute(
+            "DELETE FROM session_locks WHERE session_id = ?1 AND owner = ?2",
+            params![session_id, owner],
+        )?;
+        Ok(())
+    }
+
+    pub fn session_lock(&self, session_id: &str) -> Result<Option<SessionLock>> {
+        self.db
+            .conn
+            .query_row(
+                "SELECT session_id, owner, pid, acquired_at_ms, heartbeat_at_ms
+                 FROM session_locks
+                 WHERE session_id = ?1",
+                params![session_id],
+                row_to_session_lock,
+            )
+            .optional()
+            .map_err(Error::from)
+    }
+
+    /// Keep the full output of a tool call whose result was truncated.
+    pub fn save_tool_output(
+        &mut self,
+        session_id: &str,
+        tool_call_id: &str,
+        content: &str,
+    ) -> Result<()> {
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        ensure_session_exists(&tx, session_id)?;
+        let content = seal_text(cipher, content, &tool_output_context(tool_call_id))?;
+        tx.execute(
+            "INSERT INTO tool_outputs (tool_call_id, session_id, content, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(tool_call_id) DO UPDATE SET content = excluded.content",
+            params![tool_call_id, session_id, content, now_ms()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remember a background response for `session_id` until it finishes,
+    /// replacing any earlier one.
+    pub fn set_pending_response(
+        &mut self,
+        session_id: &str,
+        token: &str,
+        conversation_state: &serde_json::Value,
+    ) -> Result<()> {
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        ensure_session_exists(&tx, session_id)?;
+        let conversation_state_json = seal_json(
+            cipher,
+            conversation_state,
+            &pending_response_context(session_id),
+        )?;
+        tx.execute(
+            "INSERT INTO pending_responses (session_id, token, conversation_state_json, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id) DO UPDATE SET
+                token = excluded.token,
+                conversation_state_json = excluded.conversation_state_json,
+                created_at_ms = excluded.created_at_ms",
+            params![session_id, token, conversation_state_json, now_ms()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn pending_response(&self, session_id: &str) -> Result<Option<PendingResponse>> {
+        let row = self
+            .db
+            .conn
+            .query_row(
+                "SELECT token, conversation_state_json, created_at_ms
+                 FROM pending_responses
+                 WHERE session_id = ?1",
+                params![session_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        row.map(|(token, conversation_state_json, created_at_ms)| {
+            Ok(PendingResponse {
+                session_id: session_id.to_string(),
+                token,
+                conversation_state: open_json(
+                    self.db.cipher.as_ref(),
+                    serde_json::from_str(&conversation_state_json)?,
+                    &pending_response_context(session_id),
+                )?,
+                created_at_ms,
+            })
+        })
+        .transpose()
+    }
+
+    pub fn clear_pending_response(&mut self, session_id: &str) -> Result<()> {
+        self.db.conn.execute(
+            "DELETE FROM pending_responses WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record that a turn with `prompt` was submitted in `session_id`, on top
+    /// of `parent_turn_id`, replacing any earlier record. It is removed when
+    /// the turn is appended or [cleared](Sessions::clear_pending_turn).
+    pub fn begin_turn(
+        &mut self,
+        session_id: &str,
+        parent_turn_id: Option<&str>,
+        prompt: &str,
+    ) -> Result<()> {
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        ensure_session_exists(&tx, session_id)?;
+        let prompt = seal_text(cipher, prompt, &pending_turn_context(session_id))?;
+        tx.execute(
+            "INSERT INTO pending_turns (session_id, parent_turn_id, prompt, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id) DO UPDATE SET
+                parent_turn_id = excluded.parent_turn_id,
+                prompt = excluded.prompt,
+                created_at_ms = excluded.created_at_ms",
+            params![session_id, parent_turn_id, prompt, now_ms()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn pending_turn(&self, session_id: &str) -> Result<Option<PendingTurn>> {
+        let row = self
+            .db
+            .conn
+            .query_row(
+                "SELECT parent_turn_id, prompt, created_at_ms
+                 FROM pending_turns
+                 WHERE session_id = ?1",
+                params![session_id],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        row.map(|(parent_turn_id, prompt, created_at_ms)| {
+            Ok(PendingTurn {
+                session_id: session_id.to_string(),
+                parent_turn_id,
+                prompt: open_text(
+                    self.db.cipher.as_ref(),
+                    prompt,
+                    &pending_turn_context(session_id),
+                )?,
+                created_at_ms,
+            })
+        })
+        .transpose()
+    }
+
+    pub fn clear_pending_turn(&mut self, session_id: &str) -> Result<()> {
+        self.db.conn.execute(
+            "DELETE FROM pending_turns WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Keep the unsent composer text of `session_id` and the cursor's byte
+    /// offset in it. An empty draft is removed.
+    pub fn set_draft(&mut self, session_id: &str, text: &str, cursor: usize) -> Result<()> {
+        if text.is_empty() {
+            self.db.conn.execute(
+                "DELETE FROM session_drafts WHERE session_id = ?1",
+                params![session_id],
+            )?;
+            return Ok(());
+        }
+
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        ensure_session_exists(&tx, session_id)?;
+        let text = seal_text(cipher, text, &draft_context(session_id))?;
+        tx.execute(
+            "INSERT INTO session_drafts (session_id, text, cursor, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id) DO UPDATE SET
+                text = excluded.text,
+                cursor = excluded.cursor,
+                updated_at_ms = excluded.updated_at_ms",
+            params![session_id, text, cursor as i64, now_ms()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The unsent composer text of `session_id` and the cursor's byte offset
+    /// in it, if a draft was kept.
+    pub fn draft(&self, session_id: &str) -> Result<Option<(String, usize)>> {
+        let row = self
+            .db
+            .conn
+            .query_row(
+                "SELECT text, cursor FROM session_drafts WHERE session_id = ?1",
+                params![session_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()?;
+        row.map(|(text, cursor)| {
+            let text = open_text(self.db.cipher.as_ref(), text, &draft_context(session_id))?;
+            Ok((text, cursor.max(0) as usize))
+        })
+        .transpose()
+    }
+
+    /// The most recently saved full tool output of a session, as
+    /// `(tool_call_id, content)`.
+    pub fn latest_tool_output(&self, session_id: &str) -> Result<Option<(String, String)>> {
+        let row = self
+            .db
+            .conn
+            .query_row(
+                "SELECT tool_call_id, content FROM tool_outputs
+                 WHERE session_id = ?1
+                 ORDER BY created_at_ms DESC, rowid DESC
+                 LIMIT 1",
+                params![session_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        row.map(|(tool_call_id, content)| {
+            let content = open_text(
+                self.db.cipher.as_ref(),
+                content,
+                &tool_output_context(&tool_call_id),
+            )?;
+            Ok((tool_call_id, content))
+        })
+        .transpose()
     }
 }
 
@@ -532,6 +1383,7 @@ fn ensure_turn_belongs_to_session(
 
 fn insert_session_op(
     tx: &Transaction<'_>,
+    cipher: Option<&SessionCipher>,
     session_id: &str,
     op_type: &str,
     payload: &serde_json::Value,
@@ -543,26 +1395,120 @@ fn insert_session_op(
         params![
             session_id,
             op_type,
-            serde_json::to_string(payload)?,
+            seal_json(cipher, payload, &op_context(session_id, op_type))?,
             created_at_ms
         ],
     )?;
     Ok(())
 }
 
-fn generate_id(tx: &Transaction<'_>, prefix: &str) -> rusqlite::Result<String> {
+pub(crate) fn generate_id(tx: &Transaction<'_>, prefix: &str) -> rusqlite::Result<String> {
     tx.query_row("SELECT lower(hex(randomblob(16)))", [], |row| {
         let suffix: String = row.get(0)?;
         Ok(format!("{prefix}_{suffix}"))
     })
 }
 
+// ---------------------------------------------------------------------------
+// Encryption
+// ---------------------------------------------------------------------------
+
+// Contexts bind sealed values to their row and column, so ciphertext copied
+// elsewhere fails to open.
+
+pub(crate) fn title_context(session_id: &str) -> String {
+    format!("session:{session_id}:title")
+}
+
+pub(crate) fn turn_context(turn_id: &str, column: &str) -> String {
+    format!("turn:{turn_id}:{column}")
+}
+
+/// Number of tool calls among a turn's (unsealed) assistant parts.
+pub(crate) fn count_tool_calls(assistant_parts: &serde_json::Value) -> i64 {
+    assistant_parts.as_array().map_or(0, |parts| {
+        parts
+            .iter()
+            .filter(|part| part.get("ToolCall").is_some())
+            .count() as i64
+    })
+}
+
+pub(crate) fn tool_output_context(tool_call_id: &str) -> String {
+    format!("tool_output:{tool_call_id}")
+}
+
+pub(crate) fn pending_response_context(session_id: &str) -> String {
+    format!("pending_response:{session_id}")
+}
+
+pub(crate) fn summary_context(session_id: &str) -> String {
+    format!("summary:{session_id}")
+}
+
+pub(crate) fn draft_context(session_id: &str) -> String {
+    format!("draft:{session_id}")
+}
+
+pub(crate) fn pending_turn_context(session_id: &str) -> String {
+    format!("pending_turn:{session_id}")
+}
+
+pub(crate) fn op_context(session_id: &str, op_type: &str) -> String {
+    format!("op:{session_id}:{op_type}")
+}
+
+fn open_session(cipher: Option<&SessionCipher>, mut session: Session) -> Result<Session> {
+    session.title = session
+        .title
+        .take()
+        .map(|title| open_text(cipher, title, &title_context(&session.id)))
+        .transpose()?;
+    session.summary = session
+        .summary
+        .take()
+        .map(|summary| open_json(cipher, summary, &summary_context(&session.id)))
+        .transpose()?;
+    Ok(session)
+}
+
+fn open_turn(cipher: Option<&SessionCipher>, mut turn: Turn) -> Result<Turn> {
+    turn.user_parts = open_json(
+        cipher,
+        turn.user_parts,
+        &turn_context(&turn.id, "user_parts"),
+    )?;
+    turn.assistant_parts = open_json(
+        cipher,
+        turn.assistant_parts,
+        &turn_context(&turn.id, "assistant_parts"),
+    )?;
+    turn.conversation_state = open_json(
+        cipher,
+        turn.conversation_state,
+        &turn_context(&turn.id, "conversation_state"),
+    )?;
+    turn.usage = turn
+        .usage
+        .take()
+        .map(|usage| open_json(cipher, usage, &turn_context(&turn.id, "usage")))
+        .transpose()?;
+    turn.file_snapshots = turn
+        .file_snapshots
+        .take()
+        .map(|snapshots| open_json(cipher, snapshots, &turn_context(&turn.id, "file_snapshots")))
+        .transpose()?;
+    Ok(turn)
+}
+
 fn row_to_project(row: &Row<'_>) -> rusqlite::Result<Project> {
     let root_dir: String = row.get(1)?;
     Ok(Project {
         id: row.get(0)?,
         root_dir: PathBuf::from(root_dir),
         name: row.get(2)?,
+        settings: serde_json::from_value(parse_json_column(row, 5)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, Box::new(e)))?,
         created_at_ms: row.get(3)?,
         updated_at_ms: row.get(4)?,
     })
@@ -577,6 +1523,13 @@ fn row_to_session(row: &Row<'_>) -> rusqlite::Result<Session> {
         current_turn_id: row.get(4)?,
         created_at_ms: row.get(5)?,
         updated_at_ms: row.get(6)?,
+        worktree_dir: row.get::<_, Option<String>>(7)?.map(PathBuf::from),
+        provider_id: row.get(8)?,
+        model_id: row.get(9)?,
+        reasoning_effort: row.get(10)?,
+        summary: parse_optional_json_column(row, 11)?,
+        summary_turn_id: row.get(12)?,
+        pinned: row.get(13)?,
     })
 }
 
@@ -590,6 +1543,17 @@ fn row_to_turn(row: &Row<'_>) -> rusqlite::Result<Turn> {
         conversation_state: parse_json_column(row, 5)?,
         usage: parse_optional_json_column(row, 6)?,
         created_at_ms: row.get(7)?,
+        file_snapshots: parse_optional_json_column(row, 8)?,
+    })
+}
+
+fn row_to_session_lock(row: &Row<'_>) -> rusqlite::Result<SessionLock> {
+    Ok(SessionLock {
+        session_id: row.get(0)?,
+        owner: row.get(1)?,
+        pid: row.get(2)?,
+        acquired_at_ms: row.get(3)?,
+        heartbeat_at_ms: row.get(4)?,
     })
 }
 
@@ -633,7 +1597,7 @@ where
     Ok(rows)
 }
 
-fn now_ms() -> i64 {
+pub(crate) fn now_ms() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -1,9 +1,16 @@
 use std::path::Path;
 
+use crate::audit_log::AuditLog;
+use crate::bookmarks::Bookmarks;
+use crate::cipher::SessionCipher;
 use crate::database::Database;
 use crate::error::Result;
+use crate::maintenance::Maintenance;
 use crate::provider_credentials::ProviderCredentials;
 use crate::sessions::Sessions;
+use crate::sync_log::SyncLog;
+use crate::task_queue::TaskQueue;
+use crate::usage_log::UsageLog;
 
 pub struct Store {
     db: Database,
@@ -22,10 +29,44 @@ impl Store {
         })
     }
 
+    /// Encrypt session content written from now on, and decrypt sealed
+    /// content on read. `None` writes plaintext again.
+    pub fn set_session_cipher(&mut self, cipher: Option<SessionCipher>) {
+        self.db.cipher = cipher;
+    }
+
+    pub fn has_session_cipher(&self) -> bool {
+        self.db.cipher.is_some()
+    }
+
     pub fn sessions(&mut self) -> Sessions<'_> {
         Sessions { db: &mut self.db }
     }
 
+    pub fn maintenance(&mut self) -> Maintenance<'_> {
+        Maintenance { db: &mut self.db }
+    }
+
+    pub fn sync_log(&mut self) -> SyncLog<'_> {
+        SyncLog { db: &mut self.db }
+    }
+
+    pub fn usage_log(&mut self) -> UsageLog<'_> {
+        UsageLog { db: &mut self.db }
+    }
+
+    pub fn audit_log(&mut self) -> AuditLog<'_> {
+        AuditLog { db: &mut self.db }
+    }
+
+    pub fn bookmarks(&mut self) -> Bookmarks<'_> {
+        Bookmarks { db: &mut self.db }
+    }
+
+    pub fn task_queue(&mut self) -> TaskQueue<'_> {
+        TaskQueue { db: &mut self.db }
+    }
+
     pub fn provider_credentials(&mut self) -> ProviderCredentials<'_> {
         ProviderCredentials { db: &mut self.db }
     }
@@ -0,0 +1,423 @@
+use std::path::PathBuf;
+
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::{SessionCipher, open_json, seal_json, seal_text};
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::sessions::{
+    count_tool_calls, generate_id, op_context, summary_context, title_context, turn_context,
+};
+
+const DEVICE_ID_KEY: &str = "device_id";
+const PUSHED_SEQ_KEY: &str = "pushed_seq";
+const PULL_CURSOR_KEY: &str = "pull_cursor";
+
+/// A session op as exchanged with a sync remote.
+///
+/// Ops are identified by the device that recorded them and that device's
+/// local sequence number. Sessions and turns keep their ids across devices,
+/// so replaying every device's ops merges their session trees; the checkout
+/// pointer follows whichever op was applied last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteOp {
+    pub origin: String,
+    pub origin_seq: i64,
+    pub session_id: String,
+    /// Root of the session's project on the origin device. Used to file the
+    /// session under the project with the same root on other devices.
+    pub project_root: PathBuf,
+    pub op_type: String,
+    pub payload: serde_json::Value,
+    pub created_at_ms: i64,
+}
+
+pub struct SyncLog<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+impl SyncLog<'_> {
+    /// Stable id of this database on sync remotes, created on first use.
+    pub fn device_id(&mut self) -> Result<String> {
+        let tx = self.db.conn.transaction()?;
+        let device_id = match get_state(&tx, DEVICE_ID_KEY)? {
+            Some(device_id) => device_id,
+            None => {
+                let device_id = generate_id(&tx, "dev")?;
+                set_state(&tx, DEVICE_ID_KEY, &device_id)?;
+                device_id
+            }
+        };
+        tx.commit()?;
+        Ok(device_id)
+    }
+
+    /// Ops recorded on this device that have not been pushed yet, oldest
+    /// first. With a session cipher set, payloads stay sealed (plaintext ones
+    /// written before encryption was turned on are sealed here), so the
+    /// remote never sees session content.
+    pub fn pending_ops(&mut self, limit: usize) -> Result<Vec<RemoteOp>> {
+        let device_id = self.device_id()?;
+        let pushed_seq = self.pushed_seq()?;
+
+        let mut stmt = self.db.conn.prepare(
+            "SELECT o.seq, o.session_id, p.root_dir, o.op_type, o.payload_json, o.created_at_ms
+             FROM session_ops o
+             JOIN sessions s ON s.id = o.session_id
+             JOIN projects p ON p.id = s.project_id
+             WHERE o.origin IS NULL AND o.seq > ?1
+             ORDER BY o.seq ASC
+             LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(params![pushed_seq, limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let cipher = self.db.cipher.as_ref();
+        rows.into_iter()
+            .map(
+                |(seq, session_id, project_root, op_type, payload, created_at_ms)| {
+                    let mut payload = serde_json::from_str(&payload)?;
+                    if cipher.is_some() {
+                        let context = op_context(&session_id, &op_type);
+                        let opened = open_json(cipher, payload, &context)?;
+                        payload = serde_json::from_str(&seal_json(cipher, &opened, &context)?)?;
+                    }
+                    Ok(RemoteOp {
+                        origin: device_id.clone(),
+                        origin_seq: seq,
+                        session_id,
+                        project_root: PathBuf::from(project_root),
+                        op_type,
+                        payload,
+                        created_at_ms,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Record that local ops up to and including `seq` reached the remote.
+    pub fn mark_pushed(&mut self, seq: i64) -> Result<()> {
+        let tx = self.db.conn.transaction()?;
+        set_state(&tx, PUSHED_SEQ_KEY, &seq.to_string())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Opaque position in the remote's op stream reached by the last pull.
+    pub fn pull_cursor(&self) -> Result<Option<String>> {
+        get_state(&self.db.conn, PULL_CURSOR_KEY)
+    }
+
+    pub fn set_pull_cursor(&mut self, cursor: &str) -> Result<()> {
+        let tx = self.db.conn.transaction()?;
+        set_state(&tx, PULL_CURSOR_KEY, cursor)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replay ops pulled from the remote. Ops from this device and ops that
+    /// were already applied are skipped. Sealed payloads are opened with this
+    /// device's session key, so every syncing device needs the same key.
+    /// Returns how many ops were applied.
+    pub fn apply_remote_ops(&mut self, ops: &[RemoteOp]) -> Result<usize> {
+        let device_id = self.device_id()?;
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+
+        let mut applied = 0;
+        for op in ops {
+            if op.origin == device_id || op_already_applied(&tx, op)? {
+                continue;
+            }
+            let payload = open_json(
+                cipher,
+                op.payload.clone(),
+                &op_context(&op.session_id, &op.op_type),
+            )
+            .map_err(|err| match err {
+                Error::Encryption(_) => Error::Encryption(format!(
+                    "op {} from device {} is sealed with another session key",
+                    op.origin_seq, op.origin
+                )),
+                err => err,
+            })?;
+            let op = RemoteOp {
+                payload,
+                ..op.clone()
+            };
+            apply_op(&tx, cipher, &op)?;
+            applied += 1;
+        }
+
+        tx.commit()?;
+        Ok(applied)
+    }
+
+    fn pushed_seq(&self) -> Result<i64> {
+        let value = get_state(&self.db.conn, PUSHED_SEQ_KEY)?;
+        Ok(value.and_then(|value| value.parse().ok()).unwrap_or(0))
+    }
+}
+
+fn get_state(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT value FROM sync_state WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn set_state(tx: &Transaction<'_>, key: &str, value: &str) -> Result<()> {
+    tx.execute(
+        "INSERT INTO sync_state (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+fn op_already_applied(tx: &Transaction<'_>, op: &RemoteOp) -> Result<bool> {
+    Ok(tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM session_ops WHERE origin = ?1 AND origin_seq = ?2)",
+        params![op.origin, op.origin_seq],
+        |row| row.get::<_, i64>(0),
+    )? != 0)
+}
+
+fn apply_op(tx: &Transaction<'_>, cipher: Option<&SessionCipher>, op: &RemoteOp) -> Result<()> {
+    ensure_session(tx, op)?;
+
+    match op.op_type.as_str() {
+        "session.created" | "session.title_set" => {
+            if let Some(title) = op.payload.get("title").and_then(|title| title.as_str()) {
+                let title = seal_text(cipher, title, &title_context(&op.session_id))?;
+                tx.execute(
+                    "UPDATE sessions
+                     SET title = ?2
+                     WHERE id = ?1 AND (title IS NULL OR trim(title) = '')",
+                    params![op.session_id, title],
+                )?;
+            }
+        }
+        "session.renamed" => {
+            let title = op
+                .payload
+                .get("title")
+                .and_then(|title| title.as_str())
+                .map(|title| seal_text(cipher, title, &title_context(&op.session_id)))
+                .transpose()?;
+            tx.execute(
+                "UPDATE sessions SET title = ?2 WHERE id = ?1",
+                params![op.session_id, title],
+            )?;
+        }
+        "session.model_set" => {
+            let field = |name: &str| op.payload.get(name).and_then(|value| value.as_str());
+            tx.execute(
+                "UPDATE sessions
+                 SET provider_id = ?2, model_id = ?3, reasoning_effort = ?4
+                 WHERE id = ?1",
+                params![
+                    op.session_id,
+                    field("provider_id"),
+                    field("model_id"),
+                    field("reasoning_effort")
+                ],
+            )?;
+        }
+        "session.pinned" => {
+            let pinned = op
+                .payload
+                .get("pinned")
+                .and_then(|pinned| pinned.as_bool())
+                .unwrap_or(false);
+            tx.execute(
+                "UPDATE sessions SET pinned = ?2 WHERE id = ?1",
+                params![op.session_id, pinned],
+            )?;
+        }
+        "session.summarized" => {
+            if let Some(summary) = op.payload.get("summary") {
+                let summary = seal_json(cipher, summary, &summary_context(&op.session_id))?;
+                let turn_id = op
+                    .payload
+                    .get("turn_id")
+                    .and_then(|turn_id| turn_id.as_str());
+                tx.execute(
+                    "UPDATE sessions SET summary_json = ?2, summary_turn_id = ?3 WHERE id = ?1",
+                    params![op.session_id, summary, turn_id],
+                )?;
+            }
+        }
+        "turn.appended" => apply_turn_appended(tx, cipher, op)?,
+        "session.checkout" => {
+            let turn_id = op
+                .payload
+                .get("turn_id")
+                .and_then(|turn_id| turn_id.as_str());
+            let turn_exists = match turn_id {
+                Some(turn_id) => {
+                    tx.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM turns WHERE id = ?1 AND session_id = ?2)",
+                        params![turn_id, op.session_id],
+                        |row| row.get::<_, i64>(0),
+                    )? != 0
+                }
+                None => true,
+            };
+            if turn_exists {
+                tx.execute(
+                    "UPDATE sessions
+                     SET current_turn_id = ?2, updated_at_ms = max(updated_at_ms, ?3)
+                     WHERE id = ?1",
+                    params![op.session_id, turn_id, op.created_at_ms],
+                )?;
+            }
+        }
+        _ => {}
+    }
+
+    tx.execute(
+        "INSERT INTO session_ops (
+            session_id, op_type, payload_json, created_at_ms, origin, origin_seq
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            op.session_id,
+            op.op_type,
+            seal_json(
+                cipher,
+                &op.payload,
+                &op_context(&op.session_id, &op.op_type)
+            )?,
+            op.created_at_ms,
+            op.origin,
+            op.origin_seq
+        ],
+    )?;
+    Ok(())
+}
+
+/// Create the op's session (and its project) if this device has not seen it.
+fn ensure_session(tx: &Transaction<'_>, op: &RemoteOp) -> Result<()> {
+    let exists = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?1)",
+        params![op.session_id],
+        |row| row.get::<_, i64>(0),
+    )? != 0;
+    if exists {
+        return Ok(());
+    }
+
+    let root_dir = op.project_root.to_string_lossy().to_string();
+    let project_id = match tx
+        .query_row(
+            "SELECT id FROM projects WHERE root_dir = ?1",
+            params![root_dir],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+    {
+        Some(project_id) => project_id,
+        None => {
+            let project_id = generate_id(tx, "proj")?;
+            tx.execute(
+                "INSERT INTO projects (id, root_dir, name, created_at_ms, updated_at_ms)
+                 VALUES (?1, ?2, NULL, ?3, ?3)",
+                params![project_id, root_dir, op.created_at_ms],
+            )?;
+            project_id
+        }
+    };
+
+    tx.execute(
+        "INSERT INTO sessions (
+            id, project_id, title, root_turn_id, current_turn_id, created_at_ms, updated_at_ms
+         ) VALUES (?1, ?2, NULL, NULL, NULL, ?3, ?3)",
+        params![op.session_id, project_id, op.created_at_ms],
+    )?;
+    Ok(())
+}
+
+fn apply_turn_appended(
+    tx: &Transaction<'_>,
+    cipher: Option<&SessionCipher>,
+    op: &RemoteOp,
+) -> Result<()> {
+    let Some(turn_id) = op
+        .payload
+        .get("turn_id")
+        .and_then(|turn_id| turn_id.as_str())
+    else {
+        return Ok(());
+    };
+    // A parent that never reached this device (e.g. pruned on the origin)
+    // leaves the turn as a root.
+    let parent_turn_id = op
+        .payload
+        .get("parent_turn_id")
+        .and_then(|parent| parent.as_str())
+        .filter(|parent| {
+            tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM turns WHERE id = ?1)",
+                params![parent],
+                |row| row.get::<_, i64>(0),
+            )
+            .is_ok_and(|exists| exists != 0)
+        });
+
+    let column = |name: &str| -> Result<String> {
+        let value = op.payload.get(name).cloned().unwrap_or_default();
+        seal_json(cipher, &value, &turn_context(turn_id, name))
+    };
+    let usage = match op.payload.get("usage") {
+        Some(usage) if !usage.is_null() => Some(column("usage")?),
+        _ => None,
+    };
+    let file_snapshots = match op.payload.get("file_snapshots") {
+        Some(snapshots) if !snapshots.is_null() => Some(column("file_snapshots")?),
+        _ => None,
+    };
+
+    tx.execute(
+        "INSERT OR IGNORE INTO turns (
+            id, session_id, parent_turn_id,
+            user_parts_json, assistant_parts_json, conversation_state_json, usage_json, created_at_ms,
+            file_snapshots_json, tool_call_count
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            turn_id,
+            op.session_id,
+            parent_turn_id,
+            column("user_parts")?,
+            column("assistant_parts")?,
+            column("conversation_state")?,
+            usage,
+            op.created_at_ms,
+            file_snapshots,
+            count_tool_calls(&op.payload["assistant_parts"])
+        ],
+    )?;
+    tx.execute(
+        "UPDATE sessions
+         SET root_turn_id = COALESCE(root_turn_id, ?2),
+             current_turn_id = ?2,
+             updated_at_ms = max(updated_at_ms, ?3)
+         WHERE id = ?1",
+        params![op.session_id, turn_id, op.created_at_ms],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::types::Type;
+use rusqlite::{OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::{open_text, seal_text};
+use crate::database::Database;
+use crate::error::Result;
+use crate::sessions::{generate_id, now_ms};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl TaskStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A prompt waiting in, or run from, the task queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub id: String,
+    /// Checkout the prompt runs in.
+    pub project_root: PathBuf,
+    pub prompt: String,
+    /// Profile to run with instead of the project's own.
+    pub profile: Option<String>,
+    pub status: TaskStatus,
+    /// Session the run is stored in, once it started.
+    pub session_id: Option<String>,
+    /// Why the run failed.
+    pub error: Option<String>,
+    pub created_at_ms: i64,
+    pub started_at_ms: Option<i64>,
+    pub finished_at_ms: Option<i64>,
+}
+
+pub struct TaskQueue<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+impl TaskQueue<'_> {
+    /// Queue `prompt` to run in `project_root` after the tasks already
+    /// queued.
+    pub fn enqueue(
+        &mut self,
+        project_root: &Path,
+        prompt: &str,
+        profile: Option<&str>,
+    ) -> Result<QueuedTask> {
+        let cipher = self.db.cipher.as_ref();
+        let tx = self.db.conn.transaction()?;
+        let id = generate_id(&tx, "task")?;
+        let created_at_ms = now_ms();
+        tx.execute(
+            "INSERT INTO queued_tasks (id, project_root, prompt, profile, status, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id,
+                project_root.to_string_lossy(),
+                seal_text(cipher, prompt, &task_prompt_context(&id))?,
+                profile,
+                TaskStatus::Queued.as_str(),
+                created_at_ms,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(QueuedTask {
+            id,
+            project_root: project_root.to_path_buf(),
+            prompt: prompt.to_string(),
+            profile: profile.map(str::to_string),
+            status: TaskStatus::Queued,
+            session_id: None,
+            error: None,
+            created_at_ms,
+            started_at_ms: None,
+            finished_at_ms: None,
+        })
+    }
+
+    /// Mark the oldest queued task as running and return it, so concurrent
+    /// runners never pick the same task.
+    pub fn claim_next(&mut self) -> Result<Option<QueuedTask>> {
+        let tx = self.db.conn.transaction()?;
+        let id = tx
+            .query_row(
+                "SELECT id FROM queued_tasks
+                 WHERE status = ?1
+                 ORDER BY created_at_ms ASC, rowid ASC
+                 LIMIT 1",
+                params![TaskStatus::Queued.as_str()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+        tx.execute(
+            "UPDATE queued_tasks SET status = ?2, started_at_ms = ?3 WHERE id = ?1",
+            params![id, TaskStatus::Running.as_str(), now_ms()],
+        )?;
+        tx.commit()?;
+        self.task(&id)
+    }
+
+    /// Record the session a running task is stored in.
+    pub fn set_session(&mut self, task_id: &str, session_id: &str) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE queued_tasks SET session_id = ?2 WHERE id = ?1",
+            params![task_id, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Mark a task done, or failed with `error`.
+    pub fn finish(&mut self, task_id: &str, error: Option<&str>) -> Result<()> {
+        let status = match error {
+            Some(_) => TaskStatus::Failed,
+            None => TaskStatus::Done,
+        };
+        self.db.conn.execute(
+            "UPDATE queued_tasks SET status = ?2, error = ?3, finished_at_ms = ?4 WHERE id = ?1",
+            params![task_id, status.as_str(), error, now_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn task(&self, task_id: &str) -> Result<Option<QueuedTask>> {
+        let task = self
+            .db
+            .conn
+            .query_row(
+                &format!("SELECT {TASK_COLUMNS} FROM queued_tasks WHERE id = ?1"),
+                params![task_id],
+                task_from_row,
+            )
+            .optional()?;
+        task.map(|task| self.open_task(task)).transpose()
+    }
+
+    /// The `limit` most recently queued tasks, oldest first.
+    pub fn tasks(&self, limit: usize) -> Result<Vec<QueuedTask>> {
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM (
+                SELECT rowid AS seq, * FROM queued_tasks
+                ORDER BY created_at_ms DESC, rowid DESC
+                LIMIT ?1
+             )
+             ORDER BY created_at_ms ASC, seq ASC"
+        ))?;
+        let tasks = stmt
+            .query_map(params![limit as i64], task_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        tasks.into_iter().map(|task| self.open_task(task)).collect()
+    }
+
+    fn open_task(&self, mut task: QueuedTask) -> Result<QueuedTask> {
+        task.prompt = open_text(
+            self.db.cipher.as_ref(),
+            task.prompt,
+            &task_prompt_context(&task.id),
+        )?;
+        Ok(task)
+    }
+}
+
+const TASK_COLUMNS: &str = "id, project_root, prompt, profile, status, session_id, error,
+    created_at_ms, started_at_ms, finished_at_ms";
+
+fn task_from_row(row: &Row<'_>) -> rusqlite::Result<QueuedTask> {
+    let status: String = row.get(4)?;
+    let status = serde_json::from_value(serde_json::Value::String(status))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, Type::Text, Box::new(e)))?;
+    Ok(QueuedTask {
+        id: row.get(0)?,
+        project_root: PathBuf::from(row.get::<_, String>(1)?),
+        prompt: row.get(2)?,
+        profile: row.get(3)?,
+        status,
+        session_id: row.get(5)?,
+        error: row.get(6)?,
+        created_at_ms: row.get(7)?,
+        started_at_ms: row.get(8)?,
+        finished_at_ms: row.get(9)?,
+    })
+}
+
+pub(crate) fn task_prompt_context(task_id: &str) -> String {
+    format!("queued_task:{task_id}:prompt")
+}
@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::open_json;
+use crate::database::Database;
+use crate::error::Result;
+use crate::sessions::{count_tool_calls, turn_context};
+
+/// Token usage and tool activity recorded for one stored turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnUsage {
+    pub turn_id: String,
+    pub session_id: String,
+    pub project_root: PathBuf,
+    /// Local calendar date the turn was stored on, as `YYYY-MM-DD`.
+    pub day: String,
+    pub created_at_ms: i64,
+    /// The usage JSON stored with the turn, if any.
+    pub usage: Option<serde_json::Value>,
+    /// Number of tool calls the assistant made during the turn.
+    pub tool_calls: usize,
+}
+
+pub struct UsageLog<'a> {
+    pub(crate) db: &'a mut Database,
+}
+
+impl UsageLog<'_> {
+    /// Today's local calendar date as `YYYY-MM-DD`, matching [`TurnUsage::day`].
+    pub fn today(&self) -> Result<String> {
+        Ok(self
+            .db
+            .conn
+            .query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))?)
+    }
+
+    /// Usage of every turn stored at or after `since_ms` (all turns when
+    /// `None`), oldest first. Sealed usage is decrypted; tool calls come from
+    /// the stored count, except for sealed turns written before it existed.
+    pub fn turns(&self, since_ms: Option<i64>) -> Result<Vec<TurnUsage>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT t.id, t.session_id, p.root_dir,
+                    date(t.created_at_ms / 1000, 'unixepoch', 'localtime'),
+                    t.created_at_ms, t.usage_json, t.tool_call_count
+             FROM turns t
+             JOIN sessions s ON s.id = t.session_id
+             JOIN projects p ON p.id = s.project_id
+             WHERE t.created_at_ms >= ?1
+             ORDER BY t.created_at_ms ASC, t.id ASC",
+        )?;
+        let rows = stmt
+            .query_map(params![since_ms.unwrap_or(i64::MIN)], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<i64>>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let cipher = self.db.cipher.as_ref();
+        let mut turns = Vec::with_capacity(rows.len());
+        for (turn_id, session_id, project_root, day, created_at_ms, usage, tool_calls) in rows {
+            let usage = match usage {
+                Some(usage) => Some(open_json(
+                    cipher,
+                    serde_json::from_str(&usage)?,
+                    &turn_context(&turn_id, "usage"),
+                )?),
+                None => None,
+            };
+            let tool_calls = match tool_calls {
+                Some(tool_calls) => tool_calls,
+                None => self.legacy_tool_call_count(&turn_id)?,
+            };
+
+            turns.push(TurnUsage {
+                turn_id,
+                session_id,
+                project_root: PathBuf::from(project_root),
+                day,
+                created_at_ms,
+                usage,
+                tool_calls: tool_calls as usize,
+            });
+        }
+        Ok(turns)
+    }
+
+    /// Count the tool calls of a turn stored without `tool_call_count` by
+    /// opening its assistant parts.
+    fn legacy_tool_call_count(&self, turn_id: &str) -> Result<i64> {
+        let assistant_parts: String = self.db.conn.query_row(
+            "SELECT assistant_parts_json FROM turns WHERE id = ?1",
+            params![turn_id],
+            |row| row.get(0),
+        )?;
+        let assistant_parts = open_json(
+            self.db.cipher.as_ref(),
+            serde_json::from_str(&assistant_parts)?,
+            &turn_context(turn_id, "assistant_parts"),
+        )?;
+        Ok(count_tool_calls(&assistant_parts))
+    }
+}
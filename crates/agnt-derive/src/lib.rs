@@ -0,0 +1,447 @@
+//! `#[derive(Describe)]`, re-exported by `agnt_llm`: builds the `Schema` a
+//! tool's input is described to the model with from the type itself.
+//!
+//! - Structs with named fields describe as objects. Doc comments become
+//!   descriptions, fields of type `Option<_>` or marked `#[serde(default)]`
+//!   are optional, and serde's `rename`, `rename_all` and `skip` are
+//!   followed.
+//! - Enums of unit variants describe as a string enumeration, internally
+//!   tagged enums (`#[serde(tag = "...")]`) as one object per variant.
+//! - `#[describe(...)]` on a field adds JSON Schema constraints:
+//!   `min_items`, `max_items`, `min_length`, `max_length`, `minimum` and
+//!   `maximum`, e.g. `#[describe(min_items = 1)]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{
+    Attribute, Data, DataEnum, DeriveInput, Error, Expr, ExprLit, Field, Fields, Lit, LitStr, Meta,
+    Token, Type, parse_macro_input, parse_quote, token,
+};
+
+/// Constraints `#[describe(...)]` accepts, with their JSON Schema keywords.
+const CONSTRAINTS: &[(&str, &str)] = &[
+    ("min_items", "minItems"),
+    ("max_items", "maxItems"),
+    ("min_length", "minLength"),
+    ("max_length", "maxLength"),
+    ("minimum", "minimum"),
+    ("maximum", "maximum"),
+];
+
+#[proc_macro_derive(Describe, attributes(describe))]
+pub fn derive_describe(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let container = SerdeAttrs::parse(&input.attrs)?;
+    let description = option_tokens(doc_comment(&input.attrs));
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => object(
+                fields.named.iter().collect(),
+                container.rename_all.as_deref(),
+                container.default,
+                description,
+                None,
+            )?,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Describe can only be derived for structs with named fields",
+                ));
+            }
+        },
+        Data::Enum(data) => enumeration(data, &container, description)?,
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Describe can't be derived for unions",
+            ));
+        }
+    };
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::agnt_llm::Describe));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::agnt_llm::Describe for #name #ty_generics #where_clause {
+            fn describe() -> ::agnt_llm::Schema {
+                #body
+            }
+        }
+    })
+}
+
+/// `Schema::Object` for `fields`, led by the tag property when describing a
+/// variant of an internally tagged enum.
+fn object(
+    fields: Vec<&Field>,
+    rename_all: Option<&str>,
+    all_default: bool,
+    description: TokenStream2,
+    tag: Option<(&str, &str)>,
+) -> syn::Result<TokenStream2> {
+    let mut properties = Vec::new();
+    let mut required = Vec::new();
+    if let Some((tag, variant)) = tag {
+        properties.push(quote! {
+            ::agnt_llm::Property {
+                name: #tag.into(),
+                schema: ::agnt_llm::Schema::String {
+                    description: None,
+                    enumeration: Some(vec![#variant.into()]),
+                },
+            }
+        });
+        required.push(tag.to_string());
+    }
+
+    for field in fields {
+        let serde = SerdeAttrs::parse(&field.attrs)?;
+        if serde.skip {
+            continue;
+        }
+        if serde.flatten {
+            return Err(Error::new_spanned(
+                field,
+                "Describe doesn't support #[serde(flatten)]",
+            ));
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let name = serde
+            .rename
+            .unwrap_or_else(|| rename_field(&ident.unraw().to_string(), rename_all));
+
+        let ty = &field.ty;
+        let mut schema = quote! { <#ty as ::agnt_llm::Describe>::describe() };
+        if let Some(doc) = doc_comment(&field.attrs) {
+            schema = quote! { #schema.with_description(#doc) };
+        }
+        for (keyword, value) in constraints(&field.attrs)? {
+            schema = quote! { #schema.with_keyword(#keyword, #value) };
+        }
+
+        if !serde.default && !all_default && !is_option(ty) {
+            required.push(name.clone());
+        }
+        properties.push(quote! {
+            ::agnt_llm::Property {
+                name: #name.into(),
+                schema: #schema,
+            }
+        });
+    }
+
+    Ok(quote! {
+        ::agnt_llm::Schema::Object {
+            description: #description,
+            properties: vec![#(#properties),*],
+            required: vec![#(#required.into()),*],
+        }
+    })
+}
+
+fn enumeration(
+    data: &DataEnum,
+    container: &SerdeAttrs,
+    description: TokenStream2,
+) -> syn::Result<TokenStream2> {
+    if container.untagged || container.content {
+        return Err(Error::new(
+            proc_macro2::Span::call_site(),
+            "Describe supports enums of unit variants and internally tagged enums \
+             (#[serde(tag = \"...\")]), not untagged or adjacently tagged ones",
+        ));
+    }
+    let rename_all = container.rename_all.as_deref();
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let serde = SerdeAttrs::parse(&variant.attrs)?;
+        if serde.skip {
+            continue;
+        }
+        let name = serde
+            .rename
+            .clone()
+            .unwrap_or_else(|| rename_variant(&variant.ident.unraw().to_string(), rename_all));
+        variants.push((variant, serde, name));
+    }
+
+    if let Some(tag) = &container.tag {
+        let mut schemas = Vec::new();
+        for (variant, serde, name) in &variants {
+            let fields = match &variant.fields {
+                Fields::Named(fields) => fields.named.iter().collect(),
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(_) => {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "Describe doesn't support tuple variants",
+                    ));
+                }
+            };
+            schemas.push(object(
+                fields,
+                serde.rename_all.as_deref(),
+                serde.default,
+                option_tokens(doc_comment(&variant.attrs)),
+                Some((tag.as_str(), name.as_str())),
+            )?);
+        }
+        return Ok(quote! {
+            ::agnt_llm::Schema::OneOf {
+                description: #description,
+                variants: vec![#(#schemas),*],
+            }
+        });
+    }
+
+    if let Some((variant, _, _)) = variants
+        .iter()
+        .find(|(variant, _, _)| !matches!(variant.fields, Fields::Unit))
+    {
+        return Err(Error::new_spanned(
+            variant,
+            "Describe supports enums with fields only when internally tagged \
+             (#[serde(tag = \"...\")])",
+        ));
+    }
+    let names = variants.iter().map(|(_, _, name)| name);
+    Ok(quote! {
+        ::agnt_llm::Schema::String {
+            description: #description,
+            enumeration: Some(vec![#(#names.into()),*]),
+        }
+    })
+}
+
+/// The serde attributes that change what input a type accepts.
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    tag: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+    untagged: bool,
+    content: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    parsed.rename = deserialize_name(&meta)?;
+                } else if meta.path.is_ident("rename_all") {
+                    parsed.rename_all = deserialize_name(&meta)?;
+                } else if meta.path.is_ident("tag") {
+                    parsed.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                    skip_value(&meta)?;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    parsed.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    parsed.flatten = true;
+                } else if meta.path.is_ident("untagged") {
+                    parsed.untagged = true;
+                } else if meta.path.is_ident("content") {
+                    parsed.content = true;
+                    skip_value(&meta)?;
+                } else {
+                    skip_value(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// `rename = "name"`, or the `deserialize` half of
+/// `rename(serialize = "...", deserialize = "...")`: input is what the
+/// schema describes.
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse::<LitStr>()?.value()));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        if nested.path.is_ident("deserialize") {
+            name = Some(nested.value()?.parse::<LitStr>()?.value());
+        } else {
+            skip_value(&nested)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume whatever follows a serde attribute the schema doesn't depend on.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(token::Paren) {
+        meta.parse_nested_meta(|nested| skip_value(&nested))?;
+    }
+    Ok(())
+}
+
+/// The `#[describe(...)]` constraints on a field, as JSON Schema keywords
+/// and their values.
+fn constraints(attrs: &[Attribute]) -> syn::Result<Vec<(&'static str, Lit)>> {
+    let mut constraints = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("describe")) {
+        attr.parse_nested_meta(|meta| {
+            let Some((_, keyword)) = CONSTRAINTS
+                .iter()
+                .find(|(name, _)| meta.path.is_ident(name))
+            else {
+                return Err(meta.error(
+                    "unknown constraint; expected min_items, max_items, min_length, \
+                     max_length, minimum or maximum",
+                ));
+            };
+            constraints.push((*keyword, meta.value()?.parse::<Lit>()?));
+            Ok(())
+        })?;
+    }
+    Ok(constraints)
+}
+
+/// The doc comment in `attrs`, with lines joined and paragraphs kept apart.
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let mut paragraphs = vec![String::new()];
+    for attr in attrs {
+        let Meta::NameValue(meta) = &attr.meta else {
+            continue;
+        };
+        let Expr::Lit(ExprLit {
+            lit: Lit::Str(text),
+            ..
+        }) = &meta.value
+        else {
+            continue;
+        };
+        if !meta.path.is_ident("doc") {
+            continue;
+        }
+        let text = text.value();
+        // A `///` line of its own comes through empty.
+        let lines: Vec<&str> = if text.trim().is_empty() {
+            vec![""]
+        } else {
+            text.lines().collect()
+        };
+        for line in lines {
+            let line = line.trim();
+            let current = paragraphs.last_mut().expect("at least one paragraph");
+            if line.is_empty() {
+                if !current.is_empty() {
+                    paragraphs.push(String::new());
+                }
+            } else {
+                if !current.is_empty() {
+                    current.push(' ');
+                }
+                current.push_str(line);
+            }
+        }
+    }
+    let text = paragraphs
+        .into_iter()
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (!text.is_empty()).then_some(text)
+}
+
+fn option_tokens(text: Option<String>) -> TokenStream2 {
+    match text {
+        Some(text) => quote! { Some(#text.into()) },
+        None => quote! { None },
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option")
+}
+
+/// A field name under serde's `rename_all`; fields are written snake_case.
+fn rename_field(name: &str, rule: Option<&str>) -> String {
+    match rule {
+        Some("UPPERCASE" | "SCREAMING_SNAKE_CASE") => name.to_ascii_uppercase(),
+        Some("PascalCase") => pascal_case(name),
+        Some("camelCase") => lower_first(&pascal_case(name)),
+        Some("kebab-case") => name.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => name.replace('_', "-").to_ascii_uppercase(),
+        _ => name.to_string(),
+    }
+}
+
+/// A variant name under serde's `rename_all`; variants are written
+/// PascalCase.
+fn rename_variant(name: &str, rule: Option<&str>) -> String {
+    match rule {
+        Some("lowercase") => name.to_ascii_lowercase(),
+        Some("UPPERCASE") => name.to_ascii_uppercase(),
+        Some("camelCase") => lower_first(name),
+        Some("snake_case") => snake_case(name),
+        Some("SCREAMING_SNAKE_CASE") => snake_case(name).to_ascii_uppercase(),
+        Some("kebab-case") => snake_case(name).replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => snake_case(name).replace('_', "-").to_ascii_uppercase(),
+        _ => name.to_string(),
+    }
+}
+
+fn pascal_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn snake_case(pascal: &str) -> String {
+    let mut snake = String::with_capacity(pascal.len() + 4);
+    for (i, c) in pascal.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
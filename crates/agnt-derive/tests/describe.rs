@@ -0,0 +1,102 @@
+use agnt_llm::Describe;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Search the project.
+#[derive(Deserialize, Describe)]
+#[allow(dead_code)]
+struct SearchInput {
+    /// What to look for.
+    ///
+    /// A regular expression.
+    pattern: String,
+    /// Directories to search in.
+    #[describe(min_items = 1)]
+    paths: Vec<String>,
+    #[serde(rename = "max")]
+    limit: Option<usize>,
+    #[serde(default)]
+    case_sensitive: bool,
+    #[serde(skip)]
+    cache: Option<String>,
+}
+
+#[derive(Deserialize, Describe)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[allow(dead_code)]
+enum Change {
+    /// Rename a file.
+    MoveFile {
+        /// Destination path.
+        to: String,
+    },
+    DeleteFile,
+}
+
+#[derive(Deserialize, Describe)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+enum Mode {
+    Fast,
+    Thorough,
+}
+
+#[test]
+fn describes_structs_from_docs_and_serde_attributes() {
+    assert_eq!(
+        SearchInput::describe().to_json_schema(),
+        json!({
+            "type": "object",
+            "description": "Search the project.",
+            "additionalProperties": false,
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "What to look for.\n\nA regular expression."
+                },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Directories to search in.",
+                    "minItems": 1
+                },
+                "max": { "type": "integer" },
+                "case_sensitive": { "type": "boolean" }
+            },
+            "required": ["pattern", "paths"]
+        })
+    );
+}
+
+#[test]
+fn describes_tagged_and_unit_enums() {
+    assert_eq!(
+        Change::describe().to_json_schema(),
+        json!({
+            "oneOf": [
+                {
+                    "type": "object",
+                    "description": "Rename a file.",
+                    "additionalProperties": false,
+                    "properties": {
+                        "op": { "type": "string", "enum": ["move_file"] },
+                        "to": { "type": "string", "description": "Destination path." }
+                    },
+                    "required": ["op", "to"]
+                },
+                {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "op": { "type": "string", "enum": ["delete_file"] }
+                    },
+                    "required": ["op"]
+                }
+            ]
+        })
+    );
+    assert_eq!(
+        Mode::describe().to_json_schema(),
+        json!({ "type": "string", "enum": ["fast", "thorough"] })
+    );
+}
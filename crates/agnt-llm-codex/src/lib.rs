@@ -2,8 +2,9 @@
 
 use std::collections::HashMap;
 
+use agnt_llm::{Message, ProviderBehavior};
 use agnt_llm_openai::{OpenAIProviderBehavior, register_oauth_provider_with_behavior};
-use agnt_llm_registry::{Modalities, ModelLimit, ModelSpec, OAuthPkceAuth, Registry};
+use agnt_llm_registry::{Modalities, ModelAlias, ModelLimit, ModelSpec, OAuthPkceAuth, Registry};
 
 pub const PROVIDER_ID: &str = "openai-codex";
 pub const PROVIDER_NAME: &str = "OpenAI Codex";
@@ -43,6 +44,9 @@ pub fn register(registry: &mut Registry) {
         Some("https://chatgpt.com/backend-api/codex".to_string()),
         codex_behavior(),
     );
+    registry.set_provider_behavior(PROVIDER_ID, CodexPrompt);
+    registry.set_default_model(PROVIDER_ID, DEFAULT_MODEL_ID);
+    registry.add_model_alias(PROVIDER_ID, "gpt-latest", ModelAlias::Default);
 }
 
 fn codex_models() -> Vec<ModelSpec> {
@@ -98,3 +102,34 @@ fn codex_behavior() -> OpenAIProviderBehavior {
         extra_headers: headers,
     }
 }
+
+/// The Codex endpoint takes a single `instructions` string, so system
+/// messages after the first (e.g. AGENTS.md) are folded into it rather than
+/// replacing it.
+#[derive(Debug)]
+struct CodexPrompt;
+
+impl ProviderBehavior for CodexPrompt {
+    fn transform_messages(&self, messages: &mut Vec<Message>) {
+        let Some(first) = messages
+            .iter()
+            .position(|message| matches!(message, Message::System { .. }))
+        else {
+            return;
+        };
+        let mut merged = Vec::new();
+        let mut index = first + 1;
+        while index < messages.len() {
+            if matches!(messages[index], Message::System { .. }) {
+                if let Message::System { parts } = messages.remove(index) {
+                    merged.extend(parts);
+                }
+            } else {
+                index += 1;
+            }
+        }
+        if let Message::System { parts } = &mut messages[first] {
+            parts.extend(merged);
+        }
+    }
+}
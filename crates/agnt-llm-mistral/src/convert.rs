@@ -0,0 +1,142 @@
+//! Converts agnt-llm generic requests to the Mistral chat completions wire
+//! format.
+
+use agnt_llm::request::{
+    AssistantPart, GenerateRequest, Message, SystemPart, ToolChoice, UserPart,
+};
+
+use crate::types::{
+    ChatMessage, ChatRequest, FunctionCall, FunctionDefinition, Tool, ToolCall, UserChunk,
+    UserContent,
+};
+
+pub fn to_mistral_request(model_id: &str, req: &GenerateRequest) -> ChatRequest {
+    let mut messages = Vec::new();
+
+    for msg in &req.messages {
+        match msg {
+            Message::System { parts } => {
+                let content = parts
+                    .iter()
+                    .map(|p| match p {
+                        SystemPart::Text(t) => t.text.as_str(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                messages.push(ChatMessage::System { content });
+            }
+            Message::User { parts } => {
+                let has_images = parts.iter().any(|p| matches!(p, UserPart::Image(_)));
+                let content = if has_images {
+                    UserContent::Chunks(
+                        parts
+                            .iter()
+                            .map(|p| match p {
+                                UserPart::Text(t) => UserChunk::Text {
+                                    text: t.text.clone(),
+                                },
+                                UserPart::Image(img) => UserChunk::ImageUrl {
+                                    image_url: img.url.clone(),
+                                },
+                            })
+                            .collect(),
+                    )
+                } else {
+                    UserContent::Text(
+                        parts
+                            .iter()
+                            .filter_map(|p| match p {
+                                UserPart::Text(t) => Some(t.text.as_str()),
+                                UserPart::Image(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    )
+                };
+                messages.push(ChatMessage::User { content });
+            }
+            Message::Assistant { parts } => {
+                let mut content = String::new();
+                let mut tool_calls = Vec::new();
+                for part in parts {
+                    match part {
+                        AssistantPart::Text(t) => content.push_str(&t.text),
+                        // Mistral has no way to send thinking or images back; the model
+                        // only needs its answers and tool calls.
+                        AssistantPart::Reasoning(_) | AssistantPart::Image(_) => {}
+                        AssistantPart::ToolCall(tc) => tool_calls.push(ToolCall {
+                            id: tc.id.clone(),
+                            r#type: "function",
+                            function: FunctionCall {
+                                name: tc.name.clone(),
+                                arguments: tc.arguments.clone(),
+                            },
+                        }),
+                    }
+                }
+                if content.is_empty() && tool_calls.is_empty() {
+                    continue;
+                }
+                messages.push(ChatMessage::Assistant {
+                    content,
+                    tool_calls,
+                });
+            }
+            Message::Tool { parts } => {
+                for part in parts {
+                    messages.push(ChatMessage::Tool {
+                        tool_call_id: part.tool_call_id.clone(),
+                        content: part.content.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    let tools = req
+        .tools
+        .iter()
+        .map(|t| Tool {
+            r#type: "function",
+            function: FunctionDefinition {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                parameters: t.parameters.to_json_schema(),
+            },
+        })
+        .collect();
+
+    let tool_choice = match &req.options.tool_choice {
+        ToolChoice::Auto => None, // omit = auto
+        ToolChoice::None => Some(serde_json::json!("none")),
+        // Mistral calls "required" `any`.
+        ToolChoice::Required => Some(serde_json::json!("any")),
+        ToolChoice::Tool(name) => Some(serde_json::json!({
+            "type": "function",
+            "function": { "name": name },
+        })),
+    };
+
+    let response_format = req.options.response_schema.as_ref().map(|response| {
+        serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": response.name,
+                "schema": response.schema.to_json_schema(),
+            }
+        })
+    });
+
+    ChatRequest {
+        model: model_id.to_string(),
+        messages,
+        stream: true,
+        max_tokens: req.options.max_tokens,
+        temperature: req.options.temperature,
+        top_p: req.options.top_p,
+        stop: req.options.stop.clone(),
+        tools,
+        tool_choice,
+        response_format,
+    }
+}
@@ -0,0 +1,108 @@
+mod convert;
+#[cfg(feature = "registry")]
+mod register;
+mod stream;
+mod types;
+
+#[cfg(feature = "registry")]
+pub use register::register;
+
+use agnt_llm::request::GenerateRequest;
+use agnt_llm::response::Response;
+use agnt_llm::{
+    HttpSettings, LanguageModel, LanguageModelBackend, LanguageModelProvider,
+    LanguageModelProviderBackend,
+};
+use std::sync::Arc;
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Configuration for the Mistral provider.
+pub struct MistralConfig {
+    pub api_key: String,
+    pub base_url: String,
+    /// Timeouts and keep-alive for the HTTP client.
+    pub http: HttpSettings,
+}
+
+impl Default for MistralConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            base_url: "https://api.mistral.ai/v1".into(),
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+/// Create a Mistral provider with the given config. Fails if the HTTP
+/// client can't be set up with its settings.
+pub fn provider(config: MistralConfig) -> Result<LanguageModelProvider, agnt_llm::Error> {
+    Ok(LanguageModelProvider::new(MistralProvider {
+        state: Arc::new(ProviderState {
+            client: config.http.client()?,
+            config,
+        }),
+    }))
+}
+
+/// Create a Mistral provider reading `MISTRAL_API_KEY` from the environment.
+pub fn from_env() -> Result<LanguageModelProvider, agnt_llm::Error> {
+    provider(MistralConfig {
+        api_key: std::env::var("MISTRAL_API_KEY").unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Internals
+// ---------------------------------------------------------------------------
+
+struct ProviderState {
+    client: reqwest::Client,
+    config: MistralConfig,
+}
+
+struct MistralProvider {
+    state: Arc<ProviderState>,
+}
+
+impl LanguageModelProviderBackend for MistralProvider {
+    fn name(&self) -> &str {
+        "mistral"
+    }
+
+    fn model(&self, model_id: &str) -> LanguageModel {
+        LanguageModel::new(MistralModel {
+            model_id: model_id.to_string(),
+            state: Arc::clone(&self.state),
+        })
+    }
+}
+
+struct MistralModel {
+    model_id: String,
+    state: Arc<ProviderState>,
+}
+
+impl LanguageModelBackend for MistralModel {
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn provider(&self) -> &str {
+        "mistral"
+    }
+
+    fn generate(&self, request: GenerateRequest) -> Response {
+        let body = convert::to_mistral_request(&self.model_id, &request);
+        let state = Arc::clone(&self.state);
+        Response::new(stream::open(state, body))
+    }
+
+    fn request_body(&self, request: &GenerateRequest) -> Option<serde_json::Value> {
+        serde_json::to_value(convert::to_mistral_request(&self.model_id, request)).ok()
+    }
+}
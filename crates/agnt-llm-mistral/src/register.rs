@@ -0,0 +1,45 @@
+//! Registry integration for the Mistral provider.
+
+use agnt_llm_registry::{
+    ApiKeyAuth, AuthMethod, ModelSource, ProviderOptions, ProviderRegistration, Registry,
+};
+
+use crate::{MistralConfig, provider};
+
+/// The npm packages this crate can serve.
+const COMPATIBLE_PACKAGES: &[&str] = &["@ai-sdk/mistral"];
+
+/// Register this provider with the given [`Registry`] for all compatible npm
+/// packages (`@ai-sdk/mistral`).
+///
+/// Models, including the Codestral ones, come from the models.dev spec.
+pub fn register(registry: &mut Registry) {
+    for &npm in COMPATIBLE_PACKAGES {
+        registry.add_factory(npm, factory);
+    }
+
+    let mut registration = ProviderRegistration::new("mistral", "Mistral");
+    registration.npm_packages = COMPATIBLE_PACKAGES.iter().map(|s| s.to_string()).collect();
+    registration.api_endpoint = Some("https://api.mistral.ai/v1".to_string());
+    registration.auth_method = AuthMethod::ApiKey(ApiKeyAuth {
+        env: vec!["MISTRAL_API_KEY".to_string()],
+        ..Default::default()
+    });
+    registration.model_source = ModelSource::ModelsDev;
+    registry.add_registration(registration);
+}
+
+fn factory(
+    options: ProviderOptions,
+) -> Result<agnt_llm::LanguageModelProvider, agnt_llm_registry::Error> {
+    let api_key = options.auth.get("api_key").unwrap_or_default().to_string();
+
+    provider(MistralConfig {
+        api_key,
+        base_url: options
+            .api_endpoint
+            .unwrap_or_else(|| "https://api.mistral.ai/v1".into()),
+        http: options.http,
+    })
+    .map_err(|err| agnt_llm_registry::Error::Factory(Box::new(err)))
+}
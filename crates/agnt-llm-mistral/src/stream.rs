@@ -0,0 +1,232 @@
+//! Opens an SSE connection to the Mistral chat completions API and maps
+//! chunks to the agnt-llm `StreamEvent` type.
+
+use crate::ProviderState;
+use crate::types::{ChatChunk, ChatRequest, ChunkUsage, ToolCallDelta};
+use agnt_llm::error::Error;
+use agnt_llm::request::{ReasoningPart, ToolCallPart};
+use agnt_llm::stream::{FinishReason, StreamEvent, Usage};
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub fn open(
+    state: Arc<ProviderState>,
+    body: ChatRequest,
+) -> impl Stream<Item = Result<StreamEvent, Error>> + Send {
+    async_stream::try_stream! {
+        let url = format!("{}/chat/completions", state.config.base_url);
+        let resp = state
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", state.config.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::Http(Box::new(e)))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body_text = resp.text().await.unwrap_or_default();
+            Err(Error::Api {
+                code: status.as_str().to_string(),
+                message: body_text,
+                metadata: Default::default(),
+            })?;
+            unreachable!();
+        }
+
+        let mut mapper = ChunkMapper::default();
+        let mut sse = resp.bytes_stream().eventsource();
+        while let Some(event) = state.config.http.next_event(&mut sse).await? {
+            let event = event.map_err(|e| Error::Sse(e.to_string()))?;
+            if event.data == "[DONE]" {
+                break;
+            }
+            let chunk: ChatChunk = serde_json::from_str(&event.data)?;
+            for stream_event in mapper.map_chunk(chunk) {
+                yield stream_event;
+            }
+        }
+
+        if !mapper.finished {
+            Err(Error::Sse(
+                "connection lost before the response completed".to_string(),
+            ))?;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Chunk mapper (stateful — accumulates text, thinking and tool calls)
+// ---------------------------------------------------------------------------
+
+#[derive(Default)]
+struct ChunkMapper {
+    /// Thinking text of the current reasoning block, if one is open.
+    reasoning: Option<String>,
+    has_text: bool,
+    /// Tool calls in progress, by the index Mistral streams them under.
+    tool_calls: Vec<PendingToolCall>,
+    usage: Option<Usage>,
+    finished: bool,
+}
+
+struct PendingToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl ChunkMapper {
+    fn map_chunk(&mut self, chunk: ChatChunk) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        if let Some(usage) = chunk.usage {
+            self.usage = Some(map_usage(usage));
+        }
+
+        for choice in chunk.choices {
+            match choice.delta.content {
+                Some(Value::String(text)) => self.push_text(text, &mut events),
+                // Reasoning models stream a list of `thinking` and `text`
+                // chunks instead of a plain string.
+                Some(Value::Array(items)) => {
+                    for item in items {
+                        match item.get("type").and_then(Value::as_str) {
+                            Some("thinking") => {
+                                let text = thinking_text(&item);
+                                if !text.is_empty() {
+                                    self.reasoning.get_or_insert_default().push_str(&text);
+                                    events.push(StreamEvent::ReasoningDelta(text));
+                                }
+                            }
+                            Some("text") => {
+                                if let Some(text) = item.get("text").and_then(Value::as_str) {
+                                    self.push_text(text.to_string(), &mut events);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            for delta in choice.delta.tool_calls {
+                self.push_tool_call(delta, &mut events);
+            }
+
+            if let Some(reason) = choice.finish_reason {
+                self.finish(&reason, &mut events);
+            }
+        }
+        events
+    }
+
+    fn push_text(&mut self, text: String, events: &mut Vec<StreamEvent>) {
+        if text.is_empty() {
+            return;
+        }
+        self.close_reasoning(events);
+        self.has_text = true;
+        events.push(StreamEvent::TextDelta(text));
+    }
+
+    fn push_tool_call(&mut self, delta: ToolCallDelta, events: &mut Vec<StreamEvent>) {
+        // Mistral usually sends each call whole; the index is only present
+        // when one is split over several chunks.
+        let index = delta.index.unwrap_or(self.tool_calls.len());
+        if index >= self.tool_calls.len() {
+            self.close_reasoning(events);
+            let id = delta.id.unwrap_or_default();
+            let name = delta.function.name.unwrap_or_default();
+            events.push(StreamEvent::ToolCallBegin {
+                index,
+                id: id.clone(),
+                name: name.clone(),
+            });
+            self.tool_calls.push(PendingToolCall {
+                id,
+                name,
+                arguments: String::new(),
+            });
+        }
+        let Some(call) = self.tool_calls.get_mut(index) else {
+            return;
+        };
+        if let Some(arguments) = delta.function.arguments
+            && !arguments.is_empty()
+        {
+            call.arguments.push_str(&arguments);
+            events.push(StreamEvent::ToolCallDelta {
+                index,
+                arguments_delta: arguments,
+            });
+        }
+    }
+
+    fn close_reasoning(&mut self, events: &mut Vec<StreamEvent>) {
+        if let Some(text) = self.reasoning.take() {
+            events.push(StreamEvent::ReasoningDone(ReasoningPart {
+                text: Some(text),
+                metadata: HashMap::new(),
+            }));
+        }
+    }
+
+    fn finish(&mut self, reason: &str, events: &mut Vec<StreamEvent>) {
+        self.close_reasoning(events);
+        if self.has_text {
+            events.push(StreamEvent::TextDone {
+                metadata: HashMap::new(),
+            });
+        }
+        for (index, call) in std::mem::take(&mut self.tool_calls).into_iter().enumerate() {
+            events.push(StreamEvent::ToolCallEnd {
+                index,
+                call: ToolCallPart {
+                    id: call.id,
+                    name: call.name,
+                    arguments: call.arguments,
+                    metadata: HashMap::new(),
+                    display: None,
+                },
+            });
+        }
+        let reason = match reason {
+            "stop" => FinishReason::Stop,
+            "tool_calls" => FinishReason::ToolCalls,
+            "length" | "model_length" => FinishReason::Length,
+            "content_filter" => FinishReason::ContentFilter,
+            other => FinishReason::Other(other.to_string()),
+        };
+        events.push(StreamEvent::Finish {
+            reason,
+            usage: self.usage.take(),
+        });
+        self.finished = true;
+    }
+}
+
+/// The text of a `thinking` chunk, which nests its own list of text chunks.
+fn thinking_text(item: &Value) -> String {
+    match item.get("thinking") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+fn map_usage(usage: ChunkUsage) -> Usage {
+    Usage {
+        input_tokens: usage.prompt_tokens,
+        output_tokens: usage.completion_tokens,
+        reasoning_tokens: None,
+        cached_tokens: None,
+    }
+}
@@ -98,17 +98,36 @@ pub fn to_openai_request(
                                 .unwrap_or_default();
                             let encrypted_content =
                                 r.metadata.get("openai:encrypted_content").cloned();
-                            let summary: Vec<ReasoningSummary> = r
+                            let summary = r
                                 .text
                                 .iter()
-                                .map(|t| ReasoningSummary::SummaryText { text: t.clone() })
-                                .collect();
+                                .map(|t| ReasoningSummary::SummaryText { text: t.clone() });
+
+                            // Each summary section of an item is a part of its
+                            // own; put them back together.
+                            if let Some(InputItem::Reasoning {
+                                id,
+                                summary: sections,
+                                encrypted_content: previous,
+                            }) = input.last_mut()
+                                && !item_id.is_empty()
+                                && *id == item_id
+                            {
+                                sections.extend(summary);
+                                if previous.is_none() {
+                                    *previous = encrypted_content;
+                                }
+                                continue;
+                            }
                             input.push(InputItem::Reasoning {
                                 id: item_id,
-                                summary,
+                                summary: summary.collect(),
                                 encrypted_content,
                             });
                         }
+                        // Generated images stay on OpenAI's side; the
+                        // answer around them is enough context.
+                        AssistantPart::Image(_) => {}
                         AssistantPart::ToolCall(tc) => {
                             // Flush any preceding text before the tool call item.
                             flush_text(&mut text_buf, &mut text_item_id, &mut input);
@@ -142,7 +161,7 @@ pub fn to_openai_request(
         }
     }
 
-    let tools: Vec<OpenAITool> = req
+    let mut tools: Vec<OpenAITool> = req
         .tools
         .iter()
         .map(|t| OpenAITool::Function {
@@ -153,6 +172,33 @@ pub fn to_openai_request(
         })
         .collect();
 
+    // Hosted tools run on OpenAI's side; their outputs are only sent back
+    // when asked for.
+    let enabled = |key: &str| {
+        req.metadata
+            .get(key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    let mut include = if config.include_reasoning_encrypted_content {
+        vec!["reasoning.encrypted_content".to_string()]
+    } else {
+        Vec::new()
+    };
+    if enabled("web_search") {
+        tools.push(OpenAITool::WebSearch {});
+        include.push("web_search_call.action.sources".to_string());
+    }
+    if enabled("code_interpreter") {
+        tools.push(OpenAITool::CodeInterpreter {
+            container: serde_json::json!({ "type": "auto" }),
+        });
+        include.push("code_interpreter_call.outputs".to_string());
+    }
+    if enabled("image_generation") {
+        tools.push(OpenAITool::ImageGeneration {});
+    }
+
     let tool_choice = match &req.options.tool_choice {
         ToolChoice::Auto => None, // omit = auto
         ToolChoice::None => Some(serde_json::json!("none")),
@@ -182,16 +228,31 @@ pub fn to_openai_request(
         None
     };
 
+    let text = req.options.response_schema.as_ref().map(|response| {
+        serde_json::json!({
+            "format": {
+                "type": "json_schema",
+                "name": response.name,
+                "schema": response.schema.to_json_schema(),
+            }
+        })
+    });
+
+    // Background responses have to be stored to be retrieved again, which
+    // endpoints that require `store: false` don't allow.
+    let background = config.response_store != Some(false) && enabled("background");
+
     OpenAIRequest {
         model: model_id.to_string(),
         input,
         stream: true,
-        store: config.response_store,
-        include: if config.include_reasoning_encrypted_content {
-            vec!["reasoning.encrypted_content".to_string()]
+        store: if background {
+            Some(true)
         } else {
-            Vec::new()
+            config.response_store
         },
+        background: background.then_some(true),
+        include,
         instructions,
         max_output_tokens: req.options.max_tokens,
         temperature: req.options.temperature,
@@ -199,5 +260,6 @@ pub fn to_openai_request(
         tools,
         tool_choice,
         reasoning,
+        text,
     }
 }
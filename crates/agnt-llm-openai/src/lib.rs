@@ -6,15 +6,15 @@ mod types;
 
 #[cfg(feature = "registry")]
 pub use register::{
-    OpenAIProviderBehavior, register, register_oauth_provider,
+    DEFAULT_MODEL_ID, OpenAIProviderBehavior, register, register_oauth_provider,
     register_oauth_provider_with_behavior,
 };
 
 use agnt_llm::request::GenerateRequest;
 use agnt_llm::response::Response;
 use agnt_llm::{
-    LanguageModel, LanguageModelBackend, LanguageModelProvider, LanguageModelProviderBackend,
-    RequestBuilder,
+    HttpSettings, LanguageModel, LanguageModelBackend, LanguageModelProvider,
+    LanguageModelProviderBackend, RequestBuilder,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -37,6 +37,12 @@ pub struct OpenAIConfig {
     pub extra_headers: HashMap<String, String>,
     /// Whether to derive and send `chatgpt-account-id` from the auth token.
     pub include_chatgpt_account_id_header: bool,
+    /// Organization to bill, sent as `OpenAI-Organization`.
+    pub organization: Option<String>,
+    /// Project to attribute usage to, sent as `OpenAI-Project`.
+    pub project: Option<String>,
+    /// Timeouts and keep-alive for the HTTP client.
+    pub http: HttpSettings,
 }
 
 impl Default for OpenAIConfig {
@@ -48,24 +54,31 @@ impl Default for OpenAIConfig {
             include_reasoning_encrypted_content: false,
             extra_headers: HashMap::new(),
             include_chatgpt_account_id_header: false,
+            organization: None,
+            project: None,
+            http: HttpSettings::default(),
         }
     }
 }
 
-/// Create an OpenAI provider with the given config.
-pub fn provider(config: OpenAIConfig) -> LanguageModelProvider {
-    LanguageModelProvider::new(OpenAIProvider {
+/// Create an OpenAI provider with the given config. Fails if the HTTP
+/// client can't be set up with its settings.
+pub fn provider(config: OpenAIConfig) -> Result<LanguageModelProvider, agnt_llm::Error> {
+    Ok(LanguageModelProvider::new(OpenAIProvider {
         state: Arc::new(ProviderState {
-            client: reqwest::Client::new(),
+            client: config.http.client()?,
             config,
         }),
-    })
+    }))
 }
 
-/// Create an OpenAI provider reading `OPENAI_API_KEY` from the environment.
-pub fn from_env() -> LanguageModelProvider {
+/// Create an OpenAI provider reading `OPENAI_API_KEY`, and `OPENAI_ORG_ID`
+/// and `OPENAI_PROJECT_ID` if set, from the environment.
+pub fn from_env() -> Result<LanguageModelProvider, agnt_llm::Error> {
     provider(OpenAIConfig {
         auth_token: std::env::var("OPENAI_API_KEY").unwrap_or_default(),
+        organization: std::env::var("OPENAI_ORG_ID").ok(),
+        project: std::env::var("OPENAI_PROJECT_ID").ok(),
         ..Default::default()
     })
 }
@@ -134,6 +147,16 @@ pub trait OpenAIRequestExt {
     fn reasoning_effort(&mut self, effort: ReasoningEffort) -> &mut Self;
     /// Set reasoning summary mode for reasoning models.
     fn reasoning_summary(&mut self, summary: ReasoningSummary) -> &mut Self;
+    /// Run the response in the background, so it survives a dropped
+    /// connection and can be picked up with [`LanguageModel::resume`].
+    /// Ignored for endpoints configured with `response_store: Some(false)`.
+    fn background(&mut self, enabled: bool) -> &mut Self;
+    /// Let the model search the web on OpenAI's side.
+    fn web_search(&mut self, enabled: bool) -> &mut Self;
+    /// Let the model run Python in a sandbox container on OpenAI's side.
+    fn code_interpreter(&mut self, enabled: bool) -> &mut Self;
+    /// Let the model generate images on OpenAI's side.
+    fn image_generation(&mut self, enabled: bool) -> &mut Self;
 }
 
 impl OpenAIRequestExt for RequestBuilder {
@@ -144,6 +167,22 @@ impl OpenAIRequestExt for RequestBuilder {
     fn reasoning_summary(&mut self, summary: ReasoningSummary) -> &mut Self {
         self.meta("reasoning_summary", summary.as_str())
     }
+
+    fn background(&mut self, enabled: bool) -> &mut Self {
+        self.meta("background", enabled)
+    }
+
+    fn web_search(&mut self, enabled: bool) -> &mut Self {
+        self.meta("web_search", enabled)
+    }
+
+    fn code_interpreter(&mut self, enabled: bool) -> &mut Self {
+        self.meta("code_interpreter", enabled)
+    }
+
+    fn image_generation(&mut self, enabled: bool) -> &mut Self {
+        self.meta("image_generation", enabled)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -192,4 +231,14 @@ impl LanguageModelBackend for OpenAIModel {
         let event_stream = stream::open(state, body);
         Response::new(event_stream)
     }
+
+    fn request_body(&self, request: &GenerateRequest) -> Option<serde_json::Value> {
+        let body = convert::to_openai_request(&self.model_id, request, &self.state.config);
+        serde_json::to_value(body).ok()
+    }
+
+    fn resume(&self, token: &str) -> Response {
+        let state = Arc::clone(&self.state);
+        Response::new(stream::resume(state, token.to_string()))
+    }
 }
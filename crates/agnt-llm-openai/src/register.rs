@@ -3,8 +3,8 @@
 use std::collections::HashMap;
 
 use agnt_llm_registry::{
-    ApiKeyAuth, AuthMethod, ModelSource, ModelSpec, OAuthPkceAuth, ProviderOptions,
-    ProviderRegistration, Registry,
+    ApiKeyAuth, AuthMethod, CredentialField, ModelAlias, ModelSource, ModelSpec, OAuthPkceAuth,
+    ProviderOptions, ProviderRegistration, Registry,
 };
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +13,14 @@ use crate::{OpenAIConfig, provider};
 /// The npm packages this crate can serve.
 const COMPATIBLE_PACKAGES: &[&str] = &["@ai-sdk/openai"];
 
+/// Credential fields holding the organization and project an API key's
+/// usage is billed to.
+const ORGANIZATION_KEY: &str = "organization";
+const PROJECT_KEY: &str = "project";
+
+/// Model `gpt-latest` stands for with the `openai` provider.
+pub const DEFAULT_MODEL_ID: &str = "gpt-5.2";
+
 /// Register this provider with the given [`Registry`] for all compatible npm
 /// packages (`@ai-sdk/openai`).
 ///
@@ -28,12 +36,26 @@ pub fn register(registry: &mut Registry) {
     registration.api_endpoint = Some("https://api.openai.com/v1".to_string());
     registration.auth_method = AuthMethod::ApiKey(ApiKeyAuth {
         env: vec!["OPENAI_API_KEY".to_string()],
+        fields: vec![
+            CredentialField {
+                key: ORGANIZATION_KEY.to_string(),
+                label: "OpenAI organization ID".to_string(),
+                env: vec!["OPENAI_ORG_ID".to_string()],
+            },
+            CredentialField {
+                key: PROJECT_KEY.to_string(),
+                label: "OpenAI project ID".to_string(),
+                env: vec!["OPENAI_PROJECT_ID".to_string()],
+            },
+        ],
     });
     registration
         .set_factory_options(&OpenAIProviderBehavior::default())
         .expect("OpenAI provider behavior should serialize");
     registration.model_source = ModelSource::ModelsDev;
     registry.add_registration(registration);
+    registry.set_default_model("openai", DEFAULT_MODEL_ID);
+    registry.add_model_alias("openai", "gpt-latest", ModelAlias::Default);
 }
 
 /// Register an OAuth-based OpenAI provider with static model metadata.
@@ -96,7 +118,7 @@ fn factory(
         .unwrap_or_default()
         .to_string();
 
-    Ok(provider(OpenAIConfig {
+    provider(OpenAIConfig {
         auth_token,
         base_url: options
             .api_endpoint
@@ -105,7 +127,11 @@ fn factory(
         include_reasoning_encrypted_content: behavior.include_reasoning_encrypted_content,
         extra_headers: behavior.extra_headers,
         include_chatgpt_account_id_header: behavior.include_chatgpt_account_id_header,
-    }))
+        organization: options.auth.get(ORGANIZATION_KEY).map(str::to_string),
+        project: options.auth.get(PROJECT_KEY).map(str::to_string),
+        http: options.http,
+    })
+    .map_err(|err| agnt_llm_registry::Error::Factory(Box::new(err)))
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -3,75 +3,219 @@
 
 use crate::ProviderState;
 use crate::types::{
-    FunctionCallArgumentsDelta, OpenAIRequest, OutputItem, OutputItemAdded, OutputItemComplete,
-    OutputItemDone, OutputTextDelta, ReasoningSummaryTextDelta, ResponseCompleted,
+    CodeInterpreterOutput, EventSequence, FunctionCallArgumentsDelta, OpenAIRequest, OutputItem,
+    OutputItemAdded, OutputItemComplete, OutputItemDone, OutputTextDelta,
+    ReasoningSummaryPartAdded, ReasoningSummaryTextDelta, RefusalDelta, ResponseCompleted,
+    ResponseCreated, ResponseObject, UsageObject, WebSearchAction,
 };
 use agnt_llm::error::Error;
-use agnt_llm::request::{ReasoningPart, ToolCallPart};
-use agnt_llm::stream::{FinishReason, StreamEvent, Usage};
+use agnt_llm::request::{ImagePart, ReasoningPart, ToolCallPart};
+use agnt_llm::stream::{FinishReason, HostedToolCall, StreamEvent, Usage};
+use agnt_llm::trace;
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use eventsource_stream::Eventsource;
 use futures::Stream;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
-use tokio_stream::StreamExt;
+use std::time::Duration;
+
+/// Reconnect attempts in a row, without any event received in between,
+/// after the stream of a background response drops.
+const MAX_RECONNECTS: u32 = 3;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
 
 pub fn open(
     state: Arc<ProviderState>,
     body: OpenAIRequest,
 ) -> impl Stream<Item = Result<StreamEvent, Error>> + Send {
-    async_stream::try_stream! {
-        // Fire the HTTP request
-        let url = format!("{}/responses", state.config.base_url);
-        let mut req = state
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", state.config.auth_token));
-
-        if state.config.include_chatgpt_account_id_header
-            && let Some(account_id) = extract_chatgpt_account_id(&state.config.auth_token)
-        {
-            req = req.header("chatgpt-account-id", account_id);
-        }
-        for (k, v) in &state.config.extra_headers {
-            req = req.header(k, v);
-        }
+    let background = body.background == Some(true);
+    let url = format!("{}/responses", state.config.base_url);
+    let request = authorize(&state, state.client.post(&url)).json(&body);
+    run(state, request, background, None)
+}
 
-        let resp = req
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| Error::Http(Box::new(e)))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body_text = resp.text().await.unwrap_or_default();
-            Err(Error::Api {
-                code: status.as_str().to_string(),
-                message: body_text,
-                metadata: Default::default(),
-            })?;
-            unreachable!();
-        }
+/// Reattach to the background response `response_id`, replaying its events
+/// from the start.
+pub fn resume(
+    state: Arc<ProviderState>,
+    response_id: String,
+) -> impl Stream<Item = Result<StreamEvent, Error>> + Send {
+    let request = retrieve_request(&state, &response_id, None, None);
+    run(state, request, true, Some(response_id))
+}
 
-        let mut sse = resp.bytes_stream().eventsource();
+fn run(
+    state: Arc<ProviderState>,
+    mut request: reqwest::RequestBuilder,
+    background: bool,
+    mut response_id: Option<String>,
+) -> impl Stream<Item = Result<StreamEvent, Error>> + Send {
+    async_stream::try_stream! {
         let mut mapper = EventMapper::new();
+        let mut cancel = CancelOnDrop {
+            state: Arc::clone(&state),
+            response_id: response_id.clone(),
+        };
+        let mut last_sequence = None;
+        let mut last_event_id = None;
+        let mut reconnects = 0;
+
+        loop {
+            let resp = request.send().await.map_err(|e| Error::Http(Box::new(e)))?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body_text = resp.text().await.unwrap_or_default();
+                Err(Error::Api {
+                    code: status.as_str().to_string(),
+                    message: body_text,
+                    metadata: Default::default(),
+                })?;
+                unreachable!();
+            }
+
+            let mut sse = resp.bytes_stream().eventsource();
+            let mut dropped = None;
+            loop {
+                let event = match state.config.http.next_event(&mut sse).await {
+                    Ok(Some(Ok(event))) => event,
+                    Ok(None) => break,
+                    Ok(Some(Err(e))) => {
+                        dropped = Some(e.to_string());
+                        break;
+                    }
+                    // A stalled stream counts as dropped, so background
+                    // responses get reattached.
+                    Err(e) => {
+                        dropped = Some(e.to_string());
+                        break;
+                    }
+                };
 
-        while let Some(event) = sse.next().await {
-            match event {
-                Ok(event) => {
-                    if let Some(stream_event) = mapper.map_event(&event.event, &event.data)? {
-                        yield stream_event;
+                // A reattached stream may replay events already seen.
+                if let Ok(EventSequence { sequence_number: Some(sequence) }) =
+                    serde_json::from_str(&event.data)
+                {
+                    if last_sequence.is_some_and(|last| sequence <= last) {
+                        continue;
                     }
+                    last_sequence = Some(sequence);
+                }
+                if !event.id.is_empty() {
+                    last_event_id = Some(event.id.clone());
+                }
+                reconnects = 0;
+
+                let stream_events = mapper.map_event(&event.event, &event.data);
+                if background
+                    && response_id.is_none()
+                    && let Some(id) = mapper.response_id.clone()
+                {
+                    response_id = Some(id.clone());
+                    cancel.response_id = Some(id.clone());
+                    yield StreamEvent::Resumable { token: id };
+                }
+                for stream_event in stream_events {
+                    yield stream_event;
                 }
-                Err(e) => {
-                    Err(Error::Sse(e.to_string()))?;
+                if mapper.finished {
+                    cancel.response_id = None;
                 }
             }
+
+            if mapper.finished {
+                break;
+            }
+            let reason = dropped.unwrap_or_else(|| "stream ended early".to_string());
+            // Only background responses can be retrieved again.
+            let Some(id) = response_id.as_deref() else {
+                Err(Error::Sse(format!(
+                    "connection lost before the response completed: {reason}"
+                )))?;
+                unreachable!();
+            };
+            if reconnects == MAX_RECONNECTS {
+                Err(Error::Sse(format!(
+                    "lost the connection to background response {id}: {reason}"
+                )))?;
+            }
+            reconnects += 1;
+            tokio::time::sleep(RECONNECT_DELAY * reconnects).await;
+            request = retrieve_request(&state, id, last_sequence, last_event_id.as_deref());
         }
     }
 }
 
+/// Add the auth and configured headers every request needs.
+fn authorize(state: &ProviderState, mut req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    req = req.header(
+        "Authorization",
+        format!("Bearer {}", state.config.auth_token),
+    );
+    if state.config.include_chatgpt_account_id_header
+        && let Some(account_id) = extract_chatgpt_account_id(&state.config.auth_token)
+    {
+        req = req.header("chatgpt-account-id", account_id);
+    }
+    if let Some(organization) = &state.config.organization {
+        req = req.header("OpenAI-Organization", organization);
+    }
+    if let Some(project) = &state.config.project {
+        req = req.header("OpenAI-Project", project);
+    }
+    for (k, v) in &state.config.extra_headers {
+        req = req.header(k, v);
+    }
+    req
+}
+
+/// Stream the events of a stored response, after `starting_after` if given.
+/// `last_event_id` is sent for servers that resume by SSE event id instead.
+fn retrieve_request(
+    state: &ProviderState,
+    response_id: &str,
+    starting_after: Option<u64>,
+    last_event_id: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let mut url = format!(
+        "{}/responses/{response_id}?stream=true",
+        state.config.base_url
+    );
+    if let Some(sequence) = starting_after {
+        url.push_str(&format!("&starting_after={sequence}"));
+    }
+    let mut req = authorize(state, state.client.get(&url));
+    if let Some(id) = last_event_id {
+        req = req.header("Last-Event-ID", id);
+    }
+    req
+}
+
+/// Cancels a background response whose stream is dropped before it
+/// finished, e.g. because the user interrupted the turn.
+struct CancelOnDrop {
+    state: Arc<ProviderState>,
+    response_id: Option<String>,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let Some(id) = self.response_id.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let url = format!("{}/responses/{id}/cancel", self.state.config.base_url);
+        let request = authorize(&self.state, self.state.client.post(&url));
+        runtime.spawn(async move {
+            let _ = request.send().await;
+        });
+    }
+}
+
 fn extract_chatgpt_account_id(token: &str) -> Option<String> {
     let parts: Vec<&str> = token.split('.').collect();
     if parts.len() != 3 {
@@ -90,79 +234,315 @@ fn extract_chatgpt_account_id(token: &str) -> Option<String> {
 // Event mapper (stateful — tracks tool call indices)
 // ---------------------------------------------------------------------------
 
+/// Events that carry nothing the mapper needs, as opposed to ones it
+/// doesn't know.
+const IGNORED_EVENTS: &[&str] = &[
+    "response.queued",
+    "response.in_progress",
+    "response.output_text.done",
+    "response.output_text.annotation.added",
+    "response.content_part.added",
+    "response.content_part.done",
+    "response.reasoning_summary_part.done",
+    "response.reasoning_summary_text.done",
+    "response.function_call_arguments.done",
+    "response.web_search_call.in_progress",
+    "response.web_search_call.searching",
+    "response.web_search_call.completed",
+    "response.code_interpreter_call.in_progress",
+    "response.code_interpreter_call.interpreting",
+    "response.code_interpreter_call.completed",
+    "response.code_interpreter_call_code.delta",
+    "response.code_interpreter_call_code.done",
+    "response.image_generation_call.in_progress",
+    "response.image_generation_call.generating",
+    "response.image_generation_call.partial_image",
+    "response.image_generation_call.completed",
+    "keepalive",
+];
+
+/// Source name of this provider's lines in the trace log.
+const TRACE_SOURCE: &str = "openai";
+
+/// Longest payload held back waiting for the rest of a split frame.
+const MAX_PARTIAL_BYTES: usize = 1 << 20;
+
+fn usage(u: UsageObject) -> Usage {
+    Usage {
+        input_tokens: u.input_tokens,
+        output_tokens: u.output_tokens,
+        reasoning_tokens: u.output_tokens_details.and_then(|d| d.reasoning_tokens),
+        cached_tokens: u.input_tokens_details.and_then(|d| d.cached_tokens),
+    }
+}
+
+/// The response a `response.completed` or `response.incomplete` event
+/// carries. The turn finishes either way, so a payload we can't read only
+/// costs the usage.
+fn final_response(payload: Value) -> Option<ResponseObject> {
+    match serde_json::from_value::<ResponseCompleted>(payload) {
+        Ok(parsed) => Some(parsed.response),
+        Err(err) => {
+            trace::log(
+                TRACE_SOURCE,
+                format_args!("unreadable final response: {err}"),
+            );
+            None
+        }
+    }
+}
+
+fn item_type(payload: &Value) -> String {
+    payload["item"]["type"]
+        .as_str()
+        .unwrap_or("without a type")
+        .to_string()
+}
+
 struct EventMapper {
     /// Counter for tool call indices we expose to the consumer.
-    tool_call_index: usize,
+    next_tool_call_index: usize,
     /// Maps OpenAI output item ID -> our tool call index.
     id_to_index: std::collections::HashMap<String, usize>,
+    /// Function call items a `ToolCallBegin` was sent for.
+    begun_calls: HashSet<String>,
     /// Whether we saw any tool calls (to determine finish reason).
     has_tool_calls: bool,
     /// Tracks the current reasoning item ID (set on output_item.added).
     current_reasoning_id: Option<String>,
     /// Tracks the current message item ID (set on output_item.added).
     current_message_id: Option<String>,
+    /// Whether the response reached a terminal event.
+    finished: bool,
+    /// Refusal text streamed so far.
+    refusal: String,
+    /// Whether the model refused, so the response finished under the
+    /// content policy.
+    refused: bool,
+    /// ID of the response, once `response.created` arrived.
+    response_id: Option<String>,
+    /// Type and start of a payload that wasn't valid JSON on its own.
+    partial: Option<(String, String)>,
+    /// Unrecognized event and output item types, with how often they came.
+    unrecognized: BTreeMap<String, usize>,
 }
 
 impl EventMapper {
     fn new() -> Self {
         Self {
-            tool_call_index: 0,
+            next_tool_call_index: 0,
             id_to_index: std::collections::HashMap::new(),
+            begun_calls: HashSet::new(),
             has_tool_calls: false,
             current_reasoning_id: None,
             current_message_id: None,
+            finished: false,
+            refusal: String::new(),
+            refused: false,
+            response_id: None,
+            partial: None,
+            unrecognized: BTreeMap::new(),
+        }
+    }
+
+    /// Map one SSE event. This never fails the stream: a payload that isn't
+    /// JSON is held back in case its frame was split and retried joined
+    /// with the next one, and events that can't be mapped are written to
+    /// the trace log and skipped.
+    fn map_event(&mut self, event_type: &str, data: &str) -> Vec<StreamEvent> {
+        let Some((event_type, payload)) = self.payload(event_type, data) else {
+            return Vec::new();
+        };
+        // Events sent without an `event:` line still name their type in the
+        // payload.
+        let event_type = match payload.get("type").and_then(Value::as_str) {
+            Some(payload_type) if event_type.is_empty() || event_type == "message" => {
+                payload_type.to_string()
+            }
+            _ => event_type,
+        };
+        let raw = trace::enabled().then(|| payload.to_string());
+        match self.map_payload(&event_type, payload) {
+            Ok(events) => events,
+            Err(err) => {
+                trace::log(
+                    TRACE_SOURCE,
+                    format_args!(
+                        "skipped {event_type} event that didn't parse ({err}): {}",
+                        raw.unwrap_or_default()
+                    ),
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// The event's payload, with the type it was sent as. `None` while a
+    /// payload cut short is waiting for the rest of it, and for empty
+    /// keepalives. Only data starting like a JSON object is held back, and
+    /// a joined payload goes by the type it names itself, so a keepalive
+    /// can't swallow the event after it.
+    fn payload(&mut self, event_type: &str, data: &str) -> Option<(String, Value)> {
+        if let Some((partial_type, mut partial)) = self.partial.take() {
+            let len = partial.len();
+            partial.push_str(data);
+            if let Ok(payload) = serde_json::from_str::<Value>(&partial) {
+                let event_type = payload
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .map_or(partial_type, str::to_string);
+                return Some((event_type, payload));
+            }
+            partial.truncate(len);
+            trace::log(
+                TRACE_SOURCE,
+                format_args!("dropped {partial_type} payload that isn't JSON: {partial}"),
+            );
+        }
+        if data.trim().is_empty() {
+            return None;
+        }
+        match serde_json::from_str(data) {
+            Ok(payload) => Some((event_type.to_string(), payload)),
+            Err(_) if data.trim_start().starts_with('{') && data.len() <= MAX_PARTIAL_BYTES => {
+                self.partial = Some((event_type.to_string(), data.to_string()));
+                None
+            }
+            Err(_) => {
+                trace::log(
+                    TRACE_SOURCE,
+                    format_args!("dropped {event_type} payload that isn't JSON: {data}"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Note an event or output item type this version doesn't know. The
+    /// first of each type is traced with its payload, later ones are only
+    /// counted and summed up when the response ends.
+    fn unrecognized(&mut self, kind: String, payload: &Value) {
+        let count = self.unrecognized.entry(kind.clone()).or_default();
+        *count += 1;
+        if *count == 1 {
+            trace::log(TRACE_SOURCE, format_args!("unrecognized {kind}: {payload}"));
+        }
+    }
+
+    /// Trace how many unrecognized events the response had.
+    fn trace_unrecognized(&self) {
+        if self.unrecognized.is_empty() {
+            return;
+        }
+        let counts: Vec<String> = self
+            .unrecognized
+            .iter()
+            .map(|(kind, count)| format!("{kind} x{count}"))
+            .collect();
+        trace::log(
+            TRACE_SOURCE,
+            format_args!("response ended; unrecognized: {}", counts.join(", ")),
+        );
+    }
+
+    /// The stream index of the function call item `item_id`. Items that
+    /// show up before their `output_item.added` get one when they are first
+    /// seen.
+    fn tool_call_index(&mut self, item_id: &str) -> usize {
+        if let Some(index) = self.id_to_index.get(item_id) {
+            return *index;
         }
+        let index = self.next_tool_call_index;
+        self.next_tool_call_index += 1;
+        self.id_to_index.insert(item_id.to_string(), index);
+        self.has_tool_calls = true;
+        index
     }
 
-    fn map_event(&mut self, event_type: &str, data: &str) -> Result<Option<StreamEvent>, Error> {
+    fn map_payload(
+        &mut self,
+        event_type: &str,
+        payload: Value,
+    ) -> Result<Vec<StreamEvent>, serde_json::Error> {
         match event_type {
             "response.output_text.delta" => {
-                let parsed: OutputTextDelta = serde_json::from_str(data)?;
-                Ok(Some(StreamEvent::TextDelta(parsed.delta)))
+                let parsed: OutputTextDelta = serde_json::from_value(payload)?;
+                Ok(vec![StreamEvent::TextDelta(parsed.delta)])
             }
 
             "response.output_item.added" => {
-                let parsed: OutputItemAdded = serde_json::from_str(data)?;
+                let item_type = item_type(&payload);
+                let parsed: OutputItemAdded = serde_json::from_value(payload.clone())?;
                 match parsed.item {
                     OutputItem::Reasoning { id } => {
                         self.current_reasoning_id = Some(id);
-                        Ok(None)
+                        Ok(Vec::new())
                     }
                     OutputItem::Message { id } => {
                         self.current_message_id = Some(id);
-                        Ok(None)
+                        Ok(Vec::new())
                     }
                     OutputItem::FunctionCall { id, name, call_id } => {
-                        let index = self.tool_call_index;
-                        self.tool_call_index += 1;
-                        self.id_to_index.insert(id, index);
-                        self.has_tool_calls = true;
-                        Ok(Some(StreamEvent::ToolCallBegin {
+                        let index = self.tool_call_index(&id);
+                        self.begun_calls.insert(id);
+                        Ok(vec![StreamEvent::ToolCallBegin {
                             index,
                             id: call_id,
                             name,
-                        }))
+                        }])
+                    }
+                    OutputItem::WebSearchCall { id } => Ok(vec![StreamEvent::HostedToolBegin {
+                        id,
+                        name: "web_search".to_string(),
+                    }]),
+                    OutputItem::CodeInterpreterCall { id } => {
+                        Ok(vec![StreamEvent::HostedToolBegin {
+                            id,
+                            name: "code_interpreter".to_string(),
+                        }])
+                    }
+                    OutputItem::ImageGenerationCall { id } => {
+                        Ok(vec![StreamEvent::HostedToolBegin {
+                            id,
+                            name: "image_generation".to_string(),
+                        }])
+                    }
+                    OutputItem::Unknown => {
+                        self.unrecognized(format!("output item {item_type}"), &payload);
+                        Ok(Vec::new())
                     }
-                    _ => Ok(None),
                 }
             }
 
+            "response.reasoning_summary_part.added" => {
+                let parsed: ReasoningSummaryPartAdded = serde_json::from_value(payload)?;
+                Ok(vec![StreamEvent::ReasoningSectionStart {
+                    index: parsed.summary_index,
+                }])
+            }
+
             "response.reasoning_summary_text.delta" => {
-                let parsed: ReasoningSummaryTextDelta = serde_json::from_str(data)?;
-                Ok(Some(StreamEvent::ReasoningDelta(parsed.delta)))
+                let parsed: ReasoningSummaryTextDelta = serde_json::from_value(payload)?;
+                Ok(vec![StreamEvent::ReasoningDelta(parsed.delta)])
             }
 
             "response.function_call_arguments.delta" => {
-                let parsed: FunctionCallArgumentsDelta = serde_json::from_str(data)?;
-                let index = self.tool_call_index.saturating_sub(1);
-                Ok(Some(StreamEvent::ToolCallDelta {
+                let parsed: FunctionCallArgumentsDelta = serde_json::from_value(payload)?;
+                // Older servers leave out the item ID; the delta then belongs
+                // to the latest call.
+                let index = match parsed.item_id {
+                    Some(item_id) => self.tool_call_index(&item_id),
+                    None => self.next_tool_call_index.saturating_sub(1),
+                };
+                Ok(vec![StreamEvent::ToolCallDelta {
                     index,
                     arguments_delta: parsed.delta,
-                }))
+                }])
             }
 
             "response.output_item.done" => {
-                let parsed: OutputItemDone = serde_json::from_str(data)?;
+                let item_type = item_type(&payload);
+                let parsed: OutputItemDone = serde_json::from_value(payload.clone())?;
                 match parsed.item {
                     OutputItemComplete::Reasoning {
                         id,
@@ -170,24 +550,44 @@ impl EventMapper {
                         encrypted_content,
                     } => {
                         self.current_reasoning_id = None;
-                        let text = summary.first().map(|s| match s {
-                            crate::types::ReasoningSummary::SummaryText { text } => text.clone(),
-                        });
-                        let mut metadata = std::collections::HashMap::new();
-                        metadata.insert("openai:item_id".to_string(), id);
-                        if let Some(ec) = encrypted_content {
-                            metadata.insert("openai:encrypted_content".to_string(), ec);
+                        // One part per summary section, all carrying the
+                        // item ID; the encrypted content goes with the
+                        // first. Without a summary there is a single part
+                        // without text.
+                        let mut texts: Vec<Option<String>> = summary
+                            .into_iter()
+                            .map(|s| match s {
+                                crate::types::ReasoningSummary::SummaryText { text } => Some(text),
+                            })
+                            .collect();
+                        if texts.is_empty() {
+                            texts.push(None);
                         }
-                        Ok(Some(StreamEvent::ReasoningDone(ReasoningPart {
-                            text,
-                            metadata,
-                        })))
+                        let mut encrypted_content = encrypted_content;
+                        Ok(texts
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, text)| {
+                                let mut metadata = std::collections::HashMap::new();
+                                metadata.insert("openai:item_id".to_string(), id.clone());
+                                if text.is_some() {
+                                    metadata.insert(
+                                        "openai:summary_index".to_string(),
+                                        index.to_string(),
+                                    );
+                                }
+                                if let Some(ec) = encrypted_content.take() {
+                                    metadata.insert("openai:encrypted_content".to_string(), ec);
+                                }
+                                StreamEvent::ReasoningDone(ReasoningPart { text, metadata })
+                            })
+                            .collect())
                     }
                     OutputItemComplete::Message { id, .. } => {
                         self.current_message_id = None;
                         let mut metadata = std::collections::HashMap::new();
                         metadata.insert("openai:item_id".to_string(), id);
-                        Ok(Some(StreamEvent::TextDone { metadata }))
+                        Ok(vec![StreamEvent::TextDone { metadata }])
                     }
                     OutputItemComplete::FunctionCall {
                         id,
@@ -195,10 +595,20 @@ impl EventMapper {
                         name,
                         arguments,
                     } => {
-                        let index = self.id_to_index.get(&id).copied().unwrap_or(0);
+                        let index = self.tool_call_index(&id);
+                        let mut events = Vec::new();
+                        // A call whose `output_item.added` never came still
+                        // gets its begin event.
+                        if self.begun_calls.insert(id.clone()) {
+                            events.push(StreamEvent::ToolCallBegin {
+                                index,
+                                id: call_id.clone(),
+                                name: name.clone(),
+                            });
+                        }
                         let mut metadata = std::collections::HashMap::new();
                         metadata.insert("openai:item_id".to_string(), id);
-                        Ok(Some(StreamEvent::ToolCallEnd {
+                        events.push(StreamEvent::ToolCallEnd {
                             index,
                             call: ToolCallPart {
                                 id: call_id,
@@ -207,35 +617,383 @@ impl EventMapper {
                                 metadata,
                                 display: None,
                             },
-                        }))
+                        });
+                        Ok(events)
+                    }
+                    OutputItemComplete::WebSearchCall { id, status, action } => {
+                        let (input, output) = web_search_summary(action);
+                        Ok(vec![StreamEvent::HostedToolEnd(HostedToolCall {
+                            id,
+                            name: "web_search".to_string(),
+                            input,
+                            output,
+                            succeeded: status == "completed",
+                        })])
+                    }
+                    OutputItemComplete::CodeInterpreterCall {
+                        id,
+                        status,
+                        code,
+                        outputs,
+                    } => {
+                        // Images, such as plots, are shown after the call
+                        // rather than as a URL in its output.
+                        let mut images = Vec::new();
+                        let output = outputs.map(|outputs| {
+                            outputs
+                                .into_iter()
+                                .filter_map(|output| match output {
+                                    CodeInterpreterOutput::Logs { logs } => Some(logs),
+                                    CodeInterpreterOutput::Image { url } => {
+                                        images.push(StreamEvent::Image(ImagePart { url }));
+                                        None
+                                    }
+                                    CodeInterpreterOutput::Unknown => None,
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        });
+                        let mut events = vec![StreamEvent::HostedToolEnd(HostedToolCall {
+                            id,
+                            name: "code_interpreter".to_string(),
+                            input: code.unwrap_or_default(),
+                            output,
+                            succeeded: status == "completed",
+                        })];
+                        events.extend(images);
+                        Ok(events)
+                    }
+                    OutputItemComplete::ImageGenerationCall {
+                        id,
+                        status,
+                        result,
+                        output_format,
+                    } => {
+                        let mut events = vec![StreamEvent::HostedToolEnd(HostedToolCall {
+                            id,
+                            name: "image_generation".to_string(),
+                            input: String::new(),
+                            output: None,
+                            succeeded: status == "completed" && result.is_some(),
+                        })];
+                        if let Some(result) = result {
+                            let format = output_format.as_deref().unwrap_or("png");
+                            events.push(StreamEvent::Image(ImagePart {
+                                url: format!("data:image/{format};base64,{result}"),
+                            }));
+                        }
+                        Ok(events)
+                    }
+                    OutputItemComplete::Unknown => {
+                        self.unrecognized(format!("output item {item_type}"), &payload);
+                        Ok(Vec::new())
                     }
-                    _ => Ok(None),
                 }
             }
 
+            "response.created" => {
+                let parsed: ResponseCreated = serde_json::from_value(payload)?;
+                self.response_id = Some(parsed.response.id);
+                Ok(Vec::new())
+            }
+
             "response.completed" => {
-                let parsed: ResponseCompleted = serde_json::from_str(data)?;
-                let usage = parsed.response.usage.map(|u| Usage {
-                    input_tokens: u.input_tokens,
-                    output_tokens: u.output_tokens,
-                    reasoning_tokens: u.output_tokens_details.and_then(|d| d.reasoning_tokens),
-                    cached_tokens: u.input_tokens_details.and_then(|d| d.cached_tokens),
-                });
-                let reason = if self.has_tool_calls {
+                self.finished = true;
+                self.trace_unrecognized();
+                let response = final_response(payload);
+                let reason = if self.refused {
+                    FinishReason::ContentFilter
+                } else if self.has_tool_calls {
                     FinishReason::ToolCalls
                 } else {
                     FinishReason::Stop
                 };
-                Ok(Some(StreamEvent::Finish { reason, usage }))
+                Ok(vec![StreamEvent::Finish {
+                    reason,
+                    usage: response.and_then(|response| response.usage).map(usage),
+                }])
+            }
+
+            "response.incomplete" => {
+                self.finished = true;
+                self.trace_unrecognized();
+                let response = final_response(payload);
+                let reason = match response
+                    .as_ref()
+                    .and_then(|response| response.incomplete_details.as_ref())
+                    .and_then(|details| details.reason.as_deref())
+                {
+                    Some("max_output_tokens") => FinishReason::Length,
+                    Some("content_filter") => FinishReason::ContentFilter,
+                    Some(other) => FinishReason::Other(other.to_string()),
+                    None => FinishReason::Other("incomplete".to_string()),
+                };
+                Ok(vec![StreamEvent::Finish {
+                    reason,
+                    usage: response.and_then(|response| response.usage).map(usage),
+                }])
+            }
+
+            "response.refusal.delta" => {
+                let parsed: RefusalDelta = serde_json::from_value(payload)?;
+                self.refusal.push_str(&parsed.delta);
+                Ok(Vec::new())
+            }
+
+            "response.refusal.done" => {
+                self.refused = true;
+                // The final text is in the payload too; the deltas are the
+                // fallback.
+                let refusal = payload["refusal"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| std::mem::take(&mut self.refusal));
+                self.refusal.clear();
+                Ok(vec![StreamEvent::Refusal(refusal)])
+            }
+
+            "error" => {
+                self.finished = true;
+                self.trace_unrecognized();
+                Ok(vec![StreamEvent::Error(payload.to_string())])
+            }
+
+            "response.failed" => {
+                self.finished = true;
+                self.trace_unrecognized();
+                let error = serde_json::from_value::<ResponseCompleted>(payload.clone())
+                    .ok()
+                    .and_then(|parsed| parsed.response.error);
+                let message = match error {
+                    Some(error) => match error.code {
+                        Some(code) => format!("{code}: {}", error.message),
+                        None => error.message,
+                    },
+                    None => format!("response failed: {payload}"),
+                };
+                Ok(vec![StreamEvent::Error(message)])
             }
 
-            "error" => Ok(Some(StreamEvent::Error(data.to_string()))),
+            _ if IGNORED_EVENTS.contains(&event_type) => Ok(Vec::new()),
 
-            // Events we don't need: response.created, response.in_progress,
-            // response.output_text.done, response.content_part.added/done,
-            // response.reasoning_summary_part.added/done,
-            // response.reasoning_summary_text.done, etc.
-            _ => Ok(None),
+            _ => {
+                self.unrecognized(format!("event {event_type}"), &payload);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+/// What a web search call looked for, and the URLs of the sources it found
+/// when they were sent.
+fn web_search_summary(action: Option<WebSearchAction>) -> (String, Option<String>) {
+    match action {
+        Some(WebSearchAction::Search { query, sources }) => {
+            let sources = sources.map(|sources| {
+                sources
+                    .into_iter()
+                    .filter_map(|source| source.url)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+            (query.unwrap_or_default(), sources)
         }
+        Some(WebSearchAction::OpenPage { url }) => (url.unwrap_or_default(), None),
+        Some(WebSearchAction::Find { pattern, url }) => (
+            format!(
+                "{} in {}",
+                pattern.unwrap_or_default(),
+                url.unwrap_or_default()
+            ),
+            None,
+        ),
+        Some(WebSearchAction::Unknown) | None => (String::new(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventMapper, authorize};
+    use crate::{OpenAIConfig, ProviderState};
+    use agnt_llm::stream::{FinishReason, StreamEvent};
+
+    /// Feed a recorded SSE stream through a fresh mapper, frame by frame.
+    fn replay(recording: &str) -> (EventMapper, Vec<StreamEvent>) {
+        let mut mapper = EventMapper::new();
+        let mut events = Vec::new();
+        for frame in recording
+            .split("\n\n")
+            .filter(|frame| !frame.trim().is_empty())
+        {
+            let mut event_type = "message";
+            let mut data = String::new();
+            for line in frame.lines() {
+                if let Some(value) = line.strip_prefix("event: ") {
+                    event_type = value;
+                } else if let Some(value) = line.strip_prefix("data: ") {
+                    data.push_str(value);
+                }
+            }
+            events.extend(mapper.map_event(event_type, &data));
+        }
+        (mapper, events)
+    }
+
+    #[test]
+    fn maps_a_recorded_function_call() {
+        let (mapper, events) = replay(include_str!("../fixtures/function_call.sse"));
+
+        assert!(mapper.finished);
+        assert_eq!(mapper.response_id.as_deref(), Some("resp_01"));
+        assert!(mapper.unrecognized.is_empty(), "{:?}", mapper.unrecognized);
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::ReasoningSectionStart { index: 0 },
+                StreamEvent::ReasoningDelta(_),
+                StreamEvent::ReasoningDone(_),
+                StreamEvent::ToolCallBegin { index: 0, .. },
+                StreamEvent::ToolCallDelta { index: 0, .. },
+                StreamEvent::ToolCallDelta { index: 0, .. },
+                StreamEvent::ToolCallEnd { index: 0, .. },
+                StreamEvent::Finish {
+                    reason: FinishReason::ToolCalls,
+                    usage: Some(_),
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn tolerates_unknown_and_out_of_order_events() {
+        let (mapper, events) = replay(include_str!("../fixtures/out_of_order.sse"));
+
+        assert!(mapper.finished);
+        assert_eq!(
+            mapper
+                .unrecognized
+                .get("event response.audio_caption.delta"),
+            Some(&2)
+        );
+        assert_eq!(
+            mapper.unrecognized.get("output item hologram_call"),
+            Some(&1)
+        );
+        // The arguments arrived before the call was added, and the text
+        // delta without an `event:` line; the delta missing its text is
+        // skipped.
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::ToolCallDelta { index: 0, .. },
+                StreamEvent::ToolCallBegin { index: 0, .. },
+                StreamEvent::ToolCallEnd { index: 0, .. },
+                StreamEvent::TextDelta(text),
+                StreamEvent::Finish {
+                    reason: FinishReason::ToolCalls,
+                    usage: None,
+                },
+            ] if text == "Listed."
+        ));
+    }
+
+    #[test]
+    fn maps_a_generated_image() {
+        let (mapper, events) = replay(include_str!("../fixtures/image_generation.sse"));
+
+        assert!(mapper.unrecognized.is_empty(), "{:?}", mapper.unrecognized);
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::HostedToolBegin { .. },
+                StreamEvent::HostedToolEnd(call),
+                StreamEvent::Image(image),
+                StreamEvent::Finish { .. },
+            ] if call.succeeded && image.url == "data:image/png;base64,iVBORw0KGgo="
+        ));
+    }
+
+    #[test]
+    fn joins_a_payload_split_across_frames() {
+        let (mapper, events) = replay(include_str!("../fixtures/split_frame.sse"));
+
+        assert!(mapper.finished);
+        assert!(mapper.partial.is_none());
+        let text: String = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::TextDelta(delta) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hello, world");
+    }
+
+    #[test]
+    fn keeps_the_event_after_a_keepalive() {
+        let (mapper, events) = replay(include_str!("../fixtures/keepalive.sse"));
+
+        assert!(mapper.finished);
+        assert!(mapper.partial.is_none());
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::TextDelta(text),
+                StreamEvent::Finish {
+                    reason: FinishReason::Stop,
+                    ..
+                },
+            ] if text == "Hello"
+        ));
+    }
+
+    #[test]
+    fn finishes_when_the_final_response_is_unreadable() {
+        let mut mapper = EventMapper::new();
+        let events = mapper.map_event("response.completed", r#"{"response":{"usage":7}}"#);
+
+        assert!(mapper.finished);
+        assert!(matches!(
+            events.as_slice(),
+            [StreamEvent::Finish {
+                reason: FinishReason::Stop,
+                usage: None,
+            }]
+        ));
+
+        let mut mapper = EventMapper::new();
+        let events = mapper.map_event(
+            "response.completed",
+            r#"{"response":{"id":"resp_01","status":"completed","usage":{"output_tokens":3}}}"#,
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [StreamEvent::Finish {
+                usage: Some(usage),
+                ..
+            }] if usage.input_tokens == 0 && usage.output_tokens == 3
+        ));
+    }
+
+    #[test]
+    fn sends_the_credential_organization_and_project() {
+        let state = ProviderState {
+            client: reqwest::Client::new(),
+            config: OpenAIConfig {
+                auth_token: "sk-test".into(),
+                organization: Some("org-billing".into()),
+                project: Some("proj_agents".into()),
+                ..Default::default()
+            },
+        };
+        let request = authorize(
+            &state,
+            state.client.post("https://api.openai.com/v1/responses"),
+        )
+        .build()
+        .unwrap();
+
+        assert_eq!(request.headers()["OpenAI-Organization"], "org-billing");
+        assert_eq!(request.headers()["OpenAI-Project"], "proj_agents");
     }
 }
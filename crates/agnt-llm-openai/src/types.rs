@@ -16,6 +16,8 @@ pub struct OpenAIRequest {
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub store: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<bool>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include: Vec<String>,
 
@@ -39,6 +41,10 @@ pub struct OpenAIRequest {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning: Option<ReasoningConfig>,
+
+    /// Output format, e.g. a JSON schema for structured output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -118,6 +124,15 @@ pub enum OpenAITool {
         parameters: serde_json::Value,
         strict: bool,
     },
+    /// OpenAI's hosted web search.
+    #[serde(rename = "web_search")]
+    WebSearch {},
+    /// OpenAI's hosted Python sandbox.
+    #[serde(rename = "code_interpreter")]
+    CodeInterpreter { container: serde_json::Value },
+    /// OpenAI's hosted image generation.
+    #[serde(rename = "image_generation")]
+    ImageGeneration {},
 }
 
 // ---------------------------------------------------------------------------
@@ -127,18 +142,42 @@ pub enum OpenAITool {
 /// Parsed from the `data:` payload of each SSE event, keyed by `event:` type.
 #[derive(Debug, Deserialize)]
 pub struct ResponseObject {
-    #[allow(dead_code)]
     pub id: String,
     #[allow(dead_code)]
     pub status: String,
+    #[serde(default)]
     pub usage: Option<UsageObject>,
+    /// Why a `response.incomplete` response stopped.
+    #[serde(default)]
+    pub incomplete_details: Option<IncompleteDetails>,
+    /// What made a `response.failed` response fail.
+    #[serde(default)]
+    pub error: Option<ResponseError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncompleteDetails {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseError {
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub message: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UsageObject {
+    #[serde(default)]
     pub input_tokens: u32,
+    #[serde(default)]
     pub output_tokens: u32,
+    #[serde(default)]
     pub output_tokens_details: Option<OutputTokensDetails>,
+    #[serde(default)]
     pub input_tokens_details: Option<InputTokensDetails>,
 }
 
@@ -173,6 +212,15 @@ pub enum OutputItem {
         #[serde(default)]
         call_id: String,
     },
+    WebSearchCall {
+        id: String,
+    },
+    CodeInterpreterCall {
+        id: String,
+    },
+    ImageGenerationCall {
+        id: String,
+    },
     #[serde(other)]
     Unknown,
 }
@@ -184,6 +232,8 @@ pub struct OutputTextDelta {
 
 #[derive(Debug, Deserialize)]
 pub struct FunctionCallArgumentsDelta {
+    #[serde(default)]
+    pub item_id: Option<String>,
     pub delta: String,
 }
 
@@ -211,16 +261,111 @@ pub enum OutputItemComplete {
         name: String,
         arguments: String,
     },
+    WebSearchCall {
+        id: String,
+        #[serde(default)]
+        status: String,
+        #[serde(default)]
+        action: Option<WebSearchAction>,
+    },
+    CodeInterpreterCall {
+        id: String,
+        #[serde(default)]
+        status: String,
+        #[serde(default)]
+        code: Option<String>,
+        #[serde(default)]
+        outputs: Option<Vec<CodeInterpreterOutput>>,
+    },
+    ImageGenerationCall {
+        id: String,
+        #[serde(default)]
+        status: String,
+        /// The generated image, base64-encoded.
+        #[serde(default)]
+        result: Option<String>,
+        #[serde(default)]
+        output_format: Option<String>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// What a web search call did.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebSearchAction {
+    Search {
+        #[serde(default)]
+        query: Option<String>,
+        /// Only sent when `web_search_call.action.sources` is included.
+        #[serde(default)]
+        sources: Option<Vec<WebSearchSource>>,
+    },
+    OpenPage {
+        #[serde(default)]
+        url: Option<String>,
+    },
+    Find {
+        #[serde(default)]
+        pattern: Option<String>,
+        #[serde(default)]
+        url: Option<String>,
+    },
     #[serde(other)]
     Unknown,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WebSearchSource {
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Output of a code interpreter call, sent when
+/// `code_interpreter_call.outputs` is included.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CodeInterpreterOutput {
+    Logs {
+        logs: String,
+    },
+    Image {
+        url: String,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefusalDelta {
+    pub delta: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReasoningSummaryPartAdded {
+    pub summary_index: usize,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ReasoningSummaryTextDelta {
     pub delta: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ResponseCreated {
+    pub response: ResponseObject,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ResponseCompleted {
     pub response: ResponseObject,
 }
+
+/// Position of an event in its response's stream, used to pick up a
+/// background response where a dropped connection left off.
+#[derive(Debug, Deserialize)]
+pub struct EventSequence {
+    #[serde(default)]
+    pub sequence_number: Option<u64>,
+}
@@ -0,0 +1,26 @@
+//! Model IDs that stand for other models: moving names such as
+//! `gpt-latest`, and IDs a provider has since renamed.
+
+/// What a model ID registered with
+/// [`Registry::add_model_alias`](crate::Registry::add_model_alias) stands
+/// for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelAlias {
+    /// The provider's default model, set with
+    /// [`Registry::set_default_model`](crate::Registry::set_default_model).
+    Default,
+    /// Another model, e.g. the current one of a family.
+    Model(String),
+    /// The ID the model was renamed to. The old ID keeps working, with a
+    /// deprecation notice.
+    Renamed(String),
+}
+
+/// A model ID with its aliases resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedModelId {
+    /// The ID of the model itself.
+    pub id: String,
+    /// Set when the ID asked for was renamed, saying what to use instead.
+    pub deprecation: Option<String>,
+}
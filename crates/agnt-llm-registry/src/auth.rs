@@ -1,6 +1,10 @@
 //! Provider auth method declarations and resolved auth payloads.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
 
 use crate::error::Error;
 
@@ -11,6 +15,10 @@ pub enum AuthMethod {
     ApiKey(ApiKeyAuth),
     /// OAuth authorization code flow with PKCE.
     OAuthPkce(OAuthPkceAuth),
+    /// Google Application Default Credentials (a service account key or a
+    /// `gcloud auth application-default login` user), exchanged for
+    /// short-lived access tokens.
+    GoogleAdc(GoogleAdcAuth),
 }
 
 impl AuthMethod {
@@ -19,6 +27,7 @@ impl AuthMethod {
         match self {
             AuthMethod::ApiKey(_) => "api_key",
             AuthMethod::OAuthPkce(_) => "oauth_pkce",
+            AuthMethod::GoogleAdc(_) => "google_adc",
         }
     }
 }
@@ -34,6 +43,38 @@ impl Default for AuthMethod {
 pub struct ApiKeyAuth {
     /// Candidate environment variable names for this API key.
     pub env: Vec<String>,
+    /// Optional values kept with the key, e.g. the organization to bill.
+    pub fields: Vec<CredentialField>,
+}
+
+impl ApiKeyAuth {
+    /// `auth` with the [`fields`](Self::fields) set in the environment, for
+    /// a key taken from the environment too.
+    pub fn with_env_fields(&self, mut auth: ResolvedAuth) -> ResolvedAuth {
+        for field in &self.fields {
+            if let Some(value) = field
+                .env
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.trim().is_empty())
+            {
+                auth.values.insert(field.key.clone(), value);
+            }
+        }
+        auth
+    }
+}
+
+/// An optional value stored with an API key and asked for when signing in.
+#[derive(Debug, Clone, Default)]
+pub struct CredentialField {
+    /// Key in [`ResolvedAuth::values`], e.g. `organization`.
+    pub key: String,
+    /// What to ask for, e.g. `OpenAI organization ID`.
+    pub label: String,
+    /// Environment variables holding the value for a key taken from the
+    /// environment.
+    pub env: Vec<String>,
 }
 
 /// OAuth PKCE configuration.
@@ -50,6 +91,21 @@ pub struct OAuthPkceAuth {
     pub token_params: HashMap<String, String>,
 }
 
+/// Google Application Default Credentials configuration.
+#[derive(Debug, Clone, Default)]
+pub struct GoogleAdcAuth {
+    /// OAuth scopes to request; empty means `cloud-platform`.
+    pub scopes: Vec<String>,
+}
+
+/// Mints access tokens for auth methods whose tokens expire within a
+/// session. Transports that hold on to a provider for long should ask it
+/// for a token before each request rather than keep `access_token`.
+pub trait TokenSource: Send + Sync + fmt::Debug {
+    /// A valid access token, refreshed first if it is about to expire.
+    fn token(&self) -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send + '_>>;
+}
+
 /// Resolved provider auth payload returned by an auth resolver.
 #[derive(Debug, Clone, Default)]
 pub struct ResolvedAuth {
@@ -57,6 +113,8 @@ pub struct ResolvedAuth {
     pub method: String,
     /// Key/value payload (e.g. `api_key`, `access_token`, `account_id`).
     pub values: HashMap<String, String>,
+    /// Source of fresh access tokens, for methods whose tokens expire.
+    pub token_source: Option<Arc<dyn TokenSource>>,
 }
 
 impl ResolvedAuth {
@@ -66,6 +124,7 @@ impl ResolvedAuth {
         Self {
             method: "api_key".to_string(),
             values,
+            token_source: None,
         }
     }
 
@@ -75,6 +134,29 @@ impl ResolvedAuth {
         Self {
             method: "oauth_pkce".to_string(),
             values,
+            token_source: None,
+        }
+    }
+
+    /// Google ADC auth: the last minted `access_token` if there is one, the
+    /// `project_id` the credentials belong to if known, and the source to
+    /// mint new tokens from.
+    pub fn google_adc(
+        access_token: Option<String>,
+        project_id: Option<String>,
+        token_source: Arc<dyn TokenSource>,
+    ) -> Self {
+        let mut values = HashMap::new();
+        if let Some(token) = access_token {
+            values.insert("access_token".to_string(), token);
+        }
+        if let Some(project_id) = project_id {
+            values.insert("project_id".to_string(), project_id);
+        }
+        Self {
+            method: "google_adc".to_string(),
+            values,
+            token_source: Some(token_source),
         }
     }
 
@@ -88,10 +170,37 @@ impl ResolvedAuth {
 pub struct AuthRequest {
     pub provider_id: String,
     pub provider_name: String,
+    /// Named account whose credentials to use, e.g. `work` for
+    /// `openai@work`; `None` for the provider's default credentials.
+    pub account: Option<String>,
     pub auth_method: AuthMethod,
     pub env_candidates: Vec<String>,
 }
 
+impl AuthRequest {
+    /// Key the credentials are stored under: the provider ID, or
+    /// `provider@account` for a named account.
+    pub fn credential_key(&self) -> String {
+        credential_key(&self.provider_id, self.account.as_deref())
+    }
+}
+
+/// Split a provider specifier such as `openai@work` into the provider ID
+/// and the named account, if any.
+pub fn split_account(provider: &str) -> (&str, Option<&str>) {
+    match provider.split_once('@') {
+        Some((provider_id, account)) if !account.is_empty() => (provider_id, Some(account)),
+        _ => (provider, None),
+    }
+}
+
+pub(crate) fn credential_key(provider_id: &str, account: Option<&str>) -> String {
+    match account {
+        Some(account) => format!("{provider_id}@{account}"),
+        None => provider_id.to_string(),
+    }
+}
+
 /// External hook used to resolve credentials (keyring, OAuth refresh, etc).
 pub trait AuthResolver: Send + Sync {
     fn resolve(&self, request: &AuthRequest) -> Result<Option<ResolvedAuth>, Error>;
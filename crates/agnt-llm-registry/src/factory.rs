@@ -1,6 +1,6 @@
 //! Provider factory trait and configuration options.
 
-use agnt_llm::LanguageModelProvider;
+use agnt_llm::{HttpSettings, LanguageModelProvider};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 
@@ -18,6 +18,8 @@ pub struct ProviderOptions {
     pub(crate) factory_options: Option<Value>,
     /// Resolved auth payload for this provider.
     pub auth: ResolvedAuth,
+    /// Timeouts and keep-alive for the provider's HTTP client.
+    pub http: HttpSettings,
 }
 
 impl ProviderOptions {
@@ -21,7 +21,7 @@
 //! // Register a provider with a factory closure.
 //! // The closure receives ProviderOptions with resolved auth and api_endpoint.
 //! registry.add_provider("openai", |options: ProviderOptions| {
-//!     Ok(agnt_llm_openai::provider(OpenAIConfig {
+//!     agnt_llm_openai::provider(OpenAIConfig {
 //!         auth_token: options
 //!             .auth
 //!             .get("access_token")
@@ -30,7 +30,10 @@
 //!             .to_string(),
 //!         base_url: options.api_endpoint
 //!             .unwrap_or_else(|| "https://api.openai.com/v1".into()),
-//!     }))
+//!         http: options.http,
+//!         ..Default::default()
+//!     })
+//!     .map_err(|err| Error::Factory(Box::new(err)))
 //! });
 //!
 //! // Optionally load the models.dev spec for model metadata
@@ -42,8 +45,14 @@
 //! // Get a model handle
 //! let model = registry.model("openai", "gpt-4.1-nano")?;
 //! // or: let model = registry.model_from_string("openai:gpt-4.1-nano")?;
+//!
+//! // Moving names and renamed IDs resolve to the model they stand for
+//! registry.set_default_model("openai", "gpt-4.1-nano");
+//! registry.add_model_alias("openai", "gpt-latest", ModelAlias::Default);
+//! let model = registry.model("openai", "gpt-latest")?;
 //! ```
 
+pub mod alias;
 pub mod auth;
 pub mod error;
 pub mod factory;
@@ -52,7 +61,11 @@ pub mod provider;
 pub mod registry;
 pub mod spec;
 
-pub use auth::{ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, OAuthPkceAuth, ResolvedAuth};
+pub use alias::{ModelAlias, ResolvedModelId};
+pub use auth::{
+    ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, CredentialField, GoogleAdcAuth,
+    OAuthPkceAuth, ResolvedAuth, TokenSource, split_account,
+};
 pub use error::Error;
 pub use factory::{ProviderFactory, ProviderOptions};
 pub use model_source::{ModelLoader, ModelSource};
@@ -3,9 +3,15 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use agnt_llm::{LanguageModel, LanguageModelProvider};
-
-use crate::auth::{ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, ResolvedAuth};
+use agnt_llm::{
+    HttpSettings, LanguageModel, LanguageModelProvider, ModelCapabilities, ProviderBehavior,
+};
+use serde::Serialize;
+
+use crate::alias::{ModelAlias, ResolvedModelId};
+use crate::auth::{
+    ApiKeyAuth, AuthMethod, AuthRequest, AuthResolver, ResolvedAuth, credential_key, split_account,
+};
 use crate::error::Error;
 use crate::factory::{ProviderFactory, ProviderOptions};
 use crate::model_source::ModelSource;
@@ -13,6 +19,8 @@ use crate::provider::ProviderRegistration;
 use crate::spec::{ModelSpec, ModelsDevSpec, ProviderSpec};
 
 const MODELS_DEV_URL: &str = "https://models.dev/api.json";
+/// Aliases followed before giving up on a chain of them as circular.
+const MAX_ALIAS_HOPS: usize = 8;
 
 /// A provider that is configured and compatible with at least one factory.
 #[derive(Debug, Clone)]
@@ -24,7 +32,7 @@ pub struct AvailableProvider {
 }
 
 /// A provider known to the registry.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct KnownProvider {
     /// Provider identifier (e.g. `"openai"`).
     pub id: String,
@@ -66,6 +74,19 @@ pub struct Registry {
     registrations: HashMap<String, ProviderRegistration>,
     spec: Option<ModelsDevSpec>,
     auth_resolver: Option<Arc<dyn AuthResolver>>,
+    /// API endpoints set by the user, taking precedence over registrations
+    /// and the spec.
+    endpoint_overrides: HashMap<String, String>,
+    /// Prompt requirements attached to every model of a provider.
+    behaviors: HashMap<String, Arc<dyn ProviderBehavior>>,
+    /// HTTP client settings passed to every provider factory.
+    http: HttpSettings,
+    /// Model each provider's [`ModelAlias::Default`] stands for.
+    default_models: HashMap<String, String>,
+    /// Aliases keyed by provider, then by the ID they stand in for.
+    aliases: HashMap<String, HashMap<String, ModelAlias>>,
+    /// Deprecation notices for renamed IDs that models were obtained by.
+    deprecation_notices: Vec<String>,
 }
 
 impl Registry {
@@ -77,6 +98,12 @@ impl Registry {
             registrations: HashMap::new(),
             spec: None,
             auth_resolver: None,
+            endpoint_overrides: HashMap::new(),
+            behaviors: HashMap::new(),
+            http: HttpSettings::default(),
+            default_models: HashMap::new(),
+            aliases: HashMap::new(),
+            deprecation_notices: Vec::new(),
         }
     }
 
@@ -85,6 +112,58 @@ impl Registry {
         self.auth_resolver = Some(resolver);
     }
 
+    /// Send requests for a provider to `endpoint` instead of its registered
+    /// or models.dev API URL.
+    pub fn set_api_endpoint(
+        &mut self,
+        provider_id: impl Into<String>,
+        endpoint: impl Into<String>,
+    ) {
+        self.endpoint_overrides
+            .insert(provider_id.into(), endpoint.into());
+    }
+
+    /// Use these timeouts and keep-alive settings for the HTTP clients of
+    /// providers created from now on.
+    pub fn set_http_settings(&mut self, settings: HttpSettings) {
+        self.http = settings;
+    }
+
+    /// Attach `behavior` to every model obtained for a provider.
+    pub fn set_provider_behavior(
+        &mut self,
+        provider_id: impl Into<String>,
+        behavior: impl ProviderBehavior + 'static,
+    ) {
+        self.behaviors
+            .insert(provider_id.into(), Arc::new(behavior));
+    }
+
+    /// Set the model a provider's [`ModelAlias::Default`] aliases stand for.
+    pub fn set_default_model(
+        &mut self,
+        provider_id: impl Into<String>,
+        model_id: impl Into<String>,
+    ) {
+        self.default_models
+            .insert(provider_id.into(), model_id.into());
+    }
+
+    /// Let `alias` stand for another model of a provider, e.g.
+    /// `gpt-latest` for its default or an old ID for the one it was renamed
+    /// to.
+    pub fn add_model_alias(
+        &mut self,
+        provider_id: impl Into<String>,
+        alias: impl Into<String>,
+        target: ModelAlias,
+    ) {
+        self.aliases
+            .entry(provider_id.into())
+            .or_default()
+            .insert(alias.into(), target);
+    }
+
     /// Register provider metadata, including auth method and model source.
     pub fn add_registration(&mut self, registration: ProviderRegistration) {
         self.registrations
@@ -123,6 +202,7 @@ impl Registry {
 
     /// Check whether a provider is known and compatible with a registered factory.
     pub fn has_provider(&self, name: &str) -> bool {
+        let (name, _) = split_account(name);
         self.known_providers()
             .iter()
             .any(|provider| provider.id == name && provider.compatible)
@@ -133,19 +213,93 @@ impl Registry {
     // -----------------------------------------------------------------------
 
     /// Obtain a [`LanguageModel`] for the given provider and model ID.
+    ///
+    /// `provider` may name an account, as in `openai@work`, to use that
+    /// account's credentials instead of the provider's default ones.
+    ///
+    /// When the model has a spec, requests are limited to the features it
+    /// lists (see [`ModelCapabilities`]). The provider's registered
+    /// [`ProviderBehavior`] comes along with it.
+    ///
+    /// Aliases are resolved first; a renamed ID queues a notice for
+    /// [`take_deprecation_notices`](Self::take_deprecation_notices).
     pub fn model(&mut self, provider: &str, model_id: &str) -> Result<LanguageModel, Error> {
-        if let Some(result) = self.model_via_registered(provider, model_id)? {
-            return Ok(result);
+        let (provider, account) = split_account(provider);
+        let resolved = self.resolve_model_id(provider, model_id)?;
+        if let Some(notice) = resolved.deprecation
+            && !self.deprecation_notices.contains(&notice)
+        {
+            self.deprecation_notices.push(notice);
         }
+        let model_id = resolved.id.as_str();
+        let model = if let Some(result) = self.model_via_registered(provider, account, model_id)? {
+            result
+        } else if let Some(result) = self.model_via_spec(provider, account, model_id)? {
+            result
+        } else {
+            self.model_via_direct(provider, account, model_id)?
+        };
 
-        if let Some(result) = self.model_via_spec(provider, model_id)? {
-            return Ok(result);
+        let model = match self.model_spec(provider, model_id) {
+            Some(spec) => model.with_capabilities(ModelCapabilities {
+                tool_call: spec.tool_call,
+                attachment: spec.attachment,
+                reasoning: spec.reasoning,
+                structured_output: spec.structured_output,
+            }),
+            None => model,
+        };
+        Ok(match self.behaviors.get(provider) {
+            Some(behavior) => model.with_behavior(Arc::clone(behavior)),
+            None => model,
+        })
+    }
+
+    /// Follow the aliases of `model_id` to the model it stands for. IDs
+    /// without an alias resolve to themselves.
+    pub fn resolve_model_id(
+        &self,
+        provider: &str,
+        model_id: &str,
+    ) -> Result<ResolvedModelId, Error> {
+        let (provider, _) = split_account(provider);
+        let not_found = || Error::ModelNotFound {
+            provider: provider.to_string(),
+            model: model_id.to_string(),
+        };
+        let aliases = self.aliases.get(provider);
+        let mut id = model_id.to_string();
+        let mut deprecation = None;
+        for _ in 0..MAX_ALIAS_HOPS {
+            let Some(alias) = aliases.and_then(|aliases| aliases.get(&id)) else {
+                return Ok(ResolvedModelId { id, deprecation });
+            };
+            id = match alias {
+                ModelAlias::Default => self.default_models.get(provider).ok_or_else(not_found)?,
+                ModelAlias::Model(target) => target,
+                ModelAlias::Renamed(target) => {
+                    deprecation.get_or_insert_with(|| {
+                        format!(
+                            "{provider}:{model_id} was renamed to {provider}:{target}; \
+                             update configs and settings that still use the old ID"
+                        )
+                    });
+                    target
+                }
+            }
+            .clone();
         }
+        Err(not_found())
+    }
 
-        self.model_via_direct(provider, model_id)
+    /// Notices about renamed model IDs that [`model`](Self::model) resolved
+    /// since the last call, to show the user.
+    pub fn take_deprecation_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.deprecation_notices)
     }
 
-    /// Parse a combined `"provider:model"` string and return the model.
+    /// Parse a combined `"provider:model"` string, such as
+    /// `openai@work:gpt-5.2`, and return the model.
     pub fn model_from_string(&mut self, specifier: &str) -> Result<LanguageModel, Error> {
         let (provider, model_id) = specifier.split_once(':').ok_or_else(|| {
             Error::ProviderNotFound(format!(
@@ -161,6 +315,14 @@ impl Registry {
 
     /// Load the models.dev spec from the remote URL.
     pub async fn fetch_spec(&mut self) -> Result<(), Error> {
+        self.spec = Some(Self::fetch_remote_spec().await?);
+        Ok(())
+    }
+
+    /// Fetch the models.dev spec from the remote URL without loading it,
+    /// e.g. to refresh it in the background and [`load_spec`](Self::load_spec)
+    /// it later.
+    pub async fn fetch_remote_spec() -> Result<ModelsDevSpec, Error> {
         let body = reqwest::get(MODELS_DEV_URL)
             .await
             .map_err(|e| Error::Fetch(Box::new(e)))?
@@ -168,9 +330,7 @@ impl Registry {
             .await
             .map_err(|e| Error::Fetch(Box::new(e)))?;
 
-        let parsed: ModelsDevSpec = serde_json::from_str(&body)?;
-        self.spec = Some(parsed);
-        Ok(())
+        Ok(serde_json::from_str(&body)?)
     }
 
     /// Load the models.dev spec from a JSON string.
@@ -185,8 +345,14 @@ impl Registry {
         self.spec = Some(spec);
     }
 
+    /// The loaded models.dev spec, if any.
+    pub fn spec(&self) -> Option<&ModelsDevSpec> {
+        self.spec.as_ref()
+    }
+
     /// Return the provider spec for a given provider ID.
     pub fn provider_spec(&self, provider: &str) -> Option<ProviderSpec> {
+        let (provider, _) = split_account(provider);
         self.spec.as_ref()?.get(provider).cloned()
     }
 
@@ -200,14 +366,18 @@ impl Registry {
 
     /// List models for a provider from the provider registration or models.dev.
     pub fn list_models(&self, provider: &str) -> Vec<ModelSpec> {
+        let (provider, _) = split_account(provider);
         if let Some(registration) = self.registrations.get(provider) {
             return self.list_registered_models(provider, registration);
         }
         self.list_spec_models(provider)
     }
 
-    /// Get a specific model's metadata.
+    /// Get a specific model's metadata, following aliases.
     pub fn model_spec(&self, provider: &str, model_id: &str) -> Option<ModelSpec> {
+        let (provider, _) = split_account(provider);
+        let resolved = self.resolve_model_id(provider, model_id).ok()?;
+        let model_id = resolved.id.as_str();
         if let Some(registration) = self.registrations.get(provider) {
             let models = self.models_from_registration(provider, registration).ok()?;
             return models.into_iter().find(|m| m.id == model_id);
@@ -261,9 +431,23 @@ impl Registry {
             .collect()
     }
 
-    /// Build an auth request for a provider, if known to the registry.
+    /// Base API URL requests for a provider are sent to, if known.
+    pub fn api_endpoint(&self, provider_id: &str) -> Option<String> {
+        let (provider_id, _) = split_account(provider_id);
+        self.endpoint_overrides
+            .get(provider_id)
+            .cloned()
+            .or_else(|| self.registrations.get(provider_id)?.api_endpoint.clone())
+            .or_else(|| self.provider_spec(provider_id)?.api)
+    }
+
+    /// Build an auth request for a provider, if known to the registry. An
+    /// `openai@work` specifier asks for the `work` account's credentials.
     pub fn auth_request(&self, provider_id: &str) -> Option<AuthRequest> {
-        self.build_auth_request(provider_id)
+        let (provider_id, account) = split_account(provider_id);
+        let mut request = self.build_auth_request(provider_id)?;
+        request.account = account.map(str::to_string);
+        Some(request)
     }
 
     // -----------------------------------------------------------------------
@@ -356,6 +540,7 @@ impl Registry {
             .unwrap_or_else(|| AuthRequest {
                 provider_id: provider_id.to_string(),
                 provider_name: provider_id.to_string(),
+                account: None,
                 auth_method: AuthMethod::ApiKey(ApiKeyAuth::default()),
                 env_candidates: Vec::new(),
             });
@@ -364,6 +549,7 @@ impl Registry {
         let configured = self
             .resolve_auth_optional(
                 provider_id,
+                None,
                 &auth_request.provider_name,
                 &auth_request.auth_method,
                 auth_request.env_candidates.clone(),
@@ -420,6 +606,7 @@ impl Registry {
     fn model_via_registered(
         &mut self,
         provider_id: &str,
+        account: Option<&str>,
         model_id: &str,
     ) -> Result<Option<LanguageModel>, Error> {
         let registration = match self.registrations.get(provider_id).cloned() {
@@ -447,23 +634,23 @@ impl Registry {
 
             let auth = self.resolve_auth_required(
                 provider_id,
+                account,
                 &registration.name,
                 &registration.auth_method,
                 env_candidates_for_auth(&registration.auth_method),
             )?;
             let options = ProviderOptions {
                 id: provider_id.to_string(),
-                api_endpoint: registration
-                    .api_endpoint
-                    .clone()
-                    .or_else(|| provider_spec.as_ref().and_then(|ps| ps.api.clone())),
+                api_endpoint: self.api_endpoint(provider_id),
                 factory_options: registration.factory_options.clone(),
                 auth,
+                http: self.http.clone(),
             };
 
+            let cache_key = credential_key(provider_id, account);
             if let Some(npm) = effective_npm
                 && let Some(model) =
-                    self.model_from_npm(&npm, provider_id, model_id, options.clone())?
+                    self.model_from_npm(&npm, &cache_key, model_id, options.clone())?
             {
                 return Ok(Some(model));
             }
@@ -494,6 +681,7 @@ impl Registry {
     fn model_via_spec(
         &mut self,
         provider_id: &str,
+        account: Option<&str>,
         model_id: &str,
     ) -> Result<Option<LanguageModel>, Error> {
         let spec = match &self.spec {
@@ -524,27 +712,40 @@ impl Registry {
 
         let auth_method = AuthMethod::ApiKey(ApiKeyAuth {
             env: provider_spec.env.clone(),
+            ..Default::default()
         });
         let auth = self.resolve_auth_required(
             provider_id,
+            account,
             &provider_spec.name,
             &auth_method,
             provider_spec.env.clone(),
         )?;
         let options = ProviderOptions {
             id: provider_id.to_string(),
-            api_endpoint: provider_spec.api.clone(),
+            api_endpoint: self
+                .endpoint_overrides
+                .get(provider_id)
+                .cloned()
+                .or_else(|| provider_spec.api.clone()),
             factory_options: None,
             auth,
+            http: self.http.clone(),
         };
 
-        self.model_from_npm(&npm, provider_id, model_id, options)
+        self.model_from_npm(
+            &npm,
+            &credential_key(provider_id, account),
+            model_id,
+            options,
+        )
     }
 
+    /// `cache_key` tells apart the instances of a provider, one per account.
     fn model_from_npm(
         &mut self,
         npm: &str,
-        provider_id: &str,
+        cache_key: &str,
         model_id: &str,
         options: ProviderOptions,
     ) -> Result<Option<LanguageModel>, Error> {
@@ -556,13 +757,13 @@ impl Registry {
 
         let needs_rebuild = entry
             .instances
-            .get(provider_id)
+            .get(cache_key)
             .map(|cached| cached.auth_signature != signature)
             .unwrap_or(true);
         if needs_rebuild {
             let instance = entry.factory.create(options)?;
             entry.instances.insert(
-                provider_id.to_string(),
+                cache_key.to_string(),
                 CachedProvider {
                     auth_signature: signature,
                     provider: instance,
@@ -570,20 +771,21 @@ impl Registry {
             );
         }
 
-        let provider_instance = &entry.instances[provider_id].provider;
+        let provider_instance = &entry.instances[cache_key].provider;
         Ok(Some(provider_instance.model(model_id)))
     }
 
     fn model_via_direct(
         &mut self,
         provider_name: &str,
+        account: Option<&str>,
         model_id: &str,
     ) -> Result<LanguageModel, Error> {
         if !self.providers.contains_key(provider_name) {
             return Err(Error::ProviderNotFound(provider_name.to_string()));
         }
 
-        let options = self.build_direct_options(provider_name)?;
+        let options = self.build_direct_options(provider_name, account)?;
         self.model_via_direct_with_options(provider_name, model_id, options)?
             .ok_or_else(|| Error::ProviderNotFound(provider_name.to_string()))
     }
@@ -624,7 +826,11 @@ impl Registry {
     }
 
     /// Build [`ProviderOptions`] for a directly-registered provider.
-    fn build_direct_options(&self, provider_name: &str) -> Result<ProviderOptions, Error> {
+    fn build_direct_options(
+        &self,
+        provider_name: &str,
+        account: Option<&str>,
+    ) -> Result<ProviderOptions, Error> {
         let registration = self.registrations.get(provider_name).cloned();
         let provider_spec = self
             .spec
@@ -641,6 +847,7 @@ impl Registry {
                         .as_ref()
                         .map(|ps| ps.env.clone())
                         .unwrap_or_default(),
+                    ..Default::default()
                 })
             });
         let env_candidates = env_candidates_for_auth(&auth_method);
@@ -651,15 +858,13 @@ impl Registry {
             .unwrap_or_else(|| provider_name.to_string());
         let auth = self.resolve_auth_required(
             provider_name,
+            account,
             &provider_name_human,
             &auth_method,
             env_candidates,
         )?;
 
-        let api_endpoint = registration
-            .as_ref()
-            .and_then(|r| r.api_endpoint.clone())
-            .or_else(|| provider_spec.as_ref().and_then(|ps| ps.api.clone()));
+        let api_endpoint = self.api_endpoint(provider_name);
 
         Ok(ProviderOptions {
             id: provider_name.to_string(),
@@ -668,6 +873,7 @@ impl Registry {
                 .as_ref()
                 .and_then(|r| r.factory_options.clone()),
             auth,
+            http: self.http.clone(),
         })
     }
 
@@ -675,9 +881,13 @@ impl Registry {
     // Internal: auth resolution
     // -----------------------------------------------------------------------
 
+    /// Credentials for the provider's default account, or with `account`,
+    /// for that named one. Environment variables only stand for the
+    /// default account.
     fn resolve_auth_optional(
         &self,
         provider_id: &str,
+        account: Option<&str>,
         provider_name: &str,
         auth_method: &AuthMethod,
         env_candidates: Vec<String>,
@@ -686,6 +896,7 @@ impl Registry {
             let req = AuthRequest {
                 provider_id: provider_id.to_string(),
                 provider_name: provider_name.to_string(),
+                account: account.map(str::to_string),
                 auth_method: auth_method.clone(),
                 env_candidates: env_candidates.clone(),
             };
@@ -695,18 +906,19 @@ impl Registry {
         }
 
         match auth_method {
-            AuthMethod::ApiKey(_) => {
+            AuthMethod::ApiKey(api_key) if account.is_none() => {
                 if env_candidates.is_empty() {
                     return Some(ResolvedAuth {
                         method: "api_key".to_string(),
                         values: HashMap::new(),
+                        token_source: None,
                     });
                 }
 
                 env_candidates
                     .iter()
                     .find_map(|var| std::env::var(var).ok())
-                    .map(ResolvedAuth::api_key)
+                    .map(|key| api_key.with_env_fields(ResolvedAuth::api_key(key)))
             }
             _ => None,
         }
@@ -715,15 +927,22 @@ impl Registry {
     fn resolve_auth_required(
         &self,
         provider_id: &str,
+        account: Option<&str>,
         provider_name: &str,
         auth_method: &AuthMethod,
         env_candidates: Vec<String>,
     ) -> Result<ResolvedAuth, Error> {
-        self.resolve_auth_optional(provider_id, provider_name, auth_method, env_candidates)
-            .ok_or_else(|| Error::MissingCredentials {
-                provider: provider_id.to_string(),
-                method: auth_method.kind().to_string(),
-            })
+        self.resolve_auth_optional(
+            provider_id,
+            account,
+            provider_name,
+            auth_method,
+            env_candidates,
+        )
+        .ok_or_else(|| Error::MissingCredentials {
+            provider: credential_key(provider_id, account),
+            method: auth_method.kind().to_string(),
+        })
     }
 
     fn build_auth_request(&self, provider_id: &str) -> Option<AuthRequest> {
@@ -746,12 +965,14 @@ impl Registry {
             .unwrap_or_else(|| {
                 AuthMethod::ApiKey(ApiKeyAuth {
                     env: provider_spec.map(|s| s.env.clone()).unwrap_or_default(),
+                    ..Default::default()
                 })
             });
 
         Some(AuthRequest {
             provider_id: provider_id.to_string(),
             provider_name,
+            account: None,
             env_candidates: env_candidates_for_auth(&auth_method),
             auth_method,
         })
@@ -802,6 +1023,8 @@ fn options_signature(options: &ProviderOptions) -> String {
     if let Some(factory_options) = &options.factory_options {
         out.push_str(factory_options.to_string().as_str());
     }
+    out.push('|');
+    out.push_str(format!("{:?}", options.http).as_str());
 
     out
 }
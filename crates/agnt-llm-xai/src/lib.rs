@@ -0,0 +1,114 @@
+//! Registry integration for xAI's Grok models, served through the
+//! OpenAI-compatible transport.
+
+use agnt_llm_openai::OpenAIConfig;
+use agnt_llm_registry::{
+    ApiKeyAuth, AuthMethod, Modalities, ModelAlias, ModelCost, ModelLimit, ModelSource, ModelSpec,
+    ProviderOptions, ProviderRegistration, Registry,
+};
+
+pub const PROVIDER_ID: &str = "xai";
+pub const PROVIDER_NAME: &str = "xAI";
+pub const DEFAULT_MODEL_ID: &str = "grok-code-fast-1";
+
+const API_ENDPOINT: &str = "https://api.x.ai/v1";
+/// The npm package models.dev lists for xAI.
+const NPM_PACKAGE: &str = "@ai-sdk/xai";
+
+/// Register the xAI provider, authenticated with `XAI_API_KEY` or a stored
+/// API key.
+pub fn register(registry: &mut Registry) {
+    registry.add_factory(NPM_PACKAGE, factory);
+
+    let mut registration = ProviderRegistration::new(PROVIDER_ID, PROVIDER_NAME);
+    registration.npm_packages = vec![NPM_PACKAGE.to_string()];
+    registration.api_endpoint = Some(API_ENDPOINT.to_string());
+    registration.auth_method = AuthMethod::ApiKey(ApiKeyAuth {
+        env: vec!["XAI_API_KEY".to_string()],
+        ..Default::default()
+    });
+    registration.model_source = ModelSource::Static(grok_models());
+    registry.add_registration(registration);
+    registry.set_default_model(PROVIDER_ID, DEFAULT_MODEL_ID);
+    registry.add_model_alias(PROVIDER_ID, "grok-latest", ModelAlias::Default);
+}
+
+fn factory(
+    options: ProviderOptions,
+) -> Result<agnt_llm::LanguageModelProvider, agnt_llm_registry::Error> {
+    let auth_token = options.auth.get("api_key").unwrap_or_default().to_string();
+
+    agnt_llm_openai::provider(OpenAIConfig {
+        auth_token,
+        base_url: options.api_endpoint.unwrap_or_else(|| API_ENDPOINT.into()),
+        http: options.http,
+        ..Default::default()
+    })
+    .map_err(|err| agnt_llm_registry::Error::Factory(Box::new(err)))
+}
+
+fn grok_models() -> Vec<ModelSpec> {
+    vec![
+        grok_model("grok-4", "Grok 4", 256_000, 64_000, (3.0, 15.0)),
+        grok_model(
+            "grok-4-fast-reasoning",
+            "Grok 4 Fast",
+            2_000_000,
+            30_000,
+            (0.2, 0.5),
+        ),
+        grok_model(
+            "grok-4-fast-non-reasoning",
+            "Grok 4 Fast (non-reasoning)",
+            2_000_000,
+            30_000,
+            (0.2, 0.5),
+        ),
+        grok_model(
+            "grok-code-fast-1",
+            "Grok Code Fast 1",
+            256_000,
+            10_000,
+            (0.2, 1.5),
+        ),
+        grok_model("grok-3", "Grok 3", 131_072, 8_192, (3.0, 15.0)),
+        grok_model("grok-3-mini", "Grok 3 Mini", 131_072, 8_192, (0.3, 0.5)),
+    ]
+}
+
+fn grok_model(
+    id: &str,
+    name: &str,
+    context: u64,
+    output: u64,
+    (input_cost, output_cost): (f64, f64),
+) -> ModelSpec {
+    ModelSpec {
+        id: id.to_string(),
+        name: Some(name.to_string()),
+        family: Some("grok".to_string()),
+        attachment: false,
+        // Grok models reason on their own and reject OpenAI's reasoning
+        // options, so none are sent.
+        reasoning: false,
+        tool_call: true,
+        structured_output: true,
+        temperature: true,
+        knowledge: None,
+        release_date: None,
+        last_updated: None,
+        modalities: Some(Modalities {
+            input: vec!["text".to_string()],
+            output: vec!["text".to_string()],
+        }),
+        open_weights: false,
+        cost: Some(ModelCost {
+            input: input_cost,
+            output: output_cost,
+            cache_read: None,
+            cache_write: None,
+        }),
+        limit: Some(ModelLimit { context, output }),
+        provider: None,
+    }
+}
@@ -5,7 +5,9 @@ use crate::request::Schema;
 /// This is the Rust analog of zod's `.describe()` — it produces a schema
 /// descriptor that language models use to understand the expected JSON input.
 ///
-/// Implement this manually for now; a derive macro will be added later.
+/// Usually derived: `#[derive(Describe)]` builds the schema from the type's
+/// fields, doc comments and serde attributes (see [`agnt_derive`]). Implement
+/// it by hand for shapes the derive doesn't cover.
 ///
 /// # Example
 ///
@@ -102,6 +104,14 @@ impl Describe for usize {
     }
 }
 
+/// Optional fields are left out of the object's `required` list, so an
+/// option describes like its contents.
+impl<T: Describe> Describe for Option<T> {
+    fn describe() -> Schema {
+        T::describe()
+    }
+}
+
 impl<T: Describe> Describe for Vec<T> {
     fn describe() -> Schema {
         Schema::Array {
@@ -0,0 +1,82 @@
+//! HTTP client settings shared by provider transports.
+
+use std::time::Duration;
+
+use futures::Stream;
+use tokio_stream::StreamExt;
+
+use crate::error::Error;
+
+/// Timeouts and keep-alive for a provider's HTTP client. `None` leaves the
+/// client's default in place; zero turns the timeout (or keep-alive) off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpSettings {
+    /// Time allowed to establish a connection.
+    pub connect_timeout: Option<Duration>,
+    /// Time allowed for any single read from the connection.
+    pub read_timeout: Option<Duration>,
+    /// How long unused pooled connections are kept open.
+    pub pool_idle_timeout: Option<Duration>,
+    /// Interval of TCP keep-alive probes on open connections.
+    pub tcp_keepalive: Option<Duration>,
+    /// Fail a streaming response when no event arrives for this long, so a
+    /// silently stalled connection doesn't hang the turn.
+    pub stream_idle_timeout: Option<Duration>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(30)),
+            read_timeout: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            stream_idle_timeout: Some(Duration::from_secs(300)),
+        }
+    }
+}
+
+impl HttpSettings {
+    /// An HTTP client with these settings. Fields left at `None` aren't
+    /// passed on, so the client keeps its own defaults for them.
+    pub fn client(&self) -> Result<reqwest::Client, Error> {
+        // `Some(None)` for a setting turned off with zero.
+        let set = |value: Option<Duration>| value.map(|value| (!value.is_zero()).then_some(value));
+        let mut builder = reqwest::Client::builder();
+        if let Some(Some(timeout)) = set(self.connect_timeout) {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(Some(timeout)) = set(self.read_timeout) {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(timeout) = set(self.pool_idle_timeout) {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = set(self.tcp_keepalive) {
+            builder = builder.tcp_keepalive(interval);
+        }
+        builder.build().map_err(|err| Error::Http(Box::new(err)))
+    }
+
+    /// The next item of an event stream, or an error once
+    /// [`stream_idle_timeout`](Self::stream_idle_timeout) passes without one.
+    pub async fn next_event<S>(&self, stream: &mut S) -> Result<Option<S::Item>, Error>
+    where
+        S: Stream + Unpin,
+    {
+        let Some(timeout) = self
+            .stream_idle_timeout
+            .filter(|timeout| !timeout.is_zero())
+        else {
+            return Ok(stream.next().await);
+        };
+        tokio::time::timeout(timeout, stream.next())
+            .await
+            .map_err(|_| {
+                Error::Sse(format!(
+                    "no event received for {}s, the connection seems stalled",
+                    timeout.as_secs()
+                ))
+            })
+    }
+}
@@ -1,21 +1,25 @@
 pub mod error;
+pub mod http;
 pub mod model;
 pub mod provider;
 pub mod request;
 pub mod response;
 pub mod stream;
+pub mod trace;
 
 pub use error::Error;
-pub use model::{LanguageModel, LanguageModelBackend};
+pub use http::HttpSettings;
+pub use model::{LanguageModel, LanguageModelBackend, ModelCapabilities, ProviderBehavior};
 pub use provider::{LanguageModelProvider, LanguageModelProviderBackend};
 pub mod describe;
 
+pub use agnt_derive::Describe;
 pub use describe::Describe;
 pub use request::{
     AssistantPart, GenerateOptions, GenerateRequest, ImagePart, Message, Property, ReasoningPart,
-    RequestBuilder, Schema, SystemPart, TextPart, ToolCallDisplayPart, ToolCallPart,
-    ToolCallResultPart, ToolChoice, ToolDefinition, ToolDisplayBodyPart, ToolResultPart, UserPart,
-    request,
+    RequestBuilder, ResponseSchema, Schema, SystemPart, TextPart, ToolCallDisplayPart,
+    ToolCallPart, ToolCallResultPart, ToolChoice, ToolDefinition, ToolDisplayBodyPart,
+    ToolResultPart, UserPart, request,
 };
 pub use response::{GenerateResult, Response};
-pub use stream::{FinishReason, StreamEvent, Usage};
+pub use stream::{FinishReason, HostedToolCall, StreamEvent, Usage};
@@ -1,4 +1,8 @@
-use crate::request::GenerateRequest;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::request::{AssistantPart, GenerateRequest, Message, UserPart};
 use crate::response::Response;
 
 /// A concrete, type-erased language model handle.
@@ -6,6 +10,8 @@ use crate::response::Response;
 /// Wraps a [`LanguageModelBackend`] so callers never need generics.
 pub struct LanguageModel {
     inner: Box<dyn LanguageModelBackend>,
+    capabilities: ModelCapabilities,
+    behavior: Option<Arc<dyn ProviderBehavior>>,
 }
 
 impl LanguageModel {
@@ -13,9 +19,29 @@ impl LanguageModel {
     pub fn new(backend: impl LanguageModelBackend + 'static) -> Self {
         Self {
             inner: Box::new(backend),
+            capabilities: ModelCapabilities::default(),
+            behavior: None,
         }
     }
 
+    /// Declare what the model supports. Requests are stripped of anything
+    /// it doesn't before they reach the backend.
+    pub fn with_capabilities(mut self, capabilities: ModelCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Attach what the provider needs of every request built for it.
+    pub fn with_behavior(mut self, behavior: Arc<dyn ProviderBehavior>) -> Self {
+        self.behavior = Some(behavior);
+        self
+    }
+
+    /// The provider's request requirements, if it declared any.
+    pub fn behavior(&self) -> Option<&dyn ProviderBehavior> {
+        self.behavior.as_deref()
+    }
+
     /// The model identifier (e.g. `"gpt-5"`, `"claude-opus-4-6"`).
     pub fn model_id(&self) -> &str {
         self.inner.model_id()
@@ -26,9 +52,102 @@ impl LanguageModel {
         self.inner.provider()
     }
 
+    pub fn capabilities(&self) -> ModelCapabilities {
+        self.capabilities
+    }
+
     /// Generate a streaming response.
     pub fn generate(&self, request: impl Into<GenerateRequest>) -> Response {
-        self.inner.generate(request.into())
+        let mut request = request.into();
+        self.capabilities.restrict(&mut request);
+        self.inner.generate(request)
+    }
+
+    /// The JSON body [`generate`](Self::generate) would send for `request`,
+    /// if the backend can show it.
+    pub fn request_body(&self, request: impl Into<GenerateRequest>) -> Option<serde_json::Value> {
+        let mut request = request.into();
+        self.capabilities.restrict(&mut request);
+        self.inner.request_body(&request)
+    }
+
+    /// Reattach to a response started earlier, replaying it from the start.
+    /// `token` comes from [`StreamEvent::Resumable`](crate::StreamEvent::Resumable).
+    pub fn resume(&self, token: &str) -> Response {
+        self.inner.resume(token)
+    }
+}
+
+/// Request features a model supports. Everything is assumed supported
+/// unless the model's spec says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    /// Tool definitions are sent.
+    pub tool_call: bool,
+    /// Image parts of user messages are sent.
+    pub attachment: bool,
+    /// Reasoning options (metadata keys starting with `reasoning_`) and
+    /// reasoning parts of earlier turns are sent.
+    pub reasoning: bool,
+    /// A response schema is sent.
+    pub structured_output: bool,
+}
+
+impl Default for ModelCapabilities {
+    fn default() -> Self {
+        Self {
+            tool_call: true,
+            attachment: true,
+            reasoning: true,
+            structured_output: true,
+        }
+    }
+}
+
+impl ModelCapabilities {
+    /// Drop the parts of `request` the model doesn't support.
+    pub fn restrict(&self, request: &mut GenerateRequest) {
+        if !self.tool_call {
+            request.tools.clear();
+            request.options.tool_choice = Default::default();
+        }
+        if !self.reasoning {
+            request
+                .metadata
+                .retain(|key, _| !key.starts_with("reasoning_"));
+        }
+        if !self.structured_output {
+            request.options.response_schema = None;
+        }
+        if self.attachment && self.reasoning {
+            return;
+        }
+        for message in &mut request.messages {
+            match message {
+                Message::User { parts } if !self.attachment => {
+                    parts.retain(|part| !matches!(part, UserPart::Image(_)));
+                }
+                Message::Assistant { parts } if !self.reasoning => {
+                    parts.retain(|part| !matches!(part, AssistantPart::Reasoning(_)));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Prompt requirements a provider declares, applied by whoever builds the
+/// requests (e.g. the agent) so provider-specific text stays out of shared
+/// prompts.
+pub trait ProviderBehavior: Send + Sync + fmt::Debug {
+    /// Text that goes before the caller's system prompt.
+    fn system_preamble(&self) -> Option<&str> {
+        None
+    }
+
+    /// Rewrite the messages of a request before it is sent.
+    fn transform_messages(&self, messages: &mut Vec<Message>) {
+        let _ = messages;
     }
 }
 
@@ -37,4 +156,23 @@ pub trait LanguageModelBackend: Send + Sync {
     fn model_id(&self) -> &str;
     fn provider(&self) -> &str;
     fn generate(&self, request: GenerateRequest) -> Response;
+
+    /// The JSON body `generate` sends for `request`, for inspecting it.
+    /// Backends that can't show it keep the default.
+    fn request_body(&self, request: &GenerateRequest) -> Option<serde_json::Value> {
+        let _ = request;
+        None
+    }
+
+    /// Reattach to a response that emitted
+    /// [`StreamEvent::Resumable`](crate::StreamEvent::Resumable).
+    /// Backends that never do keep the default, which fails.
+    fn resume(&self, token: &str) -> Response {
+        let _ = token;
+        Response::new(futures::stream::once(async {
+            Err(Error::Other(
+                "this provider cannot resume responses".to_string(),
+            ))
+        }))
+    }
 }
@@ -23,6 +23,17 @@ pub struct GenerateOptions {
     pub top_p: Option<f32>,
     pub stop: Option<Vec<String>>,
     pub tool_choice: ToolChoice,
+    /// Schema the answer must follow, for models with structured output.
+    pub response_schema: Option<ResponseSchema>,
+}
+
+/// A JSON schema the model's answer has to match.
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    /// Name of the schema, e.g. `"session_summary"`. Some providers require
+    /// one.
+    pub name: String,
+    pub schema: Schema,
 }
 
 // ---------------------------------------------------------------------------
@@ -121,6 +132,16 @@ impl RequestBuilder {
         self
     }
 
+    /// Ask for an answer matching `schema`. Dropped for models without
+    /// structured output.
+    pub fn response_schema(&mut self, name: impl Into<String>, schema: Schema) -> &mut Self {
+        self.options.response_schema = Some(ResponseSchema {
+            name: name.into(),
+            schema,
+        });
+        self
+    }
+
     // -- metadata --
 
     pub fn meta(
@@ -164,6 +185,7 @@ pub struct TextPart {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImagePart {
+    /// A `data:` URL with the image inline, or a remote one.
     pub url: String,
 }
 
@@ -242,6 +264,9 @@ pub enum AssistantPart {
     Text(TextPart),
     ToolCall(ToolCallPart),
     Reasoning(ReasoningPart),
+    /// An image the model generated. Kept for display; providers don't take
+    /// it back as input.
+    Image(ImagePart),
 }
 
 // ---------------------------------------------------------------------------
@@ -351,6 +376,12 @@ pub enum Schema {
         properties: Vec<Property>,
         required: Vec<String>,
     },
+    /// Exactly one of `variants`, such as the variants of an internally
+    /// tagged enum.
+    OneOf {
+        description: Option<String>,
+        variants: Vec<Schema>,
+    },
     /// Escape hatch: a literal JSON Schema value for cases we don't cover.
     Raw(serde_json::Value),
 }
@@ -431,7 +462,48 @@ impl Schema {
                 }
                 obj
             }
+            Schema::OneOf {
+                description,
+                variants,
+            } => {
+                let variants: Vec<serde_json::Value> =
+                    variants.iter().map(Schema::to_json_schema).collect();
+                let mut obj = serde_json::json!({ "oneOf": variants });
+                if let Some(d) = description {
+                    obj["description"] = serde_json::json!(d);
+                }
+                obj
+            }
             Schema::Raw(v) => v.clone(),
         }
     }
+
+    /// This schema with `text` as its description, replacing any it had.
+    pub fn with_description(mut self, text: impl Into<String>) -> Schema {
+        match &mut self {
+            Schema::String { description, .. }
+            | Schema::Number { description }
+            | Schema::Integer { description }
+            | Schema::Boolean { description }
+            | Schema::Array { description, .. }
+            | Schema::Object { description, .. }
+            | Schema::OneOf { description, .. } => *description = Some(text.into()),
+            Schema::Raw(v) => {
+                if let Some(obj) = v.as_object_mut() {
+                    obj.insert("description".into(), serde_json::json!(text.into()));
+                }
+            }
+        }
+        self
+    }
+
+    /// This schema with a further JSON Schema keyword, for constraints the
+    /// variants above don't cover, e.g. `minItems`.
+    pub fn with_keyword(self, keyword: &str, value: impl Into<serde_json::Value>) -> Schema {
+        let mut v = self.to_json_schema();
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert(keyword.into(), value.into());
+        }
+        Schema::Raw(v)
+    }
 }
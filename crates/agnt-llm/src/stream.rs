@@ -1,4 +1,4 @@
-use crate::request::{ReasoningPart, ToolCallPart};
+use crate::request::{ImagePart, ReasoningPart, ToolCallPart};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -28,18 +28,46 @@ pub enum StreamEvent {
     /// A tool call is complete and ready to execute.
     ToolCallEnd { index: usize, call: ToolCallPart },
 
+    /// A new section of reasoning summary starts; the reasoning deltas that
+    /// follow belong to it. `index` counts the sections of one reasoning
+    /// item from 0.
+    ReasoningSectionStart { index: usize },
+
+    /// The model declined to answer, with what it said instead. The
+    /// response finishes with [`FinishReason::ContentFilter`].
+    Refusal(String),
+
     /// A chunk of reasoning summary text.
     ReasoningDelta(String),
 
-    /// A reasoning item is complete.
+    /// A reasoning item, or one section of its summary, is complete.
+    /// Providers that split a summary into sections send one part per
+    /// section, in order.
     ReasoningDone(ReasoningPart),
 
+    /// The model generated an image, complete.
+    Image(ImagePart),
+
+    /// A tool the provider runs itself (e.g. web search) started.
+    HostedToolBegin { id: String, name: String },
+
+    /// A provider-hosted tool finished. Nothing runs locally; the model
+    /// sees the result on the provider's side.
+    HostedToolEnd(HostedToolCall),
+
     /// Generation is complete.
     Finish {
         reason: FinishReason,
         usage: Option<Usage>,
     },
 
+    /// The response keeps generating on the provider's side even if this
+    /// stream is dropped. Pass `token` to [`LanguageModel::resume`] to
+    /// reattach to it, e.g. after a restart.
+    ///
+    /// [`LanguageModel::resume`]: crate::LanguageModel::resume
+    Resumable { token: String },
+
     /// An error occurred mid-stream.
     Error(String),
 }
@@ -63,3 +91,18 @@ pub struct Usage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cached_tokens: Option<u32>,
 }
+
+/// A call of a tool the provider runs itself, such as OpenAI's web search
+/// or code interpreter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostedToolCall {
+    pub id: String,
+    /// The provider's name for the tool, e.g. `web_search`.
+    pub name: String,
+    /// What the tool was asked, e.g. the search query or the code run.
+    pub input: String,
+    /// What the tool returned, if the provider reports it, e.g. the sources
+    /// found or the code's logs.
+    pub output: Option<String>,
+    pub succeeded: bool,
+}
@@ -0,0 +1,49 @@
+//! An opt-in log of provider traffic agnt didn't understand, such as event
+//! types added after this version, and of panics the frontends recovered
+//! from. Set `AGNT_TRACE` to a file path to have
+//! lines appended to it; without it nothing is written.
+
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable naming the trace log file.
+pub const TRACE_ENV: &str = "AGNT_TRACE";
+
+static TRACE_FILE: OnceLock<Option<Mutex<File>>> = OnceLock::new();
+
+fn trace_file() -> Option<&'static Mutex<File>> {
+    TRACE_FILE
+        .get_or_init(|| {
+            let path = std::env::var_os(TRACE_ENV).filter(|path| !path.is_empty())?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .ok()?;
+            Some(Mutex::new(file))
+        })
+        .as_ref()
+}
+
+/// Whether [`log`] writes anywhere, to skip building costly messages.
+pub fn enabled() -> bool {
+    trace_file().is_some()
+}
+
+/// Append `message` to the trace log, prefixed with the time in
+/// milliseconds since the Unix epoch and `source`, e.g. `openai`.
+pub fn log(source: &str, message: impl Display) {
+    let Some(file) = trace_file() else {
+        return;
+    };
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    if let Ok(mut file) = file.lock() {
+        let _ = writeln!(file, "{now_ms} {source}: {message}");
+    }
+}